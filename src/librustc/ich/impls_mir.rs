@@ -20,7 +20,6 @@ use std::mem;
 impl_stable_hash_for!(struct mir::GeneratorLayout<'tcx> { fields });
 impl_stable_hash_for!(struct mir::SourceInfo { span, scope });
 impl_stable_hash_for!(enum mir::Mutability { Mut, Not });
-impl_stable_hash_for!(enum mir::BorrowKind { Shared, Unique, Mut });
 impl_stable_hash_for!(enum mir::LocalKind { Var, Temp, Arg, ReturnPointer });
 impl_stable_hash_for!(struct mir::LocalDecl<'tcx> {
     mutability,
@@ -33,8 +32,24 @@ impl_stable_hash_for!(struct mir::LocalDecl<'tcx> {
 });
 impl_stable_hash_for!(struct mir::UpvarDecl { debug_name, by_ref });
 impl_stable_hash_for!(struct mir::BasicBlockData<'tcx> { statements, terminator, is_cleanup });
-impl_stable_hash_for!(struct mir::UnsafetyViolation { source_info, description, kind });
-impl_stable_hash_for!(struct mir::UnsafetyCheckResult { violations, unsafe_blocks });
+impl_stable_hash_for!(struct mir::UnsafetyViolation { source_info, details, lvalue, kind });
+impl_stable_hash_for!(enum mir::UnsafetyViolationDetails {
+    UnsafeCall,
+    InlineAsm,
+    RawPtrDeref,
+    MutableStatic,
+    ExternStatic,
+    UnionFieldRead,
+    UnionFieldWrite,
+    UnionFieldBorrow,
+    NonCopyUnionFieldAssignment,
+    BorrowOfPackedField
+});
+impl_stable_hash_for!(struct mir::UnsafetyCheckResult {
+    violations,
+    unsafe_blocks,
+    permitted_violations
+});
 
 impl<'gcx> HashStable<StableHashingContext<'gcx>>
 for mir::UnsafetyViolationKind {
@@ -253,8 +268,9 @@ for mir::AssertMessage<'gcx> {
                 len.hash_stable(hcx, hasher);
                 index.hash_stable(hcx, hasher);
             }
-            mir::AssertMessage::Math(ref const_math_err) => {
+            mir::AssertMessage::Math(ref const_math_err, ref expr) => {
                 const_math_err.hash_stable(hcx, hasher);
+                expr.hash_stable(hcx, hasher);
             }
             mir::AssertMessage::GeneratorResumedAfterReturn => (),
             mir::AssertMessage::GeneratorResumedAfterPanic => (),
@@ -291,6 +307,9 @@ for mir::StatementKind<'gcx> {
                 op.hash_stable(hcx, hasher);
                 lvalues.hash_stable(hcx, hasher);
             }
+            mir::StatementKind::Assume(ref cond) => {
+                cond.hash_stable(hcx, hasher);
+            }
             mir::StatementKind::Nop => {}
             mir::StatementKind::InlineAsm { ref asm, ref outputs, ref inputs } => {
                 asm.hash_stable(hcx, hasher);
@@ -391,11 +410,27 @@ for mir::ProjectionElem<'gcx, V, T>
     }
 }
 
-impl_stable_hash_for!(struct mir::VisibilityScopeData { span, parent_scope });
+impl_stable_hash_for!(struct mir::VisibilityScopeData { span, parent_scope, inlined });
 impl_stable_hash_for!(struct mir::VisibilityScopeInfo {
     lint_root, safety
 });
 
+impl<'gcx> HashStable<StableHashingContext<'gcx>> for mir::BorrowKind {
+    fn hash_stable<W: StableHasherResult>(&self,
+                                          hcx: &mut StableHashingContext<'gcx>,
+                                          hasher: &mut StableHasher<W>) {
+        mem::discriminant(self).hash_stable(hcx, hasher);
+
+        match *self {
+            mir::BorrowKind::Shared |
+            mir::BorrowKind::Unique => {}
+            mir::BorrowKind::Mut { allow_two_phase_borrow } => {
+                allow_two_phase_borrow.hash_stable(hcx, hasher);
+            }
+        }
+    }
+}
+
 impl<'gcx> HashStable<StableHashingContext<'gcx>> for mir::Safety {
     fn hash_stable<W: StableHasherResult>(&self,
                                           hcx: &mut StableHashingContext<'gcx>,
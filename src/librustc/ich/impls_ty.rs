@@ -371,6 +371,7 @@ for ::middle::const_val::ErrKind<'gcx> {
             MiscBinaryOp |
             MiscCatchAll |
             IndexOpFeatureGated |
+            StepLimitExceeded |
             TypeckError |
             CheckMatchError => {
                 // nothing to do
@@ -22,6 +22,18 @@ declare_lint! {
     "constant evaluation detected erroneous expression"
 }
 
+declare_lint! {
+    pub UNCONDITIONAL_PANIC,
+    Deny,
+    "operation that will always panic at runtime, whenever the function is called"
+}
+
+declare_lint! {
+    pub ARITHMETIC_OVERFLOW,
+    Warn,
+    "arithmetic operation that will always overflow, whenever the function is called"
+}
+
 declare_lint! {
     pub UNUSED_IMPORTS,
     Warn,
@@ -58,6 +70,12 @@ declare_lint! {
     "detect assignments that will never be read"
 }
 
+declare_lint! {
+    pub UNREAD_ASSIGNMENTS,
+    Allow,
+    "detect assignments that will never be read, after desugaring"
+}
+
 declare_lint! {
     pub DEAD_CODE,
     Warn,
@@ -76,6 +94,12 @@ declare_lint! {
     "detects unreachable patterns"
 }
 
+declare_lint! {
+    pub EFFECTLESS_INFINITE_LOOPS,
+    Warn,
+    "detects infinite loops that have no side effects"
+}
+
 declare_lint! {
     pub UNUSED_MACROS,
     Warn,
@@ -222,6 +246,12 @@ declare_lint! {
     "detect mut variables which don't need to be mutable"
 }
 
+declare_lint! {
+    pub LARGE_GENERATOR_LOCAL,
+    Allow,
+    "detects large locals which are held across a generator suspension point"
+}
+
 /// Does nothing as a lint pass, but registers some `Lint`s
 /// which are used by other parts of the compiler.
 #[derive(Copy, Clone)]
@@ -236,9 +266,11 @@ impl LintPass for HardwiredLints {
             UNKNOWN_LINTS,
             UNUSED_VARIABLES,
             UNUSED_ASSIGNMENTS,
+            UNREAD_ASSIGNMENTS,
             DEAD_CODE,
             UNREACHABLE_CODE,
             UNREACHABLE_PATTERNS,
+            EFFECTLESS_INFINITE_LOOPS,
             UNUSED_MACROS,
             WARNINGS,
             UNUSED_FEATURES,
@@ -250,6 +282,8 @@ impl LintPass for HardwiredLints {
             PUB_USE_OF_PRIVATE_EXTERN_CRATE,
             INVALID_TYPE_PARAM_DEFAULT,
             CONST_ERR,
+            UNCONDITIONAL_PANIC,
+            ARITHMETIC_OVERFLOW,
             RENAMED_AND_REMOVED_LINTS,
             RESOLVE_TRAIT_ON_DEFAULTED_UNIT,
             SAFE_EXTERN_STATICS,
@@ -263,7 +297,8 @@ impl LintPass for HardwiredLints {
             LATE_BOUND_LIFETIME_ARGUMENTS,
             DEPRECATED,
             UNUSED_UNSAFE,
-            UNUSED_MUT
+            UNUSED_MUT,
+            LARGE_GENERATOR_LOCAL
         )
     }
 }
@@ -103,6 +103,7 @@ pub enum ErrKind<'tcx> {
     IndexOpFeatureGated,
     Math(ConstMathErr),
     LayoutError(layout::LayoutError<'tcx>),
+    StepLimitExceeded,
 
     ErroneousReferencedConstant(Box<ConstEvalErr<'tcx>>),
 
@@ -165,6 +166,7 @@ impl<'a, 'gcx, 'tcx> ConstEvalErr<'tcx> {
             IndexOpFeatureGated => simple!("the index operation on const values is unstable"),
             Math(ref err) => Simple(err.description().into_cow()),
             LayoutError(ref err) => Simple(err.to_string().into_cow()),
+            StepLimitExceeded => simple!("constant evaluation exceeded step limit"),
 
             ErroneousReferencedConstant(_) => simple!("could not evaluate referenced constant"),
 
@@ -8,7 +8,8 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use std::cell::{Ref, RefCell};
+use std::cell::{Cell, Ref, RefCell};
+use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
 use rustc_data_structures::indexed_vec::IndexVec;
 use rustc_data_structures::stable_hasher::{HashStable, StableHasher,
                                            StableHasherResult};
@@ -17,11 +18,29 @@ use mir::{Mir, BasicBlock};
 
 use rustc_serialize as serialize;
 
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 pub struct Cache {
-    predecessors: RefCell<Option<IndexVec<BasicBlock, Vec<BasicBlock>>>>
+    predecessors: RefCell<Option<IndexVec<BasicBlock, Vec<BasicBlock>>>>,
+    version: Cell<usize>,
 }
 
+/// The next version to hand out; shared by all MIR so that a version never
+/// identifies two different bodies.
+static NEXT_VERSION: AtomicUsize = ATOMIC_USIZE_INIT;
+
+fn next_version() -> usize {
+    NEXT_VERSION.fetch_add(1, Ordering::Relaxed)
+}
+
+impl Clone for Cache {
+    fn clone(&self) -> Self {
+        // The clone is a different MIR, so it gets its own version.
+        Cache {
+            predecessors: self.predecessors.clone(),
+            version: Cell::new(next_version()),
+        }
+    }
+}
 
 impl serialize::Encodable for Cache {
     fn encode<S: serialize::Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
@@ -46,13 +65,19 @@ impl<'gcx> HashStable<StableHashingContext<'gcx>> for Cache {
 impl Cache {
     pub fn new() -> Self {
         Cache {
-            predecessors: RefCell::new(None)
+            predecessors: RefCell::new(None),
+            version: Cell::new(next_version()),
         }
     }
 
     pub fn invalidate(&self) {
         // FIXME: consider being more fine-grained
         *self.predecessors.borrow_mut() = None;
+        self.version.set(next_version());
+    }
+
+    pub fn version(&self) -> usize {
+        self.version.get()
     }
 
     pub fn predecessors(&self, mir: &Mir) -> Ref<IndexVec<BasicBlock, Vec<BasicBlock>>> {
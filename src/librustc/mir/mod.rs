@@ -36,6 +36,7 @@ use std::ops::{Index, IndexMut};
 use std::rc::Rc;
 use std::vec::IntoIter;
 use syntax::ast::{self, Name};
+use syntax::symbol::InternedString;
 use syntax_pos::Span;
 
 mod cache;
@@ -179,6 +180,17 @@ impl<'tcx> Mir<'tcx> {
         (&mut self.basic_blocks, &mut self.local_decls)
     }
 
+    /// Returns a number that changes whenever the basic blocks may have
+    /// been mutated, and that no other MIR shares, so that analyses of the
+    /// MIR can be cached until it changes.
+    ///
+    /// Mutating `local_decls` or any other field directly doesn't change
+    /// the version; code doing so must also call `basic_blocks_mut`.
+    #[inline]
+    pub fn version(&self) -> usize {
+        self.cache.version()
+    }
+
     #[inline]
     pub fn predecessors(&self) -> Ref<IndexVec<BasicBlock, Vec<BasicBlock>>> {
         self.cache.predecessors(self)
@@ -281,6 +293,20 @@ impl<'tcx> Mir<'tcx> {
     pub fn return_ty(&self) -> Ty<'tcx> {
         self.local_decls[RETURN_POINTER].ty
     }
+
+    /// Returns a `NodeId` with the lint levels in effect at `source_info`, to
+    /// be passed to `TyCtxt::struct_span_lint_node` by lints emitted from MIR.
+    ///
+    /// Returns `None` if the lint levels are unknown, i.e. if this MIR was
+    /// decoded from another crate or `source_info` comes from inlined MIR.
+    pub fn lint_root(&self, source_info: SourceInfo) -> Option<ast::NodeId> {
+        match self.visibility_scope_info {
+            ClearOnDecode::Set(ref info) => {
+                info.get(source_info.scope).map(|scope_info| scope_info.lint_root)
+            }
+            ClearOnDecode::Clear => None,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -291,7 +317,7 @@ pub struct VisibilityScopeInfo {
     pub safety: Safety,
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Safety {
     Safe,
     /// Unsafe because of a PushUnsafeBlock
@@ -416,7 +442,13 @@ pub enum BorrowKind {
     Unique,
 
     /// Data is mutable and not aliasable.
-    Mut,
+    Mut {
+        /// True if this borrow was created by auto-ref of a method call
+        /// receiver, and so may be a two-phase borrow: it is only
+        /// "reserved" until the receiver is first used, and does not
+        /// conflict with shared reads of the borrowed data before that.
+        allow_two_phase_borrow: bool
+    },
 }
 
 ///////////////////////////////////////////////////////////////////////////
@@ -725,6 +757,9 @@ pub enum TerminatorKind<'tcx> {
     /// Indicates the end of the dropping of a generator
     GeneratorDrop,
 
+    /// A goto to `real_target` that borrowck treats as possibly going to any of
+    /// `imaginary_targets`. Used by match lowering, and replaced by a plain goto
+    /// before optimizations run.
     FalseEdges {
         real_target: BasicBlock,
         imaginary_targets: Vec<BasicBlock>
@@ -913,21 +948,9 @@ impl<'tcx> TerminatorKind<'tcx> {
                 }
                 write!(fmt, "{:?}, ", cond)?;
 
-                match *msg {
-                    AssertMessage::BoundsCheck { ref len, ref index } => {
-                        write!(fmt, "{:?}, {:?}, {:?}",
-                               "index out of bounds: the len is {} but the index is {}",
-                               len, index)?;
-                    }
-                    AssertMessage::Math(ref err) => {
-                        write!(fmt, "{:?}", err.description())?;
-                    }
-                    AssertMessage::GeneratorResumedAfterReturn => {
-                        write!(fmt, "{:?}", "generator resumed after completion")?;
-                    }
-                    AssertMessage::GeneratorResumedAfterPanic => {
-                        write!(fmt, "{:?}", "generator resumed after panicking")?;
-                    }
+                write!(fmt, "{:?}", msg.description())?;
+                if let AssertMessage::BoundsCheck { ref len, ref index } = *msg {
+                    write!(fmt, ", {:?}, {:?}", len, index)?;
                 }
 
                 write!(fmt, ")")
@@ -984,11 +1007,31 @@ pub enum AssertMessage<'tcx> {
         len: Operand<'tcx>,
         index: Operand<'tcx>
     },
-    Math(ConstMathErr),
+    /// An arithmetic error, along with the source text of the expression
+    /// it happened in, if it was recorded.
+    Math(ConstMathErr, Option<InternedString>),
     GeneratorResumedAfterReturn,
     GeneratorResumedAfterPanic,
 }
 
+impl<'tcx> AssertMessage<'tcx> {
+    /// The message to panic with when the assertion fails. For bounds
+    /// checks, this is a format string taking the length and the index.
+    pub fn description(&self) -> Cow<'static, str> {
+        match *self {
+            AssertMessage::BoundsCheck { .. } =>
+                "index out of bounds: the len is {} but the index is {}".into(),
+            AssertMessage::Math(ref err, None) => err.description().into(),
+            AssertMessage::Math(ref err, Some(ref expr)) =>
+                format!("{} in `{}`", err.description(), expr).into(),
+            AssertMessage::GeneratorResumedAfterReturn =>
+                "generator resumed after completion".into(),
+            AssertMessage::GeneratorResumedAfterPanic =>
+                "generator resumed after panicking".into(),
+        }
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////////
 // Statements
 
@@ -1036,6 +1079,11 @@ pub enum StatementKind<'tcx> {
     /// (The starting point(s) arise implicitly from borrows.)
     EndRegion(region::Scope),
 
+    /// Tell the optimizer that the operand, a `bool`, is true. This is what
+    /// calls to the `assume` intrinsic are lowered to, and reaching it with
+    /// a false operand is undefined behavior.
+    Assume(Operand<'tcx>),
+
     /// No-op. Useful for deleting instructions without affecting statement indices.
     Nop,
 }
@@ -1107,6 +1155,7 @@ impl<'tcx> Debug for Statement<'tcx> {
             InlineAsm { ref asm, ref outputs, ref inputs } => {
                 write!(fmt, "asm!({:?} : {:?} : {:?})", asm, outputs, inputs)
             },
+            Assume(ref cond) => write!(fmt, "assume({:?})", cond),
             Nop => write!(fmt, "nop"),
         }
     }
@@ -1273,6 +1322,9 @@ newtype_index!(VisibilityScope
 pub struct VisibilityScopeData {
     pub span: Span,
     pub parent_scope: Option<VisibilityScope>,
+    /// For the root scope of a body inlined from another function, that
+    /// function and the span of the call it was inlined at.
+    pub inlined: Option<(DefId, Span)>,
 }
 
 ///////////////////////////////////////////////////////////////////////////
@@ -1407,7 +1459,7 @@ pub enum AggregateKind<'tcx> {
     Generator(DefId, ClosureSubsts<'tcx>, GeneratorInterior<'tcx>),
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, RustcEncodable, RustcDecodable)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, RustcEncodable, RustcDecodable)]
 pub enum BinOp {
     /// The `+` operator (addition)
     Add,
@@ -1463,7 +1515,7 @@ pub enum NullOp {
     Box,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, RustcEncodable, RustcDecodable)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, RustcEncodable, RustcDecodable)]
 pub enum UnOp {
     /// The `!` operator for logical inversion
     Not,
@@ -1490,7 +1542,7 @@ impl<'tcx> Debug for Rvalue<'tcx> {
             Ref(region, borrow_kind, ref lv) => {
                 let kind_str = match borrow_kind {
                     BorrowKind::Shared => "",
-                    BorrowKind::Mut | BorrowKind::Unique => "mut ",
+                    BorrowKind::Mut { .. } | BorrowKind::Unique => "mut ",
                 };
 
                 // When printing regions, add trailing space if necessary.
@@ -1740,10 +1792,93 @@ pub enum UnsafetyViolationKind {
     BorrowPacked(ast::NodeId),
 }
 
+/// The operation which caused an `UnsafetyViolation`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum UnsafetyViolationDetails {
+    UnsafeCall,
+    InlineAsm,
+    RawPtrDeref,
+    MutableStatic,
+    ExternStatic,
+    UnionFieldRead,
+    UnionFieldWrite,
+    UnionFieldBorrow,
+    NonCopyUnionFieldAssignment,
+    BorrowOfPackedField,
+}
+
+impl UnsafetyViolationDetails {
+    /// A short description of the operation, e.g. "call to unsafe function".
+    pub fn description(&self) -> &'static str {
+        use self::UnsafetyViolationDetails::*;
+        match *self {
+            UnsafeCall => "call to unsafe function",
+            InlineAsm => "use of inline assembly",
+            RawPtrDeref => "dereference of raw pointer",
+            MutableStatic => "use of mutable static",
+            ExternStatic => "use of extern static",
+            UnionFieldRead => "read of union field",
+            UnionFieldWrite => "write to union field",
+            UnionFieldBorrow => "borrow of union field",
+            NonCopyUnionFieldAssignment => "assignment to non-`Copy` union field",
+            BorrowOfPackedField => "borrow of packed field",
+        }
+    }
+
+    /// Explains why the operation requires `unsafe`.
+    pub fn note(&self) -> &'static str {
+        use self::UnsafetyViolationDetails::*;
+        match *self {
+            UnsafeCall => {
+                "consult the function's documentation for information on how to avoid \
+                 undefined behavior"
+            }
+            InlineAsm => {
+                "inline assembly is entirely unchecked and can cause undefined behavior"
+            }
+            RawPtrDeref => {
+                "raw pointers may be NULL, dangling or unaligned; they can violate aliasing \
+                 rules and cause data races: all of these are undefined behavior"
+            }
+            MutableStatic => {
+                "mutable statics can be mutated by multiple threads: aliasing violations or \
+                 data races will cause undefined behavior"
+            }
+            ExternStatic => {
+                "extern statics are not controlled by the Rust type system: invalid data, \
+                 aliasing violations or data races will cause undefined behavior"
+            }
+            UnionFieldRead => {
+                "the field may not be properly initialized: using uninitialized data will \
+                 cause undefined behavior"
+            }
+            UnionFieldWrite => {
+                "the write may overwrite the contents of another field that is still in use, \
+                 or only initialize part of this one: the value can be leaked or misinterpreted"
+            }
+            UnionFieldBorrow => {
+                "the field may not be properly initialized: a reference to it may expose \
+                 uninitialized data, which causes undefined behavior"
+            }
+            NonCopyUnionFieldAssignment => {
+                "the previous content of the field will be dropped, which causes undefined \
+                 behavior if the field was not properly initialized"
+            }
+            BorrowOfPackedField => {
+                "fields of packed structs might be misaligned: dereferencing a misaligned \
+                 pointer or even just creating a misaligned reference is undefined behavior"
+            }
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct UnsafetyViolation {
     pub source_info: SourceInfo,
-    pub description: &'static str,
+    pub details: UnsafetyViolationDetails,
+    /// A user-facing rendering of the lvalue involved in the operation, if
+    /// there is one and it can be named in source (e.g. `*ptr` or `u.field`).
+    pub lvalue: Option<InternedString>,
     pub kind: UnsafetyViolationKind,
 }
 
@@ -1754,6 +1889,9 @@ pub struct UnsafetyCheckResult {
     /// unsafe blocks in this function, along with whether they are used. This is
     /// used for the "unused_unsafe" lint.
     pub unsafe_blocks: Rc<[(ast::NodeId, bool)]>,
+    /// Violations that are allowed because they occur in an unsafe context,
+    /// along with the safety of that context. Only used for reporting.
+    pub permitted_violations: Rc<[(UnsafetyViolation, Safety)]>,
 }
 
 /// The layout of generator state
@@ -1877,6 +2015,8 @@ impl<'tcx> TypeFoldable<'tcx> for Statement<'tcx> {
                 Validate(op.clone(),
                          lvals.iter().map(|operand| operand.fold_with(folder)).collect()),
 
+            Assume(ref cond) => Assume(cond.fold_with(folder)),
+
             Nop => Nop,
         };
         Statement {
@@ -1905,6 +2045,8 @@ impl<'tcx> TypeFoldable<'tcx> for Statement<'tcx> {
             Validate(ref _op, ref lvalues) =>
                 lvalues.iter().any(|ty_and_lvalue| ty_and_lvalue.visit_with(visitor)),
 
+            Assume(ref cond) => cond.visit_with(visitor),
+
             Nop => false,
         }
     }
@@ -331,12 +331,16 @@ macro_rules! make_mir_visitor {
                 let VisibilityScopeData {
                     ref $($mutability)* span,
                     ref $($mutability)* parent_scope,
+                    ref $($mutability)* inlined,
                 } = *scope_data;
 
                 self.visit_span(span);
                 if let Some(ref $($mutability)* parent_scope) = *parent_scope {
                     self.visit_visibility_scope(parent_scope);
                 }
+                if let Some((_, ref $($mutability)* call_span)) = *inlined {
+                    self.visit_span(call_span);
+                }
             }
 
             fn super_statement(&mut self,
@@ -382,6 +386,9 @@ macro_rules! make_mir_visitor {
                             self.visit_operand(input, location);
                         }
                     }
+                    StatementKind::Assume(ref $($mutability)* cond) => {
+                        self.visit_operand(cond, location);
+                    }
                     StatementKind::Nop => {}
                 }
             }
@@ -510,7 +517,7 @@ macro_rules! make_mir_visitor {
                         self.visit_operand(len, location);
                         self.visit_operand(index, location);
                     }
-                    AssertMessage::Math(_) => {},
+                    AssertMessage::Math(..) => {},
                     AssertMessage::GeneratorResumedAfterReturn => {},
                     AssertMessage::GeneratorResumedAfterPanic => {},
                 }
@@ -911,7 +918,7 @@ impl<'tcx> LvalueContext<'tcx> {
     pub fn is_mutating_use(&self) -> bool {
         match *self {
             LvalueContext::Store | LvalueContext::Call |
-            LvalueContext::Borrow { kind: BorrowKind::Mut, .. } |
+            LvalueContext::Borrow { kind: BorrowKind::Mut { .. }, .. } |
             LvalueContext::Projection(Mutability::Mut) |
             LvalueContext::Drop => true,
             LvalueContext::Inspect |
@@ -931,7 +938,7 @@ impl<'tcx> LvalueContext<'tcx> {
             LvalueContext::Borrow { kind: BorrowKind::Unique, .. } |
             LvalueContext::Projection(Mutability::Not) |
             LvalueContext::Copy | LvalueContext::Move => true,
-            LvalueContext::Borrow { kind: BorrowKind::Mut, .. } | LvalueContext::Store |
+            LvalueContext::Borrow { kind: BorrowKind::Mut { .. }, .. } | LvalueContext::Store |
             LvalueContext::Call | LvalueContext::Projection(Mutability::Mut) |
             LvalueContext::Drop | LvalueContext::StorageLive | LvalueContext::StorageDead |
             LvalueContext::Validate => false,
@@ -735,6 +735,8 @@ macro_rules! options {
             Some("a space-separated list of passes, or `all`");
         pub const parse_opt_uint: Option<&'static str> =
             Some("a number");
+        pub const parse_mir_validate_skip: Option<&'static str> =
+            Some("a space-separated list of: `unions`, `packed`, or `raw-ptrs`");
         pub const parse_panic_strategy: Option<&'static str> =
             Some("either `panic` or `abort`");
         pub const parse_relro_level: Option<&'static str> =
@@ -862,6 +864,21 @@ macro_rules! options {
             }
         }
 
+        fn parse_mir_validate_skip(slot: &mut Vec<String>, v: Option<&str>) -> bool {
+            match v {
+                Some(s) => {
+                    for s in s.split_whitespace() {
+                        match s {
+                            "unions" | "packed" | "raw-ptrs" => slot.push(s.to_string()),
+                            _ => return false,
+                        }
+                    }
+                    true
+                },
+                None => false,
+            }
+        }
+
         fn parse_panic_strategy(slot: &mut Option<PanicStrategy>, v: Option<&str>) -> bool {
             match v {
                 Some("unwind") => *slot = Some(PanicStrategy::Unwind),
@@ -1016,6 +1033,8 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
         "The output of `-Z time-llvm-passes` will only reflect timings of \
          re-translated modules when used with incremental compilation" )],
         "measure time of each LLVM pass"),
+    time_mir_passes: bool = (false, parse_bool, [UNTRACKED],
+        "measure time of each MIR pass, as `-Z time-passes` does too"),
     input_stats: bool = (false, parse_bool, [UNTRACKED],
         "gather statistics about the input"),
     trans_stats: bool = (false, parse_bool, [UNTRACKED_WITH_WARNING(true,
@@ -1112,29 +1131,88 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
           "show spans for compiler debugging (expr|pat|ty)"),
     print_type_sizes: bool = (false, parse_bool, [UNTRACKED],
           "print layout information for each type encountered"),
+    unsafety_report: Option<String> = (None, parse_opt_string, [UNTRACKED],
+          "write the unsafe operations of each body to the given path as JSON"),
+    print_generator_sizes: bool = (false, parse_bool, [UNTRACKED],
+          "print the size of each generator and the locals it saves across suspension points"),
+    large_generator_local_threshold: usize = (1024, parse_uint, [TRACKED],
+          "the size in bytes above which the `large_generator_local` lint reports a saved local"),
     print_trans_items: Option<String> = (None, parse_opt_string, [UNTRACKED],
           "print the result of the translation item collection pass"),
     mir_opt_level: usize = (1, parse_uint, [TRACKED],
           "set the MIR optimization level (0-3, default: 1)"),
+    mir_opt_size_limit: Option<usize> = (None, parse_opt_uint, [TRACKED],
+          "skip the optional MIR passes on bodies with more statements than this"),
+    inline_mir_threshold: usize = (50, parse_uint, [TRACKED],
+          "the cost up to which the MIR inliner inlines a callee (default: 50)"),
+    inline_mir_hint_threshold: usize = (100, parse_uint, [TRACKED],
+          "the cost up to which the MIR inliner inlines an `#[inline]` callee (default: 100)"),
+    inline_mir_ignore_target_features: bool = (false, parse_bool, [TRACKED],
+          "let the MIR inliner inline callees whose `#[target_feature]`s the caller lacks"),
+    mir_passes: Option<String> = (None, parse_opt_string, [TRACKED],
+          "run the given comma-separated list of MIR passes, in that order, instead of the \
+           default pipeline"),
+    mir_enable_passes: Option<String> = (None, parse_opt_string, [TRACKED],
+          "enable (`+pass`) or disable (`-pass`) MIR passes on top of the pipeline, \
+           in a comma-separated list"),
+    mir_run_only_pass: Option<String> = (None, parse_opt_string, [TRACKED],
+          "run only the given optional MIR pass, and the mandatory ones, to test it in isolation"),
+    mir_print_passes: bool = (false, parse_bool, [UNTRACKED],
+          "print the MIR passes in the order they run in, and whether they are enabled"),
     mutable_noalias: bool = (false, parse_bool, [UNTRACKED],
           "emit noalias metadata for mutable references"),
     dump_mir: Option<String> = (None, parse_opt_string, [UNTRACKED],
-          "dump MIR state at various points in translation"),
+          "dump MIR state at various points in translation, for the passes and items \
+           matching a comma-separated list of patterns (`all`, a pass name, or an item \
+           path with `*` globs, optionally excluded with `!`)"),
     dump_mir_dir: Option<String> = (None, parse_opt_string, [UNTRACKED],
-          "the directory the MIR is dumped into"),
+          "the directory the MIR is dumped into (or a subdirectory of it for each crate \
+           after the first one compiled in the process)"),
     dump_mir_graphviz: bool = (false, parse_bool, [UNTRACKED],
           "in addition to `.mir` files, create graphviz `.dot` files"),
+    dump_mir_only_changed: bool = (false, parse_bool, [UNTRACKED],
+          "only dump MIR before and after the passes that change it"),
+    dump_mir_diff: bool = (false, parse_bool, [UNTRACKED],
+          "in addition to `.mir` files, create `.diff` files between the MIR before and after \
+           the passes that change it"),
+    dump_mir_json: bool = (false, parse_bool, [UNTRACKED],
+          "in addition to `.mir` files, create `.json` files with the MIR serialized as JSON"),
+    dump_mir_spanview: bool = (false, parse_bool, [UNTRACKED],
+          "in addition to `.mir` files, create `.html` files showing the source covered by \
+           the span of each MIR statement and terminator"),
     dump_mir_exclude_pass_number: bool = (false, parse_bool, [UNTRACKED],
           "if set, exclude the pass number when dumping MIR (used in tests)"),
+    dump_mir_def_use: bool = (false, parse_bool, [UNTRACKED],
+          "when dumping MIR, list the defs and uses of each local after the body"),
+    dump_mir_liveness: bool = (false, parse_bool, [UNTRACKED],
+          "when dumping MIR, annotate blocks and statements with the locals live there"),
+    dump_mir_spans: bool = (false, parse_bool, [UNTRACKED],
+          "when dumping MIR, list the source scopes with their parent and span after the locals"),
+    dump_mir_dataflow: Option<String> = (None, parse_opt_string, [UNTRACKED],
+          "render the results of the named MIR dataflow analysis (e.g. `maybe_init`) \
+           for each body as a graphviz `.dot` file in the MIR dump directory"),
+    verify_mir: bool = (false, parse_bool, [UNTRACKED],
+          "check MIR for structural and type errors after every MIR pass"),
     mir_emit_validate: usize = (0, parse_uint, [TRACKED],
           "emit Validate MIR statements, interpreted e.g. by miri (0: do not emit; 1: if function \
            contains unsafe block, only validate arguments; 2: always emit full validation)"),
+    mir_emit_validate_skip: Vec<String> = (Vec::new(), parse_mir_validate_skip, [TRACKED],
+          "do not emit Validate MIR statements for these kinds of lvalues (`unions`: lvalues \
+           of union type and their fields; `packed`: fields of packed structs; `raw-ptrs`: \
+           the contents behind raw pointers)"),
+    insert_loop_side_effects: bool = (false, parse_bool, [TRACKED],
+          "insert a side effect into infinite loops that have none, so that LLVM can't assume \
+           they terminate"),
     perf_stats: bool = (false, parse_bool, [UNTRACKED],
           "print some performance-related statistics"),
     hir_stats: bool = (false, parse_bool, [UNTRACKED],
           "print some statistics about AST and HIR"),
+    mir_opt_journal: Option<String> = (None, parse_opt_string, [UNTRACKED],
+          "write a line of JSON to the given file for each change made by the MIR passes \
+           that report their changes"),
     mir_stats: bool = (false, parse_bool, [UNTRACKED],
-          "print some statistics about MIR"),
+          "print the size of the optimized MIR, how much each MIR pass changes it, \
+           and the largest MIR bodies"),
     always_encode_mir: bool = (false, parse_bool, [TRACKED],
           "encode MIR of all functions into the crate metadata"),
     osx_rpath_install_name: bool = (false, parse_bool, [TRACKED],
@@ -1163,6 +1241,8 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
         "choose which RELRO level to use"),
     nll: bool = (false, parse_bool, [UNTRACKED],
                  "run the non-lexical lifetimes MIR pass"),
+    two_phase_borrows: bool = (false, parse_bool, [UNTRACKED],
+        "in MIR borrowck, let the `&mut` auto-ref of a method receiver be shared until used"),
     trans_time_graph: bool = (false, parse_bool, [UNTRACKED],
         "generate a graphical HTML report of time spent in trans and LLVM"),
     thinlto: bool = (false, parse_bool, [TRACKED],
@@ -2652,6 +2732,8 @@ mod tests {
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.time_llvm_passes = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.time_mir_passes = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.input_stats = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.trans_stats = true;
@@ -2712,6 +2794,28 @@ mod tests {
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.dump_mir_graphviz = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.dump_mir_only_changed = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.dump_mir_diff = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.dump_mir_json = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.dump_mir_spanview = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.dump_mir_def_use = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.dump_mir_liveness = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.dump_mir_spans = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.dump_mir_dataflow = Some(String::from("abc"));
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.verify_mir = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.mir_opt_journal = Some(String::from("abc"));
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.mir_print_passes = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
 
         // Make sure changing a [TRACKED] option changes the hash
         opts = reference.clone();
@@ -2758,6 +2862,42 @@ mod tests {
         opts.debugging_opts.mir_opt_level = 3;
         assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
 
+        opts = reference.clone();
+        opts.debugging_opts.mir_opt_size_limit = Some(1000);
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.debugging_opts.inline_mir_threshold = 10;
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.debugging_opts.inline_mir_hint_threshold = 10;
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.debugging_opts.inline_mir_ignore_target_features = true;
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.debugging_opts.mir_passes = Some(String::from("abc"));
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.debugging_opts.mir_enable_passes = Some(String::from("abc"));
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.debugging_opts.mir_run_only_pass = Some(String::from("abc"));
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.debugging_opts.mir_emit_validate_skip = vec![String::from("unions")];
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.debugging_opts.insert_loop_side_effects = true;
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
         opts = reference.clone();
         opts.debugging_opts.relro_level = Some(RelroLevel::Full);
         assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
@@ -0,0 +1,175 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The filter of `-Z dump-mir`, which selects the MIR bodies to dump, and
+//! the passes to dump them at.
+//!
+//! The filter is a list of patterns separated by commas (or `&`, as it
+//! used to be). A pattern selects:
+//!
+//! - everything, if it is `all`;
+//! - the passes whose name contains it;
+//! - the items whose path ends with it, segment by segment, a `*`
+//!   matching any part of a segment. So `foo::bar` selects `foo::bar` and
+//!   `baz::foo::bar` but not `foo::barrier`, `<impl*>::*` selects the
+//!   methods of inherent impls, and `{{closure}}` selects closures.
+//!
+//! A pattern starting with `!` is an exclusion. A body is dumped at a pass
+//! when it is selected by one of the other patterns, or there are none,
+//! and it is selected by none of the exclusions. So `all,!{{closure}}`,
+//! like `!{{closure}}`, selects everything but closures.
+
+#[derive(Clone, Debug)]
+pub struct DumpMirFilter {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+#[derive(Clone, Debug)]
+enum Pattern {
+    All,
+    Glob(String),
+}
+
+impl DumpMirFilter {
+    pub fn parse(filters: &str) -> DumpMirFilter {
+        let mut filter = DumpMirFilter { include: vec![], exclude: vec![] };
+        for pattern in filters.split(|c| c == ',' || c == '&') {
+            let pattern = pattern.trim();
+            let (patterns, pattern) = if pattern.starts_with('!') {
+                (&mut filter.exclude, pattern[1..].trim())
+            } else {
+                (&mut filter.include, pattern)
+            };
+            if pattern.is_empty() {
+                continue;
+            }
+            patterns.push(if pattern == "all" {
+                Pattern::All
+            } else {
+                Pattern::Glob(pattern.to_string())
+            });
+        }
+        if filter.include.is_empty() && !filter.exclude.is_empty() {
+            filter.include.push(Pattern::All);
+        }
+        filter
+    }
+
+    /// Whether the body of the item at `item_path` is dumped at the pass
+    /// named `pass_name`.
+    pub fn matches(&self, pass_name: &str, item_path: &str) -> bool {
+        let selected = |pattern: &Pattern| pattern.matches(pass_name, item_path);
+        self.include.iter().any(&selected) && !self.exclude.iter().any(&selected)
+    }
+}
+
+impl Pattern {
+    fn matches(&self, pass_name: &str, item_path: &str) -> bool {
+        match *self {
+            Pattern::All => true,
+            Pattern::Glob(ref glob) => {
+                pass_name.contains(&glob[..]) || path_matches(glob, item_path)
+            }
+        }
+    }
+}
+
+/// Whether the last segments of `path` are matched by those of `glob`.
+fn path_matches(glob: &str, path: &str) -> bool {
+    let glob: Vec<_> = glob.split("::").collect();
+    let path: Vec<_> = path.split("::").collect();
+    glob.len() <= path.len() &&
+        glob.iter().rev().zip(path.iter().rev()).all(|(glob, segment)| {
+            segment_matches(glob, segment)
+        })
+}
+
+/// Whether `glob` matches all of `segment`, a `*` matching any sequence of
+/// characters.
+fn segment_matches(glob: &str, segment: &str) -> bool {
+    let mut parts = glob.split('*');
+    let first = parts.next().unwrap();
+    if !segment.starts_with(first) {
+        return false;
+    }
+    let mut rest = &segment[first.len()..];
+    let parts: Vec<_> = parts.collect();
+    let (last, middle) = match parts.split_last() {
+        Some(split) => split,
+        None => return rest.is_empty(),
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DumpMirFilter;
+
+    fn dumped(filters: &str, pass_name: &str, item_path: &str) -> bool {
+        DumpMirFilter::parse(filters).matches(pass_name, item_path)
+    }
+
+    #[test]
+    fn all() {
+        assert!(dumped("all", "SimplifyCfg-final", "foo::bar"));
+        assert!(!dumped("", "SimplifyCfg-final", "foo::bar"));
+    }
+
+    #[test]
+    fn pass_names() {
+        assert!(dumped("SimplifyCfg", "SimplifyCfg-final", "foo::bar"));
+        assert!(!dumped("SimplifyCfg", "ElaborateDrops", "foo::bar"));
+    }
+
+    #[test]
+    fn whole_segments() {
+        assert!(dumped("foo::bar", "ElaborateDrops", "foo::bar"));
+        assert!(dumped("bar", "ElaborateDrops", "foo::bar"));
+        assert!(dumped("foo::bar", "ElaborateDrops", "baz::foo::bar"));
+        assert!(!dumped("foo::bar", "ElaborateDrops", "foo::barrier"));
+        assert!(!dumped("foo::bar", "ElaborateDrops", "bar"));
+        assert!(!dumped("foo", "ElaborateDrops", "foo::bar"));
+    }
+
+    #[test]
+    fn globs() {
+        assert!(dumped("foo::*", "ElaborateDrops", "foo::bar"));
+        assert!(dumped("*bar*", "ElaborateDrops", "foo::barrier"));
+        assert!(dumped("b*r", "ElaborateDrops", "foo::bar"));
+        assert!(dumped("b*r", "ElaborateDrops", "foo::br"));
+        assert!(!dumped("b*r", "ElaborateDrops", "foo::barrier"));
+        assert!(dumped("<impl*>::*", "ElaborateDrops", "<impl at foo.rs:3:1>::new"));
+        assert!(!dumped("<impl*>::*", "ElaborateDrops", "foo::new"));
+    }
+
+    #[test]
+    fn exclusions() {
+        assert!(!dumped("all,!{{closure}}", "ElaborateDrops", "main::{{closure}}"));
+        assert!(dumped("all,!{{closure}}", "ElaborateDrops", "main"));
+        assert!(dumped("!{{closure}}", "ElaborateDrops", "main"));
+        assert!(!dumped("foo::*, !foo::bar", "ElaborateDrops", "foo::bar"));
+        assert!(dumped("foo::*, !foo::bar", "ElaborateDrops", "foo::baz"));
+        assert!(!dumped("main,!SimplifyCfg", "SimplifyCfg-final", "main"));
+    }
+
+    #[test]
+    fn separators() {
+        assert!(dumped("foo&bar", "ElaborateDrops", "bar"));
+        assert!(dumped("foo,bar", "ElaborateDrops", "foo"));
+        assert!(!dumped("foo,bar", "ElaborateDrops", "baz"));
+    }
+}
@@ -0,0 +1,75 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The directory the MIR of a crate is dumped into under `-Z dump-mir`,
+//! and its index.
+//!
+//! The first crate compiled in the process dumps its MIR into the
+//! `-Z dump-mir-dir` directory itself, and every other one, like the
+//! harness of `--test` or the doctests of rustdoc, into a subdirectory of
+//! it, so that crates with the same items don't overwrite each other's
+//! dumps.
+//!
+//! The index, `index.txt`, maps the start of the names of the files of
+//! each item dumped to the full path of the item, one tab-separated pair
+//! per line, as the file names only show the end of the path.
+
+use rustc_data_structures::fx::FxHashSet;
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+
+/// The number of crates of this process that have dumped MIR.
+static CRATES_DUMPED: AtomicUsize = ATOMIC_USIZE_INIT;
+
+pub struct MirDumpDir {
+    path: PathBuf,
+
+    /// The index, created with its first entry.
+    index: Option<File>,
+
+    /// The file name prefixes already in the index.
+    indexed: FxHashSet<String>,
+}
+
+impl MirDumpDir {
+    /// Picks the directory the MIR of the crate `crate_name` is dumped
+    /// into: `base` for the first crate of the process, and
+    /// `base/<crate_name>-<n>` for the `n`th one after it.
+    pub fn new(base: &Path, crate_name: &str) -> MirDumpDir {
+        let mut path = base.to_path_buf();
+        let n = CRATES_DUMPED.fetch_add(1, Ordering::SeqCst);
+        if n > 0 {
+            path.push(format!("{}-{}", crate_name, n));
+        }
+        MirDumpDir { path, index: None, indexed: FxHashSet() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Records in the index that the files whose name starts with `prefix`
+    /// are dumps of the item at `item_path`, ignoring errors.
+    pub fn index(&mut self, prefix: &str, item_path: &str) {
+        if !self.indexed.insert(prefix.to_string()) {
+            return;
+        }
+        if self.index.is_none() {
+            let _ = fs::create_dir_all(&self.path);
+            self.index = File::create(self.path.join("index.txt")).ok();
+        }
+        if let Some(ref mut index) = self.index {
+            let _ = writeln!(index, "{}\t{}", prefix, item_path);
+        }
+    }
+}
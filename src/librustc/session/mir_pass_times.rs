@@ -0,0 +1,77 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The times of the MIR passes printed by `-Z time-passes` and
+//! `-Z time-mir-passes`: how long each pass took over all the bodies it
+//! ran on, and the body it took the longest on.
+//!
+//! Each line has the format of the other `-Z time-passes` lines, with the
+//! dump number and the registered name of the pass, so that
+//! `MIR pass 002-017 copy-prop` is the line of copy propagation.
+
+use util::common::{duration_to_secs_str, print_time_passes_entry};
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+struct PassTime {
+    name: &'static str,
+    total: Duration,
+    /// The number of times the pass ran, on a body or a round of a group.
+    runs: usize,
+    slowest: Duration,
+    slowest_body: String,
+}
+
+pub struct MirPassTimes {
+    /// The passes that ran, by suite and index in the suite.
+    passes: BTreeMap<(usize, usize), PassTime>,
+}
+
+impl MirPassTimes {
+    pub fn new() -> Self {
+        MirPassTimes { passes: BTreeMap::new() }
+    }
+
+    /// Records that the pass `name`, the `index`th of the suite
+    /// `suite_index`, took `time` on a body, calling `body` for the path of
+    /// the body only when the pass took the longest on it so far.
+    pub fn record<F>(&mut self,
+                     suite_index: usize,
+                     index: usize,
+                     name: &'static str,
+                     time: Duration,
+                     body: F)
+        where F: FnOnce() -> String
+    {
+        let pass = self.passes.entry((suite_index, index)).or_insert_with(|| PassTime {
+            name,
+            total: Duration::new(0, 0),
+            runs: 0,
+            slowest: Duration::new(0, 0),
+            slowest_body: String::new(),
+        });
+        pass.total += time;
+        pass.runs += 1;
+        if pass.runs == 1 || time > pass.slowest {
+            pass.slowest = time;
+            pass.slowest_body = body();
+        }
+    }
+
+    pub fn print(&self) {
+        for (&(suite_index, index), pass) in &self.passes {
+            let what = format!("MIR pass {:03}-{:03} {} ({} runs, slowest: {} on `{}`)",
+                               suite_index, index, pass.name, pass.runs,
+                               duration_to_secs_str(pass.slowest), pass.slowest_body);
+            print_time_passes_entry(true, &what, pass.total);
+        }
+    }
+}
@@ -0,0 +1,176 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+//! The statistics printed by `-Z mir-stats`: the size of the optimized MIR
+//! of the crate, how much each MIR pass grows or shrinks the bodies it
+//! runs on, and the largest bodies.
+
+use rustc_data_structures::fx::FxHashMap;
+
+use std::collections::BTreeMap;
+
+/// The number of the parts of one or more MIR bodies.
+#[derive(Clone, Default, Debug)]
+pub struct MirCounts {
+    pub basic_blocks: usize,
+    pub locals: usize,
+    /// The number of statements of each kind.
+    pub statements: FxHashMap<&'static str, usize>,
+    /// The number of terminators of each kind.
+    pub terminators: FxHashMap<&'static str, usize>,
+}
+
+impl MirCounts {
+    pub fn statements(&self) -> usize {
+        self.statements.values().sum()
+    }
+
+    pub fn terminators(&self) -> usize {
+        self.terminators.values().sum()
+    }
+
+    fn add(&mut self, other: &MirCounts) {
+        self.basic_blocks += other.basic_blocks;
+        self.locals += other.locals;
+        for (&kind, &count) in &other.statements {
+            *self.statements.entry(kind).or_insert(0) += count;
+        }
+        for (&kind, &count) in &other.terminators {
+            *self.terminators.entry(kind).or_insert(0) += count;
+        }
+    }
+}
+
+#[derive(Debug)]
+struct PassStats {
+    name: String,
+    /// The sums of the counts of the bodies before and after the pass.
+    before: MirCounts,
+    after: MirCounts,
+}
+
+#[derive(Debug)]
+pub struct MirStats {
+    /// The passes that ran, by suite and index in the suite.
+    passes: BTreeMap<(usize, usize), PassStats>,
+    /// The optimized bodies, with their item path.
+    bodies: Vec<(String, MirCounts)>,
+}
+
+impl MirStats {
+    pub fn new() -> Self {
+        MirStats { passes: BTreeMap::new(), bodies: vec![] }
+    }
+
+    /// Records that the pass `name`, the `index`th of the suite
+    /// `suite_index`, changed the counts of a body from `before` to `after`.
+    pub fn record_pass(&mut self,
+                       suite_index: usize,
+                       index: usize,
+                       name: &str,
+                       before: &MirCounts,
+                       after: &MirCounts) {
+        let pass = self.passes.entry((suite_index, index)).or_insert_with(|| PassStats {
+            name: name.to_string(),
+            before: MirCounts::default(),
+            after: MirCounts::default(),
+        });
+        pass.before.add(before);
+        pass.after.add(after);
+    }
+
+    /// Records the counts of an optimized body.
+    pub fn record_body(&mut self, item_path: String, counts: MirCounts) {
+        self.bodies.push((item_path, counts));
+    }
+
+    pub fn print(&self) {
+        let mut total = MirCounts::default();
+        for &(_, ref counts) in &self.bodies {
+            total.add(counts);
+        }
+        println!("mir-stats: {} bodies, {} basic blocks, {} locals, {} statements, \
+                  {} terminators",
+                 self.bodies.len(), total.basic_blocks, total.locals, total.statements(),
+                 total.terminators());
+        println!("mir-stats statements: {}", kind_counts(&total.statements));
+        println!("mir-stats terminators: {}", kind_counts(&total.terminators));
+
+        println!("mir-stats pass deltas:");
+        for (&(suite_index, index), pass) in &self.passes {
+            let (before, after) = (&pass.before, &pass.after);
+            println!("mir-stats     {:03}-{:03} {}: basic blocks {}, locals {}, \
+                      statements {}{}, terminators {}{}",
+                     suite_index, index, pass.name,
+                     delta(before.basic_blocks, after.basic_blocks),
+                     delta(before.locals, after.locals),
+                     delta(before.statements(), after.statements()),
+                     kind_deltas(&before.statements, &after.statements),
+                     delta(before.terminators(), after.terminators()),
+                     kind_deltas(&before.terminators, &after.terminators));
+        }
+
+        // Largest first, then by path.
+        let mut bodies: Vec<_> = self.bodies.iter()
+            .map(|&(ref path, ref counts)| (counts.statements(), path))
+            .collect();
+        bodies.sort_by(|&(a_count, a_path), &(b_count, b_path)| {
+            b_count.cmp(&a_count).then_with(|| a_path.cmp(b_path))
+        });
+        println!("mir-stats largest bodies:");
+        for &(count, path) in bodies.iter().take(10) {
+            println!("mir-stats     {} statements: {}", count, path);
+        }
+    }
+}
+
+/// Formats `counts` as `Kind: count` pairs, by kind.
+fn kind_counts(counts: &FxHashMap<&'static str, usize>) -> String {
+    let mut counts: Vec<_> = counts.iter().collect();
+    counts.sort();
+    let counts: Vec<_> = counts.iter()
+        .map(|&(kind, count)| format!("{}: {}", kind, count))
+        .collect();
+    counts.join(", ")
+}
+
+/// Formats the change from `before` to `after` with its sign.
+fn delta(before: usize, after: usize) -> String {
+    if after >= before {
+        format!("+{}", after - before)
+    } else {
+        format!("-{}", before - after)
+    }
+}
+
+/// Formats the kinds whose count changed, as ` (Kind +n, ...)`, or nothing
+/// if none did.
+fn kind_deltas(before: &FxHashMap<&'static str, usize>,
+               after: &FxHashMap<&'static str, usize>)
+               -> String {
+    let mut kinds: Vec<_> = before.keys().chain(after.keys()).cloned().collect();
+    kinds.sort();
+    kinds.dedup();
+    let deltas: Vec<_> = kinds.iter().filter_map(|kind| {
+        let before = before.get(kind).cloned().unwrap_or(0);
+        let after = after.get(kind).cloned().unwrap_or(0);
+        if before == after {
+            None
+        } else {
+            Some(format!("{} {}", kind, delta(before, after)))
+        }
+    }).collect();
+    if deltas.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", deltas.join(", "))
+    }
+}
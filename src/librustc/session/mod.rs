@@ -10,6 +10,9 @@
 
 pub use self::code_stats::{CodeStats, DataTypeKind, FieldInfo};
 pub use self::code_stats::{SizeKind, TypeSizeInfo, VariantInfo};
+pub use self::mir_dump_dir::MirDumpDir;
+pub use self::mir_pass_times::MirPassTimes;
+pub use self::mir_stats::{MirCounts, MirStats};
 
 use hir::def_id::{CrateNum, DefIndex};
 use ich::Fingerprint;
@@ -19,6 +22,7 @@ use middle::allocator::AllocatorKind;
 use middle::dependency_format;
 use session::search_paths::PathKind;
 use session::config::DebugInfoLevel;
+use session::dump_mir_filter::DumpMirFilter;
 use ty::tls;
 use util::nodemap::{FxHashMap, FxHashSet};
 use util::common::{duration_to_secs_str, ErrorReported};
@@ -43,6 +47,7 @@ use std::cell::{self, Cell, RefCell};
 use std::collections::HashMap;
 use std::env;
 use std::fmt;
+use std::fs::File;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
@@ -51,7 +56,11 @@ use std::time::Duration;
 
 mod code_stats;
 pub mod config;
+pub mod dump_mir_filter;
 pub mod filesearch;
+mod mir_dump_dir;
+mod mir_pass_times;
+mod mir_stats;
 pub mod search_paths;
 
 /// Represents the data associated with a compilation
@@ -118,6 +127,18 @@ pub struct Session {
     /// Data about code being compiled, gathered during compilation.
     pub code_stats: RefCell<CodeStats>,
 
+    /// The statistics about MIR printed by `-Z mir-stats`.
+    pub mir_stats: RefCell<MirStats>,
+
+    /// The times of the MIR passes printed by `-Z time-mir-passes`.
+    pub mir_pass_times: RefCell<MirPassTimes>,
+
+    /// The file of `-Z mir-opt-journal`, created on the first change.
+    pub mir_opt_journal: RefCell<Option<File>>,
+
+    /// The directory the MIR is dumped into, picked on the first dump.
+    pub mir_dump_dir: RefCell<Option<MirDumpDir>>,
+
     next_node_id: Cell<ast::NodeId>,
 
     /// If -zfuel=crate=n is specified, Some(crate).
@@ -140,6 +161,9 @@ pub struct Session {
 
     /// Metadata about the allocators for the current crate being compiled
     pub has_global_allocator: Cell<bool>,
+
+    /// The filter of `-Z dump-mir`, if any.
+    pub dump_mir_filter: Option<DumpMirFilter>,
 }
 
 pub struct PerfStats {
@@ -405,6 +429,9 @@ impl Session {
     pub fn time_llvm_passes(&self) -> bool {
         self.opts.debugging_opts.time_llvm_passes
     }
+    pub fn time_mir_passes(&self) -> bool {
+        self.opts.debugging_opts.time_passes || self.opts.debugging_opts.time_mir_passes
+    }
     pub fn trans_stats(&self) -> bool { self.opts.debugging_opts.trans_stats }
     pub fn meta_stats(&self) -> bool { self.opts.debugging_opts.meta_stats }
     pub fn asm_comments(&self) -> bool { self.opts.debugging_opts.asm_comments }
@@ -787,6 +814,8 @@ pub fn build_session_(sopts: config::Options,
         .map(|i| i.1).unwrap_or(0));
     let print_fuel_crate = sopts.debugging_opts.print_fuel.clone();
     let print_fuel = Cell::new(0);
+    let dump_mir_filter = sopts.debugging_opts.dump_mir.as_ref()
+        .map(|filters| DumpMirFilter::parse(filters));
 
     let working_dir = match env::current_dir() {
         Ok(dir) => dir.to_string_lossy().into_owned(),
@@ -835,6 +864,10 @@ pub fn build_session_(sopts: config::Options,
             decode_def_path_tables_time: Cell::new(Duration::from_secs(0)),
         },
         code_stats: RefCell::new(CodeStats::new()),
+        mir_stats: RefCell::new(MirStats::new()),
+        mir_pass_times: RefCell::new(MirPassTimes::new()),
+        mir_opt_journal: RefCell::new(None),
+        mir_dump_dir: RefCell::new(None),
         optimization_fuel_crate,
         optimization_fuel_limit,
         print_fuel_crate,
@@ -858,6 +891,7 @@ pub fn build_session_(sopts: config::Options,
             (*GLOBAL_JOBSERVER).clone()
         },
         has_global_allocator: Cell::new(false),
+        dump_mir_filter,
     };
 
     sess
@@ -216,6 +216,13 @@ fn resolve_associated_item<'a, 'tcx>(
     // Now that we know which impl is being used, we can dispatch to
     // the actual function:
     match vtbl {
+        traits::VtableImpl(ref impl_data)
+                if is_derived_clone_of_copy(tcx, param_env, &trait_ref, impl_data) => {
+            Some(Instance {
+                def: ty::InstanceDef::CloneShim(def_id, trait_ref.self_ty()),
+                substs: rcvr_substs
+            })
+        }
         traits::VtableImpl(impl_data) => {
             let (def_id, substs) = traits::find_associated_item(
                 tcx, trait_item, rcvr_substs, &impl_data);
@@ -260,6 +267,20 @@ fn resolve_associated_item<'a, 'tcx>(
     }
 }
 
+/// Whether `impl_data` is a `#[derive(Clone)]` impl whose self type is
+/// `Copy` in `param_env`, so that the clone shim can copy the value as a
+/// whole instead of cloning each field.
+fn is_derived_clone_of_copy<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                      param_env: ty::ParamEnv<'tcx>,
+                                      trait_ref: &ty::TraitRef<'tcx>,
+                                      impl_data: &traits::VtableImplData<'tcx, ()>)
+                                      -> bool {
+    Some(trait_ref.def_id) == tcx.lang_items().clone_trait() &&
+        tcx.has_attr(impl_data.impl_def_id, "automatically_derived") &&
+        !trait_ref.self_ty().moves_by_default(tcx, param_env,
+                                              tcx.def_span(impl_data.impl_def_id))
+}
+
 fn needs_fn_once_adapter_shim<'a, 'tcx>(actual_closure_kind: ty::ClosureKind,
                               trait_closure_kind: ty::ClosureKind)
     -> Result<bool, ()>
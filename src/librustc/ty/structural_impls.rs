@@ -625,6 +625,7 @@ impl<'a, 'tcx> Lift<'tcx> for const_val::ErrKind<'a> {
             MiscCatchAll => MiscCatchAll,
             IndexOpFeatureGated => IndexOpFeatureGated,
             Math(ref e) => Math(e.clone()),
+            StepLimitExceeded => StepLimitExceeded,
 
             LayoutError(ref e) => {
                 return tcx.lift(e).map(LayoutError)
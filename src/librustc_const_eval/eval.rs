@@ -379,6 +379,16 @@ fn eval_const_expr_partial<'a, 'tcx>(cx: &ConstContext<'a, 'tcx>,
           Err(err) => signal!(e, err),
       },
       hir::ExprBlock(ref block) => {
+        // Statements in const fns, allowed by `#![feature(const_loop)]`,
+        // aren't run here, only by the MIR evaluator in trans. Without
+        // the feature, evaluating the tail reports what it uses instead.
+        let has_statements = block.stmts.iter().any(|stmt| match stmt.node {
+            hir::StmtDecl(ref decl, _) => decl.node.is_local(),
+            hir::StmtExpr(..) | hir::StmtSemi(..) => true,
+        });
+        if has_statements && cx.fn_args.is_some() && tcx.sess.features.borrow().const_loop {
+            signal!(e, UnimplementedConstVal("statements in const fns"));
+        }
         match block.expr {
             Some(ref expr) => cx.eval(expr)?,
             None => mk_const(Aggregate(Tuple(&[]))),
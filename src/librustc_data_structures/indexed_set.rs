@@ -277,3 +277,227 @@ impl<'a, T: Idx> Iterator for Iter<'a, T> {
         }
     }
 }
+
+/// A set of `T`s in a universe of `universe_size` elements, stored as a
+/// sorted vector of its elements while that takes less memory than a bit
+/// per element of the universe, and as an `IdxSetBuf` otherwise.
+///
+/// It is meant for keeping many sets that are mostly small, like the
+/// per-block sets of a dataflow analysis. Set operations are done on an
+/// `IdxSet` the set is loaded into with `to_dense`, and the result is
+/// stored back with `store`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct HybridIdxSetBuf<T: Idx> {
+    universe_size: usize,
+    repr: HybridRepr<T>,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+enum HybridRepr<T: Idx> {
+    Sparse(Vec<T>),
+    Dense(IdxSetBuf<T>),
+}
+
+impl<T: Idx> fmt::Debug for HybridIdxSetBuf<T> {
+    fn fmt(&self, w: &mut fmt::Formatter) -> fmt::Result {
+        match self.repr {
+            HybridRepr::Sparse(ref elems) => w.debug_list().entries(elems).finish(),
+            HybridRepr::Dense(ref set) => fmt::Debug::fmt(set, w),
+        }
+    }
+}
+
+impl<T: Idx> HybridIdxSetBuf<T> {
+    /// Creates set holding no elements.
+    pub fn new_empty(universe_size: usize) -> Self {
+        HybridIdxSetBuf { universe_size, repr: HybridRepr::Sparse(vec![]) }
+    }
+
+    /// Creates set holding every element whose index falls in range 0..universe_size.
+    pub fn new_filled(universe_size: usize) -> Self {
+        HybridIdxSetBuf {
+            universe_size,
+            repr: HybridRepr::Dense(IdxSetBuf::new_filled(universe_size)),
+        }
+    }
+
+    /// Returns true iff set `self` contains `elem`.
+    pub fn contains(&self, elem: &T) -> bool {
+        match self.repr {
+            HybridRepr::Sparse(ref elems) => elems.binary_search(elem).is_ok(),
+            HybridRepr::Dense(ref set) => set.contains(elem),
+        }
+    }
+
+    /// Returns true iff the set is stored as a sorted vector.
+    pub fn is_sparse(&self) -> bool {
+        match self.repr {
+            HybridRepr::Sparse(_) => true,
+            HybridRepr::Dense(_) => false,
+        }
+    }
+
+    /// Returns the set as an `IdxSetBuf`.
+    pub fn to_dense(&self) -> IdxSetBuf<T> {
+        match self.repr {
+            HybridRepr::Sparse(ref elems) => {
+                let mut set = IdxSetBuf::new_empty(self.universe_size);
+                for elem in elems {
+                    set.add(elem);
+                }
+                set
+            }
+            HybridRepr::Dense(ref set) => set.clone(),
+        }
+    }
+
+    /// Overwrites `dense` with the elements of the set.
+    pub fn load_into(&self, dense: &mut IdxSet<T>) {
+        match self.repr {
+            HybridRepr::Sparse(ref elems) => {
+                dense.clear();
+                for elem in elems {
+                    dense.add(elem);
+                }
+            }
+            HybridRepr::Dense(ref set) => dense.clone_from(set),
+        }
+    }
+
+    /// Adds the elements of the set to `dense`; returns true iff this
+    /// changed `dense`.
+    pub fn union_into(&self, dense: &mut IdxSet<T>) -> bool {
+        match self.repr {
+            HybridRepr::Sparse(ref elems) => {
+                elems.iter().fold(false, |changed, elem| dense.add(elem) | changed)
+            }
+            HybridRepr::Dense(ref set) => dense.union(set),
+        }
+    }
+
+    /// Removes the elements of the set from `dense`; returns true iff
+    /// this changed `dense`.
+    pub fn subtract_from(&self, dense: &mut IdxSet<T>) -> bool {
+        match self.repr {
+            HybridRepr::Sparse(ref elems) => {
+                elems.iter().fold(false, |changed, elem| dense.remove(elem) | changed)
+            }
+            HybridRepr::Dense(ref set) => dense.subtract(set),
+        }
+    }
+
+    /// Makes the set hold exactly the elements of `dense`, picking the
+    /// smaller representation for them. The vector or the bits the set is
+    /// already stored in are reused when the representation is the same.
+    pub fn store(&mut self, dense: &IdxSet<T>) {
+        let dense_size = dense.words().len() * mem::size_of::<Word>();
+        let max_sparse_len = dense_size / mem::size_of::<T>();
+        let mut elems = match self.repr {
+            HybridRepr::Sparse(ref mut elems) => mem::replace(elems, vec![]),
+            HybridRepr::Dense(_) => vec![],
+        };
+        elems.clear();
+        for elem in dense.iter() {
+            if elems.len() == max_sparse_len {
+                if let HybridRepr::Dense(ref mut set) = self.repr {
+                    IdxSet::clone_from(set, dense);
+                    return;
+                }
+                self.repr = HybridRepr::Dense(dense.to_owned());
+                return;
+            }
+            elems.push(elem);
+        }
+        self.repr = HybridRepr::Sparse(elems);
+    }
+
+    /// Lets `f` update the set, returning what `f` returns, which must be
+    /// true iff it changed the set. A dense set is updated in place, and
+    /// stays dense; a sparse one is loaded into `scratch`, and only stored
+    /// back when it changed.
+    pub fn update<F>(&mut self, scratch: &mut IdxSet<T>, f: F) -> bool
+        where F: FnOnce(&mut IdxSet<T>) -> bool
+    {
+        if let HybridRepr::Dense(ref mut set) = self.repr {
+            return f(&mut **set);
+        }
+        self.load_into(scratch);
+        let changed = f(scratch);
+        if changed {
+            self.store(scratch);
+        }
+        changed
+    }
+
+    /// Stores a dense set as a sorted vector if that takes less memory,
+    /// like `store` would.
+    pub fn compact(&mut self) {
+        if self.is_sparse() {
+            return;
+        }
+        if let HybridRepr::Dense(set) = mem::replace(&mut self.repr, HybridRepr::Sparse(vec![])) {
+            self.store(&set);
+        }
+    }
+}
+
+#[test]
+fn hybrid_idx_set_round_trips() {
+    let mut dense: IdxSetBuf<usize> = IdxSetBuf::new_empty(200);
+    let mut hybrid = HybridIdxSetBuf::new_empty(200);
+    assert!(hybrid.is_sparse());
+
+    dense.add(&3);
+    dense.add(&130);
+    hybrid.store(&dense);
+    assert!(hybrid.is_sparse());
+    assert!(hybrid.contains(&3) && hybrid.contains(&130) && !hybrid.contains(&4));
+    assert_eq!(hybrid.to_dense(), dense);
+
+    for i in 0..100 {
+        dense.add(&i);
+    }
+    hybrid.store(&dense);
+    assert!(!hybrid.is_sparse());
+    assert!(hybrid.contains(&99) && hybrid.contains(&130) && !hybrid.contains(&100));
+    assert_eq!(hybrid.to_dense(), dense);
+}
+
+#[test]
+fn hybrid_idx_set_operations_match_dense() {
+    let mut small: IdxSetBuf<usize> = IdxSetBuf::new_empty(128);
+    small.add(&1);
+    small.add(&70);
+    let mut hybrid = HybridIdxSetBuf::new_empty(128);
+    hybrid.store(&small);
+
+    let mut dense: IdxSetBuf<usize> = IdxSetBuf::new_empty(128);
+    dense.add(&2);
+    assert!(hybrid.union_into(&mut dense));
+    assert!(!hybrid.union_into(&mut dense));
+    assert_eq!(dense.iter().collect::<Vec<_>>(), [1, 2, 70]);
+    assert!(hybrid.subtract_from(&mut dense));
+    assert_eq!(dense.iter().collect::<Vec<_>>(), [2]);
+
+    hybrid.load_into(&mut dense);
+    assert_eq!(dense, small);
+}
+
+#[test]
+fn hybrid_idx_set_update_keeps_dense_sets_dense() {
+    let mut scratch: IdxSetBuf<usize> = IdxSetBuf::new_empty(128);
+    let mut hybrid = HybridIdxSetBuf::new_empty(128);
+    assert!(hybrid.update(&mut scratch, |set| set.add(&5)));
+    assert!(!hybrid.update(&mut scratch, |set| set.add(&5)));
+    assert!(hybrid.is_sparse() && hybrid.contains(&5));
+
+    let mut filled = HybridIdxSetBuf::new_filled(128);
+    assert!(filled.update(&mut scratch, |set| {
+        (0..127).fold(false, |changed, i| set.remove(&i) | changed)
+    }));
+    assert!(!filled.is_sparse());
+    assert!(filled.contains(&127) && !filled.contains(&5));
+    filled.compact();
+    assert!(filled.is_sparse());
+    assert_eq!(filled.to_dense().iter().collect::<Vec<_>>(), [127]);
+}
@@ -268,6 +268,14 @@ pub fn compile_input(sess: &Session,
         sess.code_stats.borrow().print_type_sizes();
     }
 
+    if sess.opts.debugging_opts.mir_stats {
+        sess.mir_stats.borrow().print();
+    }
+
+    if sess.time_mir_passes() {
+        sess.mir_pass_times.borrow().print();
+    }
+
     let (phase5_result, trans) =
         phase_5_run_llvm_passes::<DefaultTransCrate>(sess, &dep_graph, trans);
 
@@ -980,6 +988,10 @@ pub fn phase_3_run_analysis_passes<'tcx, F, R>(control: &CompileController,
 
     let time_passes = sess.time_passes();
 
+    if sess.opts.debugging_opts.mir_print_passes {
+        mir::transform::registry::print_passes(sess);
+    }
+
     let query_result_on_disk_cache = time(time_passes,
         "load query result cache",
         || rustc_incremental::load_query_result_cache(sess));
@@ -1076,6 +1088,13 @@ pub fn phase_3_run_analysis_passes<'tcx, F, R>(control: &CompileController,
              || for def_id in tcx.body_owners() {
                  mir::transform::check_unsafety::check_unsafety(tcx, def_id)
              });
+
+        if let Some(ref path) = sess.opts.debugging_opts.unsafety_report {
+            time(time_passes,
+                 "unsafety report",
+                 || mir::transform::check_unsafety::write_unsafety_report(tcx, path));
+        }
+
         // Avoid overwhelming user with errors if type checking failed.
         // I'm not sure how helpful this is, to be honest, but it avoids
         // a
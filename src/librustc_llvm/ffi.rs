@@ -17,7 +17,7 @@
 use debuginfo::{DIBuilderRef, DIDescriptor, DIFile, DILexicalBlock, DISubprogram, DIType,
                 DIBasicType, DIDerivedType, DICompositeType, DIScope, DIVariable,
                 DIGlobalVariable, DIArray, DISubrange, DITemplateTypeParameter, DIEnumerator,
-                DINameSpace, DIFlags};
+                DINameSpace, DIFlags, DILocation};
 
 use libc::{c_uint, c_int, size_t, c_char};
 use libc::{c_longlong, c_ulonglong, c_void};
@@ -1556,6 +1556,12 @@ extern "C" {
                                                 Scope: DIScope,
                                                 InlinedAt: MetadataRef)
                                                 -> ValueRef;
+    pub fn LLVMRustDIBuilderCreateDILocation(Context: ContextRef,
+                                             Line: c_uint,
+                                             Column: c_uint,
+                                             Scope: DIScope,
+                                             InlinedAt: DILocation)
+                                             -> DILocation;
     pub fn LLVMRustDIBuilderCreateOpDeref() -> i64;
     pub fn LLVMRustDIBuilderCreateOpPlus() -> i64;
 
@@ -13,29 +13,34 @@
 use rustc::hir;
 use rustc::hir::def_id::{DefId};
 use rustc::infer::{InferCtxt};
-use rustc::ty::{self, TyCtxt, ParamEnv};
+use rustc::traits;
+use rustc::ty::{self, Ty, TyCtxt, ParamEnv};
 use rustc::ty::maps::Providers;
+use rustc_errors::DiagnosticBuilder;
 use rustc::mir::{AssertMessage, BasicBlock, BorrowKind, Location, Lvalue, Local};
 use rustc::mir::{Mir, Mutability, Operand, Projection, ProjectionElem, Rvalue};
-use rustc::mir::{Field, Statement, StatementKind, Terminator, TerminatorKind};
+use rustc::mir::{Statement, StatementKind, Terminator, TerminatorKind};
 use transform::nll;
+use transform::nll::region_infer::RegionInferenceContext;
+use transform::qualify_consts;
 
 use rustc_data_structures::fx::FxHashSet;
 use rustc_data_structures::indexed_set::{self, IdxSetBuf};
 use rustc_data_structures::indexed_vec::{Idx};
 
 use syntax::ast::{self};
-use syntax_pos::Span;
+use syntax_pos::{BytePos, Span};
 
 use dataflow::{do_dataflow};
 use dataflow::{MoveDataParamEnv};
 use dataflow::{BitDenotation, BlockSets, DataflowResults, DataflowResultsConsumer};
 use dataflow::{MaybeInitializedLvals, MaybeUninitializedLvals};
 use dataflow::{MovingOutStatements, EverInitializedLvals};
-use dataflow::{Borrows, BorrowData, BorrowIndex};
+use dataflow::{Borrows, BorrowData, BorrowEnd, BorrowIndex, ReserveOrActivateIndex};
 use dataflow::move_paths::{MoveError, IllegalMoveOriginKind};
 use dataflow::move_paths::{HasMoveData, MoveData, MovePathIndex, LookupResult, MoveOutIndex};
 use util::borrowck_errors::{BorrowckErrors, Origin};
+use util;
 
 use self::MutateMode::{JustWrite, WriteAndRead};
 
@@ -79,82 +84,91 @@ fn do_mir_borrowck<'a, 'gcx, 'tcx>(infcx: &InferCtxt<'a, 'gcx, 'tcx>,
     let move_data: MoveData<'tcx> = match MoveData::gather_moves(input_mir, tcx) {
         Ok(move_data) => move_data,
         Err((move_data, move_errors)) => {
-            for move_error in move_errors {
-                let (span, kind): (Span, IllegalMoveOriginKind) = match move_error {
-                    MoveError::UnionMove { .. } =>
-                        unimplemented!("dont know how to report union move errors yet."),
-                    MoveError::IllegalMove { cannot_move_out_of: o } => (o.span, o.kind),
-                };
-                let origin = Origin::Mir;
-                let mut err = match kind {
-                    IllegalMoveOriginKind::Static =>
-                        tcx.cannot_move_out_of(span, "static item", origin),
-                    IllegalMoveOriginKind::BorrowedContent =>
-                        tcx.cannot_move_out_of(span, "borrowed content", origin),
-                    IllegalMoveOriginKind::InteriorOfTypeWithDestructor { container_ty: ty } =>
-                        tcx.cannot_move_out_of_interior_of_drop(span, ty, origin),
-                    IllegalMoveOriginKind::InteriorOfSliceOrArray { ty, is_index } =>
-                        tcx.cannot_move_out_of_interior_noncopy(span, ty, is_index, origin),
-                };
-                err.emit();
-            }
+            report_move_errors(tcx, input_mir, move_errors);
             move_data
         }
     };
 
-    // Make our own copy of the MIR. This copy will be modified (in place) to
-    // contain non-lexical lifetimes. It will have a lifetime tied
-    // to the inference context.
-    let mut mir: Mir<'tcx> = input_mir.clone();
-    let mir = &mut mir;
-
-    // If we are in non-lexical mode, compute the non-lexical lifetimes.
+    // If we are in non-lexical mode, make our own copy of the MIR and
+    // compute the non-lexical lifetimes. This copy will be modified (in
+    // place) to contain non-lexical lifetimes. It will have a lifetime tied
+    // to the inference context. Otherwise (including when comparing against
+    // the AST borrowck), the input MIR is checked as is rather than cloned.
+    let mut nll_mir: Mir<'tcx>;
+    let mir: &Mir<'tcx>;
     let opt_regioncx = if !tcx.sess.opts.debugging_opts.nll {
+        mir = input_mir;
         None
     } else {
-        Some(nll::compute_regions(infcx, def_id, param_env, mir))
+        nll_mir = input_mir.clone();
+        let regioncx = nll::compute_regions(infcx, def_id, param_env, &mut nll_mir);
+        mir = &nll_mir;
+        Some(regioncx)
     };
 
+    // The move data, the region inference context and the flow results are
+    // built once here. Both the walk over the body looking for conflicts and
+    // the reporting of the errors it finds read them through `mbcx` and
+    // `state`, whether or not the AST borrowck runs too.
     let mdpe = MoveDataParamEnv { move_data: move_data, param_env: param_env };
-    let dead_unwinds = IdxSetBuf::new_empty(mir.basic_blocks().len());
-    let flow_borrows = do_dataflow(tcx, mir, id, &attributes, &dead_unwinds,
-                                   Borrows::new(tcx, mir, opt_regioncx.as_ref()),
-                                   |bd, i| bd.location(i));
-    let flow_inits = do_dataflow(tcx, mir, id, &attributes, &dead_unwinds,
-                                 MaybeInitializedLvals::new(tcx, mir, &mdpe),
-                                 |bd, i| &bd.move_data().move_paths[i]);
-    let flow_uninits = do_dataflow(tcx, mir, id, &attributes, &dead_unwinds,
-                                   MaybeUninitializedLvals::new(tcx, mir, &mdpe),
-                                   |bd, i| &bd.move_data().move_paths[i]);
-    let flow_move_outs = do_dataflow(tcx, mir, id, &attributes, &dead_unwinds,
-                                     MovingOutStatements::new(tcx, mir, &mdpe),
-                                     |bd, i| &bd.move_data().moves[i]);
-    let flow_ever_inits = do_dataflow(tcx, mir, id, &attributes, &dead_unwinds,
-                                     EverInitializedLvals::new(tcx, mir, &mdpe),
-                                     |bd, i| &bd.move_data().inits[i]);
+    let mut state = InProgress::compute(tcx, mir, id, &attributes, &mdpe, opt_regioncx.as_ref());
 
     let mut mbcx = MirBorrowckCtxt {
         tcx: tcx,
         mir: mir,
+        input_mir: input_mir,
+        def_id: def_id,
         node_id: id,
         move_data: &mdpe.move_data,
         param_env: param_env,
         storage_dead_or_drop_error_reported: FxHashSet(),
     };
 
-    let mut state = InProgress::new(flow_borrows,
-                                    flow_inits,
-                                    flow_uninits,
-                                    flow_move_outs,
-                                    flow_ever_inits);
-
     mbcx.analyze_results(&mut state); // entry point for DataflowResultsConsumer
 }
 
+/// Reports the moves out of lvalues which can't be moved out of, found while
+/// gathering the moves of `mir`.
+fn report_move_errors<'a, 'gcx, 'tcx>(tcx: TyCtxt<'a, 'gcx, 'tcx>,
+                                      mir: &Mir<'gcx>,
+                                      move_errors: Vec<MoveError<'tcx>>) {
+    for move_error in move_errors {
+        let (location, span, kind): (Location, Span, IllegalMoveOriginKind) =
+            match move_error {
+                MoveError::UnionMove { .. } =>
+                    unimplemented!("dont know how to report union move errors yet."),
+                MoveError::IllegalMove { cannot_move_out_of: o } =>
+                    (o.location, o.span, o.kind),
+            };
+        let origin = Origin::Mir;
+        let mut err = match kind {
+            IllegalMoveOriginKind::Static =>
+                tcx.cannot_move_out_of(span, "static item", origin),
+            IllegalMoveOriginKind::BorrowedContent =>
+                tcx.cannot_move_out_of(span, "borrowed content", origin),
+            IllegalMoveOriginKind::InteriorOfTypeWithDestructor { container_ty: ty } =>
+                tcx.cannot_move_out_of_interior_of_drop(span, ty, origin),
+            IllegalMoveOriginKind::InteriorOfSliceOrArray { ty, is_index } =>
+                tcx.cannot_move_out_of_interior_noncopy(span, ty, is_index, origin),
+        };
+        match kind {
+            IllegalMoveOriginKind::BorrowedContent |
+            IllegalMoveOriginKind::InteriorOfSliceOrArray { .. } =>
+                suggest_borrow_instead_of_move(tcx, mir, &mut err, location, span),
+            _ => {}
+        }
+        err.emit();
+    }
+}
+
 #[allow(dead_code)]
 pub struct MirBorrowckCtxt<'cx, 'gcx: 'tcx, 'tcx: 'cx> {
     tcx: TyCtxt<'cx, 'gcx, 'tcx>,
     mir: &'cx Mir<'tcx>,
+    /// The MIR as it was before NLL, used to find out why borrowed
+    /// temporaries weren't promoted.
+    input_mir: &'cx Mir<'gcx>,
+    def_id: DefId,
     node_id: ast::NodeId,
     move_data: &'cx MoveData<'tcx>,
     param_env: ParamEnv<'gcx>,
@@ -242,6 +256,9 @@ impl<'cx, 'gcx, 'tcx> DataflowResultsConsumer<'cx, 'tcx> for MirBorrowckCtxt<'cx
         let summary = flow_state.summary();
         debug!("MirBorrowckCtxt::process_statement({:?}, {:?}): {}", location, stmt, summary);
         let span = stmt.source_info.span;
+
+        self.check_activations(location, span, flow_state);
+
         match stmt.kind {
             StatementKind::Assign(ref lhs, ref rhs) => {
                 // NOTE: NLL RFC calls for *shallow* write; using Deep
@@ -290,6 +307,9 @@ impl<'cx, 'gcx, 'tcx> DataflowResultsConsumer<'cx, 'tcx> for MirBorrowckCtxt<'cx
                 // ignored when consuming results (update to
                 // flow_state already handled).
             }
+            StatementKind::Assume(..) => {
+                span_bug!(span, "Assume should not exist during borrowck");
+            }
             StatementKind::Nop |
             StatementKind::Validate(..) |
             StatementKind::StorageLive(..) => {
@@ -313,6 +333,9 @@ impl<'cx, 'gcx, 'tcx> DataflowResultsConsumer<'cx, 'tcx> for MirBorrowckCtxt<'cx
         let summary = flow_state.summary();
         debug!("MirBorrowckCtxt::process_terminator({:?}, {:?}): {}", location, term, summary);
         let span = term.source_info.span;
+
+        self.check_activations(location, span, flow_state);
+
         match term.kind {
             TerminatorKind::SwitchInt { ref discr, switch_ty: _, values: _, targets: _ } => {
                 self.consume_operand(ContextKind::SwitchInt.new(loc),
@@ -361,7 +384,7 @@ impl<'cx, 'gcx, 'tcx> DataflowResultsConsumer<'cx, 'tcx> for MirBorrowckCtxt<'cx
                         self.consume_operand(ContextKind::Assert.new(loc),
                                              (index, span), flow_state);
                     }
-                    AssertMessage::Math(_/*const_math_err*/) => {}
+                    AssertMessage::Math(..) => {}
                     AssertMessage::GeneratorResumedAfterReturn => {}
                     AssertMessage::GeneratorResumedAfterPanic => {}
                 }
@@ -422,7 +445,7 @@ enum MutateMode { JustWrite, WriteAndRead }
 enum Control { Continue, Break }
 
 use self::ShallowOrDeep::{Shallow, Deep};
-use self::ReadOrWrite::{Read, Write};
+use self::ReadOrWrite::{Read, Write, Activation};
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 enum ArtificialField {
@@ -455,6 +478,12 @@ enum ReadOrWrite {
     /// new values or otherwise invalidated (for example, it could be
     /// de-initialized, as in a move operation).
     Write(WriteKind),
+
+    /// The activation of a two-phase borrow, where it starts to be used
+    /// as a mutable borrow. Until then it is only reserved, and treated
+    /// like a shared borrow. The activation conflicts with other borrows
+    /// like a write does, but not with the borrow being activated.
+    Activation(WriteKind, BorrowIndex),
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
@@ -530,41 +559,49 @@ impl<'cx, 'gcx, 'tcx> MirBorrowckCtxt<'cx, 'gcx, 'tcx> {
 
         let mut error_reported = false;
         self.each_borrow_involving_path(
-            context, (sd, lvalue_span.0), flow_state, |this, _index, borrow, common_prefix| {
+            context, (sd, lvalue_span.0), flow_state, |this, index, borrow, common_prefix| {
+                let borrow_ends = || {
+                    flow_state.borrows.base_results.operator().borrow_ends(index)
+                };
                 match (rw, borrow.kind) {
+                    (Activation(_, activating), _) if activating == index => {
+                        // an activation does not conflict with its own reservation
+                        Control::Continue
+                    }
                     (Read(_), BorrowKind::Shared) => {
                         Control::Continue
                     }
+                    (Read(_), BorrowKind::Mut { .. })
+                        if !flow_state.borrows.is_borrow_active(index) =>
+                    {
+                        // a reserved two-phase borrow acts like a shared borrow
+                        Control::Continue
+                    }
                     (Read(kind), BorrowKind::Unique) |
-                    (Read(kind), BorrowKind::Mut) => {
+                    (Read(kind), BorrowKind::Mut { .. }) => {
                         match kind {
                             ReadKind::Copy => {
                                 error_reported = true;
                                 this.report_use_while_mutably_borrowed(
-                                    context, lvalue_span, borrow)
+                                    context, lvalue_span, borrow, &borrow_ends())
                             },
                             ReadKind::Borrow(bk) => {
-                                let end_issued_loan_span =
-                                    flow_state.borrows.base_results.operator().opt_region_end_span(
-                                        &borrow.region);
                                 error_reported = true;
                                 this.report_conflicting_borrow(
                                     context, common_prefix, lvalue_span, bk,
-                                    &borrow, end_issued_loan_span)
+                                    &borrow, &borrow_ends())
                             }
                         }
                         Control::Break
                     }
+                    (Activation(kind, _), _) |
                     (Write(kind), _) => {
                         match kind {
                             WriteKind::MutableBorrow(bk) => {
-                                let end_issued_loan_span =
-                                    flow_state.borrows.base_results.operator().opt_region_end_span(
-                                        &borrow.region);
                                 error_reported = true;
                                 this.report_conflicting_borrow(
                                     context, common_prefix, lvalue_span, bk,
-                                    &borrow, end_issued_loan_span)
+                                    &borrow, &borrow_ends())
                             }
                              WriteKind::StorageDeadOrDrop => {
                                 let end_span =
@@ -572,17 +609,17 @@ impl<'cx, 'gcx, 'tcx> MirBorrowckCtxt<'cx, 'gcx, 'tcx> {
                                         &borrow.region);
                                 error_reported = true;
                                 this.report_borrowed_value_does_not_live_long_enough(
-                                    context, lvalue_span, end_span)
+                                    context, lvalue_span, borrow, end_span)
                             },
                             WriteKind::Mutate => {
                                 error_reported = true;
                                 this.report_illegal_mutation_of_borrowed(
-                                    context, lvalue_span, borrow)
+                                    context, lvalue_span, borrow, &borrow_ends())
                             },
                             WriteKind::Move => {
                                 error_reported = true;
                                 this.report_move_out_while_borrowed(
-                                    context, lvalue_span, &borrow)
+                                    context, lvalue_span, &borrow, &borrow_ends())
                             },
                         }
                         Control::Break
@@ -620,6 +657,29 @@ impl<'cx, 'gcx, 'tcx> MirBorrowckCtxt<'cx, 'gcx, 'tcx> {
         self.check_if_reassignment_to_immutable_state(context, lvalue_span, flow_state);
     }
 
+    /// Checks the two-phase borrows that become active at `location`: from
+    /// now on they are mutable borrows, so no other borrow of the borrowed
+    /// data may still be in effect.
+    fn check_activations(&mut self,
+                         location: Location,
+                         span: Span,
+                         flow_state: &InProgress<'cx, 'gcx, 'tcx>) {
+        let domain = flow_state.borrows.base_results.operator();
+        for &borrow_index in domain.activations_at_location(location) {
+            if !flow_state.borrows.is_borrow_reserved(borrow_index) {
+                // the borrow has already ended on every path to here
+                continue;
+            }
+            let borrow = &domain.borrows()[borrow_index];
+            // permissions and initialization were checked at the reservation
+            self.access_lvalue(ContextKind::Activation.new(location),
+                               (&borrow.lvalue, span),
+                               (Deep, Activation(WriteKind::MutableBorrow(borrow.kind),
+                                                 borrow_index)),
+                               flow_state);
+        }
+    }
+
     fn consume_rvalue(&mut self,
                       context: Context,
                       (rvalue, span): (&Rvalue<'tcx>, Span),
@@ -630,7 +690,7 @@ impl<'cx, 'gcx, 'tcx> MirBorrowckCtxt<'cx, 'gcx, 'tcx> {
                 let access_kind = match bk {
                     BorrowKind::Shared => (Deep, Read(ReadKind::Borrow(bk))),
                     BorrowKind::Unique |
-                    BorrowKind::Mut => (Deep, Write(WriteKind::MutableBorrow(bk))),
+                    BorrowKind::Mut { .. } => (Deep, Write(WriteKind::MutableBorrow(bk))),
                 };
                 self.access_lvalue(context, (lvalue, span), access_kind, flow_state);
                 self.check_if_path_is_moved(context, InitializationRequiringAction::Borrow,
@@ -957,7 +1017,7 @@ impl<'cx, 'gcx, 'tcx> MirBorrowckCtxt<'cx, 'gcx, 'tcx> {
                         self.describe_lvalue(lvalue));
                 }
             },
-            Write(WriteKind::MutableBorrow(BorrowKind::Mut)) => {
+            Write(WriteKind::MutableBorrow(BorrowKind::Mut { .. })) => {
                 if let Err(lvalue_err) = self.is_mutable(lvalue) {
                     let mut err = self.tcx.cannot_borrow_path_as_mutable(span,
                         &format!("immutable item `{}`",
@@ -1112,8 +1172,11 @@ impl<'cx, 'gcx, 'tcx> MirBorrowckCtxt<'cx, 'gcx, 'tcx> {
     {
         let (access, lvalue) = access_lvalue;
 
-        // FIXME: analogous code in check_loans first maps `lvalue` to
-        // its base_path.
+        // Unlike the analogous code in check_loans, `lvalue` is not first
+        // mapped to its base_path: a `Box` is uniquely owned, so borrows
+        // of disjoint fields behind the same box do not conflict, just as
+        // for a struct held directly. (Derefs of references and raw
+        // pointers are still followed by the prefix checks below.)
 
         let domain = flow_state.borrows.base_results.operator();
         let data = domain.borrows();
@@ -1121,6 +1184,12 @@ impl<'cx, 'gcx, 'tcx> MirBorrowckCtxt<'cx, 'gcx, 'tcx> {
         // check for loan restricting path P being used. Accounts for
         // borrows of P, P.a.b, etc.
         'next_borrow: for i in flow_state.borrows.elems_incoming() {
+            // every borrow in effect is reserved; whether it is also
+            // active is left to `op`
+            if !i.is_reservation() {
+                continue;
+            }
+            let i = i.borrow_index();
             let borrowed = &data[i];
 
             // Is `lvalue` (or a prefix of it) already borrowed? If
@@ -1371,40 +1440,101 @@ impl<'cx, 'gcx, 'tcx> MirBorrowckCtxt<'cx, 'gcx, 'tcx> {
                 } else {
                     err.span_label(move_span, format!("value moved{} here", move_msg));
                 };
+                self.suggest_alternative_to_move(&mut err,
+                                                 self.move_data.moves[*moi].source,
+                                                 move_span);
             }
             //FIXME: add note for closure
             err.emit();
         }
     }
 
+    /// Suggests an alternative to the move at `location`: a `ref` binding when
+    /// the value is moved into a pattern, a `.clone()` when its type is
+    /// `Clone`, and otherwise a borrow when it is passed to a function.
+    fn suggest_alternative_to_move(&self,
+                                   err: &mut DiagnosticBuilder,
+                                   location: Location,
+                                   span: Span) {
+        let (moved, site) = match classify_move(self.mir, location, span) {
+            Some(classified) => classified,
+            None => return,
+        };
+        let snippet = match self.tcx.sess.codemap().span_to_snippet(span) {
+            Ok(snippet) => snippet,
+            Err(_) => return,
+        };
+        let ty = moved.ty(self.mir, self.tcx).to_ty(self.tcx);
+        if site == MoveSite::PatternBinding {
+            err.span_approximate_suggestion(span,
+                                            "consider binding by reference",
+                                            format!("ref {}", snippet));
+        } else if type_implements_clone(self.tcx, self.param_env, ty, span) {
+            let receiver = if snippet.starts_with('*') {
+                format!("({})", snippet)
+            } else {
+                snippet
+            };
+            err.span_suggestion(span,
+                                "consider cloning the value",
+                                format!("{}.clone()", receiver));
+        } else if site == MoveSite::CallArgument {
+            err.span_approximate_suggestion(span,
+                                            "consider borrowing here",
+                                            format!("&{}", snippet));
+        }
+    }
+
     fn report_move_out_while_borrowed(&mut self,
                                       _context: Context,
                                       (lvalue, span): (&Lvalue<'tcx>, Span),
-                                      borrow: &BorrowData<'tcx>) {
-        self.tcx.cannot_move_when_borrowed(span,
-                                           &self.describe_lvalue(lvalue),
-                                           Origin::Mir)
-                .span_label(self.retrieve_borrow_span(borrow),
-                            format!("borrow of `{}` occurs here",
-                                    self.describe_lvalue(&borrow.lvalue)))
-                .span_label(span, format!("move out of `{}` occurs here",
-                                          self.describe_lvalue(lvalue)))
-                .emit();
+                                      borrow: &BorrowData<'tcx>,
+                                      borrow_ends: &[BorrowEnd]) {
+        let mut err = self.tcx.cannot_move_when_borrowed(span,
+                                                         &self.describe_lvalue(lvalue),
+                                                         Origin::Mir);
+        err.span_label(self.retrieve_borrow_span(borrow),
+                       format!("borrow of `{}` occurs here",
+                               self.describe_lvalue(&borrow.lvalue)));
+        err.span_label(span, format!("move out of `{}` occurs here",
+                                     self.describe_lvalue(lvalue)));
+        self.label_borrow_ends(&mut err, borrow_ends);
+        err.emit();
     }
 
     fn report_use_while_mutably_borrowed(&mut self,
                                          _context: Context,
                                          (lvalue, span): (&Lvalue<'tcx>, Span),
-                                         borrow : &BorrowData<'tcx>) {
+                                         borrow : &BorrowData<'tcx>,
+                                         borrow_ends: &[BorrowEnd]) {
 
         let mut err = self.tcx.cannot_use_when_mutably_borrowed(
             span, &self.describe_lvalue(lvalue),
             self.retrieve_borrow_span(borrow), &self.describe_lvalue(&borrow.lvalue),
             Origin::Mir);
+        self.label_borrow_ends(&mut err, borrow_ends);
 
         err.emit();
     }
 
+    /// Points at where the offending borrow goes out of scope, so that it
+    /// is clear how long it is in the way.
+    fn label_borrow_ends(&self, err: &mut DiagnosticBuilder, borrow_ends: &[BorrowEnd]) {
+        for end in borrow_ends {
+            match *end {
+                BorrowEnd::Here(span) => {
+                    err.span_label(span, "borrow ends here");
+                }
+                BorrowEnd::TemporaryDrop(span) => {
+                    err.span_label(span, "borrow ends here, when the temporary is dropped");
+                }
+                BorrowEnd::EndOfFunction(span) => {
+                    err.span_label(span, "borrow lasts until end of function");
+                }
+            }
+        }
+    }
+
     /// Finds the span of arguments of a closure (within `maybe_closure_span`) and its usage of
     /// the local assigned at `location`.
     /// This is done by searching in statements succeeding `location`
@@ -1475,7 +1605,7 @@ impl<'cx, 'gcx, 'tcx> MirBorrowckCtxt<'cx, 'gcx, 'tcx> {
                                  (lvalue, span): (&Lvalue<'tcx>, Span),
                                  gen_borrow_kind: BorrowKind,
                                  issued_borrow: &BorrowData,
-                                 end_issued_loan_span: Option<Span>) {
+                                 borrow_ends: &[BorrowEnd]) {
         use self::prefixes::IsPrefixOf;
 
         assert!(common_prefix.is_prefix_of(lvalue));
@@ -1490,16 +1620,26 @@ impl<'cx, 'gcx, 'tcx> MirBorrowckCtxt<'cx, 'gcx, 'tcx> {
 
         let desc_lvalue = self.describe_lvalue(lvalue);
 
+        // The error labels where the issued borrow's region ends itself,
+        // e.g. as "first borrow ends here"; any other ends are added below.
+        let end_issued_loan_span = borrow_ends.iter().filter_map(|end| match *end {
+            BorrowEnd::Here(span) => Some(span),
+            BorrowEnd::TemporaryDrop(_) | BorrowEnd::EndOfFunction(_) => None,
+        }).next();
+        let other_borrow_ends: Vec<_> = borrow_ends.iter().cloned()
+            .filter(|&end| Some(end) != end_issued_loan_span.map(BorrowEnd::Here))
+            .collect();
+
         // FIXME: supply non-"" `opt_via` when appropriate
         let mut err = match (gen_borrow_kind, "immutable", "mutable",
                              issued_borrow.kind, "immutable", "mutable") {
-            (BorrowKind::Shared, lft, _, BorrowKind::Mut, _, rgt) |
-            (BorrowKind::Mut, _, lft, BorrowKind::Shared, rgt, _) =>
+            (BorrowKind::Shared, lft, _, BorrowKind::Mut { .. }, _, rgt) |
+            (BorrowKind::Mut { .. }, _, lft, BorrowKind::Shared, rgt, _) =>
                 self.tcx.cannot_reborrow_already_borrowed(
                     span, &desc_lvalue, "", lft, issued_span,
                     "it", rgt, "", end_issued_loan_span, Origin::Mir),
 
-            (BorrowKind::Mut, _, _, BorrowKind::Mut, _, _) =>
+            (BorrowKind::Mut { .. }, _, _, BorrowKind::Mut { .. }, _, _) =>
                 self.tcx.cannot_mutably_borrow_multiply(
                     span, &desc_lvalue, "", issued_span,
                     "", end_issued_loan_span, Origin::Mir),
@@ -1537,13 +1677,22 @@ impl<'cx, 'gcx, 'tcx> MirBorrowckCtxt<'cx, 'gcx, 'tcx> {
             );
         }
 
+        self.label_borrow_ends(&mut err, &other_borrow_ends);
         err.emit();
     }
 
     fn report_borrowed_value_does_not_live_long_enough(&mut self,
-                                                       _: Context,
+                                                       context: Context,
                                                        (lvalue, span): (&Lvalue, Span),
+                                                       borrow: &BorrowData,
                                                        end_span: Option<Span>) {
+        if let Lvalue::Local(local) = *lvalue {
+            if let Some(name) = self.mir.local_decls[local].name {
+                self.report_variable_does_not_live_long_enough(context, name, borrow, end_span);
+                return;
+            }
+        }
+
         let proper_span = match *lvalue {
             Lvalue::Local(local) => self.mir.local_decls[local].source_info.span,
             _ => span
@@ -1558,15 +1707,66 @@ impl<'cx, 'gcx, 'tcx> MirBorrowckCtxt<'cx, 'gcx, 'tcx> {
             err.span_label(end, "temporary value needs to live until here");
         }
 
+        let blame = qualify_consts::promotion_blame(self.tcx.global_tcx(),
+                                                    self.def_id,
+                                                    self.input_mir,
+                                                    borrow.location);
+        if let Some((blame_span, reason)) = blame {
+            err.span_note(blame_span,
+                          &format!("the temporary value can't be promoted to a constant \
+                                    because this {}", reason));
+        }
+
+        err.emit();
+    }
+
+    /// Reports that the variable `name` is dropped (or its storage killed)
+    /// at `context` while `borrow` of it is still in scope. When the borrow
+    /// only ends at the end of the same scope, the variable is being dropped
+    /// before something declared earlier that holds the borrow, and the
+    /// error explains the drop order.
+    fn report_variable_does_not_live_long_enough(&mut self,
+                                                 context: Context,
+                                                 name: ast::Name,
+                                                 borrow: &BorrowData,
+                                                 end_span: Option<Span>) {
+        let borrow_span = self.retrieve_borrow_span(borrow);
+        let drop_span = scope_end_point(self.mir.source_info(context.loc).span);
+        let desc = format!("`{}`", name);
+
+        // Variables that are still live when the function returns are only
+        // killed by the backup check at the `Return`, which has no scope of
+        // its own to compare the end of the borrow with.
+        let at_return = context.kind == ContextKind::StorageDead &&
+            context.loc.statement_index == self.mir[context.loc.block].statements.len();
+        let primary_span = if at_return { borrow_span } else { drop_span };
+
+        let mut err = self.tcx.path_does_not_live_long_enough(primary_span, &desc, Origin::Mir);
+        err.span_label(borrow_span, "borrow occurs here");
+        err.span_label(drop_span, format!("{} dropped here while still borrowed", desc));
+        if !at_return {
+            match end_span {
+                Some(end) if end.hi() == drop_span.hi() => {
+                    err.note("values in a scope are dropped in the opposite order \
+                              they are created");
+                }
+                Some(end) => {
+                    err.span_label(end, "borrowed value needs to live until here");
+                }
+                None => {}
+            }
+        }
         err.emit();
     }
 
     fn report_illegal_mutation_of_borrowed(&mut self,
                                            _: Context,
                                            (lvalue, span): (&Lvalue<'tcx>, Span),
-                                           loan: &BorrowData) {
+                                           loan: &BorrowData,
+                                           borrow_ends: &[BorrowEnd]) {
         let mut err = self.tcx.cannot_assign_to_borrowed(
             span, self.retrieve_borrow_span(loan), &self.describe_lvalue(lvalue), Origin::Mir);
+        self.label_borrow_ends(&mut err, borrow_ends);
 
         err.emit();
     }
@@ -1598,184 +1798,7 @@ impl<'cx, 'gcx, 'tcx> MirBorrowckCtxt<'cx, 'gcx, 'tcx> {
 impl<'cx, 'gcx, 'tcx> MirBorrowckCtxt<'cx, 'gcx, 'tcx> {
     // End-user visible description of `lvalue`
     fn describe_lvalue(&self, lvalue: &Lvalue<'tcx>) -> String {
-        let mut buf = String::new();
-        self.append_lvalue_to_string(lvalue, &mut buf, false);
-        buf
-    }
-
-    /// If this is a field projection, and the field is being projected from a closure type,
-    /// then returns the index of the field being projected. Note that this closure will always
-    /// be `self` in the current MIR, because that is the only time we directly access the fields
-    /// of a closure type.
-    fn is_upvar_field_projection(&self, lvalue: &Lvalue<'tcx>) -> Option<Field> {
-        match *lvalue {
-            Lvalue::Projection(ref proj) => {
-                match proj.elem {
-                    ProjectionElem::Field(field, _ty) => {
-                        let is_projection_from_ty_closure = proj.base.ty(self.mir, self.tcx)
-                                .to_ty(self.tcx).is_closure();
-
-                        if is_projection_from_ty_closure {
-                            Some(field)
-                        } else {
-                            None
-                        }
-                    },
-                    _ => None
-                }
-            },
-            _ => None
-        }
-    }
-
-    // Appends end-user visible description of `lvalue` to `buf`.
-    fn append_lvalue_to_string(&self,
-                               lvalue: &Lvalue<'tcx>,
-                               buf: &mut String,
-                               mut autoderef: bool) {
-        match *lvalue {
-            Lvalue::Local(local) => {
-                self.append_local_to_string(local, buf, "_");
-            }
-            Lvalue::Static(ref static_) => {
-                buf.push_str(&format!("{}", &self.tcx.item_name(static_.def_id)));
-            }
-            Lvalue::Projection(ref proj) => {
-                match proj.elem {
-                    ProjectionElem::Deref => {
-                        if let Some(field) = self.is_upvar_field_projection(&proj.base) {
-                            let var_index = field.index();
-                            let name = self.mir.upvar_decls[var_index].debug_name.to_string();
-                            if self.mir.upvar_decls[var_index].by_ref {
-                                buf.push_str(&name);
-                            } else {
-                                buf.push_str(&format!("*{}", &name));
-                            }
-                        } else {
-                            if autoderef {
-                                self.append_lvalue_to_string(&proj.base, buf, autoderef);
-                            } else {
-                                buf.push_str(&"*");
-                                self.append_lvalue_to_string(&proj.base, buf, autoderef);
-                            }
-                        }
-                    },
-                    ProjectionElem::Downcast(..) => {
-                        self.append_lvalue_to_string(&proj.base, buf, autoderef);
-                    },
-                    ProjectionElem::Field(field, _ty) => {
-                        autoderef = true;
-
-                        if let Some(field) = self.is_upvar_field_projection(lvalue) {
-                            let var_index = field.index();
-                            let name = self.mir.upvar_decls[var_index].debug_name.to_string();
-                            buf.push_str(&name);
-                        } else {
-                            let field_name = self.describe_field(&proj.base, field);
-                            self.append_lvalue_to_string(&proj.base, buf, autoderef);
-                            buf.push_str(&format!(".{}", field_name));
-                        }
-                    },
-                    ProjectionElem::Index(index) => {
-                        autoderef = true;
-
-                        self.append_lvalue_to_string(&proj.base, buf, autoderef);
-                        buf.push_str("[");
-                        self.append_local_to_string(index, buf, "..");
-                        buf.push_str("]");
-                    },
-                    ProjectionElem::ConstantIndex { .. } | ProjectionElem::Subslice { .. } => {
-                        autoderef = true;
-                        // Since it isn't possible to borrow an element on a particular index and
-                        // then use another while the borrow is held, don't output indices details
-                        // to avoid confusing the end-user
-                        self.append_lvalue_to_string(&proj.base, buf, autoderef);
-                        buf.push_str(&"[..]");
-                    },
-                };
-            }
-        }
-    }
-
-    // Appends end-user visible description of the `local` lvalue to `buf`. If `local` doesn't have
-    // a name, then `none_string` is appended instead
-    fn append_local_to_string(&self, local_index: Local, buf: &mut String, none_string: &str) {
-        let local = &self.mir.local_decls[local_index];
-        match local.name {
-            Some(name) => buf.push_str(&format!("{}", name)),
-            None => buf.push_str(none_string)
-        }
-    }
-
-    // End-user visible description of the `field`nth field of `base`
-    fn describe_field(&self, base: &Lvalue, field: Field) -> String {
-        match *base {
-            Lvalue::Local(local) => {
-                let local = &self.mir.local_decls[local];
-                self.describe_field_from_ty(&local.ty, field)
-            },
-            Lvalue::Static(ref static_) => {
-                self.describe_field_from_ty(&static_.ty, field)
-            },
-            Lvalue::Projection(ref proj) => {
-                match proj.elem {
-                    ProjectionElem::Deref =>
-                        self.describe_field(&proj.base, field),
-                    ProjectionElem::Downcast(def, variant_index) =>
-                        format!("{}", def.variants[variant_index].fields[field.index()].name),
-                    ProjectionElem::Field(_, field_type) =>
-                        self.describe_field_from_ty(&field_type, field),
-                    ProjectionElem::Index(..)
-                    | ProjectionElem::ConstantIndex { .. }
-                    | ProjectionElem::Subslice { .. } =>
-                        format!("{}", self.describe_field(&proj.base, field)),
-                }
-            }
-        }
-    }
-
-    // End-user visible description of the `field_index`nth field of `ty`
-    fn describe_field_from_ty(&self, ty: &ty::Ty, field: Field) -> String {
-        if ty.is_box() {
-            // If the type is a box, the field is described from the boxed type
-            self.describe_field_from_ty(&ty.boxed_ty(), field)
-        }
-        else {
-            match ty.sty {
-                ty::TyAdt(def, _) => {
-                    if def.is_enum() {
-                        format!("{}", field.index())
-                    }
-                    else {
-                        format!("{}", def.struct_variant().fields[field.index()].name)
-                    }
-                },
-                ty::TyTuple(_, _) => {
-                    format!("{}", field.index())
-                },
-                ty::TyRef(_, tnm) | ty::TyRawPtr(tnm) => {
-                    self.describe_field_from_ty(&tnm.ty, field)
-                },
-                ty::TyArray(ty, _) | ty::TySlice(ty) => {
-                    self.describe_field_from_ty(&ty, field)
-                },
-                ty::TyClosure(closure_def_id, _) => {
-                    // Convert the def-id into a node-id. node-ids are only valid for
-                    // the local code in the current crate, so this returns an `Option` in case
-                    // the closure comes from another crate. But in that case we wouldn't
-                    // be borrowck'ing it, so we can just unwrap:
-                    let node_id = self.tcx.hir.as_local_node_id(closure_def_id).unwrap();
-                    let freevar = self.tcx.with_freevars(node_id, |fv| fv[field.index()]);
-
-                    self.tcx.hir.name(freevar.var_id()).to_string()
-                 }
-                _ => {
-                    // Might need a revision when the fields in trait RFC is implemented
-                    // (https://github.com/rust-lang/rfcs/pull/1546)
-                    bug!("End-user description not implemented for field access on `{:?}`", ty.sty);
-                }
-            }
-        }
+        util::describe_lvalue(self.tcx, self.mir, lvalue).unwrap_or_else(|| "_".to_string())
     }
 
     // Retrieve span of given borrow from the current MIR representation
@@ -1812,6 +1835,137 @@ impl<'cx, 'gcx, 'tcx> MirBorrowckCtxt<'cx, 'gcx, 'tcx> {
     }
 }
 
+/// Where a move found by `classify_move` takes the moved value.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum MoveSite {
+    /// The value is moved into a by-value binding of a pattern.
+    PatternBinding,
+    /// The value is moved by the initializer of a `let` statement.
+    LetInitializer,
+    /// The value is passed as an argument of a function call.
+    CallArgument,
+    /// The value is a method receiver or an operand of an overloaded operator.
+    Receiver,
+    /// The value is moved into any other temporary.
+    Expression,
+}
+
+/// Drops and `StorageDead`s at the end of a scope have an empty span just
+/// past its end; point at the last character of the scope instead.
+fn scope_end_point(span: Span) -> Span {
+    if span.lo() == span.hi() && span.lo() > BytePos(0) {
+        span.with_lo(span.lo() - BytePos(1))
+    } else {
+        span
+    }
+}
+
+/// Returns the lvalue moved by the statement at `location`, along with
+/// where it is moved to. `span` is the span of that statement.
+fn classify_move<'a, 'tcx>(mir: &'a Mir<'tcx>, location: Location, span: Span)
+                           -> Option<(&'a Lvalue<'tcx>, MoveSite)>
+{
+    let block = &mir[location.block];
+    let stmt = match block.statements.get(location.statement_index) {
+        Some(stmt) => stmt,
+        None => return None,
+    };
+    let (dest, moved) = match stmt.kind {
+        StatementKind::Assign(ref dest, Rvalue::Use(Operand::Move(ref moved))) => (dest, moved),
+        _ => return None,
+    };
+    let local = match *dest {
+        Lvalue::Local(local) => local,
+        _ => return None,
+    };
+
+    // Bindings are assigned directly, with the span of their pattern, and so
+    // is the initializer of a `let`, with the span of the initializer. Any
+    // other expression is first moved into a temporary.
+    let decl = &mir.local_decls[local];
+    if decl.is_user_variable {
+        if decl.source_info.span == span {
+            return Some((moved, MoveSite::PatternBinding));
+        }
+        return Some((moved, MoveSite::LetInitializer));
+    }
+
+    let terminator = block.terminator();
+    if let TerminatorKind::Call { ref args, .. } = terminator.kind {
+        let is_arg = |arg: &Operand<'tcx>| match *arg {
+            Operand::Move(Lvalue::Local(arg)) => arg == local,
+            _ => false,
+        };
+        // Receivers and operands start where the call does, so prefixing
+        // them with `&` would borrow the result of the call instead.
+        if args.first().map_or(false, |arg| is_arg(arg)) &&
+            terminator.source_info.span.lo() == span.lo()
+        {
+            return Some((moved, MoveSite::Receiver));
+        }
+        if args.iter().any(|arg| is_arg(arg)) {
+            return Some((moved, MoveSite::CallArgument));
+        }
+    }
+
+    Some((moved, MoveSite::Expression))
+}
+
+/// Suggests borrowing instead of moving out of borrowed content or out of an
+/// index, either with a `ref` binding or by borrowing the moved expression.
+fn suggest_borrow_instead_of_move<'a, 'gcx, 'tcx>(tcx: TyCtxt<'a, 'gcx, 'tcx>,
+                                                  mir: &Mir,
+                                                  err: &mut DiagnosticBuilder,
+                                                  location: Location,
+                                                  span: Span) {
+    let site = match classify_move(mir, location, span) {
+        Some((_, site)) => site,
+        None => return,
+    };
+    let snippet = match tcx.sess.codemap().span_to_snippet(span) {
+        Ok(snippet) => snippet,
+        Err(_) => return,
+    };
+    match site {
+        MoveSite::PatternBinding => {
+            err.span_approximate_suggestion(span,
+                                            "consider binding by reference",
+                                            format!("ref {}", snippet));
+        }
+        MoveSite::LetInitializer => {
+            err.span_suggestion(span,
+                                "consider using a reference instead",
+                                format!("&{}", snippet));
+        }
+        MoveSite::CallArgument | MoveSite::Expression => {
+            err.span_approximate_suggestion(span,
+                                            "consider borrowing here",
+                                            format!("&{}", snippet));
+        }
+        MoveSite::Receiver => {}
+    }
+}
+
+/// Returns true if `ty` is known to implement `Clone`, ignoring regions.
+fn type_implements_clone<'a, 'gcx, 'tcx>(tcx: TyCtxt<'a, 'gcx, 'tcx>,
+                                         param_env: ParamEnv<'gcx>,
+                                         ty: Ty<'tcx>,
+                                         span: Span)
+                                         -> bool
+{
+    let clone_trait = match tcx.lang_items().clone_trait() {
+        Some(def_id) => def_id,
+        None => return false,
+    };
+    let ty = match tcx.lift_to_global(&tcx.erase_regions(&ty)) {
+        Some(ty) => ty,
+        None => return false,
+    };
+    tcx.global_tcx().infer_ctxt().enter(|infcx| {
+        traits::type_known_to_meet_bound(&infcx, param_env, ty, clone_trait, span)
+    })
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 struct Context {
     kind: ContextKind,
@@ -1833,6 +1987,7 @@ enum ContextKind {
     Assert,
     Yield,
     StorageDead,
+    Activation,
 }
 
 impl ContextKind {
@@ -1840,6 +1995,38 @@ impl ContextKind {
 }
 
 impl<'b, 'gcx, 'tcx> InProgress<'b, 'gcx, 'tcx> {
+    /// Runs the dataflow analyses MIR borrowck needs over `mir`, using the
+    /// move data in `mdpe` and the non-lexical lifetimes in `opt_regioncx`.
+    fn compute(tcx: TyCtxt<'b, 'gcx, 'tcx>,
+               mir: &'b Mir<'tcx>,
+               id: ast::NodeId,
+               attributes: &[ast::Attribute],
+               mdpe: &'b MoveDataParamEnv<'tcx, 'gcx>,
+               opt_regioncx: Option<&'b RegionInferenceContext<'tcx>>)
+               -> Self {
+        let dead_unwinds = IdxSetBuf::new_empty(mir.basic_blocks().len());
+        let flow_borrows = do_dataflow(tcx, mir, id, attributes, &dead_unwinds,
+                                       Borrows::new(tcx, mir, opt_regioncx),
+                                       |bd, i| bd.location(i.borrow_index()));
+        let flow_inits = do_dataflow(tcx, mir, id, attributes, &dead_unwinds,
+                                     MaybeInitializedLvals::new(tcx, mir, mdpe),
+                                     |bd, i| &bd.move_data().move_paths[i].lvalue);
+        let flow_uninits = do_dataflow(tcx, mir, id, attributes, &dead_unwinds,
+                                       MaybeUninitializedLvals::new(tcx, mir, mdpe),
+                                       |bd, i| &bd.move_data().move_paths[i].lvalue);
+        let flow_move_outs = do_dataflow(tcx, mir, id, attributes, &dead_unwinds,
+                                         MovingOutStatements::new(tcx, mir, mdpe),
+                                         |bd, i| &bd.move_data().moves[i]);
+        let flow_ever_inits = do_dataflow(tcx, mir, id, attributes, &dead_unwinds,
+                                          EverInitializedLvals::new(tcx, mir, mdpe),
+                                          |bd, i| &bd.move_data().inits[i]);
+        InProgress::new(flow_borrows,
+                        flow_inits,
+                        flow_uninits,
+                        flow_move_outs,
+                        flow_ever_inits)
+    }
+
     pub(super) fn new(borrows: DataflowResults<Borrows<'b, 'gcx, 'tcx>>,
                       inits: DataflowResults<MaybeInitializedLvals<'b, 'gcx, 'tcx>>,
                       uninits: DataflowResults<MaybeUninitializedLvals<'b, 'gcx, 'tcx>>,
@@ -1882,8 +2069,10 @@ impl<'b, 'gcx, 'tcx> InProgress<'b, 'gcx, 'tcx> {
         self.borrows.each_state_bit(|borrow| {
             if saw_one { s.push_str(", "); };
             saw_one = true;
-            let borrow_data = &self.borrows.base_results.operator().borrows()[borrow];
-            s.push_str(&format!("{}", borrow_data));
+            let borrow_data =
+                &self.borrows.base_results.operator().borrows()[borrow.borrow_index()];
+            let kind = if borrow.is_activation() { "active" } else { "reserved" };
+            s.push_str(&format!("{} ({})", borrow_data, kind));
         });
         s.push_str("] ");
 
@@ -1892,8 +2081,10 @@ impl<'b, 'gcx, 'tcx> InProgress<'b, 'gcx, 'tcx> {
         self.borrows.each_gen_bit(|borrow| {
             if saw_one { s.push_str(", "); };
             saw_one = true;
-            let borrow_data = &self.borrows.base_results.operator().borrows()[borrow];
-            s.push_str(&format!("{}", borrow_data));
+            let borrow_data =
+                &self.borrows.base_results.operator().borrows()[borrow.borrow_index()];
+            let kind = if borrow.is_activation() { "active" } else { "reserved" };
+            s.push_str(&format!("{} ({})", borrow_data, kind));
         });
         s.push_str("] ");
 
@@ -1974,6 +2165,16 @@ impl<'b, 'gcx, 'tcx> FlowInProgress<MaybeUninitializedLvals<'b, 'gcx, 'tcx>> {
     }
 }
 
+impl<'b, 'gcx, 'tcx> FlowInProgress<Borrows<'b, 'gcx, 'tcx>> {
+    fn is_borrow_reserved(&self, borrow: BorrowIndex) -> bool {
+        self.curr_state.contains(&ReserveOrActivateIndex::reserved(borrow))
+    }
+
+    fn is_borrow_active(&self, borrow: BorrowIndex) -> bool {
+        self.curr_state.contains(&ReserveOrActivateIndex::active(borrow))
+    }
+}
+
 impl<BD> FlowInProgress<BD> where BD: BitDenotation {
     fn each_state_bit<F>(&self, f: F) where F: FnMut(BD::Idx) {
         self.curr_state.each_bit(self.base_results.operator().bits_per_block(), f)
@@ -1997,7 +2198,7 @@ impl<BD> FlowInProgress<BD> where BD: BitDenotation {
     }
 
     fn reset_to_entry_of(&mut self, bb: BasicBlock) {
-        (*self.curr_state).clone_from(self.base_results.sets().on_entry_set_for(bb.index()));
+        (*self.curr_state).clone_from(&self.base_results.sets().on_entry_set_for(bb.index()));
     }
 
     fn reconstruct_statement_effect(&mut self, loc: Location) {
@@ -167,8 +167,10 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
             BlockSafety::Safe => None,
             BlockSafety::ExplicitUnsafe(node_id) => {
                 assert_eq!(self.push_unsafe_count, 0);
+                // Unsafe operations are credited to the innermost `unsafe` block
+                // around them, while an `unsafe fn` makes all its blocks redundant.
                 match self.unpushed_unsafe {
-                    Safety::Safe => {}
+                    Safety::Safe | Safety::ExplicitUnsafe(_) => {}
                     _ => return
                 }
                 self.unpushed_unsafe = Safety::ExplicitUnsafe(node_id);
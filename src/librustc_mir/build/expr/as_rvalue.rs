@@ -25,7 +25,13 @@ use rustc::middle::region;
 use rustc::ty::{self, Ty};
 use rustc::mir::*;
 use syntax::ast;
-use syntax_pos::Span;
+use syntax::symbol::Symbol;
+use syntax_pos::{Span, SyntaxContext};
+use transform::generator;
+
+/// The length of the longest expression whose source text is included in
+/// the message of an arithmetic assertion.
+const MAX_ASSERT_EXPR_LEN: usize = 60;
 
 impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
     /// See comment on `as_local_operand`
@@ -90,8 +96,8 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
                                          Rvalue::BinaryOp(BinOp::Eq, arg.clone(), minval));
 
                     let err = ConstMathErr::Overflow(Op::Neg);
-                    block = this.assert(block, Operand::Move(is_min), false,
-                                        AssertMessage::Math(err), expr_span);
+                    let msg = this.math_assert_message(err, expr_span);
+                    block = this.assert(block, Operand::Move(is_min), false, msg, expr_span);
                 }
                 block.and(Rvalue::UnaryOp(op, arg))
             }
@@ -203,7 +209,7 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
                         ty: this.hir.tcx().types.u32,
                         literal: Literal::Value {
                             value: this.hir.tcx().mk_const(ty::Const {
-                                val: ConstVal::Integral(ConstInt::U32(0)),
+                                val: ConstVal::Integral(ConstInt::U32(generator::UNRESUMED)),
                                 ty: this.hir.tcx().types.u32
                             }),
                         },
@@ -325,8 +331,8 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
                 }
             });
 
-            block = self.assert(block, Operand::Move(of), false,
-                                AssertMessage::Math(err), span);
+            let msg = self.math_assert_message(err, span);
+            block = self.assert(block, Operand::Move(of), false, msg, span);
 
             block.and(Rvalue::Use(Operand::Move(val)))
         } else {
@@ -348,8 +354,8 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
                 self.cfg.push_assign(block, source_info, &is_zero,
                                      Rvalue::BinaryOp(BinOp::Eq, rhs.clone(), zero));
 
-                block = self.assert(block, Operand::Move(is_zero), false,
-                                    AssertMessage::Math(zero_err), span);
+                let msg = self.math_assert_message(zero_err, span);
+                block = self.assert(block, Operand::Move(is_zero), false, msg, span);
 
                 // We only need to check for the overflow in one case:
                 // MIN / -1, and only for signed values.
@@ -373,8 +379,8 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
                     self.cfg.push_assign(block, source_info, &of,
                                          Rvalue::BinaryOp(BinOp::BitAnd, is_neg_1, is_min));
 
-                    block = self.assert(block, Operand::Move(of), false,
-                                        AssertMessage::Math(overflow_err), span);
+                    let msg = self.math_assert_message(overflow_err, span);
+                    block = self.assert(block, Operand::Move(of), false, msg, span);
                 }
             }
 
@@ -382,6 +388,28 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
         }
     }
 
+    /// The message of an assertion checking for the arithmetic error `err`
+    /// in the expression at `span`.
+    ///
+    /// The source text of the expression is included, when it is short
+    /// and written by the user, unless disabled with `-C debuginfo=0`.
+    fn math_assert_message(&self, err: ConstMathErr, span: Span) -> AssertMessage<'tcx> {
+        let sess = self.hir.tcx().sess;
+        let expr = if sess.opts.cg.debuginfo == Some(0) ||
+                      span.ctxt() != SyntaxContext::empty() {
+            None
+        } else {
+            sess.codemap().span_to_snippet(span).ok().and_then(|snippet| {
+                if snippet.len() <= MAX_ASSERT_EXPR_LEN && !snippet.contains('\n') {
+                    Some(Symbol::intern(&snippet).as_str())
+                } else {
+                    None
+                }
+            })
+        };
+        AssertMessage::Math(err, expr)
+    }
+
     // Helper to get a `-1` value of the appropriate type
     fn neg_1_literal(&mut self, span: Span, ty: Ty<'tcx>) -> Operand<'tcx> {
         let literal = match ty.sty {
@@ -46,8 +46,8 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
             ExprKind::Block { body: ast_block } => {
                 this.ast_block(destination, block, ast_block, source_info)
             }
-            ExprKind::Match { discriminant, arms } => {
-                this.match_expr(destination, expr_span, block, discriminant, arms)
+            ExprKind::Match { discriminant, arms, source } => {
+                this.match_expr(destination, expr_span, block, discriminant, arms, source)
             }
             ExprKind::NeverToAny { source } => {
                 let source = this.hir.mirror(source);
@@ -56,7 +56,13 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
                     _ => false,
                 };
 
-                unpack!(block = this.as_local_rvalue(block, source));
+                // Jumps, and `loop`s that are never broken out of, don't produce
+                // a value, so there is no need for a temporary to store it in.
+                if this.never_produces_value(&source) {
+                    unpack!(block = this.into(destination, block, source));
+                } else {
+                    unpack!(block = this.as_local_rvalue(block, source));
+                }
 
                 // This is an optimization. If the expression was a call then we already have an
                 // unreachable block. Don't bother to terminate it and create a new one.
@@ -249,6 +255,13 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
                         args.into_iter()
                             .map(|arg| unpack!(block = this.as_local_operand(block, arg)))
                             .collect();
+                    let temps: Vec<_> =
+                        Some(&fun).into_iter().chain(&args).filter_map(|operand| {
+                            match *operand {
+                                Operand::Move(Lvalue::Local(temp)) => Some(temp),
+                                _ => None
+                            }
+                        }).collect();
 
                     let success = this.cfg.start_new_block();
                     let cleanup = this.diverge_cleanup();
@@ -262,6 +275,12 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
                             Some ((destination.clone(), success))
                         }
                     });
+
+                    // The callee and its arguments are dead once the call
+                    // returns, so free their temporaries right away.
+                    for temp in temps.into_iter().rev() {
+                        this.free_temp_storage(success, source_info, temp);
+                    }
                     success.unit()
                 }
             }
@@ -288,18 +307,12 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
             ExprKind::Unsize { .. } |
             ExprKind::Repeat { .. } |
             ExprKind::Borrow { .. } |
-            ExprKind::VarRef { .. } |
-            ExprKind::SelfRef |
-            ExprKind::StaticRef { .. } |
             ExprKind::Array { .. } |
             ExprKind::Tuple { .. } |
             ExprKind::Adt { .. } |
             ExprKind::Closure { .. } |
-            ExprKind::Index { .. } |
-            ExprKind::Deref { .. } |
             ExprKind::Literal { .. } |
-            ExprKind::Yield { .. } |
-            ExprKind::Field { .. } => {
+            ExprKind::Yield { .. } => {
                 debug_assert!(match Category::of(&expr.kind).unwrap() {
                     Category::Rvalue(RvalueFunc::Into) => false,
                     _ => true,
@@ -309,6 +322,40 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
                 this.cfg.push_assign(block, source_info, destination, rvalue);
                 block.unit()
             }
+
+            // The destination has already been evaluated, so nothing can
+            // happen between reading an lvalue and storing it there: copy
+            // or move it directly instead of going through a temporary.
+            ExprKind::VarRef { .. } |
+            ExprKind::SelfRef |
+            ExprKind::StaticRef { .. } |
+            ExprKind::Index { .. } |
+            ExprKind::Deref { .. } |
+            ExprKind::Field { .. } => {
+                debug_assert!(Category::of(&expr.kind) == Some(Category::Lvalue));
+
+                let lvalue = unpack!(block = this.as_lvalue(block, expr));
+                let rvalue = Rvalue::Use(this.consume_by_copy_or_move(lvalue));
+                this.cfg.push_assign(block, source_info, destination, rvalue);
+                block.unit()
+            }
+        }
+    }
+
+    /// Whether the diverging expression `expr` leaves its destination alone,
+    /// which is the case for `break`, `continue` and `return`, and for a
+    /// `loop` nothing breaks out of.
+    fn never_produces_value(&mut self, expr: &Expr<'tcx>) -> bool {
+        match expr.kind {
+            ExprKind::Scope { ref value, .. } => {
+                let value = self.hir.mirror(value.clone());
+                self.never_produces_value(&value)
+            }
+            ExprKind::Break { .. } |
+            ExprKind::Continue { .. } |
+            ExprKind::Return { .. } |
+            ExprKind::Loop { condition: None, .. } => true,
+            _ => false,
         }
     }
 }
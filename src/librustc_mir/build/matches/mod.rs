@@ -20,6 +20,7 @@ use rustc::ty::{self, Ty};
 use rustc::mir::*;
 use rustc::hir;
 use hair::*;
+use util;
 use syntax::ast::{Name, NodeId};
 use syntax_pos::Span;
 
@@ -34,7 +35,8 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
                       span: Span,
                       mut block: BasicBlock,
                       discriminant: ExprRef<'tcx>,
-                      arms: Vec<Arm<'tcx>>)
+                      arms: Vec<Arm<'tcx>>,
+                      source: hir::MatchSource)
                       -> BlockAnd<()> {
         let discriminant_lvalue = unpack!(block = self.as_lvalue(block, discriminant));
 
@@ -42,16 +44,28 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
             blocks: arms.iter()
                         .map(|_| self.cfg.start_new_block())
                         .collect(),
+            forwarded_bindings: arms.iter().map(|arm| {
+                if source == hir::MatchSource::TryDesugar {
+                    self.forwarded_binding(arm).map(|var| (var, destination.clone()))
+                } else {
+                    None
+                }
+            }).collect(),
         };
 
         // Get the arm bodies and their scopes, while declaring bindings.
-        let arm_bodies: Vec<_> = arms.iter().map(|arm| {
+        let arm_bodies: Vec<_> = arms.iter().enumerate().map(|(arm_index, arm)| {
+            if arm_blocks.forwarded_bindings[arm_index].is_some() {
+                // The only binding of this arm is stored into `destination`,
+                // which makes evaluating the body unnecessary.
+                return (None, self.visibility_scope);
+            }
             // BUG: use arm lint level
             let body = self.hir.mirror(arm.body.clone());
             let scope = self.declare_bindings(None, body.span,
                                               LintLevel::Inherited,
                                               &arm.patterns[0]);
-            (body, scope.unwrap_or(self.visibility_scope))
+            (Some(body), scope.unwrap_or(self.visibility_scope))
         }).collect();
 
         // create binding start block for link them by false edges
@@ -80,6 +94,7 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
                         match_pairs: vec![MatchPair::new(discriminant_lvalue.clone(), pattern)],
                         bindings: vec![],
                         guard,
+                        scrutinee: discriminant_lvalue.clone(),
                         arm_index,
                         pre_binding_block: *pre_binding_block,
                         next_candidate_pre_binding_block: *next_candidate_pre_binding_block,
@@ -120,7 +135,9 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
             let mut arm_block = arm_blocks.blocks[arm_index];
             // Re-enter the visibility scope we created the bindings in.
             self.visibility_scope = visibility_scope;
-            unpack!(arm_block = self.into(destination, arm_block, body));
+            if let Some(body) = body {
+                unpack!(arm_block = self.into(destination, arm_block, body));
+            }
             self.cfg.terminate(arm_block, outer_source_info,
                                TerminatorKind::Goto { target: end_block });
         }
@@ -129,6 +146,35 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
         end_block.unit()
     }
 
+    /// The `Ok(val) => val` arm of the match generated for `expr?` just moves
+    /// `val` into the destination of the match. Returns the `val` binding of
+    /// such an arm, which is then stored into the destination directly
+    /// instead of into a local of its own.
+    fn forwarded_binding(&mut self, arm: &Arm<'tcx>) -> Option<NodeId> {
+        if arm.patterns.len() != 1 || arm.guard.is_some() {
+            return None;
+        }
+        let var = match *arm.patterns[0].kind {
+            PatternKind::Variant { ref subpatterns, .. } if subpatterns.len() == 1 => {
+                match *subpatterns[0].pattern.kind {
+                    PatternKind::Binding { mode: BindingMode::ByValue,
+                                           var,
+                                           subpattern: None, .. } => var,
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+        let mut body = self.hir.mirror(arm.body.clone());
+        while let ExprKind::Scope { value, .. } = body.kind {
+            body = self.hir.mirror(value);
+        }
+        match body.kind {
+            ExprKind::VarRef { id } if id == var => Some(var),
+            _ => None,
+        }
+    }
+
     pub fn expr_into_pattern(&mut self,
                              mut block: BasicBlock,
                              irrefutable_pat: Pattern<'tcx>,
@@ -162,6 +208,7 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
             match_pairs: vec![MatchPair::new(initializer.clone(), &irrefutable_pat)],
             bindings: vec![],
             guard: None,
+            scrutinee: initializer.clone(),
 
             // since we don't call `match_candidates`, next fields is unused
             arm_index: 0,
@@ -276,8 +323,11 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
 
 /// List of blocks for each arm (and potentially other metadata in the
 /// future).
-struct ArmBlocks {
+struct ArmBlocks<'tcx> {
     blocks: Vec<BasicBlock>,
+    /// For each arm, the binding that is stored directly into the given
+    /// destination, if any; see `forwarded_binding`.
+    forwarded_bindings: Vec<Option<(NodeId, Lvalue<'tcx>)>>,
 }
 
 #[derive(Clone, Debug)]
@@ -291,8 +341,9 @@ pub struct Candidate<'pat, 'tcx:'pat> {
     // ...these bindings established...
     bindings: Vec<Binding<'tcx>>,
 
-    // ...and the guard must be evaluated...
+    // ...and the guard must be evaluated, with the scrutinee borrowed...
     guard: Option<ExprRef<'tcx>>,
+    scrutinee: Lvalue<'tcx>,
 
     // ...and then we branch to arm with this index.
     arm_index: usize,
@@ -397,7 +448,7 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
     /// under control. See `test_candidates` for more details.
     fn match_candidates<'pat>(&mut self,
                               span: Span,
-                              arm_blocks: &mut ArmBlocks,
+                              arm_blocks: &mut ArmBlocks<'tcx>,
                               mut candidates: Vec<Candidate<'pat, 'tcx>>,
                               mut block: BasicBlock)
                               -> Vec<BasicBlock>
@@ -619,7 +670,7 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
     /// once.
     fn test_candidates<'pat>(&mut self,
                              span: Span,
-                             arm_blocks: &mut ArmBlocks,
+                             arm_blocks: &mut ArmBlocks<'tcx>,
                              candidates: &[Candidate<'pat, 'tcx>],
                              block: BasicBlock)
                              -> (Vec<BasicBlock>, usize)
@@ -711,7 +762,7 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
     /// MIR).
     fn bind_and_guard_matched_candidate<'pat>(&mut self,
                                               mut block: BasicBlock,
-                                              arm_blocks: &mut ArmBlocks,
+                                              arm_blocks: &mut ArmBlocks<'tcx>,
                                               candidate: Candidate<'pat, 'tcx>)
                                               -> Option<BasicBlock> {
         debug!("bind_and_guard_matched_candidate(block={:?}, candidate={:?})",
@@ -732,14 +783,44 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
                                    imaginary_targets:
                                        vec![candidate.next_candidate_pre_binding_block]});
 
-        self.bind_matched_candidate(block, candidate.bindings);
+        let borrows_scrutinee = !candidate.bindings.iter().any(|binding| {
+            match binding.binding_mode {
+                BindingMode::ByRef(_, BorrowKind::Shared) | BindingMode::ByValue => false,
+                BindingMode::ByRef(..) => true,
+            }
+        });
+        // The `ref` bindings, shared or mutable, stay borrowed until the
+        // guard has been evaluated.
+        let ref_bindings: Vec<_> = candidate.bindings.iter().filter_map(|binding| {
+            match binding.binding_mode {
+                BindingMode::ByRef(..) => Some(binding.var_id),
+                BindingMode::ByValue => None,
+            }
+        }).collect();
+        let mut bindings = candidate.bindings;
+        if let Some((var, ref destination)) = arm_blocks.forwarded_bindings[candidate.arm_index] {
+            if let Some(index) = bindings.iter().position(|binding| binding.var_id == var) {
+                let binding = bindings.remove(index);
+                let source_info = self.source_info(binding.span);
+                let rvalue = Rvalue::Use(self.consume_by_copy_or_move(binding.source));
+                self.cfg.push_assign(block, source_info, destination, rvalue);
+            }
+        }
+        self.bind_matched_candidate(block, bindings);
 
         if let Some(guard) = candidate.guard {
             // the block to branch to if the guard fails; if there is no
             // guard, this block is simply unreachable
             let guard = self.hir.mirror(guard);
             let source_info = self.source_info(guard.span);
-            let cond = unpack!(block = self.as_local_operand(block, guard));
+            let mut guard_borrows: Vec<_> = ref_bindings.iter()
+                .map(|var| Lvalue::Local(self.var_indices[var]))
+                .collect();
+            if borrows_scrutinee {
+                guard_borrows.extend(self.borrow_scrutinee_for_guard(block, source_info, &guard,
+                                                                     &candidate.scrutinee));
+            }
+            let cond = unpack!(block = self.guard_as_operand(block, guard, guard_borrows));
 
             let false_edge_block = self.cfg.start_new_block();
             self.cfg.terminate(block, source_info,
@@ -760,6 +841,76 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
         }
     }
 
+    /// Borrows the scrutinee of the match until the end of `guard`, so that
+    /// the borrow checker rejects any mutation of the scrutinee from within
+    /// the guard. Candidates with mutable `ref` bindings are left alone, as
+    /// this borrow would conflict with theirs: `guard_as_operand` keeps these
+    /// bindings borrowed for the whole guard instead, and check_match
+    /// forbids using them mutably in it.
+    ///
+    /// Returns the temporary holding the borrow, which `guard_as_operand`
+    /// keeps alive until the guard has been evaluated. Its region ends at
+    /// the `EndRegion` of the guard's scope. Scrutinees within packed
+    /// structs are not borrowed, as that borrow would be unsafe.
+    fn borrow_scrutinee_for_guard(&mut self,
+                                  block: BasicBlock,
+                                  source_info: SourceInfo,
+                                  guard: &Expr<'tcx>,
+                                  scrutinee: &Lvalue<'tcx>)
+                                  -> Option<Lvalue<'tcx>> {
+        let tcx = self.hir.tcx();
+        if util::is_within_packed(tcx, &self.local_decls, scrutinee) {
+            return None;
+        }
+        let region_scope = match guard.kind {
+            ExprKind::Scope { region_scope, .. } => region_scope,
+            _ => span_bug!(guard.span, "guard {:?} is not a scope", guard),
+        };
+        let region = tcx.mk_region(ty::ReScope(region_scope));
+        let scrutinee_ty = scrutinee.ty(&self.local_decls, tcx).to_ty(tcx);
+        let borrow = self.temp(tcx.mk_imm_ref(region, scrutinee_ty), guard.span);
+        self.cfg.push_assign(block, source_info, &borrow,
+                             Rvalue::Ref(region, BorrowKind::Shared, scrutinee.clone()));
+        Some(borrow)
+    }
+
+    /// Evaluates `guard`, keeping the references in `borrows`, i.e. the
+    /// `ref` bindings and the scrutinee's borrow for the guard, alive until
+    /// then: each of them is reborrowed once the guard's value is known, but
+    /// before its scope ends. Under NLL, this use is what keeps the borrows
+    /// from ending before the guard does. `CleanupPostBorrowck` removes the
+    /// reborrows and unused borrows afterwards.
+    fn guard_as_operand(&mut self,
+                        mut block: BasicBlock,
+                        guard: Expr<'tcx>,
+                        borrows: Vec<Lvalue<'tcx>>)
+                        -> BlockAnd<Operand<'tcx>> {
+        if borrows.is_empty() {
+            return self.as_local_operand(block, guard);
+        }
+        let span = guard.span;
+        let (region_scope, lint_level, value) = match guard.kind {
+            ExprKind::Scope { region_scope, lint_level, value } =>
+                (region_scope, lint_level, value),
+            _ => span_bug!(span, "guard is not a scope"),
+        };
+        let source_info = self.source_info(span);
+        let local_scope = self.local_scope();
+        self.in_scope((region_scope, source_info), lint_level, block, |this| {
+            let cond = unpack!(block = this.as_operand(block, local_scope, value));
+            let tcx = this.hir.tcx();
+            let region = tcx.mk_region(ty::ReScope(region_scope));
+            for borrow in borrows {
+                let referent = borrow.deref();
+                let referent_ty = referent.ty(&this.local_decls, tcx).to_ty(tcx);
+                let reborrow = this.temp(tcx.mk_imm_ref(region, referent_ty), span);
+                this.cfg.push_assign(block, source_info, &reborrow,
+                                     Rvalue::Ref(region, BorrowKind::Shared, referent));
+            }
+            block.and(cond)
+        })
+    }
+
     fn bind_matched_candidate(&mut self,
                               block: BasicBlock,
                               bindings: Vec<Binding<'tcx>>) {
@@ -205,7 +205,10 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
                 if let Some(otherwise_block) = otherwise_block {
                     targets.push(otherwise_block);
                 } else {
-                    targets.push(self.unreachable_block());
+                    // Every variant is tested, so the last one's block
+                    // serves as the catch all rather than an unreachable
+                    // block, and its discriminant needn't be listed.
+                    values.pop();
                 }
                 debug!("num_enum_variants: {}, tested variants: {:?}, variants: {:?}",
                        num_enum_variants, values, variants);
@@ -236,18 +239,32 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
                     (ret, TerminatorKind::if_(self.hir.tcx(), Operand::Copy(lvalue.clone()),
                                               true_bb, false_bb))
                 } else {
-                    // The switch may be inexhaustive so we
-                    // add a catch all block
-                    let otherwise = self.cfg.start_new_block();
+                    // All the constants are listed in a single switch, so
+                    // that the backend is free to lower it to a jump table.
+                    // The switch may be inexhaustive so we add a catch all
+                    // block. When every value of the type is covered, the
+                    // last value's block serves as the catch all instead,
+                    // like for enum switches, and that value isn't listed.
+                    let exhaustive = switch_ty_len(switch_ty)
+                        .map_or(false, |len| options.len() as u128 == len);
+                    let otherwise = if exhaustive {
+                        None
+                    } else {
+                        Some(self.cfg.start_new_block())
+                    };
                     let targets: Vec<_> =
                         options.iter()
                                .map(|_| self.cfg.start_new_block())
-                               .chain(Some(otherwise))
+                               .chain(otherwise)
                                .collect();
-                    let values: Vec<_> = options.iter().map(|v|
+                    let mut values: Vec<_> = options.iter().map(|v|
                         v.val.to_const_int().expect("switching on integral")
                     ).collect();
-                    (targets.clone(), TerminatorKind::SwitchInt {
+                    if exhaustive {
+                        values.pop();
+                    }
+                    let ret = targets.clone();
+                    (ret, TerminatorKind::SwitchInt {
                         discr: Operand::Copy(lvalue.clone()),
                         switch_ty,
                         values: From::from(values),
@@ -597,6 +614,7 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
             match_pairs: other_match_pairs,
             bindings: candidate.bindings.clone(),
             guard: candidate.guard.clone(),
+            scrutinee: candidate.scrutinee.clone(),
             arm_index: candidate.arm_index,
             pre_binding_block: candidate.pre_binding_block,
             next_candidate_pre_binding_block: candidate.next_candidate_pre_binding_block,
@@ -660,6 +678,7 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
             match_pairs: all_match_pairs,
             bindings: candidate.bindings.clone(),
             guard: candidate.guard.clone(),
+            scrutinee: candidate.scrutinee.clone(),
             arm_index: candidate.arm_index,
             pre_binding_block: candidate.pre_binding_block,
             next_candidate_pre_binding_block: candidate.next_candidate_pre_binding_block,
@@ -676,3 +695,17 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
 fn is_switch_ty<'tcx>(ty: Ty<'tcx>) -> bool {
     ty.is_integral() || ty.is_char() || ty.is_bool()
 }
+
+/// The number of distinct values of `ty`, if it is a fixed-size integer
+/// type small enough for a switch to list them all.
+fn switch_ty_len<'tcx>(ty: Ty<'tcx>) -> Option<u128> {
+    let bits = match ty.sty {
+        ty::TyInt(ity) => ity.bit_width(),
+        ty::TyUint(uty) => uty.bit_width(),
+        _ => None,
+    };
+    match bits {
+        Some(bits) if bits <= 16 => Some(1 << bits),
+        _ => None,
+    }
+}
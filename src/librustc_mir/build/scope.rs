@@ -515,6 +515,7 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
         let scope = self.visibility_scopes.push(VisibilityScopeData {
             span,
             parent_scope: Some(parent),
+            inlined: None,
         });
         let scope_info = VisibilityScopeInfo {
             lint_root: if let LintLevel::Explicit(lint_root) = lint_level {
@@ -694,6 +695,39 @@ impl<'a, 'gcx, 'tcx> Builder<'a, 'gcx, 'tcx> {
         span_bug!(span, "region scope {:?} not in scope to drop {:?}", region_scope, lvalue);
     }
 
+    /// Ends the storage of the temporary `temp` at the start of `block`,
+    /// instead of on exit from the scope it was scheduled to die in.
+    ///
+    /// This is used for temporaries only read by a terminator, such as
+    /// call arguments, so that their stack slots aren't kept live for the
+    /// rest of the statement. Temporaries that need dropping, or that
+    /// don't have a `StorageDead` scheduled at all, are left alone.
+    pub fn free_temp_storage(&mut self,
+                             block: BasicBlock,
+                             source_info: SourceInfo,
+                             temp: Local) {
+        for scope in self.scopes.iter_mut().rev() {
+            // The exits built so far from this scope free `temp`, so
+            // they can't be reused by the ones built from now on.
+            scope.invalidate_cache(true, true);
+
+            let position = scope.drops.iter().position(|drop_data| {
+                match drop_data.kind {
+                    DropKind::Storage => drop_data.location == Lvalue::Local(temp),
+                    DropKind::Value { .. } => false,
+                }
+            });
+            if let Some(position) = position {
+                scope.drops.remove(position);
+                self.cfg.push(block, Statement {
+                    source_info,
+                    kind: StatementKind::StorageDead(temp)
+                });
+                return;
+            }
+        }
+    }
+
     // Other
     // =====
     /// Creates a path that performs all required cleanup for unwinding.
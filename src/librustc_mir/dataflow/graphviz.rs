@@ -19,7 +19,7 @@ use dot;
 use dot::IntoCow;
 
 use std::fmt::Debug;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io;
 use std::io::prelude::*;
 use std::marker::PhantomData;
@@ -27,7 +27,7 @@ use std::path::Path;
 
 use util;
 
-use super::{BitDenotation, DataflowState};
+use super::{BitDenotation, DataflowState, Direction};
 use super::DataflowBuilder;
 
 pub trait MirWithFlowState<'tcx> {
@@ -54,19 +54,25 @@ struct Graph<'a, 'tcx, MWF:'a, P> where
     render_idx: P,
 }
 
-pub(crate) fn print_borrowck_graph_to<'a, 'tcx, BD, P>(
-    mbcx: &DataflowBuilder<'a, 'tcx, BD>,
+/// Renders the flow state of `mbcx` as a graphviz file at `path`, using
+/// `render_idx` to show each bit in terms of what it stands for, like a
+/// move path or a local.
+pub(crate) fn print_dataflow_graph_to<'tcx, MWF, P>(
+    mbcx: &MWF,
     path: &Path,
     render_idx: P)
     -> io::Result<()>
-    where BD: BitDenotation,
-          P: Fn(&BD, BD::Idx) -> &Debug
+    where MWF: MirWithFlowState<'tcx>,
+          P: Fn(&MWF::BD, <MWF::BD as BitDenotation>::Idx) -> &Debug
 {
     let g = Graph { mbcx: mbcx, phantom: PhantomData, render_idx: render_idx };
     let mut v = Vec::new();
     dot::render(&g, &mut v)?;
-    debug!("print_borrowck_graph_to path: {} node_id: {}",
-           path.display(), mbcx.node_id);
+    debug!("print_dataflow_graph_to path: {} node_id: {}",
+           path.display(), mbcx.node_id());
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
     File::create(path).and_then(|mut f| f.write_all(&v))
 }
 
@@ -131,6 +137,14 @@ impl<'a, 'tcx, MWF, P> dot::Labeller<'a> for Graph<'a, 'tcx, MWF, P>
         //                         "KILL:"  <KILL-BITS>   "=" [k1, k3, k8]
         //                                                [k9]
         // ------  -----------------------  ------------  --------------------
+        //                    [x1, x2, x5]
+        //                 [x7] "= EXIT:"   <EXIT-BITS>
+        // ------  -----------------------  ------------  --------------------
+        //
+        // The EXIT row is the ENTRY row with the GEN and KILL rows
+        // applied. For a backward analysis, whose on-entry sets hold the
+        // state at the end of each block, the ENTRY and EXIT labels are
+        // swapped.
         //
         // (In addition, the added dataflow is rendered with a colored
         // background just so it will stand out compared to the
@@ -158,10 +172,10 @@ impl<'a, 'tcx, MWF, P> dot::Labeller<'a> for Graph<'a, 'tcx, MWF, P>
                     // if not the first row, finish off the previous row
                     write!(w, "</td><td></td><td></td></tr>")?;
                 }
-                write!(w, "<tr><td></td><td {bg} {align}>{objs:?}",
+                write!(w, "<tr><td></td><td {bg} {align}>{objs}",
                        bg = BG_FLOWCONTENT,
                        align = ALIGN_RIGHT,
-                       objs = c)?;
+                       objs = dot::escape_html(&format!("{:?}", c)))?;
                 seen_one = true;
             }
             if !seen_one {
@@ -171,34 +185,36 @@ impl<'a, 'tcx, MWF, P> dot::Labeller<'a> for Graph<'a, 'tcx, MWF, P>
             }
             Ok(())
         }
+        let (entry_label, exit_label) = match <MWF::BD as BitDenotation>::direction() {
+            Direction::Forward => ("ENTRY", "EXIT"),
+            Direction::Backward => ("EXIT", "ENTRY"),
+        };
         util::write_graphviz_node_label(
             *n, self.mbcx.mir(), &mut v, 4,
             |w| {
                 let flow = self.mbcx.flow_state();
-                let entry_interp = flow.interpret_set(&flow.operator,
-                                                      flow.sets.on_entry_set_for(i),
-                                                      &self.render_idx);
+                let entry = flow.sets.on_entry_set_for(i);
+                let entry_interp = flow.interpret_set(&flow.operator, &entry, &self.render_idx);
                 chunked_present_left(w, &entry_interp[..], chunk_size)?;
                 let bits_per_block = flow.sets.bits_per_block();
-                let entry = flow.sets.on_entry_set_for(i);
                 debug!("entry set for i={i} bits_per_block: {bpb} entry: {e:?} interp: {ei:?}",
                        i=i, e=entry, bpb=bits_per_block, ei=entry_interp);
-                write!(w, "= ENTRY:</td><td {bg}><FONT {face}>{entrybits:?}</FONT></td>\
+                write!(w, "= {label}:</td><td {bg}><FONT {face}>{entrybits:?}</FONT></td>\
                                         <td></td></tr>",
+                       label = entry_label,
                        bg = BG_FLOWCONTENT,
                        face = FACE_MONOSPACE,
                        entrybits=bits_to_string(entry.words(), bits_per_block))
             },
             |w| {
                 let flow = self.mbcx.flow_state();
-                let gen_interp =
-                    flow.interpret_set(&flow.operator, flow.sets.gen_set_for(i), &self.render_idx);
-                let kill_interp =
-                    flow.interpret_set(&flow.operator, flow.sets.kill_set_for(i), &self.render_idx);
+                let gen = flow.sets.gen_set_for(i);
+                let kill = flow.sets.kill_set_for(i);
+                let gen_interp = flow.interpret_set(&flow.operator, &gen, &self.render_idx);
+                let kill_interp = flow.interpret_set(&flow.operator, &kill, &self.render_idx);
                 chunked_present_left(w, &gen_interp[..], chunk_size)?;
                 let bits_per_block = flow.sets.bits_per_block();
                 {
-                    let gen = flow.sets.gen_set_for(i);
                     debug!("gen set for i={i} bits_per_block: {bpb} gen: {g:?} interp: {gi:?}",
                            i=i, g=gen, bpb=bits_per_block, gi=gen_interp);
                     write!(w, " = GEN:</td><td {bg}><FONT {face}>{genbits:?}</FONT></td>\
@@ -209,7 +225,6 @@ impl<'a, 'tcx, MWF, P> dot::Labeller<'a> for Graph<'a, 'tcx, MWF, P>
                 }
 
                 {
-                    let kill = flow.sets.kill_set_for(i);
                     debug!("kill set for i={i} bits_per_block: {bpb} kill: {k:?} interp: {ki:?}",
                            i=i, k=kill, bpb=bits_per_block, ki=kill_interp);
                     write!(w, "<tr><td></td><td {bg} {align}>KILL:</td>\
@@ -225,14 +240,14 @@ impl<'a, 'tcx, MWF, P> dot::Labeller<'a> for Graph<'a, 'tcx, MWF, P>
                 for k in kill_interp.chunks(chunk_size) {
                     if !seen_one {
                         // continuation of row; this is fourth <td>
-                        write!(w, "<td {bg}>= {kill:?}</td></tr>",
+                        write!(w, "<td {bg}>= {kill}</td></tr>",
                                bg = BG_FLOWCONTENT,
-                               kill=k)?;
+                               kill = dot::escape_html(&format!("{:?}", k)))?;
                     } else {
                         // new row, with indent of three <td>'s
-                        write!(w, "<tr><td></td><td></td><td></td><td {bg}>{kill:?}</td></tr>",
+                        write!(w, "<tr><td></td><td></td><td></td><td {bg}>{kill}</td></tr>",
                                bg = BG_FLOWCONTENT,
-                               kill=k)?;
+                               kill = dot::escape_html(&format!("{:?}", k)))?;
                     }
                     seen_one = true;
                 }
@@ -241,6 +256,18 @@ impl<'a, 'tcx, MWF, P> dot::Labeller<'a> for Graph<'a, 'tcx, MWF, P>
                            bg = BG_FLOWCONTENT)?;
                 }
 
+                let mut exit = flow.sets.on_entry_set_for(i);
+                exit.union(&gen);
+                exit.subtract(&kill);
+                let exit_interp = flow.interpret_set(&flow.operator, &exit, &self.render_idx);
+                chunked_present_left(w, &exit_interp[..], chunk_size)?;
+                write!(w, " = {label}:</td><td {bg}><FONT {face}>{exitbits:?}</FONT></td>\
+                                       <td></td></tr>",
+                       label = exit_label,
+                       bg = BG_FLOWCONTENT,
+                       face = FACE_MONOSPACE,
+                       exitbits=bits_to_string(exit.words(), bits_per_block))?;
+
                 Ok(())
             })
             .unwrap();
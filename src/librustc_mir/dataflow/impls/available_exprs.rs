@@ -0,0 +1,307 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+pub use super::*;
+
+use rustc::mir::*;
+use rustc::util::nodemap::FxHashMap;
+use dataflow::BitDenotation;
+pub use dataflow::indexes::ExprIndex;
+use rustc_data_structures::indexed_vec::IndexVec;
+use util::def_use::DefUseAnalysis;
+use util::liveness::{borrowed_locals, LocalSet};
+
+/// An operand of an `Expr`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ExprOperand<'tcx> {
+    /// The value of a local, looking through a temporary it was just
+    /// copied into.
+    Local(Local),
+    Constant(Literal<'tcx>),
+}
+
+/// A pure rvalue, identified by its operator and operands.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Expr<'tcx> {
+    BinaryOp(BinOp, ExprOperand<'tcx>, ExprOperand<'tcx>),
+    CheckedBinaryOp(BinOp, ExprOperand<'tcx>, ExprOperand<'tcx>),
+    UnaryOp(UnOp, ExprOperand<'tcx>),
+}
+
+/// `AvailableExpressions` tracks the expressions that have been computed
+/// on every path to each point in the control flow, without any of the
+/// locals they read being changed since.
+///
+/// The expressions are the binary and unary operations whose operands are
+/// constants or locals that are never borrowed, as a local that is
+/// borrowed may be changed through a pointer. An operand that is a
+/// temporary copied from a local earlier in the same block, with the
+/// local unchanged in between, counts as that local, so that the
+/// temporaries MIR building introduces for every operand don't hide the
+/// redundancy. Only expressions computed at least twice are tracked.
+///
+/// An expression is killed by any definition, mutable borrow or storage
+/// marker of one of its operand locals.
+pub struct AvailableExpressions<'a, 'tcx: 'a> {
+    mir: &'a Mir<'tcx>,
+    exprs: IndexVec<ExprIndex, Expr<'tcx>>,
+    computations: IndexVec<ExprIndex, Vec<Location>>,
+    location_expr: FxHashMap<Location, ExprIndex>,
+    location_kills: FxHashMap<Location, Vec<ExprIndex>>,
+}
+
+impl<'a, 'tcx: 'a> AvailableExpressions<'a, 'tcx> {
+    pub fn new(mir: &'a Mir<'tcx>) -> Self {
+        let mut def_use = DefUseAnalysis::new(mir);
+        def_use.clear_and_analyze(mir);
+        let borrowed = borrowed_locals(mir);
+
+        // Hash-cons the expressions computed in the body.
+        let mut found = vec![];
+        let mut indices: FxHashMap<Expr<'tcx>, usize> = FxHashMap();
+        let mut counts = vec![];
+        let finder = OperandFinder { mir, def_use: &def_use, borrowed: &borrowed };
+        for (block, data) in mir.basic_blocks().iter_enumerated() {
+            for (statement_index, statement) in data.statements.iter().enumerate() {
+                let location = Location { block, statement_index };
+                let rvalue = match statement.kind {
+                    StatementKind::Assign(_, ref rvalue) => rvalue,
+                    _ => continue,
+                };
+                if let Some(expr) = finder.expr(rvalue, location) {
+                    let next = counts.len();
+                    let index = *indices.entry(expr.clone()).or_insert(next);
+                    if index == next {
+                        counts.push(0);
+                    }
+                    counts[index] += 1;
+                    found.push((location, expr, index));
+                }
+            }
+        }
+
+        let mut exprs = IndexVec::new();
+        let mut computations: IndexVec<ExprIndex, Vec<Location>> = IndexVec::new();
+        let mut location_expr = FxHashMap();
+        let mut tracked: FxHashMap<usize, ExprIndex> = FxHashMap();
+        for (location, expr, index) in found {
+            if counts[index] < 2 {
+                continue;
+            }
+            let expr_index = *tracked.entry(index).or_insert_with(|| {
+                computations.push(vec![]);
+                exprs.push(expr)
+            });
+            computations[expr_index].push(location);
+            location_expr.insert(location, expr_index);
+        }
+
+        // Find where the operand locals of each expression change.
+        let mut local_exprs = IndexVec::from_elem(vec![], &mir.local_decls);
+        for (expr_index, expr) in exprs.iter_enumerated() {
+            expr.each_local(|local| local_exprs[local].push(expr_index));
+        }
+        let mut location_kills = FxHashMap();
+        for (local, local_exprs) in local_exprs.iter_enumerated() {
+            if local_exprs.is_empty() {
+                continue;
+            }
+            for lvalue_use in def_use.local_info(local).defs_and_uses {
+                if lvalue_use.context.is_mutating_use() || lvalue_use.context.is_storage_marker() {
+                    location_kills.entry(lvalue_use.location)
+                        .or_insert(vec![])
+                        .extend(local_exprs.iter().cloned());
+                }
+            }
+        }
+
+        AvailableExpressions { mir, exprs, computations, location_expr, location_kills }
+    }
+
+    pub fn mir(&self) -> &Mir<'tcx> {
+        self.mir
+    }
+
+    pub fn exprs(&self) -> &IndexVec<ExprIndex, Expr<'tcx>> {
+        &self.exprs
+    }
+
+    /// Returns the expression computed by the statement at `location`, if
+    /// it is tracked.
+    pub fn expr_at(&self, location: Location) -> Option<ExprIndex> {
+        self.location_expr.get(&location).cloned()
+    }
+
+    /// Returns the locations of the statements computing `expr`.
+    pub fn computations(&self, expr: ExprIndex) -> &[Location] {
+        &self.computations[expr]
+    }
+
+    fn kill_effect(&self, sets: &mut BlockSets<ExprIndex>, location: Location) {
+        if let Some(kills) = self.location_kills.get(&location) {
+            for expr in kills {
+                sets.kill(expr);
+            }
+        }
+    }
+}
+
+impl<'tcx> Expr<'tcx> {
+    fn each_local<F: FnMut(Local)>(&self, mut f: F) {
+        let mut operand = |operand: &ExprOperand<'tcx>| {
+            if let ExprOperand::Local(local) = *operand {
+                f(local);
+            }
+        };
+        match *self {
+            Expr::BinaryOp(_, ref lhs, ref rhs) |
+            Expr::CheckedBinaryOp(_, ref lhs, ref rhs) => {
+                operand(lhs);
+                operand(rhs);
+            }
+            Expr::UnaryOp(_, ref operand_) => operand(operand_),
+        }
+    }
+}
+
+struct OperandFinder<'b, 'a: 'b, 'tcx: 'a> {
+    mir: &'a Mir<'tcx>,
+    def_use: &'b DefUseAnalysis<'tcx>,
+    borrowed: &'b LocalSet,
+}
+
+impl<'b, 'a, 'tcx> OperandFinder<'b, 'a, 'tcx> {
+    fn expr(&self, rvalue: &Rvalue<'tcx>, location: Location) -> Option<Expr<'tcx>> {
+        match *rvalue {
+            Rvalue::BinaryOp(op, ref lhs, ref rhs) => {
+                match (self.operand(lhs, location), self.operand(rhs, location)) {
+                    (Some(lhs), Some(rhs)) => Some(Expr::BinaryOp(op, lhs, rhs)),
+                    _ => None,
+                }
+            }
+            Rvalue::CheckedBinaryOp(op, ref lhs, ref rhs) => {
+                match (self.operand(lhs, location), self.operand(rhs, location)) {
+                    (Some(lhs), Some(rhs)) => Some(Expr::CheckedBinaryOp(op, lhs, rhs)),
+                    _ => None,
+                }
+            }
+            Rvalue::UnaryOp(op, ref operand) => {
+                self.operand(operand, location).map(|operand| Expr::UnaryOp(op, operand))
+            }
+            _ => None,
+        }
+    }
+
+    fn operand(&self, operand: &Operand<'tcx>, location: Location) -> Option<ExprOperand<'tcx>> {
+        match *operand {
+            Operand::Constant(ref constant) => {
+                Some(ExprOperand::Constant(constant.literal.clone()))
+            }
+            Operand::Copy(Lvalue::Local(local)) |
+            Operand::Move(Lvalue::Local(local)) => {
+                let local = self.copied_local(local, location).unwrap_or(local);
+                if self.borrowed.contains(&local) {
+                    None
+                } else {
+                    Some(ExprOperand::Local(local))
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the local that `temp` holds a copy of at `location`, if
+    /// its only definition copies it earlier in the same block, and the
+    /// local doesn't change in between.
+    fn copied_local(&self, temp: Local, location: Location) -> Option<Local> {
+        if self.mir.local_kind(temp) != LocalKind::Temp {
+            return None;
+        }
+        let mut defs = self.def_use.local_info(temp).defs_not_including_drop();
+        let def = match (defs.next(), defs.next()) {
+            (Some(def), None) => def.location,
+            _ => return None,
+        };
+        if def.block != location.block || def.statement_index >= location.statement_index {
+            return None;
+        }
+        let src = match self.mir[def.block].statements[def.statement_index].kind {
+            StatementKind::Assign(Lvalue::Local(dest),
+                                  Rvalue::Use(Operand::Copy(Lvalue::Local(src)))) |
+            StatementKind::Assign(Lvalue::Local(dest),
+                                  Rvalue::Use(Operand::Move(Lvalue::Local(src))))
+                if dest == temp => src,
+            _ => return None,
+        };
+        let changed = self.def_use.local_info(src).defs_and_uses.iter().any(|lvalue_use| {
+            (lvalue_use.context.is_mutating_use() || lvalue_use.context.is_storage_marker()) &&
+                lvalue_use.location.block == location.block &&
+                lvalue_use.location.statement_index > def.statement_index &&
+                lvalue_use.location.statement_index < location.statement_index
+        });
+        if changed {
+            None
+        } else {
+            Some(src)
+        }
+    }
+}
+
+impl<'a, 'tcx> BitDenotation for AvailableExpressions<'a, 'tcx> {
+    type Idx = ExprIndex;
+    fn name() -> &'static str { "available_exprs" }
+    fn bits_per_block(&self) -> usize {
+        self.exprs.len()
+    }
+
+    fn start_block_effect(&self, sets: &mut BlockSets<ExprIndex>) {
+        // Nothing has been computed on function entry.
+        for e in sets.on_entry.words_mut() { *e = 0; }
+    }
+
+    fn statement_effect(&self,
+                        sets: &mut BlockSets<ExprIndex>,
+                        loc: Location) {
+        if let Some(expr) = self.expr_at(loc) {
+            sets.gen(&expr);
+        }
+        // A statement like `x = x + 1` kills what it computes.
+        self.kill_effect(sets, loc);
+    }
+
+    fn terminator_effect(&self,
+                         sets: &mut BlockSets<ExprIndex>,
+                         loc: Location) {
+        self.kill_effect(sets, loc);
+    }
+
+    fn propagate_call_return(&self,
+                             _in_out: &mut IdxSet<ExprIndex>,
+                             _call_bb: mir::BasicBlock,
+                             _dest_bb: mir::BasicBlock,
+                             _dest_lval: &mir::Lvalue) {
+        // The destination of the call is already killed by the terminator
+        // effect.
+    }
+}
+
+impl<'a, 'tcx> BitwiseOperator for AvailableExpressions<'a, 'tcx> {
+    #[inline]
+    fn join(&self, pred1: usize, pred2: usize) -> usize {
+        pred1 & pred2 // "available" means computed on all paths
+    }
+}
+
+impl<'a, 'tcx> DataflowOperator for AvailableExpressions<'a, 'tcx> {
+    #[inline]
+    fn bottom_value() -> bool {
+        true // bottom = available, so that loops don't make everything unavailable
+    }
+}
@@ -9,7 +9,7 @@
 // except according to those terms.
 
 use rustc::mir::{self, Location, Mir};
-use rustc::mir::visit::Visitor;
+use rustc::mir::visit::{LvalueContext, Visitor};
 use rustc::ty::{self, Region, TyCtxt};
 use rustc::ty::RegionKind;
 use rustc::ty::RegionKind::ReScope;
@@ -17,10 +17,10 @@ use rustc::util::nodemap::{FxHashMap, FxHashSet};
 
 use rustc_data_structures::bitslice::{BitwiseOperator};
 use rustc_data_structures::indexed_set::{IdxSet};
-use rustc_data_structures::indexed_vec::{IndexVec};
+use rustc_data_structures::indexed_vec::{Idx, IndexVec};
 
 use dataflow::{BitDenotation, BlockSets, DataflowOperator};
-pub use dataflow::indexes::BorrowIndex;
+pub use dataflow::indexes::{BorrowIndex, ReserveOrActivateIndex};
 use transform::nll::region_infer::RegionInferenceContext;
 use transform::nll::ToRegionVid;
 
@@ -28,16 +28,23 @@ use syntax_pos::Span;
 
 use std::fmt;
 
-// `Borrows` maps each dataflow bit to an `Rvalue::Ref`, which can be
-// uniquely identified in the MIR by the `Location` of the assigment
-// statement in which it appears on the right hand side.
+// `Borrows` maps each pair of dataflow bits to an `Rvalue::Ref`, which
+// can be uniquely identified in the MIR by the `Location` of the
+// assigment statement in which it appears on the right hand side.
+//
+// The first bit of the pair is set while the borrow is reserved, and
+// the second while it is active. Most borrows become active as soon as
+// they are reserved; a two-phase borrow only becomes active at its
+// activation location, and until then is compatible with shared reads.
 pub struct Borrows<'a, 'gcx: 'tcx, 'tcx: 'a> {
     tcx: TyCtxt<'a, 'gcx, 'tcx>,
     mir: &'a Mir<'tcx>,
     borrows: IndexVec<BorrowIndex, BorrowData<'tcx>>,
     location_map: FxHashMap<Location, BorrowIndex>,
+    activation_map: FxHashMap<Location, Vec<BorrowIndex>>,
     region_map: FxHashMap<Region<'tcx>, FxHashSet<BorrowIndex>>,
-    region_span_map: FxHashMap<RegionKind, Span>,
+    local_map: FxHashMap<mir::Local, FxHashSet<BorrowIndex>>,
+    region_end_map: FxHashMap<RegionKind, Vec<Location>>,
     nonlexical_regioncx: Option<&'a RegionInferenceContext<'tcx>>,
 }
 
@@ -51,6 +58,32 @@ pub struct BorrowData<'tcx> {
     pub(crate) kind: mir::BorrowKind,
     pub(crate) region: Region<'tcx>,
     pub(crate) lvalue: mir::Lvalue<'tcx>,
+    /// For a two-phase borrow, the location of the first use of the
+    /// temporary it is assigned to, where it becomes active.
+    pub(crate) activation_location: Option<Location>,
+}
+
+/// A point where a borrow goes out of scope, as reported in diagnostics.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BorrowEnd {
+    /// The borrow's region ends here.
+    Here(Span),
+    /// The borrow ends when the temporary dropped here goes away.
+    TemporaryDrop(Span),
+    /// The borrow is never killed, so lasts until the end of the function.
+    EndOfFunction(Span),
+}
+
+impl ReserveOrActivateIndex {
+    pub(crate) fn reserved(i: BorrowIndex) -> Self { ReserveOrActivateIndex::new(i.index() * 2) }
+    pub(crate) fn active(i: BorrowIndex) -> Self { ReserveOrActivateIndex::new(i.index() * 2 + 1) }
+
+    pub(crate) fn is_reservation(self) -> bool { self.index() % 2 == 0 }
+    pub(crate) fn is_activation(self) -> bool { self.index() % 2 == 1 }
+
+    pub(crate) fn borrow_index(self) -> BorrowIndex {
+        BorrowIndex::new(self.index() / 2)
+    }
 }
 
 impl<'tcx> fmt::Display for BorrowData<'tcx> {
@@ -58,7 +91,7 @@ impl<'tcx> fmt::Display for BorrowData<'tcx> {
         let kind = match self.kind {
             mir::BorrowKind::Shared => "",
             mir::BorrowKind::Unique => "uniq ",
-            mir::BorrowKind::Mut => "mut ",
+            mir::BorrowKind::Mut { .. } => "mut ",
         };
         let region = format!("{}", self.region);
         let region = if region.len() > 0 { format!("{} ", region) } else { region };
@@ -76,16 +109,22 @@ impl<'a, 'gcx, 'tcx> Borrows<'a, 'gcx, 'tcx> {
             mir,
             idx_vec: IndexVec::new(),
             location_map: FxHashMap(),
+            two_phase_borrows: vec![],
             region_map: FxHashMap(),
-            region_span_map: FxHashMap()
+            local_map: FxHashMap(),
+            region_end_map: FxHashMap()
         };
         visitor.visit_mir(mir);
+        let activation_map =
+            find_activations(mir, &visitor.two_phase_borrows, &mut visitor.idx_vec);
         return Borrows { tcx: tcx,
                          mir: mir,
                          borrows: visitor.idx_vec,
                          location_map: visitor.location_map,
+                         activation_map,
                          region_map: visitor.region_map,
-                         region_span_map: visitor.region_span_map,
+                         local_map: visitor.local_map,
+                         region_end_map: visitor.region_end_map,
                          nonlexical_regioncx };
 
         struct GatherBorrows<'a, 'gcx: 'tcx, 'tcx: 'a> {
@@ -93,12 +132,16 @@ impl<'a, 'gcx, 'tcx> Borrows<'a, 'gcx, 'tcx> {
             mir: &'a Mir<'tcx>,
             idx_vec: IndexVec<BorrowIndex, BorrowData<'tcx>>,
             location_map: FxHashMap<Location, BorrowIndex>,
+            two_phase_borrows: Vec<(BorrowIndex, mir::Local)>,
             region_map: FxHashMap<Region<'tcx>, FxHashSet<BorrowIndex>>,
-            region_span_map: FxHashMap<RegionKind, Span>,
+            local_map: FxHashMap<mir::Local, FxHashSet<BorrowIndex>>,
+            region_end_map: FxHashMap<RegionKind, Vec<Location>>,
         }
 
         impl<'a, 'gcx, 'tcx> Visitor<'tcx> for GatherBorrows<'a, 'gcx, 'tcx> {
-            fn visit_rvalue(&mut self,
+            fn visit_assign(&mut self,
+                            block: mir::BasicBlock,
+                            assigned_lvalue: &mir::Lvalue<'tcx>,
                             rvalue: &mir::Rvalue<'tcx>,
                             location: mir::Location) {
                 if let mir::Rvalue::Ref(region, kind, ref lvalue) = *rvalue {
@@ -106,12 +149,20 @@ impl<'a, 'gcx, 'tcx> Borrows<'a, 'gcx, 'tcx> {
 
                     let borrow = BorrowData {
                         location: location, kind: kind, region: region, lvalue: lvalue.clone(),
+                        activation_location: None,
                     };
                     let idx = self.idx_vec.push(borrow);
                     self.location_map.insert(location, idx);
+                    if let Some(temp) = two_phase_temp(self.tcx, self.mir, kind, assigned_lvalue) {
+                        self.two_phase_borrows.push((idx, temp));
+                    }
                     let borrows = self.region_map.entry(region).or_insert(FxHashSet());
                     borrows.insert(idx);
+                    if let Some(local) = borrowed_local(lvalue) {
+                        self.local_map.entry(local).or_insert(FxHashSet()).insert(idx);
+                    }
                 }
+                self.super_assign(block, assigned_lvalue, rvalue, location);
             }
 
             fn visit_statement(&mut self,
@@ -119,7 +170,9 @@ impl<'a, 'gcx, 'tcx> Borrows<'a, 'gcx, 'tcx> {
                                statement: &mir::Statement<'tcx>,
                                location: Location) {
                 if let mir::StatementKind::EndRegion(region_scope) = statement.kind {
-                    self.region_span_map.insert(ReScope(region_scope), statement.source_info.span);
+                    self.region_end_map.entry(ReScope(region_scope))
+                        .or_insert(vec![])
+                        .push(location);
                 }
                 self.super_statement(block, statement, location);
             }
@@ -132,6 +185,35 @@ impl<'a, 'gcx, 'tcx> Borrows<'a, 'gcx, 'tcx> {
         &self.borrows[idx].location
     }
 
+    /// Returns the two-phase borrows that become active at `location`.
+    pub fn activations_at_location(&self, location: Location) -> &[BorrowIndex] {
+        self.activation_map.get(&location).map_or(&[], |borrows| &borrows[..])
+    }
+
+    fn kill_borrow(&self, sets: &mut BlockSets<ReserveOrActivateIndex>, idx: BorrowIndex) {
+        sets.kill(&ReserveOrActivateIndex::reserved(idx));
+        sets.kill(&ReserveOrActivateIndex::active(idx));
+    }
+
+    /// Kills the borrows of (parts of) `local` itself, as opposed to data
+    /// reached through a reference held in it. Those end when the storage
+    /// of `local` goes away or its value is overwritten.
+    fn kill_borrows_of_local(&self, sets: &mut BlockSets<ReserveOrActivateIndex>,
+                             local: mir::Local) {
+        if let Some(borrow_indexes) = self.local_map.get(&local) {
+            for &idx in borrow_indexes { self.kill_borrow(sets, idx); }
+        }
+    }
+
+    /// Activates the two-phase borrows whose first use is at `location`.
+    fn activate_borrows_at_location(&self,
+                                    sets: &mut BlockSets<ReserveOrActivateIndex>,
+                                    location: Location) {
+        for &idx in self.activations_at_location(location) {
+            sets.gen(&ReserveOrActivateIndex::active(idx));
+        }
+    }
+
     /// Returns the span for the "end point" given region. This will
     /// return `None` if NLL is enabled, since that concept has no
     /// meaning there.  Otherwise, return region span if it exists and
@@ -140,17 +222,120 @@ impl<'a, 'gcx, 'tcx> Borrows<'a, 'gcx, 'tcx> {
         match self.nonlexical_regioncx {
             Some(_) => None,
             None => {
-                match self.region_span_map.get(region) {
-                    Some(span) => Some(span.end_point()),
+                match self.region_end_map.get(region).and_then(|ends| ends.last()) {
+                    Some(&end) => Some(self.mir.source_info(end).span.end_point()),
                     None => Some(self.mir.span.end_point())
                 }
             }
         }
     }
 
+    /// Returns the points where the borrow `idx` goes out of scope, for
+    /// use in diagnostics. With lexical regions these are the `EndRegion`
+    /// statements of its region; with non-lexical regions they are the
+    /// last points of the region, from which control flows out of it.
+    /// Unwind paths are not considered.
+    pub fn borrow_ends(&self, idx: BorrowIndex) -> Vec<BorrowEnd> {
+        let borrow_data = &self.borrows[idx];
+        let mut end_locations = vec![];
+        match self.nonlexical_regioncx {
+            Some(regioncx) => {
+                let region = borrow_data.region.to_region_vid();
+                for (block, data) in self.mir.basic_blocks().iter_enumerated() {
+                    if data.is_cleanup {
+                        continue;
+                    }
+                    for statement_index in 0..data.statements.len() + 1 {
+                        let location = Location { block, statement_index };
+                        if !regioncx.region_contains_point(region, location) {
+                            continue;
+                        }
+                        let leaves_region = self.successors(location).iter().any(|&succ| {
+                            !regioncx.region_contains_point(region, succ)
+                        });
+                        if leaves_region {
+                            end_locations.push(location);
+                        }
+                    }
+                }
+            }
+            None => {
+                if let Some(locations) = self.region_end_map.get(borrow_data.region) {
+                    end_locations.extend(locations.iter().filter(|location| {
+                        !self.mir[location.block].is_cleanup
+                    }));
+                }
+            }
+        }
+
+        let mut ends = vec![];
+        for location in end_locations {
+            let end = self.borrow_end_at(location);
+            if !ends.contains(&end) {
+                ends.push(end);
+            }
+        }
+        if ends.is_empty() {
+            ends.push(BorrowEnd::EndOfFunction(self.mir.span.end_point()));
+        }
+        ends
+    }
+
+    /// Describes a borrow that ends at `location`. If a temporary with a
+    /// destructor was dropped just before, the borrow ends because of that
+    /// drop, and the diagnostic says so.
+    fn borrow_end_at(&self, location: Location) -> BorrowEnd {
+        let span = self.mir.source_info(location).span.end_point();
+        let data = &self.mir[location.block];
+        let mut statement_index = location.statement_index;
+        if statement_index == data.statements.len() &&
+            self.is_temporary_drop(data.terminator())
+        {
+            return BorrowEnd::TemporaryDrop(span);
+        }
+        // look through the bookkeeping statements that follow a drop
+        while statement_index > 0 {
+            match data.statements[statement_index - 1].kind {
+                mir::StatementKind::EndRegion(_) |
+                mir::StatementKind::StorageDead(_) |
+                mir::StatementKind::Nop => statement_index -= 1,
+                _ => break,
+            }
+        }
+        if statement_index == 0 {
+            let predecessors = self.mir.predecessors_for(location.block);
+            if predecessors.len() == 1 &&
+                self.is_temporary_drop(self.mir[predecessors[0]].terminator())
+            {
+                return BorrowEnd::TemporaryDrop(span);
+            }
+        }
+        BorrowEnd::Here(span)
+    }
+
+    fn is_temporary_drop(&self, terminator: &mir::Terminator<'tcx>) -> bool {
+        match terminator.kind {
+            mir::TerminatorKind::Drop { location: mir::Lvalue::Local(local), .. } => {
+                self.mir.local_kind(local) == mir::LocalKind::Temp
+            }
+            _ => false,
+        }
+    }
+
+    fn successors(&self, location: Location) -> Vec<Location> {
+        let data = &self.mir[location.block];
+        if location.statement_index < data.statements.len() {
+            vec![location.successor_within_block()]
+        } else {
+            data.terminator().successors().iter().map(|&block| {
+                Location { block, statement_index: 0 }
+            }).collect()
+        }
+    }
+
     /// Add all borrows to the kill set, if those borrows are out of scope at `location`.
     fn kill_loans_out_of_scope_at_location(&self,
-                                           sets: &mut BlockSets<BorrowIndex>,
+                                           sets: &mut BlockSets<ReserveOrActivateIndex>,
                                            location: Location) {
         if let Some(regioncx) = self.nonlexical_regioncx {
             for (borrow_index, borrow_data) in self.borrows.iter_enumerated() {
@@ -167,7 +352,7 @@ impl<'a, 'gcx, 'tcx> Borrows<'a, 'gcx, 'tcx> {
                     // mismatch here by not generating a kill for the
                     // location on the borrow itself.
                     if location != borrow_data.location {
-                        sets.kill(&borrow_index);
+                        self.kill_borrow(sets, borrow_index);
                     }
                 }
             }
@@ -176,18 +361,18 @@ impl<'a, 'gcx, 'tcx> Borrows<'a, 'gcx, 'tcx> {
 }
 
 impl<'a, 'gcx, 'tcx> BitDenotation for Borrows<'a, 'gcx, 'tcx> {
-    type Idx = BorrowIndex;
+    type Idx = ReserveOrActivateIndex;
     fn name() -> &'static str { "borrows" }
     fn bits_per_block(&self) -> usize {
-        self.borrows.len()
+        self.borrows.len() * 2
     }
-    fn start_block_effect(&self, _sets: &mut BlockSets<BorrowIndex>)  {
+    fn start_block_effect(&self, _sets: &mut BlockSets<ReserveOrActivateIndex>)  {
         // no borrows of code region_scopes have been taken prior to
         // function execution, so this method has no effect on
         // `_sets`.
     }
     fn statement_effect(&self,
-                        sets: &mut BlockSets<BorrowIndex>,
+                        sets: &mut BlockSets<ReserveOrActivateIndex>,
                         location: Location) {
         let block = &self.mir.basic_blocks().get(location.block).unwrap_or_else(|| {
             panic!("could not find block at location {:?}", location);
@@ -199,13 +384,18 @@ impl<'a, 'gcx, 'tcx> BitDenotation for Borrows<'a, 'gcx, 'tcx> {
             mir::StatementKind::EndRegion(region_scope) => {
                 if let Some(borrow_indexes) = self.region_map.get(&ReScope(region_scope)) {
                     assert!(self.nonlexical_regioncx.is_none());
-                    for idx in borrow_indexes { sets.kill(&idx); }
+                    for &idx in borrow_indexes { self.kill_borrow(sets, idx); }
                 } else {
                     // (if there is no entry, then there are no borrows to be tracked)
                 }
             }
 
-            mir::StatementKind::Assign(_, ref rhs) => {
+            mir::StatementKind::Assign(ref lhs, ref rhs) => {
+                // overwriting a local ends any borrows of its old value
+                if let mir::Lvalue::Local(local) = *lhs {
+                    self.kill_borrows_of_local(sets, local);
+                }
+
                 if let mir::Rvalue::Ref(region, _, ref lvalue) = *rhs {
                     if is_unsafe_lvalue(self.tcx, self.mir, lvalue) { return; }
                     let index = self.location_map.get(&location).unwrap_or_else(|| {
@@ -214,24 +404,34 @@ impl<'a, 'gcx, 'tcx> BitDenotation for Borrows<'a, 'gcx, 'tcx> {
                     assert!(self.region_map.get(region).unwrap_or_else(|| {
                         panic!("could not find BorrowIndexs for region {:?}", region);
                     }).contains(&index));
-                    sets.gen(&index);
+                    sets.gen(&ReserveOrActivateIndex::reserved(*index));
+                    if self.borrows[*index].activation_location.is_none() {
+                        sets.gen(&ReserveOrActivateIndex::active(*index));
+                    }
                 }
             }
 
+            mir::StatementKind::StorageDead(local) => {
+                // the borrowed storage is gone, so the borrows of it end here
+                // rather than at the end of their region
+                self.kill_borrows_of_local(sets, local);
+            }
+
             mir::StatementKind::InlineAsm { .. } |
             mir::StatementKind::SetDiscriminant { .. } |
             mir::StatementKind::StorageLive(..) |
-            mir::StatementKind::StorageDead(..) |
             mir::StatementKind::Validate(..) |
+            mir::StatementKind::Assume(..) |
             mir::StatementKind::Nop => {}
 
         }
 
+        self.activate_borrows_at_location(sets, location);
         self.kill_loans_out_of_scope_at_location(sets, location);
     }
 
     fn terminator_effect(&self,
-                         sets: &mut BlockSets<BorrowIndex>,
+                         sets: &mut BlockSets<ReserveOrActivateIndex>,
                          location: Location) {
         let block = &self.mir.basic_blocks().get(location.block).unwrap_or_else(|| {
             panic!("could not find block at location {:?}", location);
@@ -248,7 +448,7 @@ impl<'a, 'gcx, 'tcx> BitDenotation for Borrows<'a, 'gcx, 'tcx> {
                 // add some kills here as a "backup" and to avoid spurious error messages.
                 for (borrow_index, borrow_data) in self.borrows.iter_enumerated() {
                     if let ReScope(..) = borrow_data.region {
-                        sets.kill(&borrow_index);
+                        self.kill_borrow(sets, borrow_index);
                     }
                 }
             }
@@ -262,15 +462,25 @@ impl<'a, 'gcx, 'tcx> BitDenotation for Borrows<'a, 'gcx, 'tcx> {
             mir::TerminatorKind::FalseEdges {..} |
             mir::TerminatorKind::Unreachable => {}
         }
+        self.activate_borrows_at_location(sets, location);
         self.kill_loans_out_of_scope_at_location(sets, location);
     }
 
     fn propagate_call_return(&self,
-                             _in_out: &mut IdxSet<BorrowIndex>,
+                             in_out: &mut IdxSet<ReserveOrActivateIndex>,
                              _call_bb: mir::BasicBlock,
                              _dest_bb: mir::BasicBlock,
-                             _dest_lval: &mir::Lvalue) {
-        // there are no effects on the region scopes from method calls.
+                             dest_lval: &mir::Lvalue) {
+        // there are no effects on the region scopes from method calls,
+        // but the return value overwrites the destination.
+        if let mir::Lvalue::Local(local) = *dest_lval {
+            if let Some(borrow_indexes) = self.local_map.get(&local) {
+                for &idx in borrow_indexes {
+                    in_out.remove(&ReserveOrActivateIndex::reserved(idx));
+                    in_out.remove(&ReserveOrActivateIndex::active(idx));
+                }
+            }
+        }
     }
 }
 
@@ -288,6 +498,19 @@ impl<'a, 'gcx, 'tcx> DataflowOperator for Borrows<'a, 'gcx, 'tcx> {
     }
 }
 
+/// Returns the local whose own storage `lvalue` is part of, or `None` if
+/// `lvalue` is reached through a dereference (or is a static).
+fn borrowed_local(lvalue: &mir::Lvalue) -> Option<mir::Local> {
+    match *lvalue {
+        mir::Lvalue::Local(local) => Some(local),
+        mir::Lvalue::Static(_) => None,
+        mir::Lvalue::Projection(ref proj) => match proj.elem {
+            mir::ProjectionElem::Deref => None,
+            _ => borrowed_local(&proj.base),
+        },
+    }
+}
+
 fn is_unsafe_lvalue<'a, 'gcx: 'tcx, 'tcx: 'a>(
     tcx: TyCtxt<'a, 'gcx, 'tcx>,
     mir: &'a Mir<'tcx>,
@@ -319,3 +542,78 @@ fn is_unsafe_lvalue<'a, 'gcx: 'tcx, 'tcx: 'a>(
         }
     }
 }
+
+/// Returns the temporary holding a borrow of `kind` assigned to
+/// `assigned_lvalue` if it may be a two-phase borrow, which is the case when
+/// two-phase borrows are enabled, the borrow allows them, and it is assigned
+/// to a temporary.
+fn two_phase_temp<'a, 'gcx: 'tcx, 'tcx: 'a>(
+    tcx: TyCtxt<'a, 'gcx, 'tcx>,
+    mir: &'a Mir<'tcx>,
+    kind: mir::BorrowKind,
+    assigned_lvalue: &mir::Lvalue<'tcx>
+) -> Option<mir::Local> {
+    if !tcx.sess.opts.debugging_opts.two_phase_borrows {
+        return None;
+    }
+    match kind {
+        mir::BorrowKind::Mut { allow_two_phase_borrow: true } => {}
+        _ => return None,
+    }
+    match *assigned_lvalue {
+        mir::Lvalue::Local(local) if mir.local_kind(local) == mir::LocalKind::Temp => Some(local),
+        _ => None,
+    }
+}
+
+/// Sets the activation location of each of the `two_phase_borrows`, paired
+/// with the temporaries holding them, to the single use of its temporary, and
+/// returns the borrows becoming active at each location. The uses of all the
+/// temporaries are gathered in one walk over `mir`; a borrow whose temporary
+/// is used more than once is left active right away.
+fn find_activations<'tcx>(mir: &Mir<'tcx>,
+                          two_phase_borrows: &[(BorrowIndex, mir::Local)],
+                          borrows: &mut IndexVec<BorrowIndex, BorrowData<'tcx>>)
+                          -> FxHashMap<Location, Vec<BorrowIndex>> {
+    struct FindUses {
+        temps: FxHashSet<mir::Local>,
+        uses: FxHashMap<mir::Local, Vec<Location>>,
+    }
+
+    impl<'tcx> Visitor<'tcx> for FindUses {
+        fn visit_local(&mut self,
+                       local: &mir::Local,
+                       context: LvalueContext<'tcx>,
+                       location: Location) {
+            if !self.temps.contains(local) {
+                return;
+            }
+            match context {
+                LvalueContext::StorageLive | LvalueContext::StorageDead => {}
+                _ => self.uses.entry(*local).or_insert(vec![]).push(location),
+            }
+        }
+    }
+
+    let mut activation_map = FxHashMap();
+    if two_phase_borrows.is_empty() {
+        return activation_map;
+    }
+
+    let mut visitor = FindUses {
+        temps: two_phase_borrows.iter().map(|&(_, temp)| temp).collect(),
+        uses: FxHashMap(),
+    };
+    visitor.visit_mir(mir);
+
+    for &(idx, temp) in two_phase_borrows {
+        let assignment = borrows[idx].location;
+        let uses = visitor.uses.get(&temp).map_or(&[][..], |uses| &uses[..]);
+        let mut other_uses = uses.iter().filter(|&&use_location| use_location != assignment);
+        if let (Some(&activation), None) = (other_uses.next(), other_uses.next()) {
+            borrows[idx].activation_location = Some(activation);
+            activation_map.entry(activation).or_insert(vec![]).push(idx);
+        }
+    }
+    activation_map
+}
@@ -31,8 +31,14 @@ use super::drop_flag_effects_for_location;
 use super::{on_lookup_result_bits, for_location_inits};
 
 mod storage_liveness;
+mod reaching_defs;
+mod available_exprs;
+mod observed_locals;
 
 pub use self::storage_liveness::*;
+pub use self::reaching_defs::*;
+pub use self::available_exprs::*;
+pub use self::observed_locals::*;
 
 #[allow(dead_code)]
 pub(super) mod borrows;
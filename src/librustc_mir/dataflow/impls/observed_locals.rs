@@ -0,0 +1,228 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+pub use super::*;
+
+use rustc::mir::*;
+use rustc::mir::visit::{LvalueContext, Visitor};
+use rustc::util::nodemap::FxHashMap;
+use dataflow::{BitDenotation, DataflowResults, state_for_location};
+pub use dataflow::indexes::LocalBorrowIndex;
+use rustc_data_structures::indexed_vec::IndexVec;
+use util::liveness::LocalSet;
+
+/// A borrow of (a part of) a local.
+#[derive(Copy, Clone, Debug)]
+pub struct LocalBorrow {
+    pub borrowed: Local,
+    pub location: Location,
+
+    /// The local the reference is assigned to, if it is only ever used
+    /// through a dereference, so that the borrow can't outlive its
+    /// current value.
+    pub holder: Option<Local>,
+}
+
+/// `MaybeObservedLocals` tracks the borrows of locals that may still be
+/// live at each point in the control flow, so that a local none of whose
+/// borrows are live can't be observed or changed through a pointer.
+///
+/// Every `Ref` rvalue of an lvalue based on a local gets its own bit,
+/// including the reborrows through a dereference of the local, and raw
+/// pointers, which are casts of such references. A borrow only dies with
+/// the storage of its local, unless the reference is assigned to a local
+/// that is only ever used through a dereference, and is not reborrowed:
+/// the borrow then also dies with the storage of that local, or when
+/// that local is reassigned.
+///
+/// When in doubt, a borrow is live, so that its local is "maybe
+/// observed".
+pub struct MaybeObservedLocals<'a, 'tcx: 'a> {
+    mir: &'a Mir<'tcx>,
+    borrows: IndexVec<LocalBorrowIndex, LocalBorrow>,
+    location_borrows: FxHashMap<Location, LocalBorrowIndex>,
+    /// The borrows of each local.
+    local_borrows: IndexVec<Local, Vec<LocalBorrowIndex>>,
+    /// The borrows each local holds the only reference of.
+    held_borrows: IndexVec<Local, Vec<LocalBorrowIndex>>,
+}
+
+impl<'a, 'tcx: 'a> MaybeObservedLocals<'a, 'tcx> {
+    pub fn new(mir: &'a Mir<'tcx>) -> Self {
+        let mut escaping = EscapingReferences(LocalSet::new_empty(mir.local_decls.len()));
+        escaping.visit_mir(mir);
+
+        let mut borrows = IndexVec::new();
+        let mut location_borrows = FxHashMap();
+        let mut local_borrows = IndexVec::from_elem(vec![], &mir.local_decls);
+        let mut held_borrows = IndexVec::from_elem(vec![], &mir.local_decls);
+        for (block, data) in mir.basic_blocks().iter_enumerated() {
+            for (statement_index, statement) in data.statements.iter().enumerate() {
+                let (dest, lvalue) = match statement.kind {
+                    StatementKind::Assign(ref dest, Rvalue::Ref(_, _, ref lvalue)) => {
+                        (dest, lvalue)
+                    }
+                    _ => continue,
+                };
+                let borrowed = match base_local(lvalue) {
+                    Some(borrowed) => borrowed,
+                    None => continue,
+                };
+                let holder = match *dest {
+                    Lvalue::Local(holder) if !escaping.0.contains(&holder) => Some(holder),
+                    _ => None,
+                };
+                let location = Location { block, statement_index };
+                let borrow = borrows.push(LocalBorrow { borrowed, location, holder });
+                location_borrows.insert(location, borrow);
+                local_borrows[borrowed].push(borrow);
+                if let Some(holder) = holder {
+                    held_borrows[holder].push(borrow);
+                }
+            }
+        }
+
+        MaybeObservedLocals { mir, borrows, location_borrows, local_borrows, held_borrows }
+    }
+
+    pub fn mir(&self) -> &Mir<'tcx> {
+        self.mir
+    }
+
+    pub fn borrows(&self) -> &IndexVec<LocalBorrowIndex, LocalBorrow> {
+        &self.borrows
+    }
+}
+
+/// Returns the local `lvalue` is a part of, if any.
+fn base_local(lvalue: &Lvalue) -> Option<Local> {
+    match *lvalue {
+        Lvalue::Local(local) => Some(local),
+        Lvalue::Static(_) => None,
+        Lvalue::Projection(ref proj) => base_local(&proj.base),
+    }
+}
+
+/// Finds the locals that may hold a reference that outlives their
+/// value, because they are used other than through a dereference, or
+/// are reborrowed.
+struct EscapingReferences(LocalSet);
+
+impl<'tcx> Visitor<'tcx> for EscapingReferences {
+    fn visit_lvalue(&mut self,
+                    lvalue: &Lvalue<'tcx>,
+                    context: LvalueContext<'tcx>,
+                    _location: Location) {
+        let mut base = lvalue;
+        let mut through_deref = false;
+        while let Lvalue::Projection(ref proj) = *base {
+            if let ProjectionElem::Index(index) = proj.elem {
+                self.0.add(&index);
+            }
+            through_deref = proj.elem == ProjectionElem::Deref;
+            base = &proj.base;
+        }
+        if let Lvalue::Local(local) = *base {
+            let contained = match context {
+                LvalueContext::Borrow { .. } => false,
+                _ if through_deref => true,
+                LvalueContext::Store |
+                LvalueContext::StorageLive |
+                LvalueContext::StorageDead => true,
+                _ => false,
+            };
+            if !contained {
+                self.0.add(&local);
+            }
+        }
+    }
+}
+
+impl<'a, 'tcx> BitDenotation for MaybeObservedLocals<'a, 'tcx> {
+    type Idx = LocalBorrowIndex;
+    fn name() -> &'static str { "maybe_observed_locals" }
+    fn bits_per_block(&self) -> usize {
+        self.borrows.len()
+    }
+
+    fn start_block_effect(&self, _sets: &mut BlockSets<LocalBorrowIndex>) {
+        // Nothing is borrowed on function entry; the arguments are owned
+        // by the function.
+    }
+
+    fn statement_effect(&self,
+                        sets: &mut BlockSets<LocalBorrowIndex>,
+                        loc: Location) {
+        match self.mir[loc.block].statements[loc.statement_index].kind {
+            StatementKind::StorageLive(local) |
+            StatementKind::StorageDead(local) => {
+                for borrow in &self.local_borrows[local] {
+                    sets.kill(borrow);
+                }
+                for borrow in &self.held_borrows[local] {
+                    sets.kill(borrow);
+                }
+            }
+            StatementKind::Assign(Lvalue::Local(local), _) => {
+                for borrow in &self.held_borrows[local] {
+                    sets.kill(borrow);
+                }
+                if let Some(borrow) = self.location_borrows.get(&loc) {
+                    sets.gen(borrow);
+                }
+            }
+            _ => {
+                if let Some(borrow) = self.location_borrows.get(&loc) {
+                    sets.gen(borrow);
+                }
+            }
+        }
+    }
+
+    fn terminator_effect(&self,
+                         _sets: &mut BlockSets<LocalBorrowIndex>,
+                         _loc: Location) {
+        // Terminators don't borrow, and the locals they assign to or drop
+        // don't hold the only reference of a borrow.
+    }
+
+    fn propagate_call_return(&self,
+                             _in_out: &mut IdxSet<LocalBorrowIndex>,
+                             _call_bb: mir::BasicBlock,
+                             _dest_bb: mir::BasicBlock,
+                             _dest_lval: &mir::Lvalue) {
+        // Nothing to do when a call returns successfully
+    }
+}
+
+impl<'a, 'tcx> BitwiseOperator for MaybeObservedLocals<'a, 'tcx> {
+    #[inline]
+    fn join(&self, pred1: usize, pred2: usize) -> usize {
+        pred1 | pred2 // a borrow is live if it is live from any predecessor
+    }
+}
+
+impl<'a, 'tcx> DataflowOperator for MaybeObservedLocals<'a, 'tcx> {
+    #[inline]
+    fn bottom_value() -> bool {
+        false // bottom = no borrow is live
+    }
+}
+
+impl<'a, 'tcx> DataflowResults<MaybeObservedLocals<'a, 'tcx>> {
+    /// Whether `local` may be read or written through a pointer right
+    /// before the statement or terminator at `location` is executed, i.e.
+    /// whether a borrow of (a part of) it may be live there.
+    pub fn maybe_observed(&self, local: Local, location: Location) -> bool {
+        let analysis = self.operator();
+        let state = state_for_location(location, analysis, self, analysis.mir);
+        analysis.local_borrows[local].iter().any(|borrow| state.contains(borrow))
+    }
+}
@@ -0,0 +1,207 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+pub use super::*;
+
+use rustc::mir::*;
+use rustc::mir::visit::LvalueContext;
+use rustc::util::nodemap::FxHashMap;
+use dataflow::{BitDenotation, DataflowResults, state_for_location};
+pub use dataflow::indexes::DefIndex;
+use rustc_data_structures::indexed_set::IdxSetBuf;
+use rustc_data_structures::indexed_vec::IndexVec;
+use std::slice;
+use util::def_use::DefUseAnalysis;
+
+/// A definition of a local: an assignment to it or to a part of it, a
+/// call returning into it, or a mutable borrow of (a part of) it.
+#[derive(Copy, Clone, Debug)]
+pub struct Def {
+    pub local: Local,
+    pub location: Location,
+
+    /// Whether the definition overwrites the whole local, so that no other
+    /// definition of it reaches past this one.
+    pub overwrites: bool,
+}
+
+/// `ReachingDefinitions` tracks the definitions of locals that may reach
+/// each point in the control flow, i.e. that are not overwritten on some
+/// path from the definition to that point.
+///
+/// Every mutating use of a local found by `DefUseAnalysis`, except for
+/// drops, gets its own bit. Only assignments to the whole local and call
+/// destinations kill the other definitions of the local; writes to a part
+/// of it and mutable borrows may leave some of the previous value in
+/// place. The storage markers of a local kill all its definitions.
+pub struct ReachingDefinitions<'a, 'tcx: 'a> {
+    mir: &'a Mir<'tcx>,
+    defs: IndexVec<DefIndex, Def>,
+    local_defs: IndexVec<Local, Vec<DefIndex>>,
+    location_defs: FxHashMap<Location, Vec<DefIndex>>,
+}
+
+impl<'a, 'tcx: 'a> ReachingDefinitions<'a, 'tcx> {
+    pub fn new(mir: &'a Mir<'tcx>) -> Self {
+        let mut def_use = DefUseAnalysis::new(mir);
+        def_use.clear_and_analyze(mir);
+
+        let mut defs = IndexVec::new();
+        let mut local_defs = IndexVec::from_elem(vec![], &mir.local_decls);
+        let mut location_defs = FxHashMap();
+        for local in mir.local_decls.indices() {
+            for lvalue_use in def_use.local_info(local).defs_not_including_drop() {
+                let overwrites = match lvalue_use.context {
+                    LvalueContext::Store | LvalueContext::Call => true,
+                    _ => false,
+                };
+                let def = defs.push(Def { local, location: lvalue_use.location, overwrites });
+                local_defs[local].push(def);
+                location_defs.entry(lvalue_use.location).or_insert(vec![]).push(def);
+            }
+        }
+
+        ReachingDefinitions { mir, defs, local_defs, location_defs }
+    }
+
+    pub fn mir(&self) -> &Mir<'tcx> {
+        self.mir
+    }
+
+    pub fn defs(&self) -> &IndexVec<DefIndex, Def> {
+        &self.defs
+    }
+
+    /// Returns the definitions of `local`, in the order `DefUseAnalysis`
+    /// finds them.
+    pub fn defs_of(&self, local: Local) -> &[DefIndex] {
+        &self.local_defs[local]
+    }
+
+    /// Applies the definitions at `location` to `sets`.
+    fn def_effect(&self, sets: &mut BlockSets<DefIndex>, location: Location) {
+        let defs = match self.location_defs.get(&location) {
+            Some(defs) => defs,
+            None => return,
+        };
+        for &def in defs {
+            if self.defs[def].overwrites {
+                for other in &self.local_defs[self.defs[def].local] {
+                    sets.kill(other);
+                }
+            }
+        }
+        for def in defs {
+            sets.gen(def);
+        }
+    }
+}
+
+impl<'a, 'tcx> BitDenotation for ReachingDefinitions<'a, 'tcx> {
+    type Idx = DefIndex;
+    fn name() -> &'static str { "reaching_defs" }
+    fn bits_per_block(&self) -> usize {
+        self.defs.len()
+    }
+
+    fn start_block_effect(&self, _sets: &mut BlockSets<DefIndex>) {
+        // No definition reaches the start of the function; arguments are
+        // not definitions.
+    }
+
+    fn statement_effect(&self,
+                        sets: &mut BlockSets<DefIndex>,
+                        loc: Location) {
+        match self.mir[loc.block].statements[loc.statement_index].kind {
+            StatementKind::StorageLive(local) |
+            StatementKind::StorageDead(local) => {
+                for def in &self.local_defs[local] {
+                    sets.kill(def);
+                }
+            }
+            _ => self.def_effect(sets, loc),
+        }
+    }
+
+    fn terminator_effect(&self,
+                         _sets: &mut BlockSets<DefIndex>,
+                         _loc: Location) {
+        // The only definitions in terminators are call destinations, which
+        // are only defined when the call returns.
+    }
+
+    fn propagate_call_return(&self,
+                             in_out: &mut IdxSet<DefIndex>,
+                             call_bb: mir::BasicBlock,
+                             _dest_bb: mir::BasicBlock,
+                             _dest_lval: &mir::Lvalue) {
+        let location = Location {
+            block: call_bb,
+            statement_index: self.mir[call_bb].statements.len(),
+        };
+        let mut gen = IdxSetBuf::new_empty(self.bits_per_block());
+        let mut kill = IdxSetBuf::new_empty(self.bits_per_block());
+        self.def_effect(&mut BlockSets {
+            on_entry: &mut IdxSetBuf::new_empty(0),
+            gen_set: &mut gen,
+            kill_set: &mut kill,
+        }, location);
+        in_out.subtract(&kill);
+        in_out.union(&gen);
+    }
+}
+
+impl<'a, 'tcx> BitwiseOperator for ReachingDefinitions<'a, 'tcx> {
+    #[inline]
+    fn join(&self, pred1: usize, pred2: usize) -> usize {
+        pred1 | pred2 // a definition reaches if it reaches from any predecessor
+    }
+}
+
+impl<'a, 'tcx> DataflowOperator for ReachingDefinitions<'a, 'tcx> {
+    #[inline]
+    fn bottom_value() -> bool {
+        false // bottom = no definition reaches
+    }
+}
+
+impl<'a, 'tcx> DataflowResults<ReachingDefinitions<'a, 'tcx>> {
+    /// Returns the locations of the definitions of `local` that reach
+    /// `location`, right before the statement or terminator there is
+    /// executed.
+    pub fn reaching_defs<'b>(&'b self, location: Location, local: Local)
+                             -> impl Iterator<Item = Location> + 'b {
+        let analysis = self.operator();
+        ReachingDefs {
+            defs: &analysis.defs,
+            candidates: analysis.local_defs[local].iter(),
+            state: state_for_location(location, analysis, self, analysis.mir),
+        }
+    }
+}
+
+struct ReachingDefs<'b> {
+    defs: &'b IndexVec<DefIndex, Def>,
+    candidates: slice::Iter<'b, DefIndex>,
+    state: IdxSetBuf<DefIndex>,
+}
+
+impl<'b> Iterator for ReachingDefs<'b> {
+    type Item = Location;
+
+    fn next(&mut self) -> Option<Location> {
+        while let Some(def) = self.candidates.next() {
+            if self.state.contains(def) {
+                return Some(self.defs[*def].location);
+            }
+        }
+        None
+    }
+}
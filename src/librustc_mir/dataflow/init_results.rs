@@ -0,0 +1,185 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The move data of a body, with the maybe-initialized and
+//! maybe-uninitialized lvalues at each of its locations.
+//!
+//! Computing these means gathering the moves and running the
+//! initialization analyses, which is expensive, so the passes needing them
+//! share one `InitializationResults` per version of the body, through
+//! `AnalysisCache::initialization_results`; `ElaborateDrops` and the
+//! generator transform both get theirs from there. The move path indices
+//! stay valid for as long as the `InitializationResults` they come from.
+
+use rustc::mir::{BasicBlock, Location, Mir, TerminatorKind};
+use rustc::ty::TyCtxt;
+use rustc_data_structures::indexed_set::IdxSetBuf;
+use rustc_data_structures::indexed_vec::Idx;
+use syntax::ast;
+use util::elaborate_drops::DropFlagState;
+
+use super::move_paths::{HasMoveData, LookupResult, MoveData, MovePathIndex};
+use super::{do_dataflow, drop_flag_effects_for_location, on_all_drop_children_bits};
+use super::{AllSets, MaybeInitializedLvals, MaybeUninitializedLvals, MoveDataParamEnv};
+
+pub struct InitializationResults<'tcx> {
+    pub(crate) env: MoveDataParamEnv<'tcx, 'tcx>,
+
+    // Both ignore the unwind edges of the drops that can't drop anything.
+    inits: AllSets<MovePathIndex>,
+    uninits: AllSets<MovePathIndex>,
+}
+
+impl<'tcx> InitializationResults<'tcx> {
+    /// Computes the results for `mir`, the body of `id`, or returns `None`
+    /// if the body moves out of an lvalue it can't move out of.
+    pub fn compute<'a>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                       mir: &Mir<'tcx>,
+                       id: ast::NodeId)
+                       -> Option<InitializationResults<'tcx>> {
+        let move_data = match MoveData::gather_moves(mir, tcx) {
+            Ok(move_data) => move_data,
+            Err(_) => return None,
+        };
+        let param_env = tcx.param_env(tcx.hir.local_def_id(id));
+        let env = MoveDataParamEnv { move_data, param_env };
+        let dead_unwinds = find_dead_unwinds(tcx, mir, id, &env);
+        let inits =
+            do_dataflow(tcx, mir, id, &[], &dead_unwinds,
+                        MaybeInitializedLvals::new(tcx, mir, &env),
+                        |bd, p| &bd.move_data().move_paths[p].lvalue).into_sets();
+        let uninits =
+            do_dataflow(tcx, mir, id, &[], &dead_unwinds,
+                        MaybeUninitializedLvals::new(tcx, mir, &env),
+                        |bd, p| &bd.move_data().move_paths[p].lvalue).into_sets();
+        Some(InitializationResults { env, inits, uninits })
+    }
+
+    pub fn move_data(&self) -> &MoveData<'tcx> {
+        &self.env.move_data
+    }
+
+    /// Returns which lvalues may be initialized, and which may be
+    /// uninitialized, right before `loc`.
+    pub(crate) fn state_at<'a>(&self,
+                               tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                               mir: &Mir<'tcx>,
+                               loc: Location)
+                               -> InitializationData {
+        let mut data = InitializationData {
+            live: self.inits.on_entry_set_for(loc.block.index()),
+            dead: self.uninits.on_entry_set_for(loc.block.index()),
+        };
+        for stmt in 0..loc.statement_index {
+            data.apply_location(tcx, mir, &self.env,
+                                Location { block: loc.block, statement_index: stmt });
+        }
+        data
+    }
+}
+
+/// Return the set of basic blocks whose unwind edges are known
+/// to not be reachable, because they are `drop` terminators
+/// that can't drop anything.
+fn find_dead_unwinds<'a, 'tcx>(
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    mir: &Mir<'tcx>,
+    id: ast::NodeId,
+    env: &MoveDataParamEnv<'tcx, 'tcx>)
+    -> IdxSetBuf<BasicBlock>
+{
+    debug!("find_dead_unwinds({:?})", mir.span);
+    // We only need to do this pass once, because unwind edges can only
+    // reach cleanup blocks, which can't have unwind edges themselves.
+    let mut dead_unwinds = IdxSetBuf::new_empty(mir.basic_blocks().len());
+    let flow_inits =
+        do_dataflow(tcx, mir, id, &[], &dead_unwinds,
+                    MaybeInitializedLvals::new(tcx, mir, &env),
+                    |bd, p| &bd.move_data().move_paths[p].lvalue);
+    for (bb, bb_data) in mir.basic_blocks().iter_enumerated() {
+        let location = match bb_data.terminator().kind {
+            TerminatorKind::Drop { ref location, unwind: Some(_), .. } |
+            TerminatorKind::DropAndReplace { ref location, unwind: Some(_), .. } => location,
+            _ => continue,
+        };
+
+        let mut init_data = InitializationData {
+            live: flow_inits.sets().on_entry_set_for(bb.index()),
+            dead: IdxSetBuf::new_empty(env.move_data.move_paths.len()),
+        };
+        debug!("find_dead_unwinds @ {:?}: {:?}; init_data={:?}",
+               bb, bb_data, init_data.live);
+        for stmt in 0..bb_data.statements.len() {
+            let loc = Location { block: bb, statement_index: stmt };
+            init_data.apply_location(tcx, mir, env, loc);
+        }
+
+        let path = match env.move_data.rev_lookup.find(location) {
+            LookupResult::Exact(e) => e,
+            LookupResult::Parent(..) => {
+                debug!("find_dead_unwinds: has parent; skipping");
+                continue
+            }
+        };
+
+        debug!("find_dead_unwinds @ {:?}: path({:?})={:?}", bb, location, path);
+
+        let mut maybe_live = false;
+        on_all_drop_children_bits(tcx, mir, &env, path, |child| {
+            let (child_maybe_live, _) = init_data.state(child);
+            maybe_live |= child_maybe_live;
+        });
+
+        debug!("find_dead_unwinds @ {:?}: maybe_live={}", bb, maybe_live);
+        if !maybe_live {
+            dead_unwinds.add(&bb);
+        }
+    }
+
+    dead_unwinds
+}
+
+pub(crate) struct InitializationData {
+    live: IdxSetBuf<MovePathIndex>,
+    dead: IdxSetBuf<MovePathIndex>
+}
+
+impl InitializationData {
+    pub(crate) fn apply_location<'a,'tcx>(&mut self,
+                                          tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                          mir: &Mir<'tcx>,
+                                          env: &MoveDataParamEnv<'tcx, 'tcx>,
+                                          loc: Location)
+    {
+        drop_flag_effects_for_location(tcx, mir, env, loc, |path, df| {
+            debug!("at location {:?}: setting {:?} to {:?}",
+                   loc, path, df);
+            match df {
+                DropFlagState::Present => {
+                    self.live.add(&path);
+                    self.dead.remove(&path);
+                }
+                DropFlagState::Absent => {
+                    self.dead.add(&path);
+                    self.live.remove(&path);
+                }
+            }
+        });
+    }
+
+    pub(crate) fn state(&self, path: MovePathIndex) -> (bool, bool) {
+        (self.live.contains(&path), self.dead.contains(&path))
+    }
+
+    /// The lvalues that may be initialized.
+    pub(crate) fn maybe_initialized(&self) -> &IdxSetBuf<MovePathIndex> {
+        &self.live
+    }
+}
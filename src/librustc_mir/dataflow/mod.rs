@@ -10,7 +10,7 @@
 
 use syntax::ast::{self, MetaItem};
 
-use rustc_data_structures::indexed_set::{IdxSet, IdxSetBuf};
+use rustc_data_structures::indexed_set::{HybridIdxSetBuf, IdxSet, IdxSetBuf};
 use rustc_data_structures::indexed_vec::Idx;
 use rustc_data_structures::bitslice::{bitwise, BitwiseOperator};
 
@@ -24,18 +24,29 @@ use std::mem;
 use std::path::PathBuf;
 use std::usize;
 
+use transform::MirSource;
+use util::pretty;
+use util::scc;
+
 pub use self::impls::{MaybeStorageLive};
+pub use self::impls::{Def, DefIndex, ReachingDefinitions};
+pub use self::impls::{AvailableExpressions, Expr, ExprIndex, ExprOperand};
+pub use self::impls::{LocalBorrow, LocalBorrowIndex, MaybeObservedLocals};
 pub use self::impls::{MaybeInitializedLvals, MaybeUninitializedLvals};
 pub use self::impls::{DefinitelyInitializedLvals, MovingOutStatements};
 pub use self::impls::EverInitializedLvals;
-pub use self::impls::borrows::{Borrows, BorrowData, BorrowIndex};
+pub use self::impls::borrows::{Borrows, BorrowData, BorrowEnd, BorrowIndex};
+pub use self::impls::borrows::ReserveOrActivateIndex;
 pub(crate) use self::drop_flag_effects::*;
+pub use self::init_results::InitializationResults;
+pub(crate) use self::init_results::InitializationData;
 
 use self::move_paths::MoveData;
 
 mod drop_flag_effects;
 mod graphviz;
 mod impls;
+mod init_results;
 pub mod move_paths;
 
 pub(crate) use self::move_paths::indexes;
@@ -46,6 +57,8 @@ pub(crate) struct DataflowBuilder<'a, 'tcx: 'a, BD> where BD: BitDenotation
     flow_state: DataflowAnalysis<'a, 'tcx, BD>,
     print_preflow_to: Option<String>,
     print_postflow_to: Option<String>,
+    /// Where to render the results under `-Z dump-mir-dataflow`.
+    dump_to: Option<PathBuf>,
 }
 
 pub trait Dataflow<BD: BitDenotation> {
@@ -127,35 +140,93 @@ pub(crate) fn do_dataflow<'a, 'gcx, 'tcx, BD, P>(tcx: TyCtxt<'a, 'gcx, 'tcx>,
     let print_postflow_to =
         name_found(tcx.sess, attributes, "borrowck_graphviz_postflow");
 
+    let dump_to = match tcx.sess.opts.debugging_opts.dump_mir_dataflow {
+        Some(ref name) if name == BD::name() => {
+            let source = MirSource::item(tcx.hir.local_def_id(node_id));
+            Some(pretty::dump_path(tcx, "dot", None, BD::name(), &"dataflow", source))
+        }
+        _ => None,
+    };
+
     let mut mbcx = DataflowBuilder {
         node_id,
         print_preflow_to,
         print_postflow_to,
-        flow_state: DataflowAnalysis::new(tcx, mir, dead_unwinds, bd),
+        dump_to,
+        flow_state: DataflowAnalysis::new(mir, dead_unwinds, bd),
     };
 
     mbcx.dataflow(p);
     mbcx.flow_state.results()
 }
 
+/// Bodies with fewer blocks than this are solved by visiting all their
+/// blocks until nothing changes, which is cheaper for them than finding
+/// the strongly connected components of their CFG first.
+const SCC_SOLVER_MIN_BLOCKS: usize = 32;
+
 struct PropagationContext<'b, 'a: 'b, 'tcx: 'a, O> where O: 'b + BitDenotation
 {
     builder: &'b mut DataflowAnalysis<'a, 'tcx, O>,
     changed: bool,
+    /// The set the state flowing along an edge is computed in.
+    edge: IdxSetBuf<O::Idx>,
+    /// The set the sparse entry sets are loaded into to be updated.
+    entry: IdxSetBuf<O::Idx>,
 }
 
 impl<'a, 'tcx: 'a, BD> DataflowAnalysis<'a, 'tcx, BD> where BD: BitDenotation
 {
     fn propagate(&mut self) {
-        let mut temp = IdxSetBuf::new_empty(self.flow_state.sets.bits_per_block);
+        self.propagate_to_fixpoint();
+        // The entry sets that got dense were updated in place and kept
+        // dense while propagating; store the small ones sparse again.
+        for entry_set in &mut self.flow_state.sets.on_entry_sets {
+            entry_set.compact();
+        }
+    }
+
+    fn propagate_to_fixpoint(&mut self) {
+        let bits_per_block = self.flow_state.sets.bits_per_block;
+        let mut temp = IdxSetBuf::new_empty(bits_per_block);
+        let mir = self.mir;
         let mut propcx = PropagationContext {
             builder: self,
             changed: true,
+            edge: IdxSetBuf::new_empty(bits_per_block),
+            entry: IdxSetBuf::new_empty(bits_per_block),
         };
-        while propcx.changed {
-            propcx.changed = false;
-            propcx.reset(&mut temp);
-            propcx.walk_cfg(&mut temp);
+        if mir.basic_blocks().len() < SCC_SOLVER_MIN_BLOCKS {
+            while propcx.changed {
+                propcx.changed = false;
+                propcx.reset(&mut temp);
+                propcx.walk_cfg(&mut temp);
+            }
+            return;
+        }
+
+        // Solve the strongly connected components one at a time, after the
+        // components they depend on. Only the components with a cycle need
+        // their blocks visited more than once.
+        let mut components = scc::strongly_connected_components(mir);
+        if BD::direction() == Direction::Forward {
+            components.reverse();
+        }
+        for mut component in components {
+            component.sort();
+            if BD::direction() == Direction::Backward {
+                component.reverse();
+            }
+            let is_cyclic = scc::is_cyclic(mir, &component);
+            loop {
+                propcx.changed = false;
+                for &bb in &component {
+                    propcx.propagate_block(bb, &mut temp);
+                }
+                if !is_cyclic || !propcx.changed {
+                    break;
+                }
+            }
         }
     }
 
@@ -166,26 +237,73 @@ impl<'a, 'tcx: 'a, BD> DataflowAnalysis<'a, 'tcx, BD> where BD: BitDenotation
         // directly to gen-sets here). But we still need to figure out
         // the kill-sets.
 
-        {
-            let sets = &mut self.flow_state.sets.for_block(mir::START_BLOCK.index());
-            self.flow_state.operator.start_block_effect(sets);
+        let operator = &self.flow_state.operator;
+        let all_sets = &mut self.flow_state.sets;
+        match BD::direction() {
+            Direction::Forward => {
+                all_sets.update_block(mir::START_BLOCK.index(),
+                                      |sets| operator.start_block_effect(sets));
+            }
+            Direction::Backward => {
+                // A backward analysis starts from the blocks that leave
+                // the function.
+                for (bb, data) in self.mir.basic_blocks().iter_enumerated() {
+                    match data.terminator().kind {
+                        mir::TerminatorKind::Return | mir::TerminatorKind::Resume => {
+                            all_sets.update_block(bb.index(),
+                                                  |sets| operator.start_block_effect(sets));
+                        }
+                        _ => {}
+                    }
+                }
+            }
         }
 
         for (bb, data) in self.mir.basic_blocks().iter_enumerated() {
             let &mir::BasicBlockData { ref statements, ref terminator, is_cleanup: _ } = data;
 
-            let sets = &mut self.flow_state.sets.for_block(bb.index());
-            for j_stmt in 0..statements.len() {
-                let location = Location { block: bb, statement_index: j_stmt };
-                self.flow_state.operator.statement_effect(sets, location);
-            }
-
-            if terminator.is_some() {
-                let location = Location { block: bb, statement_index: statements.len() };
-                self.flow_state.operator.terminator_effect(sets, location);
-            }
+            all_sets.update_block(bb.index(), |sets| {
+                match BD::direction() {
+                    Direction::Forward => {
+                        for j_stmt in 0..statements.len() {
+                            let location = Location { block: bb, statement_index: j_stmt };
+                            operator.statement_effect(sets, location);
+                        }
+
+                        if terminator.is_some() {
+                            let location = Location {
+                                block: bb,
+                                statement_index: statements.len(),
+                            };
+                            operator.terminator_effect(sets, location);
+                        }
+                    }
+                    Direction::Backward => {
+                        if terminator.is_some() {
+                            let location = Location {
+                                block: bb,
+                                statement_index: statements.len(),
+                            };
+                            operator.terminator_effect(sets, location);
+                        }
+
+                        for j_stmt in (0..statements.len()).rev() {
+                            let location = Location { block: bb, statement_index: j_stmt };
+                            operator.statement_effect(sets, location);
+                        }
+                    }
+                }
+            });
         }
     }
+
+    /// Builds the sets and runs the analysis to a fixed point, without
+    /// the graphviz instrumentation of `do_dataflow`.
+    pub fn run(mut self) -> DataflowResults<BD> {
+        self.build_sets();
+        self.propagate();
+        self.results()
+    }
 }
 
 impl<'b, 'a: 'b, 'tcx: 'a, BD> PropagationContext<'b, 'a, 'tcx, BD> where BD: BitDenotation
@@ -197,23 +315,74 @@ impl<'b, 'a: 'b, 'tcx: 'a, BD> PropagationContext<'b, 'a, 'tcx, BD> where BD: Bi
         }
     }
 
+    /// Visits every block once, in index order for a forward analysis and
+    /// in reverse for a backward one, so that most blocks see their
+    /// predecessors, or successors, updated within the same round.
     fn walk_cfg(&mut self, in_out: &mut IdxSet<BD::Idx>) {
+        let blocks = self.builder.mir.basic_blocks().indices();
+        match BD::direction() {
+            Direction::Forward => {
+                for bb in blocks {
+                    self.propagate_block(bb, in_out);
+                }
+            }
+            Direction::Backward => {
+                for bb in blocks.rev() {
+                    self.propagate_block(bb, in_out);
+                }
+            }
+        }
+    }
+
+    /// Propagates the state on exit from `bb` into the sets of its
+    /// successors. In a backward analysis, pulls the state on entry to the
+    /// successors of `bb` into its set instead, which holds its state on
+    /// exit.
+    fn propagate_block(&mut self, bb: BasicBlock, in_out: &mut IdxSet<BD::Idx>) {
         let mir = self.builder.mir;
-        for (bb_idx, bb_data) in mir.basic_blocks().iter().enumerate() {
-            let builder = &mut self.builder;
-            {
-                let sets = builder.flow_state.sets.for_block(bb_idx);
-                debug_assert!(in_out.words().len() == sets.on_entry.words().len());
-                in_out.clone_from(sets.on_entry);
-                in_out.union(sets.gen_set);
-                in_out.subtract(sets.kill_set);
+        let bb_data = &mir[bb];
+        let builder = &mut self.builder;
+        let entry = &mut self.entry;
+        match BD::direction() {
+            Direction::Forward => {
+                builder.flow_state.sets.load_exit_state(bb.index(), in_out);
+                let dead_unwinds = builder.dead_unwinds;
+                builder.flow_state.propagate_bits_into_graph_successors_of(
+                    dead_unwinds, in_out, &mut self.edge, entry, &mut self.changed,
+                    (bb, bb_data));
+            }
+            Direction::Backward => {
+                let terminator = bb_data.terminator();
+                let dead_unwind = if builder.dead_unwinds.contains(&bb) {
+                    unwind_target(terminator)
+                } else {
+                    None
+                };
+                for (index, &succ) in terminator.successors().iter().enumerate() {
+                    if Some(succ) == dead_unwind {
+                        continue;
+                    }
+                    builder.flow_state.sets.load_exit_state(succ.index(), in_out);
+                    builder.flow_state.operator.successor_effect(in_out, bb, terminator, index);
+                    builder.flow_state.propagate_bits_into_entry_set_for(
+                        in_out, entry, &mut self.changed, &bb);
+                }
             }
-            builder.propagate_bits_into_graph_successors_of(
-                in_out, &mut self.changed, (mir::BasicBlock::new(bb_idx), bb_data));
         }
     }
 }
 
+/// The block a terminator jumps to when unwinding, if any.
+fn unwind_target(terminator: &Terminator) -> Option<BasicBlock> {
+    match terminator.kind {
+        mir::TerminatorKind::Assert { cleanup: unwind, .. } |
+        mir::TerminatorKind::Call { cleanup: unwind, .. } |
+        mir::TerminatorKind::Drop { unwind, .. } |
+        mir::TerminatorKind::DropAndReplace { unwind, .. } => unwind,
+        _ => None,
+    }
+}
+
 fn dataflow_path(context: &str, prepost: &str, path: &str) -> PathBuf {
     format!("{}_{}", context, prepost);
     let mut path = PathBuf::from(path);
@@ -232,7 +401,7 @@ impl<'a, 'tcx: 'a, BD> DataflowBuilder<'a, 'tcx, BD> where BD: BitDenotation
     {
         if let Some(ref path_str) = self.print_preflow_to {
             let path = dataflow_path(BD::name(), "preflow", path_str);
-            graphviz::print_borrowck_graph_to(self, &path, p)
+            graphviz::print_dataflow_graph_to(self, &path, p)
         } else {
             Ok(())
         }
@@ -243,26 +412,12 @@ impl<'a, 'tcx: 'a, BD> DataflowBuilder<'a, 'tcx, BD> where BD: BitDenotation
     {
         if let Some(ref path_str) = self.print_postflow_to {
             let path = dataflow_path(BD::name(), "postflow", path_str);
-            graphviz::print_borrowck_graph_to(self, &path, p)
-        } else{
-            Ok(())
+            graphviz::print_dataflow_graph_to(self, &path, &p)?;
         }
-    }
-}
-
-/// Maps each block to a set of bits
-#[derive(Debug)]
-struct Bits<E:Idx> {
-    bits: IdxSetBuf<E>,
-}
-
-impl<E:Idx> Clone for Bits<E> {
-    fn clone(&self) -> Self { Bits { bits: self.bits.clone() } }
-}
-
-impl<E:Idx> Bits<E> {
-    fn new(bits: IdxSetBuf<E>) -> Self {
-        Bits { bits: bits }
+        if let Some(ref path) = self.dump_to {
+            graphviz::print_dataflow_graph_to(self, path, &p)?;
+        }
+        Ok(())
     }
 }
 
@@ -353,11 +508,15 @@ pub trait DataflowResultsConsumer<'a, 'tcx: 'a> {
                           flow_state: &mut Self::FlowState);
 }
 
-pub fn state_for_location<T: BitDenotation>(loc: Location,
-                                            analysis: &T,
-                                            result: &DataflowResults<T>)
+/// Returns the state right before the effect of the statement or
+/// terminator at `loc`, in the direction of the analysis: before it is
+/// executed for a forward analysis, and after it is for a backward one.
+pub fn state_for_location<'tcx, T: BitDenotation>(loc: Location,
+                                                  analysis: &T,
+                                                  result: &DataflowResults<T>,
+                                                  mir: &Mir<'tcx>)
     -> IdxSetBuf<T::Idx> {
-    let mut entry = result.sets().on_entry_set_for(loc.block.index()).to_owned();
+    let mut entry = result.sets().on_entry_set_for(loc.block.index());
 
     {
         let mut sets = BlockSets {
@@ -366,10 +525,27 @@ pub fn state_for_location<T: BitDenotation>(loc: Location,
             gen_set: &mut entry,
         };
 
-        for stmt in 0..loc.statement_index {
-            let mut stmt_loc = loc;
-            stmt_loc.statement_index = stmt;
-            analysis.statement_effect(&mut sets, stmt_loc);
+        match T::direction() {
+            Direction::Forward => {
+                for stmt in 0..loc.statement_index {
+                    let mut stmt_loc = loc;
+                    stmt_loc.statement_index = stmt;
+                    analysis.statement_effect(&mut sets, stmt_loc);
+                }
+            }
+            Direction::Backward => {
+                let num_statements = mir[loc.block].statements.len();
+                if loc.statement_index < num_statements {
+                    let mut term_loc = loc;
+                    term_loc.statement_index = num_statements;
+                    analysis.terminator_effect(&mut sets, term_loc);
+                }
+                for stmt in (loc.statement_index + 1..num_statements).rev() {
+                    let mut stmt_loc = loc;
+                    stmt_loc.statement_index = stmt;
+                    analysis.statement_effect(&mut sets, stmt_loc);
+                }
+            }
         }
     }
 
@@ -402,6 +578,11 @@ impl<O: BitDenotation> DataflowResults<O> {
     pub fn operator(&self) -> &O {
         &self.0.operator
     }
+
+    /// Drops the operator, which may borrow the MIR, keeping the sets.
+    pub fn into_sets(self) -> AllSets<O::Idx> {
+        self.0.sets
+    }
 }
 
 /// State of a dataflow analysis; couples a collection of bit sets
@@ -439,31 +620,38 @@ impl<O: BitDenotation> DataflowState<O> {
     }
 }
 
+/// The sets of every block.
+///
+/// Most blocks only gen or kill a handful of bits, and most entry sets of
+/// some analyses, like the initialized lvalues, are small too, so the sets
+/// are stored as `HybridIdxSetBuf`s, which only take a bit per element of
+/// the universe when they hold many elements. They are loaded into dense
+/// sets to be updated.
 #[derive(Debug)]
 pub struct AllSets<E: Idx> {
     /// Analysis bitwidth for each block.
     bits_per_block: usize,
 
-    /// Number of words associated with each block entry
-    /// equal to bits_per_block / usize::BITS, rounded up.
-    words_per_block: usize,
-
     /// For each block, bits generated by executing the statements in
     /// the block. (For comparison, the Terminator for each block is
     /// handled in a flow-specific manner during propagation.)
-    gen_sets: Bits<E>,
+    gen_sets: Vec<HybridIdxSetBuf<E>>,
 
     /// For each block, bits killed by executing the statements in the
     /// block. (For comparison, the Terminator for each block is
     /// handled in a flow-specific manner during propagation.)
-    kill_sets: Bits<E>,
+    kill_sets: Vec<HybridIdxSetBuf<E>>,
 
     /// For each block, bits valid on entry to the block.
-    on_entry_sets: Bits<E>,
+    on_entry_sets: Vec<HybridIdxSetBuf<E>>,
 }
 
 /// Triple of sets associated with a given block.
 ///
+/// For a backward analysis, `on_entry` holds the state on exit from the
+/// block, which is where the analysis enters it, and `gen_set` and
+/// `kill_set` the effect of the block executed from its terminator up.
+///
 /// Generally, one sets up `on_entry`, `gen_set`, and `kill_set` for
 /// each block individually, and then runs the dataflow analysis which
 /// iteratively modifies the various `on_entry` sets (but leaves the
@@ -488,11 +676,11 @@ pub struct BlockSets<'a, E: Idx> {
 }
 
 impl<'a, E:Idx> BlockSets<'a, E> {
-    fn gen(&mut self, e: &E) {
+    pub(crate) fn gen(&mut self, e: &E) {
         self.gen_set.add(e);
         self.kill_set.remove(e);
     }
-    fn kill(&mut self, e: &E) {
+    pub(crate) fn kill(&mut self, e: &E) {
         self.gen_set.remove(e);
         self.kill_set.add(e);
     }
@@ -500,32 +688,57 @@ impl<'a, E:Idx> BlockSets<'a, E> {
 
 impl<E:Idx> AllSets<E> {
     pub fn bits_per_block(&self) -> usize { self.bits_per_block }
-    pub fn for_block(&mut self, block_idx: usize) -> BlockSets<E> {
-        let offset = self.words_per_block * block_idx;
-        let range = E::new(offset)..E::new(offset + self.words_per_block);
-        BlockSets {
-            on_entry: self.on_entry_sets.bits.range_mut(&range),
-            gen_set: self.gen_sets.bits.range_mut(&range),
-            kill_set: self.kill_sets.bits.range_mut(&range),
-        }
+
+    /// Loads the sets of block `block_idx` into dense sets, lets `f`
+    /// update them, and stores them back.
+    pub fn update_block<F>(&mut self, block_idx: usize, f: F)
+        where F: FnOnce(&mut BlockSets<E>)
+    {
+        let mut on_entry = self.on_entry_sets[block_idx].to_dense();
+        let mut gen_set = self.gen_sets[block_idx].to_dense();
+        let mut kill_set = self.kill_sets[block_idx].to_dense();
+        f(&mut BlockSets {
+            on_entry: &mut on_entry,
+            gen_set: &mut gen_set,
+            kill_set: &mut kill_set,
+        });
+        self.on_entry_sets[block_idx].store(&on_entry);
+        self.gen_sets[block_idx].store(&gen_set);
+        self.kill_sets[block_idx].store(&kill_set);
     }
 
-    fn lookup_set_for<'a>(&self, sets: &'a Bits<E>, block_idx: usize) -> &'a IdxSet<E> {
-        let offset = self.words_per_block * block_idx;
-        let range = E::new(offset)..E::new(offset + self.words_per_block);
-        sets.bits.range(&range)
+    /// Overwrites `in_out` with the state of block `block_idx` with its
+    /// gen and kill sets applied.
+    fn load_exit_state(&self, block_idx: usize, in_out: &mut IdxSet<E>) {
+        debug_assert!(in_out.words().len() * mem::size_of::<usize>() * 8 == self.bits_per_block);
+        self.on_entry_sets[block_idx].load_into(in_out);
+        self.gen_sets[block_idx].union_into(in_out);
+        self.kill_sets[block_idx].subtract_from(in_out);
     }
-    pub fn gen_set_for(&self, block_idx: usize) -> &IdxSet<E> {
-        self.lookup_set_for(&self.gen_sets, block_idx)
+
+    pub fn gen_set_for(&self, block_idx: usize) -> IdxSetBuf<E> {
+        self.gen_sets[block_idx].to_dense()
     }
-    pub fn kill_set_for(&self, block_idx: usize) -> &IdxSet<E> {
-        self.lookup_set_for(&self.kill_sets, block_idx)
+    pub fn kill_set_for(&self, block_idx: usize) -> IdxSetBuf<E> {
+        self.kill_sets[block_idx].to_dense()
     }
-    pub fn on_entry_set_for(&self, block_idx: usize) -> &IdxSet<E> {
-        self.lookup_set_for(&self.on_entry_sets, block_idx)
+    pub fn on_entry_set_for(&self, block_idx: usize) -> IdxSetBuf<E> {
+        self.on_entry_sets[block_idx].to_dense()
     }
 }
 
+/// The direction in which a dataflow analysis propagates its state.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// From the start block towards the exits, merging the states of
+    /// the predecessors of each block.
+    Forward,
+
+    /// From the blocks that return or resume unwinding towards the start
+    /// block, merging the states of the successors of each block.
+    Backward,
+}
+
 /// Parameterization for the precise form of data flow that is used.
 pub trait DataflowOperator: BitwiseOperator {
     /// Specifies the initial value for each bit in the `on_entry` set
@@ -545,6 +758,11 @@ pub trait BitDenotation: DataflowOperator {
     /// plugged into a filename.
     fn name() -> &'static str;
 
+    /// The direction of the analysis. A backward analysis applies the
+    /// effect of the terminator of each block first, followed by those
+    /// of its statements in reverse order.
+    fn direction() -> Direction { Direction::Forward }
+
     /// Size of each bitvector allocated for each block in the analysis.
     fn bits_per_block(&self) -> usize;
 
@@ -554,6 +772,10 @@ pub trait BitDenotation: DataflowOperator {
     ///
     /// (For example, establishing the call arguments.)
     ///
+    /// For a backward analysis, this is called on every block that
+    /// returns or resumes unwinding instead, with the effects
+    /// established *after* leaving the function.
+    ///
     /// (Typically this should only modify `sets.on_entry`, since the
     /// gen and kill sets should reflect the effects of *executing*
     /// the start block itself.)
@@ -582,11 +804,38 @@ pub trait BitDenotation: DataflowOperator {
     /// block, represented via GEN and KILL sets.
     ///
     /// The effects applied here cannot depend on which branch the
-    /// terminator took.
+    /// terminator took; those belong in `successor_effect`.
     fn terminator_effect(&self,
                          sets: &mut BlockSets<Self::Idx>,
                          location: Location);
 
+    /// Mutates `in_out`, the exit flow-state of `bb`, into the state
+    /// flowing along the edge to the `successor_index`th successor of
+    /// its terminator, for the effects that only hold on some of the
+    /// edges: a call initializes its destination only when it returns,
+    /// a switch on a discriminant picks a different variant on each
+    /// edge, an assert only holds on its success edge.
+    ///
+    /// In a backward analysis, `in_out` is the entry flow-state of the
+    /// successor instead, and is mutated into what that edge contributes
+    /// to the exit flow-state of `bb`.
+    ///
+    /// By default, this applies `propagate_call_return` on the edge a
+    /// call returns along.
+    fn successor_effect(&self,
+                        in_out: &mut IdxSet<Self::Idx>,
+                        bb: mir::BasicBlock,
+                        terminator: &mir::Terminator,
+                        successor_index: usize) {
+        if let mir::TerminatorKind::Call {
+            destination: Some((ref dest_lval, dest_bb)), ..
+        } = terminator.kind {
+            if terminator.successors()[successor_index] == dest_bb {
+                self.propagate_call_return(in_out, bb, dest_bb, dest_lval);
+            }
+        }
+    }
+
     /// Mutates the block-sets according to the (flow-dependent)
     /// effect of a successful return from a Call terminator.
     ///
@@ -595,12 +844,14 @@ pub trait BitDenotation: DataflowOperator {
     /// called on the exit flow-state of BB_x in order to set up the
     /// entry flow-state of BB_y.
     ///
-    /// This is used, in particular, as a special case during the
-    /// "propagate" loop where all of the basic blocks are repeatedly
-    /// visited. Since the effects of a Call terminator are
-    /// flow-dependent, the current MIR cannot encode them via just
-    /// GEN and KILL sets attached to the block, and so instead we add
-    /// this extra machinery to represent the flow-dependent effect.
+    /// This is called by the default `successor_effect`. Since the
+    /// effects of a Call terminator are flow-dependent, the current
+    /// MIR cannot encode them via just GEN and KILL sets attached to
+    /// the block, and so instead we add this extra machinery to
+    /// represent the flow-dependent effect.
+    ///
+    /// In a backward analysis, this is called on the entry flow-state of
+    /// BB_y in order to set up the exit flow-state of BB_x instead.
     ///
     /// FIXME: Right now this is a bit of a wart in the API. It might
     /// be better to represent this as an additional gen- and
@@ -613,10 +864,9 @@ pub trait BitDenotation: DataflowOperator {
                              dest_lval: &mir::Lvalue);
 }
 
-impl<'a, 'gcx, 'tcx: 'a, D> DataflowAnalysis<'a, 'tcx, D> where D: BitDenotation
+impl<'a, 'tcx: 'a, D> DataflowAnalysis<'a, 'tcx, D> where D: BitDenotation
 {
-    pub fn new(_tcx: TyCtxt<'a, 'gcx, 'tcx>,
-               mir: &'a Mir<'tcx>,
+    pub fn new(mir: &'a Mir<'tcx>,
                dead_unwinds: &'a IdxSet<mir::BasicBlock>,
                denotation: D) -> Self {
         let bits_per_block = denotation.bits_per_block();
@@ -627,14 +877,13 @@ impl<'a, 'gcx, 'tcx: 'a, D> DataflowAnalysis<'a, 'tcx, D> where D: BitDenotation
         let bits_per_block = words_per_block * usize_bits;
 
         let num_blocks = mir.basic_blocks().len();
-        let num_overall = num_blocks * bits_per_block;
 
-        let zeroes = Bits::new(IdxSetBuf::new_empty(num_overall));
-        let on_entry = Bits::new(if D::bottom_value() {
-            IdxSetBuf::new_filled(num_overall)
+        let zeroes = vec![HybridIdxSetBuf::new_empty(bits_per_block); num_blocks];
+        let on_entry = vec![if D::bottom_value() {
+            HybridIdxSetBuf::new_filled(bits_per_block)
         } else {
-            IdxSetBuf::new_empty(num_overall)
-        });
+            HybridIdxSetBuf::new_empty(bits_per_block)
+        }; num_blocks];
 
         DataflowAnalysis {
             mir,
@@ -642,7 +891,6 @@ impl<'a, 'gcx, 'tcx: 'a, D> DataflowAnalysis<'a, 'tcx, D> where D: BitDenotation
             flow_state: DataflowState {
                 sets: AllSets {
                     bits_per_block,
-                    words_per_block,
                     gen_sets: zeroes.clone(),
                     kill_sets: zeroes,
                     on_entry_sets: on_entry,
@@ -654,93 +902,172 @@ impl<'a, 'gcx, 'tcx: 'a, D> DataflowAnalysis<'a, 'tcx, D> where D: BitDenotation
     }
 }
 
-impl<'a, 'tcx: 'a, D> DataflowAnalysis<'a, 'tcx, D> where D: BitDenotation
-{
+impl<D: BitDenotation> DataflowState<D> {
     /// Propagates the bits of `in_out` into all the successors of `bb`,
     /// using bitwise operator denoted by `self.operator`.
     ///
-    /// For most blocks, this is entirely uniform. However, for blocks
-    /// that end with a call terminator, the effect of the call on the
-    /// dataflow state may depend on whether the call returned
-    /// successfully or unwound.
-    ///
-    /// To reflect this, the `propagate_call_return` method of the
-    /// `BitDenotation` mutates `in_out` when propagating `in_out` via
-    /// a call terminator; such mutation is performed *last*, to
-    /// ensure its side-effects do not leak elsewhere (e.g. into
-    /// unwind target).
+    /// The effect of the terminator on the dataflow state may depend on
+    /// the edge taken, e.g. a call initializes its destination only
+    /// when it returns successfully. So each successor gets a copy of
+    /// `in_out` in `edge`, with the `successor_effect` of its edge
+    /// applied, which keeps the effect of an edge from leaking into the
+    /// others (e.g. into the unwind target). Unwind edges of the blocks in
+    /// `dead_unwinds` are skipped.
     fn propagate_bits_into_graph_successors_of(
         &mut self,
-        in_out: &mut IdxSet<D::Idx>,
+        dead_unwinds: &IdxSet<mir::BasicBlock>,
+        in_out: &IdxSet<D::Idx>,
+        edge: &mut IdxSet<D::Idx>,
+        entry: &mut IdxSet<D::Idx>,
         changed: &mut bool,
         (bb, bb_data): (mir::BasicBlock, &mir::BasicBlockData))
     {
-        match bb_data.terminator().kind {
-            mir::TerminatorKind::Return |
-            mir::TerminatorKind::Resume |
-            mir::TerminatorKind::GeneratorDrop |
-            mir::TerminatorKind::Unreachable => {}
-            mir::TerminatorKind::Goto { ref target } |
-            mir::TerminatorKind::Assert { ref target, cleanup: None, .. } |
-            mir::TerminatorKind::Yield { resume: ref target, drop: None, .. } |
-            mir::TerminatorKind::Drop { ref target, location: _, unwind: None } |
-            mir::TerminatorKind::DropAndReplace {
-                ref target, value: _, location: _, unwind: None
-            } => {
-                self.propagate_bits_into_entry_set_for(in_out, changed, target);
-            }
-            mir::TerminatorKind::Yield { resume: ref target, drop: Some(ref drop), .. } => {
-                self.propagate_bits_into_entry_set_for(in_out, changed, target);
-                self.propagate_bits_into_entry_set_for(in_out, changed, drop);
-            }
-            mir::TerminatorKind::Assert { ref target, cleanup: Some(ref unwind), .. } |
-            mir::TerminatorKind::Drop { ref target, location: _, unwind: Some(ref unwind) } |
-            mir::TerminatorKind::DropAndReplace {
-                ref target, value: _, location: _, unwind: Some(ref unwind)
-            } => {
-                self.propagate_bits_into_entry_set_for(in_out, changed, target);
-                if !self.dead_unwinds.contains(&bb) {
-                    self.propagate_bits_into_entry_set_for(in_out, changed, unwind);
-                }
-            }
-            mir::TerminatorKind::SwitchInt { ref targets, .. } => {
-                for target in targets {
-                    self.propagate_bits_into_entry_set_for(in_out, changed, target);
-                }
-            }
-            mir::TerminatorKind::Call { ref cleanup, ref destination, func: _, args: _ } => {
-                if let Some(ref unwind) = *cleanup {
-                    if !self.dead_unwinds.contains(&bb) {
-                        self.propagate_bits_into_entry_set_for(in_out, changed, unwind);
-                    }
-                }
-                if let Some((ref dest_lval, ref dest_bb)) = *destination {
-                    // N.B.: This must be done *last*, after all other
-                    // propagation, as documented in comment above.
-                    self.flow_state.operator.propagate_call_return(
-                        in_out, bb, *dest_bb, dest_lval);
-                    self.propagate_bits_into_entry_set_for(in_out, changed, dest_bb);
-                }
-            }
-            mir::TerminatorKind::FalseEdges { ref real_target, ref imaginary_targets } => {
-                self.propagate_bits_into_entry_set_for(in_out, changed, real_target);
-                for target in imaginary_targets {
-                    self.propagate_bits_into_entry_set_for(in_out, changed, target);
-                }
+        let terminator = bb_data.terminator();
+        let dead_unwind = if dead_unwinds.contains(&bb) {
+            unwind_target(terminator)
+        } else {
+            None
+        };
+        for (index, &target) in terminator.successors().iter().enumerate() {
+            if Some(target) == dead_unwind {
+                continue;
             }
+            edge.clone_from(in_out);
+            self.operator.successor_effect(edge, bb, terminator, index);
+            self.propagate_bits_into_entry_set_for(edge, entry, changed, &target);
         }
     }
 
+    /// Joins `in_out` into the entry set of `bb`, loading it into `entry`
+    /// if it is sparse.
     fn propagate_bits_into_entry_set_for(&mut self,
                                          in_out: &IdxSet<D::Idx>,
+                                         entry: &mut IdxSet<D::Idx>,
                                          changed: &mut bool,
                                          bb: &mir::BasicBlock) {
-        let entry_set = self.flow_state.sets.for_block(bb.index()).on_entry;
-        let set_changed = bitwise(entry_set.words_mut(),
-                                  in_out.words(),
-                                  &self.flow_state.operator);
-        if set_changed {
+        let operator = &self.operator;
+        let entry_set = &mut self.sets.on_entry_sets[bb.index()];
+        if entry_set.update(entry, |set| bitwise(set.words_mut(), in_out.words(), operator)) {
             *changed = true;
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustc::mir::{Local, Lvalue, SourceInfo, TerminatorKind, ARGUMENT_VISIBILITY_SCOPE};
+    use syntax_pos::DUMMY_SP;
+
+    /// An analysis whose only effect is that of the edges of terminators:
+    /// the `i`th edge of a terminator sets the bit `i`.
+    struct EdgeBits;
+
+    impl BitwiseOperator for EdgeBits {
+        fn join(&self, pred1: usize, pred2: usize) -> usize {
+            pred1 | pred2
+        }
+    }
+
+    impl DataflowOperator for EdgeBits {
+        fn bottom_value() -> bool {
+            false
+        }
+    }
+
+    impl BitDenotation for EdgeBits {
+        type Idx = usize;
+        fn name() -> &'static str { "edge_bits" }
+        fn bits_per_block(&self) -> usize { 64 }
+        fn start_block_effect(&self, _sets: &mut BlockSets<usize>) {}
+        fn statement_effect(&self, _sets: &mut BlockSets<usize>, _location: Location) {}
+        fn terminator_effect(&self, _sets: &mut BlockSets<usize>, _location: Location) {}
+
+        fn successor_effect(&self,
+                            in_out: &mut IdxSet<usize>,
+                            _bb: BasicBlock,
+                            _terminator: &Terminator,
+                            successor_index: usize) {
+            in_out.add(&successor_index);
+        }
+
+        fn propagate_call_return(&self,
+                                 _in_out: &mut IdxSet<usize>,
+                                 _call_bb: BasicBlock,
+                                 _dest_bb: BasicBlock,
+                                 _dest_lval: &Lvalue) {}
+    }
+
+    fn state(num_blocks: usize) -> DataflowState<EdgeBits> {
+        let empty = vec![HybridIdxSetBuf::new_empty(64); num_blocks];
+        DataflowState {
+            sets: AllSets {
+                bits_per_block: 64,
+                gen_sets: empty.clone(),
+                kill_sets: empty.clone(),
+                on_entry_sets: empty,
+            },
+            operator: EdgeBits,
+        }
+    }
+
+    /// A block dropping a local, which goes to `bb1`, or to `bb2` when
+    /// the drop unwinds.
+    fn drop_block() -> BasicBlockData<'static> {
+        let source_info = SourceInfo { span: DUMMY_SP, scope: ARGUMENT_VISIBILITY_SCOPE };
+        BasicBlockData::new(Some(Terminator {
+            source_info,
+            kind: TerminatorKind::Drop {
+                location: Lvalue::Local(Local::new(1)),
+                target: BasicBlock::new(1),
+                unwind: Some(BasicBlock::new(2)),
+            },
+        }))
+    }
+
+    fn entry_bits(state: &DataflowState<EdgeBits>, block: usize) -> Vec<usize> {
+        state.sets.on_entry_set_for(block).iter().collect()
+    }
+
+    #[test]
+    fn edges_get_their_own_effect() {
+        let mut state = state(3);
+        let dead_unwinds: IdxSetBuf<BasicBlock> = IdxSetBuf::new_empty(3);
+        let in_out: IdxSetBuf<usize> = IdxSetBuf::new_empty(64);
+        // Scratch sets left holding bits that must not leak into any edge.
+        let mut edge: IdxSetBuf<usize> = IdxSetBuf::new_filled(64);
+        let mut entry: IdxSetBuf<usize> = IdxSetBuf::new_filled(64);
+        let mut changed = false;
+        state.propagate_bits_into_graph_successors_of(
+            &dead_unwinds, &in_out, &mut edge, &mut entry, &mut changed,
+            (BasicBlock::new(0), &drop_block()));
+        assert!(changed);
+        assert_eq!(entry_bits(&state, 0), Vec::<usize>::new());
+        assert_eq!(entry_bits(&state, 1), [0]);
+        assert_eq!(entry_bits(&state, 2), [1]);
+
+        // Propagating the same state again changes nothing.
+        changed = false;
+        state.propagate_bits_into_graph_successors_of(
+            &dead_unwinds, &in_out, &mut edge, &mut entry, &mut changed,
+            (BasicBlock::new(0), &drop_block()));
+        assert!(!changed);
+    }
+
+    #[test]
+    fn dead_unwind_edges_are_skipped() {
+        let mut state = state(3);
+        let mut dead_unwinds: IdxSetBuf<BasicBlock> = IdxSetBuf::new_empty(3);
+        dead_unwinds.add(&BasicBlock::new(0));
+        let mut in_out: IdxSetBuf<usize> = IdxSetBuf::new_empty(64);
+        in_out.add(&7);
+        let mut edge: IdxSetBuf<usize> = IdxSetBuf::new_empty(64);
+        let mut entry: IdxSetBuf<usize> = IdxSetBuf::new_empty(64);
+        let mut changed = false;
+        state.propagate_bits_into_graph_successors_of(
+            &dead_unwinds, &in_out, &mut edge, &mut entry, &mut changed,
+            (BasicBlock::new(0), &drop_block()));
+        assert_eq!(entry_bits(&state, 1), [0, 7]);
+        assert_eq!(entry_bits(&state, 2), Vec::<usize>::new());
+    }
+}
@@ -110,7 +110,7 @@ impl<'b, 'a, 'gcx, 'tcx> Gatherer<'b, 'a, 'gcx, 'tcx> {
             Lvalue::Local(local) => Ok(self.builder.data.rev_lookup.locals[local]),
             Lvalue::Static(..) => {
                 let span = self.builder.mir.source_info(self.loc).span;
-                Err(MoveError::cannot_move_out_of(span, Static))
+                Err(MoveError::cannot_move_out_of(self.loc, span, Static))
             }
             Lvalue::Projection(ref proj) => {
                 self.move_path_for_projection(lval, proj)
@@ -135,10 +135,12 @@ impl<'b, 'a, 'gcx, 'tcx> Gatherer<'b, 'a, 'gcx, 'tcx> {
         let lv_ty = proj.base.ty(mir, tcx).to_ty(tcx);
         match lv_ty.sty {
             ty::TyRef(..) | ty::TyRawPtr(..) =>
-                return Err(MoveError::cannot_move_out_of(mir.source_info(self.loc).span,
+                return Err(MoveError::cannot_move_out_of(self.loc,
+                                                         mir.source_info(self.loc).span,
                                                          BorrowedContent)),
             ty::TyAdt(adt, _) if adt.has_dtor(tcx) && !adt.is_box() =>
-                return Err(MoveError::cannot_move_out_of(mir.source_info(self.loc).span,
+                return Err(MoveError::cannot_move_out_of(self.loc,
+                                                         mir.source_info(self.loc).span,
                                                          InteriorOfTypeWithDestructor {
                     container_ty: lv_ty
                 })),
@@ -147,6 +149,7 @@ impl<'b, 'a, 'gcx, 'tcx> Gatherer<'b, 'a, 'gcx, 'tcx> {
                 return Err(MoveError::UnionMove { path: base }),
             ty::TySlice(_) =>
                 return Err(MoveError::cannot_move_out_of(
+                    self.loc,
                     mir.source_info(self.loc).span,
                     InteriorOfSliceOrArray {
                         ty: lv_ty, is_index: match proj.elem {
@@ -157,6 +160,7 @@ impl<'b, 'a, 'gcx, 'tcx> Gatherer<'b, 'a, 'gcx, 'tcx> {
             ty::TyArray(..) => match proj.elem {
                 ProjectionElem::Index(..) =>
                     return Err(MoveError::cannot_move_out_of(
+                        self.loc,
                         mir.source_info(self.loc).span,
                         InteriorOfSliceOrArray {
                             ty: lv_ty, is_index: true
@@ -286,6 +290,10 @@ impl<'b, 'a, 'gcx, 'tcx> Gatherer<'b, 'a, 'gcx, 'tcx> {
                 span_bug!(stmt.source_info.span,
                           "SetDiscriminant should not exist during borrowck");
             }
+            StatementKind::Assume(..) => {
+                span_bug!(stmt.source_info.span,
+                          "Assume should not exist during borrowck");
+            }
             StatementKind::InlineAsm { .. } |
             StatementKind::EndRegion(_) |
             StatementKind::Validate(..) |
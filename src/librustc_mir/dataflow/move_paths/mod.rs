@@ -65,6 +65,19 @@ pub(crate) mod indexes {
 
     /// Index into Borrows.locations
     new_index!(BorrowIndex, "bw");
+
+    /// Index into the Borrows dataflow bits, two per borrow: one that is
+    /// set while the borrow is reserved, and one while it is active.
+    new_index!(ReserveOrActivateIndex, "ra");
+
+    /// Index into ReachingDefinitions.defs
+    new_index!(DefIndex, "df");
+
+    /// Index into AvailableExpressions.exprs
+    new_index!(ExprIndex, "ex");
+
+    /// Index into MaybeObservedLocals.borrows
+    new_index!(LocalBorrowIndex, "lb");
 }
 
 pub use self::indexes::MovePathIndex;
@@ -267,6 +280,7 @@ impl<'tcx> MovePathLookup<'tcx> {
 
 #[derive(Debug)]
 pub struct IllegalMoveOrigin<'tcx> {
+    pub(crate) location: Location,
     pub(crate) span: Span,
     pub(crate) kind: IllegalMoveOriginKind<'tcx>,
 }
@@ -286,8 +300,10 @@ pub enum MoveError<'tcx> {
 }
 
 impl<'tcx> MoveError<'tcx> {
-    fn cannot_move_out_of(span: Span, kind: IllegalMoveOriginKind<'tcx>) -> Self {
-        let origin = IllegalMoveOrigin { span, kind };
+    fn cannot_move_out_of(location: Location,
+                          span: Span,
+                          kind: IllegalMoveOriginKind<'tcx>) -> Self {
+        let origin = IllegalMoveOrigin { location, span, kind };
         MoveError::IllegalMove { cannot_move_out_of: origin }
     }
 }
@@ -119,9 +119,15 @@ fn apply_adjustment<'a, 'gcx, 'tcx>(cx: &mut Cx<'a, 'gcx, 'tcx>,
             overloaded_lvalue(cx, hir_expr, adjustment.target, Some(call), vec![expr.to_ref()])
         }
         Adjust::Borrow(AutoBorrow::Ref(r, m)) => {
+            let borrow_kind = match m {
+                hir::MutMutable => BorrowKind::Mut {
+                    allow_two_phase_borrow: is_method_receiver(cx, hir_expr)
+                },
+                hir::MutImmutable => BorrowKind::Shared,
+            };
             ExprKind::Borrow {
                 region: r,
-                borrow_kind: to_borrow_kind(m),
+                borrow_kind,
                 arg: expr.to_ref(),
             }
         }
@@ -508,10 +514,11 @@ fn make_mirror_unadjusted<'a, 'gcx, 'tcx>(cx: &mut Cx<'a, 'gcx, 'tcx>,
                     bug!("invalid loop id for continue: {}", err)
             }
         }
-        hir::ExprMatch(ref discr, ref arms, _) => {
+        hir::ExprMatch(ref discr, ref arms, source) => {
             ExprKind::Match {
                 discriminant: discr.to_ref(),
                 arms: arms.iter().map(|a| convert_arm(cx, a)).collect(),
+                source,
             }
         }
         hir::ExprIf(ref cond, ref then, ref otherwise) => {
@@ -610,9 +617,21 @@ fn method_callee<'a, 'gcx, 'tcx>(cx: &mut Cx<'a, 'gcx, 'tcx>,
     }
 }
 
+/// Whether `expr` is the receiver of a method call, whose auto-ref may be
+/// a two-phase borrow.
+fn is_method_receiver<'a, 'gcx, 'tcx>(cx: &Cx<'a, 'gcx, 'tcx>, expr: &hir::Expr) -> bool {
+    let parent_id = cx.tcx.hir.get_parent_node(expr.id);
+    match cx.tcx.hir.find(parent_id) {
+        Some(hir::map::NodeExpr(&hir::Expr {
+            node: hir::ExprMethodCall(_, _, ref args), ..
+        })) => args.first().map_or(false, |receiver| receiver.id == expr.id),
+        _ => false,
+    }
+}
+
 fn to_borrow_kind(m: hir::Mutability) -> BorrowKind {
     match m {
-        hir::MutMutable => BorrowKind::Mut,
+        hir::MutMutable => BorrowKind::Mut { allow_two_phase_borrow: false },
         hir::MutImmutable => BorrowKind::Shared,
     }
 }
@@ -915,7 +934,7 @@ fn capture_freevar<'a, 'gcx, 'tcx>(cx: &mut Cx<'a, 'gcx, 'tcx>,
             let borrow_kind = match upvar_borrow.kind {
                 ty::BorrowKind::ImmBorrow => BorrowKind::Shared,
                 ty::BorrowKind::UniqueImmBorrow => BorrowKind::Unique,
-                ty::BorrowKind::MutBorrow => BorrowKind::Mut,
+                ty::BorrowKind::MutBorrow => BorrowKind::Mut { allow_two_phase_borrow: false },
             };
             Expr {
                 temp_lifetime,
@@ -200,6 +200,7 @@ pub enum ExprKind<'tcx> {
     Match {
         discriminant: ExprRef<'tcx>,
         arms: Vec<Arm<'tcx>>,
+        source: hir::MatchSource,
     },
     Block {
         body: &'tcx hir::Block,
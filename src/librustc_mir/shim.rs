@@ -13,6 +13,7 @@ use rustc::hir::def_id::DefId;
 use rustc::infer;
 use rustc::middle::const_val::ConstVal;
 use rustc::mir::*;
+use rustc::traits::Reveal;
 use rustc::ty::{self, Ty, TyCtxt};
 use rustc::ty::subst::{Kind, Subst, Substs};
 use rustc::ty::maps::Providers;
@@ -28,9 +29,10 @@ use std::fmt;
 use std::iter;
 
 use transform::{add_moves_for_packed_drops, add_call_guards};
-use transform::{no_landing_pads, simplify};
+use transform::{no_landing_pads, simplify, MirSource};
 use util::elaborate_drops::{self, DropElaborator, DropStyle, DropFlagMode};
 use util::patch::MirPatch;
+use util::pretty;
 
 pub fn provide(providers: &mut Providers) {
     providers.mir_shims = make_shim;
@@ -103,6 +105,8 @@ fn make_shim<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
             let name = tcx.item_name(def_id);
             if name == "clone" {
                 build_clone_shim(tcx, def_id, ty)
+            } else if name == "clone_from" && is_copy(tcx, def_id, ty) {
+                build_clone_from_shim(tcx, def_id)
             } else if name == "clone_from" {
                 debug!("make_shim({:?}: using default trait implementation", instance);
                 return tcx.optimized_mir(def_id);
@@ -122,9 +126,30 @@ fn make_shim<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     add_call_guards::CriticalCallEdges.add_call_guards(&mut result);
     debug!("make_shim({:?}) = {:?}", instance, result);
 
+    pretty::dump_mir(tcx, None, "shim", &shim_disambiguator(instance),
+                     MirSource::item(instance.def_id()), &result, |_, _| Ok(()));
+
     tcx.alloc_mir(result)
 }
 
+/// The type a shim was built for, in a form usable in the name of the
+/// file its MIR is dumped to, e.g. `[std-string-String]`.
+fn shim_disambiguator(instance: ty::InstanceDef) -> String {
+    let ty = match instance {
+        ty::InstanceDef::FnPtrShim(_, ty) |
+        ty::InstanceDef::DropGlue(_, Some(ty)) |
+        ty::InstanceDef::CloneShim(_, ty) => ty,
+        ty::InstanceDef::DropGlue(_, None) => return String::from("empty"),
+        _ => return String::from("shim"),
+    };
+    ty.to_string().replace("::", "-").chars().map(|c| {
+        match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' | ' ' => '_',
+            c => c,
+        }
+    }).collect()
+}
+
 #[derive(Copy, Clone, Debug, PartialEq)]
 enum Adjustment {
     Identity,
@@ -196,7 +221,7 @@ fn build_drop_shim<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     let mut mir = Mir::new(
         blocks,
         IndexVec::from_elem_n(
-            VisibilityScopeData { span: span, parent_scope: None }, 1
+            VisibilityScopeData { span: span, parent_scope: None, inlined: None }, 1
         ),
         ClearOnDecode::Clear,
         IndexVec::new(),
@@ -282,6 +307,13 @@ impl<'a, 'tcx> DropElaborator<'a, 'tcx> for DropShimElaborator<'a, 'tcx> {
     }
 }
 
+/// Whether the `self_ty` of a clone shim is `Copy`. Clone shims are only
+/// built for monomorphic types, so this doesn't depend on any where-clauses.
+fn is_copy<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, def_id: DefId, self_ty: Ty<'tcx>) -> bool {
+    let param_env = ty::ParamEnv::empty(Reveal::All);
+    !self_ty.moves_by_default(tcx, param_env, tcx.def_span(def_id))
+}
+
 /// Build a `Clone::clone` shim for `self_ty`. Here, `def_id` is `Clone::clone`.
 fn build_clone_shim<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
                               def_id: DefId,
@@ -291,10 +323,9 @@ fn build_clone_shim<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     debug!("build_clone_shim(def_id={:?})", def_id);
 
     let mut builder = CloneShimBuilder::new(tcx, def_id);
-    let is_copy = !self_ty.moves_by_default(tcx, tcx.param_env(def_id), builder.span);
 
     match self_ty.sty {
-        _ if is_copy => builder.copy_shim(),
+        _ if is_copy(tcx, def_id, self_ty) => builder.copy_shim(),
         ty::TyArray(ty, len) => {
             let len = len.val.to_const_int().unwrap().to_u64().unwrap();
             builder.array_shim(ty, len)
@@ -314,6 +345,16 @@ fn build_clone_shim<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     builder.into_mir()
 }
 
+/// Build a `Clone::clone_from` shim for a `Copy` type, which assigns the
+/// source to `self`. Here, `def_id` is `Clone::clone_from`.
+fn build_clone_from_shim<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, def_id: DefId) -> Mir<'tcx> {
+    debug!("build_clone_from_shim(def_id={:?})", def_id);
+
+    let mut builder = CloneShimBuilder::new(tcx, def_id);
+    builder.copy_from_shim();
+    builder.into_mir()
+}
+
 struct CloneShimBuilder<'a, 'tcx: 'a> {
     tcx: TyCtxt<'a, 'tcx, 'tcx>,
     def_id: DefId,
@@ -343,7 +384,7 @@ impl<'a, 'tcx> CloneShimBuilder<'a, 'tcx> {
         Mir::new(
             self.blocks,
             IndexVec::from_elem_n(
-                VisibilityScopeData { span: self.span, parent_scope: None }, 1
+                VisibilityScopeData { span: self.span, parent_scope: None, inlined: None }, 1
             ),
             ClearOnDecode::Clear,
             IndexVec::new(),
@@ -391,6 +432,15 @@ impl<'a, 'tcx> CloneShimBuilder<'a, 'tcx> {
         self.block(vec![ret_statement], TerminatorKind::Return, false);
     }
 
+    fn copy_from_shim(&mut self) {
+        let dest = Lvalue::Local(Local::new(1+0)).deref();
+        let src = Lvalue::Local(Local::new(1+1)).deref();
+        let assign = self.make_statement(
+            StatementKind::Assign(dest, Rvalue::Use(Operand::Copy(src)))
+        );
+        self.block(vec![assign], TerminatorKind::Return, false);
+    }
+
     fn make_lvalue(&mut self, mutability: Mutability, ty: Ty<'tcx>) -> Lvalue<'tcx> {
         let span = self.span;
         Lvalue::Local(
@@ -721,7 +771,9 @@ fn build_call_shim<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
                 source_info,
                 kind: StatementKind::Assign(
                     Lvalue::Local(ref_rcvr),
-                    Rvalue::Ref(tcx.types.re_erased, BorrowKind::Mut, rcvr_l)
+                    Rvalue::Ref(tcx.types.re_erased,
+                                BorrowKind::Mut { allow_two_phase_borrow: false },
+                                rcvr_l)
                 )
             });
             Operand::Move(Lvalue::Local(ref_rcvr))
@@ -805,7 +857,7 @@ fn build_call_shim<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     let mut mir = Mir::new(
         blocks,
         IndexVec::from_elem_n(
-            VisibilityScopeData { span: span, parent_scope: None }, 1
+            VisibilityScopeData { span: span, parent_scope: None, inlined: None }, 1
         ),
         ClearOnDecode::Clear,
         IndexVec::new(),
@@ -883,7 +935,7 @@ pub fn build_adt_ctor<'a, 'gcx, 'tcx>(infcx: &infer::InferCtxt<'a, 'gcx, 'tcx>,
     Mir::new(
         IndexVec::from_elem_n(start_block, 1),
         IndexVec::from_elem_n(
-            VisibilityScopeData { span: span, parent_scope: None }, 1
+            VisibilityScopeData { span: span, parent_scope: None, inlined: None }, 1
         ),
         ClearOnDecode::Clear,
         IndexVec::new(),
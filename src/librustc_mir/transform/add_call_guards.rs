@@ -11,7 +11,7 @@
 use rustc::ty::TyCtxt;
 use rustc::mir::*;
 use rustc_data_structures::indexed_vec::{Idx, IndexVec};
-use transform::{MirPass, MirSource};
+use transform::{MirOptContext, MirPass, MirSource};
 
 #[derive(PartialEq)]
 pub enum AddCallGuards {
@@ -41,11 +41,13 @@ pub use self::AddCallGuards::*;
  */
 
 impl MirPass for AddCallGuards {
-    fn run_pass<'a, 'tcx>(&self,
-                          _tcx: TyCtxt<'a, 'tcx, 'tcx>,
-                          _src: MirSource,
-                          mir: &mut Mir<'tcx>) {
+    fn run_pass_in_context<'a, 'tcx>(&self,
+                                     _tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                     _context: &MirOptContext<'tcx>,
+                                     _src: MirSource,
+                                     mir: &mut Mir<'tcx>) -> bool {
         self.add_call_guards(mir);
+        true
     }
 }
 
@@ -12,7 +12,7 @@ use rustc::hir::def_id::DefId;
 use rustc::mir::*;
 use rustc::ty::TyCtxt;
 
-use transform::{MirPass, MirSource};
+use transform::{MirOptContext, MirPass, MirSource};
 use util::patch::MirPatch;
 use util;
 
@@ -50,13 +50,16 @@ use util;
 pub struct AddMovesForPackedDrops;
 
 impl MirPass for AddMovesForPackedDrops {
-    fn run_pass<'a, 'tcx>(&self,
-                          tcx: TyCtxt<'a, 'tcx, 'tcx>,
-                          src: MirSource,
-                          mir: &mut Mir<'tcx>)
+    fn run_pass_in_context<'a, 'tcx>(&self,
+                                     tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                     _context: &MirOptContext<'tcx>,
+                                     src: MirSource,
+                                     mir: &mut Mir<'tcx>)
+                                     -> bool
     {
         debug!("add_moves_for_packed_drops({:?} @ {:?})", src, mir.span);
         add_moves_for_packed_drops(tcx, mir, src.def_id);
+        true
     }
 }
 
@@ -13,15 +13,72 @@
 //! introducing these calls *adds* UB -- so, conceptually, this pass is actually part
 //! of MIR building, and only after this pass we think of the program has having the
 //! normal MIR semantics.
+//!
+//! `-Z mir-emit-validate-skip` excludes some kinds of lvalues from validation, and
+//! `#[rustc_no_mir_validate]` disables validation of a whole function.  Skipped lvalues
+//! are left out of the emitted statements, so they are not validated at all.
 
 use rustc::ty::{self, TyCtxt, RegionKind};
 use rustc::hir;
 use rustc::mir::*;
 use rustc::middle::region;
-use transform::{MirPass, MirSource};
+use rustc::session::Session;
+use transform::{MirOptContext, MirPass, MirSource};
 
 pub struct AddValidation;
 
+/// The kinds of lvalues that `-Z mir-emit-validate-skip` excludes from validation.
+struct SkippedLvalues {
+    /// Lvalues of union type, and their fields.
+    unions: bool,
+    /// Fields of packed structs.
+    packed: bool,
+    /// The contents behind raw pointers.
+    raw_ptrs: bool,
+}
+
+impl SkippedLvalues {
+    fn new(kinds: &[String]) -> Self {
+        let has = |kind: &str| kinds.iter().any(|k| k == kind);
+        SkippedLvalues {
+            unions: has("unions"),
+            packed: has("packed"),
+            raw_ptrs: has("raw-ptrs"),
+        }
+    }
+
+    /// Check whether `lval`, or an lvalue it is projected from, is of a skipped kind.
+    fn contains<'a, 'tcx, D>(
+        &self,
+        lval: &Lvalue<'tcx>,
+        local_decls: &D,
+        tcx: TyCtxt<'a, 'tcx, 'tcx>
+    ) -> bool
+        where D: HasLocalDecls<'tcx>
+    {
+        if self.unions {
+            let ty = lval.ty(local_decls, tcx).to_ty(tcx);
+            if ty.ty_adt_def().map_or(false, |adt| adt.is_union()) {
+                return true;
+            }
+        }
+        match *lval {
+            Lvalue::Local(_) | Lvalue::Static(_) => false,
+            Lvalue::Projection(ref proj) => {
+                let base_ty = proj.base.ty(local_decls, tcx).to_ty(tcx);
+                let skipped = match proj.elem {
+                    ProjectionElem::Deref => self.raw_ptrs && base_ty.is_unsafe_ptr(),
+                    ProjectionElem::Field(..) => {
+                        self.packed && base_ty.ty_adt_def().map_or(false, |adt| adt.repr.packed())
+                    }
+                    _ => false,
+                };
+                skipped || self.contains(&proj.base, local_decls, tcx)
+            }
+        }
+    }
+}
+
 /// Determine the "context" of the lval: Mutability and region.
 fn lval_context<'a, 'tcx, D>(
     lval: &Lvalue<'tcx>,
@@ -186,23 +243,35 @@ fn fn_contains_unsafe<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, src: MirSource) ->
 }
 
 impl MirPass for AddValidation {
-    fn run_pass<'a, 'tcx>(&self,
-                          tcx: TyCtxt<'a, 'tcx, 'tcx>,
-                          src: MirSource,
-                          mir: &mut Mir<'tcx>)
+    fn is_enabled(&self, sess: &Session) -> bool {
+        sess.opts.debugging_opts.mir_emit_validate > 0
+    }
+
+    fn opt_out_attribute(&self) -> Option<&'static str> {
+        Some("rustc_no_mir_validate")
+    }
+
+    fn run_pass_in_context<'a, 'tcx>(&self,
+                                     tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                     _context: &MirOptContext<'tcx>,
+                                     src: MirSource,
+                                     mir: &mut Mir<'tcx>)
+                                     -> bool
     {
         let emit_validate = tcx.sess.opts.debugging_opts.mir_emit_validate;
-        if emit_validate == 0 {
-            return;
-        }
         let restricted_validation = emit_validate == 1 && fn_contains_unsafe(tcx, src);
+        let skipped = SkippedLvalues::new(&tcx.sess.opts.debugging_opts.mir_emit_validate_skip);
         let local_decls = mir.local_decls.clone(); // FIXME: Find a way to get rid of this clone.
 
-        // Convert an lvalue to a validation operand.
-        let lval_to_operand = |lval: Lvalue<'tcx>| -> ValidationOperand<'tcx, Lvalue<'tcx>> {
+        // Convert an lvalue to a validation operand, unless it is of a skipped kind.
+        let lval_to_operand = |lval: Lvalue<'tcx>|
+                                -> Option<ValidationOperand<'tcx, Lvalue<'tcx>>> {
+            if skipped.contains(&lval, &local_decls, tcx) {
+                return None;
+            }
             let (re, mutbl) = lval_context(&lval, &local_decls, tcx);
             let ty = lval.ty(&local_decls, tcx).to_ty(tcx);
-            ValidationOperand { lval, ty, re, mutbl }
+            Some(ValidationOperand { lval, ty, re, mutbl })
         };
 
         // Emit an Acquire at the beginning of the given block.  If we are in restricted emission
@@ -237,7 +306,7 @@ impl MirPass for AddValidation {
             };
             // Gather all arguments, skip return value.
             let operands = mir.local_decls.iter_enumerated().skip(1).take(mir.arg_count)
-                    .map(|(local, _)| lval_to_operand(Lvalue::Local(local))).collect();
+                    .filter_map(|(local, _)| lval_to_operand(Lvalue::Local(local))).collect();
             emit_acquire(&mut mir.basic_blocks_mut()[START_BLOCK], source_info, operands);
         }
 
@@ -253,22 +322,25 @@ impl MirPass for AddValidation {
                     // The callee may write into the return value!  Note that this relies
                     // on "release of uninitialized" to be a NOP.
                     if !restricted_validation {
-                        let release_stmt = Statement {
-                            source_info,
-                            kind: StatementKind::Validate(ValidationOp::Release,
-                                destination.iter().map(|dest| lval_to_operand(dest.0.clone()))
-                                .chain(
-                                    args.iter().filter_map(|op| {
-                                        match op {
-                                            &Operand::Copy(ref lval) |
-                                            &Operand::Move(ref lval) =>
-                                                Some(lval_to_operand(lval.clone())),
-                                            &Operand::Constant(..) => { None },
-                                        }
-                                    })
-                                ).collect())
-                        };
-                        block_data.statements.push(release_stmt);
+                        let operands: Vec<_> =
+                            destination.iter().filter_map(|dest| lval_to_operand(dest.0.clone()))
+                            .chain(
+                                args.iter().filter_map(|op| {
+                                    match op {
+                                        &Operand::Copy(ref lval) |
+                                        &Operand::Move(ref lval) =>
+                                            lval_to_operand(lval.clone()),
+                                        &Operand::Constant(..) => { None },
+                                    }
+                                })
+                            ).collect();
+                        if !operands.is_empty() {
+                            let release_stmt = Statement {
+                                source_info,
+                                kind: StatementKind::Validate(ValidationOp::Release, operands),
+                            };
+                            block_data.statements.push(release_stmt);
+                        }
                     }
                     // Remember the return destination for later
                     if let &Some(ref destination) = destination {
@@ -281,12 +353,14 @@ impl MirPass for AddValidation {
                                   source_info }) => {
                     // Before the call: Release all arguments
                     if !restricted_validation {
-                        let release_stmt = Statement {
-                            source_info,
-                            kind: StatementKind::Validate(ValidationOp::Release,
-                                    vec![lval_to_operand(lval.clone())]),
-                        };
-                        block_data.statements.push(release_stmt);
+                        if let Some(operand) = lval_to_operand(lval.clone()) {
+                            let release_stmt = Statement {
+                                source_info,
+                                kind: StatementKind::Validate(ValidationOp::Release,
+                                                              vec![operand]),
+                            };
+                            block_data.statements.push(release_stmt);
+                        }
                     }
                     // drop doesn't return anything, so we need no acquire.
                 }
@@ -300,13 +374,13 @@ impl MirPass for AddValidation {
             emit_acquire(
                 &mut mir.basic_blocks_mut()[dest_block],
                 source_info,
-                vec![lval_to_operand(dest_lval)]
+                lval_to_operand(dest_lval).into_iter().collect()
             );
         }
 
         if restricted_validation {
             // No part 3 for us.
-            return;
+            return true;
         }
 
         // PART 3
@@ -331,14 +405,15 @@ impl MirPass for AddValidation {
                         // So this is a ref, and we got all the data we wanted.
                         // Do an acquire of the result -- but only what it points to, so add a Deref
                         // projection.
-                        let dest_lval = Projection { base: dest_lval, elem: ProjectionElem::Deref };
-                        let dest_lval = Lvalue::Projection(Box::new(dest_lval));
-                        let acquire_stmt = Statement {
-                            source_info: block_data.statements[i].source_info,
-                            kind: StatementKind::Validate(ValidationOp::Acquire,
-                                    vec![lval_to_operand(dest_lval)]),
-                        };
-                        block_data.statements.insert(i+1, acquire_stmt);
+                        let dest_lval = dest_lval.deref();
+                        if let Some(operand) = lval_to_operand(dest_lval) {
+                            let acquire_stmt = Statement {
+                                source_info: block_data.statements[i].source_info,
+                                kind: StatementKind::Validate(ValidationOp::Acquire,
+                                        vec![operand]),
+                            };
+                            block_data.statements.insert(i+1, acquire_stmt);
+                        }
 
                         // The source is released until the region of the borrow ends.
                         let op = match re {
@@ -347,11 +422,13 @@ impl MirPass for AddValidation {
                                 bug!("AddValidation pass must be run before erasing lifetimes"),
                             _ => ValidationOp::Release,
                         };
-                        let release_stmt = Statement {
-                            source_info: block_data.statements[i].source_info,
-                            kind: StatementKind::Validate(op, vec![lval_to_operand(src_lval)]),
-                        };
-                        block_data.statements.insert(i, release_stmt);
+                        if let Some(operand) = lval_to_operand(src_lval) {
+                            let release_stmt = Statement {
+                                source_info: block_data.statements[i].source_info,
+                                kind: StatementKind::Validate(op, vec![operand]),
+                            };
+                            block_data.statements.insert(i, release_stmt);
+                        }
                     }
                     // Casts can change what validation does (e.g. unsizing)
                     StatementKind::Assign(_, Rvalue::Cast(kind, Operand::Copy(_), _)) |
@@ -372,24 +449,29 @@ impl MirPass for AddValidation {
                         };
 
                         // Acquire of the result
-                        let acquire_stmt = Statement {
-                            source_info: block_data.statements[i].source_info,
-                            kind: StatementKind::Validate(ValidationOp::Acquire,
-                                    vec![lval_to_operand(dest_lval)]),
-                        };
-                        block_data.statements.insert(i+1, acquire_stmt);
+                        if let Some(operand) = lval_to_operand(dest_lval) {
+                            let acquire_stmt = Statement {
+                                source_info: block_data.statements[i].source_info,
+                                kind: StatementKind::Validate(ValidationOp::Acquire,
+                                        vec![operand]),
+                            };
+                            block_data.statements.insert(i+1, acquire_stmt);
+                        }
 
                         // Release of the input
-                        let release_stmt = Statement {
-                            source_info: block_data.statements[i].source_info,
-                            kind: StatementKind::Validate(ValidationOp::Release,
-                                                            vec![lval_to_operand(src_lval)]),
-                        };
-                        block_data.statements.insert(i, release_stmt);
+                        if let Some(operand) = lval_to_operand(src_lval) {
+                            let release_stmt = Statement {
+                                source_info: block_data.statements[i].source_info,
+                                kind: StatementKind::Validate(ValidationOp::Release,
+                                                                vec![operand]),
+                            };
+                            block_data.statements.insert(i, release_stmt);
+                        }
                     }
                     _ => {},
                 }
             }
         }
+        true
     }
 }
@@ -0,0 +1,300 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! This pass replaces boxes that never escape their function with plain
+//! locals of the boxed type, removing their heap allocation.
+//!
+//! Once `Box::new` is inlined, `let b = Box::new(x);` allocates with a
+//! `Box(T)` rvalue into a temporary, initializes `(*tmp)` and moves the
+//! temporary into `b`. The locals of box type that a box is moved between
+//! form a group, which is turned into a single local when:
+//!
+//! - the group contains exactly one `Box(T)` rvalue, and neither an
+//!   argument nor the return pointer;
+//! - that rvalue is not in a loop, as a box allocated by one iteration
+//!   could still be live in a member of the group when the next iteration
+//!   allocates again, and both would need the same local;
+//! - every mention of its locals is a dereference, a storage marker, a
+//!   drop of the whole box, a call to `box_free` on it, or a move to
+//!   another local of the group. Any other use, like returning the box,
+//!   storing it elsewhere, passing it to a function or casting it to a raw
+//!   pointer, counts as escaping;
+//! - the boxed type has a known size of at most `MAX_BOX_SIZE` bytes, so
+//!   that large allocations aren't moved to the stack.
+//!
+//! The rewrite gives one local of the group the boxed type and replaces
+//! dereferences of any member with that local. The `Box(T)` rvalue, the
+//! moves within the group, their storage markers and the calls to
+//! `box_free` are removed. Drops of the whole box are kept, and drop the
+//! inner value only now that the local has the boxed type.
+//!
+//! Since the value stays in one local, its address doesn't change when
+//! the box is moved, which keeps pointers derived from `&*b` valid.
+//! Generators are skipped, as boxes are often used there to keep the
+//! generator state small.
+
+use rustc::hir::def_id::DefId;
+use rustc::middle::const_val::ConstVal;
+use rustc::mir::{BasicBlock, Constant, Literal, Local, LocalKind, Location, Lvalue, Mir};
+use rustc::mir::{NullOp, Operand, Projection, ProjectionElem, Rvalue, Statement, StatementKind};
+use rustc::mir::TerminatorKind;
+use rustc::mir::visit::{LvalueContext, MutVisitor, Visitor};
+use rustc::ty::{self, TyCtxt};
+use rustc::ty::layout::LayoutOf;
+use rustc_data_structures::indexed_vec::IndexVec;
+use transform::{MirOptContext, MirPass, MirSource};
+use util::{group_moved_locals, local_move};
+use util::scc;
+
+/// The largest boxed type, in bytes, that is moved to the stack.
+const MAX_BOX_SIZE: u64 = 4096;
+
+pub struct BoxToStack;
+
+impl MirPass for BoxToStack {
+    fn run_pass_in_context<'a, 'tcx>(&self,
+                                     tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                     context: &MirOptContext<'tcx>,
+                                     source: MirSource,
+                                     mir: &mut Mir<'tcx>) -> bool {
+        if !context.is_fn || context.is_generator {
+            return false;
+        }
+        let box_free = match tcx.lang_items().box_free_fn() {
+            Some(box_free) => box_free,
+            None => return false,
+        };
+
+        let is_candidate: IndexVec<Local, bool> = mir.local_decls.iter_enumerated()
+            .map(|(local, decl)| {
+                decl.ty.is_box() &&
+                    match mir.local_kind(local) {
+                        LocalKind::Var | LocalKind::Temp => true,
+                        LocalKind::Arg | LocalKind::ReturnPointer => false,
+                    }
+            })
+            .collect();
+        if !is_candidate.iter().any(|&candidate| candidate) {
+            return false;
+        }
+
+        let groups = group_moved_locals(mir, &is_candidate);
+        let in_cycle = scc::blocks_in_cycles(mir);
+
+        let (local_escapes, local_allocations) = {
+            let mut uses = BoxUses {
+                mir: &*mir,
+                is_candidate: &is_candidate,
+                in_cycle: &in_cycle,
+                box_free,
+                escaping: IndexVec::from_elem(false, &mir.local_decls),
+                allocations: IndexVec::from_elem(0, &mir.local_decls),
+            };
+            uses.visit_mir(mir);
+            (uses.escaping, uses.allocations)
+        };
+
+        // Decide, for the root of each group, whether the group can be
+        // replaced, and by which of its locals.
+        let mut escaping = IndexVec::from_elem(false, &mir.local_decls);
+        let mut allocations = IndexVec::from_elem(0, &mir.local_decls);
+        let mut replacement = IndexVec::from_elem(None, &mir.local_decls);
+        for local in mir.local_decls.indices() {
+            let root = groups[local];
+            escaping[root] |= !is_candidate[local] || local_escapes[local];
+            allocations[root] += local_allocations[local];
+            // Keep the user variable if there is one, for debuginfo.
+            if replacement[root].is_none() || mir.local_decls[local].is_user_variable {
+                replacement[root] = Some(local);
+            }
+        }
+
+        let param_env = tcx.param_env(source.def_id);
+        let mut replace = IndexVec::from_elem(None, &mir.local_decls);
+        let mut replaced_any = false;
+        for local in mir.local_decls.indices() {
+            let root = groups[local];
+            if escaping[root] || allocations[root] != 1 {
+                continue;
+            }
+            let boxed_ty = mir.local_decls[local].ty.boxed_ty();
+            match (tcx, param_env).layout_of(boxed_ty) {
+                Ok(layout) if layout.size.bytes() <= MAX_BOX_SIZE => {}
+                // The size of boxes with generic types is not known here
+                _ => continue,
+            }
+            replace[local] = replacement[root];
+            replaced_any = true;
+        }
+        if !replaced_any {
+            return false;
+        }
+
+        for (local, &replacement) in replace.iter_enumerated() {
+            if replacement == Some(local) {
+                let decl = &mut mir.local_decls[local];
+                decl.ty = decl.ty.boxed_ty();
+            }
+        }
+        UnboxLocals { replace, box_free }.visit_mir(mir);
+        true
+    }
+}
+
+/// Returns the local freed by a call to `box_free`, if `func` is
+/// `box_free` and the box is moved into it from a whole local.
+fn box_freed_local<'tcx>(func: &Operand<'tcx>,
+                         args: &[Operand<'tcx>],
+                         box_free: DefId)
+                         -> Option<Local> {
+    match *func {
+        Operand::Constant(box Constant {
+            literal: Literal::Value {
+                value: &ty::Const { val: ConstVal::Function(def_id, _), .. },
+                ..
+            },
+            ..
+        }) if def_id == box_free => {}
+        _ => return None,
+    }
+    match args.first() {
+        Some(&Operand::Move(Lvalue::Local(local))) if args.len() == 1 => Some(local),
+        _ => None,
+    }
+}
+
+/// Finds the locals used in a way that lets their box escape, and counts
+/// the `Box(T)` rvalues assigned to each local.
+struct BoxUses<'a, 'tcx: 'a> {
+    mir: &'a Mir<'tcx>,
+    is_candidate: &'a IndexVec<Local, bool>,
+    in_cycle: &'a IndexVec<BasicBlock, bool>,
+    box_free: DefId,
+    escaping: IndexVec<Local, bool>,
+    allocations: IndexVec<Local, usize>,
+}
+
+impl<'a, 'tcx> Visitor<'tcx> for BoxUses<'a, 'tcx> {
+    fn visit_statement(&mut self,
+                       block: BasicBlock,
+                       statement: &Statement<'tcx>,
+                       location: Location) {
+        match statement.kind {
+            StatementKind::Assign(Lvalue::Local(local), Rvalue::NullaryOp(NullOp::Box, _)) => {
+                self.allocations[local] += 1;
+                // Keep the boxes allocated in a loop on the heap.
+                if self.in_cycle[block] {
+                    self.escaping[local] = true;
+                }
+            }
+            StatementKind::StorageLive(_) | StatementKind::StorageDead(_) => {}
+            _ if local_move(self.mir, self.is_candidate, statement).is_some() => {}
+            _ => self.super_statement(block, statement, location),
+        }
+    }
+
+    fn visit_terminator_kind(&mut self,
+                             block: BasicBlock,
+                             kind: &TerminatorKind<'tcx>,
+                             location: Location) {
+        match *kind {
+            TerminatorKind::Drop { location: Lvalue::Local(_), .. } => {}
+            TerminatorKind::Call { ref func, ref args, .. }
+                if box_freed_local(func, args, self.box_free).is_some() => {}
+            _ => self.super_terminator_kind(block, kind, location),
+        }
+    }
+
+    fn visit_lvalue(&mut self,
+                    lvalue: &Lvalue<'tcx>,
+                    context: LvalueContext<'tcx>,
+                    location: Location) {
+        match *lvalue {
+            Lvalue::Projection(box Projection {
+                base: Lvalue::Local(_),
+                elem: ProjectionElem::Deref,
+            }) => {}
+            _ => self.super_lvalue(lvalue, context, location),
+        }
+    }
+
+    fn visit_local(&mut self, &local: &Local, _: LvalueContext<'tcx>, _: Location) {
+        self.escaping[local] = true;
+    }
+}
+
+/// Replaces the boxes in `replace` by the local holding their contents.
+struct UnboxLocals {
+    replace: IndexVec<Local, Option<Local>>,
+    box_free: DefId,
+}
+
+impl<'tcx> MutVisitor<'tcx> for UnboxLocals {
+    fn visit_statement(&mut self,
+                       block: BasicBlock,
+                       statement: &mut Statement<'tcx>,
+                       location: Location) {
+        let remove = match statement.kind {
+            StatementKind::Assign(Lvalue::Local(local), Rvalue::NullaryOp(NullOp::Box, _)) |
+            StatementKind::Assign(Lvalue::Local(local),
+                                  Rvalue::Use(Operand::Move(Lvalue::Local(_)))) |
+            StatementKind::StorageLive(local) |
+            StatementKind::StorageDead(local) => self.replace[local].is_some(),
+            _ => false,
+        };
+        if remove {
+            statement.make_nop();
+        } else {
+            self.super_statement(block, statement, location);
+        }
+    }
+
+    fn visit_terminator_kind(&mut self,
+                             block: BasicBlock,
+                             kind: &mut TerminatorKind<'tcx>,
+                             location: Location) {
+        let target = match *kind {
+            TerminatorKind::Call { ref func, ref args, destination: Some((_, target)), .. } => {
+                match box_freed_local(func, args, self.box_free) {
+                    Some(local) if self.replace[local].is_some() => Some(target),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        match target {
+            Some(target) => *kind = TerminatorKind::Goto { target },
+            None => self.super_terminator_kind(block, kind, location),
+        }
+    }
+
+    fn visit_lvalue(&mut self,
+                    lvalue: &mut Lvalue<'tcx>,
+                    context: LvalueContext<'tcx>,
+                    location: Location) {
+        let replacement = match *lvalue {
+            Lvalue::Projection(box Projection {
+                base: Lvalue::Local(local),
+                elem: ProjectionElem::Deref,
+            }) => self.replace[local],
+            _ => None,
+        };
+        match replacement {
+            Some(replacement) => *lvalue = Lvalue::Local(replacement),
+            None => self.super_lvalue(lvalue, context, location),
+        }
+    }
+
+    fn visit_local(&mut self, local: &mut Local, _: LvalueContext<'tcx>, _: Location) {
+        if let Some(replacement) = self.replace[*local] {
+            *local = replacement;
+        }
+    }
+}
@@ -9,8 +9,9 @@
 // except according to those terms.
 
 use rustc_data_structures::fx::FxHashSet;
-use rustc_data_structures::indexed_vec::IndexVec;
+use rustc_data_structures::indexed_vec::{Idx, IndexVec};
 
+use rustc::ty::item_path;
 use rustc::ty::maps::Providers;
 use rustc::ty::{self, TyCtxt};
 use rustc::hir;
@@ -20,7 +21,12 @@ use rustc::mir::*;
 use rustc::mir::visit::{LvalueContext, Visitor};
 
 use syntax::ast;
+use syntax::symbol::{InternedString, Symbol};
 
+use rustc_serialize::json;
+
+use std::fs::File;
+use std::io::Write;
 use std::rc::Rc;
 use util;
 
@@ -28,6 +34,7 @@ pub struct UnsafetyChecker<'a, 'tcx: 'a> {
     mir: &'a Mir<'tcx>,
     visibility_scope_info: &'a IndexVec<VisibilityScope, VisibilityScopeInfo>,
     violations: Vec<UnsafetyViolation>,
+    permitted_violations: Vec<(UnsafetyViolation, Safety)>,
     source_info: SourceInfo,
     tcx: TyCtxt<'a, 'tcx, 'tcx>,
     param_env: ty::ParamEnv<'tcx>,
@@ -44,6 +51,7 @@ impl<'a, 'gcx, 'tcx> UnsafetyChecker<'a, 'tcx> {
             mir,
             visibility_scope_info,
             violations: vec![],
+            permitted_violations: vec![],
             source_info: SourceInfo {
                 span: mir.span,
                 scope: ARGUMENT_VISIBILITY_SCOPE
@@ -82,7 +90,7 @@ impl<'a, 'tcx> Visitor<'tcx> for UnsafetyChecker<'a, 'tcx> {
                 let func_ty = func.ty(self.mir, self.tcx);
                 let sig = func_ty.fn_sig(self.tcx);
                 if let hir::Unsafety::Unsafe = sig.unsafety() {
-                    self.require_unsafe("call to unsafe function")
+                    self.require_unsafe(UnsafetyViolationDetails::UnsafeCall, None)
                 }
             }
         }
@@ -102,12 +110,13 @@ impl<'a, 'tcx> Visitor<'tcx> for UnsafetyChecker<'a, 'tcx> {
             StatementKind::StorageDead(..) |
             StatementKind::EndRegion(..) |
             StatementKind::Validate(..) |
+            StatementKind::Assume(..) |
             StatementKind::Nop => {
                 // safe (at least as emitted during MIR construction)
             }
 
             StatementKind::InlineAsm { .. } => {
-                self.require_unsafe("use of inline assembly")
+                self.require_unsafe(UnsafetyViolationDetails::InlineAsm, None)
             },
         }
         self.super_statement(block, statement, location);
@@ -125,9 +134,10 @@ impl<'a, 'tcx> Visitor<'tcx> for UnsafetyChecker<'a, 'tcx> {
                 &AggregateKind::Closure(def_id, _) |
                 &AggregateKind::Generator(def_id, _, _) => {
                     let UnsafetyCheckResult {
-                        violations, unsafe_blocks
+                        violations, unsafe_blocks, permitted_violations
                     } = self.tcx.unsafety_check_result(def_id);
                     self.register_violations(&violations, &unsafe_blocks);
+                    self.permitted_violations.extend(permitted_violations.iter().cloned());
                 }
             }
         }
@@ -145,7 +155,8 @@ impl<'a, 'tcx> Visitor<'tcx> for UnsafetyChecker<'a, 'tcx> {
                     self.visibility_scope_info[source_info.scope].lint_root;
                 self.register_violations(&[UnsafetyViolation {
                     source_info,
-                    description: "borrow of packed field",
+                    details: UnsafetyViolationDetails::BorrowOfPackedField,
+                    lvalue: self.describe_lvalue(lvalue),
                     kind: UnsafetyViolationKind::BorrowPacked(lint_root)
                 }], &[]);
             }
@@ -167,29 +178,48 @@ impl<'a, 'tcx> Visitor<'tcx> for UnsafetyChecker<'a, 'tcx> {
                 let base_ty = base.ty(self.mir, self.tcx).to_ty(self.tcx);
                 match base_ty.sty {
                     ty::TyRawPtr(..) => {
-                        self.require_unsafe("dereference of raw pointer")
+                        self.require_unsafe(UnsafetyViolationDetails::RawPtrDeref, Some(lvalue))
                     }
                     ty::TyAdt(adt, _) => {
                         if adt.is_union() {
-                            if context == LvalueContext::Store ||
-                                context == LvalueContext::Drop
-                            {
-                                let elem_ty = match elem {
-                                    &ProjectionElem::Field(_, ty) => ty,
-                                    _ => span_bug!(
-                                        self.source_info.span,
-                                        "non-field projection {:?} from union?",
-                                        lvalue)
-                                };
-                                if elem_ty.moves_by_default(self.tcx, self.param_env,
-                                                            self.source_info.span) {
-                                    self.require_unsafe(
-                                        "assignment to non-`Copy` union field")
-                                } else {
-                                    // write to non-move union, safe
+                            let elem_ty = match elem {
+                                &ProjectionElem::Field(_, ty) => ty,
+                                _ => span_bug!(
+                                    self.source_info.span,
+                                    "non-field projection {:?} from union?",
+                                    lvalue)
+                            };
+                            let details = match context {
+                                LvalueContext::Store |
+                                LvalueContext::Call |
+                                LvalueContext::Drop => {
+                                    if !elem_ty.moves_by_default(self.tcx, self.param_env,
+                                                                 self.source_info.span) {
+                                        // write to non-move union, safe
+                                        None
+                                    } else if context == LvalueContext::Drop {
+                                        // the old value of the field is dropped
+                                        Some(UnsafetyViolationDetails::NonCopyUnionFieldAssignment)
+                                    } else if self.tcx.sess.features.borrow()
+                                                  .safe_union_field_writes {
+                                        // whole-field write that drops nothing, which
+                                        // is allowed under the feature gate
+                                        None
+                                    } else {
+                                        Some(UnsafetyViolationDetails::UnionFieldWrite)
+                                    }
+                                }
+                                LvalueContext::Borrow { .. } => {
+                                    Some(UnsafetyViolationDetails::UnionFieldBorrow)
+                                }
+                                LvalueContext::Projection(Mutability::Mut) => {
+                                    // a write to (or mutable borrow of) part of the field
+                                    Some(UnsafetyViolationDetails::UnionFieldWrite)
                                 }
-                            } else {
-                                self.require_unsafe("access to union field")
+                                _ => Some(UnsafetyViolationDetails::UnionFieldRead)
+                            };
+                            if let Some(details) = details {
+                                self.require_unsafe(details, Some(lvalue))
                             }
                         }
                     }
@@ -202,14 +232,15 @@ impl<'a, 'tcx> Visitor<'tcx> for UnsafetyChecker<'a, 'tcx> {
             }
             &Lvalue::Static(box Static { def_id, ty: _ }) => {
                 if self.tcx.is_static_mut(def_id) {
-                    self.require_unsafe("use of mutable static");
+                    self.require_unsafe(UnsafetyViolationDetails::MutableStatic, Some(lvalue));
                 } else if self.tcx.is_foreign_item(def_id) {
                     let source_info = self.source_info;
                     let lint_root =
                         self.visibility_scope_info[source_info.scope].lint_root;
                     self.register_violations(&[UnsafetyViolation {
                         source_info,
-                        description: "use of extern static",
+                        details: UnsafetyViolationDetails::ExternStatic,
+                        lvalue: self.describe_lvalue(lvalue),
                         kind: UnsafetyViolationKind::ExternStatic(lint_root)
                     }], &[]);
                 }
@@ -221,18 +252,27 @@ impl<'a, 'tcx> Visitor<'tcx> for UnsafetyChecker<'a, 'tcx> {
 
 impl<'a, 'tcx> UnsafetyChecker<'a, 'tcx> {
     fn require_unsafe(&mut self,
-                      description: &'static str)
+                      details: UnsafetyViolationDetails,
+                      lvalue: Option<&Lvalue<'tcx>>)
     {
         let source_info = self.source_info;
+        let lvalue = lvalue.and_then(|lvalue| self.describe_lvalue(lvalue));
         self.register_violations(&[UnsafetyViolation {
-            source_info, description, kind: UnsafetyViolationKind::General
+            source_info, details, lvalue, kind: UnsafetyViolationKind::General
         }], &[]);
     }
 
+    /// Renders `lvalue` the way it could be written in source, or returns
+    /// `None` if it is rooted in a temporary.
+    fn describe_lvalue(&self, lvalue: &Lvalue<'tcx>) -> Option<InternedString> {
+        util::describe_lvalue(self.tcx, self.mir, lvalue).map(|s| Symbol::intern(&s).as_str())
+    }
+
     fn register_violations(&mut self,
                            violations: &[UnsafetyViolation],
                            unsafe_blocks: &[(ast::NodeId, bool)]) {
-        let within_unsafe = match self.visibility_scope_info[self.source_info.scope].safety {
+        let safety = self.visibility_scope_info[self.source_info.scope].safety;
+        let within_unsafe = match safety {
             Safety::Safe => {
                 for violation in violations {
                     if !self.violations.contains(violation) {
@@ -250,8 +290,22 @@ impl<'a, 'tcx> UnsafetyChecker<'a, 'tcx> {
                 true
             }
         };
+        if within_unsafe {
+            for violation in violations {
+                let permitted = (violation.clone(), safety);
+                if !self.permitted_violations.contains(&permitted) {
+                    self.permitted_violations.push(permitted)
+                }
+            }
+        }
+        // The blocks of a closure stay credited with what they cover when the
+        // closure is in an `unsafe` block, as they are the innermost ones.
+        let redundant_blocks = match safety {
+            Safety::BuiltinUnsafe | Safety::FnUnsafe => true,
+            Safety::Safe | Safety::ExplicitUnsafe(_) => false,
+        };
         self.inherited_blocks.extend(unsafe_blocks.iter().map(|&(node_id, is_used)| {
-            (node_id, is_used && !within_unsafe)
+            (node_id, is_used && !redundant_blocks)
         }));
     }
 }
@@ -325,7 +379,8 @@ fn unsafety_check_result<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, def_id: DefId)
             debug!("unsafety_violations: {:?} - remote, skipping", def_id);
             return UnsafetyCheckResult {
                 violations: Rc::new([]),
-                unsafe_blocks: Rc::new([])
+                unsafe_blocks: Rc::new([]),
+                permitted_violations: Rc::new([])
             }
         }
     };
@@ -338,7 +393,8 @@ fn unsafety_check_result<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, def_id: DefId)
     check_unused_unsafe(tcx, def_id, &checker.used_unsafe, &mut checker.inherited_blocks);
     UnsafetyCheckResult {
         violations: checker.violations.into(),
-        unsafe_blocks: checker.inherited_blocks.into()
+        unsafe_blocks: checker.inherited_blocks.into(),
+        permitted_violations: checker.permitted_violations.into()
     }
 }
 
@@ -363,7 +419,31 @@ fn unsafe_derive_on_repr_packed<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, def_id: D
                   &message);
 }
 
-/// Return the NodeId for an enclosing scope that is also `unsafe`
+/// Return whether `id` is a function or method declared `unsafe`
+fn is_unsafe_fn(tcx: TyCtxt, id: ast::NodeId) -> bool {
+    match tcx.hir.find(id) {
+        Some(hir::map::NodeItem(&hir::Item {
+            node: hir::ItemFn(_, hir::Unsafety::Unsafe, _, _, _, _),
+            ..
+        })) |
+        Some(hir::map::NodeImplItem(&hir::ImplItem {
+            node: hir::ImplItemKind::Method(hir::MethodSig {
+                unsafety: hir::Unsafety::Unsafe, ..
+            }, _),
+            ..
+        })) |
+        Some(hir::map::NodeTraitItem(&hir::TraitItem {
+            node: hir::TraitItemKind::Method(hir::MethodSig {
+                unsafety: hir::Unsafety::Unsafe, ..
+            }, hir::TraitMethod::Provided(_)),
+            ..
+        })) => true,
+        _ => false,
+    }
+}
+
+/// Return the NodeId for the closest enclosing scope that is also `unsafe`,
+/// i.e. the context which makes the `unsafe` block `id` redundant
 fn is_enclosed(tcx: TyCtxt,
                used_unsafe: &FxHashSet<ast::NodeId>,
                id: ast::NodeId) -> Option<(String, ast::NodeId)> {
@@ -371,10 +451,7 @@ fn is_enclosed(tcx: TyCtxt,
     if parent_id != id {
         if used_unsafe.contains(&parent_id) {
             Some(("block".to_string(), parent_id))
-        } else if let Some(hir::map::NodeItem(&hir::Item {
-            node: hir::ItemFn(_, hir::Unsafety::Unsafe, _, _, _, _),
-            ..
-        })) = tcx.hir.find(parent_id) {
+        } else if is_unsafe_fn(tcx, parent_id) {
             Some(("fn".to_string(), parent_id))
         } else {
             is_enclosed(tcx, used_unsafe, parent_id)
@@ -421,12 +498,14 @@ pub fn check_unsafety<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, def_id: DefId) {
 
     let UnsafetyCheckResult {
         violations,
-        unsafe_blocks
+        unsafe_blocks,
+        ..
     } = tcx.unsafety_check_result(def_id);
 
     for &UnsafetyViolation {
-        source_info, description, kind
+        source_info, details, kind, ..
     } in violations.iter() {
+        let description = details.description();
         // Report an error.
         match kind {
             UnsafetyViolationKind::General => {
@@ -434,37 +513,126 @@ pub fn check_unsafety<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, def_id: DefId) {
                     tcx.sess, source_info.span, E0133,
                     "{} requires unsafe function or block", description)
                     .span_label(source_info.span, description)
+                    .note(details.note())
                     .emit();
             }
             UnsafetyViolationKind::ExternStatic(lint_node_id) => {
-                tcx.lint_node(SAFE_EXTERN_STATICS,
-                              lint_node_id,
-                              source_info.span,
-                              &format!("{} requires unsafe function or \
-                                        block (error E0133)", description));
+                tcx.struct_span_lint_node(SAFE_EXTERN_STATICS,
+                                          lint_node_id,
+                                          source_info.span,
+                                          &format!("{} requires unsafe function or \
+                                                    block (error E0133)", description))
+                    .note(details.note())
+                    .emit();
             }
             UnsafetyViolationKind::BorrowPacked(lint_node_id) => {
                 if let Some(impl_def_id) = builtin_derive_def_id(tcx, def_id) {
                     tcx.unsafe_derive_on_repr_packed(impl_def_id);
                 } else {
-                    tcx.lint_node(SAFE_PACKED_BORROWS,
-                                  lint_node_id,
-                                  source_info.span,
-                                  &format!("{} requires unsafe function or \
-                                            block (error E0133)", description));
+                    tcx.struct_span_lint_node(SAFE_PACKED_BORROWS,
+                                              lint_node_id,
+                                              source_info.span,
+                                              &format!("{} requires unsafe function or \
+                                                        block (error E0133)", description))
+                        .note(details.note())
+                        .emit();
                 }
             }
         }
     }
 
-    let mut unsafe_blocks: Vec<_> = unsafe_blocks.into_iter().collect();
+    // A block can be registered more than once, e.g. by a closure and by the
+    // function containing it. It is used if any of these uses it, and it is
+    // reported at most once.
+    let mut unsafe_blocks: Vec<_> = unsafe_blocks.into_iter().cloned().collect();
     unsafe_blocks.sort();
+    unsafe_blocks.dedup_by(|&mut (id, used), &mut (prev_id, ref mut prev_used)| {
+        if id == prev_id {
+            *prev_used |= used;
+            true
+        } else {
+            false
+        }
+    });
     let used_unsafe: FxHashSet<_> = unsafe_blocks.iter()
-        .flat_map(|&&(id, used)| if used { Some(id) } else { None })
+        .flat_map(|&(id, used)| if used { Some(id) } else { None })
         .collect();
-    for &(block_id, is_used) in unsafe_blocks {
+    for &(block_id, is_used) in &unsafe_blocks {
         if !is_used {
             report_unused_unsafe(tcx, &used_unsafe, block_id);
         }
     }
 }
+
+#[derive(RustcEncodable)]
+struct ReportedBody {
+    item: String,
+    violations: Vec<ReportedViolation>,
+}
+
+#[derive(RustcEncodable)]
+struct ReportedViolation {
+    kind: String,
+    description: String,
+    lvalue: Option<String>,
+    span: String,
+    context: String,
+}
+
+/// Writes the unsafe operations of every body in the crate to `path` as JSON,
+/// for `-Z unsafety-report`.
+///
+/// Closures are checked as part of the item containing them, and constants
+/// are only promoted out of a body after this check, so every operation is
+/// listed under the path of the item it is written in.
+pub fn write_unsafety_report<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, path: &str) {
+    let mut bodies = vec![];
+    for def_id in tcx.body_owners() {
+        if tcx.is_closure(def_id) {
+            continue;
+        }
+
+        let UnsafetyCheckResult {
+            violations,
+            permitted_violations,
+            ..
+        } = tcx.unsafety_check_result(def_id);
+        let mut all: Vec<_> = violations.iter().map(|violation| (violation, Safety::Safe))
+            .chain(permitted_violations.iter().map(|&(ref violation, safety)| {
+                (violation, safety)
+            }))
+            .collect();
+        all.sort_by_key(|&(violation, _)| violation.source_info.span.lo());
+
+        let violations = all.into_iter().map(|(violation, safety)| {
+            let loc = tcx.sess.codemap().lookup_char_pos(violation.source_info.span.lo());
+            let context = match safety {
+                Safety::Safe => "safe",
+                Safety::BuiltinUnsafe => "builtin_unsafe",
+                Safety::FnUnsafe => "unsafe_fn",
+                Safety::ExplicitUnsafe(_) => "unsafe_block",
+            };
+            ReportedViolation {
+                kind: format!("{:?}", violation.details),
+                description: violation.details.description().to_string(),
+                lvalue: violation.lvalue.map(|lvalue| lvalue.to_string()),
+                span: format!("{}:{}:{}", loc.file.name, loc.line, loc.col.0 + 1),
+                context: context.to_string(),
+            }
+        }).collect();
+
+        bodies.push(ReportedBody {
+            item: item_path::with_forced_absolute_paths(|| tcx.item_path_str(def_id)),
+            violations,
+        });
+    }
+
+    let result = json::encode(&bodies).map_err(|e| e.to_string()).and_then(|json| {
+        File::create(path)
+            .and_then(|mut file| writeln!(file, "{}", json))
+            .map_err(|e| e.to_string())
+    });
+    if let Err(e) = result {
+        tcx.sess.err(&format!("could not write unsafety report to `{}`: {}", path, e));
+    }
+}
@@ -24,8 +24,9 @@ use rustc_data_structures::fx::FxHashSet;
 use rustc::middle::region;
 use rustc::mir::{BasicBlock, Location, Mir, Rvalue, Statement, StatementKind};
 use rustc::mir::visit::{MutVisitor, Visitor, TyContext};
+use rustc::session::Session;
 use rustc::ty::{Ty, RegionKind, TyCtxt};
-use transform::{MirPass, MirSource};
+use transform::{MirOptContext, MirPass, MirSource};
 
 pub struct CleanEndRegions;
 
@@ -38,12 +39,15 @@ struct DeleteTrivialEndRegions<'a> {
 }
 
 impl MirPass for CleanEndRegions {
-    fn run_pass<'a, 'tcx>(&self,
-                          tcx: TyCtxt<'a, 'tcx, 'tcx>,
-                          _source: MirSource,
-                          mir: &mut Mir<'tcx>) {
-        if !tcx.sess.emit_end_regions() { return; }
+    fn is_enabled(&self, sess: &Session) -> bool {
+        sess.emit_end_regions()
+    }
 
+    fn run_pass_in_context<'a, 'tcx>(&self,
+                                     _tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                     _context: &MirOptContext<'tcx>,
+                                     _source: MirSource,
+                                     mir: &mut Mir<'tcx>) -> bool {
         let mut gather = GatherBorrowedRegions {
             seen_regions: FxHashSet()
         };
@@ -51,6 +55,7 @@ impl MirPass for CleanEndRegions {
 
         let mut delete = DeleteTrivialEndRegions { seen_regions: &mut gather.seen_regions };
         delete.visit_mir(mir);
+        true
     }
 }
 
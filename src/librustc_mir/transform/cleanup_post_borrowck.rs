@@ -0,0 +1,90 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! This module provides one pass, `CleanupPostBorrowck`, that removes
+//! the constructs MIR building only emits for the benefit of the borrow
+//! checker.
+//!
+//! It marks the boundary between "analysis MIR", as seen by borrowck and
+//! the other checks run on `mir_validated`, and "optimization MIR", as
+//! seen by the optimization passes and by trans. It therefore runs first
+//! thing in `optimized_mir`, independently of the optimization level.
+//!
+//! Two constructs are removed:
+//!
+//! - `FalseEdges` terminators, which make borrowck consider the match
+//!   arms that could have been taken, are replaced by a `Goto` to their
+//!   real target.
+//! - The shared borrows of a match scrutinee taken while a guard runs
+//!   (see `borrow_scrutinee_for_guard`) are only read by the reborrows
+//!   keeping them alive, which are never read themselves. Both are
+//!   replaced by `Nop`s.
+
+use rustc::mir::{Local, Lvalue, Mir, Rvalue, StatementKind, TerminatorKind};
+use rustc::mir::visit::LvalueContext;
+use rustc::ty::TyCtxt;
+use rustc_data_structures::fx::FxHashSet;
+use transform::{MirOptContext, MirPass, MirSource};
+use util::def_use::DefUseAnalysis;
+
+pub struct CleanupPostBorrowck;
+
+impl MirPass for CleanupPostBorrowck {
+    fn run_pass_in_context<'a, 'tcx>(&self,
+                                     _tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                     _context: &MirOptContext<'tcx>,
+                                     _source: MirSource,
+                                     mir: &mut Mir<'tcx>) -> bool {
+        // Removing a borrow which is never read can leave the borrow it
+        // reborrows unread as well, so go on until there are none left.
+        let mut def_use_analysis = DefUseAnalysis::new(mir);
+        loop {
+            def_use_analysis.clear_and_analyze(mir);
+            let unused_temps: FxHashSet<Local> = mir.temps_iter()
+                .filter(|&temp| is_only_stored(&def_use_analysis, temp))
+                .collect();
+
+            let mut removed_borrow = false;
+            for data in mir.basic_blocks_mut() {
+                for statement in &mut data.statements {
+                    let unused_borrow = match statement.kind {
+                        StatementKind::Assign(Lvalue::Local(local), Rvalue::Ref(..)) =>
+                            unused_temps.contains(&local),
+                        _ => false,
+                    };
+                    if unused_borrow {
+                        statement.make_nop();
+                        removed_borrow = true;
+                    }
+                }
+            }
+            if !removed_borrow {
+                break;
+            }
+        }
+
+        for data in mir.basic_blocks_mut() {
+            let terminator = data.terminator_mut();
+            if let TerminatorKind::FalseEdges { real_target, .. } = terminator.kind {
+                terminator.kind = TerminatorKind::Goto { target: real_target };
+            }
+        }
+        true
+    }
+}
+
+/// Whether `local` is assigned exactly once and never read.
+fn is_only_stored(def_use_analysis: &DefUseAnalysis, local: Local) -> bool {
+    let defs_and_uses = def_use_analysis.local_info(local).defs_and_uses;
+    defs_and_uses.len() == 1 && match defs_and_uses[0].context {
+        LvalueContext::Store => true,
+        _ => false,
+    }
+}
@@ -0,0 +1,983 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Constant propagation within straight-line code.
+//!
+//! This walks each basic block from the top, evaluating the right-hand side
+//! of each assignment to a local with the constant arithmetic of
+//! `rustc_const_math`, whenever all of its operands are known constants.
+//! Uses of locals with a known value are then replaced by that value, in
+//! the operands of later statements and of the terminator, including the
+//! conditions of `Assert` and `SwitchInt`, which `SimplifyBranches` can then
+//! turn into gotos.
+//!
+//! Integers, `bool`s and `char`s are tracked, as well as the fields of
+//! tuples and structs, but not those of unions, which share their storage,
+//! the discriminants of enums, and the length of the array a slice
+//! reference was made from, so that the `Len` of what it points to can be
+//! replaced by that length. To be sure to never evaluate anything the
+//! program doesn't:
+//!
+//! - values only flow into a block from its only predecessor, when that
+//!   ends in a `Goto`, an `Assert` or a `SwitchInt`, none of which writes to
+//!   a local, and nothing is known on entry to any other block; in
+//!   particular, nothing is known after a call;
+//! - locals that are borrowed anywhere are never tracked, as they could be
+//!   modified through the reference;
+//! - statics are never read, not even immutable ones without interior
+//!   mutability: a `static mut` or a static containing an `UnsafeCell` can
+//!   be modified at runtime, and a `#[thread_local]` one has a different
+//!   value in each thread;
+//! - inline assembly forgets about the locals it writes directly, which is
+//!   enough as the memory it writes otherwise can only belong to borrowed
+//!   locals, and writing to a part of a local that isn't a field forgets
+//!   about that local;
+//! - operations that would overflow, divide by zero or shift by too much
+//!   are never evaluated, and checks guarding them are left alone.
+//!
+//! Besides values, comparisons of a local with a constant are tracked as
+//! facts, from the `Assume` statements calls to the `assume` intrinsic are
+//! lowered to. A fact is made from the comparison the assumed `bool` was
+//! computed by, and holds for the local compared and for its copies, so
+//! that comparisons of them with other constants can be evaluated: once
+//! `x != 0` is assumed, `x == 0` is known to be `false`, which removes the
+//! division by zero check of `y / x`. Facts flow like values do, and those
+//! about locals that are never written nor borrowed, like most arguments,
+//! also hold in all the blocks dominated by the one they are assumed in.
+//!
+//! The values found are also used to report the `Assert`s that will always
+//! fail, with the `unconditional_panic` lint when the assert is reached on
+//! every call of the function, and with `const_err` otherwise, as well as
+//! the additions, subtractions and multiplications reached on every call
+//! that will always overflow, with the `arithmetic_overflow` lint, whether
+//! or not overflow checks are enabled. This part runs whatever the MIR
+//! optimization level, so that the lints don't depend on it; the MIR itself
+//! is only modified when that level is > 1.
+
+use rustc::lint::builtin::{ARITHMETIC_OVERFLOW, CONST_ERR, UNCONDITIONAL_PANIC};
+use rustc::middle::const_val::ConstVal;
+use rustc::mir::{AggregateKind, AssertMessage, BasicBlock, BinOp, CastKind, Constant, Field};
+use rustc::mir::{Literal, Local, Location, Lvalue, Mir, Operand, Projection, ProjectionElem};
+use rustc::mir::{Rvalue, START_BLOCK, Statement, StatementKind, Terminator, TerminatorKind};
+use rustc::mir::UnOp;
+use rustc::mir::traversal;
+use rustc::mir::visit::{LvalueContext, MutVisitor, Visitor};
+use rustc::ty::{self, Ty, TyCtxt};
+use rustc::ty::util::IntTypeExt;
+use rustc_const_math::ConstInt;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_data_structures::indexed_vec::IndexVec;
+use syntax_pos::Span;
+use transform::{MirOptContext, MirPass, MirSource};
+use util::inline_asm::AsmEffects;
+use util::liveness::{self, LocalSet};
+
+use std::cmp::Ordering;
+
+pub struct ConstProp;
+
+impl MirPass for ConstProp {
+    fn run_pass_in_context<'a, 'tcx>(&self,
+                                     tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                     context: &MirOptContext<'tcx>,
+                                     _source: MirSource,
+                                     mir: &mut Mir<'tcx>) -> bool {
+        // Don't run on constant MIR, because trans might not be able to
+        // evaluate the modified MIR.
+        // FIXME(eddyb) Remove check after miri is merged.
+        if context.is_const_context {
+            return false;
+        }
+
+        // Like copy propagation, this only modifies the MIR when the MIR
+        // optimization level is > 1.
+        let optimize = context.mir_opt_level > 1;
+
+        let borrowed = liveness::borrowed_locals(mir);
+        let unwritten = unwritten_locals(mir, &borrowed);
+        let unions = union_locals(mir);
+
+        let predecessors = mir.predecessors().clone();
+        let dominators = mir.dominators();
+        let blocks: Vec<_> = traversal::reverse_postorder(mir).map(|(block, _)| block).collect();
+        let mut known_on_exit = FxHashMap();
+        let mut assumed_in = FxHashMap();
+        // The blocks reached on every call, through a chain of blocks each
+        // having the next one as their only successor, cleanups aside.
+        let mut unconditional = FxHashSet();
+        let mut changed = false;
+        for block in blocks {
+            let known = if predecessors[block].len() == 1 {
+                known_on_exit.get(&predecessors[block][0]).cloned().unwrap_or_default()
+            } else {
+                FxHashMap()
+            };
+            if block == START_BLOCK {
+                unconditional.insert(block);
+            } else if predecessors[block].len() == 1 {
+                let pred = predecessors[block][0];
+                if unconditional.contains(&pred) &&
+                   only_normal_successor(mir, pred) == Some(block) {
+                    unconditional.insert(block);
+                }
+            }
+
+            let mut propagator = ConstPropagator {
+                tcx,
+                borrowed: &borrowed,
+                unwritten: &unwritten,
+                unions: &unions,
+                known,
+                assumed: vec![],
+            };
+            // What is assumed about the locals that are never written holds
+            // in all the blocks dominated by the one it is assumed in.
+            for dominator in dominators.dominators(block).skip(1) {
+                if let Some(assumed) = assumed_in.get(&dominator) {
+                    for &comparison in assumed {
+                        propagator.add_fact(comparison);
+                    }
+                }
+            }
+            // Only keep the facts assumed in this block itself.
+            propagator.assumed.clear();
+            changed |= propagator.propagate_in_block(mir, block, optimize,
+                                                     unconditional.contains(&block));
+            if !propagator.assumed.is_empty() {
+                assumed_in.insert(block, propagator.assumed);
+            }
+
+            match mir[block].terminator().kind {
+                TerminatorKind::Goto { .. } |
+                TerminatorKind::Assert { .. } |
+                TerminatorKind::SwitchInt { .. } => {
+                    known_on_exit.insert(block, propagator.known);
+                }
+                _ => {}
+            }
+        }
+        changed
+    }
+}
+
+/// What is known about the value of a local.
+#[derive(Clone, Default)]
+struct Known<'tcx> {
+    /// The value of the whole local, for scalars.
+    value: Option<&'tcx ty::Const<'tcx>>,
+    /// The values of the fields of a tuple or a struct.
+    fields: FxHashMap<Field, &'tcx ty::Const<'tcx>>,
+    /// The discriminant of an enum.
+    discr: Option<ConstInt>,
+    /// The length of the slice a reference or pointer points to, when it
+    /// was made by unsizing one to an array.
+    pointee_len: Option<&'tcx ty::Const<'tcx>>,
+    /// The local this one is a copy of, as long as neither was written.
+    copy_of: Option<Local>,
+    /// The comparison the value of a `bool` was computed by.
+    comparison: Option<Comparison<'tcx>>,
+    /// The comparisons with constants known to hold.
+    facts: Vec<(BinOp, &'tcx ty::Const<'tcx>)>,
+}
+
+/// The comparison `local op value` of a local with a constant.
+#[derive(Clone, Copy, PartialEq)]
+struct Comparison<'tcx> {
+    local: Local,
+    op: BinOp,
+    value: &'tcx ty::Const<'tcx>,
+}
+
+struct ConstPropagator<'a, 'tcx: 'a> {
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    borrowed: &'a LocalSet,
+    unwritten: &'a IndexVec<Local, bool>,
+    /// Whether each local is a union, whose fields share their storage.
+    unions: &'a IndexVec<Local, bool>,
+    known: FxHashMap<Local, Known<'tcx>>,
+    /// The facts assumed about locals that are never written.
+    assumed: Vec<Comparison<'tcx>>,
+}
+
+impl<'a, 'tcx> ConstPropagator<'a, 'tcx> {
+    /// Propagates the known values through `block`, returning whether it
+    /// replaced any use by a constant.
+    fn propagate_in_block(&mut self,
+                          mir: &mut Mir<'tcx>,
+                          block: BasicBlock,
+                          optimize: bool,
+                          unconditional: bool)
+                          -> bool {
+        let mut changed = false;
+        let num_statements = mir[block].statements.len();
+        for index in 0..num_statements {
+            let location = Location { block, statement_index: index };
+            if unconditional {
+                self.lint_overflow(mir, &mir[block].statements[index]);
+            }
+            if optimize {
+                changed |= self.replace_uses_in_statement(
+                    &mut mir.basic_blocks_mut()[block].statements[index], location);
+                changed |= self.fold_len(mir, location);
+            }
+            self.record_statement(mir, &mir[block].statements[index]);
+        }
+
+        if optimize {
+            let location = Location { block, statement_index: num_statements };
+            let terminator = mir.basic_blocks_mut()[block].terminator_mut();
+            changed |= self.replace_uses_in_terminator(terminator, location);
+        }
+        self.lint_failing_assert(mir, mir[block].terminator(), unconditional);
+        changed
+    }
+
+    /// Reports `statement` if it is an addition, subtraction or
+    /// multiplication of constants known to overflow, which panics with
+    /// overflow checks and wraps around without.
+    ///
+    /// Shifts are left to the `exceeding_bitshifts` lint.
+    fn lint_overflow(&self, mir: &Mir<'tcx>, statement: &Statement<'tcx>) {
+        let (op, left, right, checked) = match statement.kind {
+            StatementKind::Assign(_, Rvalue::CheckedBinaryOp(op, ref left, ref right)) => {
+                (op, left, right, true)
+            }
+            StatementKind::Assign(_, Rvalue::BinaryOp(op, ref left, ref right)) => {
+                (op, left, right, false)
+            }
+            _ => return,
+        };
+        let symbol = match op {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            _ => return,
+        };
+        let (l, r, ty) = match (self.eval_operand(left), self.eval_operand(right)) {
+            (Some(&ty::Const { val: ConstVal::Integral(l), ty }),
+             Some(&ty::Const { val: ConstVal::Integral(r), .. })) => (l, r, ty),
+            _ => return,
+        };
+        match self.eval_binop(op, left, right) {
+            Some((_, true)) => {}
+            _ => return,
+        }
+        let lint_root = match mir.lint_root(statement.source_info) {
+            Some(lint_root) => lint_root,
+            None => return,
+        };
+
+        let consequence = if checked {
+            "which will panic at runtime".to_string()
+        } else {
+            // Without overflow checks, the result is computed modulo 2^N.
+            let (l, r) = (l.to_u128_unchecked(), r.to_u128_unchecked());
+            let wrapped = match op {
+                BinOp::Add => l.wrapping_add(r),
+                BinOp::Sub => l.wrapping_sub(r),
+                _ => l.wrapping_mul(r),
+            };
+            match self.cast_int(ConstInt::U128(wrapped), ty) {
+                Some(wrapped) => format!("which will wrap around to `{}`", wrapped),
+                None => return,
+            }
+        };
+        let span = statement.source_info.span;
+        self.tcx.struct_span_lint_node(ARITHMETIC_OVERFLOW, lint_root, span,
+                                       "this arithmetic operation will overflow")
+            .span_label(span, format!("attempt to compute `{} {} {}`, {}",
+                                      l, symbol, r, consequence))
+            .emit();
+    }
+
+    /// Reports `terminator` if it is an `Assert` known to always fail.
+    fn lint_failing_assert(&self, mir: &Mir<'tcx>, terminator: &Terminator<'tcx>,
+                           unconditional: bool) {
+        let (cond, expected, msg) = match terminator.kind {
+            TerminatorKind::Assert { ref cond, expected, ref msg, .. } => (cond, expected, msg),
+            _ => return,
+        };
+        match self.eval_operand(cond) {
+            Some(&ty::Const { val: ConstVal::Bool(cond), .. }) if cond != expected => {}
+            _ => return,
+        }
+        // Asserts coming from inlined MIR are reported in their own function.
+        let lint_root = match mir.lint_root(terminator.source_info) {
+            Some(lint_root) => lint_root,
+            None => return,
+        };
+
+        let description = match *msg {
+            AssertMessage::BoundsCheck { ref len, ref index } => {
+                let len = self.eval_operand(len).and_then(|len| len.val.to_const_int());
+                let index = self.eval_operand(index).and_then(|index| index.val.to_const_int());
+                match (len, index) {
+                    (Some(len), Some(index)) => {
+                        format!("index out of bounds: the len is {} but the index is {}",
+                                len.to_u128_unchecked(), index.to_u128_unchecked())
+                    }
+                    _ => "index out of bounds".to_string(),
+                }
+            }
+            _ => msg.description().into_owned(),
+        };
+        let lint = if unconditional { UNCONDITIONAL_PANIC } else { CONST_ERR };
+        self.tcx.lint_node(lint, lint_root, terminator.source_info.span,
+                           &format!("this operation will panic at runtime: {}", description));
+    }
+
+    fn replace_uses_in_statement(&self, statement: &mut Statement<'tcx>, location: Location)
+                                 -> bool {
+        let span = statement.source_info.span;
+        let mut replace = ReplaceUses {
+            known: &self.known,
+            unions: self.unions,
+            span,
+            replaced: false,
+        };
+        if let StatementKind::Assign(_, ref mut rvalue) = statement.kind {
+            replace.visit_rvalue(rvalue, location);
+        }
+        replace.replaced
+    }
+
+    fn replace_uses_in_terminator(&self, terminator: &mut Terminator<'tcx>, location: Location)
+                                  -> bool {
+        let span = terminator.source_info.span;
+        let mut replace = ReplaceUses {
+            known: &self.known,
+            unions: self.unions,
+            span,
+            replaced: false,
+        };
+        match terminator.kind {
+            TerminatorKind::SwitchInt { ref mut discr, .. } => {
+                replace.visit_operand(discr, location);
+            }
+            TerminatorKind::Assert { ref mut cond, .. } => {
+                replace.visit_operand(cond, location);
+            }
+            TerminatorKind::Call { ref mut args, .. } => {
+                for arg in args {
+                    replace.visit_operand(arg, location);
+                }
+            }
+            _ => {}
+        }
+        replace.replaced
+    }
+
+    /// Replaces the `Len` assigned by the statement at `location` by its
+    /// value, if that is known, returning whether it did.
+    fn fold_len(&self, mir: &mut Mir<'tcx>, location: Location) -> bool {
+        let value = match mir[location.block].statements[location.statement_index].kind {
+            StatementKind::Assign(_, ref rvalue @ Rvalue::Len(_)) => {
+                self.eval_rvalue(mir, rvalue).and_then(|known| known.value)
+            }
+            _ => None,
+        };
+        if let Some(value) = value {
+            let statement =
+                &mut mir.basic_blocks_mut()[location.block].statements[location.statement_index];
+            let span = statement.source_info.span;
+            if let StatementKind::Assign(_, ref mut rvalue) = statement.kind {
+                *rvalue = Rvalue::Use(Operand::Constant(box Constant {
+                    span,
+                    ty: value.ty,
+                    literal: Literal::Value { value },
+                }));
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Updates what is known about the locals written by `statement`.
+    fn record_statement(&mut self, mir: &Mir<'tcx>, statement: &Statement<'tcx>) {
+        match statement.kind {
+            StatementKind::Assign(ref lvalue, ref rvalue) => {
+                let known = self.eval_rvalue(mir, rvalue);
+                match *lvalue {
+                    Lvalue::Local(local) => self.set(local, known),
+                    Lvalue::Projection(box Projection {
+                        base: Lvalue::Local(local),
+                        elem: ProjectionElem::Field(field, _),
+                    }) => {
+                        self.set_field(local, field, known.and_then(|known| known.value));
+                    }
+                    _ => self.forget_base_of(lvalue),
+                }
+            }
+            StatementKind::SetDiscriminant { lvalue: Lvalue::Local(local), variant_index } => {
+                let known = self.discriminant(mir.local_decls[local].ty, variant_index)
+                    .map(|discr| Known { discr: Some(discr), ..Known::default() });
+                self.set(local, known);
+            }
+            StatementKind::SetDiscriminant { ref lvalue, .. } => self.forget_base_of(lvalue),
+            StatementKind::StorageLive(local) |
+            StatementKind::StorageDead(local) => self.forget(local),
+            StatementKind::InlineAsm { .. } => {
+                let effects = AsmEffects::of(&statement.kind).unwrap();
+                for output in effects.outputs.iter().filter(|output| !output.is_indirect) {
+                    self.forget_base_of(output.lvalue);
+                }
+            }
+            StatementKind::Assume(ref cond) => self.assume(cond),
+            StatementKind::Validate(..) |
+            StatementKind::EndRegion(_) |
+            StatementKind::Nop => {}
+        }
+    }
+
+    fn set(&mut self, local: Local, known: Option<Known<'tcx>>) {
+        match known {
+            Some(known) if !self.borrowed.contains(&local) => {
+                self.forget_relations_to(local);
+                self.known.insert(local, known);
+            }
+            _ => self.forget(local),
+        }
+    }
+
+    fn set_field(&mut self, local: Local, field: Field, value: Option<&'tcx ty::Const<'tcx>>) {
+        // Writing a field of a union overwrites the other fields.
+        if self.unions[local] {
+            return self.forget(local);
+        }
+        if self.borrowed.contains(&local) {
+            return;
+        }
+        self.forget_relations_to(local);
+        let known = self.known.entry(local).or_insert_with(Known::default);
+        known.value = None;
+        known.comparison = None;
+        known.facts.clear();
+        match value {
+            Some(value) => {
+                known.fields.insert(field, value);
+            }
+            None => {
+                known.fields.remove(&field);
+            }
+        }
+    }
+
+    /// Forgets about the local `lvalue` is a part of, unless it is only
+    /// reached through a dereference, and so isn't a part of that local.
+    fn forget_base_of(&mut self, mut lvalue: &Lvalue<'tcx>) {
+        while let Lvalue::Projection(ref proj) = *lvalue {
+            if let ProjectionElem::Deref = proj.elem {
+                return;
+            }
+            lvalue = &proj.base;
+        }
+        if let Lvalue::Local(local) = *lvalue {
+            self.forget(local);
+        }
+    }
+
+    /// Forgets about `local`, which is being written.
+    fn forget(&mut self, local: Local) {
+        self.known.remove(&local);
+        self.forget_relations_to(local);
+    }
+
+    /// Forgets that other locals are copies of `local`, or were compared
+    /// with it, as `local` is being written.
+    fn forget_relations_to(&mut self, local: Local) {
+        for known in self.known.values_mut() {
+            if known.copy_of == Some(local) {
+                known.copy_of = None;
+            }
+            if known.comparison.map(|comparison| comparison.local) == Some(local) {
+                known.comparison = None;
+            }
+        }
+    }
+
+    /// The local `local` is a copy of, or `local` itself.
+    fn original(&self, local: Local) -> Local {
+        self.known.get(&local).and_then(|known| known.copy_of).unwrap_or(local)
+    }
+
+    /// Records that `cond` is true, as well as the comparison it was
+    /// computed by, if any.
+    fn assume(&mut self, cond: &Operand<'tcx>) {
+        let local = match operand_local(cond) {
+            Some(local) => local,
+            None => return,
+        };
+        let comparison = self.known.get(&local).and_then(|known| known.comparison);
+        let true_ = self.constant(ConstVal::Bool(true), self.tcx.types.bool);
+        self.set(local, Some(Known { value: Some(true_), ..Known::default() }));
+        if let Some(comparison) = comparison {
+            self.add_fact(comparison);
+        }
+    }
+
+    /// Records that `comparison` holds, for its local and all the copies
+    /// of it.
+    fn add_fact(&mut self, comparison: Comparison<'tcx>) {
+        let Comparison { local, op, value } = comparison;
+        if self.borrowed.contains(&local) {
+            return;
+        }
+        if self.unwritten[local] && !self.assumed.contains(&comparison) {
+            self.assumed.push(comparison);
+        }
+        self.known.entry(local).or_insert_with(Known::default);
+        for (&copy, known) in self.known.iter_mut() {
+            if copy != local && known.copy_of != Some(local) {
+                continue;
+            }
+            if !known.facts.contains(&(op, value)) {
+                known.facts.push((op, value));
+            }
+            if op == BinOp::Eq {
+                known.value = Some(value);
+            }
+        }
+    }
+
+    fn discriminant(&self, ty: Ty<'tcx>, variant_index: usize) -> Option<ConstInt> {
+        match ty.sty {
+            ty::TyAdt(adt_def, _) if adt_def.is_enum() => {
+                Some(adt_def.discriminant_for_variant(self.tcx, variant_index))
+            }
+            _ => None,
+        }
+    }
+
+    fn constant(&self, val: ConstVal<'tcx>, ty: Ty<'tcx>) -> &'tcx ty::Const<'tcx> {
+        self.tcx.mk_const(ty::Const { val, ty })
+    }
+
+    fn eval_rvalue(&self, mir: &Mir<'tcx>, rvalue: &Rvalue<'tcx>) -> Option<Known<'tcx>> {
+        let scalar = |value| Some(Known { value: Some(value), ..Known::default() });
+        match *rvalue {
+            Rvalue::Use(Operand::Copy(Lvalue::Local(local))) |
+            Rvalue::Use(Operand::Move(Lvalue::Local(local))) => {
+                if self.borrowed.contains(&local) {
+                    return None;
+                }
+                let mut known = self.known.get(&local).cloned().unwrap_or_default();
+                known.copy_of = Some(self.original(local));
+                Some(known)
+            }
+            Rvalue::Use(ref operand) => self.eval_operand(operand).and_then(scalar),
+            Rvalue::BinaryOp(op, ref left, ref right) => {
+                match self.eval_binop(op, left, right) {
+                    Some((value, false)) => scalar(value),
+                    Some((_, true)) => None,
+                    None => {
+                        // Compare the original local, which the `bool` may
+                        // outlive: the operand is usually a temporary copy.
+                        self.comparison(op, left, right).map(|comparison| Known {
+                            comparison: Some(Comparison {
+                                local: self.original(comparison.local),
+                                ..comparison
+                            }),
+                            ..Known::default()
+                        })
+                    }
+                }
+            }
+            Rvalue::CheckedBinaryOp(op, ref left, ref right) => {
+                // Leave overflowing operations alone, so that the checks
+                // guarding them still get to report the overflow.
+                let value = match self.eval_binop(op, left, right) {
+                    Some((value, false)) => value,
+                    _ => return None,
+                };
+                let mut fields = FxHashMap();
+                fields.insert(Field::new(0), value);
+                fields.insert(Field::new(1),
+                              self.constant(ConstVal::Bool(false), self.tcx.types.bool));
+                Some(Known { fields, ..Known::default() })
+            }
+            Rvalue::UnaryOp(op, ref operand) => {
+                let operand = match self.eval_operand(operand) {
+                    Some(operand) => operand,
+                    None => return None,
+                };
+                let val = match (op, operand.val) {
+                    (UnOp::Not, ConstVal::Bool(b)) => Ok(ConstVal::Bool(!b)),
+                    (UnOp::Not, ConstVal::Integral(i)) => (!i).map(ConstVal::Integral),
+                    (UnOp::Neg, ConstVal::Integral(i)) => (-i).map(ConstVal::Integral),
+                    _ => return None,
+                };
+                val.ok().and_then(|val| scalar(self.constant(val, operand.ty)))
+            }
+            Rvalue::Cast(CastKind::Unsize, ref operand, _) => {
+                let pointee = operand.ty(mir, self.tcx).builtin_deref(true, ty::NoPreference);
+                match pointee.map(|mt| &mt.ty.sty) {
+                    Some(&ty::TyArray(_, len)) => {
+                        match len.val {
+                            ConstVal::Integral(_) => {
+                                Some(Known { pointee_len: Some(len), ..Known::default() })
+                            }
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                }
+            }
+            Rvalue::Cast(CastKind::Misc, ref operand, ty) => {
+                self.eval_operand(operand)
+                    .and_then(|operand| operand.val.to_const_int())
+                    .and_then(|val| self.cast_int(val, ty))
+                    .and_then(|val| scalar(self.constant(ConstVal::Integral(val), ty)))
+            }
+            Rvalue::Aggregate(ref kind, ref operands) => {
+                match **kind {
+                    AggregateKind::Tuple => {}
+                    AggregateKind::Adt(adt_def, variant_index, _, None) => {
+                        if adt_def.is_enum() {
+                            let discr = adt_def.discriminant_for_variant(self.tcx, variant_index);
+                            return Some(Known { discr: Some(discr), ..Known::default() });
+                        }
+                    }
+                    _ => return None,
+                }
+                let fields = operands.iter().enumerate().filter_map(|(i, operand)| {
+                    self.eval_operand(operand).map(|value| (Field::new(i), value))
+                }).collect();
+                Some(Known { fields, ..Known::default() })
+            }
+            Rvalue::Len(ref lvalue) => {
+                if let Lvalue::Projection(box Projection {
+                    base: Lvalue::Local(local),
+                    elem: ProjectionElem::Deref,
+                }) = *lvalue {
+                    if let Some(len) = self.known.get(&local).and_then(|known| known.pointee_len) {
+                        return scalar(len);
+                    }
+                }
+                match lvalue.ty(mir, self.tcx).to_ty(self.tcx).sty {
+                    ty::TyArray(_, len) => {
+                        match len.val {
+                            ConstVal::Integral(_) => scalar(len),
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                }
+            }
+            Rvalue::Discriminant(Lvalue::Local(local)) => {
+                self.known.get(&local).and_then(|known| known.discr).and_then(|discr| {
+                    let ty = discr.int_type().to_ty(self.tcx);
+                    scalar(self.constant(ConstVal::Integral(discr), ty))
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// The value of `operand`, if it is a constant integer, `bool` or `char`.
+    fn eval_operand(&self, operand: &Operand<'tcx>) -> Option<&'tcx ty::Const<'tcx>> {
+        match *operand {
+            Operand::Constant(box Constant { literal: Literal::Value { value }, .. }) => {
+                match value.val {
+                    ConstVal::Integral(_) | ConstVal::Bool(_) | ConstVal::Char(_) => Some(value),
+                    _ => None,
+                }
+            }
+            Operand::Constant(_) => None,
+            Operand::Copy(ref lvalue) | Operand::Move(ref lvalue) => {
+                known_value(&self.known, self.unions, lvalue)
+            }
+        }
+    }
+
+    /// Evaluates `left op right`, returning the result and whether the
+    /// operation overflowed, or `None` if it can't be evaluated.
+    fn eval_binop(&self, op: BinOp, left: &Operand<'tcx>, right: &Operand<'tcx>)
+                  -> Option<(&'tcx ty::Const<'tcx>, bool)> {
+        match (self.eval_operand(left), self.eval_operand(right)) {
+            (Some(left), Some(right)) => self.eval_const_binop(op, left, right),
+            _ => {
+                self.eval_with_facts(op, left, right)
+                    .map(|value| (self.constant(ConstVal::Bool(value), self.tcx.types.bool), false))
+            }
+        }
+    }
+
+    /// Evaluates `left op right` on constants, like `eval_binop`.
+    fn eval_const_binop(&self,
+                        op: BinOp,
+                        left: &'tcx ty::Const<'tcx>,
+                        right: &'tcx ty::Const<'tcx>)
+                        -> Option<(&'tcx ty::Const<'tcx>, bool)> {
+        let bool_ty = self.tcx.types.bool;
+        match (left.val, right.val) {
+            (ConstVal::Bool(l), ConstVal::Bool(r)) => {
+                let val = match op {
+                    BinOp::BitAnd => l & r,
+                    BinOp::BitOr => l | r,
+                    BinOp::BitXor => l ^ r,
+                    BinOp::Eq => l == r,
+                    BinOp::Ne => l != r,
+                    BinOp::Lt => l < r,
+                    BinOp::Le => l <= r,
+                    BinOp::Gt => l > r,
+                    BinOp::Ge => l >= r,
+                    _ => return None,
+                };
+                Some((self.constant(ConstVal::Bool(val), bool_ty), false))
+            }
+            (ConstVal::Char(l), ConstVal::Char(r)) => {
+                let val = match op {
+                    BinOp::Eq => l == r,
+                    BinOp::Ne => l != r,
+                    BinOp::Lt => l < r,
+                    BinOp::Le => l <= r,
+                    BinOp::Gt => l > r,
+                    BinOp::Ge => l >= r,
+                    _ => return None,
+                };
+                Some((self.constant(ConstVal::Bool(val), bool_ty), false))
+            }
+            (ConstVal::Integral(l), ConstVal::Integral(r)) => {
+                let result = match op {
+                    BinOp::Add => l + r,
+                    BinOp::Sub => l - r,
+                    BinOp::Mul => l * r,
+                    BinOp::Div => l / r,
+                    BinOp::Rem => l % r,
+                    BinOp::BitAnd => l & r,
+                    BinOp::BitOr => l | r,
+                    BinOp::BitXor => l ^ r,
+                    BinOp::Shl => l << r,
+                    BinOp::Shr => l >> r,
+                    BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => {
+                        let ordering = match l.try_cmp(r) {
+                            Ok(ordering) => ordering,
+                            Err(_) => return None,
+                        };
+                        let val = match op {
+                            BinOp::Eq => ordering == Ordering::Equal,
+                            BinOp::Ne => ordering != Ordering::Equal,
+                            BinOp::Lt => ordering == Ordering::Less,
+                            BinOp::Le => ordering != Ordering::Greater,
+                            BinOp::Gt => ordering == Ordering::Greater,
+                            _ => ordering != Ordering::Less,
+                        };
+                        return Some((self.constant(ConstVal::Bool(val), bool_ty), false));
+                    }
+                    BinOp::Offset => return None,
+                };
+                match result {
+                    Ok(val) => Some((self.constant(ConstVal::Integral(val), left.ty), false)),
+                    // The value doesn't matter, as overflowing results are
+                    // never propagated.
+                    Err(_) => Some((left, true)),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// The comparison `left op right`, if it compares a local with a
+    /// constant, in that order.
+    fn comparison(&self, op: BinOp, left: &Operand<'tcx>, right: &Operand<'tcx>)
+                  -> Option<Comparison<'tcx>> {
+        let (local, op, value) = match (self.eval_operand(left), self.eval_operand(right)) {
+            (None, Some(value)) => (operand_local(left), op, value),
+            (Some(value), None) => (operand_local(right), reverse_comparison(op), value),
+            _ => return None,
+        };
+        match local {
+            Some(local) if is_comparison(op) && !self.borrowed.contains(&local) => {
+                Some(Comparison { local, op, value })
+            }
+            _ => None,
+        }
+    }
+
+    /// Evaluates the comparison `left op right` of a local with a constant
+    /// from the facts known about the local.
+    fn eval_with_facts(&self, op: BinOp, left: &Operand<'tcx>, right: &Operand<'tcx>)
+                       -> Option<bool> {
+        let Comparison { local, op, value } = match self.comparison(op, left, right) {
+            Some(comparison) => comparison,
+            None => return None,
+        };
+        let facts = match self.known.get(&local) {
+            Some(known) => &known.facts,
+            None => return None,
+        };
+        for &(fact_op, fact_value) in facts {
+            if fact_value == value {
+                if fact_op == op {
+                    return Some(true);
+                }
+                if fact_op == negate_comparison(op) {
+                    return Some(false);
+                }
+            }
+            // The local isn't equal to a value that contradicts a fact.
+            if op == BinOp::Eq || op == BinOp::Ne {
+                if let Some((&ty::Const { val: ConstVal::Bool(false), .. }, _)) =
+                       self.eval_const_binop(fact_op, value, fact_value) {
+                    return Some(op == BinOp::Ne);
+                }
+            }
+        }
+        None
+    }
+
+    fn cast_int(&self, val: ConstInt, ty: Ty<'tcx>) -> Option<ConstInt> {
+        let v = val.to_u128_unchecked();
+        let target = &self.tcx.sess.target;
+        match ty.sty {
+            ty::TyInt(int_ty) => {
+                Some(ConstInt::new_signed_truncating(v as i128, int_ty, target.isize_ty))
+            }
+            ty::TyUint(uint_ty) => {
+                Some(ConstInt::new_unsigned_truncating(v, uint_ty, target.usize_ty))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The successor of `block` that isn't a cleanup block, if there is only one.
+fn only_normal_successor(mir: &Mir, block: BasicBlock) -> Option<BasicBlock> {
+    let successors = mir[block].terminator().successors();
+    let mut normal = successors.iter().filter(|&&succ| !mir[succ].is_cleanup);
+    match (normal.next(), normal.next()) {
+        (Some(&succ), None) => Some(succ),
+        _ => None,
+    }
+}
+
+/// The local `operand` copies or moves, if it is a whole local.
+fn operand_local(operand: &Operand) -> Option<Local> {
+    match *operand {
+        Operand::Copy(Lvalue::Local(local)) | Operand::Move(Lvalue::Local(local)) => Some(local),
+        _ => None,
+    }
+}
+
+fn is_comparison(op: BinOp) -> bool {
+    match op {
+        BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => true,
+        _ => false,
+    }
+}
+
+/// The comparison `op'` such that `a op b` is `b op' a`.
+fn reverse_comparison(op: BinOp) -> BinOp {
+    match op {
+        BinOp::Lt => BinOp::Gt,
+        BinOp::Le => BinOp::Ge,
+        BinOp::Gt => BinOp::Lt,
+        BinOp::Ge => BinOp::Le,
+        op => op,
+    }
+}
+
+/// The comparison `op'` such that `a op' b` is `!(a op b)`.
+fn negate_comparison(op: BinOp) -> BinOp {
+    match op {
+        BinOp::Eq => BinOp::Ne,
+        BinOp::Ne => BinOp::Eq,
+        BinOp::Lt => BinOp::Ge,
+        BinOp::Le => BinOp::Gt,
+        BinOp::Gt => BinOp::Le,
+        BinOp::Ge => BinOp::Lt,
+        op => op,
+    }
+}
+
+/// Finds the locals that are never written nor borrowed, so that their
+/// value doesn't change once the function is entered.
+fn unwritten_locals(mir: &Mir, borrowed: &LocalSet) -> IndexVec<Local, bool> {
+    struct FindWrites {
+        written: IndexVec<Local, bool>,
+    }
+
+    impl<'tcx> Visitor<'tcx> for FindWrites {
+        fn visit_local(&mut self, &local: &Local, context: LvalueContext<'tcx>, _: Location) {
+            if context.is_mutating_use() || context.is_storage_marker() {
+                self.written[local] = true;
+            }
+        }
+    }
+
+    let mut finder = FindWrites { written: IndexVec::from_elem(false, &mir.local_decls) };
+    finder.visit_mir(mir);
+    finder.written.iter_enumerated()
+        .map(|(local, &written)| !written && !borrowed.contains(&local))
+        .collect()
+}
+
+/// Whether each local of `mir` is a union.
+fn union_locals(mir: &Mir) -> IndexVec<Local, bool> {
+    mir.local_decls.iter().map(|decl| {
+        match decl.ty.sty {
+            ty::TyAdt(adt_def, _) => adt_def.is_union(),
+            _ => false,
+        }
+    }).collect()
+}
+
+/// The known value of `lvalue`, if it is a local or a field of one that
+/// isn't a union.
+fn known_value<'tcx>(known: &FxHashMap<Local, Known<'tcx>>,
+                     unions: &IndexVec<Local, bool>,
+                     lvalue: &Lvalue<'tcx>)
+                     -> Option<&'tcx ty::Const<'tcx>> {
+    match *lvalue {
+        Lvalue::Local(local) => known.get(&local).and_then(|known| known.value),
+        Lvalue::Projection(ref proj) => {
+            match (&proj.base, &proj.elem) {
+                (&Lvalue::Local(local), &ProjectionElem::Field(..)) if unions[local] => None,
+                (&Lvalue::Local(local), &ProjectionElem::Field(field, _)) => {
+                    known.get(&local).and_then(|known| known.fields.get(&field).cloned())
+                }
+                _ => None,
+            }
+        }
+        // See the module documentation for why statics are never read.
+        Lvalue::Static(_) => None,
+    }
+}
+
+/// Replaces the operands with a known value by a constant.
+struct ReplaceUses<'a, 'tcx: 'a> {
+    known: &'a FxHashMap<Local, Known<'tcx>>,
+    unions: &'a IndexVec<Local, bool>,
+    span: Span,
+    /// Whether an operand was replaced.
+    replaced: bool,
+}
+
+impl<'a, 'tcx> MutVisitor<'tcx> for ReplaceUses<'a, 'tcx> {
+    fn visit_operand(&mut self, operand: &mut Operand<'tcx>, location: Location) {
+        let value = match *operand {
+            Operand::Copy(ref lvalue) | Operand::Move(ref lvalue) => {
+                known_value(self.known, self.unions, lvalue)
+            }
+            Operand::Constant(_) => None,
+        };
+        match value {
+            Some(value) => {
+                *operand = Operand::Constant(box Constant {
+                    span: self.span,
+                    ty: value.ty,
+                    literal: Literal::Value { value },
+                });
+                self.replaced = true;
+            }
+            None => self.super_operand(operand, location),
+        }
+    }
+}
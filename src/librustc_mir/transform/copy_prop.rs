@@ -29,139 +29,149 @@
 //! (non-mutating) use of `SRC`. These restrictions are conservative and may be relaxed in the
 //! future.
 
-use rustc::hir;
+use dataflow::{DataflowAnalysis, DataflowResults, MaybeObservedLocals};
 use rustc::mir::{Constant, Local, LocalKind, Location, Lvalue, Mir, Operand, Rvalue, StatementKind};
-use rustc::mir::visit::MutVisitor;
+use rustc::mir::visit::{LvalueContext, MutVisitor};
 use rustc::ty::TyCtxt;
-use transform::{MirPass, MirSource};
-use util::def_use::DefUseAnalysis;
+use rustc_data_structures::indexed_set::IdxSetBuf;
+use transform::{MirOptContext, MirPass, MirSource};
+use util::def_use::{DefUseAnalysis, Use};
+use util::journal::Journal;
 
 pub struct CopyPropagation;
 
 impl MirPass for CopyPropagation {
-    fn run_pass<'a, 'tcx>(&self,
-                          tcx: TyCtxt<'a, 'tcx, 'tcx>,
-                          source: MirSource,
-                          mir: &mut Mir<'tcx>) {
+    fn run_pass_in_context<'a, 'tcx>(&self,
+                                     tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                     context: &MirOptContext<'tcx>,
+                                     source: MirSource,
+                                     mir: &mut Mir<'tcx>) -> bool {
         // Don't run on constant MIR, because trans might not be able to
         // evaluate the modified MIR.
         // FIXME(eddyb) Remove check after miri is merged.
-        let id = tcx.hir.as_local_node_id(source.def_id).unwrap();
-        match (tcx.hir.body_owner_kind(id), source.promoted) {
-            (_, Some(_)) |
-            (hir::BodyOwnerKind::Const, _) |
-            (hir::BodyOwnerKind::Static(_), _) => return,
-
-            (hir::BodyOwnerKind::Fn, _) => {
-                if tcx.is_const_fn(source.def_id) {
-                    // Don't run on const functions, as, again, trans might not be able to evaluate
-                    // the optimized IR.
-                    return
-                }
-            }
-        }
-
-        // We only run when the MIR optimization level is > 1.
-        // This avoids a slow pass, and messing up debug info.
-        if tcx.sess.opts.debugging_opts.mir_opt_level <= 1 {
-            return;
+        if context.is_const_context {
+            return false;
         }
 
+        let journal = Journal::new(tcx, source, &self.name());
         let mut def_use_analysis = DefUseAnalysis::new(mir);
+        let mut changed_any = false;
         loop {
-            def_use_analysis.analyze(mir);
+            def_use_analysis.clear_and_analyze(mir);
 
-            if eliminate_self_assignments(mir, &def_use_analysis) {
-                def_use_analysis.analyze(mir);
+            if eliminate_self_assignments(mir, &def_use_analysis, &journal) {
+                def_use_analysis.clear_and_analyze(mir);
+                changed_any = true;
             }
 
-            let mut changed = false;
-            for dest_local in mir.local_decls.indices() {
-                debug!("Considering destination local: {:?}", dest_local);
-
-                let action;
-                let location;
-                {
-                    // The destination must have exactly one def.
-                    let dest_use_info = def_use_analysis.local_info(dest_local);
-                    let dest_def_count = dest_use_info.def_count_not_including_drop();
-                    if dest_def_count == 0 {
-                        debug!("  Can't copy-propagate local: dest {:?} undefined",
-                               dest_local);
-                        continue
-                    }
-                    if dest_def_count > 1 {
-                        debug!("  Can't copy-propagate local: dest {:?} defined {} times",
-                               dest_local,
-                               dest_use_info.def_count());
-                        continue
-                    }
-                    if dest_use_info.use_count() == 0 {
-                        debug!("  Can't copy-propagate local: dest {:?} unused",
-                               dest_local);
-                        continue
-                    }
-                    // Conservatively gives up if the dest is an argument,
-                    // because there may be uses of the original argument value.
-                    if mir.local_kind(dest_local) == LocalKind::Arg {
-                        debug!("  Can't copy-propagate local: dest {:?} (argument)",
-                            dest_local);
-                        continue;
-                    }
-                    let dest_lvalue_def = dest_use_info.defs_not_including_drop().next().unwrap();
-                    location = dest_lvalue_def.location;
-
-                    let basic_block = &mir[location.block];
-                    let statement_index = location.statement_index;
-                    let statement = match basic_block.statements.get(statement_index) {
-                        Some(statement) => statement,
-                        None => {
-                            debug!("  Can't copy-propagate local: used in terminator");
-                            continue
-                        }
-                    };
+            let propagation = find_propagation(mir, &def_use_analysis);
+
+            let changed = match propagation {
+                Some((action, dest_local, location)) => {
+                    action.perform(mir, &def_use_analysis, &journal, dest_local, location)
+                }
+                None => false,
+            };
+            // FIXME(pcwalton): Update the use-def chains to delete the instructions instead of
+            // regenerating the chains.
+            if !changed {
+                break
+            }
+            changed_any = true;
+        }
+        changed_any
+    }
+}
 
-                    // That use of the source must be an assignment.
-                    match statement.kind {
-                        StatementKind::Assign(Lvalue::Local(local), Rvalue::Use(ref operand)) if
-                                local == dest_local => {
-                            let maybe_action = match *operand {
-                                Operand::Copy(ref src_lvalue) |
-                                Operand::Move(ref src_lvalue) => {
-                                    Action::local_copy(&mir, &def_use_analysis, src_lvalue)
-                                }
-                                Operand::Constant(ref src_constant) => {
-                                    Action::constant(src_constant)
-                                }
-                            };
-                            match maybe_action {
-                                Some(this_action) => action = this_action,
-                                None => continue,
-                            }
+/// Finds a propagation to perform, as an action with the destination
+/// local and the location of the assignment to it.
+fn find_propagation<'tcx>(mir: &Mir<'tcx>, def_use_analysis: &DefUseAnalysis<'tcx>)
+                          -> Option<(Action<'tcx>, Local, Location)> {
+    // Which locals may be observed through a borrow, computed when a
+    // candidate first needs it. The body doesn't change until the
+    // propagation found here is performed.
+    let mut observed = None;
+    for dest_local in mir.local_decls.indices() {
+        debug!("Considering destination local: {:?}", dest_local);
+
+        let action;
+        let location;
+        {
+            // The destination must have exactly one def.
+            let dest_use_info = def_use_analysis.local_info(dest_local);
+            let dest_def_count = dest_use_info.def_count_not_including_drop();
+            if dest_def_count == 0 {
+                debug!("  Can't copy-propagate local: dest {:?} undefined",
+                       dest_local);
+                continue
+            }
+            if dest_def_count > 1 {
+                debug!("  Can't copy-propagate local: dest {:?} defined {} times",
+                       dest_local,
+                       dest_use_info.def_count());
+                continue
+            }
+            if dest_use_info.use_count() == 0 {
+                debug!("  Can't copy-propagate local: dest {:?} unused",
+                       dest_local);
+                continue
+            }
+            // Conservatively gives up if the dest is an argument,
+            // because there may be uses of the original argument value.
+            if mir.local_kind(dest_local) == LocalKind::Arg {
+                debug!("  Can't copy-propagate local: dest {:?} (argument)",
+                    dest_local);
+                continue;
+            }
+            let dest_lvalue_def = dest_use_info.defs_not_including_drop().next().unwrap();
+            location = dest_lvalue_def.location;
+
+            let basic_block = &mir[location.block];
+            let statement_index = location.statement_index;
+            let statement = match basic_block.statements.get(statement_index) {
+                Some(statement) => statement,
+                None => {
+                    debug!("  Can't copy-propagate local: used in terminator");
+                    continue
+                }
+            };
+
+            // That use of the source must be an assignment.
+            match statement.kind {
+                StatementKind::Assign(Lvalue::Local(local), Rvalue::Use(ref operand)) if
+                        local == dest_local => {
+                    let maybe_action = match *operand {
+                        Operand::Copy(ref src_lvalue) |
+                        Operand::Move(ref src_lvalue) => {
+                            Action::local_copy(mir, def_use_analysis, &mut observed,
+                                               src_lvalue, dest_local, location)
                         }
-                        _ => {
-                            debug!("  Can't copy-propagate local: source use is not an \
-                                    assignment");
-                            continue
+                        Operand::Constant(ref src_constant) => {
+                            Action::constant(src_constant)
                         }
+                    };
+                    match maybe_action {
+                        Some(this_action) => action = this_action,
+                        None => continue,
                     }
                 }
-
-                changed = action.perform(mir, &def_use_analysis, dest_local, location) || changed;
-                // FIXME(pcwalton): Update the use-def chains to delete the instructions instead of
-                // regenerating the chains.
-                break
-            }
-            if !changed {
-                break
+                _ => {
+                    debug!("  Can't copy-propagate local: source use is not an \
+                            assignment");
+                    continue
+                }
             }
         }
+
+        return Some((action, dest_local, location))
     }
+    None
 }
 
 fn eliminate_self_assignments<'tcx>(
     mir: &mut Mir<'tcx>,
     def_use_analysis: &DefUseAnalysis<'tcx>,
+    journal: &Journal,
 ) -> bool {
     let mut changed = false;
 
@@ -189,6 +199,7 @@ fn eliminate_self_assignments<'tcx>(
             }
             debug!("Deleting a self-assignment for {:?}", dest_local);
             mir.make_statement_nop(location);
+            journal.nopped(location);
             changed = true;
         }
     }
@@ -202,8 +213,13 @@ enum Action<'tcx> {
 }
 
 impl<'tcx> Action<'tcx> {
-    fn local_copy(mir: &Mir<'tcx>, def_use_analysis: &DefUseAnalysis, src_lvalue: &Lvalue<'tcx>)
-                  -> Option<Action<'tcx>> {
+    fn local_copy<'a>(mir: &'a Mir<'tcx>,
+                      def_use_analysis: &DefUseAnalysis<'tcx>,
+                      observed: &mut Option<DataflowResults<MaybeObservedLocals<'a, 'tcx>>>,
+                      src_lvalue: &Lvalue<'tcx>,
+                      dest_local: Local,
+                      location: Location)
+                      -> Option<Action<'tcx>> {
         // The source must be a local.
         let src_local = if let Lvalue::Local(local) = *src_lvalue {
             local
@@ -212,16 +228,29 @@ impl<'tcx> Action<'tcx> {
             return None;
         };
 
+        // Borrows of the source are checked by `borrows_are_dead`, and
+        // don't count as uses or mutations below.
+        let src_use_info = def_use_analysis.local_info(src_local);
+        let is_borrow = |lvalue_use: &Use| match lvalue_use.context {
+            LvalueContext::Borrow { .. } => true,
+            _ => false,
+        };
+        if src_use_info.defs_and_uses.iter().any(&is_borrow) &&
+           !borrows_are_dead(mir, def_use_analysis, observed, src_local, dest_local, location) {
+            return None
+        }
+
         // We're trying to copy propagate a local.
         // There must be exactly one use of the source used in a statement (not in a terminator).
-        let src_use_info = def_use_analysis.local_info(src_local);
-        let src_use_count = src_use_info.use_count();
+        let src_use_count = src_use_info.defs_and_uses.iter().filter(|lvalue_use| {
+            lvalue_use.context.is_nonmutating_use() && !is_borrow(lvalue_use)
+        }).count();
         if src_use_count == 0 {
             debug!("  Can't copy-propagate local: no uses");
             return None
         }
         if src_use_count != 1 {
-            debug!("  Can't copy-propagate local: {} uses", src_use_info.use_count());
+            debug!("  Can't copy-propagate local: {} uses", src_use_count);
             return None
         }
 
@@ -237,12 +266,13 @@ impl<'tcx> Action<'tcx> {
         //
         //     SRC = X;
         //     USE(SRC);
-        let src_def_count = src_use_info.def_count_not_including_drop();
+        let src_def_count = src_use_info.defs_not_including_drop()
+            .filter(|lvalue_use| !is_borrow(lvalue_use))
+            .count();
         // allow function arguments to be propagated
         if src_def_count > 1 ||
             (src_def_count == 0 && mir.local_kind(src_local) != LocalKind::Arg) {
-            debug!("  Can't copy-propagate local: {} defs of src",
-                   src_use_info.def_count_not_including_drop());
+            debug!("  Can't copy-propagate local: {} defs of src", src_def_count);
             return None
         }
 
@@ -256,6 +286,7 @@ impl<'tcx> Action<'tcx> {
     fn perform(self,
                mir: &mut Mir<'tcx>,
                def_use_analysis: &DefUseAnalysis<'tcx>,
+               journal: &Journal,
                dest_local: Local,
                location: Location)
                -> bool {
@@ -269,23 +300,27 @@ impl<'tcx> Action<'tcx> {
                 debug!("  Replacing all uses of {:?} with {:?} (local)",
                        dest_local,
                        src_local);
-                for lvalue_use in &def_use_analysis.local_info(dest_local).defs_and_uses {
+                for lvalue_use in def_use_analysis.local_info(dest_local).defs_and_uses {
                     if lvalue_use.context.is_storage_marker() {
-                        mir.make_statement_nop(lvalue_use.location)
+                        mir.make_statement_nop(lvalue_use.location);
+                        journal.nopped(lvalue_use.location);
                     }
                 }
-                for lvalue_use in &def_use_analysis.local_info(src_local).defs_and_uses {
+                for lvalue_use in def_use_analysis.local_info(src_local).defs_and_uses {
                     if lvalue_use.context.is_storage_marker() {
-                        mir.make_statement_nop(lvalue_use.location)
+                        mir.make_statement_nop(lvalue_use.location);
+                        journal.nopped(lvalue_use.location);
                     }
                 }
 
                 // Replace all uses of the destination local with the source local.
                 def_use_analysis.replace_all_defs_and_uses_with(dest_local, mir, src_local);
+                journal.replaced_local(dest_local, src_local);
 
                 // Finally, zap the now-useless assignment instruction.
                 debug!("  Deleting assignment");
                 mir.make_statement_nop(location);
+                journal.nopped(location);
 
                 true
             }
@@ -297,16 +332,17 @@ impl<'tcx> Action<'tcx> {
                        dest_local,
                        src_constant);
                 let dest_local_info = def_use_analysis.local_info(dest_local);
-                for lvalue_use in &dest_local_info.defs_and_uses {
+                for lvalue_use in dest_local_info.defs_and_uses {
                     if lvalue_use.context.is_storage_marker() {
-                        mir.make_statement_nop(lvalue_use.location)
+                        mir.make_statement_nop(lvalue_use.location);
+                        journal.nopped(lvalue_use.location);
                     }
                 }
 
                 // Replace all uses of the destination local with the constant.
                 let mut visitor = ConstantPropagationVisitor::new(dest_local,
                                                                   src_constant);
-                for dest_lvalue_use in &dest_local_info.defs_and_uses {
+                for dest_lvalue_use in dest_local_info.defs_and_uses {
                     visitor.visit_location(mir, dest_lvalue_use.location)
                 }
 
@@ -319,6 +355,7 @@ impl<'tcx> Action<'tcx> {
                            visitor.uses_replaced,
                            use_count);
                     mir.make_statement_nop(location);
+                    journal.nopped(location);
                     true
                 } else if visitor.uses_replaced == 0 {
                     debug!("  No uses replaced; not deleting assignment");
@@ -334,6 +371,58 @@ impl<'tcx> Action<'tcx> {
     }
 }
 
+/// Whether the source of the copy `DEST = SRC` at `location` can't change
+/// through a pointer between the copy and the uses of `DEST`, so that
+/// these can use `SRC` instead.
+///
+/// This is the case if all the borrows of `SRC` are taken before the copy
+/// in its block, and none of them is live at the copy. Then every path
+/// from a borrow to a use of `DEST` goes through the copy, as long as
+/// `DEST` isn't used before the copy in its block, e.g. by a previous
+/// iteration of a loop.
+///
+/// `observed` holds the `MaybeObservedLocals` results of `mir`, if they
+/// were needed before.
+fn borrows_are_dead<'a, 'tcx>(mir: &'a Mir<'tcx>,
+                              def_use_analysis: &DefUseAnalysis<'tcx>,
+                              observed: &mut Option<DataflowResults<MaybeObservedLocals<'a, 'tcx>>>,
+                              src_local: Local,
+                              dest_local: Local,
+                              location: Location)
+                              -> bool {
+    let precedes_copy = |lvalue_use: &Use| {
+        lvalue_use.location.block == location.block &&
+            lvalue_use.location.statement_index < location.statement_index
+    };
+    let borrows_precede_copy = def_use_analysis.local_info(src_local).defs_and_uses.iter()
+        .filter(|lvalue_use| match lvalue_use.context {
+            LvalueContext::Borrow { .. } => true,
+            _ => false,
+        })
+        .all(&precedes_copy);
+    if !borrows_precede_copy {
+        debug!("  Can't copy-propagate local: source borrowed after the copy");
+        return false
+    }
+    let dest_used_before_copy = def_use_analysis.local_info(dest_local).defs_and_uses.iter()
+        .any(|lvalue_use| !lvalue_use.context.is_storage_marker() && precedes_copy(lvalue_use));
+    if dest_used_before_copy {
+        debug!("  Can't copy-propagate local: dest used before the copy in its block");
+        return false
+    }
+
+    if observed.is_none() {
+        let dead_unwinds = IdxSetBuf::new_empty(mir.basic_blocks().len());
+        let analysis = DataflowAnalysis::new(mir, &dead_unwinds, MaybeObservedLocals::new(mir));
+        *observed = Some(analysis.run());
+    }
+    if observed.as_ref().unwrap().maybe_observed(src_local, location) {
+        debug!("  Can't copy-propagate local: source may be observed through a borrow");
+        return false
+    }
+    true
+}
+
 struct ConstantPropagationVisitor<'tcx> {
     dest_local: Local,
     constant: Constant<'tcx>,
@@ -8,37 +8,31 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use rustc::hir;
 use rustc::ty::TyCtxt;
 use rustc::mir::*;
 use rustc_data_structures::indexed_vec::Idx;
-use transform::{MirPass, MirSource};
+use transform::{MirOptContext, MirPass, MirSource};
 
 pub struct Deaggregator;
 
 impl MirPass for Deaggregator {
-    fn run_pass<'a, 'tcx>(&self,
-                          tcx: TyCtxt<'a, 'tcx, 'tcx>,
-                          source: MirSource,
-                          mir: &mut Mir<'tcx>) {
+    fn run_pass_in_context<'a, 'tcx>(&self,
+                                     tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                     context: &MirOptContext<'tcx>,
+                                     source: MirSource,
+                                     mir: &mut Mir<'tcx>) -> bool {
         let node_path = tcx.item_path_str(source.def_id);
         debug!("running on: {:?}", node_path);
-        // we only run when mir_opt_level > 2
-        if tcx.sess.opts.debugging_opts.mir_opt_level <= 2 {
-            return;
-        }
-
         // Don't run on constant MIR, because trans might not be able to
         // evaluate the modified MIR.
         // FIXME(eddyb) Remove check after miri is merged.
-        let id = tcx.hir.as_local_node_id(source.def_id).unwrap();
-        match (tcx.hir.body_owner_kind(id), source.promoted) {
-            (hir::BodyOwnerKind::Fn, None) => {},
-            _ => return
+        if !context.is_fn {
+            return false;
         }
         // In fact, we might not want to trigger in other cases.
         // Ex: when we could use SROA.  See issue #35259
 
+        let mut changed = false;
         for bb in mir.basic_blocks_mut() {
             let mut curr: usize = 0;
             while let Some(idx) = get_aggregate_statement_index(curr, &bb.statements) {
@@ -68,18 +62,12 @@ impl MirPass for Deaggregator {
                     let rhs = Rvalue::Use(op.clone());
 
                     let lhs_cast = if adt_def.is_enum() {
-                        Lvalue::Projection(Box::new(LvalueProjection {
-                            base: lhs.clone(),
-                            elem: ProjectionElem::Downcast(adt_def, variant),
-                        }))
+                        lhs.clone().downcast(adt_def, variant)
                     } else {
                         lhs.clone()
                     };
 
-                    let lhs_proj = Lvalue::Projection(Box::new(LvalueProjection {
-                        base: lhs_cast,
-                        elem: ProjectionElem::Field(Field::new(i), ty),
-                    }));
+                    let lhs_proj = lhs_cast.field(Field::new(i), ty);
                     let new_statement = Statement {
                         source_info: src_info,
                         kind: StatementKind::Assign(lhs_proj, rhs),
@@ -102,8 +90,10 @@ impl MirPass for Deaggregator {
 
                 curr = bb.statements.len();
                 bb.statements.extend(suffix_stmts);
+                changed = true;
             }
         }
+        changed
     }
 }
 
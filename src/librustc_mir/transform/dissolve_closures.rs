@@ -0,0 +1,373 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Replaces closures whose calls were all inlined with one local per
+//! capture, removing the closure environment.
+//!
+//! Once the inliner has inlined `f(3)` in `let f = |x| x + offset; f(3)`,
+//! the caller still builds the environment with a closure aggregate into
+//! `f`, borrows it, and the inlined body reads the captures through that
+//! borrow, as `(*(*r).0)`. The locals holding a closure, which it is moved
+//! between, form a group, which is dissolved when:
+//!
+//! - the group contains exactly one construction of the closure, and
+//!   neither an argument nor the return pointer;
+//! - that construction is not in a loop, as a closure constructed by one
+//!   iteration could still be live in a member of the group when the next
+//!   iteration constructs it again, and both would need the same capture
+//!   locals;
+//! - every mention of its locals is a projection to one of the captures,
+//!   a storage marker, a drop, a move to another local of the group, or a
+//!   borrow into a local that is assigned only there and is only used
+//!   through projections to the captures. Any other use, like a call that
+//!   wasn't inlined or passing the closure to a function, keeps it whole;
+//! - at most one of the captures needs dropping, so that drops of the
+//!   whole closure can become drops of that capture.
+//!
+//! The rewrite assigns each captured operand to a fresh local where the
+//! closure was constructed, and replaces the projections to the captures
+//! with those locals. The borrows, the moves within the group and their
+//! storage markers are removed.
+
+use rustc::mir::{AggregateKind, BasicBlock, Local, LocalDecl, LocalKind, Location, Lvalue, Mir};
+use rustc::mir::{Operand, Projection, ProjectionElem, Rvalue, Statement, StatementKind};
+use rustc::mir::TerminatorKind;
+use rustc::mir::visit::{LvalueContext, MutVisitor, Visitor};
+use rustc::ty::{self, TyCtxt};
+use rustc_data_structures::indexed_vec::{Idx, IndexVec};
+use std::mem;
+use util::{group_moved_locals, local_move};
+use util::scc;
+
+/// Dissolves the closures of `mir` whose environment isn't needed as a
+/// whole anymore, returning whether it dissolved any.
+pub fn dissolve_closures<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                   param_env: ty::ParamEnv<'tcx>,
+                                   mir: &mut Mir<'tcx>) -> bool {
+    let is_candidate: IndexVec<Local, bool> = mir.local_decls.iter_enumerated()
+        .map(|(local, decl)| {
+            match decl.ty.sty {
+                ty::TyClosure(..) => match mir.local_kind(local) {
+                    LocalKind::Var | LocalKind::Temp => true,
+                    LocalKind::Arg | LocalKind::ReturnPointer => false,
+                },
+                _ => false,
+            }
+        })
+        .collect();
+    if !is_candidate.iter().any(|&candidate| candidate) {
+        return false;
+    }
+
+    let groups = group_moved_locals(mir, &is_candidate);
+    let borrowed = find_borrows(mir, &is_candidate);
+    let in_cycle = scc::blocks_in_cycles(mir);
+
+    let (local_escapes, local_constructions) = {
+        let mut uses = EnvUses {
+            mir: &*mir,
+            is_candidate: &is_candidate,
+            borrowed: &borrowed,
+            in_cycle: &in_cycle,
+            escaping: IndexVec::from_elem(false, &mir.local_decls),
+            constructions: IndexVec::from_elem(0, &mir.local_decls),
+        };
+        uses.visit_mir(mir);
+        (uses.escaping, uses.constructions)
+    };
+
+    // Decide, for the root of each group, whether the closure can be
+    // dissolved. A borrow escaping lets the closure it borrows escape.
+    let mut escaping = IndexVec::from_elem(false, &mir.local_decls);
+    let mut constructions = IndexVec::from_elem(0, &mir.local_decls);
+    for local in mir.local_decls.indices() {
+        let root = groups[local];
+        escaping[root] |= local_escapes[local];
+        constructions[root] += local_constructions[local];
+        if let Some(env) = borrowed[local] {
+            escaping[groups[env]] |= local_escapes[local];
+        }
+    }
+
+    let mut captures = IndexVec::from_elem(None, &mir.local_decls);
+    let mut dropped = IndexVec::from_elem(None, &mir.local_decls);
+    for local in mir.local_decls.indices() {
+        if !is_candidate[local] || groups[local] != local ||
+           escaping[local] || constructions[local] != 1 {
+            continue;
+        }
+        let (ty, span) = {
+            let decl = &mir.local_decls[local];
+            (decl.ty, decl.source_info.span)
+        };
+        let upvar_tys: Vec<_> = match ty.sty {
+            ty::TyClosure(def_id, substs) => substs.upvar_tys(def_id, tcx).collect(),
+            _ => bug!("closure local {:?} has type {:?}", local, ty),
+        };
+        let needs_drop: Vec<_> = upvar_tys.iter()
+            .enumerate()
+            .filter(|&(_, upvar_ty)| upvar_ty.needs_drop(tcx, param_env))
+            .map(|(field, _)| field)
+            .collect();
+        if needs_drop.len() > 1 {
+            continue;
+        }
+        dropped[local] = needs_drop.first().cloned();
+        captures[local] = Some(upvar_tys.into_iter()
+            .map(|upvar_ty| mir.local_decls.push(LocalDecl::new_temp(upvar_ty, span)))
+            .collect::<Vec<_>>());
+    }
+    if captures.iter().all(|captures| captures.is_none()) {
+        return false;
+    }
+
+    // Constructions turn into several statements, which the visitor below
+    // can't do, so they are expanded beforehand.
+    for data in mir.basic_blocks_mut() {
+        let statements = mem::replace(&mut data.statements, Vec::new());
+        for statement in statements {
+            let replacement = match statement.kind {
+                StatementKind::Assign(Lvalue::Local(local), Rvalue::Aggregate(ref kind, _)) => {
+                    match **kind {
+                        AggregateKind::Closure(..) => captures[groups[local]].as_ref(),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            };
+            match (replacement, statement.kind) {
+                (Some(replacement), StatementKind::Assign(_, Rvalue::Aggregate(_, operands))) => {
+                    for (&capture, operand) in replacement.iter().zip(operands) {
+                        data.statements.push(Statement {
+                            source_info: statement.source_info,
+                            kind: StatementKind::Assign(Lvalue::Local(capture),
+                                                        Rvalue::Use(operand)),
+                        });
+                    }
+                }
+                (_, kind) => data.statements.push(Statement {
+                    source_info: statement.source_info,
+                    kind,
+                }),
+            }
+        }
+    }
+
+    DissolveEnvs { is_candidate, groups, borrowed, captures, dropped }.visit_mir(mir);
+    true
+}
+
+/// Maps the locals that are assigned exactly once, by a borrow of a
+/// candidate closure, to that closure.
+fn find_borrows<'tcx>(mir: &Mir<'tcx>,
+                      is_candidate: &IndexVec<Local, bool>)
+                      -> IndexVec<Local, Option<Local>> {
+    let mut borrowed = IndexVec::from_elem(None, &mir.local_decls);
+    let mut definitions = IndexVec::from_elem(0, &mir.local_decls);
+    for data in mir.basic_blocks() {
+        for statement in &data.statements {
+            if let StatementKind::Assign(Lvalue::Local(local), ref rvalue) = statement.kind {
+                definitions[local] += 1;
+                if let Rvalue::Ref(_, _, Lvalue::Local(env)) = *rvalue {
+                    if is_candidate[env] {
+                        borrowed[local] = Some(env);
+                    }
+                }
+            }
+        }
+        if let TerminatorKind::Call {
+            destination: Some((Lvalue::Local(local), _)), ..
+        } = data.terminator().kind {
+            definitions[local] += 1;
+        }
+    }
+
+    for local in mir.local_decls.indices() {
+        let assigned_once = definitions[local] == 1 && match mir.local_kind(local) {
+            LocalKind::Var | LocalKind::Temp => true,
+            LocalKind::Arg | LocalKind::ReturnPointer => false,
+        };
+        if !assigned_once {
+            borrowed[local] = None;
+        }
+    }
+    borrowed
+}
+
+/// Returns the closure and the index of the capture that `lvalue` projects
+/// to, either as `env.i` or as `(*r).i` for a borrow `r` of `env`.
+fn capture_of(lvalue: &Lvalue,
+              is_candidate: &IndexVec<Local, bool>,
+              borrowed: &IndexVec<Local, Option<Local>>)
+              -> Option<(Local, usize)> {
+    match *lvalue {
+        Lvalue::Projection(box Projection {
+            base: Lvalue::Local(local),
+            elem: ProjectionElem::Field(field, _),
+        }) if is_candidate[local] => Some((local, field.index())),
+        Lvalue::Projection(box Projection {
+            base: Lvalue::Projection(box Projection {
+                base: Lvalue::Local(local),
+                elem: ProjectionElem::Deref,
+            }),
+            elem: ProjectionElem::Field(field, _),
+        }) => borrowed[local].map(|env| (env, field.index())),
+        _ => None,
+    }
+}
+
+/// Finds the locals used in a way that needs their closure as a whole,
+/// and counts the constructions of a closure assigned to each local.
+struct EnvUses<'a, 'tcx: 'a> {
+    mir: &'a Mir<'tcx>,
+    is_candidate: &'a IndexVec<Local, bool>,
+    borrowed: &'a IndexVec<Local, Option<Local>>,
+    in_cycle: &'a IndexVec<BasicBlock, bool>,
+    escaping: IndexVec<Local, bool>,
+    constructions: IndexVec<Local, usize>,
+}
+
+impl<'a, 'tcx> Visitor<'tcx> for EnvUses<'a, 'tcx> {
+    fn visit_statement(&mut self,
+                       block: BasicBlock,
+                       statement: &Statement<'tcx>,
+                       location: Location) {
+        match statement.kind {
+            StatementKind::Assign(Lvalue::Local(local),
+                                  Rvalue::Aggregate(box AggregateKind::Closure(..),
+                                                    ref operands))
+                if self.is_candidate[local] => {
+                self.constructions[local] += 1;
+                // Keep the closures constructed in a loop whole.
+                if self.in_cycle[block] {
+                    self.escaping[local] = true;
+                }
+                for operand in operands {
+                    self.visit_operand(operand, location);
+                }
+            }
+            StatementKind::Assign(Lvalue::Local(local), Rvalue::Ref(..))
+                if self.borrowed[local].is_some() => {}
+            StatementKind::StorageLive(_) | StatementKind::StorageDead(_) => {}
+            _ if local_move(self.mir, self.is_candidate, statement).is_some() => {}
+            _ => self.super_statement(block, statement, location),
+        }
+    }
+
+    fn visit_terminator_kind(&mut self,
+                             block: BasicBlock,
+                             kind: &TerminatorKind<'tcx>,
+                             location: Location) {
+        match *kind {
+            TerminatorKind::Drop { location: Lvalue::Local(local), .. }
+                if self.is_candidate[local] => {}
+            _ => self.super_terminator_kind(block, kind, location),
+        }
+    }
+
+    fn visit_lvalue(&mut self,
+                    lvalue: &Lvalue<'tcx>,
+                    context: LvalueContext<'tcx>,
+                    location: Location) {
+        if capture_of(lvalue, self.is_candidate, self.borrowed).is_none() {
+            self.super_lvalue(lvalue, context, location);
+        }
+    }
+
+    fn visit_local(&mut self, &local: &Local, _: LvalueContext<'tcx>, _: Location) {
+        self.escaping[local] = true;
+    }
+}
+
+/// Replaces the projections to the captures of the dissolved closures,
+/// which have `captures` at the root of their group, by the capture locals.
+struct DissolveEnvs {
+    is_candidate: IndexVec<Local, bool>,
+    groups: IndexVec<Local, Local>,
+    borrowed: IndexVec<Local, Option<Local>>,
+    captures: IndexVec<Local, Option<Vec<Local>>>,
+    /// The capture that needs dropping, if any, at the root of each group.
+    dropped: IndexVec<Local, Option<usize>>,
+}
+
+impl DissolveEnvs {
+    fn is_dissolved(&self, local: Local) -> bool {
+        self.is_candidate[local] && self.captures[self.groups[local]].is_some()
+    }
+}
+
+impl<'tcx> MutVisitor<'tcx> for DissolveEnvs {
+    fn visit_statement(&mut self,
+                       block: BasicBlock,
+                       statement: &mut Statement<'tcx>,
+                       location: Location) {
+        let remove = match statement.kind {
+            StatementKind::Assign(Lvalue::Local(local), Rvalue::Ref(..)) => {
+                self.borrowed[local].map_or(false, |env| self.is_dissolved(env))
+            }
+            StatementKind::Assign(Lvalue::Local(local),
+                                  Rvalue::Use(Operand::Move(Lvalue::Local(_)))) => {
+                self.is_dissolved(local)
+            }
+            StatementKind::StorageLive(local) |
+            StatementKind::StorageDead(local) => {
+                self.is_dissolved(local) ||
+                    self.borrowed[local].map_or(false, |env| self.is_dissolved(env))
+            }
+            _ => false,
+        };
+        if remove {
+            statement.make_nop();
+        } else {
+            self.super_statement(block, statement, location);
+        }
+    }
+
+    fn visit_terminator_kind(&mut self,
+                             block: BasicBlock,
+                             kind: &mut TerminatorKind<'tcx>,
+                             location: Location) {
+        let replacement = match *kind {
+            TerminatorKind::Drop { location: Lvalue::Local(local), target, unwind }
+                if self.is_dissolved(local) => {
+                let root = self.groups[local];
+                match (self.dropped[root], &self.captures[root]) {
+                    (Some(field), &Some(ref captures)) => Some(TerminatorKind::Drop {
+                        location: Lvalue::Local(captures[field]),
+                        target,
+                        unwind,
+                    }),
+                    _ => Some(TerminatorKind::Goto { target }),
+                }
+            }
+            _ => None,
+        };
+        match replacement {
+            Some(replacement) => *kind = replacement,
+            None => self.super_terminator_kind(block, kind, location),
+        }
+    }
+
+    fn visit_lvalue(&mut self,
+                    lvalue: &mut Lvalue<'tcx>,
+                    context: LvalueContext<'tcx>,
+                    location: Location) {
+        let replacement = match capture_of(lvalue, &self.is_candidate, &self.borrowed) {
+            Some((env, field)) => {
+                self.captures[self.groups[env]].as_ref().map(|captures| captures[field])
+            }
+            None => None,
+        };
+        match replacement {
+            Some(replacement) => *lvalue = Lvalue::Local(replacement),
+            None => self.super_lvalue(lvalue, context, location),
+        }
+    }
+}
@@ -18,8 +18,9 @@ use std::io;
 use rustc::mir::Mir;
 use rustc::session::config::{OutputFilenames, OutputType};
 use rustc::ty::TyCtxt;
-use transform::{MirPass, MirSource};
+use transform::{MirOptContext, MirPass, MirSource};
 use util as mir_util;
+use util::RenderedDump;
 
 pub struct Marker(pub &'static str);
 
@@ -28,11 +29,14 @@ impl MirPass for Marker {
         Cow::Borrowed(self.0)
     }
 
-    fn run_pass<'a, 'tcx>(&self,
-                          _tcx: TyCtxt<'a, 'tcx, 'tcx>,
-                          _source: MirSource,
-                          _mir: &mut Mir<'tcx>)
+    fn run_pass_in_context<'a, 'tcx>(&self,
+                                     _tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                     _context: &MirOptContext<'tcx>,
+                                     _source: MirSource,
+                                     _mir: &mut Mir<'tcx>)
+                                     -> bool
     {
+        false
     }
 }
 
@@ -48,21 +52,73 @@ impl fmt::Display for Disambiguator {
 }
 
 
-pub fn on_mir_pass<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
-                             pass_num: &fmt::Display,
-                             pass_name: &str,
-                             source: MirSource,
-                             mir: &Mir<'tcx>,
-                             is_after: bool) {
-    if mir_util::dump_enabled(tcx, pass_name, source) {
-        mir_util::dump_mir(tcx,
-                           Some(pass_num),
-                           pass_name,
-                           &Disambiguator { is_after },
-                           source,
-                           mir,
-                           |_, _| Ok(()) );
+/// Dumps `mir` before the pass `pass_name` runs on it, and returns the
+/// dump, to be given to `after_mir_pass`. Under `-Z dump-mir-only-changed`,
+/// the dump is only rendered, and written by `after_mir_pass` if the pass
+/// changes `mir`.
+pub fn before_mir_pass<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                 pass_num: &fmt::Display,
+                                 pass_name: &str,
+                                 source: MirSource,
+                                 mir: &Mir<'tcx>)
+                                 -> Option<RenderedDump> {
+    let before = mir_util::render_mir_dump(tcx,
+                                           Some(pass_num),
+                                           pass_name,
+                                           &Disambiguator { is_after: false },
+                                           source,
+                                           mir,
+                                           |_, _| Ok(()));
+    if let Some(ref before) = before {
+        if !tcx.sess.opts.debugging_opts.dump_mir_only_changed {
+            before.write();
+        }
     }
+    before
+}
+
+/// Dumps `mir` after the pass `pass_name` ran on it, given the dump
+/// `before_mir_pass` returned, if any. The promoted bodies the pass didn't
+/// change are not dumped again. Under `-Z dump-mir-only-changed`, neither
+/// dump is written if the pass didn't change `mir`. Under
+/// `-Z dump-mir-diff`, a diff between the two is written if it did.
+pub fn after_mir_pass<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                pass_num: &fmt::Display,
+                                pass_name: &str,
+                                source: MirSource,
+                                mir: &Mir<'tcx>,
+                                before: Option<RenderedDump>) {
+    let after = mir_util::render_mir_dump(tcx,
+                                          Some(pass_num),
+                                          pass_name,
+                                          &Disambiguator { is_after: true },
+                                          source,
+                                          mir,
+                                          |_, _| Ok(()));
+    let mut after = match after {
+        Some(after) => after,
+        None => return,
+    };
+    let opts = &tcx.sess.opts.debugging_opts;
+    let before = match before {
+        Some(before) => before,
+        None => {
+            after.write();
+            return;
+        }
+    };
+    let changed = !before.same_mir(&after);
+    if !changed && opts.dump_mir_only_changed {
+        return;
+    }
+    if changed && opts.dump_mir_only_changed {
+        before.write();
+    }
+    if changed && opts.dump_mir_diff {
+        after.write_diff(&before);
+    }
+    after.elide_unchanged_promoted(&before);
+    after.write();
 }
 
 pub fn emit_mir<'a, 'tcx>(
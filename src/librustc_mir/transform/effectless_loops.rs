@@ -0,0 +1,159 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! This pass finds infinite loops without side effects, reports them with
+//! the `effectless_infinite_loops` lint, and under
+//! `-Z insert-loop-side-effects` makes them effectful.
+//!
+//! LLVM assumes that loops without side effects terminate, so it may
+//! remove such a loop, or anything that follows it, even though the loop
+//! never exits. Such loops are almost always bugs anyway.
+//!
+//! A loop is a strongly connected component of the CFG with at least one
+//! edge. It is infinite when no edge leaves it, unwind edges included, and
+//! it is effectless when none of its statements and terminators is
+//! effectful. The effectful ones are:
+//!
+//! - assignments to statics, or through a dereference;
+//! - inline assembly that is volatile, writes to memory, or has an output
+//!   that is a static or a dereference;
+//! - calls, which include volatile and atomic accesses, as those are
+//!   intrinsics;
+//! - drops, which may run arbitrary code;
+//! - asserts, which may panic;
+//! - yields.
+//!
+//! The inserted side effect is a volatile empty inline assembly statement
+//! with a `memory` clobber, at the start of the first block of the loop.
+
+use rustc::hir;
+use rustc::lint::builtin::EFFECTLESS_INFINITE_LOOPS;
+use rustc::mir::{BasicBlock, Lvalue, Mir, ProjectionElem, SourceInfo, Statement};
+use rustc::mir::{StatementKind, TerminatorKind};
+use rustc::ty::TyCtxt;
+use syntax::ast::{AsmDialect, StrStyle};
+use syntax::symbol::Symbol;
+use transform::{MirOptContext, MirPass, MirSource};
+use util::inline_asm::AsmEffects;
+use util::scc;
+
+pub struct EffectlessLoops;
+
+impl MirPass for EffectlessLoops {
+    fn run_pass_in_context<'a, 'tcx>(&self,
+                                     tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                     _context: &MirOptContext<'tcx>,
+                                     _source: MirSource,
+                                     mir: &mut Mir<'tcx>) -> bool {
+        let loops: Vec<_> = scc::strongly_connected_components(mir).into_iter()
+            .filter(|blocks| {
+                scc::is_cyclic(mir, blocks) &&
+                    is_infinite_loop(mir, blocks) &&
+                    !has_side_effects(mir, blocks)
+            })
+            .collect();
+
+        for blocks in &loops {
+            let source_info = loop_source_info(mir, blocks);
+            if let Some(lint_root) = mir.lint_root(source_info) {
+                tcx.lint_node(EFFECTLESS_INFINITE_LOOPS, lint_root, source_info.span,
+                              "infinite loop without side effects");
+            }
+        }
+
+        if !tcx.sess.opts.debugging_opts.insert_loop_side_effects {
+            return false;
+        }
+        for blocks in &loops {
+            let source_info = loop_source_info(mir, blocks);
+            let header = *blocks.iter().min().unwrap();
+            mir[header].statements.insert(0, side_effect(source_info));
+        }
+        !loops.is_empty()
+    }
+}
+
+/// Whether no edge leaves `blocks`.
+fn is_infinite_loop(mir: &Mir, blocks: &[BasicBlock]) -> bool {
+    blocks.iter().all(|&block| {
+        mir[block].terminator().successors().iter().all(|target| blocks.contains(target))
+    })
+}
+
+fn has_side_effects(mir: &Mir, blocks: &[BasicBlock]) -> bool {
+    blocks.iter().any(|&block| {
+        let data = &mir[block];
+        data.statements.iter().any(|statement| match statement.kind {
+            StatementKind::Assign(ref lvalue, _) |
+            StatementKind::SetDiscriminant { ref lvalue, .. } => writes_outside_locals(lvalue),
+            StatementKind::InlineAsm { .. } => {
+                let effects = AsmEffects::of(&statement.kind).unwrap();
+                effects.has_side_effects() ||
+                    effects.outputs.iter().any(|output| writes_outside_locals(output.lvalue))
+            }
+            StatementKind::StorageLive(_) |
+            StatementKind::StorageDead(_) |
+            StatementKind::EndRegion(_) |
+            StatementKind::Validate(..) |
+            StatementKind::Assume(..) |
+            StatementKind::Nop => false,
+        }) || match data.terminator().kind {
+            TerminatorKind::Goto { .. } |
+            TerminatorKind::SwitchInt { .. } |
+            TerminatorKind::FalseEdges { .. } => false,
+            _ => true,
+        }
+    })
+}
+
+/// Whether writing to `lvalue` may be observed outside of the function.
+fn writes_outside_locals(lvalue: &Lvalue) -> bool {
+    match *lvalue {
+        Lvalue::Local(_) => false,
+        Lvalue::Static(_) => true,
+        Lvalue::Projection(ref proj) => {
+            proj.elem == ProjectionElem::Deref || writes_outside_locals(&proj.base)
+        }
+    }
+}
+
+/// Picks the source info of a jump within the loop, which spans the whole
+/// loop expression.
+fn loop_source_info(mir: &Mir, blocks: &[BasicBlock]) -> SourceInfo {
+    let mut sorted = blocks.to_vec();
+    sorted.sort();
+    sorted.iter()
+        .map(|&block| mir[block].terminator())
+        .find(|terminator| match terminator.kind {
+            TerminatorKind::Goto { target } => blocks.contains(&target),
+            _ => false,
+        })
+        .unwrap_or_else(|| mir[sorted[0]].terminator())
+        .source_info
+}
+
+/// Builds `asm!("" ::: "memory" : "volatile")`.
+fn side_effect<'tcx>(source_info: SourceInfo) -> Statement<'tcx> {
+    let asm = hir::InlineAsm {
+        asm: Symbol::intern(""),
+        asm_str_style: StrStyle::Cooked,
+        outputs: hir::HirVec::from(vec![]),
+        inputs: hir::HirVec::from(vec![]),
+        clobbers: hir::HirVec::from(vec![Symbol::intern("memory")]),
+        volatile: true,
+        alignstack: false,
+        dialect: AsmDialect::Att,
+        ctxt: source_info.span.ctxt(),
+    };
+    Statement {
+        source_info,
+        kind: StatementKind::InlineAsm { asm: Box::new(asm), outputs: vec![], inputs: vec![] },
+    }
+}
@@ -8,25 +8,19 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use dataflow::move_paths::{HasMoveData, MoveData, MovePathIndex, LookupResult};
-use dataflow::{MaybeInitializedLvals, MaybeUninitializedLvals};
-use dataflow::{DataflowResults};
+use dataflow::move_paths::{MoveData, MovePathIndex, LookupResult};
+use dataflow::{InitializationData, InitializationResults};
 use dataflow::{on_all_children_bits, on_all_drop_children_bits};
 use dataflow::{drop_flag_effects_for_location, on_lookup_result_bits};
 use dataflow::MoveDataParamEnv;
-use dataflow;
-use rustc::hir;
 use rustc::ty::{self, TyCtxt};
 use rustc::mir::*;
 use rustc::middle::const_val::ConstVal;
 use rustc::util::nodemap::FxHashMap;
-use rustc_data_structures::indexed_set::IdxSetBuf;
-use rustc_data_structures::indexed_vec::Idx;
-use transform::{MirPass, MirSource};
+use transform::{MirOptContext, MirPass, MirSource};
 use util::patch::MirPatch;
 use util::elaborate_drops::{DropFlagState, Unwind, elaborate_drop};
 use util::elaborate_drops::{DropElaborator, DropStyle, DropFlagMode};
-use syntax::ast;
 use syntax_pos::Span;
 
 use std::fmt;
@@ -34,144 +28,37 @@ use std::fmt;
 pub struct ElaborateDrops;
 
 impl MirPass for ElaborateDrops {
-    fn run_pass<'a, 'tcx>(&self,
-                          tcx: TyCtxt<'a, 'tcx, 'tcx>,
-                          src: MirSource,
-                          mir: &mut Mir<'tcx>)
+    fn run_pass_in_context<'a, 'tcx>(&self,
+                                     tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                     context: &MirOptContext<'tcx>,
+                                     src: MirSource,
+                                     mir: &mut Mir<'tcx>)
+                                     -> bool
     {
         debug!("elaborate_drops({:?} @ {:?})", src, mir.span);
 
         // Don't run on constant MIR, because trans might not be able to
         // evaluate the modified MIR.
         // FIXME(eddyb) Remove check after miri is merged.
-        let id = tcx.hir.as_local_node_id(src.def_id).unwrap();
-        match (tcx.hir.body_owner_kind(id), src.promoted) {
-            (hir::BodyOwnerKind::Fn, None) => {},
-            _ => return
+        if !context.is_fn {
+            return false
         }
-        let param_env = tcx.param_env(src.def_id);
-        let move_data = MoveData::gather_moves(mir, tcx).unwrap();
+        // Borrowck reports the moves out of lvalues that can't be moved out
+        // of before drops are elaborated.
+        let results = context.analyses.initialization_results(tcx, src, mir).unwrap();
         let elaborate_patch = {
             let mir = &*mir;
-            let env = MoveDataParamEnv {
-                move_data,
-                param_env,
-            };
-            let dead_unwinds = find_dead_unwinds(tcx, mir, id, &env);
-            let flow_inits =
-                dataflow::do_dataflow(tcx, mir, id, &[], &dead_unwinds,
-                                      MaybeInitializedLvals::new(tcx, mir, &env),
-                                      |bd, p| &bd.move_data().move_paths[p]);
-            let flow_uninits =
-                dataflow::do_dataflow(tcx, mir, id, &[], &dead_unwinds,
-                                      MaybeUninitializedLvals::new(tcx, mir, &env),
-                                      |bd, p| &bd.move_data().move_paths[p]);
-
             ElaborateDropsCtxt {
                 tcx,
                 mir,
-                env: &env,
-                flow_inits,
-                flow_uninits,
+                env: &results.env,
+                results: &results,
                 drop_flags: FxHashMap(),
                 patch: MirPatch::new(mir),
             }.elaborate()
         };
         elaborate_patch.apply(mir);
-    }
-}
-
-/// Return the set of basic blocks whose unwind edges are known
-/// to not be reachable, because they are `drop` terminators
-/// that can't drop anything.
-fn find_dead_unwinds<'a, 'tcx>(
-    tcx: TyCtxt<'a, 'tcx, 'tcx>,
-    mir: &Mir<'tcx>,
-    id: ast::NodeId,
-    env: &MoveDataParamEnv<'tcx, 'tcx>)
-    -> IdxSetBuf<BasicBlock>
-{
-    debug!("find_dead_unwinds({:?})", mir.span);
-    // We only need to do this pass once, because unwind edges can only
-    // reach cleanup blocks, which can't have unwind edges themselves.
-    let mut dead_unwinds = IdxSetBuf::new_empty(mir.basic_blocks().len());
-    let flow_inits =
-        dataflow::do_dataflow(tcx, mir, id, &[], &dead_unwinds,
-                           MaybeInitializedLvals::new(tcx, mir, &env),
-                           |bd, p| &bd.move_data().move_paths[p]);
-    for (bb, bb_data) in mir.basic_blocks().iter_enumerated() {
-        let location = match bb_data.terminator().kind {
-            TerminatorKind::Drop { ref location, unwind: Some(_), .. } |
-            TerminatorKind::DropAndReplace { ref location, unwind: Some(_), .. } => location,
-            _ => continue,
-        };
-
-        let mut init_data = InitializationData {
-            live: flow_inits.sets().on_entry_set_for(bb.index()).to_owned(),
-            dead: IdxSetBuf::new_empty(env.move_data.move_paths.len()),
-        };
-        debug!("find_dead_unwinds @ {:?}: {:?}; init_data={:?}",
-               bb, bb_data, init_data.live);
-        for stmt in 0..bb_data.statements.len() {
-            let loc = Location { block: bb, statement_index: stmt };
-            init_data.apply_location(tcx, mir, env, loc);
-        }
-
-        let path = match env.move_data.rev_lookup.find(location) {
-            LookupResult::Exact(e) => e,
-            LookupResult::Parent(..) => {
-                debug!("find_dead_unwinds: has parent; skipping");
-                continue
-            }
-        };
-
-        debug!("find_dead_unwinds @ {:?}: path({:?})={:?}", bb, location, path);
-
-        let mut maybe_live = false;
-        on_all_drop_children_bits(tcx, mir, &env, path, |child| {
-            let (child_maybe_live, _) = init_data.state(child);
-            maybe_live |= child_maybe_live;
-        });
-
-        debug!("find_dead_unwinds @ {:?}: maybe_live={}", bb, maybe_live);
-        if !maybe_live {
-            dead_unwinds.add(&bb);
-        }
-    }
-
-    dead_unwinds
-}
-
-struct InitializationData {
-    live: IdxSetBuf<MovePathIndex>,
-    dead: IdxSetBuf<MovePathIndex>
-}
-
-impl InitializationData {
-    fn apply_location<'a,'tcx>(&mut self,
-                               tcx: TyCtxt<'a, 'tcx, 'tcx>,
-                               mir: &Mir<'tcx>,
-                               env: &MoveDataParamEnv<'tcx, 'tcx>,
-                               loc: Location)
-    {
-        drop_flag_effects_for_location(tcx, mir, env, loc, |path, df| {
-            debug!("at location {:?}: setting {:?} to {:?}",
-                   loc, path, df);
-            match df {
-                DropFlagState::Present => {
-                    self.live.add(&path);
-                    self.dead.remove(&path);
-                }
-                DropFlagState::Absent => {
-                    self.dead.add(&path);
-                    self.live.remove(&path);
-                }
-            }
-        });
-    }
-
-    fn state(&self, path: MovePathIndex) -> (bool, bool) {
-        (self.live.contains(&path), self.dead.contains(&path))
+        true
     }
 }
 
@@ -286,8 +173,7 @@ struct ElaborateDropsCtxt<'a, 'tcx: 'a> {
     tcx: TyCtxt<'a, 'tcx, 'tcx>,
     mir: &'a Mir<'tcx>,
     env: &'a MoveDataParamEnv<'tcx, 'tcx>,
-    flow_inits: DataflowResults<MaybeInitializedLvals<'a, 'tcx, 'tcx>>,
-    flow_uninits:  DataflowResults<MaybeUninitializedLvals<'a, 'tcx, 'tcx>>,
+    results: &'a InitializationResults<'tcx>,
     drop_flags: FxHashMap<MovePathIndex, Local>,
     patch: MirPatch<'tcx>,
 }
@@ -300,17 +186,7 @@ impl<'b, 'tcx> ElaborateDropsCtxt<'b, 'tcx> {
     }
 
     fn initialization_data_at(&self, loc: Location) -> InitializationData {
-        let mut data = InitializationData {
-            live: self.flow_inits.sets().on_entry_set_for(loc.block.index())
-                .to_owned(),
-            dead: self.flow_uninits.sets().on_entry_set_for(loc.block.index())
-                .to_owned(),
-        };
-        for stmt in 0..loc.statement_index {
-            data.apply_location(self.tcx, self.mir, self.env,
-                                Location { block: loc.block, statement_index: stmt });
-        }
-        data
+        self.results.state_at(self.tcx, self.mir, loc)
     }
 
     fn create_drop_flag(&mut self, index: MovePathIndex, span: Span) {
@@ -36,10 +36,15 @@
 //!     }
 //!
 //! This pass computes the meaning of the state field and the MIR locals which are live
-//! across a suspension point. There are however two hardcoded generator states:
+//! across a suspension point. For a generator with N suspension points the states are:
 //!     0 - Generator have not been resumed yet
-//!     1 - Generator has returned / is completed
-//!     2 - Generator has been poisoned
+//!     1..=N - Generator is suspended at the given suspension point
+//!     N + 1 - Generator has returned / is completed
+//!     N + 2 - Generator has been poisoned
+//!
+//! The unresumed state is fixed since generators are created before the number of
+//! suspension points is known. Keeping the suspension states contiguous right after it
+//! lets the switches dispatching on the state be lowered as jump tables.
 //!
 //! It also rewrites `return x` and `yield y` as setting a new generator state and returning
 //! GeneratorState::Complete(x) and GeneratorState::Yielded(y) respectively.
@@ -50,21 +55,23 @@
 //! the action to take.
 //!
 //! One of them is the implementation of Generator::resume.
-//! For generators with state 0 (unresumed) it starts the execution of the generator.
-//! For generators with state 1 (returned) and state 2 (poisoned) it panics.
+//! For generators in the unresumed state it starts the execution of the generator.
+//! For generators in the returned and poisoned states it panics.
 //! Otherwise it continues the execution from the last suspension point.
 //!
 //! The other function is the drop glue for the generator.
-//! For generators with state 0 (unresumed) it drops the upvars of the generator.
-//! For generators with state 1 (returned) and state 2 (poisoned) it does nothing.
+//! For generators in the unresumed state it drops the upvars of the generator.
+//! For generators in the returned and poisoned states it does nothing.
 //! Otherwise it drops all the values in scope at the last suspension point.
 
 use rustc::hir;
 use rustc::hir::def_id::DefId;
+use rustc::lint::builtin::LARGE_GENERATOR_LOCAL;
 use rustc::middle::const_val::ConstVal;
 use rustc::mir::*;
 use rustc::mir::visit::{LvalueContext, Visitor, MutVisitor};
 use rustc::ty::{self, TyCtxt, AdtDef, Ty, GeneratorInterior};
+use rustc::ty::layout::LayoutOf;
 use rustc::ty::subst::{Kind, Substs};
 use util::dump_mir;
 use util::liveness::{self, LivenessMode};
@@ -75,13 +82,23 @@ use std::collections::HashMap;
 use std::borrow::Cow;
 use std::iter::once;
 use std::mem;
-use transform::{MirPass, MirSource};
+use transform::{MirOptContext, MirPass, MirSource};
 use transform::simplify;
 use transform::no_landing_pads::no_landing_pads;
-use dataflow::{self, MaybeStorageLive, state_for_location};
+use dataflow::{self, MaybeStorageLive, MoveDataParamEnv};
+use dataflow::state_for_location;
+use dataflow::move_paths::{LookupResult, MovePathIndex};
+use syntax_pos::Span;
 
 pub struct StateTransform;
 
+/// The state of a generator which has not been resumed yet. This is the state
+/// generators are created in by `build::expr::as_rvalue`.
+pub const UNRESUMED: u32 = 0;
+
+/// The state of the first suspension point. The others follow contiguously.
+const FIRST_SUSPENSION_POINT: u32 = UNRESUMED + 1;
+
 struct RenameLocalVisitor {
     from: Local,
     to: Local,
@@ -113,10 +130,7 @@ impl<'tcx> MutVisitor<'tcx> for DerefArgVisitor {
                     context: LvalueContext<'tcx>,
                     location: Location) {
         if *lvalue == Lvalue::Local(self_arg()) {
-            *lvalue = Lvalue::Projection(Box::new(Projection {
-                base: lvalue.clone(),
-                elem: ProjectionElem::Deref,
-            }));
+            *lvalue = lvalue.clone().deref();
         } else {
             self.super_lvalue(lvalue, context, location);
         }
@@ -151,11 +165,25 @@ struct TransformVisitor<'a, 'tcx: 'a> {
     // A list of suspension points, generated during the transform
     suspension_points: Vec<SuspensionPoint>,
 
+    // The number of suspension points in the generator, known before the transform
+    // so that the returned and poisoned states can follow the suspension states
+    suspension_point_count: u32,
+
     // The original RETURN_POINTER local
     new_ret_local: Local,
 }
 
 impl<'a, 'tcx> TransformVisitor<'a, 'tcx> {
+    // The state of a generator which has returned / is completed
+    fn returned_state(&self) -> u32 {
+        FIRST_SUSPENSION_POINT + self.suspension_point_count
+    }
+
+    // The state of a generator which has been poisoned
+    fn poisoned_state(&self) -> u32 {
+        self.returned_state() + 1
+    }
+
     // Make a GeneratorState rvalue
     fn make_state(&self, idx: usize, val: Operand<'tcx>) -> Rvalue<'tcx> {
         let adt = AggregateKind::Adt(self.state_adt_ref, idx, self.state_substs, None);
@@ -164,12 +192,7 @@ impl<'a, 'tcx> TransformVisitor<'a, 'tcx> {
 
     // Create a Lvalue referencing a generator struct field
     fn make_field(&self, idx: usize, ty: Ty<'tcx>) -> Lvalue<'tcx> {
-        let base = Lvalue::Local(self_arg());
-        let field = Projection {
-            base: base,
-            elem: ProjectionElem::Field(Field::new(idx), ty),
-        };
-        Lvalue::Projection(Box::new(field))
+        Lvalue::Local(self_arg()).field(Field::new(idx), ty)
     }
 
     // Create a statement which changes the generator state
@@ -248,18 +271,31 @@ impl<'a, 'tcx> MutVisitor<'tcx> for TransformVisitor<'a, 'tcx> {
                     self.make_state(state_idx, v)),
             });
             let state = if let Some(resume) = resume { // Yield
-                let state = 3 + self.suspension_points.len() as u32;
+                let state = FIRST_SUSPENSION_POINT + self.suspension_points.len() as u32;
+                let storage_liveness = self.storage_liveness.get(&block).unwrap().clone();
+
+                // Locals which have live storage here but aren't saved in the generator
+                // struct stay plain stack locals. End their storage before returning,
+                // `create_cases` makes it live again on the way back in.
+                for l in storage_liveness.iter() {
+                    if !self.remap.contains_key(&l) {
+                        data.statements.push(Statement {
+                            source_info,
+                            kind: StatementKind::StorageDead(l),
+                        });
+                    }
+                }
 
                 self.suspension_points.push(SuspensionPoint {
                     state,
                     resume,
                     drop,
-                    storage_liveness: self.storage_liveness.get(&block).unwrap().clone(),
+                    storage_liveness,
                 });
 
                 state
             } else { // Return
-                 1 // state for returned
+                self.returned_state()
             };
             data.statements.push(self.set_state(state, source_info));
             data.terminator.as_mut().unwrap().kind = TerminatorKind::Return;
@@ -333,9 +369,11 @@ impl<'tcx> Visitor<'tcx> for StorageIgnored {
 }
 
 fn locals_live_across_suspend_points<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                               context: &MirOptContext<'tcx>,
                                                mir: &Mir<'tcx>,
                                                source: MirSource) ->
                                                (liveness::LocalSet,
+                                                HashMap<BasicBlock, liveness::LocalSet>,
                                                 HashMap<BasicBlock, liveness::LocalSet>) {
     let dead_unwinds = IdxSetBuf::new_empty(mir.basic_blocks().len());
     let node_id = tcx.hir.as_local_node_id(source.def_id).unwrap();
@@ -347,6 +385,10 @@ fn locals_live_across_suspend_points<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     let mut ignored = StorageIgnored(IdxSetBuf::new_filled(mir.local_decls.len()));
     ignored.visit_mir(mir);
 
+    // Which locals may hold a value at each suspension point. Bodies moving out
+    // of lvalues that can't be tracked are left to liveness alone.
+    let init_results = context.analyses.initialization_results(tcx, source, mir);
+
     let mut set = liveness::LocalSet::new_empty(mir.local_decls.len());
     let liveness = liveness::liveness_of_locals(mir, LivenessMode {
         include_regular_use: true,
@@ -355,6 +397,7 @@ fn locals_live_across_suspend_points<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     liveness::dump_mir(tcx, "generator_liveness", source, mir, &liveness);
 
     let mut storage_liveness_map = HashMap::new();
+    let mut suspension_liveness_map = HashMap::new();
 
     for (block, data) in mir.basic_blocks().iter_enumerated() {
         if let TerminatorKind::Yield { .. } = data.terminator().kind {
@@ -363,7 +406,7 @@ fn locals_live_across_suspend_points<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
                 statement_index: data.statements.len(),
             };
 
-            let storage_liveness = state_for_location(loc, &analysis, &storage_live);
+            let storage_liveness = state_for_location(loc, &analysis, &storage_live, mir);
 
             storage_liveness_map.insert(block, storage_liveness.clone());
 
@@ -372,23 +415,68 @@ fn locals_live_across_suspend_points<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
             // Mark locals without storage statements as always having live storage
             live_locals.union(&ignored.0);
 
+            // A local has to be saved at this suspension point only if it can be read
+            // once the generator is resumed (or dropped) from here, without being
+            // redefined first, i.e. if it is live right after the yield. Uses leading
+            // up to the yield itself don't count, so a local which is fully consumed
+            // before the yield stays on the stack even if its storage spans the yield.
+            let block_liveness = liveness.statement_precise(mir, block);
+
             // Locals live are live at this point only if they are used across suspension points
             // and their storage is live
-            live_locals.intersect(&liveness.outs[block]);
+            live_locals.intersect(block_liveness.live_after(loc));
+
+            // Liveness counts writes to a part of a local as uses of the whole
+            // local, so a local which was moved out of before the yield and is
+            // written to field by field after it still looks live. Its value can't
+            // be read after the yield either way, so it isn't saved here.
+            if let Some(ref results) = init_results {
+                let init = results.state_at(tcx, mir, loc);
+                let uninit: Vec<_> = live_locals.iter()
+                    .filter(|&local| {
+                        !may_be_initialized(tcx, mir, &results.env, init.maybe_initialized(), local)
+                    })
+                    .collect();
+                for local in uninit {
+                    live_locals.remove(&local);
+                }
+            }
+
+            // The generator argument is ignored
+            live_locals.remove(&self_arg());
 
             // Add the locals life at this suspension point to the set of locals which live across
             // any suspension points
             set.union(&live_locals);
+
+            suspension_liveness_map.insert(block, live_locals);
         }
     }
 
-    // The generator argument is ignored
-    set.remove(&self_arg());
+    (set, storage_liveness_map, suspension_liveness_map)
+}
 
-    (set, storage_liveness_map)
+/// Whether `local`, or any part of it, may be initialized according to `init`.
+fn may_be_initialized<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                mir: &Mir<'tcx>,
+                                env: &MoveDataParamEnv<'tcx, 'tcx>,
+                                init: &IdxSetBuf<MovePathIndex>,
+                                local: Local)
+                                -> bool {
+    match env.move_data.rev_lookup.find(&Lvalue::Local(local)) {
+        LookupResult::Exact(path) => {
+            let mut initialized = false;
+            dataflow::on_all_children_bits(tcx, mir, &env.move_data, path, |child| {
+                initialized |= init.contains(&child);
+            });
+            initialized
+        }
+        LookupResult::Parent(_) => true,
+    }
 }
 
 fn compute_layout<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                            context: &MirOptContext<'tcx>,
                             source: MirSource,
                             interior: GeneratorInterior<'tcx>,
                             mir: &mut Mir<'tcx>)
@@ -397,7 +485,8 @@ fn compute_layout<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
         HashMap<BasicBlock, liveness::LocalSet>)
 {
     // Use a liveness analysis to compute locals which are live across a suspension point
-    let (live_locals, storage_liveness) = locals_live_across_suspend_points(tcx, mir, source);
+    let (live_locals, storage_liveness, suspension_liveness) =
+        locals_live_across_suspend_points(tcx, context, mir, source);
 
     // Erase regions from the types passed in from typeck so we can compare them with
     // MIR types
@@ -420,6 +509,8 @@ fn compute_layout<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
         }
     }
 
+    lint_large_saved_locals(tcx, source, mir, &suspension_liveness);
+
     let upvar_len = mir.upvar_decls.len();
     let dummy_local = LocalDecl::new_internal(tcx.mk_nil(), mir.span);
 
@@ -444,6 +535,119 @@ fn compute_layout<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     (remap, layout, storage_liveness)
 }
 
+/// Reports the `large_generator_local` lint for locals saved in the generator
+/// struct which are larger than `-Z large-generator-local-threshold`. Each local
+/// is reported for the first suspension point it is held across.
+fn lint_large_saved_locals<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                     source: MirSource,
+                                     mir: &Mir<'tcx>,
+                                     suspension_liveness: &HashMap<BasicBlock,
+                                                                   liveness::LocalSet>) {
+    let visibility_scope_info = match mir.visibility_scope_info {
+        ClearOnDecode::Set(ref data) => data,
+        ClearOnDecode::Clear => return,
+    };
+    let threshold = tcx.sess.opts.debugging_opts.large_generator_local_threshold as u64;
+    let param_env = tcx.param_env(source.def_id);
+
+    let mut suspension_points: Vec<_> = suspension_liveness.iter().collect();
+    suspension_points.sort_by_key(|&(&block, _)| block);
+
+    let mut reported = liveness::LocalSet::new_empty(mir.local_decls.len());
+    for (&block, live_locals) in suspension_points {
+        let yield_span = mir[block].terminator().source_info.span;
+        for local in live_locals.iter() {
+            let decl = &mir.local_decls[local];
+            if decl.internal || !reported.add(&local) {
+                continue;
+            }
+
+            let size = match (tcx, param_env).layout_of(decl.ty) {
+                Ok(layout) => layout.size.bytes(),
+                // The size of locals with generic types is not known here
+                Err(_) => continue,
+            };
+            if size <= threshold {
+                continue;
+            }
+
+            let what = match decl.name {
+                Some(name) => format!("local `{}`", name),
+                None => "temporary".to_string(),
+            };
+            let lint_root = visibility_scope_info[decl.source_info.scope].lint_root;
+            tcx.struct_span_lint_node(LARGE_GENERATOR_LOCAL,
+                                      lint_root,
+                                      decl.source_info.span,
+                                      &format!("{} of type `{}` is held across a yield",
+                                               what, decl.ty))
+                .span_label(decl.source_info.span,
+                            format!("this is {} bytes, which is saved in the generator", size))
+                .span_label(yield_span, "held across this yield")
+                .emit();
+        }
+    }
+}
+
+fn describe_size(size: Option<u64>) -> String {
+    match size {
+        Some(bytes) => format!("{} bytes", bytes),
+        None => "an unknown number of bytes".to_string(),
+    }
+}
+
+/// Reports the layout computed for the generator as a note on its span, used
+/// by `-Z print-generator-sizes`.
+fn print_generator_sizes<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                   def_id: DefId,
+                                   gen_ty: Ty<'tcx>,
+                                   layout: &GeneratorLayout<'tcx>,
+                                   span: Span) {
+    let param_env = tcx.param_env(def_id);
+    let size_of = |ty: Ty<'tcx>| {
+        (tcx, param_env).layout_of(ty).ok().map(|layout| layout.size.bytes())
+    };
+
+    let upvar_tys: Vec<_> = match gen_ty.sty {
+        ty::TyGenerator(gen_def_id, substs, _) => substs.upvar_tys(gen_def_id, tcx).collect(),
+        ref t => bug!("type of generator not a generator: {:?}", t),
+    };
+
+    // We can't ask for the layout of the generator type itself since that
+    // requires the MIR we are building, so lay out its fields as a tuple instead.
+    // This matches the univariant layout used for generators.
+    let field_tys: Vec<_> = upvar_tys.into_iter()
+        .chain(once(tcx.types.u32))
+        .chain(layout.fields.iter().map(|decl| decl.ty))
+        .collect();
+    let gen_size = size_of(tcx.intern_tup(&field_tys, false));
+    let discr_size = size_of(tcx.types.u32);
+
+    let mut diag = tcx.sess.diagnostic().span_note_diag(span,
+        &format!("generator `{}` is {}, with a state discriminant of {}",
+                 tcx.item_path_str(def_id),
+                 describe_size(gen_size),
+                 describe_size(discr_size)));
+
+    if layout.fields.is_empty() {
+        diag.note("no locals are saved across suspension points");
+    }
+
+    for decl in &layout.fields {
+        let name = match decl.name {
+            Some(name) => format!("local `{}`", name),
+            None => "temporary".to_string(),
+        };
+        diag.span_note(decl.source_info.span,
+                       &format!("saved {} of type `{}` is {}",
+                                name,
+                                decl.ty,
+                                describe_size(size_of(decl.ty))));
+    }
+
+    diag.emit();
+}
+
 fn insert_switch<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
                            mir: &mut Mir<'tcx>,
                            cases: Vec<(u32, BasicBlock)>,
@@ -451,6 +655,11 @@ fn insert_switch<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
                            default: TerminatorKind<'tcx>) {
     let default_block = insert_term_block(mir, default);
 
+    // Callers cover every state, so keep the values sorted and dense
+    let mut cases = cases;
+    cases.sort_by_key(|&(state, _)| state);
+    debug_assert!(cases.iter().enumerate().all(|(i, &(state, _))| state == i as u32));
+
     let switch = TerminatorKind::SwitchInt {
         discr: Operand::Copy(transform.make_field(transform.state_field, tcx.types.u32)),
         switch_ty: tcx.types.u32,
@@ -540,14 +749,24 @@ fn create_generator_drop_shim<'a, 'tcx>(
 
     let source_info = source_info(&mir);
 
+    let return_block = insert_term_block(&mut mir, TerminatorKind::Return);
+
     let mut cases = create_cases(&mut mir, transform, |point| point.drop);
 
-    cases.insert(0, (0, drop_clean));
+    // Suspension points without a drop target have nothing left to drop
+    for point in &transform.suspension_points {
+        if point.drop.is_none() {
+            cases.push((point.state, return_block));
+        }
+    }
 
-    // The returned state (1) and the poisoned state (2) falls through to
-    // the default case which is just to return
+    cases.push((UNRESUMED, drop_clean));
 
-    insert_switch(tcx, &mut mir, cases, &transform, TerminatorKind::Return);
+    // Generators in the returned and poisoned states have nothing left to drop either
+    cases.push((transform.returned_state(), return_block));
+    cases.push((transform.poisoned_state(), return_block));
+
+    insert_switch(tcx, &mut mir, cases, &transform, TerminatorKind::Unreachable);
 
     for block in mir.basic_blocks_mut() {
         let kind = &mut block.terminator_mut().kind;
@@ -652,18 +871,20 @@ fn create_generator_resume_function<'a, 'tcx>(
     for block in mir.basic_blocks_mut() {
         let source_info = block.terminator().source_info;
         if let &TerminatorKind::Resume = &block.terminator().kind {
-            block.statements.push(transform.set_state(1, source_info));
+            block.statements.push(transform.set_state(transform.returned_state(), source_info));
         }
     }
 
     let mut cases = create_cases(mir, &transform, |point| Some(point.resume));
 
-    // Jump to the entry point on the 0 state
-    cases.insert(0, (0, BasicBlock::new(0)));
-    // Panic when resumed on the returned (1) state
-    cases.insert(1, (1, insert_panic_block(tcx, mir, AssertMessage::GeneratorResumedAfterReturn)));
-    // Panic when resumed on the poisoned (2) state
-    cases.insert(2, (2, insert_panic_block(tcx, mir, AssertMessage::GeneratorResumedAfterPanic)));
+    // Jump to the entry point on the unresumed state
+    cases.push((UNRESUMED, BasicBlock::new(0)));
+    // Panic when resumed on the returned state
+    cases.push((transform.returned_state(),
+                insert_panic_block(tcx, mir, AssertMessage::GeneratorResumedAfterReturn)));
+    // Panic when resumed on the poisoned state
+    cases.push((transform.poisoned_state(),
+                insert_panic_block(tcx, mir, AssertMessage::GeneratorResumedAfterPanic)));
 
     insert_switch(tcx, mir, cases, &transform, TerminatorKind::Unreachable);
 
@@ -749,15 +970,16 @@ fn create_cases<'a, 'tcx, F>(mir: &mut Mir<'tcx>,
 }
 
 impl MirPass for StateTransform {
-    fn run_pass<'a, 'tcx>(&self,
-                    tcx: TyCtxt<'a, 'tcx, 'tcx>,
-                    source: MirSource,
-                    mir: &mut Mir<'tcx>) {
+    fn run_pass_in_context<'a, 'tcx>(&self,
+                                     tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                     context: &MirOptContext<'tcx>,
+                                     source: MirSource,
+                                     mir: &mut Mir<'tcx>) -> bool {
         let yield_ty = if let Some(yield_ty) = mir.yield_ty {
             yield_ty
         } else {
             // This only applies to generators
-            return
+            return false
         };
 
         assert!(mir.generator_drop.is_none());
@@ -790,10 +1012,21 @@ impl MirPass for StateTransform {
         // Extract locals which are live across suspension point into `layout`
         // `remap` gives a mapping from local indices onto generator struct indices
         // `storage_liveness` tells us which locals have live storage at suspension points
-        let (remap, layout, storage_liveness) = compute_layout(tcx, source, interior, mir);
+        let (remap, layout, storage_liveness) = compute_layout(tcx, context, source, interior, mir);
+
+        if tcx.sess.opts.debugging_opts.print_generator_sizes {
+            print_generator_sizes(tcx, def_id, gen_ty, &layout, mir.span);
+        }
 
         let state_field = mir.upvar_decls.len();
 
+        let suspension_point_count = mir.basic_blocks().iter().filter(|data| {
+            match data.terminator().kind {
+                TerminatorKind::Yield { .. } => true,
+                _ => false,
+            }
+        }).count() as u32;
+
         // Run the transformation which converts Lvalues from Local to generator struct
         // accesses for locals in `remap`.
         // It also rewrites `return x` and `yield y` as writing a new generator state and returning
@@ -805,6 +1038,7 @@ impl MirPass for StateTransform {
             remap,
             storage_liveness,
             suspension_points: Vec::new(),
+            suspension_point_count,
             new_ret_local,
             state_field,
         };
@@ -817,7 +1051,7 @@ impl MirPass for StateTransform {
         mir.generator_layout = Some(layout);
 
         // Insert `drop(generator_struct)` which is used to drop upvars for generators in
-        // the unresumed (0) state.
+        // the unresumed state.
         // This is expanded to a drop ladder in `elaborate_generator_drops`.
         let drop_clean = insert_clean_drop(mir);
 
@@ -843,5 +1077,6 @@ impl MirPass for StateTransform {
 
         // Create the Generator::resume function
         create_generator_resume_function(tcx, transform, def_id, source, mir);
+        true
     }
 }
@@ -0,0 +1,162 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! This pass eliminates the computations of expressions that are
+//! available, i.e. already computed on every path to them with the same
+//! operands, even when those paths go through other blocks, like `x ^ y`
+//! after an `if` that computes it in both branches.
+//!
+//! The availability comes from the `AvailableExpressions` dataflow
+//! analysis. Every expression with a redundant computation gets a new
+//! temporary, which each of its other computations copies its result
+//! into, and the redundant computations are replaced by a copy of that
+//! temporary. An expression computed on only some of the paths to a
+//! computation is left alone, as is an expression whose operands may have
+//! changed since.
+
+use dataflow::{AvailableExpressions, BitDenotation, BlockSets, DataflowAnalysis};
+use dataflow::{DataflowResults, ExprIndex};
+use rustc::mir::{LocalDecl, Location, Lvalue, Mir, Operand, Rvalue, Statement, StatementKind};
+use rustc::mir::traversal;
+use rustc::ty::TyCtxt;
+use rustc::util::nodemap::{FxHashMap, FxHashSet};
+use rustc_data_structures::indexed_set::IdxSetBuf;
+use rustc_data_structures::indexed_vec::Idx;
+use transform::{MirOptContext, MirPass, MirSource};
+
+pub struct GlobalCse;
+
+impl MirPass for GlobalCse {
+    fn run_pass_in_context<'a, 'tcx>(&self,
+                                     tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                     context: &MirOptContext<'tcx>,
+                                     _source: MirSource,
+                                     mir: &mut Mir<'tcx>) -> bool {
+        if context.is_const_context {
+            return false;
+        }
+
+        let (redundant, computations) = {
+            let analysis = AvailableExpressions::new(mir);
+            if analysis.exprs().is_empty() {
+                return false;
+            }
+            let dead_unwinds = IdxSetBuf::new_empty(mir.basic_blocks().len());
+            let results = DataflowAnalysis::new(mir, &dead_unwinds, analysis).run();
+            let redundant = find_redundant(mir, &results);
+            let analysis = results.operator();
+            let computations: FxHashMap<ExprIndex, Vec<Location>> = redundant.iter()
+                .map(|&(_, expr)| (expr, analysis.computations(expr).to_vec()))
+                .collect();
+            (redundant, computations)
+        };
+        if redundant.is_empty() {
+            return false;
+        }
+
+        // Create a temporary for each expression with a redundant
+        // computation.
+        let mut stashes = FxHashMap();
+        for &(location, expr) in &redundant {
+            if stashes.contains_key(&expr) {
+                continue;
+            }
+            let (ty, span) = {
+                let statement = &mir[location.block].statements[location.statement_index];
+                match statement.kind {
+                    StatementKind::Assign(_, ref rvalue) => {
+                        (rvalue.ty(&*mir, tcx), statement.source_info.span)
+                    }
+                    _ => bug!("computation of an expression is not an assignment: {:?}",
+                              statement),
+                }
+            };
+            let stash = mir.local_decls.push(LocalDecl::new_temp(ty, span));
+            stashes.insert(expr, stash);
+        }
+
+        // Replace the redundant computations by a copy of the temporary.
+        let redundant_locations: FxHashSet<Location> =
+            redundant.iter().map(|&(location, _)| location).collect();
+        for &(location, expr) in &redundant {
+            let statement = &mut mir[location.block].statements[location.statement_index];
+            if let StatementKind::Assign(_, ref mut rvalue) = statement.kind {
+                *rvalue = Rvalue::Use(Operand::Copy(Lvalue::Local(stashes[&expr])));
+            }
+        }
+
+        // Store the result of every other computation in the temporary,
+        // inserting from the end of each block so that the statement
+        // indices stay valid.
+        let mut stores = vec![];
+        for (expr, locations) in &computations {
+            for &location in locations {
+                if redundant_locations.contains(&location) {
+                    continue;
+                }
+                let statement = &mir[location.block].statements[location.statement_index];
+                let dest = match statement.kind {
+                    StatementKind::Assign(ref dest, _) => dest.clone(),
+                    _ => bug!("computation of an expression is not an assignment: {:?}",
+                              statement),
+                };
+                stores.push((location, Statement {
+                    source_info: statement.source_info,
+                    kind: StatementKind::Assign(Lvalue::Local(stashes[expr]),
+                                                Rvalue::Use(Operand::Copy(dest))),
+                }));
+            }
+        }
+        stores.sort_by(|&(a, _), &(b, _)| {
+            (b.block, b.statement_index).cmp(&(a.block, a.statement_index))
+        });
+        for (location, store) in stores {
+            mir[location.block].statements.insert(location.statement_index + 1, store);
+        }
+        true
+    }
+}
+
+/// Returns the computations of expressions that are already available,
+/// with the expression they compute.
+fn find_redundant<'a, 'tcx>(mir: &Mir<'tcx>,
+                            results: &DataflowResults<AvailableExpressions<'a, 'tcx>>)
+                            -> Vec<(Location, ExprIndex)> {
+    let analysis = results.operator();
+    let mut redundant = vec![];
+    let bits_per_block = analysis.bits_per_block();
+    let mut gen = IdxSetBuf::new_empty(bits_per_block);
+    let mut kill = IdxSetBuf::new_empty(bits_per_block);
+    // Everything is available on entry to unreachable blocks.
+    for (block, data) in traversal::preorder(mir) {
+        let mut available = results.sets().on_entry_set_for(block.index());
+        for statement_index in 0..data.statements.len() {
+            let location = Location { block, statement_index };
+            if let Some(expr) = analysis.expr_at(location) {
+                if available.contains(&expr) {
+                    redundant.push((location, expr));
+                }
+            }
+            gen.clear();
+            kill.clear();
+            {
+                let mut sets = BlockSets {
+                    on_entry: &mut available,
+                    gen_set: &mut gen,
+                    kill_set: &mut kill,
+                };
+                analysis.statement_effect(&mut sets, location);
+            }
+            available.union(&gen);
+            available.subtract(&kill);
+        }
+    }
+    redundant
+}
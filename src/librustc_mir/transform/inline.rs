@@ -24,17 +24,18 @@ use rustc::ty::subst::{Subst,Substs};
 
 use std::collections::VecDeque;
 use std::iter;
-use transform::{MirPass, MirSource};
+use transform::{MirOptContext, MirPass, MirSource};
+use super::dissolve_closures::dissolve_closures;
 use super::simplify::{remove_dead_blocks, CfgSimplifier};
+use util::journal::Journal;
 
 use syntax::{attr};
 use syntax::abi::Abi;
 
-const DEFAULT_THRESHOLD: usize = 50;
-const HINT_THRESHOLD: usize = 100;
-
 const INSTR_COST: usize = 5;
 const CALL_PENALTY: usize = 25;
+const LANDING_PAD_PENALTY: usize = 10;
+const CONST_SWITCH_BONUS: usize = 10;
 
 const UNKNOWN_SIZE_COST: usize = 10;
 
@@ -46,26 +47,40 @@ struct CallSite<'tcx> {
     substs: &'tcx Substs<'tcx>,
     bb: BasicBlock,
     location: SourceInfo,
+    /// The index in the inlining history of the inlined call this callsite
+    /// came from, if it wasn't in the caller to begin with.
+    inlined_from: Option<usize>,
+}
+
+/// A call inlined into the caller.
+#[derive(Copy, Clone, Debug)]
+struct InlinedCall {
+    callee: DefId,
+    /// The index in the inlining history of the inlined call this call came
+    /// from, if it wasn't in the caller to begin with.
+    inlined_from: Option<usize>,
 }
 
 impl MirPass for Inline {
-    fn run_pass<'a, 'tcx>(&self,
-                          tcx: TyCtxt<'a, 'tcx, 'tcx>,
-                          source: MirSource,
-                          mir: &mut Mir<'tcx>) {
-        if tcx.sess.opts.debugging_opts.mir_opt_level >= 2 {
-            Inliner { tcx, source }.run_pass(mir);
-        }
+    fn run_pass_in_context<'a, 'tcx>(&self,
+                                     tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                     _context: &MirOptContext<'tcx>,
+                                     source: MirSource,
+                                     mir: &mut Mir<'tcx>) -> bool {
+        let journal = Journal::new(tcx, source, &self.name());
+        Inliner { tcx, source, journal }.run_pass(mir)
     }
 }
 
 struct Inliner<'a, 'tcx: 'a> {
     tcx: TyCtxt<'a, 'tcx, 'tcx>,
     source: MirSource,
+    journal: Journal<'a>,
 }
 
 impl<'a, 'tcx> Inliner<'a, 'tcx> {
-    fn run_pass(&self, caller_mir: &mut Mir<'tcx>) {
+    /// Inlines the calls of `caller_mir`, returning whether it inlined any.
+    fn run_pass(&self, caller_mir: &mut Mir<'tcx>) -> bool {
         // Keep a queue of callsites to try inlining on. We take
         // advantage of the fact that queries detect cycles here to
         // allow us to try and fetch the fully optimized MIR of a
@@ -79,6 +94,7 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
         // file. =)
 
         let mut callsites = VecDeque::new();
+        let mut history = Vec::new();
 
         let param_env = self.tcx.param_env(self.source.def_id);
 
@@ -104,18 +120,20 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
                                     callee: instance.def_id(),
                                     substs: instance.substs,
                                     bb,
-                                    location: terminator.source_info
+                                    location: terminator.source_info,
+                                    inlined_from: None,
                                 });
                             }
                         }
                     }
             }
         } else {
-            return;
+            return false;
         }
 
         let mut local_change;
         let mut changed = false;
+        let mut inlined_closure = false;
 
         loop {
             local_change = false;
@@ -126,10 +144,17 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
                     continue;
                 }
 
+                if self.is_recursive(&history, callsite) {
+                    debug!("checking whether to inline callsite {:?} - recursive", callsite);
+                    continue;
+                }
+
                 let callee_mir = match ty::queries::optimized_mir::try_get(self.tcx,
                                                                            callsite.location.span,
                                                                            callsite.callee) {
-                    Ok(ref callee_mir) if self.should_inline(callsite, callee_mir) => {
+                    Ok(ref callee_mir)
+                        if self.should_inline(callsite, caller_mir, callee_mir) =>
+                    {
                         subst_and_normalize(callee_mir, self.tcx, &callsite.substs, param_env)
                     }
                     Ok(_) => continue,
@@ -148,6 +173,11 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
                     continue;
                 }
                 debug!("attempting to inline callsite {:?} - success", callsite);
+                let inlined_from = Some(history.len());
+                history.push(InlinedCall {
+                    callee: callsite.callee,
+                    inlined_from: callsite.inlined_from,
+                });
 
                 // Add callsites from inlined function
                 for (bb, bb_data) in caller_mir.basic_blocks().iter_enumerated().skip(start) {
@@ -156,19 +186,18 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
                     if let TerminatorKind::Call {
                         func: Operand::Constant(ref f), .. } = terminator.kind {
                         if let ty::TyFnDef(callee_def_id, substs) = f.ty.sty {
-                            // Don't inline the same function multiple times.
-                            if callsite.callee != callee_def_id {
-                                callsites.push_back(CallSite {
-                                    callee: callee_def_id,
-                                    substs,
-                                    bb,
-                                    location: terminator.source_info
-                                });
-                            }
+                            callsites.push_back(CallSite {
+                                callee: callee_def_id,
+                                substs,
+                                bb,
+                                location: terminator.source_info,
+                                inlined_from,
+                            });
                         }
                     }
                 }
 
+                inlined_closure |= self.tcx.is_closure(callsite.callee);
                 local_change = true;
                 changed = true;
             }
@@ -180,27 +209,49 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
 
         // Simplify if we inlined anything.
         if changed {
+            // Closures whose calls were inlined only need their captures.
+            if inlined_closure {
+                dissolve_closures(self.tcx, param_env, caller_mir);
+            }
             debug!("Running simplify cfg on {:?}", self.source);
             CfgSimplifier::new(caller_mir).simplify();
             remove_dead_blocks(caller_mir);
         }
+        changed
+    }
+
+    /// Whether `callsite` calls the caller, or a function already inlined on
+    /// the way to it, in which case inlining it could go on forever. Warns
+    /// about such calls to `#[inline(always)]` functions, which can't be
+    /// inlined as they ask.
+    fn is_recursive(&self, history: &[InlinedCall], callsite: CallSite<'tcx>) -> bool {
+        let mut recursive = callsite.callee == self.source.def_id;
+        let mut inlined_from = callsite.inlined_from;
+        while let Some(index) = inlined_from {
+            recursive |= history[index].callee == callsite.callee;
+            inlined_from = history[index].inlined_from;
+        }
+
+        if recursive {
+            let attrs = self.tcx.get_attrs(callsite.callee);
+            if let attr::InlineAttr::Always = attr::find_inline_attr(None, &attrs[..]) {
+                let path = self.tcx.item_path_str(callsite.callee);
+                self.tcx.sess.span_warn(callsite.location.span, &format!(
+                    "cannot inline recursive call to `#[inline(always)]` function `{}`", path));
+            }
+        }
+        recursive
     }
 
     fn should_inline(&self,
                      callsite: CallSite<'tcx>,
+                     caller_mir: &Mir<'tcx>,
                      callee_mir: &Mir<'tcx>)
                      -> bool
     {
         debug!("should_inline({:?})", callsite);
         let tcx = self.tcx;
 
-        // Don't inline closures that have captures
-        // FIXME: Handle closures better
-        if callee_mir.upvar_decls.len() > 0 {
-            debug!("    upvar decls present - not inlining");
-            return false;
-        }
-
         // Cannot inline generators which haven't been transformed yet
         if callee_mir.yield_ty.is_some() {
             debug!("    yield ty present - not inlining");
@@ -208,21 +259,55 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
         }
 
         let attrs = tcx.get_attrs(callsite.callee);
+
+        // Naked functions have no prologue, and their body is written for it.
+        if attr::contains_name(&attrs[..], "naked") {
+            debug!("    #[naked] present - not inlining");
+            return false;
+        }
+
+        // Inline assembly makes assumptions about the surrounding function,
+        // like the registers it may clobber and the labels it defines.
+        let has_inline_asm = callee_mir.basic_blocks().iter().any(|data| {
+            data.statements.iter().any(|statement| match statement.kind {
+                StatementKind::InlineAsm { .. } => true,
+                _ => false,
+            })
+        });
+        if has_inline_asm {
+            debug!("    inline asm present - not inlining");
+            return false;
+        }
+
+        // The callee may use instructions that the features of the caller
+        // don't allow, and these would then end up in the caller.
+        if !tcx.sess.opts.debugging_opts.inline_mir_ignore_target_features {
+            let caller_features = target_features(tcx, self.source.def_id);
+            let missing = target_features(tcx, callsite.callee).into_iter()
+                .find(|feature| !caller_features.contains(feature));
+            if let Some(feature) = missing {
+                debug!("    caller lacks target feature `{}` - not inlining", feature);
+                return false;
+            }
+        }
+
         let hint = attr::find_inline_attr(None, &attrs[..]);
 
         let hinted = match hint {
-            // Just treat inline(always) as a hint for now,
-            // there are cases that prevent inlining that we
-            // need to check for first.
-            attr::InlineAttr::Always => true,
+            attr::InlineAttr::Always |
+            attr::InlineAttr::Hint => true,
             attr::InlineAttr::Never => {
-                debug!("#[inline(never)] present - not inlining");
+                debug!("    #[inline(never)] present - not inlining");
                 return false
             }
-            attr::InlineAttr::Hint => true,
             attr::InlineAttr::None => false,
         };
 
+        if attr::contains_name(&attrs[..], "cold") {
+            debug!("    #[cold] present - not inlining");
+            return false;
+        }
+
         // Only inline local functions if they would be eligible for cross-crate
         // inlining. This is to ensure that the final crate doesn't have MIR that
         // reference unexported symbols
@@ -231,19 +316,32 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
                 debug!("    callee is an exported function - not inlining");
                 return false;
             }
+        } else if callsite.substs.types().count() == 0 && !hinted {
+            // Functions from other crates that are neither generic nor
+            // `#[inline]` only have MIR with `-Z always-encode-mir`, and
+            // nothing makes the private items they use available to us.
+            let mut items = LinkableItems { tcx, linkable: true };
+            items.visit_mir(callee_mir);
+            if !items.linkable {
+                debug!("    callee uses private items of its crate - not inlining");
+                return false;
+            }
+        }
+
+        // Inlining is left to LLVM for `#[inline(always)]` functions anyway,
+        // so doing it here, whatever the cost, only gives MIR optimizations
+        // more to work with.
+        if let attr::InlineAttr::Always = hint {
+            debug!("INLINING {:?} because inline(always)", callsite);
+            return true;
         }
 
         let mut threshold = if hinted {
-            HINT_THRESHOLD
+            tcx.sess.opts.debugging_opts.inline_mir_hint_threshold
         } else {
-            DEFAULT_THRESHOLD
+            tcx.sess.opts.debugging_opts.inline_mir_threshold
         };
 
-        // Significantly lower the threshold for inlining cold functions
-        if attr::contains_name(&attrs[..], "cold") {
-            threshold /= 5;
-        }
-
         // Give a bonus functions with a small number of blocks,
         // We normally have two or three blocks for even
         // very small functions.
@@ -256,77 +354,41 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
 
         let param_env = tcx.param_env(self.source.def_id);
 
-        let mut first_block = true;
-        let mut cost = 0;
-
-        // Traverse the MIR manually so we can account for the effects of
-        // inlining on the CFG.
-        let mut work_list = vec![START_BLOCK];
-        let mut visited = BitVector::new(callee_mir.basic_blocks().len());
-        while let Some(bb) = work_list.pop() {
-            if !visited.insert(bb.index()) { continue; }
-            let blk = &callee_mir.basic_blocks()[bb];
-
-            for stmt in &blk.statements {
-                // Don't count StorageLive/StorageDead in the inlining cost.
-                match stmt.kind {
-                    StatementKind::StorageLive(_) |
-                    StatementKind::StorageDead(_) |
-                    StatementKind::Nop => {}
-                    _ => cost += INSTR_COST
-                }
-            }
-            let term = blk.terminator();
-            let mut is_drop = false;
-            match term.kind {
-                TerminatorKind::Drop { ref location, target, unwind } |
-                TerminatorKind::DropAndReplace { ref location, target, unwind, .. } => {
-                    is_drop = true;
-                    work_list.push(target);
-                    // If the location doesn't actually need dropping, treat it like
-                    // a regular goto.
-                    let ty = location.ty(callee_mir, tcx).subst(tcx, callsite.substs);
-                    let ty = ty.to_ty(tcx);
-                    if ty.needs_drop(tcx, param_env) {
-                        cost += CALL_PENALTY;
-                        if let Some(unwind) = unwind {
-                            work_list.push(unwind);
-                        }
-                    } else {
-                        cost += INSTR_COST;
+        // The callee arguments that are constants at the call site.
+        let mut constant_locals = BitVector::new(callee_mir.local_decls.len());
+        if callee_mir.spread_arg.is_none() {
+            let call = &caller_mir[callsite.bb].terminator().kind;
+            if let TerminatorKind::Call { ref args, .. } = *call {
+                for (i, arg) in args.iter().enumerate() {
+                    if let Operand::Constant(_) = *arg {
+                        constant_locals.insert(i + 1);
                     }
                 }
-
-                TerminatorKind::Unreachable |
-                TerminatorKind::Call { destination: None, .. } if first_block => {
-                    // If the function always diverges, don't inline
-                    // unless the cost is zero
-                    threshold = 0;
-                }
-
-                TerminatorKind::Call {func: Operand::Constant(ref f), .. } => {
-                    if let ty::TyFnDef(def_id, _) = f.ty.sty {
-                        // Don't give intrinsics the extra penalty for calls
-                        let f = tcx.fn_sig(def_id);
-                        if f.abi() == Abi::RustIntrinsic || f.abi() == Abi::PlatformIntrinsic {
-                            cost += INSTR_COST;
-                        } else {
-                            cost += CALL_PENALTY;
-                        }
-                    }
-                }
-                TerminatorKind::Assert { .. } => cost += CALL_PENALTY,
-                _ => cost += INSTR_COST
             }
+        }
 
-            if !is_drop {
-                for &succ in &term.successors()[..] {
-                    work_list.push(succ);
+        let needs_drop = |location: &Lvalue<'tcx>| {
+            let ty = location.ty(callee_mir, tcx).subst(tcx, callsite.substs);
+            ty.to_ty(tcx).needs_drop(tcx, param_env)
+        };
+        let is_intrinsic = |func: &Operand<'tcx>| {
+            if let Operand::Constant(ref f) = *func {
+                if let ty::TyFnDef(def_id, _) = f.ty.sty {
+                    let abi = tcx.fn_sig(def_id).abi();
+                    return abi == Abi::RustIntrinsic || abi == Abi::PlatformIntrinsic;
                 }
             }
+            false
+        };
+        let body = body_cost(callee_mir.basic_blocks(), &constant_locals, needs_drop, is_intrinsic);
+        debug!("    {:?}", body);
 
-            first_block = false;
+        // If the function always diverges, don't inline
+        // unless the cost is zero
+        if body.diverges {
+            threshold = 0;
         }
+        let mut cost = body.cost;
 
         // Count up the cost of local variables and temps, if we know the size
         // use that, otherwise we use a moderately-large dummy cost.
@@ -345,17 +407,12 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
             }
         }
 
-        if let attr::InlineAttr::Always = hint {
-            debug!("INLINING {:?} because inline(always) [cost={}]", callsite, cost);
+        if cost <= threshold {
+            debug!("INLINING {:?} [cost={} <= threshold={}]", callsite, cost, threshold);
             true
         } else {
-            if cost <= threshold {
-                debug!("INLINING {:?} [cost={} <= threshold={}]", callsite, cost, threshold);
-                true
-            } else {
-                debug!("NOT inlining {:?} [cost={} > threshold={}]", callsite, cost, threshold);
-                false
-            }
+            debug!("NOT inlining {:?} [cost={} > threshold={}]", callsite, cost, threshold);
+            false
         }
     }
 
@@ -375,13 +432,18 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
                 let mut scope_map = IndexVec::with_capacity(callee_mir.visibility_scopes.len());
                 let mut promoted_map = IndexVec::with_capacity(callee_mir.promoted.len());
 
+                // The callee's scopes keep their spans, so that its code is
+                // still attributed to its own lines, and its root scope goes
+                // under the scope of the call, marked as inlined there.
                 for mut scope in callee_mir.visibility_scopes.iter().cloned() {
-                    if scope.parent_scope.is_none() {
-                        scope.parent_scope = Some(callsite.location.scope);
-                        scope.span = callee_mir.span;
-                    }
-
-                    scope.span = callsite.location.span;
+                    scope.parent_scope = match scope.parent_scope {
+                        Some(parent) => Some(scope_map[parent]),
+                        None => {
+                            scope.span = callee_mir.span;
+                            scope.inlined = Some((callsite.callee, callsite.location.span));
+                            Some(callsite.location.scope)
+                        }
+                    };
 
                     let idx = caller_mir.visibility_scopes.push(scope);
                     scope_map.push(idx);
@@ -391,7 +453,10 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
                     let mut local = callee_mir.local_decls[loc].clone();
 
                     local.source_info.scope = scope_map[local.source_info.scope];
-                    local.source_info.span = callsite.location.span;
+                    local.lexical_scope = scope_map[local.lexical_scope];
+                    if local.name.is_none() {
+                        local.source_info.span = callsite.location.span;
+                    }
 
                     let idx = caller_mir.local_decls.push(local);
                     local_map.push(idx);
@@ -427,7 +492,7 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
                     debug!("Creating temp for return destination");
                     let dest = Rvalue::Ref(
                         self.tcx.types.re_erased,
-                        BorrowKind::Mut,
+                        BorrowKind::Mut { allow_two_phase_borrow: false },
                         destination.0);
 
                     let ty = dest.ty(caller_mir, self.tcx);
@@ -486,7 +551,8 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
 
                 for (bb, mut block) in callee_mir.basic_blocks_mut().drain_enumerated(..) {
                     integrator.visit_basic_block_data(bb, &mut block);
-                    caller_mir.basic_blocks_mut().push(block);
+                    let new_block = caller_mir.basic_blocks_mut().push(block);
+                    self.journal.new_block(new_block);
                 }
 
                 let terminator = Terminator {
@@ -512,7 +578,7 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
                          callsite: &CallSite<'tcx>, caller_mir: &mut Mir<'tcx>) -> Local {
         let arg = Rvalue::Ref(
             self.tcx.types.re_erased,
-            BorrowKind::Mut,
+            BorrowKind::Mut { allow_two_phase_borrow: false },
             arg.deref());
 
         let ty = arg.ty(caller_mir, self.tcx);
@@ -652,6 +718,174 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
     }
 }
 
+/// The cost of inlining the blocks of a callee.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct BodyCost {
+    cost: usize,
+    /// Whether the callee diverges right away, in its start block.
+    diverges: bool,
+}
+
+/// Estimates the cost of inlining the blocks reachable from the start block
+/// of a callee.
+///
+/// Switches on the locals in `constant_locals`, which are the arguments that
+/// are constants at the call site and the locals copied from them, give a
+/// bonus instead of costing anything, as they fold into gotos once inlined.
+/// `needs_drop` tells whether a dropped lvalue has drop glue, and
+/// `is_intrinsic` whether a called function is an intrinsic.
+fn body_cost<'tcx, D, I>(blocks: &IndexVec<BasicBlock, BasicBlockData<'tcx>>,
+                         constant_locals: &BitVector,
+                         needs_drop: D,
+                         is_intrinsic: I)
+                         -> BodyCost
+    where D: Fn(&Lvalue<'tcx>) -> bool,
+          I: Fn(&Operand<'tcx>) -> bool
+{
+    let mut constant_locals = constant_locals.clone();
+    let mut landing_pads = BitVector::new(blocks.len());
+    let mut cost = 0;
+    let mut bonus = 0;
+    let mut diverges = false;
+
+    // Traverse the MIR manually so we can account for the effects of
+    // inlining on the CFG.
+    let mut work_list = vec![START_BLOCK];
+    let mut visited = BitVector::new(blocks.len());
+    while let Some(bb) = work_list.pop() {
+        if !visited.insert(bb.index()) { continue; }
+        let data = &blocks[bb];
+
+        for statement in &data.statements {
+            cost += statement_cost(statement);
+            propagate_constant(statement, &mut constant_locals);
+        }
+
+        let kind = &data.terminator().kind;
+        let mut successors = kind.successors().into_owned();
+        match *kind {
+            TerminatorKind::Drop { ref location, target, .. } |
+            TerminatorKind::DropAndReplace { ref location, target, .. }
+                if !needs_drop(location) =>
+            {
+                // If the location doesn't actually need dropping, treat it like
+                // a regular goto.
+                cost += INSTR_COST;
+                successors = vec![target];
+            }
+
+            TerminatorKind::Unreachable |
+            TerminatorKind::Call { destination: None, .. } if bb == START_BLOCK => {
+                diverges = true;
+            }
+
+            TerminatorKind::SwitchInt { ref discr, .. }
+                if operand_is_constant(discr, &constant_locals) =>
+            {
+                bonus += CONST_SWITCH_BONUS;
+            }
+
+            _ => cost += terminator_cost(kind, &is_intrinsic),
+        }
+
+        for succ in successors {
+            // Unwinding from a regular block into a cleanup block needs a
+            // landing pad, which is shared by all the edges into that block.
+            if !data.is_cleanup && blocks[succ].is_cleanup && landing_pads.insert(succ.index()) {
+                cost += LANDING_PAD_PENALTY;
+            }
+            work_list.push(succ);
+        }
+    }
+
+    BodyCost { cost: cost.saturating_sub(bonus), diverges }
+}
+
+/// The features that `#[target_feature]` attributes enable for `def_id`, as
+/// passed to LLVM.
+fn target_features(tcx: TyCtxt, def_id: DefId) -> Vec<String> {
+    let mut features = vec![];
+    for attr in tcx.get_attrs(def_id).iter() {
+        if !attr.check_name("target_feature") {
+            continue;
+        }
+        if let Some(value) = attr.value_str() {
+            features.extend(value.as_str().split(",")
+                .map(|feature| feature.trim())
+                .filter(|feature| !feature.is_empty())
+                .map(|feature| feature.to_string()));
+        }
+    }
+    features
+}
+
+/// The cost of inlining a statement. Statements that don't make it into the
+/// generated code are free.
+fn statement_cost(statement: &Statement) -> usize {
+    match statement.kind {
+        StatementKind::StorageLive(_) |
+        StatementKind::StorageDead(_) |
+        StatementKind::Validate(..) |
+        StatementKind::EndRegion(_) |
+        StatementKind::Nop => 0,
+        StatementKind::InlineAsm { .. } => CALL_PENALTY,
+        StatementKind::Assign(..) |
+        StatementKind::SetDiscriminant { .. } |
+        StatementKind::Assume(_) => INSTR_COST,
+    }
+}
+
+/// The cost of inlining a terminator, not counting the landing pads it
+/// needs. Gotos and returns are free, since inlining merges their blocks
+/// into the caller's.
+fn terminator_cost<'tcx, I>(kind: &TerminatorKind<'tcx>, is_intrinsic: I) -> usize
+    where I: Fn(&Operand<'tcx>) -> bool
+{
+    match *kind {
+        TerminatorKind::Goto { .. } |
+        TerminatorKind::FalseEdges { .. } |
+        TerminatorKind::Return |
+        TerminatorKind::Unreachable => 0,
+        // Don't give intrinsics the extra penalty for calls
+        TerminatorKind::Call { ref func, .. } if is_intrinsic(func) => INSTR_COST,
+        TerminatorKind::Drop { .. } |
+        TerminatorKind::DropAndReplace { .. } |
+        TerminatorKind::Call { .. } |
+        TerminatorKind::Assert { .. } => CALL_PENALTY,
+        TerminatorKind::SwitchInt { .. } |
+        TerminatorKind::Resume |
+        TerminatorKind::Yield { .. } |
+        TerminatorKind::GeneratorDrop => INSTR_COST,
+    }
+}
+
+/// Whether `operand` is a constant, or a local holding one.
+fn operand_is_constant(operand: &Operand, constant_locals: &BitVector) -> bool {
+    match *operand {
+        Operand::Constant(_) => true,
+        Operand::Copy(Lvalue::Local(local)) |
+        Operand::Move(Lvalue::Local(local)) => constant_locals.contains(local.index()),
+        _ => false,
+    }
+}
+
+/// Adds the local assigned by `statement` to `constant_locals` if it is a
+/// copy, or the discriminant, of a constant.
+fn propagate_constant(statement: &Statement, constant_locals: &mut BitVector) {
+    if let StatementKind::Assign(Lvalue::Local(local), ref rvalue) = statement.kind {
+        let is_constant = match *rvalue {
+            Rvalue::Use(ref operand) => operand_is_constant(operand, constant_locals),
+            Rvalue::Discriminant(Lvalue::Local(source)) => {
+                constant_locals.contains(source.index())
+            }
+            _ => false,
+        };
+        if is_constant {
+            constant_locals.insert(local.index());
+        }
+    }
+}
+
 fn type_size_of<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
                           param_env: ty::ParamEnv<'tcx>,
                           ty: Ty<'tcx>) -> Option<u64> {
@@ -682,6 +916,50 @@ fn subst_and_normalize<'a, 'tcx: 'a>(
     mir.fold_with(&mut f)
 }
 
+/// Checks whether a body from another crate only uses items that can be
+/// used from this one.
+struct LinkableItems<'a, 'tcx: 'a> {
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    linkable: bool,
+}
+
+impl<'a, 'tcx> LinkableItems<'a, 'tcx> {
+    /// Whether `def_id` is exported by its crate or foreign, or is
+    /// `#[inline]` and so gets instantiated wherever it is used.
+    fn is_linkable(&self, def_id: DefId) -> bool {
+        def_id.is_local() ||
+            self.tcx.is_exported_symbol(def_id) ||
+            self.tcx.is_foreign_item(def_id) ||
+            attr::requests_inline(&self.tcx.get_attrs(def_id))
+    }
+}
+
+impl<'a, 'tcx> Visitor<'tcx> for LinkableItems<'a, 'tcx> {
+    fn visit_ty(&mut self, ty: &Ty<'tcx>, _: TyContext) {
+        for ty in ty.walk() {
+            self.linkable &= match ty.sty {
+                // Generic functions get instantiated wherever they are used.
+                ty::TyFnDef(def_id, substs) => {
+                    substs.types().next().is_some() || self.is_linkable(def_id)
+                }
+                // Closures are never instantiated outside of their crate.
+                ty::TyClosure(def_id, _) => {
+                    def_id.is_local() || self.tcx.is_exported_symbol(def_id)
+                }
+                _ => true,
+            };
+        }
+    }
+
+    fn visit_static(&mut self,
+                    static_: &Static<'tcx>,
+                    context: LvalueContext<'tcx>,
+                    location: Location) {
+        self.linkable &= self.is_linkable(static_.def_id);
+        self.super_static(static_, context, location);
+    }
+}
+
 /**
  * Integrator.
  *
@@ -830,3 +1108,144 @@ impl<'a, 'tcx> MutVisitor<'tcx> for Integrator<'a, 'tcx> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syntax_pos::DUMMY_SP;
+
+    fn local(index: usize) -> Lvalue<'static> {
+        Lvalue::Local(Local::new(index))
+    }
+
+    fn block(statements: Vec<StatementKind<'static>>,
+             terminator: TerminatorKind<'static>)
+             -> BasicBlockData<'static> {
+        let source_info = SourceInfo { span: DUMMY_SP, scope: ARGUMENT_VISIBILITY_SCOPE };
+        let mut data = BasicBlockData::new(Some(Terminator { source_info, kind: terminator }));
+        data.statements = statements.into_iter()
+            .map(|kind| Statement { source_info, kind })
+            .collect();
+        data
+    }
+
+    fn call(destination: usize, cleanup: Option<usize>) -> TerminatorKind<'static> {
+        TerminatorKind::Call {
+            func: Operand::Copy(local(1)),
+            args: vec![],
+            destination: Some((local(0), BasicBlock::new(destination))),
+            cleanup: cleanup.map(BasicBlock::new),
+        }
+    }
+
+    fn assign(dest: usize, rvalue: Rvalue<'static>) -> StatementKind<'static> {
+        StatementKind::Assign(local(dest), rvalue)
+    }
+
+    fn cost(blocks: Vec<BasicBlockData<'static>>, needs_drop: bool) -> BodyCost {
+        let blocks = blocks.into_iter().collect();
+        body_cost(&blocks, &BitVector::new(8), |_| needs_drop, |_| false)
+    }
+
+    #[test]
+    fn storage_and_nops_are_free() {
+        let blocks = vec![
+            block(vec![StatementKind::StorageLive(Local::new(2)),
+                       StatementKind::StorageDead(Local::new(2)),
+                       StatementKind::Nop],
+                  TerminatorKind::Return),
+        ];
+        assert_eq!(cost(blocks, true), BodyCost { cost: 0, diverges: false });
+    }
+
+    #[test]
+    fn statements_cost_an_instruction() {
+        let blocks = vec![
+            block(vec![assign(2, Rvalue::Use(Operand::Copy(local(1)))),
+                       assign(0, Rvalue::Use(Operand::Move(local(2))))],
+                  TerminatorKind::Goto { target: BasicBlock::new(1) }),
+            block(vec![], TerminatorKind::Return),
+        ];
+        assert_eq!(cost(blocks, true).cost, 2 * INSTR_COST);
+    }
+
+    #[test]
+    fn calls_and_drops_are_expensive() {
+        let blocks = || vec![
+            block(vec![], call(1, None)),
+            block(vec![], TerminatorKind::Drop {
+                location: local(2),
+                target: BasicBlock::new(2),
+                unwind: None,
+            }),
+            block(vec![], TerminatorKind::Return),
+        ];
+        assert_eq!(cost(blocks(), true).cost, 2 * CALL_PENALTY);
+        // Drops of values without drop glue are only gotos.
+        assert_eq!(cost(blocks(), false).cost, CALL_PENALTY + INSTR_COST);
+    }
+
+    #[test]
+    fn intrinsics_cost_an_instruction() {
+        assert_eq!(terminator_cost(&call(1, None), |_| true), INSTR_COST);
+        assert_eq!(terminator_cost(&call(1, None), |_| false), CALL_PENALTY);
+    }
+
+    #[test]
+    fn landing_pads_are_penalized_once_per_cleanup_block() {
+        let mut cleanup = block(vec![], TerminatorKind::Resume);
+        cleanup.is_cleanup = true;
+        let blocks = vec![
+            block(vec![], call(1, Some(3))),
+            block(vec![], call(2, Some(3))),
+            block(vec![], TerminatorKind::Return),
+            cleanup,
+        ];
+        assert_eq!(cost(blocks, true).cost, 2 * CALL_PENALTY + LANDING_PAD_PENALTY + INSTR_COST);
+    }
+
+    #[test]
+    fn unwinding_from_cleanup_blocks_needs_no_landing_pad() {
+        let mut drop = block(vec![], TerminatorKind::Drop {
+            location: local(2),
+            target: BasicBlock::new(3),
+            unwind: None,
+        });
+        drop.is_cleanup = true;
+        let mut resume = block(vec![], TerminatorKind::Resume);
+        resume.is_cleanup = true;
+        let blocks = vec![
+            block(vec![], call(1, Some(2))),
+            block(vec![], TerminatorKind::Return),
+            drop,
+            resume,
+        ];
+        assert_eq!(cost(blocks, true).cost,
+                   2 * CALL_PENALTY + LANDING_PAD_PENALTY + INSTR_COST);
+    }
+
+    #[test]
+    fn diverging_start_block() {
+        let blocks = vec![block(vec![], TerminatorKind::Unreachable)];
+        assert_eq!(cost(blocks, true), BodyCost { cost: 0, diverges: true });
+    }
+
+    #[test]
+    fn constants_propagate_through_copies_and_discriminants() {
+        let mut constant_locals = BitVector::new(8);
+        constant_locals.insert(1);
+        let statements = vec![
+            assign(3, Rvalue::Use(Operand::Copy(local(1)))),
+            assign(4, Rvalue::Discriminant(local(3))),
+            assign(5, Rvalue::Use(Operand::Move(local(2)))),
+        ];
+        let source_info = SourceInfo { span: DUMMY_SP, scope: ARGUMENT_VISIBILITY_SCOPE };
+        for kind in statements {
+            propagate_constant(&Statement { source_info, kind }, &mut constant_locals);
+        }
+        assert!(operand_is_constant(&Operand::Move(local(3)), &constant_locals));
+        assert!(operand_is_constant(&Operand::Copy(local(4)), &constant_locals));
+        assert!(!operand_is_constant(&Operand::Copy(local(5)), &constant_locals));
+        assert!(!operand_is_constant(&Operand::Copy(local(2)), &constant_locals));
+    }
+}
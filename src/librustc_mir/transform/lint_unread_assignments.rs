@@ -0,0 +1,102 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! This pass implements the `unread_assignments` lint, which reports
+//! assignments to user variables whose value is never read.
+//!
+//! It complements the HIR-based `unused_assignments` lint by working on
+//! MIR, after desugaring. An assignment is reported when:
+//!
+//! - it assigns to a whole local that is a named user variable, whose
+//!   name doesn't start with an underscore;
+//! - the local is dead after the assignment, as computed by
+//!   `util::liveness`, counting drops as uses so that values observed
+//!   by a `Drop` impl are not reported;
+//! - no borrow of the local may be live at the assignment, as computed by
+//!   `MaybeObservedLocals`, since the liveness analysis doesn't track uses
+//!   through references;
+//! - the local is not a union, whose fields may alias each other;
+//! - the local is read somewhere, as variables that are never read at all
+//!   are reported by `unused_variables` instead.
+//!
+//! Assignments coming from inlined MIR are not reported.
+
+use dataflow::{DataflowAnalysis, MaybeObservedLocals};
+use rustc::lint;
+use rustc::lint::builtin::UNREAD_ASSIGNMENTS;
+use rustc::mir::{Lvalue, Mir, StatementKind};
+use rustc::ty::TyCtxt;
+use rustc_data_structures::indexed_set::IdxSetBuf;
+use transform::{MirOptContext, MirPass, MirSource};
+use util::liveness::{self, LivenessMode, LocalSet};
+
+pub struct LintUnreadAssignments;
+
+impl MirPass for LintUnreadAssignments {
+    fn run_pass_in_context<'a, 'tcx>(&self,
+                                     tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                     _context: &MirOptContext<'tcx>,
+                                     source: MirSource,
+                                     mir: &mut Mir<'tcx>) -> bool {
+        // Skip the liveness and dataflow analyses when nothing could be
+        // reported.
+        if let Some(node_id) = tcx.hir.as_local_node_id(source.def_id) {
+            if tcx.lint_level_at_node(UNREAD_ASSIGNMENTS, node_id).0 == lint::Allow {
+                return false;
+            }
+        }
+
+        let mode = LivenessMode { include_regular_use: true, include_drops: true };
+        let liveness = liveness::liveness_of_locals(mir, mode);
+
+        let mut ever_live = LocalSet::new_empty(mir.local_decls.len());
+        let mut unread = vec![];
+        for block in mir.basic_blocks().indices() {
+            let data = &mir[block];
+            for live in liveness.live_in_block(mir, block) {
+                if let Some(statement) = data.statements.get(live.location.statement_index) {
+                    if let StatementKind::Assign(Lvalue::Local(local), _) = statement.kind {
+                        if !live.after.contains(&local) {
+                            unread.push((local, live.location, statement.source_info));
+                        }
+                    }
+                }
+                ever_live.union(&live.before);
+            }
+        }
+        if unread.is_empty() {
+            return false;
+        }
+
+        let dead_unwinds = IdxSetBuf::new_empty(mir.basic_blocks().len());
+        let observed =
+            DataflowAnalysis::new(mir, &dead_unwinds, MaybeObservedLocals::new(mir)).run();
+        for (local, location, source_info) in unread {
+            let decl = &mir.local_decls[local];
+            let name = match decl.name {
+                Some(name) if decl.is_user_variable => name,
+                _ => continue,
+            };
+            if name.as_str().starts_with("_") ||
+               observed.maybe_observed(local, location) ||
+               !ever_live.contains(&local) ||
+               decl.ty.ty_adt_def().map_or(false, |adt| adt.is_union()) {
+                continue;
+            }
+            let lint_root = match mir.lint_root(source_info) {
+                Some(lint_root) => lint_root,
+                None => continue,
+            };
+            tcx.lint_node(UNREAD_ASSIGNMENTS, lint_root, source_info.span,
+                          &format!("value assigned to `{}` is never read", name));
+        }
+        false
+    }
+}
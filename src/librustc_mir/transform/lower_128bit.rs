@@ -13,23 +13,26 @@
 use rustc::hir::def_id::DefId;
 use rustc::middle::lang_items::LangItem;
 use rustc::mir::*;
+use rustc::session::Session;
 use rustc::ty::{Slice, Ty, TyCtxt, TypeVariants};
 use rustc_data_structures::indexed_vec::{Idx};
-use transform::{MirPass, MirSource};
+use transform::{MirOptContext, MirPass, MirSource};
 use syntax;
 
 pub struct Lower128Bit;
 
 impl MirPass for Lower128Bit {
-    fn run_pass<'a, 'tcx>(&self,
-                          tcx: TyCtxt<'a, 'tcx, 'tcx>,
-                          _src: MirSource,
-                          mir: &mut Mir<'tcx>) {
-        if !tcx.sess.opts.debugging_opts.lower_128bit_ops {
-            return
-        }
+    fn is_enabled(&self, sess: &Session) -> bool {
+        sess.opts.debugging_opts.lower_128bit_ops
+    }
 
+    fn run_pass_in_context<'a, 'tcx>(&self,
+                                     tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                     _context: &MirOptContext<'tcx>,
+                                     _src: MirSource,
+                                     mir: &mut Mir<'tcx>) -> bool {
         self.lower_128bit_ops(tcx, mir);
+        true
     }
 }
 
@@ -0,0 +1,76 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! This pass lowers calls to the `assume` intrinsic to `Assume` statements.
+//!
+//! As a call, `assume(cond)` ends its block, so that the passes working on
+//! straight-line code, like `ConstProp`, can't see past it, nor learn
+//! anything from it. The statement keeps the block going, and is translated
+//! back to `llvm.assume`.
+//!
+//! It runs after inlining, so that it also lowers the calls inlined from
+//! other functions.
+
+use rustc::mir::{AggregateKind, Mir, Operand, Rvalue, Statement, StatementKind};
+use rustc::mir::TerminatorKind;
+use rustc::ty::{self, TyCtxt};
+use syntax::abi::Abi;
+use transform::{MirOptContext, MirPass, MirSource};
+
+pub struct LowerAssume;
+
+impl MirPass for LowerAssume {
+    fn run_pass_in_context<'a, 'tcx>(&self,
+                                     tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                     _context: &MirOptContext<'tcx>,
+                                     _source: MirSource,
+                                     mir: &mut Mir<'tcx>) -> bool {
+        let mut changed = false;
+        for data in mir.basic_blocks_mut() {
+            let (cond, destination, target) = match data.terminator().kind {
+                TerminatorKind::Call {
+                    ref func, ref args, destination: Some((ref destination, target)), ..
+                } if is_assume(tcx, func) => (args[0].clone(), destination.clone(), target),
+                _ => continue,
+            };
+
+            let source_info = data.terminator().source_info;
+            data.statements.push(Statement {
+                source_info,
+                kind: StatementKind::Assume(cond),
+            });
+            data.statements.push(Statement {
+                source_info,
+                kind: StatementKind::Assign(destination,
+                                            Rvalue::Aggregate(box AggregateKind::Tuple, vec![])),
+            });
+            // The intrinsic never unwinds, so dropping the cleanup edge is fine.
+            data.terminator_mut().kind = TerminatorKind::Goto { target };
+            changed = true;
+        }
+        changed
+    }
+}
+
+/// Whether `func` is the `assume` intrinsic.
+fn is_assume<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, func: &Operand<'tcx>) -> bool {
+    match *func {
+        Operand::Constant(ref func) => {
+            match func.ty.sty {
+                ty::TyFnDef(def_id, _) => {
+                    tcx.fn_sig(def_id).abi() == Abi::RustIntrinsic &&
+                        tcx.item_name(def_id) == "assume"
+                }
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
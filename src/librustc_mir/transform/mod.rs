@@ -9,8 +9,10 @@
 // except according to those terms.
 
 use build;
+use dataflow::InitializationResults;
 use rustc::hir::def_id::{CrateNum, DefId, LOCAL_CRATE};
 use rustc::mir::{Mir, Promoted};
+use rustc::session::Session;
 use rustc::ty::TyCtxt;
 use rustc::ty::maps::Providers;
 use rustc::ty::steal::Steal;
@@ -18,13 +20,20 @@ use rustc::hir;
 use rustc::hir::intravisit::{self, Visitor, NestedVisitorMap};
 use rustc::util::nodemap::DefIdSet;
 use std::borrow::Cow;
+use std::cell::RefCell;
+use std::fmt;
 use std::rc::Rc;
+use std::time::Instant;
 use syntax::ast;
 use syntax_pos::Span;
+use util::count_mir;
+use util::mir_validator::MirValidator;
+use self::registry::{group_end, suite_passes, MirSuite};
 
 pub mod add_validation;
 pub mod add_moves_for_packed_drops;
 pub mod clean_end_regions;
+pub mod cleanup_post_borrowck;
 pub mod check_unsafety;
 pub mod simplify_branches;
 pub mod simplify;
@@ -40,10 +49,19 @@ pub mod dump_mir;
 pub mod deaggregator;
 pub mod instcombine;
 pub mod copy_prop;
+pub mod global_cse;
+pub mod const_prop;
+pub mod move_last_uses;
+pub mod lint_unread_assignments;
+pub mod effectless_loops;
 pub mod generator;
 pub mod inline;
+pub mod dissolve_closures;
+pub mod box_to_stack;
+pub mod lower_assume;
 pub mod nll;
 pub mod lower_128bit;
+pub mod registry;
 
 pub(crate) fn provide(providers: &mut Providers) {
     self::qualify_consts::provide(providers);
@@ -127,6 +145,98 @@ impl MirSource {
     }
 }
 
+/// Facts about a body that passes commonly gate on, computed once per
+/// body by `run_passes!` and handed to `MirPass::run_pass_in_context`.
+#[derive(Debug)]
+pub struct MirOptContext<'tcx> {
+    /// The `-Z mir-opt-level` in effect.
+    pub mir_opt_level: usize,
+
+    /// True if this is the body of a fn, including a `const fn`, rather
+    /// than of a const, a static or a promoted.
+    pub is_fn: bool,
+
+    /// True if the body may be evaluated at compile time, i.e. if it is a
+    /// const, a static, a `const fn` or a promoted. Trans might not be able
+    /// to evaluate such bodies once they are optimized.
+    pub is_const_context: bool,
+
+    /// True if this is the body of a generator.
+    pub is_generator: bool,
+
+    /// The analyses of the body shared by the passes.
+    pub analyses: AnalysisCache<'tcx>,
+}
+
+impl<'tcx> MirOptContext<'tcx> {
+    pub fn new<'a>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                   source: MirSource,
+                   mir: &Mir<'tcx>)
+                   -> MirOptContext<'tcx> {
+        let id = tcx.hir.as_local_node_id(source.def_id).unwrap();
+        let is_fn = match (tcx.hir.body_owner_kind(id), source.promoted) {
+            (hir::BodyOwnerKind::Fn, None) => true,
+            _ => false,
+        };
+        MirOptContext {
+            mir_opt_level: tcx.sess.opts.debugging_opts.mir_opt_level,
+            is_fn,
+            is_const_context: !is_fn || tcx.is_const_fn(source.def_id),
+            is_generator: mir.yield_ty.is_some() || mir.generator_layout.is_some(),
+            analyses: AnalysisCache::new(),
+        }
+    }
+}
+
+/// Analyses of a body that several passes need, computed when a pass
+/// first asks for them and kept until a pass changes the body, i.e. for
+/// as long as `Mir::version` stays the same.
+///
+/// Only `basic_blocks_mut` changes the version, so a pass which changes
+/// `local_decls` (or any other part of the body) must also go through
+/// `basic_blocks_mut`, e.g. by using a `MutVisitor` or a `MirPatch`, or
+/// later passes will see analyses of the body as it was before.
+pub struct AnalysisCache<'tcx> {
+    initialization: RefCell<Option<(usize, Option<Rc<InitializationResults<'tcx>>>)>>,
+}
+
+impl<'tcx> AnalysisCache<'tcx> {
+    fn new() -> Self {
+        AnalysisCache {
+            initialization: RefCell::new(None),
+        }
+    }
+
+    /// Returns the move data and initialization flow results of `mir`,
+    /// which must be the body of `source`, or `None` if the body moves out
+    /// of an lvalue it can't move out of.
+    pub fn initialization_results<'a>(&self,
+                                      tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                      source: MirSource,
+                                      mir: &Mir<'tcx>)
+                                      -> Option<Rc<InitializationResults<'tcx>>> {
+        let version = mir.version();
+        if let Some((cached_version, ref results)) = *self.initialization.borrow() {
+            if cached_version == version {
+                return results.clone();
+            }
+        }
+        let id = tcx.hir.as_local_node_id(source.def_id).unwrap();
+        let results = InitializationResults::compute(tcx, mir, id).map(Rc::new);
+        *self.initialization.borrow_mut() = Some((version, results.clone()));
+        results
+    }
+}
+
+impl<'tcx> fmt::Debug for AnalysisCache<'tcx> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cached_version = self.initialization.borrow().as_ref().map(|&(version, _)| version);
+        f.debug_struct("AnalysisCache")
+            .field("initialization", &cached_version)
+            .finish()
+    }
+}
+
 /// Generates a default name for the pass based on the name of the
 /// type `T`.
 pub fn default_name<T: ?Sized>() -> Cow<'static, str> {
@@ -140,38 +250,182 @@ pub fn default_name<T: ?Sized>() -> Cow<'static, str> {
 
 /// A streamlined trait that you can implement to create a pass; the
 /// pass will be named after the type, and it will consist of a main
-/// loop that goes over each available MIR and applies `run_pass`.
+/// loop that goes over each available MIR and applies `run_pass_in_context`.
 pub trait MirPass {
     fn name<'a>(&'a self) -> Cow<'a, str> {
         default_name::<Self>()
     }
 
-    fn run_pass<'a, 'tcx>(&self,
-                          tcx: TyCtxt<'a, 'tcx, 'tcx>,
-                          source: MirSource,
-                          mir: &mut Mir<'tcx>);
+    /// Whether the flags of `sess` let the pass run. The pass manager
+    /// neither runs nor dumps the passes that are disabled, by this, by
+    /// `-Z mir-enable-passes` or by the `-Z mir-opt-level`.
+    fn is_enabled(&self, _sess: &Session) -> bool {
+        true
+    }
+
+    /// The attribute keeping the pass from running on the body of the item
+    /// it is on, if any; the pass manager checks it, like `is_enabled`.
+    fn opt_out_attribute(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Runs the pass on `mir`, described by `context`, returning whether it
+    /// changed it. This is what `run_passes!` calls.
+    ///
+    /// The passes of a group are rerun until none of them changes the
+    /// body, so theirs must not claim a change they didn't make. The other
+    /// passes may return `true` when they can't tell cheaply.
+    fn run_pass_in_context<'a, 'tcx>(&self,
+                                     tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                     context: &MirOptContext<'tcx>,
+                                     source: MirSource,
+                                     mir: &mut Mir<'tcx>) -> bool;
 }
 
-pub macro run_passes($tcx:ident, $mir:ident, $def_id:ident, $suite_index:expr; $($pass:expr,)*) {{
-    let suite_index: usize = $suite_index;
+/// The number of times the passes of a group are run at most, should they
+/// keep changing the body.
+const MAX_GROUP_ROUNDS: usize = 8;
+
+/// Checks `mir` with the `MirValidator`, reporting a bug in the pass
+/// `pass_name` that ran last if it is invalid.
+fn verify_mir<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                        source: MirSource,
+                        mir: &Mir<'tcx>,
+                        pass_name: &str) {
+    let errors = MirValidator::new(tcx, source.def_id, mir).validate();
+    if !errors.is_empty() {
+        let errors: Vec<_> = errors.iter().map(|error| error.to_string()).collect();
+        span_bug!(mir.span, "invalid MIR for {:?} after `{}`:\n{}",
+                  source, pass_name, errors.join("\n"));
+    }
+}
+
+/// Returns the path of the item of `source`, followed by the index of the
+/// promoted body if it is one.
+fn body_path<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, source: MirSource) -> String {
+    let path = tcx.item_path_str(source.def_id);
+    match source.promoted {
+        Some(promoted) => format!("{} ({:?})", path, promoted),
+        None => path,
+    }
+}
+
+/// Returns whether `mir` has more statements than `-Z mir-opt-size-limit`
+/// allows the optional passes to run on, noting it the first time, when
+/// `noted` is false.
+fn over_size_limit<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                             source: MirSource,
+                             mir: &Mir<'tcx>,
+                             noted: &mut bool) -> bool {
+    let limit = match tcx.sess.opts.debugging_opts.mir_opt_size_limit {
+        Some(limit) => limit,
+        None => return false,
+    };
+    let statements: usize = mir.basic_blocks().iter().map(|data| data.statements.len()).sum();
+    if statements <= limit {
+        return false;
+    }
+    if !*noted {
+        tcx.sess.span_note_without_error(mir.span, &format!(
+            "skipping the optional MIR passes on `{}`, whose {} statements are more than \
+             the {} of `-Z mir-opt-size-limit`",
+            body_path(tcx, source), statements, limit));
+        *noted = true;
+    }
+    true
+}
+
+/// Records the counts of the optimized `mir` of `def_id`, and of its
+/// promoted bodies, for `-Z mir-stats`.
+fn record_mir_stats<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, def_id: DefId, mir: &Mir<'tcx>) {
+    let path = tcx.item_path_str(def_id);
+    let mut stats = tcx.sess.mir_stats.borrow_mut();
+    for (index, promoted_mir) in mir.promoted.iter_enumerated() {
+        stats.record_body(format!("{} ({:?})", path, index), count_mir(promoted_mir));
+    }
+    stats.record_body(path, count_mir(mir));
+}
+
+pub macro run_passes($tcx:ident, $mir:ident, $def_id:ident, $suite:expr) {{
+    let suite: MirSuite = $suite;
+    let suite_index = suite as usize;
+    let passes = suite_passes($tcx.sess, suite);
     let run_passes = |mir: &mut _, promoted| {
         let source = MirSource {
             def_id: $def_id,
             promoted
         };
-        let mut index = 0;
-        let mut run_pass = |pass: &MirPass| {
-            let run_hooks = |mir: &_, index, is_after| {
-                dump_mir::on_mir_pass($tcx, &format_args!("{:03}-{:03}", suite_index, index),
-                                      &pass.name(), source, mir, is_after);
+        let context = MirOptContext::new($tcx, source, mir);
+        let mut counts = if $tcx.sess.opts.debugging_opts.mir_stats {
+            Some(count_mir(mir))
+        } else {
+            None
+        };
+        let time_passes = $tcx.sess.time_mir_passes();
+        let mut noted_size = false;
+        // Runs the pass at `index`, in the `round`th run of its group,
+        // returning whether it changed `mir`. The rounds after the first
+        // are dumped under the name of the pass followed by the round.
+        let mut run_pass = |index: usize, round: usize| {
+            let pass = &passes[index];
+            if !pass.enabled || pass.pass.opt_out_attribute().map_or(false, |attr| {
+                $tcx.has_attr(source.def_id, attr)
+            }) {
+                return false;
+            }
+            if !pass.mandatory && over_size_limit($tcx, source, mir, &mut noted_size) {
+                return false;
+            }
+            let name = if round == 1 {
+                pass.pass.name()
+            } else {
+                Cow::from(format!("{}-round{}", pass.pass.name(), round))
             };
-            run_hooks(mir, index, false);
-            pass.run_pass($tcx, source, mir);
-            run_hooks(mir, index, true);
-
-            index += 1;
+            let before = dump_mir::before_mir_pass(
+                $tcx, &format_args!("{:03}-{:03}", suite_index, index), &name, source, mir);
+            let start = if time_passes { Some(Instant::now()) } else { None };
+            let changed = pass.pass.run_pass_in_context($tcx, &context, source, mir);
+            if let Some(start) = start {
+                $tcx.sess.mir_pass_times.borrow_mut().record(
+                    suite_index, index, pass.name, start.elapsed(), || body_path($tcx, source));
+            }
+            dump_mir::after_mir_pass(
+                $tcx, &format_args!("{:03}-{:03}", suite_index, index), &name, source, mir,
+                before);
+            if $tcx.sess.opts.debugging_opts.verify_mir {
+                verify_mir($tcx, source, mir, &name);
+            }
+            if let Some(ref mut counts) = counts {
+                let after = count_mir(mir);
+                $tcx.sess.mir_stats.borrow_mut().record_pass(
+                    suite_index, index, &pass.pass.name(), counts, &after);
+                *counts = after;
+            }
+            changed
         };
-        $(run_pass(&$pass);)*
+        let mut start = 0;
+        while start < passes.len() {
+            let end = group_end(&passes, start);
+            let mut round = 1;
+            loop {
+                let mut changed = false;
+                for index in start..end {
+                    changed |= run_pass(index, round);
+                }
+                if !changed || passes[start].group.is_none() {
+                    break;
+                }
+                if round == MAX_GROUP_ROUNDS {
+                    $tcx.sess.warn(&format!(
+                        "MIR pass group `{}` still changed `{}` after {} rounds",
+                        passes[start].group.unwrap(), $tcx.item_path_str(source.def_id),
+                        MAX_GROUP_ROUNDS));
+                    break;
+                }
+                round += 1;
+            }
+            start = end;
+        }
     };
 
     run_passes(&mut $mir, None);
@@ -189,15 +443,7 @@ fn mir_const<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, def_id: DefId) -> &'tcx Stea
     let _ = tcx.unsafety_check_result(def_id);
 
     let mut mir = tcx.mir_built(def_id).steal();
-    run_passes![tcx, mir, def_id, 0;
-        // Remove all `EndRegion` statements that are not involved in borrows.
-        clean_end_regions::CleanEndRegions,
-
-        // What we need to do constant evaluation.
-        simplify::SimplifyCfg::new("initial"),
-        type_check::TypeckMir,
-        rustc_peek::SanityCheck,
-    ];
+    run_passes![tcx, mir, def_id, MirSuite::Const];
     tcx.alloc_steal_mir(mir)
 }
 
@@ -210,11 +456,7 @@ fn mir_validated<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, def_id: DefId) -> &'tcx
     }
 
     let mut mir = tcx.mir_const(def_id).steal();
-    run_passes![tcx, mir, def_id, 1;
-        // What we need to run borrowck etc.
-        qualify_consts::QualifyAndPromoteConstants,
-        simplify::SimplifyCfg::new("qualify-consts"),
-    ];
+    run_passes![tcx, mir, def_id, MirSuite::Validated];
     tcx.alloc_steal_mir(mir)
 }
 
@@ -225,41 +467,19 @@ fn optimized_mir<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, def_id: DefId) -> &'tcx
     let _ = tcx.borrowck(def_id);
 
     let mut mir = tcx.mir_validated(def_id).steal();
-    run_passes![tcx, mir, def_id, 2;
-        no_landing_pads::NoLandingPads,
-        simplify_branches::SimplifyBranches::new("initial"),
-
-        // These next passes must be executed together
-        add_call_guards::CriticalCallEdges,
-        elaborate_drops::ElaborateDrops,
-        no_landing_pads::NoLandingPads,
-        // AddValidation needs to run after ElaborateDrops and before EraseRegions, and it needs
-        // an AllCallEdges pass right before it.
-        add_call_guards::AllCallEdges,
-        add_validation::AddValidation,
-        // AddMovesForPackedDrops needs to run after drop
-        // elaboration.
-        add_moves_for_packed_drops::AddMovesForPackedDrops,
-
-        simplify::SimplifyCfg::new("elaborate-drops"),
-
-        // No lifetime analysis based on borrowing can be done from here on out.
-
-        // From here on out, regions are gone.
-        erase_regions::EraseRegions,
-
-        lower_128bit::Lower128Bit,
-
-        // Optimizations begin.
-        inline::Inline,
-        instcombine::InstCombine,
-        deaggregator::Deaggregator,
-        copy_prop::CopyPropagation,
-        simplify::SimplifyLocals,
-
-        generator::StateTransform,
-        add_call_guards::CriticalCallEdges,
-        dump_mir::Marker("PreTrans"),
-    ];
+    run_passes![tcx, mir, def_id, MirSuite::Optimized];
+
+    if tcx.sess.opts.debugging_opts.mir_stats {
+        record_mir_stats(tcx, def_id, &mir);
+    }
+
+    // Trans relies on the MIR it gets being well-formed.
+    if cfg!(debug_assertions) && !tcx.sess.opts.debugging_opts.verify_mir {
+        verify_mir(tcx, MirSource::item(def_id), &mir, "PreTrans");
+        for (index, promoted_mir) in mir.promoted.iter_enumerated() {
+            let source = MirSource { def_id, promoted: Some(index) };
+            verify_mir(tcx, source, promoted_mir, "PreTrans");
+        }
+    }
     tcx.alloc_mir(mir)
 }
@@ -0,0 +1,216 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! This pass turns the last use of a local of `Copy` type into a move.
+//!
+//! MIR building picks `Operand::Copy` or `Operand::Move` from the type of
+//! the operand alone, so even the last use of a local of `Copy` type is a
+//! copy. Trans has to keep the original value intact across a copy, which
+//! for large types like `[u8; 4096]` means copying it to a fresh alloca
+//! before passing it by reference. When the local is not used afterwards,
+//! moving out of it is equivalent, and lets trans use the value in place.
+//!
+//! A copy is only turned into a move when:
+//!
+//! - the operand is a whole local, other than the return pointer;
+//! - the local is dead after the statement or terminator using it, as
+//!   computed by `util::liveness`;
+//! - the local is never borrowed, as the liveness analysis doesn't track
+//!   uses through references;
+//! - the local is mentioned only once by that statement or terminator,
+//!   so that the moved-from value can't alias another operand or the
+//!   destination.
+//!
+//! MIR building passes call arguments through a temporary, as in
+//! `_5 = _1; sink(move _5)`, so a move into the temporary still copies the
+//! value once. When the temporary is only assigned by such a move and then
+//! passed to the call, the call is made to take `move _1` directly, which
+//! trans passes by reference without any copy.
+//!
+//! `Copy` types have no drop glue, so this doesn't change which values get
+//! dropped. It runs on optimization MIR only, after borrowck has run, and
+//! not when generating debuginfo, as a debugger would otherwise show values
+//! moved out of by a call that the source only copies.
+
+use rustc::mir::{BasicBlock, Local, LocalKind, Location, Lvalue, Mir, Operand, Rvalue};
+use rustc::mir::{StatementKind, TerminatorKind};
+use rustc::mir::visit::{LvalueContext, MutVisitor, Visitor};
+use rustc::session::Session;
+use rustc::session::config::NoDebugInfo;
+use rustc::ty::TyCtxt;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use transform::{MirOptContext, MirPass, MirSource};
+use util::def_use::DefUseAnalysis;
+use util::liveness::{self, LivenessMode};
+
+pub struct MoveLastUses;
+
+impl MirPass for MoveLastUses {
+    fn is_enabled(&self, sess: &Session) -> bool {
+        sess.opts.debuginfo == NoDebugInfo
+    }
+
+    fn run_pass_in_context<'a, 'tcx>(&self,
+                                     _tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                     _context: &MirOptContext<'tcx>,
+                                     _source: MirSource,
+                                     mir: &mut Mir<'tcx>) -> bool {
+        let last_uses = find_last_uses(mir);
+        if last_uses.is_empty() {
+            return false;
+        }
+        MakeMoves { last_uses: &last_uses }.visit_mir(mir);
+        forward_moved_arguments(mir, &last_uses);
+        true
+    }
+}
+
+/// Finds the locations at which a local is copied for the last time.
+fn find_last_uses<'tcx>(mir: &Mir<'tcx>) -> FxHashSet<(Location, Local)> {
+    let borrowed = liveness::borrowed_locals(mir);
+
+    let mode = LivenessMode { include_regular_use: true, include_drops: true };
+    let liveness = liveness::liveness_of_locals(mir, mode);
+
+    let mut last_uses = FxHashSet();
+    for block in mir.basic_blocks().indices() {
+        let data = &mir[block];
+        for live in liveness.live_in_block(mir, block) {
+            let location = live.location;
+            let mut uses = UsesAt { copied: vec![], mentions: FxHashMap() };
+            match data.statements.get(location.statement_index) {
+                Some(statement) => uses.visit_statement(block, statement, location),
+                None => uses.visit_terminator(block, data.terminator(), location),
+            }
+
+            for local in uses.copied {
+                if mir.local_kind(local) != LocalKind::ReturnPointer &&
+                   !live.after.contains(&local) &&
+                   !borrowed.contains(&local) &&
+                   uses.mentions[&local] == 1 {
+                    last_uses.insert((location, local));
+                }
+            }
+        }
+    }
+    last_uses
+}
+
+/// Collects the locals copied as a whole by a single statement or
+/// terminator, and how many times each local is mentioned by it.
+struct UsesAt {
+    copied: Vec<Local>,
+    mentions: FxHashMap<Local, usize>,
+}
+
+impl<'tcx> Visitor<'tcx> for UsesAt {
+    fn visit_operand(&mut self, operand: &Operand<'tcx>, location: Location) {
+        if let Operand::Copy(Lvalue::Local(local)) = *operand {
+            self.copied.push(local);
+        }
+        self.super_operand(operand, location);
+    }
+
+    fn visit_local(&mut self, &local: &Local, _: LvalueContext<'tcx>, _: Location) {
+        *self.mentions.entry(local).or_insert(0) += 1;
+    }
+}
+
+struct MakeMoves<'a> {
+    last_uses: &'a FxHashSet<(Location, Local)>,
+}
+
+impl<'a, 'tcx> MutVisitor<'tcx> for MakeMoves<'a> {
+    fn visit_operand(&mut self, operand: &mut Operand<'tcx>, location: Location) {
+        let local = match *operand {
+            Operand::Copy(Lvalue::Local(local)) => local,
+            _ => return self.super_operand(operand, location),
+        };
+        if self.last_uses.contains(&(location, local)) {
+            *operand = Operand::Move(Lvalue::Local(local));
+        }
+    }
+}
+
+/// Passes the locals moved into call argument temporaries by `MakeMoves`
+/// to the calls directly, and removes the moves into the temporaries.
+fn forward_moved_arguments<'tcx>(mir: &mut Mir<'tcx>,
+                                 last_uses: &FxHashSet<(Location, Local)>) {
+    let mut def_use = DefUseAnalysis::new(mir);
+    def_use.clear_and_analyze(mir);
+
+    let mut forwards = vec![];
+    for (block, data) in mir.basic_blocks().iter_enumerated() {
+        let args = match data.terminator().kind {
+            TerminatorKind::Call { ref args, .. } => args,
+            _ => continue,
+        };
+        for (index, arg) in args.iter().enumerate() {
+            let temp = match *arg {
+                Operand::Move(Lvalue::Local(temp))
+                    if mir.local_kind(temp) == LocalKind::Temp => temp,
+                _ => continue,
+            };
+            // The temporary must only be assigned once and passed here.
+            let mentions = def_use.local_info(temp).defs_and_uses.iter()
+                .filter(|temp_use| !temp_use.context.is_storage_marker())
+                .count();
+            if mentions != 2 {
+                continue;
+            }
+            if let Some((statement_index, local)) = moved_into(mir, block, temp) {
+                let location = Location { block, statement_index };
+                if last_uses.contains(&(location, local)) &&
+                   mir.local_decls[local].ty == mir.local_decls[temp].ty {
+                    forwards.push((location, index, local));
+                }
+            }
+        }
+    }
+
+    for (location, index, local) in forwards {
+        let data = &mut mir.basic_blocks_mut()[location.block];
+        data.statements[location.statement_index].make_nop();
+        if let TerminatorKind::Call { ref mut args, .. } = data.terminator_mut().kind {
+            args[index] = Operand::Move(Lvalue::Local(local));
+        }
+    }
+}
+
+/// Finds the statement of `block` moving a local into `temp`, when neither
+/// is mentioned by any later statement, nor the local by the terminator.
+fn moved_into<'tcx>(mir: &Mir<'tcx>, block: BasicBlock, temp: Local)
+                    -> Option<(usize, Local)> {
+    let data = &mir[block];
+    let mut terminator_uses = UsesAt { copied: vec![], mentions: FxHashMap() };
+    let location = Location { block, statement_index: data.statements.len() };
+    terminator_uses.visit_terminator(block, data.terminator(), location);
+
+    let mut later_uses = UsesAt { copied: vec![], mentions: FxHashMap() };
+    for (statement_index, statement) in data.statements.iter().enumerate().rev() {
+        if let StatementKind::Assign(Lvalue::Local(dest),
+                                     Rvalue::Use(Operand::Move(Lvalue::Local(local)))) =
+            statement.kind {
+            if dest == temp {
+                if later_uses.mentions.contains_key(&local) ||
+                   terminator_uses.mentions.contains_key(&local) {
+                    return None;
+                }
+                return Some((statement_index, local));
+            }
+        }
+        let location = Location { block, statement_index };
+        later_uses.visit_statement(block, statement, location);
+        if later_uses.mentions.contains_key(&temp) {
+            return None;
+        }
+    }
+    None
+}
@@ -105,28 +105,14 @@ fn dump_mir_results<'a, 'gcx, 'tcx>(
 
     let regular_liveness_per_location: FxHashMap<_, _> = mir.basic_blocks()
         .indices()
-        .flat_map(|bb| {
-            let mut results = vec![];
-            liveness
-                .regular
-                .simulate_block(&mir, bb, |location, local_set| {
-                    results.push((location, local_set.clone()));
-                });
-            results
-        })
+        .flat_map(|bb| liveness.regular.live_in_block(&mir, bb))
+        .map(|live| (live.location, live.before))
         .collect();
 
     let drop_liveness_per_location: FxHashMap<_, _> = mir.basic_blocks()
         .indices()
-        .flat_map(|bb| {
-            let mut results = vec![];
-            liveness
-                .drop
-                .simulate_block(&mir, bb, |location, local_set| {
-                    results.push((location, local_set.clone()));
-                });
-            results
-        })
+        .flat_map(|bb| liveness.drop.live_in_block(&mir, bb))
+        .map(|live| (live.location, live.before))
         .collect();
 
     mir_util::dump_mir(infcx.tcx, None, "nll", &0, source, mir, |pass_where, out| {
@@ -139,7 +125,8 @@ fn dump_mir_results<'a, 'gcx, 'tcx>(
             // Before each basic block, dump out the values
             // that are live on entry to the basic block.
             PassWhere::BeforeBlock(bb) => {
-                let s = live_variable_set(&liveness.regular.ins[bb], &liveness.drop.ins[bb]);
+                let s = live_variable_set(liveness.regular.live_on_entry(bb),
+                                          liveness.drop.live_on_entry(bb));
                 writeln!(out, "    | Live variables on entry to {:?}: {}", bb, s)?;
             }
 
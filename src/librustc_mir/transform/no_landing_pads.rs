@@ -11,19 +11,26 @@
 //! This pass removes the unwind branch of all the terminators when the no-landing-pads option is
 //! specified.
 
+use rustc::session::Session;
 use rustc::ty::TyCtxt;
 use rustc::mir::*;
 use rustc::mir::visit::MutVisitor;
-use transform::{MirPass, MirSource};
+use transform::{MirOptContext, MirPass, MirSource};
 
 pub struct NoLandingPads;
 
 impl MirPass for NoLandingPads {
-    fn run_pass<'a, 'tcx>(&self,
-                          tcx: TyCtxt<'a, 'tcx, 'tcx>,
-                          _: MirSource,
-                          mir: &mut Mir<'tcx>) {
-        no_landing_pads(tcx, mir)
+    fn is_enabled(&self, sess: &Session) -> bool {
+        sess.no_landing_pads()
+    }
+
+    fn run_pass_in_context<'a, 'tcx>(&self,
+                                     _tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                     _context: &MirOptContext<'tcx>,
+                                     _: MirSource,
+                                     mir: &mut Mir<'tcx>) -> bool {
+        NoLandingPads.visit_mir(mir);
+        true
     }
 }
 
@@ -13,10 +13,20 @@
 //! The rvalues considered constant are trees of temps,
 //! each with exactly one initialization, and holding
 //! a constant value with no interior mutability.
+//! Borrows of their fields and of their elements at
+//! constant in-bounds indices are promoted as well.
 //! They are placed into a new MIR constant body in
 //! `promoted` and the borrow rvalue is replaced with
 //! a `Literal::Promoted` using the index into `promoted`
 //! of that constant MIR.
+//! Where the borrow appears doesn't matter, e.g. one
+//! passed directly as a call argument is a candidate
+//! like any other, as its rvalue is still in a temp.
+//! Calls to `const fn`s with such arguments are moved
+//! into the promoted MIR with them, and so evaluated
+//! at compile time, where a panic is an error.
+//! Identical promoted values, even from different
+//! functions, may end up at the same address.
 //!
 //! This pass assumes that every use is dominated by an
 //! initialization and can otherwise silence errors, if
@@ -68,7 +78,7 @@ impl TempState {
 /// of a larger candidate.
 #[derive(Debug)]
 pub enum Candidate {
-    /// Borrow of a constant temporary.
+    /// Borrow of a constant temporary, or of a projection of one.
     Ref(Location),
 
     /// Array of indices found in the third argument of
@@ -12,7 +12,8 @@
 //! static initializers and functions and also drives promotion.
 //!
 //! The Qualif flags below can be used to also provide better
-//! diagnostics as to why a constant rvalue wasn't promoted.
+//! diagnostics as to why a constant rvalue wasn't promoted: each
+//! flag is blamed on the first subexpression that added it.
 
 use rustc_data_structures::bitvec::BitVector;
 use rustc_data_structures::indexed_set::IdxSetBuf;
@@ -30,14 +31,14 @@ use rustc::mir::visit::{LvalueContext, Visitor};
 use rustc::middle::lang_items;
 use syntax::abi::Abi;
 use syntax::attr;
-use syntax::feature_gate::UnstableFeatures;
+use syntax::feature_gate::{emit_feature_err, GateIssue, UnstableFeatures};
 use syntax_pos::{Span, DUMMY_SP};
 
 use std::fmt;
 use std::rc::Rc;
 use std::usize;
 
-use transform::{MirPass, MirSource};
+use transform::{MirOptContext, MirPass, MirSource};
 use super::promote_consts::{self, Candidate, TempState};
 
 bitflags! {
@@ -88,6 +89,86 @@ impl<'a, 'tcx> Qualif {
             *self = *self - Qualif::NEEDS_DROP;
         }
     }
+
+    /// What a subexpression with the first of the given flags is.
+    fn reason(self) -> &'static str {
+        QUALIF_REASONS[self.bits().trailing_zeros() as usize]
+    }
+}
+
+/// What a subexpression with each of the flags is, in the order of their bits.
+const QUALIF_REASONS: [&str; 7] = [
+    "has interior mutability",
+    "needs to be dropped",
+    "is a function argument",
+    "reads a static",
+    "borrows a static",
+    "isn't constant",
+    "is a temporary that can't be promoted",
+];
+
+/// The span of a subexpression, and what it is that gave it a flag.
+type Blame = (Span, &'static str);
+
+/// The blame of each of the flags of a qualification.
+#[derive(Copy, Clone, Default)]
+struct QualifBlames([Option<Blame>; 7]);
+
+impl QualifBlames {
+    /// Blame the given flags on `span`, for `reason`, or for
+    /// what the flag itself says.
+    fn blame(&mut self, qualif: Qualif, span: Span, reason: Option<&'static str>) {
+        for (bit, blame) in self.0.iter_mut().enumerate() {
+            if qualif.bits() & (1 << bit) != 0 {
+                *blame = Some((span, reason.unwrap_or(QUALIF_REASONS[bit])));
+            }
+        }
+    }
+
+    /// Take the blames of the given flags from `other`.
+    fn take(&mut self, qualif: Qualif, other: &QualifBlames) {
+        for (bit, blame) in self.0.iter_mut().enumerate() {
+            if qualif.bits() & (1 << bit) != 0 {
+                *blame = other.0[bit];
+            }
+        }
+    }
+
+    /// The blame of the first of the given flags.
+    fn first(&self, qualif: Qualif) -> Option<Blame> {
+        self.0.iter().enumerate()
+            .filter(|&(bit, _)| qualif.bits() & (1 << bit) != 0)
+            .filter_map(|(_, &blame)| blame)
+            .next()
+    }
+}
+
+/// The qualifications of the temps and of the return value
+/// along the paths to a block.
+#[derive(Clone)]
+struct PathQualifs {
+    temps: IndexVec<Local, Option<Qualif>>,
+    ret: Option<Qualif>,
+}
+
+impl PathQualifs {
+    /// Join the qualifications along other paths merging here.
+    fn join(&mut self, other: &PathQualifs) {
+        for (temp, &qualif) in self.temps.iter_mut().zip(&other.temps) {
+            *temp = join_qualifs(*temp, qualif);
+        }
+        self.ret = join_qualifs(self.ret, other.ret);
+    }
+}
+
+/// Join the qualifications of a local along two paths,
+/// on either of which it may be unassigned.
+fn join_qualifs(a: Option<Qualif>, b: Option<Qualif>) -> Option<Qualif> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a | b),
+        (a, None) => a,
+        (None, b) => b,
+    }
 }
 
 /// What kind of item we are in.
@@ -120,12 +201,20 @@ struct Qualifier<'a, 'gcx: 'a+'tcx, 'tcx: 'a> {
     tcx: TyCtxt<'a, 'gcx, 'tcx>,
     param_env: ty::ParamEnv<'tcx>,
     temp_qualif: IndexVec<Local, Option<Qualif>>,
+    temp_blames: IndexVec<Local, QualifBlames>,
     return_qualif: Option<Qualif>,
     qualif: Qualif,
+    blames: QualifBlames,
     const_fn_arg_vars: BitVector,
     local_needs_drop: IndexVec<Local, Option<Span>>,
     temp_promotion_state: IndexVec<Local, TempState>,
-    promotion_candidates: Vec<Candidate>
+    promotion_candidates: Vec<Candidate>,
+
+    /// Borrows of temps that couldn't be promoted, and what prevented it.
+    unpromoted_borrows: Vec<(Location, Option<Blame>)>,
+
+    /// Shuffle indices that aren't constant, and what prevented it.
+    shuffle_errors: Vec<(Span, Option<Blame>)>
 }
 
 impl<'a, 'tcx> Qualifier<'a, 'tcx, 'tcx> {
@@ -146,12 +235,16 @@ impl<'a, 'tcx> Qualifier<'a, 'tcx, 'tcx> {
             tcx,
             param_env: tcx.param_env(def_id),
             temp_qualif: IndexVec::from_elem(None, &mir.local_decls),
+            temp_blames: IndexVec::from_elem(QualifBlames::default(), &mir.local_decls),
             return_qualif: None,
             qualif: Qualif::empty(),
+            blames: QualifBlames::default(),
             const_fn_arg_vars: BitVector::new(mir.local_decls.len()),
             local_needs_drop: IndexVec::from_elem(None, &mir.local_decls),
             temp_promotion_state: temps,
-            promotion_candidates: vec![]
+            promotion_candidates: vec![],
+            unpromoted_borrows: vec![],
+            shuffle_errors: vec![]
         }
     }
 
@@ -176,11 +269,49 @@ impl<'a, 'tcx> Qualifier<'a, 'tcx, 'tcx> {
         }
     }
 
+    /// Whether `#![feature(const_loop)]` allows loops, and the
+    /// statements and variables they need, which it only does
+    /// in `const fn`s.
+    fn allows_loops(&self) -> bool {
+        self.mode == Mode::ConstFn && self.tcx.sess.features.borrow().const_loop
+    }
+
     /// Add the given qualification to self.qualif.
     fn add(&mut self, qualif: Qualif) {
+        let span = self.span;
+        self.blames.blame(qualif - self.qualif, span, None);
         self.qualif = self.qualif | qualif;
     }
 
+    /// Add the given qualification to self.qualif, blaming
+    /// the current span for `reason`.
+    fn add_because(&mut self, qualif: Qualif, reason: &'static str) {
+        let span = self.span;
+        self.blames.blame(qualif - self.qualif, span, Some(reason));
+        self.qualif = self.qualif | qualif;
+    }
+
+    /// Add the given qualification to self.qualif,
+    /// keeping the blames it already had.
+    fn add_with(&mut self, qualif: Qualif, blames: &QualifBlames) {
+        self.blames.take(qualif - self.qualif, blames);
+        self.qualif = self.qualif | qualif;
+    }
+
+    /// Replace the `from` flags of self.qualif with `to`,
+    /// blaming them on what `from` was blamed on.
+    fn replace(&mut self, from: Qualif, to: Qualif) {
+        let blame = self.blames.first(self.qualif & from);
+        self.qualif = self.qualif - from;
+        match blame {
+            Some((span, reason)) => {
+                self.blames.blame(to - self.qualif, span, Some(reason));
+                self.qualif = self.qualif | to;
+            }
+            None => self.add(to)
+        }
+    }
+
     /// Add the given type's qualification to self.qualif.
     fn add_type(&mut self, ty: Ty<'tcx>) {
         self.add(Qualif::MUTABLE_INTERIOR | Qualif::NEEDS_DROP);
@@ -191,10 +322,13 @@ impl<'a, 'tcx> Qualifier<'a, 'tcx, 'tcx> {
     /// out empty, and its value after the closure returns will
     /// be combined with the value before the call to nest.
     fn nest<F: FnOnce(&mut Self)>(&mut self, f: F) {
-        let original = self.qualif;
+        let (original, original_blames) = (self.qualif, self.blames);
         self.qualif = Qualif::empty();
         f(self);
-        self.add(original);
+        let (qualif, blames) = (self.qualif, self.blames);
+        self.qualif = original;
+        self.blames = original_blames;
+        self.add_with(qualif, &blames);
     }
 
     /// Check if an Lvalue with the current qualifications could
@@ -214,8 +348,7 @@ impl<'a, 'tcx> Qualifier<'a, 'tcx, 'tcx> {
                 .emit();
 
             // Replace STATIC with NOT_CONST to avoid further errors.
-            self.qualif = self.qualif - Qualif::STATIC;
-            self.add(Qualif::NOT_CONST);
+            self.replace(Qualif::STATIC, Qualif::NOT_CONST);
 
             false
         } else {
@@ -225,13 +358,17 @@ impl<'a, 'tcx> Qualifier<'a, 'tcx, 'tcx> {
 
     /// Assign the current qualification to the given destination.
     fn assign(&mut self, dest: &Lvalue<'tcx>, location: Location) {
-        let qualif = self.qualif;
+        let (qualif, blames) = (self.qualif, self.blames);
         let span = self.span;
+        let allows_loops = self.allows_loops();
         let store = |slot: &mut Option<Qualif>| {
-            if slot.is_some() {
-                span_bug!(span, "multiple assignments to {:?}", dest);
+            match *slot {
+                // A temp assigned in a loop, e.g. the unit
+                // temp of its body, may be assigned again.
+                Some(previous) if allows_loops => *slot = Some(previous | qualif),
+                Some(_) => span_bug!(span, "multiple assignments to {:?}", dest),
+                None => *slot = Some(qualif)
             }
-            *slot = Some(qualif);
         };
 
         // Only handle promotable temps in non-const functions.
@@ -241,6 +378,7 @@ impl<'a, 'tcx> Qualifier<'a, 'tcx, 'tcx> {
                 && self.temp_promotion_state[index].is_promotable() {
                     debug!("store to promotable temp {:?}", index);
                     store(&mut self.temp_qualif[index]);
+                    self.temp_blames[index] = blames;
                 }
             }
             return;
@@ -258,12 +396,19 @@ impl<'a, 'tcx> Qualifier<'a, 'tcx, 'tcx> {
         match *dest {
             Lvalue::Local(index) if self.mir.local_kind(index) == LocalKind::Temp => {
                 debug!("store to temp {:?}", index);
-                store(&mut self.temp_qualif[index])
+                store(&mut self.temp_qualif[index]);
+                self.temp_blames[index] = blames;
             }
             Lvalue::Local(index) if self.mir.local_kind(index) == LocalKind::ReturnPointer => {
                 debug!("store to return pointer {:?}", index);
                 store(&mut self.return_qualif)
             }
+            Lvalue::Local(index) if self.mir.local_kind(index) == LocalKind::Var
+                                 && allows_loops => {
+                // Reading a variable is never constant,
+                // so its qualification needn't be kept.
+                debug!("store to variable {:?}", index);
+            }
 
             Lvalue::Projection(box Projection {
                 base: Lvalue::Local(index),
@@ -286,90 +431,283 @@ impl<'a, 'tcx> Qualifier<'a, 'tcx, 'tcx> {
         }
     }
 
-    /// Qualify a whole const, static initializer or const fn.
-    fn qualify_const(&mut self) -> (Qualif, Rc<IdxSetBuf<Local>>) {
-        debug!("qualifying {} {:?}", self.mode, self.def_id);
+    /// Check if a borrow of the given Lvalue could be promoted: it must be
+    /// a temp, or fields and constant in-bounds indices of one, with no
+    /// interior mutability or destructor anywhere along the way.
+    /// Otherwise, returns what prevents it, if there is one subexpression
+    /// to blame.
+    fn check_promotable_borrow(&self, lvalue: &Lvalue<'tcx>) -> Result<(), Option<Blame>> {
+        let temp = match base_local(lvalue) {
+            Some(local) if self.mir.local_kind(local) == LocalKind::Temp => local,
+            _ => return Err(None)
+        };
+        let proj = match *lvalue {
+            Lvalue::Projection(ref proj) => proj,
+            _ => return Ok(())
+        };
+
+        let base_ty = proj.base.ty(self.mir, self.tcx).to_ty(self.tcx);
+        let temp_span = self.mir.local_decls[temp].source_info.span;
+        if !base_ty.is_freeze(self.tcx, self.param_env, DUMMY_SP) {
+            return Err(Some((temp_span, Qualif::MUTABLE_INTERIOR.reason())));
+        }
+        if base_ty.needs_drop(self.tcx, self.param_env) {
+            return Err(Some((temp_span, Qualif::NEEDS_DROP.reason())));
+        }
+
+        match proj.elem {
+            ProjectionElem::Field(..) => {}
+            ProjectionElem::Index(index) => {
+                let in_bounds = match (self.constant_index(index), &base_ty.sty) {
+                    (Some(index), &ty::TyArray(_, len)) => {
+                        len.val.to_const_int().and_then(|len| len.to_u64())
+                            .map_or(false, |len| index < len)
+                    }
+                    _ => false
+                };
+                if !in_bounds {
+                    let index_span = self.mir.local_decls[index].source_info.span;
+                    return Err(Some((index_span,
+                                     "isn't a constant index in bounds of the array")));
+                }
+            }
+            _ => return Err(None)
+        }
+        self.check_promotable_borrow(&proj.base)
+    }
+
+    /// The value of the given index, if it is a temp initialized
+    /// with an integer literal.
+    fn constant_index(&self, index: Local) -> Option<u64> {
+        let location = match self.temp_promotion_state[index] {
+            TempState::Defined { location, .. } => location,
+            _ => return None
+        };
+        let statements = &self.mir[location.block].statements;
+        if location.statement_index >= statements.len() {
+            return None;
+        }
+        match statements[location.statement_index].kind {
+            StatementKind::Assign(_, Rvalue::Use(Operand::Constant(box Constant {
+                literal: Literal::Value { value }, ..
+            }))) => {
+                value.val.to_const_int().and_then(|index| index.to_u64())
+            }
+            _ => None
+        }
+    }
+
+    /// Check the temps and variables of a const, static initializer
+    /// or const fn when it returns, from the block `bb`.
+    fn check_return(&mut self, bb: BasicBlock) {
+        let mir = self.mir;
+
+        // Check for unused values. This usually means
+        // there are extra statements in the AST, which only
+        // `#![feature(const_loop)]` allows.
+        if !self.allows_loops() {
+            for temp in mir.temps_iter() {
+                if self.temp_qualif[temp].is_none() {
+                    continue;
+                }
+
+                let state = self.temp_promotion_state[temp];
+                if let TempState::Defined { location, uses: 0 } = state {
+                    let data = &mir[location.block];
+                    let stmt_idx = location.statement_index;
+
+                    // Get the span for the initialization.
+                    let source_info = if stmt_idx < data.statements.len() {
+                        data.statements[stmt_idx].source_info
+                    } else {
+                        data.terminator().source_info
+                    };
+                    self.span = source_info.span;
+
+                    // Treat this as a statement in the AST.
+                    self.statement_like();
+                }
+            }
+        }
+
+        // Make sure there are no extra unassigned variables.
+        self.qualif = Qualif::NOT_CONST;
+        for index in mir.vars_iter() {
+            if !self.const_fn_arg_vars.contains(index.index()) {
+                debug!("unassigned variable {:?}", index);
+                self.assign(&Lvalue::Local(index), Location {
+                    block: bb,
+                    statement_index: usize::MAX,
+                });
+            }
+        }
+    }
 
+    /// Qualify the blocks of a const, static initializer or const fn
+    /// with branches, under `#![feature(const_if_match)]`, in reverse
+    /// postorder. The qualifications of the temps and of the return
+    /// value are tracked along each path, and joined where paths merge.
+    /// Loops are rejected, unless `#![feature(const_loop)]` allows
+    /// them, and unwinding edges aren't followed. The
+    /// other paths are still qualified after a rejection, so that all
+    /// of their errors are reported.
+    fn qualify_branches(&mut self) {
         let mir = self.mir;
 
-        let mut seen_blocks = BitVector::new(mir.basic_blocks().len());
-        let mut bb = START_BLOCK;
-        loop {
-            seen_blocks.insert(bb.index());
+        let order: Vec<_> = traversal::reverse_postorder(mir).map(|(bb, _)| bb).collect();
+        let mut position = IndexVec::from_elem(usize::MAX, mir.basic_blocks());
+        for (i, &bb) in order.iter().enumerate() {
+            position[bb] = i;
+        }
+
+        let mut entry_qualifs = IndexVec::from_elem(None, mir.basic_blocks());
+        entry_qualifs[START_BLOCK] = Some(PathQualifs {
+            temps: self.temp_qualif.clone(),
+            ret: self.return_qualif,
+        });
+        let mut return_qualif = None;
+        let mut rejected = false;
+
+        for &bb in &order {
+            // Blocks only reached by unwinding aren't qualified.
+            let entry = match entry_qualifs[bb].take() {
+                Some(entry) => entry,
+                None => continue
+            };
+            self.temp_qualif = entry.temps;
+            self.return_qualif = entry.ret;
 
             self.visit_basic_block_data(bb, &mir[bb]);
 
-            let target = match mir[bb].terminator().kind {
+            let targets = match mir[bb].terminator().kind {
                 TerminatorKind::Goto { target } |
                 TerminatorKind::Drop { target, .. } |
                 TerminatorKind::Assert { target, .. } |
-                TerminatorKind::Call { destination: Some((_, target)), .. } => {
-                    Some(target)
+                TerminatorKind::Call { destination: Some((_, target)), .. } => vec![target],
+
+                TerminatorKind::SwitchInt { ref targets, .. } => targets.clone(),
+
+                TerminatorKind::FalseEdges { real_target, ref imaginary_targets } => {
+                    let mut targets = imaginary_targets.clone();
+                    targets.push(real_target);
+                    targets
                 }
 
                 // Non-terminating calls cannot produce any value.
-                TerminatorKind::Call { destination: None, .. } => {
-                    break;
+                TerminatorKind::Call { destination: None, .. } |
+                TerminatorKind::Unreachable => vec![],
+
+                TerminatorKind::Return => {
+                    self.check_return(bb);
+                    return_qualif = join_qualifs(return_qualif, self.return_qualif);
+                    vec![]
                 }
 
-                TerminatorKind::SwitchInt {..} |
                 TerminatorKind::DropAndReplace { .. } |
                 TerminatorKind::Resume |
                 TerminatorKind::GeneratorDrop |
-                TerminatorKind::Yield { .. } |
-                TerminatorKind::Unreachable |
-                TerminatorKind::FalseEdges { .. } => None,
+                TerminatorKind::Yield { .. } => {
+                    self.not_const();
+                    rejected = true;
+                    vec![]
+                }
+            };
 
-                TerminatorKind::Return => {
-                    // Check for unused values. This usually means
-                    // there are extra statements in the AST.
-                    for temp in mir.temps_iter() {
-                        if self.temp_qualif[temp].is_none() {
-                            continue;
-                        }
+            let exit = PathQualifs {
+                temps: self.temp_qualif.clone(),
+                ret: self.return_qualif,
+            };
+            for target in targets {
+                // No loops allowed, except in const fns under
+                // `#![feature(const_loop)]`. Their body is still only
+                // qualified once: its temps don't outlive an iteration,
+                // and the variables they change are never constant.
+                if position[target] <= position[bb] {
+                    if self.allows_loops() {
+                        continue;
+                    }
+                    if self.mode == Mode::ConstFn {
+                        self.add(Qualif::NOT_CONST);
+                        emit_feature_err(&self.tcx.sess.parse_sess, "const_loop",
+                                         self.span, GateIssue::Language,
+                                         "loops in constant functions are unstable");
+                    } else {
+                        self.not_const();
+                    }
+                    rejected = true;
+                    continue;
+                }
 
-                        let state = self.temp_promotion_state[temp];
-                        if let TempState::Defined { location, uses: 0 } = state {
-                            let data = &mir[location.block];
-                            let stmt_idx = location.statement_index;
+                if let Some(ref mut entry) = entry_qualifs[target] {
+                    entry.join(&exit);
+                    continue;
+                }
+                entry_qualifs[target] = Some(exit.clone());
+            }
+        }
 
-                            // Get the span for the initialization.
-                            let source_info = if stmt_idx < data.statements.len() {
-                                data.statements[stmt_idx].source_info
-                            } else {
-                                data.terminator().source_info
-                            };
-                            self.span = source_info.span;
+        self.return_qualif = if rejected { None } else { return_qualif };
+    }
 
-                            // Treat this as a statement in the AST.
-                            self.statement_like();
-                        }
+    /// Qualify a whole const, static initializer or const fn.
+    fn qualify_const(&mut self) -> (Qualif, Rc<IdxSetBuf<Local>>) {
+        debug!("qualifying {} {:?}", self.mode, self.def_id);
+
+        let mir = self.mir;
+
+        if self.tcx.sess.features.borrow().const_if_match {
+            self.qualify_branches();
+        } else {
+            let mut seen_blocks = BitVector::new(mir.basic_blocks().len());
+            let mut bb = START_BLOCK;
+            loop {
+                seen_blocks.insert(bb.index());
+
+                self.visit_basic_block_data(bb, &mir[bb]);
+
+                let target = match mir[bb].terminator().kind {
+                    TerminatorKind::Goto { target } |
+                    TerminatorKind::Drop { target, .. } |
+                    TerminatorKind::Assert { target, .. } |
+                    TerminatorKind::Call { destination: Some((_, target)), .. } => {
+                        Some(target)
                     }
 
-                    // Make sure there are no extra unassigned variables.
-                    self.qualif = Qualif::NOT_CONST;
-                    for index in mir.vars_iter() {
-                        if !self.const_fn_arg_vars.contains(index.index()) {
-                            debug!("unassigned variable {:?}", index);
-                            self.assign(&Lvalue::Local(index), Location {
-                                block: bb,
-                                statement_index: usize::MAX,
-                            });
-                        }
+                    // Non-terminating calls cannot produce any value.
+                    TerminatorKind::Call { destination: None, .. } => {
+                        break;
                     }
 
-                    break;
-                }
-            };
+                    TerminatorKind::SwitchInt {..} |
+                    TerminatorKind::FalseEdges { .. } => {
+                        self.add(Qualif::NOT_CONST);
+                        emit_feature_err(&self.tcx.sess.parse_sess, "const_if_match",
+                                         self.span, GateIssue::Language,
+                                         &format!("branches in {}s are unstable", self.mode));
+                        break;
+                    }
 
-            match target {
-                // No loops allowed.
-                Some(target) if !seen_blocks.contains(target.index()) => {
-                    bb = target;
-                }
-                _ => {
-                    self.not_const();
-                    break;
+                    TerminatorKind::DropAndReplace { .. } |
+                    TerminatorKind::Resume |
+                    TerminatorKind::GeneratorDrop |
+                    TerminatorKind::Yield { .. } |
+                    TerminatorKind::Unreachable => None,
+
+                    TerminatorKind::Return => {
+                        self.check_return(bb);
+                        break;
+                    }
+                };
+
+                match target {
+                    // No loops allowed.
+                    Some(target) if !seen_blocks.contains(target.index()) => {
+                        bb = target;
+                    }
+                    _ => {
+                        self.not_const();
+                        break;
+                    }
                 }
             }
         }
@@ -392,8 +730,12 @@ impl<'a, 'tcx> Qualifier<'a, 'tcx, 'tcx> {
             match *candidate {
                 Candidate::Ref(Location { block: bb, statement_index: stmt_idx }) => {
                     match self.mir[bb].statements[stmt_idx].kind {
-                        StatementKind::Assign(_, Rvalue::Ref(_, _, Lvalue::Local(index))) => {
-                            promoted_temps.add(&index);
+                        StatementKind::Assign(_, Rvalue::Ref(_, _, ref lvalue)) => {
+                            // The borrowed temp, or the one whose fields
+                            // and elements are borrowed.
+                            if let Some(index) = base_local(lvalue) {
+                                promoted_temps.add(&index);
+                            }
                         }
                         _ => {}
                     }
@@ -406,6 +748,15 @@ impl<'a, 'tcx> Qualifier<'a, 'tcx, 'tcx> {
     }
 }
 
+/// The local an Lvalue is, or is a projection of.
+fn base_local(lvalue: &Lvalue) -> Option<Local> {
+    match *lvalue {
+        Lvalue::Local(local) => Some(local),
+        Lvalue::Static(_) => None,
+        Lvalue::Projection(ref proj) => base_local(&proj.base),
+    }
+}
+
 /// Accumulates an Rvalue or Call's effects in self.qualif.
 /// For functions (constant or not), it also records
 /// candidates for promotion in promotion_candidates.
@@ -422,7 +773,7 @@ impl<'a, 'tcx> Visitor<'tcx> for Qualifier<'a, 'tcx, 'tcx> {
                 self.add(Qualif::FN_ARGUMENT);
             }
             LocalKind::Var => {
-                self.add(Qualif::NOT_CONST);
+                self.add_because(Qualif::NOT_CONST, "is a local variable");
             }
             LocalKind::Temp => {
                 if !self.temp_promotion_state[local].is_promotable() {
@@ -430,7 +781,8 @@ impl<'a, 'tcx> Visitor<'tcx> for Qualifier<'a, 'tcx, 'tcx> {
                 }
 
                 if let Some(qualif) = self.temp_qualif[local] {
-                    self.add(qualif);
+                    let blames = self.temp_blames[local];
+                    self.add_with(qualif, &blames);
                 } else {
                     self.not_const();
                 }
@@ -475,8 +827,7 @@ impl<'a, 'tcx> Visitor<'tcx> for Qualifier<'a, 'tcx, 'tcx> {
                             }
 
                             if this.qualif.intersects(Qualif::STATIC_REF) {
-                                this.qualif = this.qualif - Qualif::STATIC_REF;
-                                this.add(Qualif::STATIC);
+                                this.replace(Qualif::STATIC_REF, Qualif::STATIC);
                             }
 
                             let base_ty = proj.base.ty(this.mir, this.tcx).to_ty(this.tcx);
@@ -568,8 +919,16 @@ impl<'a, 'tcx> Visitor<'tcx> for Qualifier<'a, 'tcx, 'tcx> {
             Rvalue::Cast(CastKind::ReifyFnPointer, ..) |
             Rvalue::Cast(CastKind::UnsafeFnPointer, ..) |
             Rvalue::Cast(CastKind::ClosureFnPointer, ..) |
-            Rvalue::Cast(CastKind::Unsize, ..) |
-            Rvalue::Discriminant(..) => {}
+            Rvalue::Cast(CastKind::Unsize, ..) => {}
+
+            Rvalue::Discriminant(..) => {
+                // Only `match` reads discriminants, which can only be
+                // used to branch under `#![feature(const_if_match)]`,
+                // and the constant evaluator can't read them yet.
+                if self.mode != Mode::Fn && self.tcx.sess.features.borrow().const_if_match {
+                    self.not_const();
+                }
+            }
 
             Rvalue::Len(_) => {
                 // Static lvalues in consts would have errored already,
@@ -581,12 +940,11 @@ impl<'a, 'tcx> Visitor<'tcx> for Qualifier<'a, 'tcx, 'tcx> {
                 // Static lvalues in consts would have errored already,
                 // only keep track of references to them here.
                 if self.qualif.intersects(Qualif::STATIC) {
-                    self.qualif = self.qualif - Qualif::STATIC;
-                    self.add(Qualif::STATIC_REF);
+                    self.replace(Qualif::STATIC, Qualif::STATIC_REF);
                 }
 
                 let ty = lvalue.ty(self.mir, self.tcx).to_ty(self.tcx);
-                if kind == BorrowKind::Mut {
+                if let BorrowKind::Mut { .. } = kind {
                     // In theory, any zero-sized value could be borrowed
                     // mutably without consequences. However, only &mut []
                     // is allowed right now, and only in functions.
@@ -619,25 +977,40 @@ impl<'a, 'tcx> Visitor<'tcx> for Qualifier<'a, 'tcx, 'tcx> {
                     // it means that our "silent insertion of statics" could change
                     // initializer values (very bad).
                     if self.qualif.intersects(Qualif::MUTABLE_INTERIOR) {
+                        let blame = self.blames.first(Qualif::MUTABLE_INTERIOR);
                         // Replace MUTABLE_INTERIOR with NOT_CONST to avoid
                         // duplicate errors (from reborrowing, for example).
-                        self.qualif = self.qualif - Qualif::MUTABLE_INTERIOR;
-                        self.add(Qualif::NOT_CONST);
+                        self.replace(Qualif::MUTABLE_INTERIOR, Qualif::NOT_CONST);
                         if self.mode != Mode::Fn {
-                            span_err!(self.tcx.sess, self.span, E0492,
-                                      "cannot borrow a constant which may contain \
-                                       interior mutability, create a static instead");
+                            let mut err = struct_span_err!(self.tcx.sess, self.span, E0492,
+                                "cannot borrow a constant which may contain \
+                                 interior mutability, create a static instead");
+                            err.span_label(self.span, "borrow of a value with interior mutability");
+                            if let Some((span, _)) = blame {
+                                if !self.span.contains(span) {
+                                    err.span_note(span, "this value has interior mutability");
+                                }
+                            }
+                            err.emit();
                         }
                     }
                 }
 
                 // We might have a candidate for promotion.
                 let candidate = Candidate::Ref(location);
-                if !self.qualif.intersects(Qualif::NEVER_PROMOTE) {
-                    // We can only promote direct borrows of temps.
-                    if let Lvalue::Local(local) = *lvalue {
-                        if self.mir.local_kind(local) == LocalKind::Temp {
-                            self.promotion_candidates.push(candidate);
+                let promotable = if self.qualif.intersects(Qualif::NEVER_PROMOTE) {
+                    Err(self.blames.first(self.qualif & Qualif::NEVER_PROMOTE))
+                } else {
+                    self.check_promotable_borrow(lvalue)
+                };
+                match promotable {
+                    Ok(()) => self.promotion_candidates.push(candidate),
+                    Err(blame) => {
+                        let borrows_temp = base_local(lvalue).map_or(false, |local| {
+                            self.mir.local_kind(local) == LocalKind::Temp
+                        });
+                        if borrows_temp {
+                            self.unpromoted_borrows.push((location, blame));
                         }
                     }
                 }
@@ -748,8 +1121,8 @@ impl<'a, 'tcx> Visitor<'tcx> for Qualifier<'a, 'tcx, 'tcx> {
                         if !this.qualif.intersects(Qualif::NEVER_PROMOTE) {
                             this.promotion_candidates.push(candidate);
                         } else {
-                            span_err!(this.tcx.sess, this.span, E0526,
-                                      "shuffle indices are not constant");
+                            let blame = this.blames.first(this.qualif & Qualif::NEVER_PROMOTE);
+                            this.shuffle_errors.push((this.span, blame));
                         }
                     }
                 });
@@ -790,7 +1163,8 @@ impl<'a, 'tcx> Visitor<'tcx> for Qualifier<'a, 'tcx, 'tcx> {
                     }
                 }
             } else {
-                self.qualif = Qualif::NOT_CONST;
+                self.qualif = Qualif::empty();
+                self.add_because(Qualif::NOT_CONST, "calls a function that isn't a `const fn`");
                 if self.mode != Mode::Fn {
                     // FIXME(#24111) Remove this check when const fn stabilizes
                     let (msg, note) = if let UnstableFeatures::Disallow =
@@ -808,9 +1182,20 @@ impl<'a, 'tcx> Visitor<'tcx> for Qualifier<'a, 'tcx, 'tcx> {
                                  self.mode),
                          None)
                     };
-                    let mut err = struct_span_err!(self.tcx.sess, self.span, E0015, "{}", msg);
+                    // Point at the callee itself, when it's named.
+                    let span = match *func {
+                        Operand::Constant(ref callee) if self.span.contains(callee.span) => {
+                            callee.span
+                        }
+                        _ => self.span
+                    };
+                    let mut err = struct_span_err!(self.tcx.sess, span, E0015, "{}", msg);
+                    if let ty::TyFnDef(def_id, _) = fn_ty.sty {
+                        err.span_label(span, format!("`{}` is not a `const fn`",
+                                                     self.tcx.item_path_str(def_id)));
+                    }
                     if let Some(note) = note {
-                        err.span_note(self.span, note);
+                        err.span_note(span, note);
                     }
                     err.emit();
                 }
@@ -819,7 +1204,8 @@ impl<'a, 'tcx> Visitor<'tcx> for Qualifier<'a, 'tcx, 'tcx> {
             if let Some((ref dest, _)) = *destination {
                 // Avoid propagating irrelevant callee/argument qualifications.
                 if self.qualif.intersects(Qualif::CONST_ERROR) {
-                    self.qualif = Qualif::NOT_CONST;
+                    self.qualif = self.qualif & Qualif::CONST_ERROR;
+                    self.replace(Qualif::CONST_ERROR, Qualif::NOT_CONST);
                 } else {
                     // Be conservative about the returned value of a const fn.
                     let tcx = self.tcx;
@@ -870,6 +1256,7 @@ impl<'a, 'tcx> Visitor<'tcx> for Qualifier<'a, 'tcx, 'tcx> {
         // Check the allowed const fn argument forms.
         if let (Mode::ConstFn, &Lvalue::Local(index)) = (self.mode, dest) {
             if self.mir.local_kind(index) == LocalKind::Var &&
+               !self.allows_loops() &&
                self.const_fn_arg_vars.insert(index.index()) {
 
                 // Direct use of an argument is permitted.
@@ -914,6 +1301,7 @@ impl<'a, 'tcx> Visitor<'tcx> for Qualifier<'a, 'tcx, 'tcx> {
                 StatementKind::InlineAsm {..} |
                 StatementKind::EndRegion(_) |
                 StatementKind::Validate(..) |
+                StatementKind::Assume(..) |
                 StatementKind::Nop => {}
             }
         });
@@ -953,21 +1341,44 @@ fn mir_const_qualif<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     (qualif.bits(), promoted_temps)
 }
 
+/// What prevented the promotion of the borrow of a temp at `location`
+/// in the function `def_id`, whose MIR is `mir`, for diagnostics about
+/// that temp not living long enough: the span of the subexpression to
+/// blame, and what it is.
+pub fn promotion_blame<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                 def_id: DefId,
+                                 mir: &'a Mir<'tcx>,
+                                 location: Location)
+                                 -> Option<(Span, &'static str)> {
+    if mir.return_ty().references_error() {
+        return None;
+    }
+
+    let mut qualifier = Qualifier::new(tcx, def_id, mir, Mode::Fn);
+    while let Some((bb, data)) = qualifier.rpo.next() {
+        qualifier.visit_basic_block_data(bb, data);
+    }
+    qualifier.unpromoted_borrows.iter()
+        .find(|&&(borrow, _)| borrow == location)
+        .and_then(|&(_, blame)| blame)
+}
+
 pub struct QualifyAndPromoteConstants;
 
 impl MirPass for QualifyAndPromoteConstants {
-    fn run_pass<'a, 'tcx>(&self,
-                          tcx: TyCtxt<'a, 'tcx, 'tcx>,
-                          src: MirSource,
-                          mir: &mut Mir<'tcx>) {
+    fn run_pass_in_context<'a, 'tcx>(&self,
+                                     tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                     _context: &MirOptContext<'tcx>,
+                                     src: MirSource,
+                                     mir: &mut Mir<'tcx>) -> bool {
         // There's not really any point in promoting errorful MIR.
         if mir.return_ty().references_error() {
             tcx.sess.delay_span_bug(mir.span, "QualifyAndPromoteConstants: Mir had errors");
-            return;
+            return false;
         }
 
         if src.promoted.is_some() {
-            return;
+            return false;
         }
 
         let def_id = src.def_id;
@@ -992,7 +1403,7 @@ impl MirPass for QualifyAndPromoteConstants {
         if mode == Mode::Fn || mode == Mode::ConstFn {
             // This is ugly because Qualifier holds onto mir,
             // which can't be mutated until its scope ends.
-            let (temps, candidates) = {
+            let (temps, candidates, shuffle_errors) = {
                 let mut qualifier = Qualifier::new(tcx, def_id, mir, mode);
                 if mode == Mode::ConstFn {
                     // Enforce a constant-like CFG for `const fn`.
@@ -1003,9 +1414,22 @@ impl MirPass for QualifyAndPromoteConstants {
                     }
                 }
 
-                (qualifier.temp_promotion_state, qualifier.promotion_candidates)
+                (qualifier.temp_promotion_state,
+                 qualifier.promotion_candidates,
+                 qualifier.shuffle_errors)
             };
 
+            for (span, blame) in shuffle_errors {
+                let mut err = struct_span_err!(tcx.sess, span, E0526,
+                                               "shuffle indices are not constant");
+                if let Some((blame_span, reason)) = blame {
+                    err.span_note(blame_span,
+                                  &format!("the shuffle indices can't be promoted to a \
+                                            constant because this {}", reason));
+                }
+                err.emit();
+            }
+
             // Do the actual promotion, now that we know what's viable.
             promote_consts::promote_candidates(mir, tcx, temps, candidates);
         } else {
@@ -1047,7 +1471,7 @@ impl MirPass for QualifyAndPromoteConstants {
             // `#[thread_local]` statics don't have to be `Sync`.
             for attr in &tcx.get_attrs(def_id)[..] {
                 if attr.check_name("thread_local") {
-                    return;
+                    return true;
                 }
             }
             let ty = mir.return_ty();
@@ -1065,5 +1489,6 @@ impl MirPass for QualifyAndPromoteConstants {
                 }
             });
         }
+        true
     }
 }
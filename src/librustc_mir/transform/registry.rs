@@ -0,0 +1,501 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The registry of the MIR passes, which lists them in the order they run
+//! in by default, with the suite they run in: `mir_const`, `mir_validated`
+//! or `optimized_mir`.
+//!
+//! `-Z mir-passes=a,b,...` replaces that order with the given one, naming
+//! the passes by their registered names, which don't change with the types
+//! implementing them. A pass stays in its suite, so the passes of a suite
+//! must be given after those of the suites before it. The mandatory
+//! passes, which check or lower the MIR, can't be left out, and keep their
+//! default order relative to each other; the optimizations can be left out
+//! or moved anywhere in their suite.
+//!
+//! Whether a pass runs is decided here too: an optimization runs from a
+//! given `-Z mir-opt-level` on, unless `-Z mir-enable-passes=+a,-b,...`
+//! enables or disables it, the last toggle of a pass winning, and any pass
+//! only runs if the flags it depends on let it, as `MirPass::is_enabled`
+//! tells. A disabled pass keeps its place and number in the pipeline, but
+//! neither runs nor is dumped. `-Z mir-print-passes` prints the resulting
+//! pipeline.
+//!
+//! `-Z mir-run-only-pass=<pass>` disables every optimization but the given
+//! one, which runs whatever the opt level, for the tests of a single pass,
+//! whose dumps then don't change with the optimizations before it.
+//!
+//! The passes of a group, like the `cleanup` one of copy propagation,
+//! constant propagation and the simplifications after it, make way for
+//! each other, so `run_passes!` reruns them, in order, until none of them
+//! changes the body. Only the consecutive passes of a group are rerun
+//! together, so moving one of them away with `-Z mir-passes` takes it out
+//! of the group.
+//!
+//! A pass may rely on what others did to the body: add-validation needs the
+//! call edges all-call-edges splits, and the inliner needs erased regions.
+//! It lists them in `must_run_after`, and the passes that may undo what
+//! others did, like the CFG simplifications merging the blocks the call
+//! edges were split into, list them in `invalidates`. An order given to
+//! `-Z mir-passes` is rejected unless every pass runs after those it must
+//! run after that are in the pipeline, with none of the passes in between
+//! invalidating them.
+
+use rustc::session::Session;
+use super::*;
+
+/// The query whose MIR a suite of passes produces.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MirSuite {
+    Const,
+    Validated,
+    Optimized,
+}
+
+impl MirSuite {
+    fn name(self) -> &'static str {
+        match self {
+            MirSuite::Const => "mir_const",
+            MirSuite::Validated => "mir_validated",
+            MirSuite::Optimized => "optimized_mir",
+        }
+    }
+}
+
+pub struct RegisteredPass {
+    /// The name `-Z mir-passes` and `-Z mir-enable-passes` refer to the
+    /// pass by.
+    pub name: &'static str,
+    pub suite: MirSuite,
+    pub mandatory: bool,
+
+    /// The `-Z mir-opt-level` an optional pass runs from by default.
+    pub min_opt_level: usize,
+
+    /// Whether the pass runs, as decided by `suite_passes`.
+    pub enabled: bool,
+
+    /// The group the pass is rerun with until they change nothing.
+    pub group: Option<&'static str>,
+
+    /// The passes whose changes this one relies on.
+    pub must_run_after: &'static [&'static str],
+
+    /// The passes whose changes this one may undo.
+    pub invalidates: &'static [&'static str],
+
+    pub pass: Box<MirPass>,
+}
+
+impl RegisteredPass {
+    fn in_group(self, group: &'static str) -> RegisteredPass {
+        RegisteredPass { group: Some(group), ..self }
+    }
+
+    fn must_run_after(self, passes: &'static [&'static str]) -> RegisteredPass {
+        RegisteredPass { must_run_after: passes, ..self }
+    }
+
+    fn invalidates(self, passes: &'static [&'static str]) -> RegisteredPass {
+        RegisteredPass { invalidates: passes, ..self }
+    }
+}
+
+fn mandatory<P: MirPass + 'static>(name: &'static str, suite: MirSuite, pass: P)
+                                   -> RegisteredPass {
+    RegisteredPass {
+        name,
+        suite,
+        mandatory: true,
+        min_opt_level: 0,
+        enabled: true,
+        group: None,
+        must_run_after: &[],
+        invalidates: &[],
+        pass: Box::new(pass),
+    }
+}
+
+fn optional<P: MirPass + 'static>(name: &'static str,
+                                  suite: MirSuite,
+                                  min_opt_level: usize,
+                                  pass: P)
+                                  -> RegisteredPass {
+    RegisteredPass {
+        name,
+        suite,
+        mandatory: false,
+        min_opt_level,
+        enabled: true,
+        group: None,
+        must_run_after: &[],
+        invalidates: &[],
+        pass: Box::new(pass),
+    }
+}
+
+/// Returns the passes in their default order.
+fn registered_passes() -> Vec<RegisteredPass> {
+    use self::MirSuite::*;
+    vec![
+        // Remove all `EndRegion` statements that are not involved in borrows.
+        mandatory("clean-end-regions", Const, clean_end_regions::CleanEndRegions),
+
+        // What we need to do constant evaluation.
+        mandatory("simplify-cfg-initial", Const, simplify::SimplifyCfg::new("initial"))
+            .invalidates(CALL_EDGES),
+        mandatory("typeck", Const, type_check::TypeckMir),
+        mandatory("rustc-peek", Const, rustc_peek::SanityCheck),
+
+        // What we need to run borrowck etc.
+        mandatory("qualify-consts", Validated, qualify_consts::QualifyAndPromoteConstants),
+        mandatory("simplify-cfg-qualify-consts", Validated,
+                  simplify::SimplifyCfg::new("qualify-consts"))
+            .invalidates(CALL_EDGES),
+        mandatory("lint-unread-assignments", Validated,
+                  lint_unread_assignments::LintUnreadAssignments),
+
+        // Remove the constructs only emitted for the benefit of borrowck.
+        // Everything from here on out works on "optimization MIR".
+        mandatory("cleanup-post-borrowck", Optimized, cleanup_post_borrowck::CleanupPostBorrowck),
+
+        mandatory("no-landing-pads-initial", Optimized, no_landing_pads::NoLandingPads),
+        mandatory("simplify-branches-initial", Optimized,
+                  simplify_branches::SimplifyBranches::new("initial")),
+
+        // These next passes must be executed together
+        mandatory("critical-call-edges-elaborate-drops", Optimized,
+                  add_call_guards::CriticalCallEdges),
+        mandatory("elaborate-drops", Optimized, elaborate_drops::ElaborateDrops)
+            .must_run_after(&["critical-call-edges-elaborate-drops"]),
+        mandatory("no-landing-pads-elaborate-drops", Optimized, no_landing_pads::NoLandingPads)
+            .must_run_after(&["elaborate-drops"]),
+        // AddValidation needs to run after ElaborateDrops and before EraseRegions, and it needs
+        // an AllCallEdges pass right before it.
+        mandatory("all-call-edges", Optimized, add_call_guards::AllCallEdges),
+        mandatory("add-validation", Optimized, add_validation::AddValidation)
+            .must_run_after(&["elaborate-drops", "all-call-edges"]),
+        // AddMovesForPackedDrops needs to run after drop
+        // elaboration.
+        mandatory("add-moves-for-packed-drops", Optimized,
+                  add_moves_for_packed_drops::AddMovesForPackedDrops)
+            .must_run_after(&["elaborate-drops"]),
+
+        mandatory("simplify-cfg-elaborate-drops", Optimized,
+                  simplify::SimplifyCfg::new("elaborate-drops"))
+            .invalidates(CALL_EDGES),
+
+        // No lifetime analysis based on borrowing can be done from here on out.
+
+        // From here on out, regions are gone.
+        mandatory("erase-regions", Optimized, erase_regions::EraseRegions),
+
+        mandatory("lower-128bit", Optimized, lower_128bit::Lower128Bit),
+
+        // Optimizations begin.
+        optional("inline", Optimized, 2, inline::Inline).must_run_after(&["erase-regions"]),
+        optional("lower-assume", Optimized, 0, lower_assume::LowerAssume),
+        optional("box-to-stack", Optimized, 2, box_to_stack::BoxToStack),
+        optional("instcombine", Optimized, 1, instcombine::InstCombine),
+        optional("deaggregator", Optimized, 3, deaggregator::Deaggregator),
+        // Copy propagation is slow, and messes up debug info.
+        optional("copy-prop", Optimized, 2, copy_prop::CopyPropagation).in_group("cleanup"),
+        optional("global-cse", Optimized, 2, global_cse::GlobalCse).in_group("cleanup"),
+        optional("const-prop", Optimized, 0, const_prop::ConstProp).in_group("cleanup"),
+        optional("simplify-branches-after-const-prop", Optimized, 0,
+                 simplify_branches::SimplifyBranches::new("after-const-prop"))
+            .in_group("cleanup"),
+        optional("simplify-cfg-after-const-prop", Optimized, 0,
+                 simplify::SimplifyCfg::new("after-const-prop"))
+            .in_group("cleanup")
+            .invalidates(CALL_EDGES),
+        optional("move-last-uses", Optimized, 1, move_last_uses::MoveLastUses),
+        optional("simplify-locals", Optimized, 0, simplify::SimplifyLocals),
+        mandatory("effectless-loops", Optimized, effectless_loops::EffectlessLoops),
+
+        mandatory("state-transform", Optimized, generator::StateTransform),
+        mandatory("critical-call-edges-pre-trans", Optimized, add_call_guards::CriticalCallEdges),
+        mandatory("pre-trans", Optimized, dump_mir::Marker("PreTrans"))
+            .must_run_after(&["critical-call-edges-pre-trans"]),
+    ]
+}
+
+/// The passes splitting call edges, which the CFG simplifications undo by
+/// merging the blocks they split them into.
+const CALL_EDGES: &[&str] = &[
+    "critical-call-edges-elaborate-drops",
+    "all-call-edges",
+    "critical-call-edges-pre-trans",
+];
+
+/// Returns the passes of `suite`, in the order they run in, with whether
+/// they are enabled.
+pub fn suite_passes(sess: &Session, suite: MirSuite) -> Vec<RegisteredPass> {
+    let mut passes = match sess.opts.debugging_opts.mir_passes {
+        None => {
+            let passes = registered_passes();
+            debug_assert_eq!(check_order(&passes), Ok(()));
+            passes
+        }
+        Some(ref names) => match pipeline(names) {
+            Ok(passes) => passes,
+            Err(message) => sess.fatal(&message),
+        },
+    };
+    passes.retain(|pass| pass.suite == suite);
+
+    let toggles = match sess.opts.debugging_opts.mir_enable_passes {
+        None => vec![],
+        Some(ref toggles) => match parse_toggles(toggles) {
+            Ok(toggles) => toggles,
+            Err(message) => sess.fatal(&message),
+        },
+    };
+    let only = match sess.opts.debugging_opts.mir_run_only_pass {
+        None => None,
+        Some(ref name) => match parse_only_pass(name) {
+            Ok(name) => Some(name),
+            Err(message) => sess.fatal(&message),
+        },
+    };
+    let opt_level = sess.opts.debugging_opts.mir_opt_level;
+    for pass in &mut passes {
+        let toggle = toggles.iter().rev().find(|&&(name, _)| name == pass.name);
+        pass.enabled = (pass.mandatory || match (only, toggle) {
+            (Some(only), _) => pass.name == only,
+            (None, Some(&(_, enabled))) => enabled,
+            (None, None) => opt_level >= pass.min_opt_level,
+        }) && pass.pass.is_enabled(sess);
+    }
+    passes
+}
+
+/// Returns the end of the run of passes starting at `start` that are
+/// rerun together: the consecutive passes of its group, or just it.
+pub fn group_end(passes: &[RegisteredPass], start: usize) -> usize {
+    match passes[start].group {
+        None => start + 1,
+        group => {
+            start + passes[start..].iter().take_while(|pass| pass.group == group).count()
+        }
+    }
+}
+
+/// Prints the passes of every suite, in the order they run in, with the
+/// number of their dumps, their name in the dumps and their group, for
+/// `-Z mir-print-passes`.
+pub fn print_passes(sess: &Session) {
+    for &suite in &[MirSuite::Const, MirSuite::Validated, MirSuite::Optimized] {
+        for (index, pass) in suite_passes(sess, suite).iter().enumerate() {
+            let group = match pass.group {
+                Some(group) => format!(", in {}", group),
+                None => String::new(),
+            };
+            println!("{:03}-{:03} {} ({}){}{}",
+                     suite as usize, index, pass.name, pass.pass.name(), group,
+                     if pass.enabled { "" } else { ", disabled" });
+        }
+    }
+}
+
+fn unknown_pass(name: &str, flag: &str, registered: &[RegisteredPass]) -> String {
+    let names: Vec<_> = registered.iter().map(|pass| pass.name).collect();
+    format!("unknown MIR pass `{}` in `{}`, expected one of: {}",
+            name, flag, names.join(", "))
+}
+
+/// Parses the comma-separated list of `+<pass>` and `-<pass>` given to
+/// `-Z mir-enable-passes` into the names of the passes, with whether they
+/// are enabled.
+fn parse_toggles(toggles: &str) -> Result<Vec<(&'static str, bool)>, String> {
+    let registered = registered_passes();
+    let mut parsed = vec![];
+    for toggle in toggles.split(',').map(|toggle| toggle.trim()).filter(|t| !t.is_empty()) {
+        let (name, enabled) = if toggle.starts_with('+') {
+            (toggle[1..].trim(), true)
+        } else if toggle.starts_with('-') {
+            (toggle[1..].trim(), false)
+        } else {
+            return Err(format!("expected `+<pass>` or `-<pass>` in `-Z mir-enable-passes`, \
+                                found `{}`", toggle));
+        };
+        let pass = match registered.iter().find(|pass| pass.name == name) {
+            Some(pass) => pass,
+            None => return Err(unknown_pass(name, "-Z mir-enable-passes", &registered)),
+        };
+        if pass.mandatory && !enabled {
+            return Err(format!("mandatory MIR pass `{}` can't be disabled", name));
+        }
+        parsed.push((pass.name, enabled));
+    }
+    Ok(parsed)
+}
+
+/// Parses the name given to `-Z mir-run-only-pass`, which must be that of
+/// an optional pass.
+fn parse_only_pass(name: &str) -> Result<&'static str, String> {
+    let registered = registered_passes();
+    let name = name.trim();
+    match registered.iter().find(|pass| pass.name == name) {
+        Some(pass) if pass.mandatory => {
+            Err(format!("mandatory MIR pass `{}` always runs, `-Z mir-run-only-pass` expects \
+                         an optional one", name))
+        }
+        Some(pass) => Ok(pass.name),
+        None => Err(unknown_pass(name, "-Z mir-run-only-pass", &registered)),
+    }
+}
+
+/// Returns the passes named by `names`, the comma-separated list given to
+/// `-Z mir-passes`, in that order, or why they can't run in that order.
+fn pipeline(names: &str) -> Result<Vec<RegisteredPass>, String> {
+    let registered = registered_passes();
+    let mut order: Vec<usize> = vec![];
+    for name in names.split(',').map(|name| name.trim()).filter(|name| !name.is_empty()) {
+        let index = match registered.iter().position(|pass| pass.name == name) {
+            Some(index) => index,
+            None => return Err(unknown_pass(name, "-Z mir-passes", &registered)),
+        };
+        let pass = &registered[index];
+        if order.contains(&index) {
+            return Err(format!("MIR pass `{}` is given more than once to `-Z mir-passes`",
+                               name));
+        }
+        if let Some(&previous) = order.last() {
+            let previous = &registered[previous];
+            if pass.suite < previous.suite {
+                return Err(format!("MIR pass `{}` of `{}` can't run after `{}` of `{}`",
+                                   name, pass.suite.name(),
+                                   previous.name, previous.suite.name()));
+            }
+        }
+        if pass.mandatory {
+            let previous = order.iter().rev().cloned().find(|&i| registered[i].mandatory);
+            if let Some(previous) = previous {
+                if previous > index {
+                    return Err(format!("mandatory MIR pass `{}` must run before `{}`",
+                                       name, registered[previous].name));
+                }
+            }
+        }
+        order.push(index);
+    }
+
+    for (index, pass) in registered.iter().enumerate() {
+        if pass.mandatory && !order.contains(&index) {
+            return Err(format!("mandatory MIR pass `{}` can't be left out of `-Z mir-passes`",
+                               pass.name));
+        }
+    }
+
+    let mut registered: Vec<_> = registered.into_iter().map(Some).collect();
+    let passes: Vec<_> =
+        order.into_iter().map(|index| registered[index].take().unwrap()).collect();
+    check_order(&passes)?;
+    Ok(passes)
+}
+
+/// Checks that every pass of `passes` runs after the passes it must run
+/// after that are in `passes`, with none of the passes in between
+/// invalidating them.
+fn check_order(passes: &[RegisteredPass]) -> Result<(), String> {
+    for (index, pass) in passes.iter().enumerate() {
+        for &required in pass.must_run_after {
+            let position = match passes.iter().position(|other| other.name == required) {
+                Some(position) => position,
+                None => continue,
+            };
+            if position > index {
+                return Err(format!("MIR pass `{}` must run after `{}`", pass.name, required));
+            }
+            let between = &passes[position + 1..index];
+            let invalidating = between.iter().find(|other| other.invalidates.contains(&required));
+            if let Some(invalidating) = invalidating {
+                return Err(format!("MIR pass `{}` must run after `{}` without `{}` in between, \
+                                    which undoes it",
+                                   pass.name, required, invalidating.name));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pass(name: &'static str) -> RegisteredPass {
+        mandatory(name, MirSuite::Optimized, dump_mir::Marker(name))
+    }
+
+    #[test]
+    fn default_order() {
+        assert_eq!(check_order(&registered_passes()), Ok(()));
+    }
+
+    #[test]
+    fn constraints_name_registered_passes() {
+        let registered = registered_passes();
+        for pass in &registered {
+            for name in pass.must_run_after.iter().chain(pass.invalidates) {
+                assert!(registered.iter().any(|other| other.name == *name),
+                        "MIR pass `{}` refers to unknown pass `{}`", pass.name, name);
+            }
+        }
+    }
+
+    #[test]
+    fn must_run_after() {
+        assert_eq!(check_order(&[pass("a"), pass("b").must_run_after(&["a"])]), Ok(()));
+        assert_eq!(check_order(&[pass("b").must_run_after(&["a"]), pass("a")]),
+                   Err(String::from("MIR pass `b` must run after `a`")));
+        assert_eq!(check_order(&[pass("b").must_run_after(&["a"])]), Ok(()));
+    }
+
+    #[test]
+    fn invalidates() {
+        assert_eq!(check_order(&[pass("a"),
+                                 pass("c").invalidates(&["a"]),
+                                 pass("b").must_run_after(&["a"])]),
+                   Err(String::from("MIR pass `b` must run after `a` without `c` in between, \
+                                     which undoes it")));
+        assert_eq!(check_order(&[pass("a"),
+                                 pass("b").must_run_after(&["a"]),
+                                 pass("c").invalidates(&["a"])]),
+                   Ok(()));
+        assert_eq!(check_order(&[pass("c").invalidates(&["a"]),
+                                 pass("a"),
+                                 pass("b").must_run_after(&["a"])]),
+                   Ok(()));
+    }
+
+    #[test]
+    fn pipeline_order() {
+        let default: Vec<_> = registered_passes().iter().map(|pass| pass.name).collect();
+        assert!(pipeline(&default.join(",")).is_ok());
+
+        let mut moved: Vec<_> = default.iter().cloned().filter(|&name| name != "inline").collect();
+        let erase_regions = moved.iter().position(|&name| name == "erase-regions").unwrap();
+        moved.insert(erase_regions, "inline");
+        assert_eq!(pipeline(&moved.join(",")).err(),
+                   Some(String::from("MIR pass `inline` must run after `erase-regions`")));
+
+        let mut moved: Vec<_> = default.iter().cloned()
+            .filter(|&name| name != "simplify-cfg-after-const-prop")
+            .collect();
+        let pre_trans = moved.iter().position(|&name| name == "pre-trans").unwrap();
+        moved.insert(pre_trans, "simplify-cfg-after-const-prop");
+        assert_eq!(pipeline(&moved.join(",")).err(),
+                   Some(String::from("MIR pass `pre-trans` must run after \
+                                      `critical-call-edges-pre-trans` without \
+                                      `simplify-cfg-after-const-prop` in between, \
+                                      which undoes it")));
+    }
+}
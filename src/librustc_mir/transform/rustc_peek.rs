@@ -14,31 +14,34 @@ use syntax_pos::Span;
 
 use rustc::ty::{self, TyCtxt};
 use rustc::mir::{self, Mir, Location};
-use rustc_data_structures::indexed_set::IdxSetBuf;
+use rustc_data_structures::indexed_set::{IdxSet, IdxSetBuf};
 use rustc_data_structures::indexed_vec::Idx;
-use transform::{MirPass, MirSource};
+use transform::{MirOptContext, MirPass, MirSource};
 
 use dataflow::do_dataflow;
 use dataflow::MoveDataParamEnv;
-use dataflow::BitDenotation;
+use dataflow::{BitDenotation, Direction};
 use dataflow::DataflowResults;
 use dataflow::{DefinitelyInitializedLvals, MaybeInitializedLvals, MaybeUninitializedLvals};
+use dataflow::{DefIndex, ReachingDefinitions};
 use dataflow::move_paths::{MovePathIndex, LookupResult};
 use dataflow::move_paths::{HasMoveData, MoveData};
 use dataflow;
+use util::liveness::{LivenessMode, MaybeLiveLocals};
 
 use dataflow::has_rustc_mir_with;
 
 pub struct SanityCheck;
 
 impl MirPass for SanityCheck {
-    fn run_pass<'a, 'tcx>(&self, tcx: TyCtxt<'a, 'tcx, 'tcx>,
-                          src: MirSource, mir: &mut Mir<'tcx>) {
+    fn run_pass_in_context<'a, 'tcx>(&self, tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                     _context: &MirOptContext<'tcx>,
+                                     src: MirSource, mir: &mut Mir<'tcx>) -> bool {
         let def_id = src.def_id;
         let id = tcx.hir.as_local_node_id(def_id).unwrap();
         if !tcx.has_attr(def_id, "rustc_mir_borrowck") {
             debug!("skipping rustc_peek::SanityCheck on {}", tcx.item_path_str(def_id));
-            return;
+            return false;
         } else {
             debug!("running rustc_peek::SanityCheck on {}", tcx.item_path_str(def_id));
         }
@@ -51,15 +54,15 @@ impl MirPass for SanityCheck {
         let flow_inits =
             do_dataflow(tcx, mir, id, &attributes, &dead_unwinds,
                         MaybeInitializedLvals::new(tcx, mir, &mdpe),
-                        |bd, i| &bd.move_data().move_paths[i]);
+                        |bd, i| &bd.move_data().move_paths[i].lvalue);
         let flow_uninits =
             do_dataflow(tcx, mir, id, &attributes, &dead_unwinds,
                         MaybeUninitializedLvals::new(tcx, mir, &mdpe),
-                        |bd, i| &bd.move_data().move_paths[i]);
+                        |bd, i| &bd.move_data().move_paths[i].lvalue);
         let flow_def_inits =
             do_dataflow(tcx, mir, id, &attributes, &dead_unwinds,
                         DefinitelyInitializedLvals::new(tcx, mir, &mdpe),
-                        |bd, i| &bd.move_data().move_paths[i]);
+                        |bd, i| &bd.move_data().move_paths[i].lvalue);
 
         if has_rustc_mir_with(&attributes, "rustc_peek_maybe_init").is_some() {
             sanity_check_via_rustc_peek(tcx, mir, id, &attributes, &flow_inits);
@@ -70,9 +73,25 @@ impl MirPass for SanityCheck {
         if has_rustc_mir_with(&attributes, "rustc_peek_definite_init").is_some() {
             sanity_check_via_rustc_peek(tcx, mir, id, &attributes, &flow_def_inits);
         }
+        if has_rustc_mir_with(&attributes, "rustc_peek_liveness").is_some() {
+            let mode = LivenessMode { include_regular_use: true, include_drops: true };
+            let flow_liveness =
+                do_dataflow(tcx, mir, id, &attributes, &dead_unwinds,
+                            MaybeLiveLocals::new(mir, mode),
+                            |bd, i| &bd.mir().local_decls[i]);
+            sanity_check_via_rustc_peek(tcx, mir, id, &attributes, &flow_liveness);
+        }
+        if has_rustc_mir_with(&attributes, "rustc_peek_reaching_defs").is_some() {
+            let flow_reaching_defs =
+                do_dataflow(tcx, mir, id, &attributes, &dead_unwinds,
+                            ReachingDefinitions::new(mir),
+                            |bd, i| &bd.defs()[i]);
+            sanity_check_via_rustc_peek(tcx, mir, id, &attributes, &flow_reaching_defs);
+        }
         if has_rustc_mir_with(&attributes, "stop_after_dataflow").is_some() {
             tcx.sess.fatal("stop_after_dataflow ended compilation");
         }
+        false
     }
 }
 
@@ -92,12 +111,16 @@ impl MirPass for SanityCheck {
 /// (If there are any calls to `rustc_peek` that do not match the
 /// expression form above, then that emits an error as well, but those
 /// errors are not intended to be used for unit tests.)
+///
+/// For a backward analysis, the bit-state is the one right after the
+/// borrow, as seen going backward from the end of the block, so that
+/// the borrow itself doesn't count.
 pub fn sanity_check_via_rustc_peek<'a, 'tcx, O>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
                                                 mir: &Mir<'tcx>,
                                                 id: ast::NodeId,
                                                 _attributes: &[ast::Attribute],
                                                 results: &DataflowResults<O>)
-    where O: BitDenotation<Idx=MovePathIndex> + HasMoveData<'tcx>
+    where O: PeekBit<'tcx>
 {
     debug!("sanity_check_via_rustc_peek id: {:?}", id);
     // FIXME: this is not DRY. Figure out way to abstract this and
@@ -109,13 +132,172 @@ pub fn sanity_check_via_rustc_peek<'a, 'tcx, O>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     }
 }
 
+/// An analysis that can be checked with `rustc_peek`.
+pub trait PeekBit<'tcx>: BitDenotation {
+    /// Returns whether the bit-state of `lvalue` in `state` is set, or
+    /// `None` if the analysis doesn't track `lvalue` on its own.
+    fn peek_bit(&self, lvalue: &mir::Lvalue<'tcx>, state: &IdxSet<Self::Idx>) -> Option<bool>;
+
+    /// Reports that the bit-state of `lvalue` in `state` is not set at the
+    /// `rustc_peek` at `span`.
+    fn report_unset<'a>(&self,
+                        tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                        span: Span,
+                        _lvalue: &mir::Lvalue<'tcx>,
+                        _state: &IdxSet<Self::Idx>) {
+        tcx.sess.span_err(span, "rustc_peek: bit not set");
+    }
+
+    /// Reports that the analysis doesn't track `lvalue`, peeked at at
+    /// `span`.
+    fn report_untracked<'a>(&self,
+                            tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                            span: Span,
+                            _lvalue: &mir::Lvalue<'tcx>) {
+        tcx.sess.span_err(span, "rustc_peek: argument untracked");
+    }
+}
+
+/// The bit-state of an lvalue without a move path of its own, like a
+/// field that is never moved out of or assigned on its own, or an array
+/// element, is the one of the closest lvalue it is a part of that has a
+/// move path. An lvalue behind a dereference of that lvalue isn't
+/// tracked.
+fn move_path_peek_bit<'tcx, O>(analysis: &O,
+                               lvalue: &mir::Lvalue<'tcx>,
+                               state: &IdxSet<MovePathIndex>)
+                               -> Option<bool>
+    where O: HasMoveData<'tcx>
+{
+    let move_data = analysis.move_data();
+    match move_data.rev_lookup.find(lvalue) {
+        LookupResult::Exact(mpi) => Some(state.contains(&mpi)),
+        LookupResult::Parent(Some(mpi)) => {
+            if is_behind_deref(lvalue, &move_data.move_paths[mpi].lvalue) {
+                None
+            } else {
+                Some(state.contains(&mpi))
+            }
+        }
+        LookupResult::Parent(None) => None,
+    }
+}
+
+/// Whether `lvalue`, which is a part of `base`, is behind a dereference of
+/// a part of `base`.
+fn is_behind_deref<'tcx>(lvalue: &mir::Lvalue<'tcx>, base: &mir::Lvalue<'tcx>) -> bool {
+    let mut lvalue = lvalue;
+    while lvalue != base {
+        match *lvalue {
+            mir::Lvalue::Projection(ref proj) => {
+                if proj.elem == mir::ProjectionElem::Deref {
+                    return true;
+                }
+                lvalue = &proj.base;
+            }
+            _ => bug!("{:?} is not a part of {:?}", lvalue, base),
+        }
+    }
+    false
+}
+
+fn move_path_report_untracked<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                        span: Span,
+                                        lvalue: &mir::Lvalue<'tcx>) {
+    tcx.sess.span_err(span, &format!("rustc_peek: `{:?}` has no move path", lvalue));
+}
+
+impl<'a, 'gcx, 'tcx> PeekBit<'tcx> for MaybeInitializedLvals<'a, 'gcx, 'tcx> {
+    fn peek_bit(&self, lvalue: &mir::Lvalue<'tcx>, state: &IdxSet<MovePathIndex>)
+                -> Option<bool> {
+        move_path_peek_bit(self, lvalue, state)
+    }
+
+    fn report_untracked<'b>(&self,
+                            tcx: TyCtxt<'b, 'tcx, 'tcx>,
+                            span: Span,
+                            lvalue: &mir::Lvalue<'tcx>) {
+        move_path_report_untracked(tcx, span, lvalue)
+    }
+}
+
+impl<'a, 'gcx, 'tcx> PeekBit<'tcx> for MaybeUninitializedLvals<'a, 'gcx, 'tcx> {
+    fn peek_bit(&self, lvalue: &mir::Lvalue<'tcx>, state: &IdxSet<MovePathIndex>)
+                -> Option<bool> {
+        move_path_peek_bit(self, lvalue, state)
+    }
+
+    fn report_untracked<'b>(&self,
+                            tcx: TyCtxt<'b, 'tcx, 'tcx>,
+                            span: Span,
+                            lvalue: &mir::Lvalue<'tcx>) {
+        move_path_report_untracked(tcx, span, lvalue)
+    }
+}
+
+impl<'a, 'gcx, 'tcx> PeekBit<'tcx> for DefinitelyInitializedLvals<'a, 'gcx, 'tcx> {
+    fn peek_bit(&self, lvalue: &mir::Lvalue<'tcx>, state: &IdxSet<MovePathIndex>)
+                -> Option<bool> {
+        move_path_peek_bit(self, lvalue, state)
+    }
+
+    fn report_untracked<'b>(&self,
+                            tcx: TyCtxt<'b, 'tcx, 'tcx>,
+                            span: Span,
+                            lvalue: &mir::Lvalue<'tcx>) {
+        move_path_report_untracked(tcx, span, lvalue)
+    }
+}
+
+impl<'a, 'tcx> PeekBit<'tcx> for MaybeLiveLocals<'a, 'tcx> {
+    fn peek_bit(&self, lvalue: &mir::Lvalue<'tcx>, state: &IdxSet<mir::Local>)
+                -> Option<bool> {
+        match *lvalue {
+            mir::Lvalue::Local(local) => Some(state.contains(&local)),
+            _ => None,
+        }
+    }
+}
+
+/// The bit-state of a local is set when exactly one of its definitions
+/// reaches the peek.
+impl<'a, 'tcx> PeekBit<'tcx> for ReachingDefinitions<'a, 'tcx> {
+    fn peek_bit(&self, lvalue: &mir::Lvalue<'tcx>, state: &IdxSet<DefIndex>)
+                -> Option<bool> {
+        reaching_def_count(self, lvalue, state).map(|count| count == 1)
+    }
+
+    fn report_unset<'b>(&self,
+                        tcx: TyCtxt<'b, 'tcx, 'tcx>,
+                        span: Span,
+                        lvalue: &mir::Lvalue<'tcx>,
+                        state: &IdxSet<DefIndex>) {
+        let count = reaching_def_count(self, lvalue, state).unwrap_or(0);
+        tcx.sess.struct_span_err(span, "rustc_peek: bit not set")
+            .note(&format!("{} definitions of `{:?}` reach here", count, lvalue))
+            .emit();
+    }
+}
+
+fn reaching_def_count<'tcx>(analysis: &ReachingDefinitions,
+                            lvalue: &mir::Lvalue<'tcx>,
+                            state: &IdxSet<DefIndex>)
+                            -> Option<usize> {
+    match *lvalue {
+        mir::Lvalue::Local(local) => {
+            Some(analysis.defs_of(local).iter().filter(|def| state.contains(def)).count())
+        }
+        _ => None,
+    }
+}
+
 fn each_block<'a, 'tcx, O>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
                            mir: &Mir<'tcx>,
                            results: &DataflowResults<O>,
                            bb: mir::BasicBlock) where
-    O: BitDenotation<Idx=MovePathIndex> + HasMoveData<'tcx>
+    O: PeekBit<'tcx>
 {
-    let move_data = results.0.operator.move_data();
+    let operator = results.operator();
     let mir::BasicBlockData { ref statements, ref terminator, is_cleanup: _ } = mir[bb];
 
     let (args, span) = match is_rustc_peek(tcx, terminator) {
@@ -138,21 +320,36 @@ fn each_block<'a, 'tcx, O>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
         }
     };
 
-    let mut entry = results.0.sets.on_entry_set_for(bb.index()).to_owned();
-    let mut gen = results.0.sets.gen_set_for(bb.index()).to_owned();
-    let mut kill = results.0.sets.kill_set_for(bb.index()).to_owned();
+    let mut entry = results.0.sets.on_entry_set_for(bb.index());
+    let mut gen = results.0.sets.gen_set_for(bb.index());
+    let mut kill = results.0.sets.kill_set_for(bb.index());
 
     // Emulate effect of all statements in the block up to (but not
     // including) the borrow within `peek_arg_lval`. Do *not* include
     // call to `peek_arg_lval` itself (since we are peeking the state
     // of the argument at time immediate preceding Call to
-    // `rustc_peek`).
+    // `rustc_peek`). A backward analysis starts from the end of the
+    // block instead, with the effect of the call.
 
     let mut sets = dataflow::BlockSets { on_entry: &mut entry,
                                       gen_set: &mut gen,
                                       kill_set: &mut kill };
 
-    for (j, stmt) in statements.iter().enumerate() {
+    let indices: Vec<usize> = match O::direction() {
+        Direction::Forward => (0..statements.len()).collect(),
+        Direction::Backward => {
+            for e in sets.gen_set.words_mut() { *e = 0; }
+            for e in sets.kill_set.words_mut() { *e = 0; }
+            let location = Location { block: bb, statement_index: statements.len() };
+            operator.terminator_effect(&mut sets, location);
+            sets.on_entry.union(sets.gen_set);
+            sets.on_entry.subtract(sets.kill_set);
+            (0..statements.len()).rev().collect()
+        }
+    };
+
+    for j in indices {
+        let stmt = &statements[j];
         debug!("rustc_peek: ({:?},{}) {:?}", bb, j, stmt);
         let (lvalue, rvalue) = match stmt.kind {
             mir::StatementKind::Assign(ref lvalue, ref rvalue) => {
@@ -163,6 +360,7 @@ fn each_block<'a, 'tcx, O>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
             mir::StatementKind::InlineAsm { .. } |
             mir::StatementKind::EndRegion(_) |
             mir::StatementKind::Validate(..) |
+            mir::StatementKind::Assume(..) |
             mir::StatementKind::Nop => continue,
             mir::StatementKind::SetDiscriminant{ .. } =>
                 span_bug!(stmt.source_info.span,
@@ -172,17 +370,16 @@ fn each_block<'a, 'tcx, O>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
         if lvalue == peek_arg_lval {
             if let mir::Rvalue::Ref(_, mir::BorrowKind::Shared, ref peeking_at_lval) = *rvalue {
                 // Okay, our search is over.
-                match move_data.rev_lookup.find(peeking_at_lval) {
-                    LookupResult::Exact(peek_mpi) => {
-                        let bit_state = sets.on_entry.contains(&peek_mpi);
+                match operator.peek_bit(peeking_at_lval, sets.on_entry) {
+                    Some(bit_state) => {
                         debug!("rustc_peek({:?} = &{:?}) bit_state: {}",
                                lvalue, peeking_at_lval, bit_state);
                         if !bit_state {
-                            tcx.sess.span_err(span, "rustc_peek: bit not set");
+                            operator.report_unset(tcx, span, peeking_at_lval, sets.on_entry);
                         }
                     }
-                    LookupResult::Parent(..) => {
-                        tcx.sess.span_err(span, "rustc_peek: argument untracked");
+                    None => {
+                        operator.report_untracked(tcx, span, peeking_at_lval);
                     }
                 }
                 return;
@@ -196,14 +393,12 @@ fn each_block<'a, 'tcx, O>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
             }
         }
 
-        let lhs_mpi = move_data.rev_lookup.find(lvalue);
-
-        debug!("rustc_peek: computing effect on lvalue: {:?} ({:?}) in stmt: {:?}",
-               lvalue, lhs_mpi, stmt);
+        debug!("rustc_peek: computing effect on lvalue: {:?} in stmt: {:?}",
+               lvalue, stmt);
         // reset GEN and KILL sets before emulating their effect.
         for e in sets.gen_set.words_mut() { *e = 0; }
         for e in sets.kill_set.words_mut() { *e = 0; }
-        results.0.operator.statement_effect(&mut sets, Location { block: bb, statement_index: j });
+        operator.statement_effect(&mut sets, Location { block: bb, statement_index: j });
         sets.on_entry.union(sets.gen_set);
         sets.on_entry.subtract(sets.kill_set);
     }
@@ -43,7 +43,8 @@ use rustc::ty::TyCtxt;
 use rustc::mir::*;
 use rustc::mir::visit::{MutVisitor, Visitor, LvalueContext};
 use std::borrow::Cow;
-use transform::{MirPass, MirSource};
+use transform::{MirOptContext, MirPass, MirSource};
+use util::journal::Journal;
 
 pub struct SimplifyCfg { label: String }
 
@@ -53,12 +54,14 @@ impl SimplifyCfg {
     }
 }
 
-pub fn simplify_cfg(mir: &mut Mir) {
-    CfgSimplifier::new(mir).simplify();
-    remove_dead_blocks(mir);
+/// Simplifies the CFG of `mir`, returning whether that changed it.
+pub fn simplify_cfg(mir: &mut Mir) -> bool {
+    let changed = CfgSimplifier::new(mir).simplify();
+    let removed = remove_dead_blocks(mir);
 
     // FIXME: Should probably be moved into some kind of pass manager
     mir.basic_blocks_mut().raw.shrink_to_fit();
+    changed || removed
 }
 
 impl MirPass for SimplifyCfg {
@@ -66,12 +69,13 @@ impl MirPass for SimplifyCfg {
         Cow::Borrowed(&self.label)
     }
 
-    fn run_pass<'a, 'tcx>(&self,
-                          _tcx: TyCtxt<'a, 'tcx, 'tcx>,
-                          _src: MirSource,
-                          mir: &mut Mir<'tcx>) {
+    fn run_pass_in_context<'a, 'tcx>(&self,
+                                     _tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                     _context: &MirOptContext<'tcx>,
+                                     _src: MirSource,
+                                     mir: &mut Mir<'tcx>) -> bool {
         debug!("SimplifyCfg({:?}) - simplifying {:?}", self.label, mir);
-        simplify_cfg(mir);
+        simplify_cfg(mir)
     }
 }
 
@@ -104,8 +108,10 @@ impl<'a, 'tcx: 'a> CfgSimplifier<'a, 'tcx> {
         }
     }
 
-    pub fn simplify(mut self) {
-        self.strip_nops();
+    /// Simplifies the CFG until that changes nothing, returning whether
+    /// anything changed.
+    pub fn simplify(mut self) -> bool {
+        let mut changed_any = self.strip_nops();
 
         loop {
             let mut changed = false;
@@ -142,7 +148,9 @@ impl<'a, 'tcx: 'a> CfgSimplifier<'a, 'tcx> {
             }
 
             if !changed { break }
+            changed_any = true;
         }
+        changed_any
     }
 
     // Collapse a goto chain starting from `start`
@@ -270,18 +278,24 @@ impl<'a, 'tcx: 'a> CfgSimplifier<'a, 'tcx> {
         false
     }
 
-    fn strip_nops(&mut self) {
+    fn strip_nops(&mut self) -> bool {
+        let mut changed = false;
         for blk in self.basic_blocks.iter_mut() {
+            let len = blk.statements.len();
             blk.statements.retain(|stmt| if let StatementKind::Nop = stmt.kind {
                 false
             } else {
                 true
-            })
+            });
+            changed |= blk.statements.len() != len;
         }
+        changed
     }
 }
 
-pub fn remove_dead_blocks(mir: &mut Mir) {
+/// Removes the blocks unreachable from the start block, returning whether
+/// there were any.
+pub fn remove_dead_blocks(mir: &mut Mir) -> bool {
     let mut seen = BitVector::new(mir.basic_blocks().len());
     for (bb, _) in traversal::preorder(mir) {
         seen.insert(bb.index());
@@ -301,6 +315,9 @@ pub fn remove_dead_blocks(mir: &mut Mir) {
         }
         used_blocks += 1;
     }
+    if used_blocks == num_blocks {
+        return false;
+    }
     basic_blocks.raw.truncate(used_blocks);
 
     for block in basic_blocks {
@@ -308,16 +325,18 @@ pub fn remove_dead_blocks(mir: &mut Mir) {
             *target = replacements[target.index()];
         }
     }
+    true
 }
 
 
 pub struct SimplifyLocals;
 
 impl MirPass for SimplifyLocals {
-    fn run_pass<'a, 'tcx>(&self,
-                          _: TyCtxt<'a, 'tcx, 'tcx>,
-                          _: MirSource,
-                          mir: &mut Mir<'tcx>) {
+    fn run_pass_in_context<'a, 'tcx>(&self,
+                                     tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                     _context: &MirOptContext<'tcx>,
+                                     source: MirSource,
+                                     mir: &mut Mir<'tcx>) -> bool {
         let mut marker = DeclMarker { locals: BitVector::new(mir.local_decls.len()) };
         marker.visit_mir(mir);
         // Return pointer and arguments are always live
@@ -325,10 +344,18 @@ impl MirPass for SimplifyLocals {
         for idx in mir.args_iter() {
             marker.locals.insert(idx.index());
         }
+        let num_locals = mir.local_decls.len();
         let map = make_local_map(&mut mir.local_decls, marker.locals);
+        let journal = Journal::new(tcx, source, &self.name());
+        for (old, &new) in map.iter().enumerate() {
+            if new != !0 && new != old {
+                journal.replaced_local(Local::new(old), Local::new(new));
+            }
+        }
         // Update references to all vars and tmps now
         LocalUpdater { map: map }.visit_mir(mir);
         mir.local_decls.shrink_to_fit();
+        mir.local_decls.len() != num_locals
     }
 }
 
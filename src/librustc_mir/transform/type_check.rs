@@ -24,7 +24,7 @@ use rustc::mir::visit::{LvalueContext, Visitor};
 use std::fmt;
 use syntax::ast;
 use syntax_pos::{Span, DUMMY_SP};
-use transform::{MirPass, MirSource};
+use transform::{MirOptContext, MirPass, MirSource};
 
 use rustc_data_structures::fx::FxHashSet;
 use rustc_data_structures::indexed_vec::Idx;
@@ -593,6 +593,12 @@ impl<'a, 'gcx, 'tcx> TypeChecker<'a, 'gcx, 'tcx> {
                     );
                 };
             }
+            StatementKind::Assume(ref cond) => {
+                let cond_ty = cond.ty(mir, tcx);
+                if cond_ty != tcx.types.bool {
+                    span_mirbug!(self, stmt, "bad Assume ({:?}, not bool", cond_ty);
+                }
+            }
             StatementKind::StorageLive(_) |
             StatementKind::StorageDead(_) |
             StatementKind::InlineAsm { .. } |
@@ -1177,7 +1183,12 @@ impl<'a, 'gcx, 'tcx> TypeChecker<'a, 'gcx, 'tcx> {
 pub struct TypeckMir;
 
 impl MirPass for TypeckMir {
-    fn run_pass<'a, 'tcx>(&self, tcx: TyCtxt<'a, 'tcx, 'tcx>, src: MirSource, mir: &mut Mir<'tcx>) {
+    fn run_pass_in_context<'a, 'tcx>(&self,
+                                     tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                     _context: &MirOptContext<'tcx>,
+                                     src: MirSource,
+                                     mir: &mut Mir<'tcx>)
+                                     -> bool {
         let def_id = src.def_id;
         let id = tcx.hir.as_local_node_id(def_id).unwrap();
         debug!("run_pass: {:?}", def_id);
@@ -1185,7 +1196,7 @@ impl MirPass for TypeckMir {
         if tcx.sess.err_count() > 0 {
             // compiling a broken program can obviously result in a
             // broken MIR, so try not to report duplicate errors.
-            return;
+            return false;
         }
         let param_env = tcx.param_env(def_id);
         tcx.infer_ctxt().enter(|infcx| {
@@ -1194,6 +1205,7 @@ impl MirPass for TypeckMir {
             // For verification purposes, we just ignore the resulting
             // region constraint sets. Not our problem. =)
         });
+        false
     }
 }
 
@@ -10,11 +10,20 @@
 
 
 use rustc::ty::{self, TyCtxt};
+use rustc::ty::layout::LayoutOf;
 use rustc::mir::*;
+use rustc::mir::tcx::LvalueTy;
+
+use std::cmp;
 
 /// Return `true` if this lvalue is allowed to be less aligned
 /// than its containing struct (because it is within a packed
 /// struct).
+///
+/// The whole projection chain is considered, so a packed struct
+/// anywhere between the reference and its innermost base (or
+/// dereference) makes the lvalue suspect, unless the layouts along
+/// the chain show that its address is aligned anyway.
 pub fn is_disaligned<'a, 'tcx, L>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
                                   local_decls: &L,
                                   param_env: ty::ParamEnv<'tcx>,
@@ -29,11 +38,23 @@ pub fn is_disaligned<'a, 'tcx, L>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     }
 
     let ty = lvalue.ty(local_decls, tcx).to_ty(tcx);
-    match tcx.layout_raw(param_env.and(ty)) {
+    let required = match tcx.layout_raw(param_env.and(ty)) {
         Ok(layout) if layout.align.abi() == 1 => {
             // if the alignment is 1, the type can't be further
             // disaligned.
             debug!("is_disaligned({:?}) - align = 1", lvalue);
+            return false
+        }
+        Ok(layout) => layout.align.abi(),
+        Err(_) => {
+            debug!("is_disaligned({:?}) - unknown layout", lvalue);
+            return true
+        }
+    };
+
+    match known_alignment(tcx, local_decls, param_env, lvalue) {
+        Some(align) if align >= required => {
+            debug!("is_disaligned({:?}) - known to be aligned to {}", lvalue, align);
             false
         }
         _ => {
@@ -43,10 +64,84 @@ pub fn is_disaligned<'a, 'tcx, L>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     }
 }
 
-fn is_within_packed<'a, 'tcx, L>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
-                                 local_decls: &L,
-                                 lvalue: &Lvalue<'tcx>)
-                                 -> bool
+/// Returns the alignment that the address of `lvalue` is known to have,
+/// working outwards from its innermost base or dereference, which are
+/// ABI-aligned, through the field offsets of each intermediate layout.
+/// Returns `None` if some layout along the way is not known.
+fn known_alignment<'a, 'tcx, L>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                local_decls: &L,
+                                param_env: ty::ParamEnv<'tcx>,
+                                lvalue: &Lvalue<'tcx>)
+                                -> Option<u64>
+    where L: HasLocalDecls<'tcx>
+{
+    let abi_align = |ty: ty::Ty<'tcx>| {
+        (tcx, param_env).layout_of(ty).ok().map(|layout| layout.align.abi())
+    };
+    let (base, elem) = match *lvalue {
+        Lvalue::Local(..) | Lvalue::Static(..) => {
+            return abi_align(lvalue.ty(local_decls, tcx).to_ty(tcx))
+        }
+        Lvalue::Projection(box Projection { ref base, ref elem }) => (base, elem),
+    };
+
+    match *elem {
+        ProjectionElem::Deref => abi_align(lvalue.ty(local_decls, tcx).to_ty(tcx)),
+        ProjectionElem::Downcast(..) => known_alignment(tcx, local_decls, param_env, base),
+        ProjectionElem::Field(field, _) => {
+            let base_align = match known_alignment(tcx, local_decls, param_env, base) {
+                Some(align) => align,
+                None => return None,
+            };
+            let base_ty = base.ty(local_decls, tcx);
+            let layout = match (tcx, param_env).layout_of(base_ty.to_ty(tcx)) {
+                Ok(layout) => layout,
+                Err(_) => return None,
+            };
+            let layout = match base_ty {
+                LvalueTy::Downcast { variant_index, .. } => {
+                    layout.for_variant((tcx, param_env), variant_index)
+                }
+                LvalueTy::Ty { .. } => layout,
+            };
+            let offset = layout.fields.offset(field.index()).bytes();
+            Some(offset_alignment(base_align, offset))
+        }
+        ProjectionElem::Index(..) |
+        ProjectionElem::ConstantIndex { .. } |
+        ProjectionElem::Subslice { .. } => {
+            // elements are at multiples of the element size
+            let base_align = match known_alignment(tcx, local_decls, param_env, base) {
+                Some(align) => align,
+                None => return None,
+            };
+            let elem_ty = match base.ty(local_decls, tcx).to_ty(tcx).builtin_index() {
+                Some(elem_ty) => elem_ty,
+                None => return None,
+            };
+            match (tcx, param_env).layout_of(elem_ty) {
+                Ok(layout) => Some(offset_alignment(base_align, layout.size.bytes())),
+                Err(_) => None,
+            }
+        }
+    }
+}
+
+/// The alignment of an address `offset` bytes past one aligned to `align`.
+fn offset_alignment(align: u64, offset: u64) -> u64 {
+    if offset == 0 {
+        align
+    } else {
+        cmp::min(align, 1u64 << offset.trailing_zeros())
+    }
+}
+
+/// Returns whether `lvalue` is a field of a `#[repr(packed)]` struct, without
+/// an intermediate dereference.
+pub fn is_within_packed<'a, 'gcx, 'tcx, L>(tcx: TyCtxt<'a, 'gcx, 'tcx>,
+                                           local_decls: &L,
+                                           lvalue: &Lvalue<'tcx>)
+                                           -> bool
     where L: HasLocalDecls<'tcx>
 {
     let mut lvalue = lvalue;
@@ -10,24 +10,40 @@
 
 //! Def-use analysis.
 
-use rustc::mir::{Local, Location, Mir};
+use rustc::mir::{BasicBlock, Local, Location, Mir, Statement};
 use rustc::mir::visit::{LvalueContext, MutVisitor, Visitor};
-use rustc_data_structures::indexed_vec::IndexVec;
+use rustc_data_structures::indexed_vec::{Idx, IndexVec};
 use std::marker::PhantomData;
 use std::mem;
 use std::slice;
 use std::iter;
+use util::inline_asm::AsmEffects;
 
+/// The defs and uses of every local of a MIR body.
+///
+/// They are kept in a single vector grouped by local, so analyzing a body
+/// doesn't allocate per local, and `clear_and_analyze` reuses the storage
+/// from the previous run.
 pub struct DefUseAnalysis<'tcx> {
-    info: IndexVec<Local, Info<'tcx>>,
+    /// The defs and uses of all locals, grouped by local, and in visiting
+    /// order within each group.
+    defs_and_uses: Vec<Use<'tcx>>,
+
+    /// The end of the group of each local in `defs_and_uses`. Each group
+    /// starts where the one of the previous local ends.
+    ends: IndexVec<Local, usize>,
+
+    /// The defs and uses in visiting order, kept between runs to reuse
+    /// its allocation.
+    found: Vec<(Local, Use<'tcx>)>,
 }
 
-#[derive(Clone)]
-pub struct Info<'tcx> {
-    pub defs_and_uses: Vec<Use<'tcx>>,
+#[derive(Copy, Clone)]
+pub struct Info<'a, 'tcx: 'a> {
+    pub defs_and_uses: &'a [Use<'tcx>],
 }
 
-#[derive(Clone)]
+#[derive(Copy, Clone)]
 pub struct Use<'tcx> {
     pub context: LvalueContext<'tcx>,
     pub location: Location,
@@ -36,35 +52,60 @@ pub struct Use<'tcx> {
 impl<'tcx> DefUseAnalysis<'tcx> {
     pub fn new(mir: &Mir<'tcx>) -> DefUseAnalysis<'tcx> {
         DefUseAnalysis {
-            info: IndexVec::from_elem_n(Info::new(), mir.local_decls.len()),
+            defs_and_uses: vec![],
+            ends: IndexVec::from_elem_n(0, mir.local_decls.len()),
+            found: vec![],
         }
     }
 
-    pub fn analyze(&mut self, mir: &Mir<'tcx>) {
-        self.clear();
-
+    /// Forgets the results of any previous run and analyzes `mir`.
+    pub fn clear_and_analyze(&mut self, mir: &Mir<'tcx>) {
         let mut finder = DefUseFinder {
-            info: mem::replace(&mut self.info, IndexVec::new()),
+            found: mem::replace(&mut self.found, vec![]),
         };
+        finder.found.clear();
         finder.visit_mir(mir);
-        self.info = finder.info
-    }
+        self.found = finder.found;
+
+        // Group the defs and uses by local with a counting sort, which keeps
+        // the visiting order within each group. `ends[local]` holds the
+        // number of entries of `local`, then the start of its group, and is
+        // bumped past each entry placed in the group.
+        self.ends.raw.clear();
+        self.ends.raw.resize(mir.local_decls.len(), 0);
+        for &(local, _) in &self.found {
+            self.ends[local] += 1;
+        }
+        let mut start = 0;
+        for end in self.ends.iter_mut() {
+            let count = *end;
+            *end = start;
+            start += count;
+        }
 
-    fn clear(&mut self) {
-        for info in &mut self.info {
-            info.clear();
+        self.defs_and_uses.clear();
+        self.defs_and_uses.extend(self.found.iter().map(|&(_, lvalue_use)| lvalue_use));
+        for &(local, lvalue_use) in &self.found {
+            self.defs_and_uses[self.ends[local]] = lvalue_use;
+            self.ends[local] += 1;
         }
     }
 
-    pub fn local_info(&self, local: Local) -> &Info<'tcx> {
-        &self.info[local]
+    pub fn local_info<'a>(&'a self, local: Local) -> Info<'a, 'tcx> {
+        let start = match local.index() {
+            0 => 0,
+            index => self.ends[Local::new(index - 1)],
+        };
+        Info {
+            defs_and_uses: &self.defs_and_uses[start..self.ends[local]],
+        }
     }
 
     fn mutate_defs_and_uses<F>(&self, local: Local, mir: &mut Mir<'tcx>, mut callback: F)
                                where F: for<'a> FnMut(&'a mut Local,
                                                       LvalueContext<'tcx>,
                                                       Location) {
-        for lvalue_use in &self.info[local].defs_and_uses {
+        for lvalue_use in self.local_info(local).defs_and_uses {
             MutateUseVisitor::new(local,
                                   &mut callback,
                                   mir).visit_location(mir, lvalue_use.location)
@@ -81,32 +122,32 @@ impl<'tcx> DefUseAnalysis<'tcx> {
 }
 
 struct DefUseFinder<'tcx> {
-    info: IndexVec<Local, Info<'tcx>>,
+    found: Vec<(Local, Use<'tcx>)>,
 }
 
 impl<'tcx> Visitor<'tcx> for DefUseFinder<'tcx> {
+    fn visit_statement(&mut self,
+                       block: BasicBlock,
+                       statement: &Statement<'tcx>,
+                       location: Location) {
+        match AsmEffects::of(&statement.kind) {
+            Some(effects) => effects.visit_with(self, location),
+            None => self.super_statement(block, statement, location),
+        }
+    }
+
     fn visit_local(&mut self,
                    &local: &Local,
                    context: LvalueContext<'tcx>,
                    location: Location) {
-        self.info[local].defs_and_uses.push(Use {
+        self.found.push((local, Use {
             context,
             location,
-        });
+        }));
     }
 }
 
-impl<'tcx> Info<'tcx> {
-    fn new() -> Info<'tcx> {
-        Info {
-            defs_and_uses: vec![],
-        }
-    }
-
-    fn clear(&mut self) {
-        self.defs_and_uses.clear();
-    }
-
+impl<'a, 'tcx> Info<'a, 'tcx> {
     pub fn def_count(&self) -> usize {
         self.defs_and_uses.iter().filter(|lvalue_use| lvalue_use.context.is_mutating_use()).count()
     }
@@ -117,7 +158,7 @@ impl<'tcx> Info<'tcx> {
 
     pub fn defs_not_including_drop(
         &self,
-    ) -> iter::Filter<slice::Iter<Use<'tcx>>, fn(&&Use<'tcx>) -> bool> {
+    ) -> iter::Filter<slice::Iter<'a, Use<'tcx>>, fn(&&Use<'tcx>) -> bool> {
         self.defs_and_uses.iter().filter(|lvalue_use| {
             lvalue_use.context.is_mutating_use() && !lvalue_use.context.is_drop()
         })
@@ -0,0 +1,135 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+use rustc::ty::{self, TyCtxt};
+use rustc::mir::*;
+use rustc::mir::tcx::LvalueTy;
+
+/// Renders `lvalue` the way it would be written in source, for use in
+/// diagnostics: fields are named where the type has names, a closure's
+/// upvars are shown as the variables they capture, and dereferences are
+/// left implicit where a field or index access would autoderef anyway.
+///
+/// Returns `None` if `lvalue` is rooted in a temporary.
+pub fn describe_lvalue<'a, 'gcx, 'tcx>(tcx: TyCtxt<'a, 'gcx, 'tcx>,
+                                       mir: &Mir<'tcx>,
+                                       lvalue: &Lvalue<'tcx>)
+                                       -> Option<String> {
+    LvalueDescriber { tcx, mir }.describe(lvalue, false)
+}
+
+struct LvalueDescriber<'a, 'gcx: 'tcx, 'tcx: 'a> {
+    tcx: TyCtxt<'a, 'gcx, 'tcx>,
+    mir: &'a Mir<'tcx>,
+}
+
+impl<'a, 'gcx, 'tcx> LvalueDescriber<'a, 'gcx, 'tcx> {
+    /// `projected` is true if a field or element of `lvalue` is being
+    /// described, so that dereferences of references and boxes can be
+    /// elided.
+    fn describe(&self, lvalue: &Lvalue<'tcx>, projected: bool) -> Option<String> {
+        let proj = match *lvalue {
+            Lvalue::Local(local) => {
+                return self.mir.local_decls[local].name.map(|name| name.to_string());
+            }
+            Lvalue::Static(ref static_) => {
+                return Some(self.tcx.item_name(static_.def_id).to_string());
+            }
+            Lvalue::Projection(ref proj) => proj,
+        };
+
+        match proj.elem {
+            ProjectionElem::Deref => {
+                let through_raw_ptr = proj.base.ty(self.mir, self.tcx).to_ty(self.tcx)
+                    .is_unsafe_ptr();
+                let autoderef = projected && !through_raw_ptr;
+                if let Some(field) = self.upvar_field(&proj.base) {
+                    let upvar = &self.mir.upvar_decls[field.index()];
+                    let name = upvar.debug_name.to_string();
+                    // a by-ref capture is dereferenced implicitly in the
+                    // closure body
+                    return Some(if upvar.by_ref || autoderef {
+                        name
+                    } else {
+                        format!("*{}", name)
+                    });
+                }
+                let base = match self.describe(&proj.base, autoderef) {
+                    Some(base) => base,
+                    None => return None,
+                };
+                Some(if autoderef {
+                    base
+                } else if projected {
+                    format!("(*{})", base)
+                } else {
+                    format!("*{}", base)
+                })
+            }
+            ProjectionElem::Field(field, _) => {
+                if let Some(field) = self.upvar_field(lvalue) {
+                    return Some(self.mir.upvar_decls[field.index()].debug_name.to_string());
+                }
+                self.describe(&proj.base, true).map(|base| {
+                    format!("{}.{}", base, self.field_name(&proj.base, field))
+                })
+            }
+            ProjectionElem::Downcast(..) => self.describe(&proj.base, projected),
+            ProjectionElem::Index(index) => {
+                self.describe(&proj.base, true).map(|base| {
+                    match self.mir.local_decls[index].name {
+                        Some(name) => format!("{}[{}]", base, name),
+                        None => format!("{}[..]", base),
+                    }
+                })
+            }
+            ProjectionElem::ConstantIndex { .. } |
+            ProjectionElem::Subslice { .. } => {
+                // the exact elements don't matter to the user, as borrowing
+                // one element of an array conflicts with using any other
+                self.describe(&proj.base, true).map(|base| format!("{}[..]", base))
+            }
+        }
+    }
+
+    /// If `lvalue` is a field of the closure environment, returns the upvar
+    /// it holds.
+    fn upvar_field(&self, lvalue: &Lvalue<'tcx>) -> Option<Field> {
+        match *lvalue {
+            Lvalue::Projection(ref proj) => match proj.elem {
+                ProjectionElem::Field(field, _)
+                    if proj.base.ty(self.mir, self.tcx).to_ty(self.tcx).is_closure() =>
+                {
+                    Some(field)
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The name of `field` of `base`: the declared name for structs and
+    /// enum variants, or its index for tuples and enums that have not
+    /// been downcast.
+    fn field_name(&self, base: &Lvalue<'tcx>, field: Field) -> String {
+        match base.ty(self.mir, self.tcx) {
+            LvalueTy::Downcast { adt_def, variant_index, .. } => {
+                adt_def.variants[variant_index].fields[field.index()].name.to_string()
+            }
+            LvalueTy::Ty { ty } => match ty.sty {
+                ty::TyAdt(adt_def, _) if !adt_def.is_enum() => {
+                    adt_def.struct_variant().fields[field.index()].name.to_string()
+                }
+                _ => field.index().to_string(),
+            },
+        }
+    }
+}
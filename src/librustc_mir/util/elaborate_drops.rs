@@ -530,7 +530,9 @@ impl<'l, 'b, 'tcx, D> DropCtxt<'l, 'b, 'tcx, D>
         let result = BasicBlockData {
             statements: vec![self.assign(
                 &Lvalue::Local(ref_lvalue),
-                Rvalue::Ref(tcx.types.re_erased, BorrowKind::Mut, self.lvalue.clone())
+                Rvalue::Ref(tcx.types.re_erased,
+                            BorrowKind::Mut { allow_two_phase_borrow: false },
+                            self.lvalue.clone())
             )],
             terminator: Some(Terminator {
                 kind: TerminatorKind::Call {
@@ -548,28 +550,20 @@ impl<'l, 'b, 'tcx, D> DropCtxt<'l, 'b, 'tcx, D>
     }
 
     /// create a loop that drops an array:
-    ///
-
     ///
     /// loop-block:
-    ///    can_go = cur == length_or_end
+    ///    can_go = cur == length
     ///    if can_go then succ else drop-block
     /// drop-block:
-    ///    if ptr_based {
-    ///        ptr = cur
-    ///        cur = cur.offset(1)
-    ///    } else {
-    ///        ptr = &mut LV[cur]
-    ///        cur = cur + 1
-    ///    }
+    ///    ptr = &mut LV[cur]
+    ///    cur = cur + 1
     ///    drop(ptr)
     fn drop_loop(&mut self,
                  succ: BasicBlock,
                  cur: Local,
-                 length_or_end: &Lvalue<'tcx>,
+                 length: &Lvalue<'tcx>,
                  ety: Ty<'tcx>,
-                 unwind: Unwind,
-                 ptr_based: bool)
+                 unwind: Unwind)
                  -> BasicBlock
     {
         let copy = |lv: &Lvalue<'tcx>| Operand::Copy(lv.clone());
@@ -584,16 +578,11 @@ impl<'l, 'b, 'tcx, D> DropCtxt<'l, 'b, 'tcx, D>
         let can_go = &Lvalue::Local(self.new_temp(tcx.types.bool));
 
         let one = self.constant_usize(1);
-        let (ptr_next, cur_next) = if ptr_based {
-            (Rvalue::Use(copy(&Lvalue::Local(cur))),
-             Rvalue::BinaryOp(BinOp::Offset, copy(&Lvalue::Local(cur)), one))
-        } else {
-            (Rvalue::Ref(
-                 tcx.types.re_erased,
-                 BorrowKind::Mut,
-                 self.lvalue.clone().index(cur)),
-             Rvalue::BinaryOp(BinOp::Add, copy(&Lvalue::Local(cur)), one))
-        };
+        let ptr_next = Rvalue::Ref(
+            tcx.types.re_erased,
+            BorrowKind::Mut { allow_two_phase_borrow: false },
+            self.lvalue.clone().index(cur));
+        let cur_next = Rvalue::BinaryOp(BinOp::Add, copy(&Lvalue::Local(cur)), one);
 
         let drop_block = BasicBlockData {
             statements: vec![
@@ -613,7 +602,7 @@ impl<'l, 'b, 'tcx, D> DropCtxt<'l, 'b, 'tcx, D>
             statements: vec![
                 self.assign(can_go, Rvalue::BinaryOp(BinOp::Eq,
                                                      copy(&Lvalue::Local(cur)),
-                                                     copy(length_or_end)))
+                                                     copy(length)))
             ],
             is_cleanup: unwind.is_cleanup(),
             terminator: Some(Terminator {
@@ -632,103 +621,40 @@ impl<'l, 'b, 'tcx, D> DropCtxt<'l, 'b, 'tcx, D>
         loop_block
     }
 
+    // create a pair of drop-loops of `lvalue`, which drops its contents
+    // even in the case of 1 panic. Both loops count an index up to the
+    // length of `lvalue`, which is computed once before entering them, so
+    // that the trip count of the loops is visible to MIR optimizations.
     fn open_drop_for_array(&mut self, ety: Ty<'tcx>) -> BasicBlock {
         debug!("open_drop_for_array({:?})", ety);
-
-        // if size_of::<ety>() == 0 {
-        //     index_based_loop
-        // } else {
-        //     ptr_based_loop
-        // }
-
         let tcx = self.tcx();
 
-        let move_ = |lv: &Lvalue<'tcx>| Operand::Move(lv.clone());
-        let size = &Lvalue::Local(self.new_temp(tcx.types.usize));
-        let size_is_zero = &Lvalue::Local(self.new_temp(tcx.types.bool));
-        let base_block = BasicBlockData {
-            statements: vec![
-                self.assign(size, Rvalue::NullaryOp(NullOp::SizeOf, ety)),
-                self.assign(size_is_zero, Rvalue::BinaryOp(BinOp::Eq,
-                                                           move_(size),
-                                                           self.constant_usize(0)))
-            ],
-            is_cleanup: self.unwind.is_cleanup(),
-            terminator: Some(Terminator {
-                source_info: self.source_info,
-                kind: TerminatorKind::if_(
-                    tcx,
-                    move_(size_is_zero),
-                    self.drop_loop_pair(ety, false),
-                    self.drop_loop_pair(ety, true)
-                )
-            })
-        };
-        self.elaborator.patch().new_block(base_block)
-    }
-
-    // create a pair of drop-loops of `lvalue`, which drops its contents
-    // even in the case of 1 panic. If `ptr_based`, create a pointer loop,
-    // otherwise create an index loop.
-    fn drop_loop_pair(&mut self, ety: Ty<'tcx>, ptr_based: bool) -> BasicBlock {
-        debug!("drop_loop_pair({:?}, {:?})", ety, ptr_based);
-        let tcx = self.tcx();
-        let iter_ty = if ptr_based {
-            tcx.mk_mut_ptr(ety)
-        } else {
-            tcx.types.usize
-        };
-
-        let cur = self.new_temp(iter_ty);
+        let cur = self.new_temp(tcx.types.usize);
         let length = Lvalue::Local(self.new_temp(tcx.types.usize));
-        let length_or_end = if ptr_based {
-            Lvalue::Local(self.new_temp(iter_ty))
-        } else {
-            length.clone()
-        };
 
         let unwind = self.unwind.map(|unwind| {
             self.drop_loop(unwind,
                            cur,
-                           &length_or_end,
+                           &length,
                            ety,
-                           Unwind::InCleanup,
-                           ptr_based)
+                           Unwind::InCleanup)
         });
 
         let succ = self.succ; // FIXME(#6393)
         let loop_block = self.drop_loop(
             succ,
             cur,
-            &length_or_end,
+            &length,
             ety,
-            unwind,
-            ptr_based);
+            unwind);
 
-        let cur = Lvalue::Local(cur);
+        // length = Len(LV)
+        // index = 0
         let zero = self.constant_usize(0);
-        let mut drop_block_stmts = vec![];
-        drop_block_stmts.push(self.assign(&length, Rvalue::Len(self.lvalue.clone())));
-        if ptr_based {
-            let tmp_ty = tcx.mk_mut_ptr(self.lvalue_ty(self.lvalue));
-            let tmp = Lvalue::Local(self.new_temp(tmp_ty));
-            // tmp = &LV;
-            // cur = tmp as *mut T;
-            // end = Offset(cur, len);
-            drop_block_stmts.push(self.assign(&tmp, Rvalue::Ref(
-                tcx.types.re_erased, BorrowKind::Mut, self.lvalue.clone()
-            )));
-            drop_block_stmts.push(self.assign(&cur, Rvalue::Cast(
-                CastKind::Misc, Operand::Move(tmp.clone()), iter_ty
-            )));
-            drop_block_stmts.push(self.assign(&length_or_end,
-                Rvalue::BinaryOp(BinOp::Offset,
-                     Operand::Copy(cur.clone()), Operand::Move(length.clone())
-            )));
-        } else {
-            // index = 0 (length already pushed)
-            drop_block_stmts.push(self.assign(&cur, Rvalue::Use(zero)));
-        }
+        let drop_block_stmts = vec![
+            self.assign(&length, Rvalue::Len(self.lvalue.clone())),
+            self.assign(&Lvalue::Local(cur), Rvalue::Use(zero)),
+        ];
         let drop_block = self.elaborator.patch().new_block(BasicBlockData {
             statements: drop_block_stmts,
             is_cleanup: unwind.is_cleanup(),
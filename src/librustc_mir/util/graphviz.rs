@@ -121,12 +121,14 @@ fn write_node<W: Write>(block: BasicBlock, mir: &Mir, w: &mut W) -> io::Result<(
 }
 
 /// Write graphviz DOT edges with labels between the given basic block and all of its successors.
+/// The edges into cleanup blocks are dashed.
 fn write_edges<W: Write>(source: BasicBlock, mir: &Mir, w: &mut W) -> io::Result<()> {
     let terminator = mir[source].terminator();
     let labels = terminator.kind.fmt_successor_labels();
 
     for (&target, label) in terminator.successors().iter().zip(labels) {
-        writeln!(w, r#"    {} -> {} [label="{}"];"#, node(source), node(target), label)?;
+        let style = if mir[target].is_cleanup { r#", style="dashed""# } else { "" };
+        writeln!(w, r#"    {} -> {} [label="{}"{}];"#, node(source), node(target), label, style)?;
     }
 
     Ok(())
@@ -0,0 +1,98 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A structured view of what an `InlineAsm` statement reads and writes.
+//!
+//! The MIR visitor visits every output of inline assembly as a `Store`,
+//! which is wrong in two ways: a read-write output, like `"+r"(x)`, also
+//! reads its lvalue, and an indirect output, like `"=*m"(p)`, only reads
+//! the pointer in its lvalue, and writes what it points to. Analyses that
+//! need to be exact about locals, like def-use and liveness, visit inline
+//! assembly through `AsmEffects::visit_with` instead.
+
+use rustc::mir::{Location, Lvalue, Operand, StatementKind};
+use rustc::mir::visit::{LvalueContext, Visitor};
+
+/// An output of an `InlineAsm` statement.
+#[derive(Copy, Clone, Debug)]
+pub struct AsmOutput<'a, 'tcx: 'a> {
+    pub lvalue: &'a Lvalue<'tcx>,
+    /// Whether the assembly reads the output before writing it.
+    pub is_rw: bool,
+    /// Whether the assembly writes through the pointer held by the lvalue,
+    /// rather than to the lvalue itself.
+    pub is_indirect: bool,
+}
+
+/// What an `InlineAsm` statement reads and writes.
+#[derive(Clone, Debug)]
+pub struct AsmEffects<'a, 'tcx: 'a> {
+    pub outputs: Vec<AsmOutput<'a, 'tcx>>,
+    pub inputs: &'a [Operand<'tcx>],
+    pub volatile: bool,
+    /// Whether the assembly has a `memory` clobber, i.e. may read or write
+    /// any memory.
+    pub clobbers_memory: bool,
+}
+
+impl<'a, 'tcx> AsmEffects<'a, 'tcx> {
+    /// The effects of `kind`, if it is an `InlineAsm` statement.
+    pub fn of(kind: &'a StatementKind<'tcx>) -> Option<AsmEffects<'a, 'tcx>> {
+        let (asm, outputs, inputs) = match *kind {
+            StatementKind::InlineAsm { ref asm, ref outputs, ref inputs } => {
+                (asm, outputs, inputs)
+            }
+            _ => return None,
+        };
+        Some(AsmEffects {
+            outputs: asm.outputs.iter().zip(outputs).map(|(output, lvalue)| AsmOutput {
+                lvalue,
+                is_rw: output.is_rw,
+                is_indirect: output.is_indirect,
+            }).collect(),
+            inputs,
+            volatile: asm.volatile,
+            clobbers_memory: asm.clobbers.iter().any(|clobber| &*clobber.as_str() == "memory"),
+        })
+    }
+
+    /// Whether the assembly may write to memory other than its direct
+    /// outputs.
+    pub fn writes_memory(&self) -> bool {
+        self.clobbers_memory || self.outputs.iter().any(|output| output.is_indirect)
+    }
+
+    /// Whether the assembly has effects besides writing its direct outputs,
+    /// so that it must be kept even when they are unused.
+    pub fn has_side_effects(&self) -> bool {
+        self.volatile || self.writes_memory()
+    }
+
+    /// Visits the outputs and inputs with the contexts they are actually
+    /// used in: an indirect output is a `Copy` of the pointer, and a
+    /// read-write output is a `Store` followed by an `Inspect`, like the
+    /// destination and the operands of an assignment, so that liveness
+    /// sees the output as live before the statement.
+    pub fn visit_with<V: Visitor<'tcx>>(&self, visitor: &mut V, location: Location) {
+        for output in &self.outputs {
+            if output.is_indirect {
+                visitor.visit_lvalue(output.lvalue, LvalueContext::Copy, location);
+            } else {
+                visitor.visit_lvalue(output.lvalue, LvalueContext::Store, location);
+                if output.is_rw {
+                    visitor.visit_lvalue(output.lvalue, LvalueContext::Inspect, location);
+                }
+            }
+        }
+        for input in self.inputs {
+            visitor.visit_operand(input, location);
+        }
+    }
+}
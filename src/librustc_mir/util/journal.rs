@@ -0,0 +1,122 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The journal of the changes made by MIR passes, written under
+//! `-Z mir-opt-journal=PATH` to help bisect miscompilations through the
+//! pipeline without diffing dumps.
+//!
+//! A pass creates a `Journal` for the body it runs on, and reports its
+//! changes to it: the statements it turns into nops, the locals whose uses
+//! it replaces by another local, and the blocks it adds. Each change is
+//! written as a line of JSON naming the pass and the body. A disabled
+//! journal ignores the changes.
+
+use rustc::mir::{BasicBlock, Local, Location};
+use rustc::session::Session;
+use rustc::ty::TyCtxt;
+use rustc_data_structures::indexed_vec::Idx;
+use rustc_serialize::json;
+use std::fs::File;
+use std::io::Write;
+use transform::MirSource;
+
+pub struct Journal<'a> {
+    sess: &'a Session,
+    /// The body the pass runs on, if the journal is enabled.
+    body: Option<JournalBody>,
+}
+
+struct JournalBody {
+    pass_name: String,
+    item: String,
+    promoted: Option<usize>,
+}
+
+#[derive(RustcEncodable)]
+struct JournalEntry<'a> {
+    pass: &'a str,
+    item: &'a str,
+    promoted: Option<usize>,
+    event: &'static str,
+    location: Option<String>,
+    block: Option<String>,
+    dest: Option<String>,
+    src: Option<String>,
+}
+
+impl<'a> Journal<'a> {
+    /// Creates the journal of the pass `pass_name` running on the body of
+    /// `source`.
+    pub fn new<'gcx, 'tcx>(tcx: TyCtxt<'a, 'gcx, 'tcx>,
+                           source: MirSource,
+                           pass_name: &str)
+                           -> Journal<'a> {
+        let body = if tcx.sess.opts.debugging_opts.mir_opt_journal.is_some() {
+            Some(JournalBody {
+                pass_name: pass_name.to_string(),
+                item: tcx.item_path_str(source.def_id),
+                promoted: source.promoted.map(|promoted| promoted.index()),
+            })
+        } else {
+            None
+        };
+        Journal { sess: tcx.sess, body }
+    }
+
+    /// Records that the statement at `location` was turned into a nop.
+    pub fn nopped(&self, location: Location) {
+        self.record("nopped", Some(format!("{:?}", location)), None, None);
+    }
+
+    /// Records that the uses of `dest` were replaced by uses of `src`.
+    pub fn replaced_local(&self, dest: Local, src: Local) {
+        self.record("replaced_local", None, None, Some((dest, src)));
+    }
+
+    /// Records that `block` was added.
+    pub fn new_block(&self, block: BasicBlock) {
+        self.record("new_block", None, Some(block), None);
+    }
+
+    fn record(&self,
+              event: &'static str,
+              location: Option<String>,
+              block: Option<BasicBlock>,
+              locals: Option<(Local, Local)>) {
+        let body = match self.body {
+            Some(ref body) => body,
+            None => return,
+        };
+        let entry = JournalEntry {
+            pass: &body.pass_name,
+            item: &body.item,
+            promoted: body.promoted,
+            event,
+            location,
+            block: block.map(|block| format!("{:?}", block)),
+            dest: locals.map(|(dest, _)| format!("{:?}", dest)),
+            src: locals.map(|(_, src)| format!("{:?}", src)),
+        };
+        let entry = json::encode(&entry).unwrap();
+
+        let mut file = self.sess.mir_opt_journal.borrow_mut();
+        if file.is_none() {
+            let path = self.sess.opts.debugging_opts.mir_opt_journal.as_ref().unwrap();
+            match File::create(path) {
+                Ok(created) => *file = Some(created),
+                Err(e) => {
+                    self.sess.fatal(&format!("could not create MIR optimization journal `{}`: {}",
+                                             path, e))
+                }
+            }
+        }
+        let _ = writeln!(file.as_mut().unwrap(), "{}", entry);
+    }
+}
@@ -0,0 +1,213 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The JSON serialization of MIR bodies written by `-Z dump-mir-json`,
+//! for tools that would otherwise have to parse the pretty-printed MIR.
+//!
+//! Types, statements and terminators are serialized as the strings the
+//! pretty printer shows for them, tagged with their kind. The schema may
+//! change, in which case `MIR_JSON_VERSION` is bumped.
+
+use rustc::mir::*;
+use rustc::ty::TyCtxt;
+use rustc_data_structures::indexed_vec::Idx;
+use rustc_serialize::json;
+use std::io::{self, Write};
+use syntax_pos::Span;
+use transform::MirSource;
+
+/// The version of the schema of the serialized bodies.
+pub const MIR_JSON_VERSION: u32 = 1;
+
+#[derive(RustcEncodable)]
+struct JsonDump {
+    version: u32,
+    item: String,
+    promoted: Option<usize>,
+    pass_name: String,
+    disambiguator: String,
+    body: JsonBody,
+}
+
+#[derive(RustcEncodable)]
+struct JsonBody {
+    span: JsonSpan,
+    arg_count: usize,
+    locals: Vec<JsonLocal>,
+    blocks: Vec<JsonBlock>,
+    promoted: Vec<JsonBody>,
+}
+
+#[derive(RustcEncodable)]
+struct JsonLocal {
+    /// `return`, `arg`, `var` or `temp`.
+    kind: &'static str,
+    name: Option<String>,
+    ty: String,
+    mutable: bool,
+}
+
+#[derive(RustcEncodable)]
+struct JsonBlock {
+    is_cleanup: bool,
+    statements: Vec<JsonStatement>,
+    terminator: JsonTerminator,
+}
+
+#[derive(RustcEncodable)]
+struct JsonStatement {
+    kind: &'static str,
+    text: String,
+    span: JsonSpan,
+}
+
+#[derive(RustcEncodable)]
+struct JsonTerminator {
+    kind: &'static str,
+    text: String,
+    span: JsonSpan,
+    successors: Vec<JsonSuccessor>,
+}
+
+#[derive(RustcEncodable)]
+struct JsonSuccessor {
+    label: String,
+    target: usize,
+}
+
+#[derive(RustcEncodable)]
+struct JsonSpan {
+    file: String,
+    line: usize,
+    col: usize,
+    end_line: usize,
+    end_col: usize,
+}
+
+/// Writes the JSON serialization of `mir`, the body of `source` at the
+/// pass `pass_name`, with its promoted bodies nested.
+pub fn write_mir_json<'a, 'gcx, 'tcx>(tcx: TyCtxt<'a, 'gcx, 'tcx>,
+                                      node_path: &str,
+                                      pass_name: &str,
+                                      disambiguator: &str,
+                                      source: MirSource,
+                                      mir: &Mir<'tcx>,
+                                      w: &mut Write)
+                                      -> io::Result<()> {
+    let dump = JsonDump {
+        version: MIR_JSON_VERSION,
+        item: node_path.to_string(),
+        promoted: source.promoted.map(|promoted| promoted.index()),
+        pass_name: pass_name.to_string(),
+        disambiguator: disambiguator.to_string(),
+        body: body(tcx, mir),
+    };
+    let json = json::encode(&dump).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    writeln!(w, "{}", json)
+}
+
+fn body(tcx: TyCtxt, mir: &Mir) -> JsonBody {
+    let locals = mir.local_decls.iter_enumerated().map(|(local, decl)| {
+        JsonLocal {
+            kind: match mir.local_kind(local) {
+                LocalKind::ReturnPointer => "return",
+                LocalKind::Arg => "arg",
+                LocalKind::Var => "var",
+                LocalKind::Temp => "temp",
+            },
+            name: decl.name.map(|name| name.to_string()),
+            ty: decl.ty.to_string(),
+            mutable: decl.mutability == Mutability::Mut,
+        }
+    }).collect();
+
+    let blocks = mir.basic_blocks().iter().map(|data| {
+        let statements = data.statements.iter().map(|statement| {
+            JsonStatement {
+                kind: statement_kind(&statement.kind),
+                text: format!("{:?}", statement),
+                span: span(tcx, statement.source_info.span),
+            }
+        }).collect();
+
+        let terminator = data.terminator();
+        let mut text = String::new();
+        terminator.kind.fmt_head(&mut text).unwrap();
+        let successors = terminator.successors().iter()
+            .zip(terminator.kind.fmt_successor_labels())
+            .map(|(target, label)| {
+                JsonSuccessor { label: label.into_owned(), target: target.index() }
+            })
+            .collect();
+
+        JsonBlock {
+            is_cleanup: data.is_cleanup,
+            statements,
+            terminator: JsonTerminator {
+                kind: terminator_kind(&terminator.kind),
+                text,
+                span: span(tcx, terminator.source_info.span),
+                successors,
+            },
+        }
+    }).collect();
+
+    JsonBody {
+        span: span(tcx, mir.span),
+        arg_count: mir.arg_count,
+        locals,
+        blocks,
+        promoted: mir.promoted.iter().map(|promoted| body(tcx, promoted)).collect(),
+    }
+}
+
+fn span(tcx: TyCtxt, span: Span) -> JsonSpan {
+    let codemap = tcx.sess.codemap();
+    let lo = codemap.lookup_char_pos(span.lo());
+    let hi = codemap.lookup_char_pos(span.hi());
+    JsonSpan {
+        file: lo.file.name.to_string(),
+        line: lo.line,
+        col: lo.col.0 + 1,
+        end_line: hi.line,
+        end_col: hi.col.0 + 1,
+    }
+}
+
+pub fn statement_kind(kind: &StatementKind) -> &'static str {
+    match *kind {
+        StatementKind::Assign(..) => "Assign",
+        StatementKind::SetDiscriminant { .. } => "SetDiscriminant",
+        StatementKind::StorageLive(_) => "StorageLive",
+        StatementKind::StorageDead(_) => "StorageDead",
+        StatementKind::InlineAsm { .. } => "InlineAsm",
+        StatementKind::Validate(..) => "Validate",
+        StatementKind::EndRegion(_) => "EndRegion",
+        StatementKind::Assume(_) => "Assume",
+        StatementKind::Nop => "Nop",
+    }
+}
+
+pub fn terminator_kind(kind: &TerminatorKind) -> &'static str {
+    match *kind {
+        TerminatorKind::Goto { .. } => "Goto",
+        TerminatorKind::SwitchInt { .. } => "SwitchInt",
+        TerminatorKind::Resume => "Resume",
+        TerminatorKind::Return => "Return",
+        TerminatorKind::Unreachable => "Unreachable",
+        TerminatorKind::Drop { .. } => "Drop",
+        TerminatorKind::DropAndReplace { .. } => "DropAndReplace",
+        TerminatorKind::Call { .. } => "Call",
+        TerminatorKind::Assert { .. } => "Assert",
+        TerminatorKind::Yield { .. } => "Yield",
+        TerminatorKind::GeneratorDrop => "GeneratorDrop",
+        TerminatorKind::FalseEdges { .. } => "FalseEdges",
+    }
+}
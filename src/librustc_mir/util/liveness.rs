@@ -32,15 +32,24 @@
 //! pre-existing references can be used to access the value (e.g. at movable
 //! generator yield points, all pre-existing references are invalidated, so this
 //! doesn't matter).
+//!
+//! The block-level fixpoint is computed by the dataflow framework, running
+//! `MaybeLiveLocals` as a backward analysis.
 
+use dataflow::{BitDenotation, BlockSets, DataflowAnalysis, DataflowOperator, Direction};
 use rustc::mir::*;
 use rustc::mir::visit::{LvalueContext, Visitor};
+use rustc::util::nodemap::FxHashMap;
+use rustc_data_structures::bitslice::BitwiseOperator;
 use rustc_data_structures::indexed_vec::{Idx, IndexVec};
-use rustc_data_structures::indexed_set::IdxSetBuf;
-use util::pretty::{dump_enabled, write_basic_block, write_mir_intro};
+use rustc_data_structures::indexed_set::{IdxSet, IdxSetBuf};
+use util::inline_asm::AsmEffects;
+use util::pretty::{dump_enabled, dump_path, write_basic_block, write_mir_intro};
 use rustc::ty::item_path;
-use std::path::{Path, PathBuf};
+use std::cell::RefCell;
 use std::fs;
+use std::mem;
+use std::rc::Rc;
 use rustc::ty::TyCtxt;
 use std::io::{self, Write};
 use transform::MirSource;
@@ -48,8 +57,8 @@ use transform::MirSource;
 pub type LocalSet = IdxSetBuf<Local>;
 
 /// This gives the result of the liveness analysis at the boundary of
-/// basic blocks. You can use `simulate_block` to obtain the
-/// intra-block results.
+/// basic blocks. You can use `statement_precise` and `live_in_block` to
+/// obtain the intra-block results.
 pub struct LivenessResult {
     /// Liveness mode in use when these results were computed.
     pub mode: LivenessMode,
@@ -60,6 +69,10 @@ pub struct LivenessResult {
     /// Live variables on exit to each basic block. This is equal to
     /// the union of the `ins` for each successor.
     pub outs: IndexVec<BasicBlock, LocalSet>,
+
+    /// The intra-block results of the blocks `statement_precise` was
+    /// asked about.
+    blocks: RefCell<FxHashMap<BasicBlock, Rc<BlockLiveness>>>,
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -84,46 +97,205 @@ pub struct LivenessMode {
 /// `mir`. The liveness mode `mode` determines what sorts of uses are
 /// considered to make a variable live (e.g., do drops count?).
 pub fn liveness_of_locals<'tcx>(mir: &Mir<'tcx>, mode: LivenessMode) -> LivenessResult {
-    let locals = mir.local_decls.len();
-    let def_use: IndexVec<_, _> = mir.basic_blocks()
-        .iter()
-        .map(|b| block(mode, b, locals))
-        .collect();
-
-    let mut ins: IndexVec<_, _> = mir.basic_blocks()
+    let dead_unwinds = IdxSetBuf::new_empty(mir.basic_blocks().len());
+    let analysis = DataflowAnalysis::new(mir, &dead_unwinds, MaybeLiveLocals::new(mir, mode));
+    let results = analysis.run();
+    let sets = results.sets();
+
+    // The sets on entry of a backward analysis are the ones on exit from
+    // each block.
+    let outs: IndexVec<_, _> = mir.basic_blocks()
         .indices()
-        .map(|_| LocalSet::new_empty(locals))
+        .map(|b| sets.on_entry_set_for(b.index()))
         .collect();
-    let mut outs = ins.clone();
-
-    let mut changed = true;
-    let mut bits = LocalSet::new_empty(locals);
-    while changed {
-        changed = false;
-
-        for b in mir.basic_blocks().indices().rev() {
-            // outs[b] = ∪ {ins of successors}
-            bits.clear();
-            for &successor in mir.basic_blocks()[b].terminator().successors().into_iter() {
-                bits.union(&ins[successor]);
-            }
-            outs[b].clone_from(&bits);
+    let ins = outs.iter_enumerated()
+        .map(|(b, out)| {
+            // ins[b] = use ∪ (outs[b] - def)
+            let mut bits = out.clone();
+            bits.subtract(&sets.kill_set_for(b.index()));
+            bits.union(&sets.gen_set_for(b.index()));
+            bits
+        })
+        .collect();
+
+    LivenessResult { mode, ins, outs, blocks: RefCell::new(FxHashMap()) }
+}
+
+/// The liveness of locals as a backward dataflow analysis: a use of a
+/// local sets its bit, and a definition clears it.
+pub struct MaybeLiveLocals<'a, 'tcx: 'a> {
+    mir: &'a Mir<'tcx>,
+    mode: LivenessMode,
+}
+
+impl<'a, 'tcx: 'a> MaybeLiveLocals<'a, 'tcx> {
+    pub fn new(mir: &'a Mir<'tcx>, mode: LivenessMode) -> Self {
+        MaybeLiveLocals { mir, mode }
+    }
 
-            // bits = use ∪ (bits - def)
-            def_use[b].apply(&mut bits);
+    pub fn mir(&self) -> &Mir<'tcx> {
+        self.mir
+    }
+}
+
+impl<'a, 'tcx> BitDenotation for MaybeLiveLocals<'a, 'tcx> {
+    type Idx = Local;
+    fn name() -> &'static str { "maybe_live_locals" }
+    fn direction() -> Direction { Direction::Backward }
+    fn bits_per_block(&self) -> usize {
+        self.mir.local_decls.len()
+    }
+
+    fn start_block_effect(&self, _sets: &mut BlockSets<Local>) {
+        // Nothing is live once the function is left
+    }
+
+    fn statement_effect(&self,
+                        sets: &mut BlockSets<Local>,
+                        loc: Location) {
+        let statement = &self.mir[loc.block].statements[loc.statement_index];
+        defs_uses(self.mode, self.mir, loc, statement).apply_to_sets(sets);
+    }
+
+    fn terminator_effect(&self,
+                         sets: &mut BlockSets<Local>,
+                         loc: Location) {
+        let terminator = &self.mir[loc.block].terminator;
+        defs_uses(self.mode, self.mir, loc, terminator).apply_to_sets(sets);
+    }
 
-            // update bits on entry and flag if they have changed
-            if ins[b] != bits {
-                ins[b].clone_from(&bits);
-                changed = true;
+    fn propagate_call_return(&self,
+                             _in_out: &mut IdxSet<Local>,
+                             _call_bb: BasicBlock,
+                             _dest_bb: BasicBlock,
+                             _dest_lval: &Lvalue) {
+        // The destination is already defined by the terminator effect, on
+        // both the return and the unwind edges.
+    }
+}
+
+impl<'a, 'tcx> BitwiseOperator for MaybeLiveLocals<'a, 'tcx> {
+    #[inline]
+    fn join(&self, pred1: usize, pred2: usize) -> usize {
+        pred1 | pred2 // live if live in any successor
+    }
+}
+
+impl<'a, 'tcx> DataflowOperator for MaybeLiveLocals<'a, 'tcx> {
+    #[inline]
+    fn bottom_value() -> bool {
+        false // bottom = dead
+    }
+}
+
+/// Returns the locals that have a borrow of (a part of) them taken
+/// anywhere in `mir`, and so could be used through a reference this
+/// analysis doesn't see.
+pub fn borrowed_locals<'tcx>(mir: &Mir<'tcx>) -> LocalSet {
+    let mut borrowed = BorrowedLocals(LocalSet::new_empty(mir.local_decls.len()));
+    borrowed.visit_mir(mir);
+    borrowed.0
+}
+
+struct BorrowedLocals(LocalSet);
+
+impl<'tcx> Visitor<'tcx> for BorrowedLocals {
+    fn visit_rvalue(&mut self, rvalue: &Rvalue<'tcx>, location: Location) {
+        if let Rvalue::Ref(_, _, ref lvalue) = *rvalue {
+            let mut lvalue = lvalue;
+            while let Lvalue::Projection(ref proj) = *lvalue {
+                lvalue = &proj.base;
+            }
+            if let Lvalue::Local(local) = *lvalue {
+                self.0.add(&local);
             }
         }
+        self.super_rvalue(rvalue, location);
     }
+}
+
+/// The locals live around a single statement or terminator.
+#[derive(Clone, Debug)]
+pub struct LiveAtLocation {
+    pub location: Location,
 
-    LivenessResult { mode, ins, outs }
+    /// Locals live on entry to the statement or terminator.
+    pub before: LocalSet,
+
+    /// Locals live on exit from the statement or terminator.
+    pub after: LocalSet,
 }
 
 impl LivenessResult {
+    /// Returns the locals live on entry to `block`.
+    pub fn live_on_entry(&self, block: BasicBlock) -> &LocalSet {
+        &self.ins[block]
+    }
+
+    /// Returns the locals live on exit from `block`.
+    pub fn live_on_exit(&self, block: BasicBlock) -> &LocalSet {
+        &self.outs[block]
+    }
+
+    /// Returns the locals live on entry to the statement or terminator at
+    /// `location`.
+    pub fn live_at<'tcx>(&self, mir: &Mir<'tcx>, location: Location) -> LocalSet {
+        self.statement_precise(mir, location.block).live_before(location).clone()
+    }
+
+    /// Whether `local` is live on entry to the statement or terminator at
+    /// `location`.
+    pub fn is_live_at<'tcx>(&self, mir: &Mir<'tcx>, local: Local, location: Location) -> bool {
+        self.statement_precise(mir, location.block).live_before(location).contains(&local)
+    }
+
+    /// Returns the location of the last use of `local` in `block` after
+    /// which its value is dead, if any. See `BlockLiveness::last_use_of`.
+    pub fn last_use_of<'tcx>(&self, mir: &Mir<'tcx>, local: Local, block: BasicBlock)
+                             -> Option<Location> {
+        self.statement_precise(mir, block).last_use_of(local)
+    }
+
+    /// Returns the locals live around each statement and the terminator of
+    /// `block`. The block is walked backward the first time it is asked
+    /// about, and the results are kept for the following queries; the
+    /// blocks that are never asked about cost nothing.
+    pub fn statement_precise<'tcx>(&self, mir: &Mir<'tcx>, block: BasicBlock)
+                                   -> Rc<BlockLiveness> {
+        if let Some(liveness) = self.blocks.borrow().get(&block) {
+            return liveness.clone();
+        }
+
+        let mut before = vec![];
+        self.simulate_block(mir, block, |_, bits| before.push(bits.clone()));
+        before.reverse();
+        let liveness = Rc::new(BlockLiveness {
+            block,
+            before,
+            exit: self.outs[block].clone(),
+        });
+        self.blocks.borrow_mut().insert(block, liveness.clone());
+        liveness
+    }
+
+    /// Returns the locals live around each statement and the terminator of
+    /// `block`, in execution order. The results are computed from the
+    /// block-level fixpoint when called, one block at a time.
+    pub fn live_in_block<'tcx>(&self, mir: &Mir<'tcx>, block: BasicBlock)
+                               -> Vec<LiveAtLocation> {
+        let mut results = vec![];
+        let mut after = self.outs[block].clone();
+        self.simulate_block(mir, block, |location, before| {
+            results.push(LiveAtLocation {
+                location,
+                before: before.clone(),
+                after: mem::replace(&mut after, before.clone()),
+            });
+        });
+        results.reverse();
+        results
+    }
+
     /// Walks backwards through the statements/terminator in the given
     /// basic block `block`.  At each point within `block`, invokes
     /// the callback `op` with the current location and the set of
@@ -146,7 +318,8 @@ impl LivenessResult {
             block,
             statement_index,
         };
-        let terminator_defs_uses = self.defs_uses(mir, terminator_location, &data.terminator);
+        let terminator_defs_uses =
+            defs_uses(self.mode, mir, terminator_location, &data.terminator);
         terminator_defs_uses.apply(&mut bits);
         callback(terminator_location, &bits);
 
@@ -157,35 +330,80 @@ impl LivenessResult {
                 block,
                 statement_index,
             };
-            let statement_defs_uses = self.defs_uses(mir, statement_location, statement);
+            let statement_defs_uses = defs_uses(self.mode, mir, statement_location, statement);
             statement_defs_uses.apply(&mut bits);
             callback(statement_location, &bits);
         }
 
         assert_eq!(bits, self.ins[block]);
     }
+}
 
-    fn defs_uses<'tcx, V>(&self, mir: &Mir<'tcx>, location: Location, thing: &V) -> DefsUses
-    where
-        V: MirVisitable<'tcx>,
-    {
-        let locals = mir.local_decls.len();
-        let mut visitor = DefsUsesVisitor {
-            mode: self.mode,
-            defs_uses: DefsUses {
-                defs: LocalSet::new_empty(locals),
-                uses: LocalSet::new_empty(locals),
-            },
-        };
+/// The locals live around each statement and the terminator of a block,
+/// as computed by `LivenessResult::statement_precise`.
+#[derive(Debug)]
+pub struct BlockLiveness {
+    block: BasicBlock,
+
+    /// Locals live on entry to each statement, then to the terminator.
+    before: Vec<LocalSet>,
 
-        // Visit the various parts of the basic block in reverse. If we go
-        // forward, the logic in `add_def` and `add_use` would be wrong.
-        thing.apply(location, &mut visitor);
+    /// Locals live on exit from the block.
+    exit: LocalSet,
+}
+
+impl BlockLiveness {
+    /// Returns the locals live on entry to the statement or terminator at
+    /// `location`.
+    pub fn live_before(&self, location: Location) -> &LocalSet {
+        assert_eq!(location.block, self.block);
+        &self.before[location.statement_index]
+    }
 
-        visitor.defs_uses
+    /// Returns the locals live on exit from the statement or terminator at
+    /// `location`.
+    pub fn live_after(&self, location: Location) -> &LocalSet {
+        assert_eq!(location.block, self.block);
+        self.before.get(location.statement_index + 1).unwrap_or(&self.exit)
+    }
+
+    /// Returns the location of the last use of `local` in the block after
+    /// which its value is dead, i.e. isn't read again before it is
+    /// redefined or the function returns, if any.
+    ///
+    /// In a block that defines and uses `local` twice, this is the second
+    /// use, unless `local` is live on exit, in which case it is the first.
+    pub fn last_use_of(&self, local: Local) -> Option<Location> {
+        (0..self.before.len()).rev()
+            .map(|statement_index| Location { block: self.block, statement_index })
+            .find(|&location| {
+                self.live_before(location).contains(&local) &&
+                    !self.live_after(location).contains(&local)
+            })
     }
 }
 
+/// Returns the locals defined and used by the statement or terminator
+/// `thing` at `location`.
+fn defs_uses<'tcx, V>(mode: LivenessMode, mir: &Mir<'tcx>, location: Location, thing: &V)
+                      -> DefsUses
+where
+    V: MirVisitable<'tcx>,
+{
+    let locals = mir.local_decls.len();
+    let mut visitor = DefsUsesVisitor {
+        mode,
+        defs_uses: DefsUses {
+            defs: LocalSet::new_empty(locals),
+            uses: LocalSet::new_empty(locals),
+        },
+    };
+
+    thing.apply(location, &mut visitor);
+
+    visitor.defs_uses
+}
+
 struct DefsUsesVisitor {
     mode: LivenessMode,
     defs_uses: DefsUses,
@@ -202,6 +420,17 @@ impl DefsUses {
         bits.subtract(&self.defs) | bits.union(&self.uses)
     }
 
+    /// Records the effect of `apply` in the gen and kill sets of a block,
+    /// after the effects of the statements that follow.
+    fn apply_to_sets(&self, sets: &mut BlockSets<Local>) {
+        for local in self.defs.iter() {
+            sets.kill(&local);
+        }
+        for local in self.uses.iter() {
+            sets.gen(&local);
+        }
+    }
+
     fn add_def(&mut self, index: Local) {
         // If it was used already in the block, remove that use
         // now that we found a definition.
@@ -233,6 +462,16 @@ impl DefsUses {
 }
 
 impl<'tcx> Visitor<'tcx> for DefsUsesVisitor {
+    fn visit_statement(&mut self,
+                       block: BasicBlock,
+                       statement: &Statement<'tcx>,
+                       location: Location) {
+        match AsmEffects::of(&statement.kind) {
+            Some(effects) => effects.visit_with(self, location),
+            None => self.super_statement(block, statement, location),
+        }
+    }
+
     fn visit_local(&mut self, &local: &Local, context: LvalueContext<'tcx>, _: Location) {
         match context {
             ///////////////////////////////////////////////////////////////////////////
@@ -298,30 +537,6 @@ impl<'tcx> Visitor<'tcx> for DefsUsesVisitor {
     }
 }
 
-fn block<'tcx>(mode: LivenessMode, b: &BasicBlockData<'tcx>, locals: usize) -> DefsUses {
-    let mut visitor = DefsUsesVisitor {
-        mode,
-        defs_uses: DefsUses {
-            defs: LocalSet::new_empty(locals),
-            uses: LocalSet::new_empty(locals),
-        },
-    };
-
-    let dummy_location = Location {
-        block: BasicBlock::new(0),
-        statement_index: 0,
-    };
-
-    // Visit the various parts of the basic block in reverse. If we go
-    // forward, the logic in `add_def` and `add_use` would be wrong.
-    visitor.visit_terminator(BasicBlock::new(0), b.terminator(), dummy_location);
-    for statement in b.statements.iter().rev() {
-        visitor.visit_statement(BasicBlock::new(0), statement, dummy_location);
-    }
-
-    visitor.defs_uses
-}
-
 trait MirVisitable<'tcx> {
     fn apply<V>(&self, location: Location, visitor: &mut V)
     where
@@ -371,14 +586,7 @@ fn dump_matched_mir_node<'a, 'tcx>(
     mir: &Mir<'tcx>,
     result: &LivenessResult,
 ) {
-    let mut file_path = PathBuf::new();
-    if let Some(ref file_dir) = tcx.sess.opts.debugging_opts.dump_mir_dir {
-        let p = Path::new(file_dir);
-        file_path.push(p);
-    };
-    let item_id = tcx.hir.as_local_node_id(source.def_id).unwrap();
-    let file_name = format!("rustc.node{}{}-liveness.mir", item_id, pass_name);
-    file_path.push(&file_name);
+    let file_path = dump_path(tcx, "mir", None, pass_name, &"liveness", source);
     let _ = fs::File::create(&file_path).and_then(|mut file| {
         writeln!(file, "// MIR local liveness analysis for `{}`", node_path)?;
         writeln!(file, "// source = {:?}", source)?;
@@ -398,17 +606,9 @@ pub fn write_mir_fn<'a, 'tcx>(
 ) -> io::Result<()> {
     write_mir_intro(tcx, src, mir, w)?;
     for block in mir.basic_blocks().indices() {
-        let print = |w: &mut Write, prefix, result: &IndexVec<BasicBlock, LocalSet>| {
-            let live: Vec<String> = mir.local_decls
-                .indices()
-                .filter(|i| result[block].contains(i))
-                .map(|i| format!("{:?}", i))
-                .collect();
-            writeln!(w, "{} {{{}}}", prefix, live.join(", "))
-        };
-        print(w, "   ", &result.ins)?;
+        writeln!(w, "    {}", locals_to_string(mir, result.live_on_entry(block)))?;
         write_basic_block(tcx, block, mir, &mut |_, _| Ok(()), w)?;
-        print(w, "   ", &result.outs)?;
+        writeln!(w, "    {}", locals_to_string(mir, result.live_on_exit(block)))?;
         if block.index() + 1 != mir.basic_blocks().len() {
             writeln!(w, "")?;
         }
@@ -417,3 +617,13 @@ pub fn write_mir_fn<'a, 'tcx>(
     writeln!(w, "}}")?;
     Ok(())
 }
+
+/// Formats the locals in `set` as `{_1, _3}`, in index order.
+pub fn locals_to_string(mir: &Mir, set: &LocalSet) -> String {
+    let locals: Vec<String> = mir.local_decls
+        .indices()
+        .filter(|i| set.contains(i))
+        .map(|i| format!("{:?}", i))
+        .collect();
+    format!("{{{}}}", locals.join(", "))
+}
@@ -0,0 +1,454 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Structural and type checks of a MIR body, meant to catch bugs in
+//! passes right after the pass that introduced them, rather than as LLVM
+//! assertions or miscompilations during trans.
+//!
+//! The validator checks that:
+//!
+//! - every block a terminator can jump to exists;
+//! - every local mentioned is declared in `local_decls`;
+//! - the rvalue of every assignment has the type of its destination, once
+//!   regions are erased and associated types normalized;
+//! - cleanup blocks only jump to cleanup blocks;
+//! - storage markers are balanced, i.e. no `StorageLive` is reached on a
+//!   local whose storage is live on every path to it, and no
+//!   `StorageDead` on a local whose storage is dead on every path.
+//!
+//! It runs after every pass under `-Z verify-mir`, and after the last
+//! pass of `optimized_mir` when rustc is built with debug assertions.
+
+use rustc::hir::def_id::DefId;
+use rustc::mir::{BasicBlock, BasicBlockData, Local, Location, Mir, StatementKind, START_BLOCK};
+use rustc::mir::visit::{LvalueContext, Visitor};
+use rustc::ty::{self, Ty, TyCtxt, TypeFoldable};
+use rustc_data_structures::bitvec::BitVector;
+use rustc_data_structures::indexed_vec::{Idx, IndexVec};
+use std::fmt;
+use util::liveness::LocalSet;
+
+/// A problem found by the `MirValidator`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MirValidationError<'tcx> {
+    /// A terminator jumps to a block that doesn't exist.
+    MissingBlock { location: Location, target: BasicBlock },
+
+    /// A local is mentioned that is not declared in `local_decls`.
+    UndeclaredLocal { location: Location, local: Local },
+
+    /// The rvalue of an assignment doesn't have the type of its destination.
+    MismatchedAssignment { location: Location, lvalue_ty: Ty<'tcx>, rvalue_ty: Ty<'tcx> },
+
+    /// A cleanup block jumps to a block that is not a cleanup block.
+    CleanupToNonCleanup { block: BasicBlock, target: BasicBlock },
+
+    /// `StorageLive` of a local whose storage is live on every path to it.
+    StorageAlreadyLive { location: Location, local: Local },
+
+    /// `StorageDead` of a local whose storage is dead on every path to it.
+    StorageAlreadyDead { location: Location, local: Local },
+}
+
+impl<'tcx> fmt::Display for MirValidationError<'tcx> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        use self::MirValidationError::*;
+
+        match *self {
+            MissingBlock { location, target } =>
+                write!(fmt, "{:?}: jump to missing block {:?}", location, target),
+            UndeclaredLocal { location, local } =>
+                write!(fmt, "{:?}: use of undeclared local {:?}", location, local),
+            MismatchedAssignment { location, lvalue_ty, rvalue_ty } =>
+                write!(fmt, "{:?}: assignment of a value of type `{}` to an lvalue of type `{}`",
+                       location, rvalue_ty, lvalue_ty),
+            CleanupToNonCleanup { block, target } =>
+                write!(fmt, "cleanup block {:?} jumps to non-cleanup block {:?}", block, target),
+            StorageAlreadyLive { location, local } =>
+                write!(fmt, "{:?}: `StorageLive` of {:?}, which is already live", location, local),
+            StorageAlreadyDead { location, local } =>
+                write!(fmt, "{:?}: `StorageDead` of {:?}, which is already dead", location, local),
+        }
+    }
+}
+
+pub struct MirValidator<'a, 'tcx: 'a> {
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    def_id: DefId,
+    mir: &'a Mir<'tcx>,
+    errors: Vec<MirValidationError<'tcx>>,
+}
+
+impl<'a, 'tcx> MirValidator<'a, 'tcx> {
+    /// Creates a validator for `mir`, the body of `def_id` or one of its
+    /// promoted constants.
+    pub fn new(tcx: TyCtxt<'a, 'tcx, 'tcx>, def_id: DefId, mir: &'a Mir<'tcx>) -> Self {
+        MirValidator { tcx, def_id, mir, errors: vec![] }
+    }
+
+    /// Checks the body, returning all the errors found.
+    ///
+    /// Types and storage markers are only checked once the body is known
+    /// to only mention existing blocks and locals.
+    pub fn validate(mut self) -> Vec<MirValidationError<'tcx>> {
+        let mir = self.mir;
+        let blocks = mir.basic_blocks();
+        check_targets(blocks, &mut self.errors);
+        check_locals(blocks, mir.local_decls.len(), &mut self.errors);
+        if self.errors.is_empty() {
+            check_cleanup_targets(blocks, &mut self.errors);
+            check_storage_markers(blocks, mir.local_decls.len(), &mut self.errors);
+            // Types can't be compared reliably once errors have been
+            // reported, as the body might be missing parts.
+            if !self.tcx.sess.has_errors() {
+                self.check_assignment_types();
+            }
+        }
+        self.errors
+    }
+
+    fn check_assignment_types(&mut self) {
+        let tcx = self.tcx;
+        let mir = self.mir;
+        let param_env = tcx.param_env(self.def_id);
+        for (block, data) in mir.basic_blocks().iter_enumerated() {
+            for (statement_index, statement) in data.statements.iter().enumerate() {
+                let (lvalue, rvalue) = match statement.kind {
+                    StatementKind::Assign(ref lvalue, ref rvalue) => (lvalue, rvalue),
+                    _ => continue,
+                };
+                let lvalue_ty = tcx.erase_regions(&lvalue.ty(mir, tcx).to_ty(tcx));
+                let rvalue_ty = tcx.erase_regions(&rvalue.ty(mir, tcx));
+                if lvalue_ty == rvalue_ty || may_be_subtype(lvalue_ty) ||
+                   may_be_subtype(rvalue_ty) {
+                    continue;
+                }
+                if (lvalue_ty.has_projections() || rvalue_ty.has_projections()) &&
+                   tcx.normalize_associated_type_in_env(&lvalue_ty, param_env) ==
+                       tcx.normalize_associated_type_in_env(&rvalue_ty, param_env) {
+                    continue;
+                }
+                self.errors.push(MirValidationError::MismatchedAssignment {
+                    location: Location { block, statement_index },
+                    lvalue_ty,
+                    rvalue_ty,
+                });
+            }
+        }
+    }
+}
+
+fn check_targets<'tcx>(blocks: &IndexVec<BasicBlock, BasicBlockData<'tcx>>,
+                       errors: &mut Vec<MirValidationError<'tcx>>) {
+    for (block, data) in blocks.iter_enumerated() {
+        let location = Location { block, statement_index: data.statements.len() };
+        for &target in data.terminator().successors().iter() {
+            if target.index() >= blocks.len() {
+                errors.push(MirValidationError::MissingBlock { location, target });
+            }
+        }
+    }
+}
+
+fn check_locals<'tcx>(blocks: &IndexVec<BasicBlock, BasicBlockData<'tcx>>,
+                      num_locals: usize,
+                      errors: &mut Vec<MirValidationError<'tcx>>) {
+    let mut finder = UndeclaredLocals { num_locals, errors };
+    for (block, data) in blocks.iter_enumerated() {
+        finder.visit_basic_block_data(block, data);
+    }
+}
+
+fn check_cleanup_targets<'tcx>(blocks: &IndexVec<BasicBlock, BasicBlockData<'tcx>>,
+                               errors: &mut Vec<MirValidationError<'tcx>>) {
+    for (block, data) in blocks.iter_enumerated() {
+        if !data.is_cleanup {
+            continue;
+        }
+        for &target in data.terminator().successors().iter() {
+            if !blocks[target].is_cleanup {
+                errors.push(MirValidationError::CleanupToNonCleanup { block, target });
+            }
+        }
+    }
+}
+
+/// Tracks, for every local with a `StorageLive` somewhere, whether its
+/// storage may be live and whether it may be dead at each point, and
+/// reports the markers that can't have any effect.
+fn check_storage_markers<'tcx>(blocks: &IndexVec<BasicBlock, BasicBlockData<'tcx>>,
+                               num_locals: usize,
+                               errors: &mut Vec<MirValidationError<'tcx>>) {
+    let mut marked = LocalSet::new_empty(num_locals);
+    for data in blocks {
+        for statement in &data.statements {
+            if let StatementKind::StorageLive(local) = statement.kind {
+                marked.add(&local);
+            }
+        }
+    }
+
+    // The storage of marked locals starts out dead.
+    let empty = StorageState {
+        maybe_live: LocalSet::new_empty(num_locals),
+        maybe_dead: LocalSet::new_empty(num_locals),
+    };
+    let mut on_entry = IndexVec::from_elem(empty, blocks);
+    on_entry[START_BLOCK].maybe_dead.union(&marked);
+
+    let mut dirty: Vec<BasicBlock> = blocks.indices().rev().collect();
+    while let Some(block) = dirty.pop() {
+        let mut state = on_entry[block].clone();
+        for statement in &blocks[block].statements {
+            state.apply(&statement.kind);
+        }
+        for &target in blocks[block].terminator().successors().iter() {
+            let entry = &mut on_entry[target];
+            let changed = entry.maybe_live.union(&state.maybe_live) |
+                          entry.maybe_dead.union(&state.maybe_dead);
+            if changed && !dirty.contains(&target) {
+                dirty.push(target);
+            }
+        }
+    }
+
+    // Unreachable blocks have no meaningful state on entry.
+    let mut reachable = BitVector::new(blocks.len());
+    let mut stack = vec![START_BLOCK];
+    while let Some(block) = stack.pop() {
+        if reachable.insert(block.index()) {
+            stack.extend(blocks[block].terminator().successors().iter().cloned());
+        }
+    }
+
+    for (block, data) in blocks.iter_enumerated() {
+        if !reachable.contains(block.index()) {
+            continue;
+        }
+        let mut state = on_entry[block].clone();
+        for (statement_index, statement) in data.statements.iter().enumerate() {
+            let location = Location { block, statement_index };
+            match statement.kind {
+                StatementKind::StorageLive(local)
+                    if marked.contains(&local) && !state.maybe_dead.contains(&local) => {
+                    errors.push(MirValidationError::StorageAlreadyLive { location, local });
+                }
+                StatementKind::StorageDead(local)
+                    if marked.contains(&local) && !state.maybe_live.contains(&local) => {
+                    errors.push(MirValidationError::StorageAlreadyDead { location, local });
+                }
+                _ => {}
+            }
+            state.apply(&statement.kind);
+        }
+    }
+}
+
+/// Whether `ty` may be assigned a value of another type through
+/// subtyping, even once regions are erased: late-bound regions are kept by
+/// region erasure, so `for<'a> fn(&'a u8)` remains distinct from
+/// `fn(&u8)`.
+fn may_be_subtype<'tcx>(ty: Ty<'tcx>) -> bool {
+    ty.walk().any(|ty| match ty.sty {
+        ty::TyFnPtr(_) | ty::TyDynamic(..) => true,
+        _ => false,
+    })
+}
+
+#[derive(Clone)]
+struct StorageState {
+    maybe_live: LocalSet,
+    maybe_dead: LocalSet,
+}
+
+impl StorageState {
+    fn apply(&mut self, kind: &StatementKind) {
+        match *kind {
+            StatementKind::StorageLive(local) => {
+                self.maybe_live.add(&local);
+                self.maybe_dead.remove(&local);
+            }
+            StatementKind::StorageDead(local) => {
+                self.maybe_dead.add(&local);
+                self.maybe_live.remove(&local);
+            }
+            _ => {}
+        }
+    }
+}
+
+struct UndeclaredLocals<'a, 'tcx: 'a> {
+    num_locals: usize,
+    errors: &'a mut Vec<MirValidationError<'tcx>>,
+}
+
+impl<'a, 'tcx> Visitor<'tcx> for UndeclaredLocals<'a, 'tcx> {
+    fn visit_local(&mut self, &local: &Local, _: LvalueContext<'tcx>, location: Location) {
+        if local.index() >= self.num_locals {
+            self.errors.push(MirValidationError::UndeclaredLocal { location, local });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustc::mir::{Lvalue, Operand, Rvalue, SourceInfo, Statement, Terminator, TerminatorKind};
+    use rustc::mir::ARGUMENT_VISIBILITY_SCOPE;
+    use syntax_pos::DUMMY_SP;
+
+    fn local(index: usize) -> Local {
+        Local::new(index)
+    }
+
+    fn block(statements: Vec<StatementKind<'static>>,
+             terminator: TerminatorKind<'static>)
+             -> BasicBlockData<'static> {
+        let source_info = SourceInfo { span: DUMMY_SP, scope: ARGUMENT_VISIBILITY_SCOPE };
+        let mut data = BasicBlockData::new(Some(Terminator { source_info, kind: terminator }));
+        data.statements = statements.into_iter()
+            .map(|kind| Statement { source_info, kind })
+            .collect();
+        data
+    }
+
+    fn goto(target: usize) -> TerminatorKind<'static> {
+        TerminatorKind::Goto { target: BasicBlock::new(target) }
+    }
+
+    fn at(block: usize, statement_index: usize) -> Location {
+        Location { block: BasicBlock::new(block), statement_index }
+    }
+
+    /// Runs the checks `MirValidator::validate` runs before the type checks,
+    /// on a body with `num_locals` locals.
+    fn validate(blocks: Vec<BasicBlockData<'static>>,
+                num_locals: usize)
+                -> Vec<MirValidationError<'static>> {
+        let blocks = blocks.into_iter().collect();
+        let mut errors = vec![];
+        check_targets(&blocks, &mut errors);
+        check_locals(&blocks, num_locals, &mut errors);
+        if errors.is_empty() {
+            check_cleanup_targets(&blocks, &mut errors);
+            check_storage_markers(&blocks, num_locals, &mut errors);
+        }
+        errors
+    }
+
+    #[test]
+    fn well_formed_body() {
+        let blocks = vec![
+            block(vec![StatementKind::StorageLive(local(1)),
+                       StatementKind::Assign(Lvalue::Local(local(0)),
+                                             Rvalue::Use(Operand::Copy(Lvalue::Local(local(1))))),
+                       StatementKind::StorageDead(local(1))],
+                  goto(1)),
+            block(vec![], TerminatorKind::Return),
+        ];
+        assert_eq!(validate(blocks, 2), vec![]);
+    }
+
+    #[test]
+    fn missing_block() {
+        let blocks = vec![
+            block(vec![StatementKind::Nop], goto(2)),
+            block(vec![], TerminatorKind::Return),
+        ];
+        assert_eq!(validate(blocks, 1), vec![
+            MirValidationError::MissingBlock { location: at(0, 1), target: BasicBlock::new(2) },
+        ]);
+    }
+
+    #[test]
+    fn undeclared_local() {
+        let blocks = vec![
+            block(vec![StatementKind::Assign(Lvalue::Local(local(0)),
+                                             Rvalue::Use(Operand::Move(Lvalue::Local(local(3)))))],
+                  TerminatorKind::Return),
+        ];
+        assert_eq!(validate(blocks, 2), vec![
+            MirValidationError::UndeclaredLocal { location: at(0, 0), local: local(3) },
+        ]);
+    }
+
+    #[test]
+    fn cleanup_to_non_cleanup() {
+        let mut cleanup = block(vec![], goto(1));
+        cleanup.is_cleanup = true;
+        let blocks = vec![
+            block(vec![], TerminatorKind::Drop {
+                location: Lvalue::Local(local(1)),
+                target: BasicBlock::new(1),
+                unwind: Some(BasicBlock::new(2)),
+            }),
+            block(vec![], TerminatorKind::Return),
+            cleanup,
+        ];
+        assert_eq!(validate(blocks, 2), vec![
+            MirValidationError::CleanupToNonCleanup {
+                block: BasicBlock::new(2),
+                target: BasicBlock::new(1),
+            },
+        ]);
+    }
+
+    #[test]
+    fn storage_already_live() {
+        let blocks = vec![
+            block(vec![StatementKind::StorageLive(local(1)),
+                       StatementKind::StorageLive(local(1))],
+                  TerminatorKind::Return),
+        ];
+        assert_eq!(validate(blocks, 2), vec![
+            MirValidationError::StorageAlreadyLive { location: at(0, 1), local: local(1) },
+        ]);
+    }
+
+    #[test]
+    fn storage_already_dead() {
+        let blocks = vec![
+            block(vec![StatementKind::StorageLive(local(1)),
+                       StatementKind::StorageDead(local(1))],
+                  goto(1)),
+            block(vec![StatementKind::StorageDead(local(1))], TerminatorKind::Return),
+        ];
+        assert_eq!(validate(blocks, 2), vec![
+            MirValidationError::StorageAlreadyDead { location: at(1, 0), local: local(1) },
+        ]);
+    }
+
+    #[test]
+    fn storage_live_on_some_paths() {
+        // Coming around the loop, the storage of `_1` is already live at
+        // its `StorageLive`, but not when coming from `bb0`.
+        let blocks = vec![
+            block(vec![], goto(1)),
+            block(vec![StatementKind::StorageLive(local(1))], goto(2)),
+            block(vec![], TerminatorKind::FalseEdges {
+                real_target: BasicBlock::new(3),
+                imaginary_targets: vec![BasicBlock::new(1)],
+            }),
+            block(vec![StatementKind::StorageDead(local(1))], TerminatorKind::Return),
+        ];
+        assert_eq!(validate(blocks, 2), vec![]);
+    }
+
+    #[test]
+    fn unreachable_blocks_are_not_checked_for_storage() {
+        let blocks = vec![
+            block(vec![], TerminatorKind::Return),
+            block(vec![StatementKind::StorageDead(local(1)),
+                       StatementKind::StorageLive(local(1))],
+                  TerminatorKind::Return),
+        ];
+        assert_eq!(validate(blocks, 2), vec![]);
+    }
+}
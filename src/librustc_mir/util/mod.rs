@@ -11,14 +11,28 @@
 pub mod borrowck_errors;
 pub mod elaborate_drops;
 pub mod def_use;
+pub mod inline_asm;
+pub mod journal;
 pub mod patch;
+pub mod scc;
 
 mod alignment;
+mod describe_lvalue;
 mod graphviz;
+mod json;
+mod moved_locals;
 mod pretty;
+mod spanview;
+mod stats;
 pub mod liveness;
+pub mod mir_validator;
 
-pub use self::alignment::is_disaligned;
-pub use self::pretty::{dump_enabled, dump_mir, write_mir_pretty, PassWhere};
+pub use self::alignment::{is_disaligned, is_within_packed};
+pub use self::describe_lvalue::describe_lvalue;
+pub use self::pretty::{dump_enabled, dump_mir, render_mir_dump, write_mir_pretty, PassWhere};
+pub use self::pretty::RenderedDump;
+pub use self::json::MIR_JSON_VERSION;
+pub use self::moved_locals::{group_moved_locals, local_move};
+pub use self::stats::count_mir;
 pub use self::graphviz::{write_mir_graphviz};
 pub use self::graphviz::write_node_label as write_graphviz_node_label;
@@ -0,0 +1,60 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Groups of locals that a value is moved between as a whole, for the
+//! passes that rewrite such a value in every local it passes through.
+
+use rustc::mir::{Local, Lvalue, Mir, Operand, Rvalue, Statement, StatementKind};
+use rustc_data_structures::indexed_vec::IndexVec;
+
+/// Groups the candidate locals that a value is moved between, mapping
+/// each local to the root of its group.
+pub fn group_moved_locals<'tcx>(mir: &Mir<'tcx>,
+                                is_candidate: &IndexVec<Local, bool>)
+                                -> IndexVec<Local, Local> {
+    fn find(parents: &IndexVec<Local, Local>, mut local: Local) -> Local {
+        while parents[local] != local {
+            local = parents[local];
+        }
+        local
+    }
+
+    let mut parents: IndexVec<Local, Local> = mir.local_decls.indices().collect();
+    for data in mir.basic_blocks() {
+        for statement in &data.statements {
+            if let Some((dest, src)) = local_move(mir, is_candidate, statement) {
+                let dest_root = find(&parents, dest);
+                let src_root = find(&parents, src);
+                parents[dest_root] = src_root;
+            }
+        }
+    }
+
+    for local in mir.local_decls.indices() {
+        let root = find(&parents, local);
+        parents[local] = root;
+    }
+    parents
+}
+
+/// Returns the destination and source of `dest = move src`, when both
+/// are candidate locals of the same type.
+pub fn local_move<'tcx>(mir: &Mir<'tcx>,
+                        is_candidate: &IndexVec<Local, bool>,
+                        statement: &Statement<'tcx>)
+                        -> Option<(Local, Local)> {
+    match statement.kind {
+        StatementKind::Assign(Lvalue::Local(dest),
+                              Rvalue::Use(Operand::Move(Lvalue::Local(src))))
+            if is_candidate[dest] && is_candidate[src] &&
+               mir.local_decls[dest].ty == mir.local_decls[src].ty => Some((dest, src)),
+        _ => None,
+    }
+}
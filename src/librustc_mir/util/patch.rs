@@ -12,6 +12,8 @@ use rustc::ty::Ty;
 use rustc::mir::*;
 use rustc_data_structures::indexed_vec::{IndexVec, Idx};
 use syntax_pos::Span;
+use std::iter::Peekable;
+use std::mem;
 
 /// This struct represents a patch to MIR, which can add
 /// new statements and basic blocks and patch over block
@@ -148,25 +150,32 @@ impl<'tcx> MirPatch<'tcx> {
         let mut new_statements = self.new_statements;
         new_statements.sort_by(|u,v| u.0.cmp(&v.0));
 
-        let mut delta = 0;
-        let mut last_bb = START_BLOCK;
-        for (mut loc, stmt) in new_statements {
-            if loc.block != last_bb {
-                delta = 0;
-                last_bb = loc.block;
+        // Rebuild each patched block in a single pass, rather than shifting
+        // its statements over once per inserted statement.
+        let mut new_statements = new_statements.into_iter().peekable();
+        while let Some(block) = new_statements.peek().map(|&(loc, _)| loc.block) {
+            let data = &mut mir[block];
+            let old_statements = mem::replace(&mut data.statements, vec![]);
+            let end = Location { block, statement_index: old_statements.len() };
+            let mut statements = Vec::with_capacity(old_statements.len());
+            for (statement_index, statement) in old_statements.into_iter().enumerate() {
+                let loc = Location { block, statement_index };
+                while let Some(kind) = next_statement_at(&mut new_statements, loc) {
+                    debug!("MirPatch: adding statement {:?} at loc {:?}", kind, loc);
+                    statements.push(Statement { source_info: statement.source_info, kind });
+                }
+                statements.push(statement);
+            }
+            let source_info = data.terminator().source_info;
+            while let Some(kind) = next_statement_at(&mut new_statements, end) {
+                debug!("MirPatch: adding statement {:?} at loc {:?}", kind, end);
+                statements.push(Statement { source_info, kind });
+            }
+            data.statements = statements;
+
+            if let Some(&(loc, _)) = new_statements.peek() {
+                assert!(loc.block != block, "MirPatch: statement added past the end at {:?}", loc);
             }
-            debug!("MirPatch: adding statement {:?} at loc {:?}+{}",
-                   stmt, loc, delta);
-            loc.statement_index += delta;
-            let source_info = Self::source_info_for_index(
-                &mir[loc.block], loc
-            );
-            mir[loc.block].statements.insert(
-                loc.statement_index, Statement {
-                    source_info,
-                    kind: stmt
-                });
-            delta += 1;
         }
     }
 
@@ -185,3 +194,17 @@ impl<'tcx> MirPatch<'tcx> {
         Self::source_info_for_index(data, loc)
     }
 }
+
+/// Takes the next statement out of `new_statements` if it is to be added
+/// at `loc`.
+fn next_statement_at<'tcx, I>(new_statements: &mut Peekable<I>,
+                              loc: Location)
+                              -> Option<StatementKind<'tcx>>
+    where I: Iterator<Item = (Location, StatementKind<'tcx>)>
+{
+    match new_statements.peek() {
+        Some(&(next, _)) if next == loc => {}
+        _ => return None,
+    }
+    new_statements.next().map(|(_, kind)| kind)
+}
@@ -11,15 +11,24 @@
 use rustc::hir;
 use rustc::hir::def_id::{DefId, LOCAL_CRATE};
 use rustc::mir::*;
+use rustc::mir::visit::{LvalueContext, Visitor};
+use rustc::session::MirDumpDir;
 use rustc::ty::TyCtxt;
 use rustc::ty::item_path;
 use rustc_data_structures::fx::FxHashMap;
 use rustc_data_structures::indexed_vec::{Idx};
+use std::cmp;
 use std::fmt::Display;
 use std::fs;
 use std::io::{self, Write};
+use std::iter;
 use std::path::{PathBuf, Path};
 use super::graphviz::write_mir_fn_graphviz;
+use super::json::write_mir_json;
+use super::def_use::DefUseAnalysis;
+use super::liveness::{self, LivenessMode};
+use super::spanview::write_mir_spanview;
+use syntax_pos::Span;
 use transform::MirSource;
 
 const INDENT: &'static str = "    ";
@@ -28,6 +37,7 @@ const ALIGN: usize = 40;
 
 /// An indication of where we are in the control flow graph. Used for printing
 /// extra information in `dump_mir`
+#[derive(Copy, Clone, Debug)]
 pub enum PassWhere {
     /// We have not started dumping the control flow graph, but we are about to.
     BeforeCFG,
@@ -43,19 +53,31 @@ pub enum PassWhere {
 }
 
 /// If the session is properly configured, dumps a human-readable
-/// representation of the mir into:
-///
-/// ```text
-/// rustc.node<node_id>.<pass_num>.<pass_name>.<disambiguator>
-/// ```
+/// representation of the mir into the file given by `dump_path`.
 ///
 /// Output from this function is controlled by passing `-Z dump-mir=<filter>`,
 /// where `<filter>` takes the following forms:
 ///
 /// - `all` -- dump MIR for all fns, all passes, all everything
-/// - `substring1&substring2,...` -- `&`-separated list of substrings
-///   that can appear in the pass-name or the `item_path_str` for the given
-///   node-id. If any one of the substrings match, the data is dumped out.
+/// - `pattern1,pattern2,...` -- comma-separated list of pass names, item
+///   paths and exclusions; see `rustc::session::dump_mir_filter`.
+///
+/// With `-Z dump-mir-liveness`, each basic block is also preceded by the
+/// locals live on entry to and exit from it, and each statement and
+/// terminator by the locals live on entry to it, as computed by
+/// `util::liveness` with drops counted as uses. These lines start with `|`
+/// rather than `//`, as mir-opt tests ignore comments.
+///
+/// With `-Z dump-mir-def-use`, the body is followed by the defs, uses and
+/// storage markers of each local, with their location and context, as
+/// `util::def_use` finds them; copy propagation and the other passes using
+/// it go by these counts. These lines start with `|` as well.
+///
+/// Each statement and terminator is followed by a comment with its scope
+/// and span, and the promoted constants it uses, whose bodies follow that
+/// of their parent. With `-Z dump-mir-spans`, the locals are also followed
+/// by the list of the visibility scopes, with their parent and span, on
+/// lines starting with `|` as well.
 pub fn dump_mir<'a, 'gcx, 'tcx, F>(tcx: TyCtxt<'a, 'gcx, 'tcx>,
                                    pass_num: Option<&Display>,
                                    pass_name: &str,
@@ -81,36 +103,37 @@ pub fn dump_enabled<'a, 'gcx, 'tcx>(tcx: TyCtxt<'a, 'gcx, 'tcx>,
                                     pass_name: &str,
                                     source: MirSource)
                                     -> bool {
-    let filters = match tcx.sess.opts.debugging_opts.dump_mir {
+    let filter = match tcx.sess.dump_mir_filter {
         None => return false,
-        Some(ref filters) => filters,
+        Some(ref filter) => filter,
     };
     let node_path = item_path::with_forced_impl_filename_line(|| { // see notes on #41697 below
         tcx.item_path_str(source.def_id)
     });
-    filters.split("&")
-           .any(|filter| {
-               filter == "all" ||
-                   pass_name.contains(filter) ||
-                   node_path.contains(filter)
-           })
+    filter.matches(pass_name, &node_path)
 }
 
 // #41697 -- we use `with_forced_impl_filename_line()` because
 // `item_path_str()` would otherwise trigger `type_of`, and this can
 // run while we are already attempting to evaluate `type_of`.
 
-fn dump_matched_mir_node<'a, 'gcx, 'tcx, F>(tcx: TyCtxt<'a, 'gcx, 'tcx>,
-                                            pass_num: Option<&Display>,
-                                            pass_name: &str,
-                                            node_path: &str,
-                                            disambiguator: &Display,
-                                            source: MirSource,
-                                            mir: &Mir<'tcx>,
-                                            mut extra_data: F)
-where
-    F: FnMut(PassWhere, &mut Write) -> io::Result<()>
-{
+/// Returns the path of the file that the dump of `source` after
+/// `pass_name` is written to, with the given extension:
+/// `rustc.<item>.<hash>[-<promoted>].<pass_num>.<pass_name>.<disambiguator>.<extension>`,
+/// in the directory of the crate (see `rustc::session::MirDumpDir`).
+///
+/// `<item>` is the end of the def path of the item, and `<hash>` the start
+/// of its def path hash, which tells apart the items with the same `<item>`
+/// and doesn't change across compilations, unlike node ids. Closures are
+/// numbered within their parent, so that `main-{{closure}}[1]` is the
+/// second closure of `main`.
+pub fn dump_path<'a, 'gcx, 'tcx>(tcx: TyCtxt<'a, 'gcx, 'tcx>,
+                                 extension: &str,
+                                 pass_num: Option<&Display>,
+                                 pass_name: &str,
+                                 disambiguator: &Display,
+                                 source: MirSource)
+                                 -> PathBuf {
     let promotion_id = match source.promoted {
         Some(id) => format!("-{:?}", id),
         None => String::new()
@@ -125,41 +148,526 @@ where
         }
     };
 
-    let mut file_path = PathBuf::new();
-    if let Some(ref file_dir) = tcx.sess.opts.debugging_opts.dump_mir_dir {
-        let p = Path::new(file_dir);
-        file_path.push(p);
-    };
+    let item_name = format!("rustc.{}.{:016x}",
+                            short_item_name(tcx, source.def_id),
+                            tcx.def_path_hash(source.def_id).0.to_smaller_hash());
+
+    let mut dump_dir = tcx.sess.mir_dump_dir.borrow_mut();
+    if dump_dir.is_none() {
+        let base = tcx.sess.opts.debugging_opts.dump_mir_dir.as_ref()
+            .map_or(PathBuf::new(), PathBuf::from);
+        *dump_dir = Some(MirDumpDir::new(&base, &tcx.crate_name(LOCAL_CRATE).as_str()));
+    }
+    let dump_dir = dump_dir.as_mut().unwrap();
+    let item_path = item_path::with_forced_impl_filename_line(|| { // see notes on #41697
+        tcx.item_path_str(source.def_id)
+    });
+    dump_dir.index(&item_name, &item_path);
+
+    let file_name = format!("{}{}{}.{}.{}.{}",
+                            item_name, promotion_id, pass_num, pass_name, disambiguator,
+                            extension);
+    dump_dir.path().join(&file_name)
+}
 
-    let _ = fs::create_dir_all(&file_path);
-    let item_name = tcx.hir.def_path(source.def_id).to_filename_friendly_no_crate();
-    let file_name = format!("rustc.{}{}{}.{}.{}.mir",
-                            item_name, promotion_id, pass_num, pass_name, disambiguator);
-    file_path.push(&file_name);
-    let _ = fs::File::create(&file_path).and_then(|mut file| {
-        writeln!(file, "// MIR for `{}`", node_path)?;
-        writeln!(file, "// source = {:?}", source)?;
-        writeln!(file, "// pass_name = {}", pass_name)?;
-        writeln!(file, "// disambiguator = {}", disambiguator)?;
-        if let Some(ref layout) = mir.generator_layout {
-            writeln!(file, "// generator_layout = {:?}", layout)?;
+/// The maximum length of the `<item>` part of the dump file names.
+const MAX_ITEM_NAME_LEN: usize = 40;
+
+/// Returns the last components of the def path of `def_id`, as many as fit
+/// in `MAX_ITEM_NAME_LEN`, separated by `-`, with the characters that
+/// don't belong in a file name replaced by `_`.
+fn short_item_name(tcx: TyCtxt, def_id: DefId) -> String {
+    let components: Vec<_> = tcx.def_path(def_id).data.iter().map(|component| {
+        let name = if component.disambiguator == 0 {
+            format!("{}", component.data.as_interned_str())
+        } else {
+            format!("{}[{}]", component.data.as_interned_str(), component.disambiguator)
+        };
+        name.chars().map(|c| {
+            if c.is_alphanumeric() || "_{}[]".contains(c) { c } else { '_' }
+        }).collect::<String>()
+    }).collect();
+
+    let mut len = 0;
+    let mut kept = 0;
+    for component in components.iter().rev() {
+        let component_len = component.chars().count() + if kept > 0 { 1 } else { 0 };
+        if kept > 0 && len + component_len > MAX_ITEM_NAME_LEN {
+            break;
         }
-        writeln!(file, "")?;
-        extra_data(PassWhere::BeforeCFG, &mut file)?;
-        write_mir_fn(tcx, source, mir, &mut extra_data, &mut file)?;
-        extra_data(PassWhere::AfterCFG, &mut file)?;
-        Ok(())
+        len += component_len;
+        kept += 1;
+    }
+    let name = components[components.len() - kept..].join("-");
+    name.chars().take(MAX_ITEM_NAME_LEN).collect()
+}
+
+fn dump_matched_mir_node<'a, 'gcx, 'tcx, F>(tcx: TyCtxt<'a, 'gcx, 'tcx>,
+                                            pass_num: Option<&Display>,
+                                            pass_name: &str,
+                                            node_path: &str,
+                                            disambiguator: &Display,
+                                            source: MirSource,
+                                            mir: &Mir<'tcx>,
+                                            extra_data: F)
+where
+    F: FnMut(PassWhere, &mut Write) -> io::Result<()>
+{
+    render_matched_mir_node(tcx, pass_num, pass_name, node_path, disambiguator, source, mir,
+                            extra_data).write();
+}
+
+/// Renders the dump of `mir` that `dump_mir` would write, without writing
+/// it. Returns `None` if the dump is not enabled.
+pub fn render_mir_dump<'a, 'gcx, 'tcx, F>(tcx: TyCtxt<'a, 'gcx, 'tcx>,
+                                          pass_num: Option<&Display>,
+                                          pass_name: &str,
+                                          disambiguator: &Display,
+                                          source: MirSource,
+                                          mir: &Mir<'tcx>,
+                                          extra_data: F)
+                                          -> Option<RenderedDump>
+where
+    F: FnMut(PassWhere, &mut Write) -> io::Result<()>
+{
+    if !dump_enabled(tcx, pass_name, source) {
+        return None;
+    }
+
+    let node_path = item_path::with_forced_impl_filename_line(|| { // see notes on #41697 below
+        tcx.item_path_str(source.def_id)
     });
+    Some(render_matched_mir_node(tcx, pass_num, pass_name, &node_path,
+                                 disambiguator, source, mir, extra_data))
+}
+
+/// A dump of a MIR body, rendered but not written yet.
+pub struct RenderedDump {
+    path: PathBuf,
+
+    /// The comments identifying the dump.
+    header: Vec<u8>,
+
+    /// The MIR itself.
+    body: Vec<u8>,
+
+    /// The promoted constants of the body, if it isn't one itself, which are
+    /// written after it.
+    promoted: Vec<RenderedPromoted>,
+
+    /// The graphviz rendering, under `-Z dump-mir-graphviz`.
+    graphviz: Option<Vec<u8>>,
+
+    /// The JSON serialization, under `-Z dump-mir-json`.
+    json: Option<Vec<u8>>,
 
-    if tcx.sess.opts.debugging_opts.dump_mir_graphviz {
-        file_path.set_extension("dot");
-        let _ = fs::File::create(&file_path).and_then(|mut file| {
-            write_mir_fn_graphviz(tcx, source.def_id, mir, &mut file)?;
+    /// The HTML view of the spans, under `-Z dump-mir-spanview`.
+    spanview: Option<Vec<u8>>,
+}
+
+struct RenderedPromoted {
+    index: Promoted,
+    body: Vec<u8>,
+
+    /// Whether the promoted body is replaced by a reference to the dump
+    /// before the pass, as the pass didn't change it.
+    elided: bool,
+}
+
+impl RenderedDump {
+    /// Whether `self` and `other` show the same MIR.
+    pub fn same_mir(&self, other: &RenderedDump) -> bool {
+        self.body == other.body &&
+            self.promoted.len() == other.promoted.len() &&
+            self.promoted.iter().zip(&other.promoted).all(|(a, b)| a.body == b.body)
+    }
+
+    /// Elides the promoted bodies that are the same as in `before`, the
+    /// dump of the body before the pass, so that they are only dumped again
+    /// when the pass changes them.
+    pub fn elide_unchanged_promoted(&mut self, before: &RenderedDump) {
+        for (promoted, before) in self.promoted.iter_mut().zip(&before.promoted) {
+            promoted.elided = promoted.body == before.body;
+        }
+    }
+
+    /// The MIR of the body and its promoted constants, in full.
+    fn mir_text(&self) -> String {
+        let mut text = String::from_utf8_lossy(&self.body).into_owned();
+        for promoted in &self.promoted {
+            text.push('\n');
+            text.push_str(&String::from_utf8_lossy(&promoted.body));
+        }
+        text
+    }
+
+    /// Writes the dump out, ignoring errors.
+    pub fn write(&self) {
+        if let Some(dir) = self.path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let _ = fs::File::create(&self.path).and_then(|mut file| {
+            file.write_all(&self.header)?;
+            file.write_all(&self.body)?;
+            for promoted in &self.promoted {
+                writeln!(file, "")?;
+                if promoted.elided {
+                    let before = self.path.file_name().map_or(String::new(), |name| {
+                        name.to_string_lossy().replace(".after.", ".before.")
+                    });
+                    writeln!(file, "// {:?} is unchanged by the pass, see {}",
+                             promoted.index, before)?;
+                } else {
+                    file.write_all(&promoted.body)?;
+                }
+            }
             Ok(())
         });
+
+        if let Some(ref graphviz) = self.graphviz {
+            let _ = fs::File::create(&self.path.with_extension("dot")).and_then(|mut file| {
+                file.write_all(graphviz)
+            });
+        }
+
+        if let Some(ref json) = self.json {
+            let _ = fs::File::create(&self.path.with_extension("json")).and_then(|mut file| {
+                file.write_all(json)
+            });
+        }
+
+        if let Some(ref spanview) = self.spanview {
+            let _ = fs::File::create(&self.path.with_extension("html")).and_then(|mut file| {
+                file.write_all(spanview)
+            });
+        }
+    }
+
+    /// Writes a unified diff from the MIR of `before` to that of `self`
+    /// next to `self`, ignoring errors.
+    pub fn write_diff(&self, before: &RenderedDump) {
+        let file_name = |path: &Path| {
+            path.file_name().map_or(String::new(), |name| name.to_string_lossy().into_owned())
+        };
+        let diff = unified_diff(&file_name(&before.path),
+                                &before.mir_text(),
+                                &file_name(&self.path),
+                                &self.mir_text());
+        if let Some(dir) = self.path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let _ = fs::File::create(&self.path.with_extension("diff")).and_then(|mut file| {
+            file.write_all(diff.as_bytes())
+        });
     }
 }
 
+/// The number of unchanged lines shown around the changed ones in a diff.
+const DIFF_CONTEXT: usize = 3;
+
+/// Returns a unified diff from `before` to `after`, line by line, which
+/// is empty if they are the same.
+pub fn unified_diff(before_name: &str, before: &str, after_name: &str, after: &str) -> String {
+    let before: Vec<_> = before.lines().collect();
+    let after: Vec<_> = after.lines().collect();
+    let lines = diff_lines(&before, &after);
+
+    // The positions in `before` and `after` at each line of the diff.
+    let mut positions = Vec::with_capacity(lines.len() + 1);
+    let (mut i, mut j) = (0, 0);
+    for &line in &lines {
+        positions.push((i, j));
+        match line {
+            DiffLine::Same => { i += 1; j += 1; }
+            DiffLine::Removed => i += 1,
+            DiffLine::Added => j += 1,
+        }
+    }
+    positions.push((i, j));
+
+    let changed: Vec<_> = (0..lines.len()).filter(|&l| lines[l] != DiffLine::Same).collect();
+    if changed.is_empty() {
+        return String::new();
+    }
+
+    let mut diff = format!("--- {}\n+++ {}\n", before_name, after_name);
+    let mut next = 0;
+    while next < changed.len() {
+        // A hunk goes on as long as the changes are close enough for
+        // their contexts to overlap.
+        let start = changed[next].saturating_sub(DIFF_CONTEXT);
+        let mut end = changed[next] + 1;
+        next += 1;
+        while next < changed.len() && changed[next] <= end + 2 * DIFF_CONTEXT {
+            end = changed[next] + 1;
+            next += 1;
+        }
+        let end = cmp::min(end + DIFF_CONTEXT, lines.len());
+
+        let (before_start, after_start) = positions[start];
+        let (before_end, after_end) = positions[end];
+        diff.push_str(&format!("@@ -{} +{} @@\n",
+                               hunk_range(before_start, before_end),
+                               hunk_range(after_start, after_end)));
+        for l in start..end {
+            let (i, j) = positions[l];
+            let (marker, text) = match lines[l] {
+                DiffLine::Same => (' ', before[i]),
+                DiffLine::Removed => ('-', before[i]),
+                DiffLine::Added => ('+', after[j]),
+            };
+            diff.push(marker);
+            diff.push_str(text);
+            diff.push('\n');
+        }
+    }
+    diff
+}
+
+/// Formats the range of lines from `start` to `end` of one side of a hunk,
+/// with the line before the hunk for an empty range.
+fn hunk_range(start: usize, end: usize) -> String {
+    if start == end {
+        format!("{},0", start)
+    } else {
+        format!("{},{}", start + 1, end - start)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum DiffLine {
+    Same,
+    Removed,
+    Added,
+}
+
+/// Returns the lines of a shortest diff from `a` to `b`.
+fn diff_lines(a: &[&str], b: &[&str]) -> Vec<DiffLine> {
+    // The lines both start or end with don't need to be searched.
+    let prefix = a.iter().zip(b).take_while(|&(x, y)| x == y).count();
+    let suffix = a[prefix..].iter().rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|&(x, y)| x == y)
+        .count();
+
+    let mut lines = vec![DiffLine::Same; prefix];
+    lines.extend(shortest_edit(&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]));
+    lines.extend(iter::repeat(DiffLine::Same).take(suffix));
+    lines
+}
+
+/// Myers' O(ND) difference algorithm: finds the furthest reaching path on
+/// each diagonal `k = x - y` of the edit graph with `d` non-diagonal
+/// edges, for increasing `d`, until one reaches the end of both `a` and
+/// `b`, then walks back from the end.
+fn shortest_edit(a: &[&str], b: &[&str]) -> Vec<DiffLine> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let offset = n + m + 1;
+    let mut v = vec![0; 2 * offset as usize + 1];
+
+    // The furthest reaching `x` on the diagonals from `-d` to `d` before
+    // each step `d`, at index `k + d`.
+    let mut trace: Vec<Vec<isize>> = vec![];
+    let mut d = 0;
+    'search: loop {
+        trace.push(v[(offset - d) as usize..(offset + d + 1) as usize].to_vec());
+        let mut k = -d;
+        while k <= d {
+            let down = k == -d ||
+                (k != d && v[(offset + k - 1) as usize] < v[(offset + k + 1) as usize]);
+            let mut x = if down {
+                v[(offset + k + 1) as usize]
+            } else {
+                v[(offset + k - 1) as usize] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[(offset + k) as usize] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+            k += 2;
+        }
+        d += 1;
+    }
+
+    let mut lines = vec![];
+    let (mut x, mut y) = (n, m);
+    while d > 0 {
+        let v = &trace[d as usize];
+        let at = move |k: isize| v[(k + d) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) { k + 1 } else { k - 1 };
+        let prev_x = at(prev_k);
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            lines.push(DiffLine::Same);
+            x -= 1;
+            y -= 1;
+        }
+        lines.push(if x == prev_x { DiffLine::Added } else { DiffLine::Removed });
+        x = prev_x;
+        y = prev_y;
+        d -= 1;
+    }
+    // The first step only follows the diagonal.
+    for _ in 0..x {
+        lines.push(DiffLine::Same);
+    }
+    lines.reverse();
+    lines
+}
+
+fn render_matched_mir_node<'a, 'gcx, 'tcx, F>(tcx: TyCtxt<'a, 'gcx, 'tcx>,
+                                              pass_num: Option<&Display>,
+                                              pass_name: &str,
+                                              node_path: &str,
+                                              disambiguator: &Display,
+                                              source: MirSource,
+                                              mir: &Mir<'tcx>,
+                                              extra_data: F)
+                                              -> RenderedDump
+where
+    F: FnMut(PassWhere, &mut Write) -> io::Result<()>
+{
+    let path = dump_path(tcx, "mir", pass_num, pass_name, disambiguator, source);
+
+    let mut header = vec![];
+    let _ = write_dump_header(node_path, pass_name, disambiguator, source, mir, &mut header);
+
+    let mut body = vec![];
+    let _ = write_dump_body(tcx, source, mir, extra_data, &mut body);
+
+    let promoted = if source.promoted.is_none() {
+        mir.promoted.iter_enumerated().map(|(index, promoted_mir)| {
+            let source = MirSource { def_id: source.def_id, promoted: Some(index) };
+            let mut body = vec![];
+            let _ = write_mir_fn(tcx, source, promoted_mir, &mut |_, _| Ok(()), &mut body);
+            RenderedPromoted { index, body, elided: false }
+        }).collect()
+    } else {
+        vec![]
+    };
+
+    let graphviz = if tcx.sess.opts.debugging_opts.dump_mir_graphviz && source.def_id.is_local() {
+        let mut graphviz = vec![];
+        let _ = write_mir_fn_graphviz(tcx, source.def_id, mir, &mut graphviz);
+        Some(graphviz)
+    } else {
+        None
+    };
+
+    let json = if tcx.sess.opts.debugging_opts.dump_mir_json {
+        let mut json = vec![];
+        let _ = write_mir_json(tcx, node_path, pass_name, &disambiguator.to_string(), source, mir,
+                               &mut json);
+        Some(json)
+    } else {
+        None
+    };
+
+    let spanview = if tcx.sess.opts.debugging_opts.dump_mir_spanview {
+        let title = format!("{} {} {}", node_path, pass_name, disambiguator);
+        let mut spanview = vec![];
+        let _ = write_mir_spanview(tcx, &title, mir, &mut spanview);
+        Some(spanview)
+    } else {
+        None
+    };
+
+    RenderedDump { path, header, body, promoted, graphviz, json, spanview }
+}
+
+fn write_dump_header<'tcx>(node_path: &str,
+                           pass_name: &str,
+                           disambiguator: &Display,
+                           source: MirSource,
+                           mir: &Mir<'tcx>,
+                           w: &mut Write)
+                           -> io::Result<()> {
+    writeln!(w, "// MIR for `{}`", node_path)?;
+    writeln!(w, "// source = {:?}", source)?;
+    writeln!(w, "// pass_name = {}", pass_name)?;
+    writeln!(w, "// disambiguator = {}", disambiguator)?;
+    if let Some(ref layout) = mir.generator_layout {
+        writeln!(w, "// generator_layout = {:?}", layout)?;
+    }
+    writeln!(w, "")
+}
+
+fn write_dump_body<'a, 'gcx, 'tcx, F>(tcx: TyCtxt<'a, 'gcx, 'tcx>,
+                                      source: MirSource,
+                                      mir: &Mir<'tcx>,
+                                      mut extra_data: F,
+                                      w: &mut Write)
+                                      -> io::Result<()>
+where
+    F: FnMut(PassWhere, &mut Write) -> io::Result<()>
+{
+    extra_data(PassWhere::BeforeCFG, w)?;
+    if tcx.sess.opts.debugging_opts.dump_mir_liveness {
+        let mode = LivenessMode { include_regular_use: true, include_drops: true };
+        let result = liveness::liveness_of_locals(mir, mode);
+        let mut live_in_block = vec![];
+        write_mir_fn(tcx, source, mir, &mut |pass_where, w| {
+            match pass_where {
+                PassWhere::BeforeBlock(block) => {
+                    writeln!(w, "{}| live on entry to {:?}: {}", INDENT, block,
+                             liveness::locals_to_string(mir, result.live_on_entry(block)))?;
+                    writeln!(w, "{}| live on exit from {:?}: {}", INDENT, block,
+                             liveness::locals_to_string(mir, result.live_on_exit(block)))?;
+                    live_in_block = result.live_in_block(mir, block);
+                }
+                PassWhere::InCFG(location) => {
+                    let live = &live_in_block[location.statement_index];
+                    writeln!(w, "{0}{0}| live: {1}", INDENT,
+                             liveness::locals_to_string(mir, &live.before))?;
+                }
+                PassWhere::BeforeCFG | PassWhere::AfterCFG => {}
+            }
+            extra_data(pass_where, w)
+        }, w)?;
+    } else {
+        write_mir_fn(tcx, source, mir, &mut extra_data, w)?;
+    }
+    if tcx.sess.opts.debugging_opts.dump_mir_def_use {
+        write_def_use(mir, w)?;
+    }
+    extra_data(PassWhere::AfterCFG, w)
+}
+
+/// Writes the defs and uses of each local, as `DefUseAnalysis` finds them,
+/// with their context.
+fn write_def_use(mir: &Mir, w: &mut Write) -> io::Result<()> {
+    let mut analysis = DefUseAnalysis::new(mir);
+    analysis.clear_and_analyze(mir);
+    writeln!(w, "")?;
+    for local in mir.local_decls.indices() {
+        let info = analysis.local_info(local);
+        writeln!(w, "| def-use of {:?}: defs: {}, uses: {}",
+                 local, info.def_count(), info.use_count())?;
+        for lvalue_use in info.defs_and_uses {
+            let context = &lvalue_use.context;
+            let role = if context.is_mutating_use() {
+                "def"
+            } else if context.is_nonmutating_use() {
+                "use"
+            } else {
+                "marker"
+            };
+            let context = match *context {
+                LvalueContext::Borrow { kind, .. } => format!("Borrow({:?})", kind),
+                ref context => format!("{:?}", context),
+            };
+            writeln!(w, "|{}{:?}: {} ({})", INDENT, lvalue_use.location, role, context)?;
+        }
+    }
+    Ok(())
+}
+
 /// Write out a human-readable textual representation for the given MIR.
 pub fn write_mir_pretty<'a, 'gcx, 'tcx>(tcx: TyCtxt<'a, 'gcx, 'tcx>,
                                         single: Option<DefId>,
@@ -238,10 +746,13 @@ where
     for statement in &data.statements {
         extra_data(PassWhere::InCFG(current_location), w)?;
         let indented_mir = format!("{0}{0}{1:?};", INDENT, statement);
-        writeln!(w, "{0:1$} // {2}",
+        let mut uses = PromotedUses(vec![]);
+        uses.visit_statement(block, statement, current_location);
+        writeln!(w, "{0:1$} // {2}{3}",
                  indented_mir,
                  ALIGN,
-                 comment(tcx, statement.source_info))?;
+                 comment(tcx, statement.source_info),
+                 promoted_uses_comment(mir, &uses.0))?;
 
         current_location.statement_index += 1;
     }
@@ -249,10 +760,13 @@ where
     // Terminator at the bottom.
     extra_data(PassWhere::InCFG(current_location), w)?;
     let indented_terminator = format!("{0}{0}{1:?};", INDENT, data.terminator().kind);
-    writeln!(w, "{0:1$} // {2}",
+    let mut uses = PromotedUses(vec![]);
+    uses.visit_terminator(block, data.terminator(), current_location);
+    writeln!(w, "{0:1$} // {2}{3}",
              indented_terminator,
              ALIGN,
-             comment(tcx, data.terminator().source_info))?;
+             comment(tcx, data.terminator().source_info),
+             promoted_uses_comment(mir, &uses.0))?;
 
     writeln!(w, "{}}}", INDENT)
 }
@@ -261,6 +775,58 @@ fn comment(tcx: TyCtxt, SourceInfo { span, scope }: SourceInfo) -> String {
     format!("scope {} at {}", scope.index(), tcx.sess.codemap().span_to_string(span))
 }
 
+/// Collects the promoted constants used by a statement or terminator.
+struct PromotedUses(Vec<Promoted>);
+
+impl<'tcx> Visitor<'tcx> for PromotedUses {
+    fn visit_literal(&mut self, literal: &Literal<'tcx>, _location: Location) {
+        if let Literal::Promoted { index } = *literal {
+            self.0.push(index);
+        }
+    }
+}
+
+/// Names the promoted constants in `uses`, with their type, at the end of
+/// the comment of a statement or terminator.
+fn promoted_uses_comment(mir: &Mir, uses: &[Promoted]) -> String {
+    uses.iter().map(|&index| match mir.promoted.get(index) {
+        Some(promoted_mir) => format!("; uses {:?}: {}", index, promoted_mir.return_ty()),
+        None => format!("; uses {:?}", index),
+    }).collect()
+}
+
+/// Prints every visibility scope with its parent and span. The spans only
+/// name the file, without its directory, so that tests can match them.
+fn write_scope_spans(tcx: TyCtxt, mir: &Mir, w: &mut Write) -> io::Result<()> {
+    for (scope, data) in mir.visibility_scopes.iter_enumerated() {
+        let parent = match data.parent_scope {
+            Some(parent) => format!(" in scope {}", parent.index()),
+            None => String::new(),
+        };
+        let inlined = match data.inlined {
+            Some((def_id, call_span)) => format!(", inlined from `{}` at {}",
+                                                 tcx.item_path_str(def_id),
+                                                 short_span_to_string(tcx, call_span)),
+            None => String::new(),
+        };
+        writeln!(w, "{}| scope {}{} at {}{}",
+                 INDENT, scope.index(), parent, short_span_to_string(tcx, data.span), inlined)?;
+    }
+    Ok(())
+}
+
+fn short_span_to_string(tcx: TyCtxt, span: Span) -> String {
+    let codemap = tcx.sess.codemap();
+    let lo = codemap.lookup_char_pos(span.lo());
+    let hi = codemap.lookup_char_pos(span.hi());
+    let name = lo.file.name.to_string();
+    let file = match Path::new(&name).file_name() {
+        Some(file) => file.to_string_lossy().into_owned(),
+        None => name.clone(),
+    };
+    format!("{}:{}:{}: {}:{}", file, lo.line, lo.col.0 + 1, hi.line, hi.col.0 + 1)
+}
+
 /// Prints user-defined variables in a scope tree.
 ///
 /// Returns the total number of variables printed.
@@ -357,6 +923,10 @@ pub fn write_mir_intro<'a, 'gcx, 'tcx>(tcx: TyCtxt<'a, 'gcx, 'tcx>,
 
     write_temp_decls(mir, w)?;
 
+    if tcx.sess.opts.debugging_opts.dump_mir_spans {
+        write_scope_spans(tcx, mir, w)?;
+    }
+
     // Add an empty line before the first block is printed.
     writeln!(w, "")?;
 
@@ -366,8 +936,11 @@ pub fn write_mir_intro<'a, 'gcx, 'tcx>(tcx: TyCtxt<'a, 'gcx, 'tcx>,
 fn write_mir_sig(tcx: TyCtxt, src: MirSource, mir: &Mir, w: &mut Write)
                  -> io::Result<()>
 {
-    let id = tcx.hir.as_local_node_id(src.def_id).unwrap();
-    let body_owner_kind = tcx.hir.body_owner_kind(id);
+    // Shims are built for functions of other crates, too.
+    let body_owner_kind = match tcx.hir.as_local_node_id(src.def_id) {
+        Some(id) => tcx.hir.body_owner_kind(id),
+        None => hir::BodyOwnerKind::Fn,
+    };
     match (body_owner_kind, src.promoted) {
         (_, Some(i)) => write!(w, "{:?} in", i)?,
         (hir::BodyOwnerKind::Fn, _) => write!(w, "fn")?,
@@ -0,0 +1,116 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The strongly connected components of the CFG, i.e. its maximal sets
+//! of blocks that can all reach each other.
+
+use rustc::mir::{BasicBlock, Mir};
+use rustc_data_structures::indexed_vec::IndexVec;
+use std::cmp;
+
+/// Returns the strongly connected components of the CFG, using Tarjan's
+/// algorithm. Every block is in exactly one of them, including the
+/// unreachable ones.
+///
+/// The components come in reverse topological order: no edge goes from a
+/// component to an earlier one.
+pub fn strongly_connected_components(mir: &Mir) -> Vec<Vec<BasicBlock>> {
+    let blocks = mir.basic_blocks();
+    let mut index: IndexVec<BasicBlock, Option<usize>> = IndexVec::from_elem(None, blocks);
+    let mut lowlink = IndexVec::from_elem(0, blocks);
+    let mut on_stack = IndexVec::from_elem(false, blocks);
+    let mut stack = vec![];
+    let mut next_index = 0;
+    let mut components = vec![];
+
+    for root in blocks.indices() {
+        if index[root].is_some() {
+            continue;
+        }
+
+        // Each frame holds a block, and how many of its successors have
+        // been visited so far.
+        let mut frames = vec![(root, 0)];
+        index[root] = Some(next_index);
+        lowlink[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+
+        loop {
+            let (block, visited) = match frames.last() {
+                Some(&frame) => frame,
+                None => break,
+            };
+            let successors = blocks[block].terminator().successors();
+            if let Some(&successor) = successors.get(visited) {
+                frames.last_mut().unwrap().1 += 1;
+                match index[successor] {
+                    None => {
+                        index[successor] = Some(next_index);
+                        lowlink[successor] = next_index;
+                        next_index += 1;
+                        stack.push(successor);
+                        on_stack[successor] = true;
+                        frames.push((successor, 0));
+                    }
+                    Some(successor_index) => {
+                        if on_stack[successor] {
+                            lowlink[block] = cmp::min(lowlink[block], successor_index);
+                        }
+                    }
+                }
+                continue;
+            }
+
+            frames.pop();
+            if let Some(&(parent, _)) = frames.last() {
+                lowlink[parent] = cmp::min(lowlink[parent], lowlink[block]);
+            }
+            if Some(lowlink[block]) == index[block] {
+                let mut component = vec![];
+                loop {
+                    let member = stack.pop().unwrap();
+                    on_stack[member] = false;
+                    component.push(member);
+                    if member == block {
+                        break;
+                    }
+                }
+                components.push(component);
+            }
+        }
+    }
+    components
+}
+
+/// Whether `component` contains a cycle, i.e. has more than one block, or
+/// a block that jumps to itself.
+pub fn is_cyclic(mir: &Mir, component: &[BasicBlock]) -> bool {
+    if component.len() > 1 {
+        return true;
+    }
+    let block = component[0];
+    mir[block].terminator().successors().contains(&block)
+}
+
+/// Whether each block is in a cycle of the CFG, and so can run several
+/// times in a single call.
+pub fn blocks_in_cycles(mir: &Mir) -> IndexVec<BasicBlock, bool> {
+    let mut in_cycle = IndexVec::from_elem(false, mir.basic_blocks());
+    for component in strongly_connected_components(mir) {
+        if is_cyclic(mir, &component) {
+            for block in component {
+                in_cycle[block] = true;
+            }
+        }
+    }
+    in_cycle
+}
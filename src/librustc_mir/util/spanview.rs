@@ -0,0 +1,141 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+//! The HTML view of a MIR body written by `-Z dump-mir-spanview`: the
+//! source of the body, with each region covered by the span of a
+//! statement or terminator highlighted in the color of its block, and
+//! tooltipped by the statements and terminators covering it.
+//!
+//! Regions covered by several spans are nested. A span that overlaps
+//! another without being nested in it is cut where the other ends. Code
+//! expanded from a macro is shown as covering the macro call.
+
+use rustc::mir::*;
+use rustc::ty::TyCtxt;
+use rustc_data_structures::indexed_vec::Idx;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use syntax_pos::Span;
+
+/// A region of the source covered by the span of statements and
+/// terminators, as byte offsets from the start of the body.
+struct Region {
+    lo: usize,
+    hi: usize,
+    /// The block of the first statement or terminator covering it.
+    block: BasicBlock,
+    /// The statements and terminators covering it.
+    mir: Vec<String>,
+}
+
+/// Writes the HTML view of `mir`, titled `title`.
+pub fn write_mir_spanview<'a, 'gcx, 'tcx>(tcx: TyCtxt<'a, 'gcx, 'tcx>,
+                                          title: &str,
+                                          mir: &Mir<'tcx>,
+                                          w: &mut Write)
+                                          -> io::Result<()> {
+    let codemap = tcx.sess.codemap();
+    let body_span = mir.span.source_callsite();
+    let source = codemap.span_to_snippet(body_span).map_err(|e| {
+        io::Error::new(io::ErrorKind::Other, format!("{:?}", e))
+    })?;
+    let regions = regions(mir, body_span);
+
+    writeln!(w, "<!DOCTYPE html>")?;
+    writeln!(w, "<html>")?;
+    writeln!(w, "<head>")?;
+    writeln!(w, "<meta charset=\"utf-8\">")?;
+    writeln!(w, "<title>{}</title>", escape(title))?;
+    writeln!(w, "<style>")?;
+    writeln!(w, ".code {{ font-family: monospace; white-space: pre; }}")?;
+    writeln!(w, ".region {{ border-radius: 3px; padding: 1px; }}")?;
+    writeln!(w, ".region:hover {{ outline: 1px solid black; }}")?;
+    for block in mir.basic_blocks().indices() {
+        writeln!(w, ".{:?} {{ background-color: hsla({}, 80%, 70%, 0.4); }}",
+                 block, block.index() * 47 % 360)?;
+    }
+    writeln!(w, "</style>")?;
+    writeln!(w, "</head>")?;
+    writeln!(w, "<body>")?;
+    writeln!(w, "<div class=\"code\">")?;
+
+    // Indent the first line as in the file.
+    let col = codemap.lookup_char_pos(body_span.lo()).col.0;
+    write!(w, "{:1$}", "", col)?;
+
+    let mut pos = 0;
+    let mut open: Vec<usize> = vec![];
+    for region in &regions {
+        while let Some(&hi) = open.last() {
+            if hi > region.lo {
+                break;
+            }
+            write!(w, "{}</span>", escape(&source[pos..hi]))?;
+            pos = hi;
+            open.pop();
+        }
+        write!(w, "{}", escape(&source[pos..region.lo]))?;
+        pos = region.lo;
+        write!(w, "<span class=\"region {:?}\" title=\"{}\">",
+               region.block, escape(&region.mir.join("\n")).replace('\n', "&#10;"))?;
+        let hi = match open.last() {
+            Some(&outer_hi) if outer_hi < region.hi => outer_hi,
+            _ => region.hi,
+        };
+        open.push(hi);
+    }
+    while let Some(hi) = open.pop() {
+        write!(w, "{}</span>", escape(&source[pos..hi]))?;
+        pos = hi;
+    }
+    writeln!(w, "{}", escape(&source[pos..]))?;
+
+    writeln!(w, "</div>")?;
+    writeln!(w, "</body>")?;
+    writeln!(w, "</html>")
+}
+
+/// Returns the regions covered by the statements and terminators of `mir`
+/// within `body_span`, outer regions first.
+fn regions(mir: &Mir, body_span: Span) -> Vec<Region> {
+    let mut regions = BTreeMap::new();
+    {
+        let mut cover = |span: Span, block: BasicBlock, text: String| {
+            let span = span.source_callsite();
+            if span.lo() >= span.hi() || !body_span.contains(span) {
+                return;
+            }
+            let lo = (span.lo() - body_span.lo()).0 as usize;
+            let hi = (span.hi() - body_span.lo()).0 as usize;
+            // Sort by start, then by decreasing end.
+            regions.entry((lo, !hi))
+                .or_insert_with(|| Region { lo, hi, block, mir: vec![] })
+                .mir.push(text);
+        };
+        for (block, data) in mir.basic_blocks().iter_enumerated() {
+            for (index, statement) in data.statements.iter().enumerate() {
+                cover(statement.source_info.span, block,
+                      format!("{:?}[{}]: {:?};", block, index, statement));
+            }
+            let terminator = data.terminator();
+            cover(terminator.source_info.span, block,
+                  format!("{:?}[{}]: {:?};", block, data.statements.len(), terminator.kind));
+        }
+    }
+    regions.into_iter().map(|(_, region)| region).collect()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,31 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+use rustc::mir::Mir;
+use rustc::session::MirCounts;
+use super::json::{statement_kind, terminator_kind};
+
+/// Counts the parts of `mir`, for `-Z mir-stats`. Its promoted bodies
+/// are counted separately.
+pub fn count_mir(mir: &Mir) -> MirCounts {
+    let mut counts = MirCounts::default();
+    counts.basic_blocks = mir.basic_blocks().len();
+    counts.locals = mir.local_decls.len();
+    for data in mir.basic_blocks().iter() {
+        for statement in &data.statements {
+            *counts.statements.entry(statement_kind(&statement.kind)).or_insert(0) += 1;
+        }
+        if let Some(ref terminator) = data.terminator {
+            *counts.terminators.entry(terminator_kind(&terminator.kind)).or_insert(0) += 1;
+        }
+    }
+    counts
+}
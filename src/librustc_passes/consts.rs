@@ -510,10 +510,18 @@ fn check_expr<'a, 'tcx>(v: &mut CheckCrateVisitor<'a, 'tcx>, e: &hir::Expr, node
             }
         }
 
+        hir::ExprIndex(ref base, _) |
+        hir::ExprField(ref base, _) |
+        hir::ExprTupField(ref base, _) => {
+            // Borrowing a part of a value promotes all of it, which
+            // must thus have no interior mutability or destructor.
+            let base_ty = v.tables.expr_ty_adjusted(base);
+            if !v.type_has_only_promotable_values(base_ty) {
+                v.promotable = false;
+            }
+        }
+
         hir::ExprBlock(_) |
-        hir::ExprIndex(..) |
-        hir::ExprField(..) |
-        hir::ExprTupField(..) |
         hir::ExprArray(_) |
         hir::ExprType(..) |
         hir::ExprTup(..) => {}
@@ -27,7 +27,10 @@ use rustc::ty::layout::{Align, LayoutOf};
 
 use rustc::hir;
 
+use rustc_data_structures::stable_hasher::StableHasher;
+
 use std::ffi::{CStr, CString};
+use std::hash::{Hash, Hasher};
 use syntax::ast;
 use syntax::attr;
 
@@ -104,6 +107,48 @@ pub fn addr_of(ccx: &CrateContext,
     gv
 }
 
+/// Like `addr_of`, but the global is shared by all the codegen units of
+/// the crate that need an identical one, so that e.g. the same table
+/// promoted in functions trans'd in different codegen units is only kept
+/// once: its name is derived from the constant and its alignment, and it
+/// has `linkonce_odr` linkage. Constants that refer to other globals, or
+/// to named types, aren't shared, as those names may mean different things
+/// in different codegen units.
+pub fn addr_of_shared(ccx: &CrateContext,
+                      cv: ValueRef,
+                      align: Align,
+                      kind: &str)
+                      -> ValueRef {
+    let contents = llvm::build_string(|s| unsafe {
+        llvm::LLVMRustWriteValueToString(cv, s);
+    }).expect("non-UTF8 constant");
+    if contents.contains('@') || contents.contains('%') {
+        return addr_of(ccx, cv, align, kind);
+    }
+
+    let mut hasher = StableHasher::<u128>::new();
+    contents.hash(&mut hasher);
+    align.abi().hash(&mut hasher);
+    let name = format!("{}.shared.{:x}", kind, hasher.finish());
+    if let Some(gv) = declare::get_declared_value(ccx, &name) {
+        return gv;
+    }
+
+    let gv = declare::define_global(ccx, &name, val_ty(cv)).unwrap_or_else(|| {
+        bug!("symbol `{}` is already defined", name);
+    });
+    unsafe {
+        llvm::LLVMSetInitializer(gv, cv);
+        set_global_alignment(ccx, gv, align);
+        llvm::LLVMSetGlobalConstant(gv, True);
+        SetUnnamedAddr(gv, true);
+        llvm::LLVMRustSetLinkage(gv, llvm::Linkage::LinkOnceODRLinkage);
+        llvm::LLVMRustSetVisibility(gv, llvm::Visibility::Hidden);
+    }
+    llvm::SetUniqueComdat(ccx.llmod(), gv);
+    gv
+}
+
 pub fn get_static(ccx: &CrateContext, def_id: DefId) -> ValueRef {
     let instance = Instance::mono(ccx.tcx(), def_id);
     if let Some(&g) = ccx.instances().borrow().get(&instance) {
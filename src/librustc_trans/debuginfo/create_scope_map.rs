@@ -10,17 +10,21 @@
 
 use super::{FunctionDebugContext, FunctionDebugContextData};
 use super::metadata::file_metadata;
-use super::utils::{DIB, span_start};
+use super::namespace::mangled_name_of_item;
+use super::utils::{DIB, create_DIArray, get_namespace_for_item, span_start};
 
 use llvm;
-use llvm::debuginfo::DIScope;
+use llvm::debuginfo::{DIFlags, DILocation, DIScope, DISubprogram};
 use common::CrateContext;
+use rustc::hir::def_id::DefId;
 use rustc::mir::{Mir, VisibilityScope};
+use rustc::session::config;
 
 use libc::c_uint;
+use std::ffi::CString;
 use std::ptr;
 
-use syntax_pos::Pos;
+use syntax_pos::{Pos, Span};
 
 use rustc_data_structures::bitvec::BitVector;
 use rustc_data_structures::indexed_vec::{Idx, IndexVec};
@@ -30,6 +34,9 @@ use syntax_pos::BytePos;
 #[derive(Clone, Copy, Debug)]
 pub struct MirDebugScope {
     pub scope_metadata: DIScope,
+    // The location of the call this scope's code was inlined at, if it was
+    // inlined at the MIR level, and null otherwise.
+    pub inlined_at: DILocation,
     // Start and end offsets of the file to which this DIScope belongs.
     // These are used to quickly determine whether some span refers to the same file.
     pub file_start_pos: BytePos,
@@ -48,6 +55,7 @@ pub fn create_mir_scopes(ccx: &CrateContext, mir: &Mir, debug_context: &Function
     -> IndexVec<VisibilityScope, MirDebugScope> {
     let null_scope = MirDebugScope {
         scope_metadata: ptr::null_mut(),
+        inlined_at: ptr::null_mut(),
         file_start_pos: BytePos(0),
         file_end_pos: BytePos(0)
     };
@@ -96,12 +104,35 @@ fn make_mir_scope(ccx: &CrateContext,
         let loc = span_start(ccx, mir.span);
         scopes[scope] = MirDebugScope {
             scope_metadata: debug_context.fn_metadata,
+            inlined_at: ptr::null_mut(),
             file_start_pos: loc.file.start_pos,
             file_end_pos: loc.file.end_pos,
         };
         return;
     };
 
+    // The root scope of an inlined body is the callee's own subprogram, as
+    // seen from the location of the call.
+    if let Some((callee, call_span)) = scope_data.inlined {
+        let call_loc = span_start(ccx, call_span);
+        let inlined_at = unsafe {
+            llvm::LLVMRustDIBuilderCreateDILocation(
+                ccx.llcx(),
+                call_loc.line as c_uint,
+                call_loc.col.to_usize() as c_uint,
+                parent_scope.scope_metadata,
+                parent_scope.inlined_at)
+        };
+        let loc = span_start(ccx, scope_data.span);
+        scopes[scope] = MirDebugScope {
+            scope_metadata: inlined_subprogram(ccx, callee, scope_data.span),
+            inlined_at,
+            file_start_pos: loc.file.start_pos,
+            file_end_pos: loc.file.end_pos,
+        };
+        return;
+    }
+
     if !has_variables.contains(scope.index()) {
         // Do not create a DIScope if there are no variables
         // defined in this MIR Scope, to avoid debuginfo bloat.
@@ -130,7 +161,41 @@ fn make_mir_scope(ccx: &CrateContext,
     };
     scopes[scope] = MirDebugScope {
         scope_metadata,
+        inlined_at: parent_scope.inlined_at,
         file_start_pos: loc.file.start_pos,
         file_end_pos: loc.file.end_pos,
     };
 }
+
+/// Creates the subprogram of a function inlined at the MIR level, which has
+/// no LLVM function of its own in this body.
+fn inlined_subprogram(ccx: &CrateContext, def_id: DefId, span: Span) -> DISubprogram {
+    let loc = span_start(ccx, span);
+    let file_metadata = file_metadata(ccx, &loc.file.name, def_id.krate);
+    let name = ccx.tcx().def_key(def_id).disambiguated_data.data.to_string();
+    let linkage_name = CString::new(mangled_name_of_item(ccx, def_id, "")).unwrap();
+    let name = CString::new(name).unwrap();
+
+    unsafe {
+        let function_type_metadata = llvm::LLVMRustDIBuilderCreateSubroutineType(
+            DIB(ccx),
+            file_metadata,
+            create_DIArray(DIB(ccx), &[]));
+        llvm::LLVMRustDIBuilderCreateFunction(
+            DIB(ccx),
+            get_namespace_for_item(ccx, def_id),
+            name.as_ptr(),
+            linkage_name.as_ptr(),
+            file_metadata,
+            loc.line as c_uint,
+            function_type_metadata,
+            true,
+            true,
+            loc.line as c_uint,
+            DIFlags::FlagPrototyped,
+            ccx.sess().opts.optimize != config::OptLevel::No,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut())
+    }
+}
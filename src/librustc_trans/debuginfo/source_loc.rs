@@ -15,18 +15,23 @@ use super::metadata::UNKNOWN_COLUMN_NUMBER;
 use super::FunctionDebugContext;
 
 use llvm;
-use llvm::debuginfo::DIScope;
+use llvm::debuginfo::{DILocation, DIScope};
 use builder::Builder;
 
 use libc::c_uint;
 use std::ptr;
 use syntax_pos::{Span, Pos};
 
-/// Sets the current debug location at the beginning of the span.
+/// Sets the current debug location at the beginning of the span, in code
+/// inlined at `inlined_at` if that isn't null.
 ///
 /// Maps to a call to llvm::LLVMSetCurrentDebugLocation(...).
 pub fn set_source_location(
-    debug_context: &FunctionDebugContext, builder: &Builder, scope: DIScope, span: Span
+    debug_context: &FunctionDebugContext,
+    builder: &Builder,
+    scope: DIScope,
+    inlined_at: DILocation,
+    span: Span
 ) {
     let function_debug_context = match *debug_context {
         FunctionDebugContext::DebugInfoDisabled => return,
@@ -40,7 +45,7 @@ pub fn set_source_location(
     let dbg_loc = if function_debug_context.source_locations_enabled.get() {
         debug!("set_source_location: {}", builder.sess().codemap().span_to_string(span));
         let loc = span_start(builder.ccx, span);
-        InternalDebugLocation::new(scope, loc.line, loc.col.to_usize())
+        InternalDebugLocation::new(scope, inlined_at, loc.line, loc.col.to_usize())
     } else {
         UnknownLocation
     };
@@ -65,14 +70,16 @@ pub fn start_emitting_source_locations(dbg_context: &FunctionDebugContext) {
 
 #[derive(Copy, Clone, PartialEq)]
 pub enum InternalDebugLocation {
-    KnownLocation { scope: DIScope, line: usize, col: usize },
+    KnownLocation { scope: DIScope, inlined_at: DILocation, line: usize, col: usize },
     UnknownLocation
 }
 
 impl InternalDebugLocation {
-    pub fn new(scope: DIScope, line: usize, col: usize) -> InternalDebugLocation {
+    pub fn new(scope: DIScope, inlined_at: DILocation, line: usize, col: usize)
+               -> InternalDebugLocation {
         KnownLocation {
             scope,
+            inlined_at,
             line,
             col,
         }
@@ -81,7 +88,7 @@ impl InternalDebugLocation {
 
 pub fn set_debug_location(builder: &Builder, debug_location: InternalDebugLocation) {
     let metadata_node = match debug_location {
-        KnownLocation { scope, line, .. } => {
+        KnownLocation { scope, inlined_at, line, .. } => {
             // Always set the column to zero like Clang and GCC
             let col = UNKNOWN_COLUMN_NUMBER;
             debug!("setting debug location to {} {}", line, col);
@@ -92,7 +99,7 @@ pub fn set_debug_location(builder: &Builder, debug_location: InternalDebugLocati
                     line as c_uint,
                     col as c_uint,
                     scope,
-                    ptr::null_mut())
+                    inlined_at)
             }
         }
         UnknownLocation => {
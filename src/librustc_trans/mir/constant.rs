@@ -247,6 +247,11 @@ impl<'tcx> ConstLvalue<'tcx> {
     }
 }
 
+/// The number of basic blocks the evaluation of a constant or const fn
+/// body may go through, so that a loop that doesn't end, under
+/// `#![feature(const_loop)]`, is an error rather than a hang.
+const STEP_LIMIT: usize = 1_000_000;
+
 /// Machinery for translating a constant's MIR to LLVM values.
 /// FIXME(eddyb) use miri and lower its allocations to LLVM.
 struct MirConstContext<'a, 'tcx: 'a> {
@@ -317,7 +322,16 @@ impl<'a, 'tcx> MirConstContext<'a, 'tcx> {
         // report as many errors as we possibly can.
         let mut failure = Ok(());
 
+        let mut steps = 0;
         loop {
+            steps += 1;
+            if steps > STEP_LIMIT {
+                let span = self.mir[bb].terminator().source_info.span;
+                let err = ConstEvalErr { span: span, kind: ErrKind::StepLimitExceeded };
+                err.report(tcx, span, "expression");
+                return Err(err);
+            }
+
             let data = &self.mir[bb];
             for statement in &data.statements {
                 let span = statement.source_info.span;
@@ -333,6 +347,7 @@ impl<'a, 'tcx> MirConstContext<'a, 'tcx> {
                     mir::StatementKind::StorageDead(_) |
                     mir::StatementKind::Validate(..) |
                     mir::StatementKind::EndRegion(_) |
+                    mir::StatementKind::Assume(..) |
                     mir::StatementKind::Nop => {}
                     mir::StatementKind::InlineAsm { .. } |
                     mir::StatementKind::SetDiscriminant{ .. } => {
@@ -366,7 +381,7 @@ impl<'a, 'tcx> MirConstContext<'a, 'tcx> {
                                     index: common::const_to_uint(index.llval)
                                 }
                             }
-                            mir::AssertMessage::Math(ref err) => {
+                            mir::AssertMessage::Math(ref err, _) => {
                                 ErrKind::Math(err.clone())
                             }
                             mir::AssertMessage::GeneratorResumedAfterReturn |
@@ -381,6 +396,20 @@ impl<'a, 'tcx> MirConstContext<'a, 'tcx> {
                     target
                 }
 
+                mir::TerminatorKind::SwitchInt { ref discr, ref values, ref targets, .. } => {
+                    let discr = self.const_operand(discr, span)?;
+                    let discr = const_to_opt_u128(discr.llval, false).unwrap_or_else(|| {
+                        span_bug!(span, "non-integral switch discriminant in constant");
+                    });
+                    let (otherwise, targets) = targets.split_last().unwrap();
+                    values.iter().zip(targets)
+                        .find(|&(value, _)| {
+                            let value = Const::from_constint(self.ccx, value);
+                            const_to_opt_u128(value.llval, false) == Some(discr)
+                        })
+                        .map_or(*otherwise, |(_, &target)| target)
+                }
+
                 mir::TerminatorKind::Call { ref func, ref args, ref destination, .. } => {
                     let fn_ty = func.ty(self.mir, tcx);
                     let fn_ty = self.monomorphize(&fn_ty);
@@ -794,8 +823,16 @@ impl<'a, 'tcx> MirConstContext<'a, 'tcx> {
                         } else {
                             self.ccx.tcx().data_layout.pointer_align
                         };
-                        if bk == mir::BorrowKind::Mut {
+                        // Only tables are worth sharing across codegen units,
+                        // other constants keep the alignment upgrades of `addr_of`.
+                        let is_table = match ty.sty {
+                            ty::TyArray(..) => self.ccx.shared().type_is_freeze(ty),
+                            _ => false
+                        };
+                        if let mir::BorrowKind::Mut { .. } = bk {
                             consts::addr_of_mut(self.ccx, llval, align, "ref_mut")
+                        } else if is_table {
+                            consts::addr_of_shared(self.ccx, llval, align, "ref")
                         } else {
                             consts::addr_of(self.ccx, llval, align, "ref")
                         }
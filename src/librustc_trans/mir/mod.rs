@@ -28,6 +28,7 @@ use syntax_pos::{DUMMY_SP, NO_EXPANSION, BytePos, Span};
 use syntax::symbol::keywords;
 
 use std::iter;
+use std::ptr;
 
 use rustc_data_structures::bitvec::BitVector;
 use rustc_data_structures::indexed_vec::{IndexVec, Idx};
@@ -111,7 +112,8 @@ impl<'a, 'tcx> MirContext<'a, 'tcx> {
 
     pub fn set_debug_loc(&mut self, bcx: &Builder, source_info: mir::SourceInfo) {
         let (scope, span) = self.debug_loc(source_info);
-        debuginfo::set_source_location(&self.debug_context, bcx, scope, span);
+        let inlined_at = self.scopes[source_info.scope].inlined_at;
+        debuginfo::set_source_location(&self.debug_context, bcx, scope, inlined_at, span);
     }
 
     pub fn debug_loc(&mut self, source_info: mir::SourceInfo) -> (DIScope, Span) {
@@ -269,7 +271,8 @@ pub fn trans_mir<'a, 'tcx: 'a>(
                 let lvalue = LvalueRef::alloca(&bcx, layout, &name.as_str());
                 if dbg {
                     let (scope, span) = mircx.debug_loc(decl.source_info);
-                    declare_local(&bcx, &mircx.debug_context, name, layout.ty, scope,
+                    let inlined_at = debug_scope.inlined_at;
+                    declare_local(&bcx, &mircx.debug_context, name, layout.ty, scope, inlined_at,
                         VariableAccess::DirectVariable { alloca: lvalue.llval },
                         VariableKind::LocalVariable, span);
                 }
@@ -417,7 +420,7 @@ fn arg_local_refs<'a, 'tcx>(bcx: &Builder<'a, 'tcx>,
                     bcx,
                     &mircx.debug_context,
                     arg_decl.name.unwrap_or(keywords::Invalid.name()),
-                    arg_ty, scope,
+                    arg_ty, scope, ptr::null_mut(),
                     variable_access,
                     VariableKind::ArgumentVariable(arg_index + 1),
                     DUMMY_SP
@@ -505,6 +508,7 @@ fn arg_local_refs<'a, 'tcx>(bcx: &Builder<'a, 'tcx>,
                     arg_decl.name.unwrap_or(keywords::Invalid.name()),
                     arg.layout.ty,
                     scope,
+                    ptr::null_mut(),
                     variable_access,
                     VariableKind::ArgumentVariable(arg_index + 1),
                     DUMMY_SP
@@ -574,6 +578,7 @@ fn arg_local_refs<'a, 'tcx>(bcx: &Builder<'a, 'tcx>,
                     decl.debug_name,
                     ty,
                     scope,
+                    ptr::null_mut(),
                     variable_access,
                     VariableKind::CapturedVariable,
                     DUMMY_SP
@@ -11,6 +11,7 @@
 use rustc::mir;
 
 use asm;
+use base;
 use builder::Builder;
 
 use super::MirContext;
@@ -82,6 +83,11 @@ impl<'a, 'tcx> MirContext<'a, 'tcx> {
                 asm::trans_inline_asm(&bcx, asm, outputs, input_vals);
                 bcx
             }
+            mir::StatementKind::Assume(ref cond) => {
+                let cond = self.trans_operand(&bcx, cond).immediate();
+                base::call_assume(&bcx, cond);
+                bcx
+            }
             mir::StatementKind::EndRegion(_) |
             mir::StatementKind::Validate(..) |
             mir::StatementKind::Nop => bcx,
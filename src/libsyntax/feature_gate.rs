@@ -428,6 +428,17 @@ declare_features! (
 
     // In-band lifetime bindings (e.g. `fn foo(x: &'a u8) -> &'a u8`)
     (active, in_band_lifetimes, "1.23.0", Some(44524)),
+
+    // Allow whole-field writes of non-`Copy` union fields outside `unsafe`
+    // when no old value has to be dropped
+    (active, safe_union_field_writes, "1.23.0", None),
+
+    // `if` and `match`, and other branches without loops, in constants,
+    // statics and `const fn`s
+    (active, const_if_match, "1.23.0", None),
+
+    // Loops, and the `let` statements and assignments they need, in `const fn`s
+    (active, const_loop, "1.23.0", None),
 );
 
 declare_features! (
@@ -794,6 +805,12 @@ pub const BUILTIN_ATTRIBUTES: &'static [(&'static str, AttributeType, AttributeG
                                                           libcore functions that are inlined \
                                                           across crates and will never be stable",
                                                           cfg_fn!(rustc_attrs))),
+    ("rustc_no_mir_validate", Whitelisted, Gated(Stability::Unstable,
+                                                 "rustc_attrs",
+                                                 "the `#[rustc_no_mir_validate]` attribute \
+                                                  is just used to suppress MIR validation \
+                                                  and will never be stable",
+                                                 cfg_fn!(rustc_attrs))),
     ("compiler_builtins", Whitelisted, Gated(Stability::Unstable,
                                              "compiler_builtins",
                                              "the `#[compiler_builtins]` attribute is used to \
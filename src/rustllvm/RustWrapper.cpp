@@ -629,7 +629,8 @@ extern "C" LLVMMetadataRef LLVMRustDIBuilderCreateFunction(
       LineNo, unwrapDI<DISubroutineType>(Ty), IsLocalToUnit, IsDefinition,
       ScopeLine, fromRust(Flags), IsOptimized, TParams,
       unwrapDIPtr<DISubprogram>(Decl));
-  unwrap<Function>(Fn)->setSubprogram(Sub);
+  if (Fn)
+    unwrap<Function>(Fn)->setSubprogram(Sub);
   return wrap(Sub);
 #else
   return wrap(Builder->createFunction(
@@ -879,6 +880,17 @@ LLVMRustDIBuilderCreateDebugLocation(LLVMContextRef ContextRef, unsigned Line,
   return wrap(MetadataAsValue::get(Context, debug_loc.getAsMDNode()));
 }
 
+extern "C" LLVMMetadataRef
+LLVMRustDIBuilderCreateDILocation(LLVMContextRef ContextRef, unsigned Line,
+                                  unsigned Column, LLVMMetadataRef Scope,
+                                  LLVMMetadataRef InlinedAt) {
+  LLVMContext &Context = *unwrap(ContextRef);
+
+  return wrap(DILocation::get(Context, Line, Column,
+                              unwrapDIPtr<MDNode>(Scope),
+                              unwrapDIPtr<MDNode>(InlinedAt)));
+}
+
 extern "C" int64_t LLVMRustDIBuilderCreateOpDeref() {
   return dwarf::DW_OP_deref;
 }
@@ -0,0 +1,35 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -O -C no-prepopulate-passes
+
+#![crate_type = "lib"]
+
+// The storage of a call argument ends as soon as the call returns, so that
+// the arguments of the calls making up a single expression can share a slot.
+
+#[inline(never)]
+pub fn sink(x: [u8; 1024]) -> u32 {
+    x[0] as u32
+}
+
+// CHECK-LABEL: @sum
+#[no_mangle]
+pub fn sum(x: [u8; 1024]) -> u32 {
+// CHECK: [[S__3:%[0-9]+]] = bitcast [1024 x i8]* %_3 to i8*
+// CHECK: call void @llvm.lifetime.start{{.*}}(i{{[0-9 ]+}}, i8* [[S__3]])
+// CHECK: call i32 @{{.*}}sink
+// CHECK: [[E__3:%[0-9]+]] = bitcast [1024 x i8]* %_3 to i8*
+// CHECK: call void @llvm.lifetime.end{{.*}}(i{{[0-9 ]+}}, i8* [[E__3]])
+// The last use of `x` is passed to the second call in place.
+// CHECK-NOT: %_5
+// CHECK: call i32 @{{.*}}sink
+    sink(x) + sink(x)
+}
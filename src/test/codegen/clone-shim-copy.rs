@@ -0,0 +1,31 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -O -C no-prepopulate-passes
+
+#![crate_type = "lib"]
+
+// The clone shim of an array of `Copy` elements is a single copy of the
+// array, without a loop over its elements.
+
+#[derive(Clone)]
+pub struct Bytes([u8; 64]);
+
+// CHECK-LABEL: @clone_bytes
+#[no_mangle]
+pub fn clone_bytes(b: &Bytes) -> Bytes {
+    b.clone()
+}
+
+// CHECK-LABEL: define {{.*}}4core5clone5Clone5clone
+// CHECK-NOT: br
+// CHECK: call void @llvm.memcpy
+// CHECK-NOT: br
+// CHECK: ret void
@@ -0,0 +1,29 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -C no-prepopulate-passes
+
+#![crate_type = "lib"]
+
+// The shim calling an `Fn` closure through `FnOnce` borrows the closure
+// environment in place, without copying it.
+
+fn call_once<F: FnOnce() -> u8>(f: F) -> u8 {
+    f()
+}
+
+#[no_mangle]
+pub fn first_byte(bytes: [u8; 4096]) -> u8 {
+    call_once(move || bytes[0])
+}
+
+// CHECK-LABEL: define {{.*}}4core3ops8function6FnOnce9call_once
+// CHECK-NOT: @llvm.memcpy
+// CHECK: {{^}}}
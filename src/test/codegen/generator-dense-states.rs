@@ -0,0 +1,48 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// compile-flags: -C no-prepopulate-passes
+
+// Check that the generator state switches use dense values, so they can be
+// lowered as jump tables rather than chains of comparisons.
+
+#![crate_type = "lib"]
+#![feature(generators, generator_trait)]
+
+use std::ops::Generator;
+
+// CHECK: switch i32 %{{.*}}, label %{{[a-zA-Z0-9_]+}} [
+// CHECK-NEXT: i32 0, label
+// CHECK-NEXT: i32 1, label
+// CHECK-NEXT: i32 2, label
+// CHECK-NEXT: i32 3, label
+// CHECK-NEXT: i32 4, label
+// CHECK-NEXT: i32 5, label
+// CHECK-NEXT: i32 6, label
+// CHECK-NEXT: i32 7, label
+// CHECK-NEXT: i32 8, label
+// CHECK-NEXT: i32 9, label
+// CHECK-NEXT: i32 10, label
+// CHECK-NEXT: ]
+#[no_mangle]
+pub fn resume_eight_yields() {
+    let mut gen = || {
+        yield 0;
+        yield 1;
+        yield 2;
+        yield 3;
+        yield 4;
+        yield 5;
+        yield 6;
+        yield 7;
+    };
+    let _ = gen.resume();
+}
@@ -0,0 +1,21 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -O -Z insert-loop-side-effects
+
+#![crate_type = "lib"]
+#![allow(effectless_infinite_loops)]
+
+// CHECK-LABEL: @spin
+#[no_mangle]
+pub fn spin() -> ! {
+    // CHECK: call void asm sideeffect ""
+    loop {}
+}
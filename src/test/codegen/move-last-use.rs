@@ -0,0 +1,31 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -C no-prepopulate-passes
+
+#![crate_type = "lib"]
+
+// The last use of a large `Copy` argument is passed to the callee in place,
+// so only the first call copies it.
+
+#[inline(never)]
+#[no_mangle]
+pub fn sink(_: [u8; 4096]) {}
+
+// CHECK-LABEL: @last_use
+#[no_mangle]
+pub fn last_use(a: [u8; 4096]) {
+// CHECK: call void @llvm.memcpy
+// CHECK: call void @sink
+// CHECK-NOT: @llvm.memcpy
+// CHECK: call void @sink
+    sink(a);
+    sink(a);
+}
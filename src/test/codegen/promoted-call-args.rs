@@ -0,0 +1,37 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -C no-prepopulate-passes
+
+#![crate_type = "lib"]
+
+// Check that borrows of constant arrays passed directly as call arguments
+// are promoted, to one constant shared by all the calls, instead of having
+// the array built on the stack for each call.
+
+// CHECK: @ref.shared.{{[0-9a-f]+}} = {{.*}}constant [3 x i32] [i32 1, i32 2, i32 3]
+// CHECK-NOT: [3 x i32] [i32 1, i32 2, i32 3]
+
+#[inline(never)]
+#[no_mangle]
+pub fn takes_slice(s: &[i32]) -> usize {
+    s.len()
+}
+
+// CHECK-LABEL: @calls
+#[no_mangle]
+pub fn calls() -> usize {
+// CHECK-NOT: alloca [3 x i32]
+// CHECK-NOT: store i32 1
+// CHECK: call {{.*}} @takes_slice
+// CHECK-NOT: store i32 1
+// CHECK: call {{.*}} @takes_slice
+    takes_slice(&[1, 2, 3]) + takes_slice(&[1, 2, 3])
+}
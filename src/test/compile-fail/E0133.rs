@@ -14,4 +14,5 @@ fn main() {
     f();
     //~^ ERROR E0133
     //~| NOTE call to unsafe function
+    //~| NOTE consult the function's documentation
 }
@@ -244,7 +244,7 @@ fn main() {
         unsafe fn bump2(mut block: *mut Block2) {
             let x = &mut block;
             let p : *const u8 = &*(*block).current;
-            //[mir]~^ ERROR cannot borrow `*block.current` as immutable because it is also borrowed as mutable
+            //[mir]~^ ERROR cannot borrow `*(*block).current` as immutable because it is also borrowed as mutable
             // No errors in AST because of issue rust#38899
         }
     }
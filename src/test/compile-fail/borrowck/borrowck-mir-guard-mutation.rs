@@ -0,0 +1,51 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// ignore-tidy-linelength
+// compile-flags: -Z borrowck=mir
+
+// The scrutinee of a match is borrowed while a guard is evaluated, so
+// mutating it from within the guard is an error.
+
+fn assign_in_guard() {
+    let mut x = Some(1);
+    match x {
+        Some(_) if { x = None; false } => {}
+        //~^ ERROR cannot assign in a pattern guard
+        //~| ERROR cannot assign to `x` because it is borrowed
+        _ => {}
+    }
+}
+
+fn assign_field_in_guard() {
+    let mut t = (Some(1), 2);
+    match t.0 {
+        Some(n) if { t.0 = None; n > 0 } => {}
+        //~^ ERROR cannot assign in a pattern guard
+        //~| ERROR cannot assign to `t.0` because it is borrowed
+        _ => {}
+    }
+}
+
+fn push_in_guard() {
+    let mut v = vec![1];
+    match v {
+        _ if { v.push(2); false } => {}
+        //~^ ERROR cannot mutably borrow in a pattern guard
+        //~| ERROR cannot borrow `v` as mutable because it is also borrowed as immutable
+        _ => {}
+    }
+}
+
+fn main() {
+    assign_in_guard();
+    assign_field_in_guard();
+    push_in_guard();
+}
@@ -0,0 +1,41 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// compile-flags: -Z borrowck=mir
+
+// Borrows through a `&mut` reference still conflict when the borrowed
+// paths overlap.
+
+struct S {
+    a: u32,
+    b: u32,
+}
+
+fn same_field(r: &mut S) {
+    let a = &mut r.a;
+    let a2 = &mut r.a; //~ ERROR cannot borrow `r.a` as mutable more than once at a time
+    *a += 1;
+    *a2 += 1;
+}
+
+fn field_and_whole(r: &mut S) {
+    let a = &mut r.a;
+    let s = &*r; //~ ERROR cannot borrow `*r` as immutable because it is also borrowed as mutable
+    *a += s.b;
+}
+
+fn field_and_reference(r: &mut S) {
+    let b = &r.b;
+    let moved = r; //~ ERROR cannot move out of `r` because it is borrowed
+    moved.a = *b;
+}
+
+fn main() {}
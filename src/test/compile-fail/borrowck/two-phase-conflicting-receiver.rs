@@ -0,0 +1,24 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// revisions: lxl nll
+//[lxl]compile-flags: -Z borrowck=mir -Z two-phase-borrows
+//[nll]compile-flags: -Z borrowck=mir -Z two-phase-borrows -Z nll
+
+// A reserved borrow only tolerates reads: mutably borrowing the receiver
+// again while evaluating the arguments is still an error.
+
+fn main() {
+    let mut v = vec![1, 2, 3];
+    v.push(v.pop().unwrap());
+    //[lxl]~^ ERROR cannot borrow `v` as mutable more than once at a time
+    //[nll]~^^ ERROR cannot borrow `v` as mutable more than once at a time
+}
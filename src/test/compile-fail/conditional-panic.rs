@@ -0,0 +1,28 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// must-compile-successfully
+
+// Check that an operation that will panic at runtime, but only when it is
+// reached, is reported with a `const_err` warning rather than an error.
+
+fn index(cond: bool) -> i32 {
+    let array = [1, 2, 3];
+    if cond {
+        array[3]
+        //~^ WARN this operation will panic at runtime: index out of bounds
+    } else {
+        0
+    }
+}
+
+fn main() {
+    index(false);
+}
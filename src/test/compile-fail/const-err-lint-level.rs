@@ -0,0 +1,38 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Zforce-overflow-checks=on
+// must-compile-successfully
+
+// Check that the lints reported for operations that always panic respect
+// the lint levels of the scope they were written in.
+
+#![feature(stmt_expr_attributes)]
+#![warn(unconditional_panic)]
+
+fn black_box<T>(_: T) {
+    unimplemented!()
+}
+
+fn main() {
+    let a = 255u8 + 1;
+    //~^ WARN attempt to add with overflow
+    //~| WARN this arithmetic operation will overflow
+    #[allow(const_err, unconditional_panic, arithmetic_overflow)]
+    {
+        let b = 255u8 + 1;
+        let _c = [5u8][1];
+        black_box(b);
+    }
+    let _d = [5u8][1];
+    //~^ WARN this operation will panic at runtime
+    //~| index out of bounds: the len is 1 but the index is 1
+    black_box(a);
+}
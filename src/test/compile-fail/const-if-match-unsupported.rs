@@ -0,0 +1,26 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that loops, and matches on enums, are still rejected in constants
+// and `const fn`s under `#![feature(const_if_match)]`.
+
+#![feature(const_fn, const_if_match)]
+
+const fn spin(b: bool) -> u32 {
+    while b {} //~ ERROR constant function contains unimplemented expression type
+    0
+}
+
+const fn is_some(o: Option<u32>) -> bool {
+    match o { Some(_) => true, None => false }
+    //~^ ERROR constant function contains unimplemented expression type
+}
+
+fn main() {}
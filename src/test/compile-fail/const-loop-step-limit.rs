@@ -0,0 +1,23 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that evaluating a loop that doesn't end is an error, not a hang.
+
+#![feature(const_fn, const_if_match, const_loop)]
+
+const fn forever() -> u32 {
+    loop {} //~ ERROR constant evaluation error
+}
+
+const NEVER: u32 = forever();
+
+fn main() {
+    println!("{}", NEVER);
+}
@@ -0,0 +1,23 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that branches in constants, statics and `const fn`s are gated.
+
+#![feature(const_fn)]
+
+const fn abs(x: i32) -> i32 {
+    if x < 0 { -x } else { x } //~ ERROR branches in constant functions are unstable
+}
+
+const ONE: u32 = if true { 1 } else { 2 }; //~ ERROR branches in constants are unstable
+
+static TWO: u32 = if false { 1 } else { 2 }; //~ ERROR branches in statics are unstable
+
+fn main() {}
@@ -0,0 +1,24 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+#![feature(untagged_unions)]
+
+struct NonCopy(u8);
+
+union U {
+    a: u8,
+    b: NonCopy,
+}
+
+fn main() {
+    let mut u = U { a: 1 };
+    u.b = NonCopy(2); //~ ERROR write to union field requires unsafe
+}
@@ -0,0 +1,29 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z mir-opt-level=2
+// must-compile-successfully
+
+// Tests that the MIR inliner doesn't inline recursive `#[inline(always)]`
+// functions into themselves, and warns about it.
+
+#[inline(always)]
+fn countdown(n: u32) -> u32 {
+    if n == 0 {
+        0
+    } else {
+        countdown(n - 1) + 1
+        //~^ WARN cannot inline recursive call to `#[inline(always)]` function `countdown`
+    }
+}
+
+fn main() {
+    countdown(3);
+}
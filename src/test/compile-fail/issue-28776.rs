@@ -14,4 +14,5 @@ fn main() {
     (&ptr::write)(1 as *mut _, 42);
     //~^ ERROR E0133
     //~| NOTE call to unsafe function
+    //~| NOTE consult the function's documentation
 }
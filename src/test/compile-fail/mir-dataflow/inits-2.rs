@@ -0,0 +1,67 @@
+// Copyright 2015 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Test of maybe_inits state computed by MIR dataflow, on a body with
+// enough blocks to be solved one strongly connected component at a time.
+
+#![feature(core_intrinsics, rustc_attrs)]
+
+use std::intrinsics::rustc_peek;
+use std::mem::drop;
+
+struct S(i32);
+
+#[rustc_mir_borrowck]
+#[rustc_mir(rustc_peek_maybe_init,stop_after_dataflow)]
+fn foo(test: bool, x: S, y: S) -> S {
+    let mut z;
+    loop {
+        // `z` is only initialized by the previous iteration.
+        unsafe { rustc_peek(&z); }
+        unsafe { rustc_peek(&z); }
+        unsafe { rustc_peek(&z); }
+        unsafe { rustc_peek(&z); }
+        z = S(1);
+        if test {
+            break;
+        }
+    }
+
+    unsafe { rustc_peek(&x); }
+    unsafe { rustc_peek(&y); }
+    unsafe { rustc_peek(&z); }
+    unsafe { rustc_peek(&x); }
+    unsafe { rustc_peek(&y); }
+    unsafe { rustc_peek(&z); }
+    unsafe { rustc_peek(&x); }
+    unsafe { rustc_peek(&y); }
+    unsafe { rustc_peek(&z); }
+
+    drop(x);
+    drop(z);
+
+    // `x` and `z` are uninitialized from here on.
+    unsafe { rustc_peek(&x); } //~ ERROR rustc_peek: bit not set
+    unsafe { rustc_peek(&y); }
+    unsafe { rustc_peek(&z); } //~ ERROR rustc_peek: bit not set
+    unsafe { rustc_peek(&x); } //~ ERROR rustc_peek: bit not set
+    unsafe { rustc_peek(&y); }
+    unsafe { rustc_peek(&z); } //~ ERROR rustc_peek: bit not set
+    unsafe { rustc_peek(&x); } //~ ERROR rustc_peek: bit not set
+    unsafe { rustc_peek(&y); }
+    unsafe { rustc_peek(&z); } //~ ERROR rustc_peek: bit not set
+
+    y
+}
+
+fn main() {
+    foo(true, S(13), S(14));
+    foo(false, S(13), S(14));
+}
@@ -0,0 +1,45 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Test of maybe_inits state computed by MIR dataflow for the fields of
+// structs, which get their own move path once they are moved out of or
+// assigned on their own.
+
+#![feature(core_intrinsics, rustc_attrs)]
+
+use std::intrinsics::rustc_peek;
+use std::mem::drop;
+
+struct S {
+    a: Box<i32>,
+    b: Box<i32>,
+}
+
+#[rustc_mir_borrowck]
+#[rustc_mir(rustc_peek_maybe_init,stop_after_dataflow)]
+fn foo(mut s: S) {
+    drop(s.a);
+
+    unsafe { rustc_peek(&s.a); } //~ ERROR rustc_peek: bit not set
+    unsafe { rustc_peek(&s.b); }
+    unsafe { rustc_peek(&s); }
+
+    s.a = Box::new(1);
+
+    unsafe { rustc_peek(&s.a); }
+
+    drop(s);
+
+    unsafe { rustc_peek(&s.a); } //~ ERROR rustc_peek: bit not set
+    unsafe { rustc_peek(&s.b); } //~ ERROR rustc_peek: bit not set
+}
+
+fn main() {
+}
@@ -0,0 +1,52 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// General test of the liveness of locals computed by MIR dataflow, which
+// runs backward. Each `rustc_peek(&x)` checks whether `x` is live right
+// after it; the borrow it takes is itself a use, so that a local peeked
+// at is live on every path leading to the peek.
+
+#![feature(core_intrinsics, rustc_attrs)]
+
+use std::intrinsics::rustc_peek;
+
+#[rustc_mir_borrowck]
+#[rustc_mir(rustc_peek_liveness,stop_after_dataflow)]
+fn foo(n: u32) -> u32 {
+    let mut i = 0;
+    let mut x = 0;
+    let mut y = 0;
+    loop {
+        // `i` is read by the comparison below, in this iteration and the
+        // following ones.
+        unsafe { rustc_peek(&i); }
+        if i == n {
+            break;
+        }
+
+        // `y` is assigned before it is read again, even around the loop.
+        unsafe { rustc_peek(&y); } //~ ERROR rustc_peek: bit not set
+        y = i * 2;
+
+        // `x` is read by its own update.
+        unsafe { rustc_peek(&x); }
+        x = x + y;
+        i = i + 1;
+    }
+
+    // `i` is never read after the loop, while `x` is returned.
+    unsafe { rustc_peek(&i); } //~ ERROR rustc_peek: bit not set
+    unsafe { rustc_peek(&x); }
+    x
+}
+
+fn main() {
+    foo(3);
+}
@@ -0,0 +1,41 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// Test of the liveness of locals computed by MIR dataflow, for locals that
+// are read on only some paths, or only dropped.
+
+#![feature(core_intrinsics, rustc_attrs)]
+
+use std::intrinsics::rustc_peek;
+
+#[rustc_mir_borrowck]
+#[rustc_mir(rustc_peek_liveness,stop_after_dataflow)]
+fn foo(test: bool, s: String, n: usize) -> usize {
+    let mut len = n;
+    if test {
+        // `len` is assigned before it is read again.
+        unsafe { rustc_peek(&len); } //~ ERROR rustc_peek: bit not set
+        len = s.len();
+    }
+
+    // `s` is dropped at the end of the function, which counts as a use.
+    unsafe { rustc_peek(&s); }
+
+    // `n` is never read again.
+    unsafe { rustc_peek(&n); } //~ ERROR rustc_peek: bit not set
+
+    len
+}
+
+fn main() {
+    foo(true, String::new(), 3);
+    foo(false, String::new(), 3);
+}
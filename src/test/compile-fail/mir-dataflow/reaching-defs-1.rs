@@ -0,0 +1,58 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// General test of the reaching definitions computed by MIR dataflow. Each
+// `rustc_peek(&x)` checks whether exactly one definition of `x` reaches
+// it.
+
+#![feature(core_intrinsics, rustc_attrs)]
+
+use std::intrinsics::rustc_peek;
+
+#[rustc_mir_borrowck]
+#[rustc_mir(rustc_peek_reaching_defs,stop_after_dataflow)]
+fn foo(test: bool, n: u32) -> u32 {
+    let mut x = 0;
+    // Only the initialization of `x` reaches here.
+    unsafe { rustc_peek(&x); }
+
+    let mut i = 0;
+    while i < n {
+        // The initialization and the redefinition from the previous
+        // iteration both reach here.
+        unsafe { rustc_peek(&x); } //~ ERROR rustc_peek: bit not set
+        x = x + i;
+        // Only the redefinition reaches here.
+        unsafe { rustc_peek(&x); }
+        i = i + 1;
+    }
+
+    // The initialization still reaches here when the loop doesn't run.
+    unsafe { rustc_peek(&x); } //~ ERROR rustc_peek: bit not set
+
+    x = 0;
+    // The assignment above overwrites every previous definition.
+    unsafe { rustc_peek(&x); }
+
+    let y;
+    if test {
+        y = 1;
+    } else {
+        y = 2;
+    }
+    // Each branch defines `y`.
+    unsafe { rustc_peek(&y); } //~ ERROR rustc_peek: bit not set
+
+    x + y
+}
+
+fn main() {
+    foo(true, 3);
+}
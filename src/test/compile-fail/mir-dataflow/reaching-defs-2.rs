@@ -0,0 +1,47 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// Test of the reaching definitions computed by MIR dataflow, for
+// arguments, call destinations and assignments to fields.
+
+#![feature(core_intrinsics, rustc_attrs)]
+
+use std::intrinsics::rustc_peek;
+
+struct S(i32, i32);
+
+fn make() -> S {
+    S(1, 2)
+}
+
+#[rustc_mir_borrowck]
+#[rustc_mir(rustc_peek_reaching_defs,stop_after_dataflow)]
+fn foo(mut x: i32) -> i32 {
+    // Arguments are not defined in the body.
+    unsafe { rustc_peek(&x); } //~ ERROR rustc_peek: bit not set
+    x = 1;
+    unsafe { rustc_peek(&x); }
+
+    // A call defines the local it returns into.
+    let mut s = make();
+    unsafe { rustc_peek(&s); }
+
+    // Assigning to a field leaves the rest of `s` in place, so the call
+    // still reaches here too.
+    s.0 = x;
+    unsafe { rustc_peek(&s); } //~ ERROR rustc_peek: bit not set
+
+    s.0 + s.1
+}
+
+fn main() {
+    foo(0);
+}
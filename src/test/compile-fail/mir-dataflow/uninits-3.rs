@@ -0,0 +1,63 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Test of maybe_uninits state computed by MIR dataflow for the fields of
+// structs, which get their own move path once they are moved out of.
+
+#![feature(core_intrinsics, rustc_attrs)]
+
+use std::intrinsics::rustc_peek;
+use std::mem::drop;
+
+struct S {
+    a: Box<i32>,
+    b: Box<i32>,
+}
+
+struct Outer {
+    s: S,
+    c: Box<i32>,
+}
+
+#[rustc_mir_borrowck]
+#[rustc_mir(rustc_peek_maybe_uninit,stop_after_dataflow)]
+fn foo(s: S, o: Outer, r: &S) {
+    unsafe { rustc_peek(&s.a); } //~ ERROR rustc_peek: bit not set
+
+    drop(s.a);
+
+    // `s.a` is moved out of, but `s.b` is not, and has the state of `s`,
+    // which doesn't include the states of its fields.
+    unsafe { rustc_peek(&s.a); }
+    unsafe { rustc_peek(&s.b); } //~ ERROR rustc_peek: bit not set
+    unsafe { rustc_peek(&s); } //~ ERROR rustc_peek: bit not set
+
+    drop(o.s.a);
+
+    // `o.s` has a move path as the parent of `o.s.a`, while `o.c` has the
+    // state of `o`.
+    unsafe { rustc_peek(&o.s.a); }
+    unsafe { rustc_peek(&o.s.b); } //~ ERROR rustc_peek: bit not set
+    unsafe { rustc_peek(&o.s); } //~ ERROR rustc_peek: bit not set
+    unsafe { rustc_peek(&o.c); } //~ ERROR rustc_peek: bit not set
+
+    drop(o);
+
+    // Moving out of `o` moves out of every part of it.
+    unsafe { rustc_peek(&o.s.a); }
+    unsafe { rustc_peek(&o.s.b); }
+    unsafe { rustc_peek(&o.c); }
+
+    // Nothing behind a reference can be moved out of.
+    unsafe { rustc_peek(&r.a); } //~ ERROR has no move path
+}
+
+fn main() {
+}
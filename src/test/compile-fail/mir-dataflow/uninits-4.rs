@@ -0,0 +1,37 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Test of maybe_uninits state computed by MIR dataflow for enums. Moving
+// out of the field of a variant doesn't change the state of the whole
+// value, which only a move of the whole value does.
+
+#![feature(core_intrinsics, rustc_attrs)]
+
+use std::intrinsics::rustc_peek;
+use std::mem::drop;
+
+#[rustc_mir_borrowck]
+#[rustc_mir(rustc_peek_maybe_uninit,stop_after_dataflow)]
+fn foo(x: Option<Box<i32>>, y: Option<Box<i32>>) {
+    match x {
+        Some(b) => drop(b),
+        None => {}
+    }
+
+    unsafe { rustc_peek(&x); } //~ ERROR rustc_peek: bit not set
+    unsafe { rustc_peek(&y); } //~ ERROR rustc_peek: bit not set
+
+    drop(y);
+
+    unsafe { rustc_peek(&y); }
+}
+
+fn main() {
+}
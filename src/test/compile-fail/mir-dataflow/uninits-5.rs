@@ -0,0 +1,31 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Test of maybe_uninits state computed by MIR dataflow for arrays. The
+// elements of an array can't be moved out of on their own, so they all
+// have the state of the array.
+
+#![feature(core_intrinsics, rustc_attrs)]
+
+use std::intrinsics::rustc_peek;
+use std::mem::drop;
+
+#[rustc_mir_borrowck]
+#[rustc_mir(rustc_peek_maybe_uninit,stop_after_dataflow)]
+fn foo(a: [Box<i32>; 2], i: usize) {
+    unsafe { rustc_peek(&a[i]); } //~ ERROR rustc_peek: bit not set
+
+    drop(a);
+
+    unsafe { rustc_peek(&a[i]); }
+}
+
+fn main() {
+}
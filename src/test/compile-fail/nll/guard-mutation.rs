@@ -0,0 +1,75 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// ignore-tidy-linelength
+// compile-flags: -Z borrowck=mir -Z nll
+
+// With non-lexical lifetimes, the borrow of the scrutinee taken for a guard
+// is still live until the guard has been evaluated, so mutating the
+// scrutinee from within the guard is an error. The same goes for the borrows
+// of `ref` bindings, even when the guard doesn't use them after the
+// mutation. The arm itself may mutate the scrutinee.
+
+fn assign_in_guard() {
+    let mut x = Some(1);
+    match x {
+        Some(_) if { x = None; false } => {}
+        //~^ ERROR cannot assign in a pattern guard
+        //~| ERROR cannot assign to `x` because it is borrowed
+        _ => {}
+    }
+}
+
+fn push_in_guard() {
+    let mut v = vec![1];
+    match v {
+        _ if { v.push(2); false } => {}
+        //~^ ERROR cannot mutably borrow in a pattern guard
+        //~| ERROR cannot borrow `v` as mutable because it is also borrowed as immutable
+        _ => {}
+    }
+}
+
+fn assign_after_reading_shared_ref_binding() {
+    let mut x = Some(1);
+    match x {
+        Some(ref n) if { let big = *n > 0; x = None; big } => {}
+        //~^ ERROR cannot assign in a pattern guard
+        //~| ERROR cannot assign to `x` because it is borrowed
+        _ => {}
+    }
+}
+
+fn assign_with_unused_mut_ref_binding() {
+    let mut x = Some(1);
+    match x {
+        Some(ref mut _n) if { x = None; false } => {}
+        //~^ ERROR cannot assign in a pattern guard
+        //~| ERROR cannot assign to `x` because it is borrowed
+        _ => {}
+    }
+}
+
+fn assign_in_arm() {
+    let mut x = Some(1);
+    match x {
+        Some(n) if n > 0 => { x = None; }
+        _ => {}
+    }
+    drop(x);
+}
+
+fn main() {
+    assign_in_guard();
+    push_in_guard();
+    assign_after_reading_shared_ref_binding();
+    assign_with_unused_mut_ref_binding();
+    assign_in_arm();
+}
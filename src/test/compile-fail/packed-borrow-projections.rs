@@ -0,0 +1,96 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// Borrows of fields of packed structs are checked through every projection
+// leading to them, including the ones created by autoref.
+
+#![deny(safe_packed_borrows)]
+#![allow(dead_code)]
+
+use std::ptr;
+
+#[repr(packed)]
+struct Packed {
+    a: u8,
+    b: u32,
+    inner: Inner,
+}
+
+struct Inner {
+    field: u32,
+}
+
+impl Inner {
+    fn get(&self) -> u32 {
+        self.field
+    }
+}
+
+trait Describe {
+    fn describe(&self) -> u32;
+}
+
+impl Describe for u32 {
+    fn describe(&self) -> u32 {
+        *self
+    }
+}
+
+#[repr(packed)]
+struct PackedPair {
+    x: u32,
+    y: u32,
+}
+
+#[repr(C)]
+struct Aligned {
+    x: u32,
+    pair: PackedPair,
+}
+
+#[repr(C)]
+struct Unaligned {
+    a: u8,
+    pair: PackedPair,
+}
+
+fn main() {
+    let p = Packed { a: 0, b: 1, inner: Inner { field: 2 } };
+
+    let _ = &p.inner.field; //~ ERROR borrow of packed field requires unsafe
+                            //~| hard error
+    let _ = &p.inner; //~ ERROR borrow of packed field requires unsafe
+                      //~| hard error
+    let _ = p.inner.get(); //~ ERROR borrow of packed field requires unsafe
+                           //~| hard error
+    let _ = p.b.describe(); //~ ERROR borrow of packed field requires unsafe
+                            //~| hard error
+    let _ = &p.b as *const u32; //~ ERROR borrow of packed field requires unsafe
+                                //~| hard error
+    let _ = &p.a; // ok, has align 1
+    let _ = &p as *const Packed; // ok, the struct itself has align 1
+    let _ = unsafe { // ok, no reference to the field is created
+        ptr::read_unaligned((&p as *const Packed as *const u8).offset(1) as *const u32)
+    };
+
+    unsafe {
+        let _ = &p.inner.field; // ok
+        let _ = p.inner.get(); // ok
+    }
+
+    let a = Aligned { x: 0, pair: PackedPair { x: 1, y: 2 } };
+    let _ = &a.pair.x; // ok, the offsets keep it aligned
+    let _ = &a.pair.y; // ok, the offsets keep it aligned
+
+    let u = Unaligned { a: 0, pair: PackedPair { x: 1, y: 2 } };
+    let _ = &u.pair.x; //~ ERROR borrow of packed field requires unsafe
+                       //~| hard error
+}
@@ -0,0 +1,52 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that calls to `const fn`s aren't promoted when an argument isn't
+// constant, or when the result has interior mutability or a destructor.
+
+#![feature(const_fn, const_cell_new)]
+
+use std::cell::Cell;
+
+struct WithDrop(u32);
+
+impl Drop for WithDrop {
+    fn drop(&mut self) {}
+}
+
+const fn double(x: u32) -> u32 {
+    x * 2
+}
+
+const fn with_drop(x: u32) -> WithDrop {
+    WithDrop(x)
+}
+
+const fn cell(x: u32) -> Cell<u32> {
+    Cell::new(x)
+}
+
+fn argument(x: u32) -> &'static u32 {
+    &double(x)
+    //~^ ERROR borrowed value does not live long enough
+}
+
+fn destructor() -> &'static WithDrop {
+    &with_drop(1)
+    //~^ ERROR borrowed value does not live long enough
+}
+
+fn interior_mutability() -> &'static Cell<u32> {
+    &cell(1)
+    //~^ ERROR borrowed value does not live long enough
+}
+
+fn main() {
+}
@@ -0,0 +1,36 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that the fields of constants aren't promoted when the constant
+// has interior mutability or a destructor, even outside of the field.
+
+use std::cell::Cell;
+
+struct WithDrop(u32);
+
+impl Drop for WithDrop {
+    fn drop(&mut self) {}
+}
+
+const CELL_PAIR: (Option<Cell<u32>>, u32) = (None, 1);
+const DROP_PAIR: (Option<WithDrop>, u32) = (None, 2);
+
+fn cell_field() -> &'static u32 {
+    &CELL_PAIR.1
+    //~^ ERROR borrowed value does not live long enough
+}
+
+fn drop_field() -> &'static u32 {
+    &DROP_PAIR.1
+    //~^ ERROR borrowed value does not live long enough
+}
+
+fn main() {
+}
@@ -21,6 +21,7 @@ unsafe impl UnsafeTrait for *mut isize {
         *self += 1;
         //~^ ERROR E0133
         //~| NOTE dereference of raw pointer
+        //~| NOTE raw pointers may be NULL
     }
 }
 
@@ -0,0 +1,23 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that an operation that will panic whenever the function runs is
+// reported as an error by the deny-by-default `unconditional_panic` lint.
+
+fn index() -> i32 {
+    let array = [1, 2, 3];
+    array[3]
+    //~^ ERROR this operation will panic at runtime
+    //~| index out of bounds: the len is 3 but the index is 3
+}
+
+fn main() {
+    index();
+}
@@ -19,6 +19,7 @@ const unsafe fn dummy(v: u32) -> u32 {
 const VAL: u32 = dummy(0xFFFF);
 //~^ ERROR E0133
 //~| NOTE call to unsafe function
+//~| NOTE consult the function's documentation
 
 fn main() {
     assert_eq!(VAL, 0xFFFF0000);
@@ -0,0 +1,52 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// ignore-windows
+// ignore-android
+// min-lldb-version: 310
+
+// compile-flags:-g -Z mir-opt-level=2
+
+// Tests that stepping into a function inlined at the MIR level stops in the
+// function, at its own lines.
+
+// === GDB TESTS ===================================================================================
+
+// gdb-command:run
+// gdb-command:step
+// gdb-command:frame
+// gdb-check:[...]mir_inlined_function::add_one[...]
+// gdb-check:[...]#loc1[...]
+
+// === LLDB TESTS ==================================================================================
+
+// lldb-command:set set stop-line-count-before 0
+// lldb-command:set set stop-line-count-after 1
+// lldb-command:run
+// lldb-command:step
+// lldb-command:frame select
+// lldb-check:[...]add_one[...]
+// lldb-check:[...]#loc1[...]
+
+#![allow(unused)]
+
+fn main() {
+    let x = 41;
+    let y = add_one(x); // #break
+    zzz(y);
+}
+
+#[inline(always)]
+fn add_one(x: u32) -> u32 {
+    x + 1 // #loc1
+}
+
+#[inline(never)]
+fn zzz(_: u32) {}
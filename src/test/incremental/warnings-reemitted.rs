@@ -13,7 +13,8 @@
 // must-compile-successfully
 
 #![allow(warnings)]
+#![warn(unconditional_panic)]
 
 fn main() {
-    255u8 + 1; //~ WARNING this expression will panic at run-time
+    let _e = [5u8][1]; //~ WARNING this operation will panic at runtime
 }
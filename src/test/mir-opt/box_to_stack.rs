@@ -0,0 +1,61 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z mir-opt-level=2
+
+#![feature(box_syntax)]
+
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+fn xor() -> i32 {
+    let p = box Point { x: 1, y: 2 };
+    p.x ^ p.y
+}
+
+fn main() {
+    xor();
+}
+
+// END RUST SOURCE
+// START rustc.xor.BoxToStack.before.mir
+//     ...
+//     scope 1 {
+//         let _1: std::boxed::Box<Point>;
+//     }
+//     ...
+//     bb0: {
+//         StorageLive(_1);
+//         StorageLive(_2);
+//         _2 = Box(Point);
+//         (*_2) = Point { x: const 1i32, y: const 2i32 };
+//         _1 = move _2;
+//         ...
+//         _3 = ((*_1).0: i32);
+//     ...
+// END rustc.xor.BoxToStack.before.mir
+// START rustc.xor.BoxToStack.after.mir
+//     ...
+//     scope 1 {
+//         let _1: Point;
+//     }
+//     ...
+//     bb0: {
+//         nop;
+//         nop;
+//         nop;
+//         _1 = Point { x: const 1i32, y: const 2i32 };
+//         nop;
+//         ...
+//         _3 = (_1.0: i32);
+//     ...
+// END rustc.xor.BoxToStack.after.mir
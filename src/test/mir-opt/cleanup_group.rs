@@ -0,0 +1,36 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that the cleanup passes are rerun until they change nothing: the
+// first round of constant propagation folds the condition, after which
+// the branch is removed and the join merged into its only predecessor, so
+// that the second round can fold the sum.
+
+fn join(a: u32) -> u32 {
+    let x = true;
+    let y = if x { 2u32 } else { a };
+    y + 1
+}
+
+fn main() {
+    join(0);
+}
+
+// END RUST SOURCE
+// START rustc.join.ConstProp.after.mir
+// ...
+//         switchInt(const true) -> [0u8: bb2, otherwise: bb1];
+// ...
+// END rustc.join.ConstProp.after.mir
+// START rustc.join.ConstProp-round2.after.mir
+// ...
+//         _0 = const 3u32;
+// ...
+// END rustc.join.ConstProp-round2.after.mir
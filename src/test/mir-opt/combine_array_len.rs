@@ -21,13 +21,13 @@ fn main() {
 // END RUST SOURCE
 
 // START rustc.norm2.InstCombine.before.mir
-//     _5 = Len(_1);
+//     _4 = Len(_1);
 //     ...
-//     _10 = Len(_1);
+//     _8 = Len(_1);
 // END rustc.norm2.InstCombine.before.mir
 
 // START rustc.norm2.InstCombine.after.mir
-//     _5 = const 2usize;
+//     _4 = const 2usize;
 //     ...
-//     _10 = const 2usize;
+//     _8 = const 2usize;
 // END rustc.norm2.InstCombine.after.mir
@@ -0,0 +1,86 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// ignore-tidy-linelength
+
+// Check that constants are propagated along straight-line code, through
+// integer arithmetic, bools, tuple fields and enum discriminants, and that
+// overflowing operations and the checks guarding them are left alone.
+
+enum Shape {
+    Point,
+    Circle(u32),
+}
+
+fn ints() -> u32 {
+    let x = 2u32;
+    let y = x * 3;
+    y + 1
+}
+
+fn bools(a: u8) -> u8 {
+    let t = true;
+    let f = !t;
+    if f & t { a } else { 1 }
+}
+
+fn tuples() -> u32 {
+    let t = (1u32, 2u32);
+    t.0 + t.1
+}
+
+fn discriminants() -> u32 {
+    let shape = Shape::Circle(5);
+    match shape {
+        Shape::Point => 0,
+        Shape::Circle(_) => 1,
+    }
+}
+
+fn overflow() -> u8 {
+    let x = 255u8;
+    x + 1
+}
+
+fn main() {
+    ints();
+    bools(0);
+    tuples();
+    discriminants();
+    overflow();
+}
+
+// END RUST SOURCE
+// START rustc.ints.ConstProp.after.mir
+// ...
+//         _0 = const 7u32;
+// ...
+// END rustc.ints.ConstProp.after.mir
+// START rustc.bools.ConstProp.after.mir
+// ...
+//         switchInt(const false) -> [0u8: bb2, otherwise: bb1];
+// ...
+// END rustc.bools.ConstProp.after.mir
+// START rustc.tuples.ConstProp.after.mir
+// ...
+//         _0 = const 3u32;
+// ...
+// END rustc.tuples.ConstProp.after.mir
+// START rustc.discriminants.ConstProp.after.mir
+// ...
+//         _2 = const 1isize;
+// ...
+// END rustc.discriminants.ConstProp.after.mir
+// START rustc.overflow.ConstProp.after.mir
+// ...
+//         _3 = CheckedAdd(const 255u8, const 1u8);
+//         assert(!move (_3.1: bool), "attempt to add with overflow in `x + 1`") -> bb1;
+// ...
+// END rustc.overflow.ConstProp.after.mir
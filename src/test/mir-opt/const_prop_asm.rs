@@ -0,0 +1,35 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that constants are propagated across inline assembly, which only
+// makes the locals it writes unknown.
+
+#![feature(asm)]
+
+fn mixed(y: u32) -> u32 {
+    let t = (2u32, 3u32);
+    let z: u32;
+    unsafe { asm!("" : "=r"(z) : "0"(y)); }
+    t.0 * t.1 + z
+}
+
+fn main() {
+    mixed(1);
+}
+
+// END RUST SOURCE
+// START rustc.mixed.ConstProp.after.mir
+// ...
+//         _8 = const 3u32;
+//         _9 = CheckedMul(const 2u32, const 3u32);
+// ...
+//         _6 = const 6u32;
+// ...
+// END rustc.mixed.ConstProp.after.mir
@@ -0,0 +1,61 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// ignore-tidy-linelength
+
+// Check that calls to `assume` are lowered to `Assume` statements, and that
+// what is assumed is used to evaluate comparisons in the blocks the
+// assumption dominates only: the division by zero check of `x / y` is
+// removed after `assume(y != 0)`, but not when the assumption is made in
+// the other branch of an `if`.
+
+#![feature(core_intrinsics)]
+
+use std::intrinsics::assume;
+
+fn dominated(x: u32, y: u32) -> u32 {
+    unsafe { assume(y != 0); }
+    x / y
+}
+
+fn sibling(c: bool, x: u32, y: u32) -> u32 {
+    if c {
+        unsafe { assume(y != 0); }
+        x
+    } else {
+        x / y
+    }
+}
+
+fn main() {
+    dominated(1, 1);
+    sibling(false, 1, 1);
+}
+
+// END RUST SOURCE
+// START rustc.dominated.ConstProp.after.mir
+// ...
+//         _5 = Ne(move _6, const 0u32);
+//         assume(move _5);
+//         _4 = ();
+//         goto -> bb1;
+// ...
+//         _9 = Eq(move _8, const 0u32);
+//         assert(!const false, "attempt to divide by zero in `x / y`") -> bb2;
+// ...
+// END rustc.dominated.ConstProp.after.mir
+// START rustc.sibling.ConstProp.after.mir
+// ...
+//         assume(move _7);
+// ...
+//         _11 = Eq(move _10, const 0u32);
+//         assert(!move _11, "attempt to divide by zero in `x / y`") -> bb4;
+// ...
+// END rustc.sibling.ConstProp.after.mir
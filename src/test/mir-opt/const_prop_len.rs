@@ -0,0 +1,51 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that the length of a slice made from a reference to an array is
+// known to constant propagation, whether the array is a local or a
+// promoted constant.
+
+fn local(i: usize) -> u32 {
+    let array = [1, 2, 3];
+    let slice: &[u32] = &array;
+    slice[i]
+}
+
+fn promoted(i: usize) -> u32 {
+    let slice: &[u32] = &[1, 2, 3];
+    slice[i]
+}
+
+fn main() {
+    local(0);
+    promoted(0);
+}
+
+// END RUST SOURCE
+// START rustc.local.ConstProp.before.mir
+// ...
+//         _6 = Len((*_3));
+// ...
+// END rustc.local.ConstProp.before.mir
+// START rustc.local.ConstProp.after.mir
+// ...
+//         _6 = const 3usize;
+// ...
+// END rustc.local.ConstProp.after.mir
+// START rustc.promoted.ConstProp.before.mir
+// ...
+//         _6 = Len((*_2));
+// ...
+// END rustc.promoted.ConstProp.before.mir
+// START rustc.promoted.ConstProp.after.mir
+// ...
+//         _6 = const 3usize;
+// ...
+// END rustc.promoted.ConstProp.after.mir
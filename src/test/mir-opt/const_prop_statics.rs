@@ -0,0 +1,63 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that the values of statics that can change at runtime, through
+// interior mutability, by being `static mut`, or by being thread-local,
+// are never folded by constant propagation.
+
+#![feature(thread_local)]
+
+use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+
+static COUNTER: AtomicUsize = ATOMIC_USIZE_INIT;
+
+static mut FLAG: u32 = 0;
+
+#[thread_local]
+static LOCAL: u32 = 0;
+
+fn atomic() -> bool {
+    COUNTER.load(Ordering::SeqCst) == 0
+}
+
+fn mutable() -> bool {
+    unsafe { FLAG == 0 }
+}
+
+fn thread_local() -> bool {
+    LOCAL == 0
+}
+
+fn main() {
+    COUNTER.fetch_add(1, Ordering::SeqCst);
+    unsafe { FLAG = 1; }
+    atomic();
+    mutable();
+    thread_local();
+}
+
+// END RUST SOURCE
+// START rustc.atomic.ConstProp.after.mir
+// ...
+//         _0 = Eq(move _1, const 0usize);
+// ...
+// END rustc.atomic.ConstProp.after.mir
+// START rustc.mutable.ConstProp.after.mir
+// ...
+//         _1 = (FLAG: u32);
+//         _0 = Eq(move _1, const 0u32);
+// ...
+// END rustc.mutable.ConstProp.after.mir
+// START rustc.thread_local.ConstProp.after.mir
+// ...
+//         _1 = (LOCAL: u32);
+//         _0 = Eq(move _1, const 0u32);
+// ...
+// END rustc.thread_local.ConstProp.after.mir
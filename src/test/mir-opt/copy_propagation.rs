@@ -8,6 +8,8 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+// compile-flags: -Z dump-mir-spans -Z mir-run-only-pass=copy-prop
+
 fn test(x: u32) -> u32 {
     let y = x;
     y
@@ -20,14 +22,14 @@ fn main() {
 
 // END RUST SOURCE
 // START rustc.test.CopyPropagation.before.mir
+//  ...
+//  | scope 0 at copy_propagation.rs:13:1: 16:2
+//  | scope 1 in scope 0 at copy_propagation.rs:13:24: 16:2
+//  | scope 2 in scope 1 at copy_propagation.rs:14:5: 16:2
 //  bb0: {
 //      ...
-//      _3 = _1;
-//      ...
-//      _2 = move _3;
-//      ...
-//      _4 = _2;
-//      _0 = move _4;
+//      _2 = _1;
+//      _0 = _2;
 //      ...
 //      return;
 //  }
@@ -35,7 +37,7 @@ fn main() {
 // START rustc.test.CopyPropagation.after.mir
 //  bb0: {
 //      ...
-//      _0 = move _1;
+//      _0 = _1;
 //      ...
 //      return;
 //  }
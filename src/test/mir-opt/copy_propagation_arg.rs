@@ -11,6 +11,8 @@
 // Check that CopyPropagation does not propagate an assignment to a function argument
 // (doing so can break usages of the original argument value)
 
+// compile-flags: -Z mir-run-only-pass=copy-prop
+
 fn dummy(x: u8) -> u8 {
     x
 }
@@ -0,0 +1,71 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that a copy of a borrowed local is propagated once the borrow is
+// dead, but not while the local may still change through it.
+
+fn borrow_dead(x: u32) -> u32 {
+    {
+        let r = &x;
+        let _z = *r;
+    }
+    let y = x;
+    y
+}
+
+fn borrow_after(mut x: u32) -> u32 {
+    let y = x;
+    {
+        let r = &mut x;
+        *r = 5;
+    }
+    y
+}
+
+fn main() {
+    borrow_dead(0);
+    borrow_after(0);
+}
+
+// END RUST SOURCE
+// START rustc.borrow_dead.CopyPropagation.before.mir
+//  bb0: {
+//      ...
+//      _2 = &_1;
+//      ...
+//      _4 = _1;
+//      _0 = _4;
+//      ...
+//      return;
+//  }
+// END rustc.borrow_dead.CopyPropagation.before.mir
+// START rustc.borrow_dead.CopyPropagation.after.mir
+//  bb0: {
+//      ...
+//      _2 = &_1;
+//      ...
+//      _0 = _1;
+//      ...
+//      return;
+//  }
+// END rustc.borrow_dead.CopyPropagation.after.mir
+// START rustc.borrow_after.CopyPropagation.after.mir
+//  bb0: {
+//      ...
+//      _2 = _1;
+//      ...
+//      _3 = &mut _1;
+//      (*_3) = const 5u32;
+//      ...
+//      _0 = _2;
+//      ...
+//      return;
+//  }
+// END rustc.borrow_after.CopyPropagation.after.mir
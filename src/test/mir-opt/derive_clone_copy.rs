@@ -0,0 +1,59 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that derived `Clone` impls of `Copy` types are replaced by clone
+// shims copying the value as a whole, and that derived impls of other types
+// still clone field by field.
+
+#[derive(Clone)]
+struct Named {
+    id: u32,
+    name: String,
+}
+
+#[derive(Clone, Copy)]
+struct Pair<T> {
+    a: T,
+    b: T,
+}
+
+fn main() {
+    let named = Named { id: 0, name: String::new() };
+    let _ = named.clone();
+
+    let pair = Pair { a: 1u32, b: 2u32 };
+    let mut other = pair.clone();
+    other.clone_from(&pair);
+}
+
+// END RUST SOURCE
+// START rustc.{{impl}}-clone.SimplifyCfg-initial.after.mir
+// ...
+//         _2 = const std::clone::Clone::clone(move _3) -> bb1;
+// ...
+//         _4 = const std::clone::Clone::clone(move _5) -> bb2;
+// ...
+// END rustc.{{impl}}-clone.SimplifyCfg-initial.after.mir
+// START rustc.clone-Clone-clone.shim.Pair_u32_.mir
+// ...
+//     bb0: {
+//         _0 = (*_1);
+//         return;
+//     }
+// }
+// END rustc.clone-Clone-clone.shim.Pair_u32_.mir
+// START rustc.clone-Clone-clone_from.shim.Pair_u32_.mir
+// ...
+//     bb0: {
+//         (*_1) = (*_2);
+//         return;
+//     }
+// }
+// END rustc.clone-Clone-clone_from.shim.Pair_u32_.mir
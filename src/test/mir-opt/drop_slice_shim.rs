@@ -0,0 +1,66 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// ignore-tidy-linelength
+
+// Check that the drop glue of a slice computes the length of the slice once,
+// and drops the elements with a loop counting an index up to it.
+
+use std::ptr;
+
+fn drop_strings(strings: &mut [String]) {
+    unsafe {
+        ptr::drop_in_place(strings);
+    }
+}
+
+fn main() {
+    let mut strings = [String::from("a"), String::from("b")];
+    drop_strings(&mut strings);
+    std::mem::forget(strings);
+}
+
+// END RUST SOURCE
+// START rustc.ptr-drop_in_place.shim.[std-string-String].mir
+// ...
+//     let mut _2: usize;
+//     let mut _3: usize;
+//     ...
+//     bb0: {
+//         _3 = Len((*_1));
+//         _2 = const 0usize;
+//         goto -> bb6;
+//     }
+//     bb1: {
+//         return;
+//     }
+//     bb2: {
+//         resume;
+//     }
+//     bb3: {
+//         _4 = &mut (*_1)[_2];
+//         _2 = Add(_2, const 1usize);
+//         drop((*_4)) -> bb4;
+//     }
+//     bb4: {
+//         _5 = Eq(_2, _3);
+//         switchInt(move _5) -> [0u8: bb3, otherwise: bb2];
+//     }
+//     bb5: {
+//         _6 = &mut (*_1)[_2];
+//         _2 = Add(_2, const 1usize);
+//         drop((*_6)) -> [return: bb6, unwind: bb4];
+//     }
+//     bb6: {
+//         _7 = Eq(_2, _3);
+//         switchInt(move _7) -> [0u8: bb5, otherwise: bb1];
+//     }
+// }
+// END rustc.ptr-drop_in_place.shim.[std-string-String].mir
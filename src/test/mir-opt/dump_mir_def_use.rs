@@ -0,0 +1,35 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// compile-flags: -Z dump-mir-def-use
+
+// Check that `-Z dump-mir-def-use` lists the defs and uses of each local
+// after the body.
+
+fn pair(x: u32) -> (u32, u32) {
+    let y = x;
+    (y, y)
+}
+
+fn main() {
+    pair(0);
+}
+
+// END RUST SOURCE
+// START rustc.pair.SimplifyCfg-qualify-consts.after.mir
+// ...
+// | def-use of _2: defs: 1, uses: 2
+// |     bb0[0]: marker (StorageLive)
+// |     bb0[1]: def (Store)
+// |     bb0[3]: use (Copy)
+// |     bb0[5]: use (Copy)
+// ...
+// END rustc.pair.SimplifyCfg-qualify-consts.after.mir
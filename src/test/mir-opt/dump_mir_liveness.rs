@@ -0,0 +1,70 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z dump-mir-liveness
+
+// Check that `-Z dump-mir-liveness` annotates each basic block with the
+// locals live on entry to and exit from it, and each statement with the
+// locals live on entry to it.
+
+fn pick(c: bool, x: u32, y: u32) -> u32 {
+    if c { x } else { y }
+}
+
+fn main() {
+    pick(true, 1, 2);
+    spin(true, false);
+}
+
+fn spin(mut x: bool, y: bool) -> bool {
+    while x {
+        x = y;
+    }
+    x
+}
+
+// END RUST SOURCE
+// START rustc.pick.SimplifyCfg-qualify-consts.after.mir
+//     ...
+//     | live on entry to bb0: {_1, _2, _3}
+//     | live on exit from bb0: {_2, _3}
+//     bb0: {
+//     ...
+//     | live on entry to bb1: {_2}
+//     | live on exit from bb1: {}
+//     bb1: {
+//         | live: {_2}
+//         _0 = _2;
+//         | live: {}
+//         goto -> bb3;
+//     }
+//     | live on entry to bb2: {_3}
+//     | live on exit from bb2: {}
+//     bb2: {
+//         | live: {_3}
+//         _0 = _3;
+//         | live: {}
+//         goto -> bb3;
+//     }
+//     ...
+// END rustc.pick.SimplifyCfg-qualify-consts.after.mir
+// START rustc.spin.SimplifyCfg-qualify-consts.after.mir
+//     ...
+//     | live on entry to bb0: {_1, _2}
+//     ...
+//     | live: {_2}
+//     _1 = _2;
+//     | live: {_1, _2}
+//     ...
+//     | live: {_1}
+//     _0 = _1;
+//     | live: {}
+//     ...
+// END rustc.spin.SimplifyCfg-qualify-consts.after.mir
@@ -46,8 +46,8 @@ fn foo<F>(f: F) where F: FnOnce() -> i32 {
 //         _2 = const foo(move _3) -> [return: bb1, unwind: bb3];
 //     }
 //     bb1: {
-//         EndRegion('19s);
 //         StorageDead(_3);
+//         EndRegion('19s);
 //         _0 = ();
 //         drop(_1) -> bb4;
 //     }
@@ -70,14 +70,10 @@ fn foo<F>(f: F) where F: FnOnce() -> i32 {
 //     ...
 //     let _2: &'15_0rs D;
 //     ...
-//     let mut _3: i32;
 //     bb0: {
 //         StorageLive(_2);
 //         _2 = &'15_0rs (*(_1.0: &'19s D));
-//         StorageLive(_3);
-//         _3 = ((*_2).0: i32);
-//         _0 = move _3;
-//         StorageDead(_3);
+//         _0 = ((*_2).0: i32);
 //         EndRegion('15_0rs);
 //         StorageDead(_2);
 //         return;
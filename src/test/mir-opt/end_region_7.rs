@@ -78,14 +78,10 @@ fn foo<F>(f: F) where F: FnOnce() -> i32 {
 //     ...
 //     let _2: &'15_0rs D;
 //     ...
-//     let mut _3: i32;
 //     bb0: {
 //         StorageLive(_2);
 //         _2 = &'15_0rs (_1.0: D);
-//         StorageLive(_3);
-//         _3 = ((*_2).0: i32);
-//         _0 = move _3;
-//         StorageDead(_3);
+//         _0 = ((*_2).0: i32);
 //         EndRegion('15_0rs);
 //         StorageDead(_2);
 //         drop(_1) -> bb1;
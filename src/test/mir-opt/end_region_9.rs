@@ -48,10 +48,9 @@ fn main() {
 //     let mut _4: &'33_0rs i32;
 //     ...
 //     let mut _3: ();
-//     let mut _5: !;
-//     let mut _6: ();
-//     let mut _7: bool;
-//     let mut _8: !;
+//     let mut _5: ();
+//     let mut _6: bool;
+//     let mut _7: !;
 //     bb0: {
 //        StorageLive(_1);
 //        _1 = const false;
@@ -62,13 +61,13 @@ fn main() {
 //    }
 //
 //    bb1: {
-//        StorageLive(_7);
-//        _7 = _1;
-//        switchInt(move _7) -> [0u8: bb3, otherwise: bb2];
+//        StorageLive(_6);
+//        _6 = _1;
+//        switchInt(move _6) -> [0u8: bb3, otherwise: bb2];
 //    }
 //    bb2: {
 //        _0 = ();
-//        StorageDead(_7);
+//        StorageDead(_6);
 //        EndRegion('33_0rs);
 //        StorageDead(_4);
 //        StorageDead(_2);
@@ -77,8 +76,8 @@ fn main() {
 //    }
 //    bb3: {
 //        _4 = &'33_0rs _2;
-//        _6 = ();
-//        StorageDead(_7);
+//        _5 = ();
+//        StorageDead(_6);
 //        _1 = const true;
 //        _3 = ();
 //        goto -> bb1;
@@ -88,9 +88,9 @@ fn query() -> bool { true }
 //         _5 = const <std::cell::Cell<T>>::set(move _6, move _7) -> bb3;
 //     }
 //     bb3: {
-//         EndRegion('16s);
 //         StorageDead(_7);
 //         StorageDead(_6);
+//         EndRegion('16s);
 //         StorageDead(_9);
 //         StorageLive(_11);
 //         _11 = const query() -> bb4;
@@ -120,9 +120,9 @@ fn query() -> bool { true }
 //         _13 = const <std::cell::Cell<T>>::set(move _14, move_15) -> bb7;
 //     }
 //     bb7: {
-//         EndRegion('33s);
 //         StorageDead(_15);
 //         StorageDead(_14);
+//         EndRegion('33s);
 //         StorageDead(_17);
 //         _1 = ();
 //         EndRegion('35_0rs);
@@ -0,0 +1,70 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that an expression computed on every path to a computation of it
+// is reused there, and that it isn't when an operand changed in between or
+// when it was computed on only some of the paths.
+
+fn diamond(c: bool, x: u32, y: u32) -> u32 {
+    let z;
+    if c {
+        z = x ^ y;
+    } else {
+        z = (x ^ y) | 1;
+    }
+    z & (x ^ y)
+}
+
+fn store(c: bool, mut x: u32, y: u32) -> u32 {
+    let z = x ^ y;
+    if c {
+        x = 0;
+    }
+    z & (x ^ y)
+}
+
+fn partial(c: bool, x: u32, y: u32) -> u32 {
+    let mut z = 0;
+    if c {
+        z = x ^ y;
+    }
+    z & (x ^ y)
+}
+
+fn main() {
+    diamond(true, 1, 2);
+    store(true, 1, 2);
+    partial(true, 1, 2);
+}
+
+// END RUST SOURCE
+// START rustc.diamond.GlobalCse.after.mir
+// ...
+//         _4 = BitXor(move _7, move _8);
+//         _16 = _4;
+// ...
+//         _9 = BitXor(move _10, move _11);
+//         _16 = _9;
+// ...
+//         _13 = _16;
+// ...
+// END rustc.diamond.GlobalCse.after.mir
+// START rustc.store.GlobalCse.after.mir
+// ...
+//         _10 = BitXor(move _11, move _12);
+//         _0 = BitAnd(move _4, move _10);
+// ...
+// END rustc.store.GlobalCse.after.mir
+// START rustc.partial.GlobalCse.after.mir
+// ...
+//         _10 = BitXor(move _11, move _12);
+//         _0 = BitAnd(move _9, move _10);
+// ...
+// END rustc.partial.GlobalCse.after.mir
@@ -0,0 +1,49 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that `if let` and `while let` on a local read the discriminant
+// straight from the local and bind the payload from a projection of it,
+// without copying the scrutinee to a temporary first.
+
+fn if_let(opt: Option<u32>) -> u32 {
+    if let Some(x) = opt { x } else { 0 }
+}
+
+fn while_let(mut opt: Option<u32>) -> u32 {
+    let mut last = 0;
+    while let Some(x) = opt {
+        last = x;
+        opt = None;
+    }
+    last
+}
+
+fn main() {
+    if_let(Some(1));
+    while_let(Some(2));
+}
+
+// END RUST SOURCE
+// START rustc.if_let.SimplifyBranches-initial.before.mir
+//     bb0: {
+//         _3 = discriminant(_1);
+//         ...
+//         _2 = ((_1 as Some).0: u32);
+//         _0 = _2;
+//         ...
+// END rustc.if_let.SimplifyBranches-initial.before.mir
+// START rustc.while_let.SimplifyBranches-initial.before.mir
+//     bb1: {
+//         _5 = discriminant(_1);
+//         ...
+//         _4 = ((_1 as Some).0: u32);
+//         _2 = _4;
+//         ...
+// END rustc.while_let.SimplifyBranches-initial.before.mir
@@ -0,0 +1,66 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z span_free_formats
+
+// Tests that the MIR inliner flattens chains of `#[inline(always)]` calls,
+// and leaves calls to `#[cold]` functions alone.
+
+fn main() {
+    println!("{}", flatten(1));
+    println!("{}", keep_cold(1));
+}
+
+fn flatten(x: u32) -> u32 {
+    first(x)
+}
+
+#[inline(always)]
+fn first(x: u32) -> u32 {
+    second(x)
+}
+
+#[inline(always)]
+fn second(x: u32) -> u32 {
+    third(x)
+}
+
+#[inline(always)]
+fn third(x: u32) -> u32 {
+    x
+}
+
+fn keep_cold(x: u32) -> u32 {
+    cold_path(x)
+}
+
+#[cold]
+#[inline]
+fn cold_path(x: u32) -> u32 {
+    x
+}
+
+// END RUST SOURCE
+// START rustc.flatten.Inline.after.mir
+// ...
+// bb0: {
+//     ...
+//     return;
+// }
+// }
+// END rustc.flatten.Inline.after.mir
+// START rustc.keep_cold.Inline.after.mir
+// ...
+// bb0: {
+//     ...
+//     _0 = const cold_path(move _2) -> bb1;
+// }
+// ...
+// END rustc.keep_cold.Inline.after.mir
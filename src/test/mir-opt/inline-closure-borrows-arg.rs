@@ -30,19 +30,14 @@ fn foo<T: Copy>(_t: T, q: &i32) -> i32 {
 // ...
 // bb0: {
 //     ...
-//     _3 = [closure@NodeId(39)];
-//     ...
-//     _4 = &_3;
-//     ...
 //     _6 = &(*_2);
 //     ...
 //     _7 = &(*_2);
 //     _5 = (move _6, move _7);
-//     _9 = move (_5.0: &i32);
-//     _10 = move (_5.1: &i32);
-//     StorageLive(_8);
-//     _8 = (*_9);
-//     _0 = move _8;
+//     _8 = move (_5.0: &i32);
+//     _9 = move (_5.1: &i32);
+//     ...
+//     _0 = (*_8);
 //     ...
 //     return;
 // }
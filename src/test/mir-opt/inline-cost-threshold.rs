@@ -0,0 +1,61 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z span_free_formats
+
+// Tests the MIR inliner's decisions on either side of the default threshold.
+// Both callees have at most three blocks, which raises the threshold to 62.
+// `add_one` costs two assignments, an overflow check and a word of locals,
+// 36 in all, while `times_three_plus_one` costs four assignments, two
+// overflow checks and two words of locals, 72 in all.
+
+fn main() {
+    println!("{}", accept(1));
+    println!("{}", reject(1));
+}
+
+fn accept(x: u32) -> u32 {
+    add_one(0u8, x)
+}
+
+fn reject(x: u32) -> u32 {
+    times_three_plus_one(0u8, x)
+}
+
+fn add_one<T: Copy>(_t: T, x: u32) -> u32 {
+    x + 1
+}
+
+fn times_three_plus_one<T: Copy>(_t: T, x: u32) -> u32 {
+    x * 3 + 1
+}
+
+// END RUST SOURCE
+// START rustc.accept.Inline.after.mir
+// ...
+// bb0: {
+//     ...
+//     goto -> bb2;
+// }
+// ...
+// bb2: {
+//     ...
+//     assert(!move (_3.1: bool), "attempt to add with overflow in `x + 1`") -> bb3;
+// }
+// ...
+// END rustc.accept.Inline.after.mir
+// START rustc.reject.Inline.after.mir
+// ...
+// bb0: {
+//     ...
+//     _0 = const times_three_plus_one::<u8>(const 0u8, move _2) -> bb1;
+// }
+// ...
+// END rustc.reject.Inline.after.mir
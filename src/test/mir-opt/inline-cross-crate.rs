@@ -0,0 +1,39 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// aux-build:inline_getter.rs
+// compile-flags: -Z span_free_formats
+
+// Tests that the MIR inliner inlines small functions from other crates when
+// their MIR is in the crate's metadata.
+
+extern crate inline_getter;
+
+use inline_getter::Counter;
+
+fn main() {
+    println!("{}", count(&Counter::new()));
+}
+
+fn count(counter: &Counter) -> u32 {
+    counter.count()
+}
+
+// END RUST SOURCE
+// START rustc.count.Inline.after.mir
+// ...
+// bb0: {
+//     ...
+//     _0 = ((*_2).0: u32);
+//     ...
+//     return;
+// }
+// }
+// END rustc.count.Inline.after.mir
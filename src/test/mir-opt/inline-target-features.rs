@@ -0,0 +1,82 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z span_free_formats
+// ignore-arm
+// ignore-aarch64
+// ignore-emscripten
+// ignore-mips
+// ignore-mips64
+// ignore-powerpc
+// ignore-powerpc64
+// ignore-s390x
+// ignore-sparc
+
+// Tests that the MIR inliner doesn't inline a callee with target features
+// that the caller lacks, nor a callee containing inline assembly.
+
+#![feature(asm, target_feature)]
+
+#[inline]
+#[target_feature = "+avx"]
+fn set_low_bit(x: u32) -> u32 {
+    x | 1
+}
+
+#[inline]
+fn pause() {
+    unsafe { asm!("pause" :::: "volatile") }
+}
+
+fn without_avx(x: u32) -> u32 {
+    set_low_bit(x)
+}
+
+#[target_feature = "+avx"]
+fn with_avx(x: u32) -> u32 {
+    set_low_bit(x)
+}
+
+fn spin() {
+    pause()
+}
+
+fn main() {
+    without_avx(2);
+    with_avx(2);
+    spin();
+}
+
+// END RUST SOURCE
+// START rustc.without_avx.Inline.after.mir
+// ...
+// bb0: {
+//     ...
+//     _0 = const set_low_bit(move _2) -> bb1;
+// }
+// ...
+// END rustc.without_avx.Inline.after.mir
+// START rustc.with_avx.Inline.after.mir
+// ...
+// bb0: {
+//     ...
+//     _0 = BitOr(move _3, const 1u32);
+//     ...
+//     return;
+// }
+// ...
+// END rustc.with_avx.Inline.after.mir
+// START rustc.spin.Inline.after.mir
+// ...
+// bb0: {
+//     _0 = const pause() -> bb1;
+// }
+// ...
+// END rustc.spin.Inline.after.mir
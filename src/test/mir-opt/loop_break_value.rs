@@ -0,0 +1,46 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that `break value` stores the value directly into the destination
+// of the `loop`, and that a `loop` which is never broken out of doesn't get
+// a temporary of its own.
+
+fn compute() -> u32 {
+    42
+}
+
+fn main() {
+    let x = loop { break compute(); };
+    drop(x);
+    never_broken();
+}
+
+fn never_broken() -> u32 {
+    loop {
+        return 0;
+    }
+}
+
+// END RUST SOURCE
+// START rustc.main.SimplifyCfg-initial.after.mir
+//     bb0: {
+//         StorageLive(_1);
+//         _1 = const compute() -> bb1;
+//     }
+// END rustc.main.SimplifyCfg-initial.after.mir
+// START rustc.never_broken.SimplifyCfg-initial.after.mir
+//     let mut _0: u32;
+//     let mut _1: ();
+//
+//     bb0: {
+//         _0 = const 0u32;
+//         return;
+//     }
+// END rustc.never_broken.SimplifyCfg-initial.after.mir
@@ -94,18 +94,18 @@ fn main() {
 //     ...
 //     _1 = move (_14.0: i128);
 //     ...
-//     assert(!move (_2.1: bool), "attempt to add with overflow") -> bb1;
+//     assert(!move (_2.1: bool), "attempt to add with overflow in `x += 1`") -> bb1;
 //     ...
-//     assert(!move (_3.1: bool), "attempt to subtract with overflow") -> bb2;
+//     assert(!move (_3.1: bool), "attempt to subtract with overflow in `x -= 2`") -> bb2;
 //     ...
-//     assert(!move (_4.1: bool), "attempt to multiply with overflow") -> bb3;
+//     assert(!move (_4.1: bool), "attempt to multiply with overflow in `x *= 3`") -> bb3;
 //     ...
-//     assert(!move (_13.1: bool), "attempt to shift left with overflow") -> bb8;
+//     assert(!move (_13.1: bool), "attempt to shift left with overflow in `x <<= 6`") -> bb8;
 //     ...
 //     _16 = const 6i32 as u128 (Misc);
 //     _13 = const i128_shlo(_1, move _16) -> bb14;
 //     ...
-//     assert(!move (_14.1: bool), "attempt to shift right with overflow") -> bb9;
+//     assert(!move (_14.1: bool), "attempt to shift right with overflow in `x >>= 7`") -> bb9;
 // END rustc.test_signed.Lower128Bit.after.mir
 
 // START rustc.test_unsigned.Lower128Bit.after.mir
@@ -130,16 +130,16 @@ fn main() {
 //     ...
 //     _1 = move (_8.0: u128);
 //     ...
-//     assert(!move (_2.1: bool), "attempt to add with overflow") -> bb1;
+//     assert(!move (_2.1: bool), "attempt to add with overflow in `x += 1`") -> bb1;
 //     ...
-//     assert(!move (_3.1: bool), "attempt to subtract with overflow") -> bb2;
+//     assert(!move (_3.1: bool), "attempt to subtract with overflow in `x -= 2`") -> bb2;
 //     ...
-//     assert(!move (_4.1: bool), "attempt to multiply with overflow") -> bb3;
+//     assert(!move (_4.1: bool), "attempt to multiply with overflow in `x *= 3`") -> bb3;
 //     ...
-//     assert(!move (_7.1: bool), "attempt to shift left with overflow") -> bb6;
+//     assert(!move (_7.1: bool), "attempt to shift left with overflow in `x <<= 6`") -> bb6;
 //     ...
 //     _10 = const 6i32 as u128 (Misc);
 //     _7 = const u128_shlo(_1, move _10) -> bb12;
 //     ...
-//     assert(!move (_8.1: bool), "attempt to shift right with overflow") -> bb7;
+//     assert(!move (_8.1: bool), "attempt to shift right with overflow in `x >>= 7`") -> bb7;
 // END rustc.test_unsigned.Lower128Bit.after.mir
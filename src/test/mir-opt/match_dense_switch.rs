@@ -0,0 +1,128 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// ignore-tidy-linelength
+
+// Check that matches on C-like enums and integers are lowered to a single
+// `SwitchInt` listing every tested value, and that when a match tests every
+// variant of an enum or every value of an integer type, the last one is the
+// catch-all target instead of an unreachable block.
+
+enum Digit { Zero, One, Two, Three, Four, Five, Six, Seven, Eight, Nine }
+
+fn exhaustive(d: Digit) -> u32 {
+    match d {
+        Digit::Zero => 0,
+        Digit::One => 1,
+        Digit::Two => 2,
+        Digit::Three => 3,
+        Digit::Four => 4,
+        Digit::Five => 5,
+        Digit::Six => 6,
+        Digit::Seven => 7,
+        Digit::Eight => 8,
+        Digit::Nine => 9,
+    }
+}
+
+fn wildcard(d: Digit) -> u32 {
+    match d {
+        Digit::Zero => 0,
+        Digit::One => 1,
+        Digit::Two => 2,
+        _ => 3,
+    }
+}
+
+fn integer(x: u8) -> u32 {
+    match x {
+        0 => 10,
+        1 => 11,
+        2 => 12,
+        _ => 0,
+    }
+}
+
+fn every_u8(x: u8) -> u8 {
+    match x {
+        0 => 1, 1 => 2, 2 => 3, 3 => 4, 4 => 5, 5 => 6, 6 => 7, 7 => 8,
+        8 => 9, 9 => 10, 10 => 11, 11 => 12, 12 => 13, 13 => 14, 14 => 15, 15 => 16,
+        16 => 17, 17 => 18, 18 => 19, 19 => 20, 20 => 21, 21 => 22, 22 => 23, 23 => 24,
+        24 => 25, 25 => 26, 26 => 27, 27 => 28, 28 => 29, 29 => 30, 30 => 31, 31 => 32,
+        32 => 33, 33 => 34, 34 => 35, 35 => 36, 36 => 37, 37 => 38, 38 => 39, 39 => 40,
+        40 => 41, 41 => 42, 42 => 43, 43 => 44, 44 => 45, 45 => 46, 46 => 47, 47 => 48,
+        48 => 49, 49 => 50, 50 => 51, 51 => 52, 52 => 53, 53 => 54, 54 => 55, 55 => 56,
+        56 => 57, 57 => 58, 58 => 59, 59 => 60, 60 => 61, 61 => 62, 62 => 63, 63 => 64,
+        64 => 65, 65 => 66, 66 => 67, 67 => 68, 68 => 69, 69 => 70, 70 => 71, 71 => 72,
+        72 => 73, 73 => 74, 74 => 75, 75 => 76, 76 => 77, 77 => 78, 78 => 79, 79 => 80,
+        80 => 81, 81 => 82, 82 => 83, 83 => 84, 84 => 85, 85 => 86, 86 => 87, 87 => 88,
+        88 => 89, 89 => 90, 90 => 91, 91 => 92, 92 => 93, 93 => 94, 94 => 95, 95 => 96,
+        96 => 97, 97 => 98, 98 => 99, 99 => 100, 100 => 101, 101 => 102, 102 => 103, 103 => 104,
+        104 => 105, 105 => 106, 106 => 107, 107 => 108, 108 => 109, 109 => 110, 110 => 111, 111 => 112,
+        112 => 113, 113 => 114, 114 => 115, 115 => 116, 116 => 117, 117 => 118, 118 => 119, 119 => 120,
+        120 => 121, 121 => 122, 122 => 123, 123 => 124, 124 => 125, 125 => 126, 126 => 127, 127 => 128,
+        128 => 129, 129 => 130, 130 => 131, 131 => 132, 132 => 133, 133 => 134, 134 => 135, 135 => 136,
+        136 => 137, 137 => 138, 138 => 139, 139 => 140, 140 => 141, 141 => 142, 142 => 143, 143 => 144,
+        144 => 145, 145 => 146, 146 => 147, 147 => 148, 148 => 149, 149 => 150, 150 => 151, 151 => 152,
+        152 => 153, 153 => 154, 154 => 155, 155 => 156, 156 => 157, 157 => 158, 158 => 159, 159 => 160,
+        160 => 161, 161 => 162, 162 => 163, 163 => 164, 164 => 165, 165 => 166, 166 => 167, 167 => 168,
+        168 => 169, 169 => 170, 170 => 171, 171 => 172, 172 => 173, 173 => 174, 174 => 175, 175 => 176,
+        176 => 177, 177 => 178, 178 => 179, 179 => 180, 180 => 181, 181 => 182, 182 => 183, 183 => 184,
+        184 => 185, 185 => 186, 186 => 187, 187 => 188, 188 => 189, 189 => 190, 190 => 191, 191 => 192,
+        192 => 193, 193 => 194, 194 => 195, 195 => 196, 196 => 197, 197 => 198, 198 => 199, 199 => 200,
+        200 => 201, 201 => 202, 202 => 203, 203 => 204, 204 => 205, 205 => 206, 206 => 207, 207 => 208,
+        208 => 209, 209 => 210, 210 => 211, 211 => 212, 212 => 213, 213 => 214, 214 => 215, 215 => 216,
+        216 => 217, 217 => 218, 218 => 219, 219 => 220, 220 => 221, 221 => 222, 222 => 223, 223 => 224,
+        224 => 225, 225 => 226, 226 => 227, 227 => 228, 228 => 229, 229 => 230, 230 => 231, 231 => 232,
+        232 => 233, 233 => 234, 234 => 235, 235 => 236, 236 => 237, 237 => 238, 238 => 239, 239 => 240,
+        240 => 241, 241 => 242, 242 => 243, 243 => 244, 244 => 245, 245 => 246, 246 => 247, 247 => 248,
+        248 => 249, 249 => 250, 250 => 251, 251 => 252, 252 => 253, 253 => 254, 254 => 255, 255 => 0,
+        _ => 0,
+    }
+}
+
+fn main() {
+    exhaustive(Digit::Nine);
+    wildcard(Digit::Five);
+    integer(1);
+    every_u8(7);
+}
+
+// END RUST SOURCE
+// START rustc.exhaustive.SimplifyBranches-initial.before.mir
+//  bb0: {
+//      ...
+//      _2 = discriminant(_1);
+//      switchInt(move _2) -> [0isize: bb11, 1isize: bb12, 2isize: bb13, 3isize: bb14, 4isize: bb15, 5isize: bb16, 6isize: bb17, 7isize: bb18, 8isize: bb19, otherwise: bb20];
+//  }
+// END rustc.exhaustive.SimplifyBranches-initial.before.mir
+// START rustc.wildcard.SimplifyBranches-initial.before.mir
+//  bb0: {
+//      ...
+//      _2 = discriminant(_1);
+//      switchInt(move _2) -> [0isize: bb5, 1isize: bb6, 2isize: bb7, otherwise: bb8];
+//  }
+//  ...
+//  bb8: {
+//      goto -> bb4;
+//  }
+// END rustc.wildcard.SimplifyBranches-initial.before.mir
+// START rustc.integer.SimplifyBranches-initial.before.mir
+//  bb0: {
+//      ...
+//      switchInt(_1) -> [0u8: bb5, 1u8: bb6, 2u8: bb7, otherwise: bb8];
+//  }
+// END rustc.integer.SimplifyBranches-initial.before.mir
+// START rustc.every_u8.SimplifyBranches-initial.before.mir
+//  bb0: {
+//      ...
+//      switchInt(_1) -> [0u8: bb258, 1u8: bb259, 2u8: bb260, 3u8: bb261, 4u8: bb262, 5u8: bb263, 6u8: bb264, 7u8: bb265, 8u8: bb266, 9u8: bb267, 10u8: bb268, 11u8: bb269, 12u8: bb270, 13u8: bb271, 14u8: bb272, 15u8: bb273, 16u8: bb274, 17u8: bb275, 18u8: bb276, 19u8: bb277, 20u8: bb278, 21u8: bb279, 22u8: bb280, 23u8: bb281, 24u8: bb282, 25u8: bb283, 26u8: bb284, 27u8: bb285, 28u8: bb286, 29u8: bb287, 30u8: bb288, 31u8: bb289, 32u8: bb290, 33u8: bb291, 34u8: bb292, 35u8: bb293, 36u8: bb294, 37u8: bb295, 38u8: bb296, 39u8: bb297, 40u8: bb298, 41u8: bb299, 42u8: bb300, 43u8: bb301, 44u8: bb302, 45u8: bb303, 46u8: bb304, 47u8: bb305, 48u8: bb306, 49u8: bb307, 50u8: bb308, 51u8: bb309, 52u8: bb310, 53u8: bb311, 54u8: bb312, 55u8: bb313, 56u8: bb314, 57u8: bb315, 58u8: bb316, 59u8: bb317, 60u8: bb318, 61u8: bb319, 62u8: bb320, 63u8: bb321, 64u8: bb322, 65u8: bb323, 66u8: bb324, 67u8: bb325, 68u8: bb326, 69u8: bb327, 70u8: bb328, 71u8: bb329, 72u8: bb330, 73u8: bb331, 74u8: bb332, 75u8: bb333, 76u8: bb334, 77u8: bb335, 78u8: bb336, 79u8: bb337, 80u8: bb338, 81u8: bb339, 82u8: bb340, 83u8: bb341, 84u8: bb342, 85u8: bb343, 86u8: bb344, 87u8: bb345, 88u8: bb346, 89u8: bb347, 90u8: bb348, 91u8: bb349, 92u8: bb350, 93u8: bb351, 94u8: bb352, 95u8: bb353, 96u8: bb354, 97u8: bb355, 98u8: bb356, 99u8: bb357, 100u8: bb358, 101u8: bb359, 102u8: bb360, 103u8: bb361, 104u8: bb362, 105u8: bb363, 106u8: bb364, 107u8: bb365, 108u8: bb366, 109u8: bb367, 110u8: bb368, 111u8: bb369, 112u8: bb370, 113u8: bb371, 114u8: bb372, 115u8: bb373, 116u8: bb374, 117u8: bb375, 118u8: bb376, 119u8: bb377, 120u8: bb378, 121u8: bb379, 122u8: bb380, 123u8: bb381, 124u8: bb382, 125u8: bb383, 126u8: bb384, 127u8: bb385, 128u8: bb386, 129u8: bb387, 130u8: bb388, 131u8: bb389, 132u8: bb390, 133u8: bb391, 134u8: bb392, 135u8: bb393, 136u8: bb394, 137u8: bb395, 138u8: bb396, 139u8: bb397, 140u8: bb398, 141u8: bb399, 142u8: bb400, 143u8: bb401, 144u8: bb402, 145u8: bb403, 146u8: bb404, 147u8: bb405, 148u8: bb406, 149u8: bb407, 150u8: bb408, 151u8: bb409, 152u8: bb410, 153u8: bb411, 154u8: bb412, 155u8: bb413, 156u8: bb414, 157u8: bb415, 158u8: bb416, 159u8: bb417, 160u8: bb418, 161u8: bb419, 162u8: bb420, 163u8: bb421, 164u8: bb422, 165u8: bb423, 166u8: bb424, 167u8: bb425, 168u8: bb426, 169u8: bb427, 170u8: bb428, 171u8: bb429, 172u8: bb430, 173u8: bb431, 174u8: bb432, 175u8: bb433, 176u8: bb434, 177u8: bb435, 178u8: bb436, 179u8: bb437, 180u8: bb438, 181u8: bb439, 182u8: bb440, 183u8: bb441, 184u8: bb442, 185u8: bb443, 186u8: bb444, 187u8: bb445, 188u8: bb446, 189u8: bb447, 190u8: bb448, 191u8: bb449, 192u8: bb450, 193u8: bb451, 194u8: bb452, 195u8: bb453, 196u8: bb454, 197u8: bb455, 198u8: bb456, 199u8: bb457, 200u8: bb458, 201u8: bb459, 202u8: bb460, 203u8: bb461, 204u8: bb462, 205u8: bb463, 206u8: bb464, 207u8: bb465, 208u8: bb466, 209u8: bb467, 210u8: bb468, 211u8: bb469, 212u8: bb470, 213u8: bb471, 214u8: bb472, 215u8: bb473, 216u8: bb474, 217u8: bb475, 218u8: bb476, 219u8: bb477, 220u8: bb478, 221u8: bb479, 222u8: bb480, 223u8: bb481, 224u8: bb482, 225u8: bb483, 226u8: bb484, 227u8: bb485, 228u8: bb486, 229u8: bb487, 230u8: bb488, 231u8: bb489, 232u8: bb490, 233u8: bb491, 234u8: bb492, 235u8: bb493, 236u8: bb494, 237u8: bb495, 238u8: bb496, 239u8: bb497, 240u8: bb498, 241u8: bb499, 242u8: bb500, 243u8: bb501, 244u8: bb502, 245u8: bb503, 246u8: bb504, 247u8: bb505, 248u8: bb506, 249u8: bb507, 250u8: bb508, 251u8: bb509, 252u8: bb510, 253u8: bb511, 254u8: bb512, otherwise: bb513];
+//  }
+// END rustc.every_u8.SimplifyBranches-initial.before.mir
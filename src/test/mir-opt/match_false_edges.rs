@@ -49,29 +49,29 @@ fn main() {
 
 // END RUST SOURCE
 //
-// START rustc.full_tested_match.SimplifyBranches-initial.before.mir
+// START rustc.full_tested_match.CleanupPostBorrowck.before.mir
 //  bb0: {
 //      ...
 //      _2 = std::option::Option<i32>::Some(const 42i32,);
 //      _5 = discriminant(_2);
-//      switchInt(move _5) -> [0isize: bb5, 1isize: bb3, otherwise: bb7];
+//      switchInt(move _5) -> [0isize: bb5, otherwise: bb3];
 //  }
 //  bb1: { // arm1
-//      StorageLive(_7);
-//      _7 = _3;
-//      _1 = (const 1i32, move _7);
-//      StorageDead(_7);
-//      goto -> bb12;
+//      StorageLive(_9);
+//      _9 = _3;
+//      _1 = (const 1i32, move _9);
+//      StorageDead(_9);
+//      goto -> bb11;
 //  }
 //  bb2: { // binding3(empty) and arm3
 //      _1 = (const 3i32, const 3i32);
-//      goto -> bb12;
+//      goto -> bb11;
 //  }
 //  bb3: {
-//      falseEdges -> [real: bb8, imaginary: bb4]; //pre_binding1
+//      falseEdges -> [real: bb7, imaginary: bb4]; //pre_binding1
 //  }
 //  bb4: {
-//      falseEdges -> [real: bb11, imaginary: bb5]; //pre_binding2
+//      falseEdges -> [real: bb10, imaginary: bb5]; //pre_binding2
 //  }
 //  bb5: {
 //      falseEdges -> [real: bb2, imaginary: bb6]; //pre_binding3
@@ -79,97 +79,134 @@ fn main() {
 //  bb6: {
 //      unreachable;
 //  }
-//  bb7: {
-//      unreachable;
-//  }
-//  bb8: { // binding1 and guard
+//  bb7: { // binding1 and guard
 //      StorageLive(_3);
 //      _3 = ((_2 as Some).0: i32);
-//      StorageLive(_6);
-//      _6 = const guard() -> bb9;
+//      _6 = &_2;
+//      StorageLive(_7);
+//      _7 = const guard() -> bb8;
 //  }
-//  bb9: { // end of guard
-//      switchInt(move _6) -> [0u8: bb10, otherwise: bb1];
+//  bb8: { // end of guard
+//      _8 = &(*_6);
+//      EndRegion();
+//      switchInt(move _7) -> [0u8: bb9, otherwise: bb1];
 //  }
-//  bb10: { // to pre_binding2
+//  bb9: { // to pre_binding2
 //      falseEdges -> [real: bb4, imaginary: bb4];
 //  }
-//  bb11: { // bindingNoLandingPads.before.mir2 and arm2
+//  bb10: { // bindingNoLandingPads.before.mir2 and arm2
 //      StorageLive(_4);
 //      _4 = ((_2 as Some).0: i32);
-//      StorageLive(_8);
-//      _8 = _4;
-//      _1 = (const 2i32, move _8);
-//      StorageDead(_8);
-//      goto -> bb12;
+//      StorageLive(_10);
+//      _10 = _4;
+//      _1 = (const 2i32, move _10);
+//      StorageDead(_10);
+//      goto -> bb11;
 //  }
-//  bb12: {
+//  bb11: {
 //      ...
 //      return;
 //  }
-// END rustc.full_tested_match.SimplifyBranches-initial.before.mir
+// END rustc.full_tested_match.CleanupPostBorrowck.before.mir
 //
-// START rustc.full_tested_match2.SimplifyBranches-initial.before.mir
+// START rustc.full_tested_match.CleanupPostBorrowck.after.mir
+//  bb0: {
+//      ...
+//      _5 = discriminant(_2);
+//      switchInt(move _5) -> [0isize: bb5, otherwise: bb3];
+//  }
+//  ...
+//  bb3: {
+//      goto -> bb7;
+//  }
+//  bb4: {
+//      goto -> bb10;
+//  }
+//  bb5: {
+//      goto -> bb2;
+//  }
+//  bb6: {
+//      unreachable;
+//  }
+//  bb7: {
+//      StorageLive(_3);
+//      _3 = ((_2 as Some).0: i32);
+//      nop;
+//      StorageLive(_7);
+//      _7 = const guard() -> bb8;
+//  }
+//  bb8: {
+//      nop;
+//      EndRegion();
+//      switchInt(move _7) -> [0u8: bb9, otherwise: bb1];
+//  }
+//  bb9: {
+//      goto -> bb4;
+//  }
+//  ...
+// END rustc.full_tested_match.CleanupPostBorrowck.after.mir
+//
+// START rustc.full_tested_match2.CleanupPostBorrowck.before.mir
 //  bb0: {
 //      ...
 //      _2 = std::option::Option<i32>::Some(const 42i32,);
 //      _5 = discriminant(_2);
-//      switchInt(move _5) -> [0isize: bb4, 1isize: bb3, otherwise: bb7];
+//      switchInt(move _5) -> [0isize: bb4, otherwise: bb3];
 //  }
 //  bb1: { // arm1
-//      StorageLive(_7);
-//      _7 = _3;
-//      _1 = (const 1i32, move _7);
-//      StorageDead(_7);
-//      goto -> bb12;
+//      StorageLive(_9);
+//      _9 = _3;
+//      _1 = (const 1i32, move _9);
+//      StorageDead(_9);
+//      goto -> bb11;
 //  }
 //  bb2: { // binding3(empty) and arm3
 //      _1 = (const 3i32, const 3i32);
-//      goto -> bb12;
+//      goto -> bb11;
 //  }
 //  bb3: {
-//      falseEdges -> [real: bb8, imaginary: bb4]; //pre_binding1
+//      falseEdges -> [real: bb7, imaginary: bb4]; //pre_binding1
 //  }
 //  bb4: {
 //      falseEdges -> [real: bb2, imaginary: bb5]; //pre_binding2
 //  }
 //  bb5: {
-//      falseEdges -> [real: bb11, imaginary: bb6]; //pre_binding3
+//      falseEdges -> [real: bb10, imaginary: bb6]; //pre_binding3
 //  }
 //  bb6: {
 //      unreachable;
 //  }
-//  bb7: {
-//      unreachable;
-//  }
-//  bb8: { // binding1 and guard
+//  bb7: { // binding1 and guard
 //      StorageLive(_3);
 //      _3 = ((_2 as Some).0: i32);
-//      StorageLive(_6);
-//      _6 = const guard() -> bb9;
+//      _6 = &_2;
+//      StorageLive(_7);
+//      _7 = const guard() -> bb8;
 //  }
-//  bb9: { // end of guard
-//      switchInt(move _6) -> [0u8: bb10, otherwise: bb1];
+//  bb8: { // end of guard
+//      _8 = &(*_6);
+//      EndRegion();
+//      switchInt(move _7) -> [0u8: bb9, otherwise: bb1];
 //  }
-//  bb10: { // to pre_binding2
+//  bb9: { // to pre_binding2
 //      falseEdges -> [real: bb5, imaginary: bb4];
 //  }
-//  bb11: { // binding2 and arm2
+//  bb10: { // binding2 and arm2
 //      StorageLive(_4);
 //      _4 = ((_2 as Some).0: i32);
-//      StorageLive(_8);
-//      _8 = _4;
-//      _1 = (const 2i32, move _8);
-//      StorageDead(_8);
-//      goto -> bb12;
+//      StorageLive(_10);
+//      _10 = _4;
+//      _1 = (const 2i32, move _10);
+//      StorageDead(_10);
+//      goto -> bb11;
 //  }
-//  bb12: {
+//  bb11: {
 //      ...
 //      return;
 //  }
-// END rustc.full_tested_match2.SimplifyBranches-initial.before.mir
+// END rustc.full_tested_match2.CleanupPostBorrowck.before.mir
 //
-// START rustc.main.SimplifyBranches-initial.before.mir
+// START rustc.main.CleanupPostBorrowck.before.mir
 // bb0: {
 //     ...
 //     _2 = std::option::Option<i32>::Some(const 1i32,);
@@ -203,11 +240,14 @@ fn main() {
 //  bb8: { // binding1: Some(w) if guard()
 //      StorageLive(_3);
 //      _3 = ((_2 as Some).0: i32);
-//      StorageLive(_8);
-//      _8 = const guard() -> bb9;
+//      _8 = &_2;
+//      StorageLive(_9);
+//      _9 = const guard() -> bb9;
 //  }
 //  bb9: { //end of guard
-//      switchInt(move _8) -> [0u8: bb10, otherwise: bb1];
+//      _10 = &(*_8);
+//      EndRegion();
+//      switchInt(move _9) -> [0u8: bb10, otherwise: bb1];
 //  }
 //  bb10: { // to pre_binding2
 //      falseEdges -> [real: bb4, imaginary: bb4];
@@ -221,14 +261,17 @@ fn main() {
 //  bb12: { // binding3: Some(y) if guard2(y)
 //      StorageLive(_5);
 //      _5 = ((_2 as Some).0: i32);
-//      StorageLive(_10);
-//      StorageLive(_11);
-//      _11 = _5;
-//      _10 = const guard2(move _11) -> bb13;
+//      _11 = &_2;
+//      StorageLive(_12);
+//      StorageLive(_13);
+//      _13 = _5;
+//      _12 = const guard2(move _13) -> bb13;
 //  }
 //  bb13: { // end of guard2
-//      StorageDead(_11);
-//      switchInt(move _10) -> [0u8: bb14, otherwise: bb2];
+//      StorageDead(_13);
+//      _14 = &(*_11);
+//      EndRegion();
+//      switchInt(move _12) -> [0u8: bb14, otherwise: bb2];
 //  }
 //  bb14: { // to pre_binding4
 //      falseEdges -> [real: bb6, imaginary: bb6];
@@ -243,4 +286,4 @@ fn main() {
 //     ...
 //     return;
 // }
-// END rustc.main.SimplifyBranches-initial.before.mir
+// END rustc.main.CleanupPostBorrowck.before.mir
@@ -0,0 +1,46 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that the last copy of a local of `Copy` type becomes a move, that
+// the moved local is passed to the call in place of the temporary, and that
+// earlier copies and copies of borrowed locals are left alone.
+
+fn sink(_: [u8; 4096]) {}
+
+fn main() {
+    let a = [1u8; 4096];
+    sink(a);
+    sink(a);
+    let b = [2u8; 4096];
+    let r = &b;
+    sink(b);
+    drop(r);
+}
+
+// END RUST SOURCE
+// START rustc.main.MoveLastUses.before.mir
+//     ...
+//     _3 = _1;
+//     ...
+//     _5 = _1;
+//     ...
+//     _9 = _6;
+//     ...
+// END rustc.main.MoveLastUses.before.mir
+// START rustc.main.MoveLastUses.after.mir
+//     ...
+//     _3 = _1;
+//     ...
+//     nop;
+//     _4 = const sink(move _1) -> bb2;
+//     ...
+//     _9 = _6;
+//     ...
+// END rustc.main.MoveLastUses.after.mir
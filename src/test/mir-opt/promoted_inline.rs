@@ -0,0 +1,49 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// Check that the promoted constants of a body are dumped after it.
+
+fn consume(_: &[i32], _: &[i32]) {}
+
+fn main() {
+    let x: &[i32] = &[1, 2, 3];
+    let y: &[i32] = &[4, 5];
+    consume(x, y);
+}
+
+// END RUST SOURCE
+// START rustc.main.QualifyAndPromoteConstants.after.mir
+// fn main() -> () {
+// ...
+// promoted[0] in main: &[i32; 2] = {
+// ...
+//     bb0: {
+//         _1 = [const 4i32, const 5i32];
+//         _0 = &_1;
+//         return;
+//     }
+// }
+// promoted[1] in main: &[i32; 3] = {
+// ...
+//     bb0: {
+//         _1 = [const 1i32, const 2i32, const 3i32];
+//         _0 = &_1;
+//         return;
+//     }
+// }
+// END rustc.main.QualifyAndPromoteConstants.after.mir
+// START rustc.main.SimplifyCfg-qualify-consts.before.mir
+// ...
+// promoted[0] in main: &[i32; 2] = {
+// ...
+// promoted[1] in main: &[i32; 3] = {
+// ...
+// END rustc.main.SimplifyCfg-qualify-consts.before.mir
@@ -0,0 +1,28 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that the payload of the success arm of `expr?` is stored directly
+// into the destination of the `?` expression, without a local of its own.
+
+fn first(x: Result<u32, i32>) -> Result<u32, i32> {
+    let y = x?;
+    Ok(y)
+}
+
+fn main() {
+    let _ = first(Ok(1));
+}
+
+// END RUST SOURCE
+// START rustc.first.SimplifyCfg-initial.after.mir
+//     ...
+//     _2 = ((_3 as Ok).0: u32);
+//     ...
+// END rustc.first.SimplifyCfg-initial.after.mir
@@ -52,8 +52,8 @@ fn main() {
 //     }
 //     bb1: {
 //         Validate(Acquire, [_3: ()]);
-//         EndRegion(ReScope(Node(ItemLocalId(17))));
 //         StorageDead(_4);
+//         EndRegion(ReScope(Node(ItemLocalId(17))));
 //         StorageDead(_5);
 //         _0 = ();
 //         EndRegion(ReScope(Remainder(BlockRemainder { block: ItemLocalId(19), first_statement_index: 3 })));
@@ -0,0 +1,40 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z verbose -Z mir-emit-validate=2 -Z mir-emit-validate-skip=unions
+
+// Make sure unions and their fields are not validated when skipped.
+
+union U {
+    a: u32,
+    b: f32,
+}
+
+fn first(u: U) -> u32 {
+    let r = unsafe { &u.a };
+    *r
+}
+
+fn main() {
+    first(U { a: 1 });
+}
+
+// END RUST SOURCE
+// START rustc.first.EraseRegions.after.mir
+// fn first(_1: U) -> u32 {
+//     ...
+//     bb0: {
+//         StorageLive(_2);
+//         _2 = &ReErased (_1.0: u32);
+//         ...
+//         return;
+//     }
+// }
+// END rustc.first.EraseRegions.after.mir
@@ -0,0 +1,42 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z verbose -Z mir-emit-validate=2 -Z mir-emit-validate-skip=packed
+
+// Make sure fields of packed structs are not validated when skipped, but the structs are.
+
+#[repr(packed)]
+struct P {
+    a: u8,
+    b: u32,
+}
+
+fn second(p: P) -> u32 {
+    let r = unsafe { &p.b };
+    *r
+}
+
+fn main() {
+    second(P { a: 1, b: 2 });
+}
+
+// END RUST SOURCE
+// START rustc.second.EraseRegions.after.mir
+// fn second(_1: P) -> u32 {
+//     ...
+//     bb0: {
+//         Validate(Acquire, [_1: P]);
+//         StorageLive(_2);
+//         _2 = &ReErased (_1.1: u32);
+//         ...
+//         return;
+//     }
+// }
+// END rustc.second.EraseRegions.after.mir
@@ -0,0 +1,37 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z verbose -Z mir-emit-validate=2 -Z mir-emit-validate-skip=raw-ptrs
+
+// Make sure the contents behind raw pointers are not validated when skipped, but the
+// pointers are.
+
+fn third(p: *const u32) -> u32 {
+    let r = unsafe { &*p };
+    *r
+}
+
+fn main() {
+    third(&0);
+}
+
+// END RUST SOURCE
+// START rustc.third.EraseRegions.after.mir
+// fn third(_1: *const u32) -> u32 {
+//     ...
+//     bb0: {
+//         Validate(Acquire, [_1: *const u32]);
+//         StorageLive(_2);
+//         _2 = &ReErased (*_1);
+//         ...
+//         return;
+//     }
+// }
+// END rustc.third.EraseRegions.after.mir
@@ -0,0 +1,50 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z verbose -Z mir-emit-validate=2
+
+// Make sure `#[rustc_no_mir_validate]` suppresses validation of a function.
+
+#![feature(rustc_attrs)]
+
+#[rustc_no_mir_validate]
+fn skipped(x: u32) -> u32 {
+    x
+}
+
+fn checked(x: u32) -> u32 {
+    x
+}
+
+fn main() {
+    skipped(0);
+    checked(0);
+}
+
+// END RUST SOURCE
+// START rustc.skipped.EraseRegions.after.mir
+// fn skipped(_1: u32) -> u32 {
+//     ...
+//     bb0: {
+//         _0 = _1;
+//         return;
+//     }
+// }
+// END rustc.skipped.EraseRegions.after.mir
+// START rustc.checked.EraseRegions.after.mir
+// fn checked(_1: u32) -> u32 {
+//     ...
+//     bb0: {
+//         Validate(Acquire, [_1: u32]);
+//         _0 = _1;
+//         return;
+//     }
+// }
+// END rustc.checked.EraseRegions.after.mir
@@ -0,0 +1,24 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// error-pattern:mir-inline-panic-location.rs:19:5
+// compile-flags: -C debug-assertions -Z mir-opt-level=2
+
+// Tests that an overflow in a function inlined at the MIR level reports the
+// line of the overflow in the inlined function, not the one of the call.
+
+#[inline(always)]
+fn add(x: u8, y: u8) -> u8 {
+    x + y
+}
+
+fn main() {
+    add(200, std::env::args().count() as u8 + 199);
+}
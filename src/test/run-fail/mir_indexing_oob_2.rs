@@ -10,6 +10,8 @@
 
 // error-pattern:index out of bounds: the len is 5 but the index is 10
 
+#![allow(unconditional_panic)]
+
 const C: &'static [u8; 5] = b"hello";
 
 fn test() -> u8 {
@@ -0,0 +1,25 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// error-pattern:thread 'main' panicked at 'attempt to add with overflow in `total + item.weight`'
+// compile-flags: -C debug-assertions
+
+struct Item {
+    weight: u8,
+}
+
+fn main() {
+    let items = [Item { weight: 100 }, Item { weight: 100 }, Item { weight: 100 }];
+    let mut total = 0u8;
+    for item in &items {
+        total = total + item.weight;
+    }
+    println!("{}", total);
+}
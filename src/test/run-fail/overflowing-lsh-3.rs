@@ -8,7 +8,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-// error-pattern:thread 'main' panicked at 'attempt to shift left with overflow'
+// error-pattern:thread 'main' panicked at 'attempt to shift left with overflow in `1_u64 << 64`'
 // compile-flags: -C debug-assertions
 
 #![warn(exceeding_bitshifts)]
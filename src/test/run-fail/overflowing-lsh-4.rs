@@ -8,7 +8,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-// error-pattern:thread 'main' panicked at 'attempt to shift left with overflow'
+// error-pattern:thread 'main' panicked at 'attempt to shift left with overflow in `1_i8 << 17`'
 // compile-flags: -C debug-assertions
 
 // This function is checking that our automatic truncation does not
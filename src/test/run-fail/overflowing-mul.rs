@@ -8,7 +8,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-// error-pattern:thread 'main' panicked at 'attempt to multiply with overflow'
+// error-pattern:thread 'main' panicked at 'attempt to multiply with overflow in `200u8 * 4`'
 // compile-flags: -C debug-assertions
 
 fn main() {
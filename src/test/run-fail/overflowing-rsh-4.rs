@@ -8,7 +8,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-// error-pattern:thread 'main' panicked at 'attempt to shift right with overflow'
+// error-pattern:thread 'main' panicked at 'attempt to shift right with overflow in `2_i8 >> 17`'
 // compile-flags: -C debug-assertions
 
 // This function is checking that our (type-based) automatic
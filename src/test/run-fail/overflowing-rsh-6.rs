@@ -8,7 +8,8 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-// error-pattern:thread 'main' panicked at 'attempt to shift right with overflow'
+// ignore-tidy-linelength
+// error-pattern:thread 'main' panicked at 'attempt to shift right with overflow in `1i64 >> [64][0]`'
 // compile-flags: -C debug-assertions
 
 #![warn(exceeding_bitshifts)]
@@ -8,7 +8,8 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-// error-pattern:thread 'main' panicked at 'attempt to subtract with overflow'
+// ignore-tidy-linelength
+// error-pattern:thread 'main' panicked at 'attempt to subtract with overflow in `42u8 - (42u8 + 1)`'
 // compile-flags: -C debug-assertions
 
 fn main() {
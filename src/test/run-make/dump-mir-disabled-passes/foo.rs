@@ -0,0 +1,26 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![feature(rustc_attrs)]
+
+fn validated(x: &mut u32) {
+    *x += 1;
+}
+
+#[rustc_no_mir_validate]
+fn skipped(x: &mut u32) {
+    *x += 1;
+}
+
+fn main() {
+    let mut x = 0;
+    validated(&mut x);
+    skipped(&mut x);
+}
@@ -0,0 +1,22 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Shifts the node ids of everything after it.
+#[cfg(extra)]
+fn extra() -> u32 {
+    let f = || 3;
+    f()
+}
+
+fn main() {
+    let f = |x: u32| x + 1;
+    let g = |x: u32| x + 2;
+    f(g(0));
+}
@@ -0,0 +1,63 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// Reads back the JSON dump of `answer` in foo.rs.
+
+#![feature(rustc_private)]
+
+extern crate serialize;
+
+use serialize::json::Json;
+use std::env;
+use std::fs::File;
+use std::io::Read;
+
+fn string<'a>(json: &'a Json, key: &str) -> &'a str {
+    json[key].as_string().unwrap()
+}
+
+fn main() {
+    let path = env::args().nth(1).unwrap();
+    let mut text = String::new();
+    File::open(&path).unwrap().read_to_string(&mut text).unwrap();
+    let dump = Json::from_str(&text).unwrap();
+
+    assert_eq!(dump["version"].as_u64(), Some(1));
+    assert_eq!(string(&dump, "item"), "answer");
+    assert_eq!(string(&dump, "pass_name"), "SimplifyCfg-after-const-prop");
+    assert_eq!(string(&dump, "disambiguator"), "after");
+    assert!(dump["promoted"].is_null());
+
+    let body = &dump["body"];
+    assert_eq!(body["arg_count"].as_u64(), Some(1));
+    let locals = body["locals"].as_array().unwrap();
+    assert_eq!(string(&locals[0], "kind"), "return");
+    assert!(string(&locals[0], "ty").ends_with("u32"));
+    assert_eq!(string(&locals[1], "kind"), "arg");
+    assert_eq!(string(&locals[1], "ty"), "u32");
+
+    let blocks = body["blocks"].as_array().unwrap();
+    let terminator = &blocks[0]["terminator"];
+    assert_eq!(string(terminator, "kind"), "SwitchInt");
+    let targets: Vec<_> = terminator["successors"].as_array().unwrap().iter()
+        .map(|successor| successor["target"].as_u64().unwrap() as usize)
+        .collect();
+    assert_eq!(targets.len(), 2);
+    assert!(targets.iter().all(|&target| target < blocks.len()));
+    assert!(blocks.iter().any(|block| string(&block["terminator"], "kind") == "Return"));
+
+    let span = &terminator["span"];
+    assert!(string(span, "file").ends_with("foo.rs"));
+    let line = span["line"].as_u64().unwrap();
+    assert!(line >= 12 && line <= 18);
+
+    assert_eq!(body["promoted"].as_array().unwrap().len(), 2);
+}
@@ -0,0 +1,31 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+fn big(x: u32) -> u32 {
+    let a = x;
+    let b = a;
+    let c = b;
+    let d = c;
+    let e = d;
+    let f = e;
+    let g = f;
+    let h = g;
+    h
+}
+
+fn small(x: u32) -> u32 {
+    let y = x;
+    y
+}
+
+fn main() {
+    big(0);
+    small(0);
+}
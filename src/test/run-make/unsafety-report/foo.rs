@@ -0,0 +1,34 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+#![crate_type = "lib"]
+
+pub union U {
+    pub a: u32,
+    pub b: f32,
+}
+
+pub fn deref(p: *const u32) -> u32 {
+    unsafe { *p }
+}
+
+pub unsafe fn field(u: U) -> f32 {
+    u.b
+}
+
+pub fn from_closure(p: *const u32) -> u32 {
+    let f = || unsafe { *p };
+    f()
+}
+
+pub fn safe() -> u32 {
+    0
+}
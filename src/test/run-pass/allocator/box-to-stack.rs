@@ -0,0 +1,111 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z mir-opt-level=2
+// no-prefer-dynamic
+
+// Boxes that don't escape their function are not allocated on the heap,
+// but their contents are still dropped.
+
+#![feature(global_allocator, heap_api, allocator_api)]
+
+use std::cell::Cell;
+use std::heap::{Alloc, AllocErr, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+
+static ALLOCS: AtomicUsize = ATOMIC_USIZE_INIT;
+
+struct A;
+
+unsafe impl<'a> Alloc for &'a A {
+    unsafe fn alloc(&mut self, layout: Layout) -> Result<*mut u8, AllocErr> {
+        ALLOCS.fetch_add(1, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&mut self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: A = A;
+
+struct Big {
+    data: [u64; 16],
+}
+
+struct Noisy<'a> {
+    drops: &'a Cell<usize>,
+}
+
+impl<'a> Drop for Noisy<'a> {
+    fn drop(&mut self) {
+        self.drops.set(self.drops.get() + 1);
+    }
+}
+
+#[inline(never)]
+fn sum(data: &[u64]) -> u64 {
+    data.iter().sum()
+}
+
+fn local_box() -> u64 {
+    let b = Box::new(Big { data: [3; 16] });
+    sum(&b.data)
+}
+
+fn moved_box() -> u64 {
+    let a = Box::new(Big { data: [1; 16] });
+    let b = a;
+    sum(&b.data)
+}
+
+fn dropped_box(drops: &Cell<usize>) {
+    let b = Box::new(Noisy { drops });
+    assert_eq!(b.drops.get(), 0);
+}
+
+// Each iteration allocates a new box while the one of the previous
+// iteration is still in `keep`, so the box must stay on the heap.
+fn box_kept_across_iterations() -> usize {
+    let mut keep;
+    let mut i = 0;
+    loop {
+        let b = Box::new(vec![i]);
+        keep = b;
+        i += 1;
+        if i == 3 {
+            break;
+        }
+    }
+    keep.len() + keep[0]
+}
+
+fn returned_box() -> Box<u64> {
+    Box::new(7)
+}
+
+fn main() {
+    let drops = Cell::new(0);
+
+    let n = ALLOCS.load(Ordering::SeqCst);
+    assert_eq!(local_box(), 48);
+    assert_eq!(moved_box(), 16);
+    dropped_box(&drops);
+    assert_eq!(ALLOCS.load(Ordering::SeqCst), n);
+    assert_eq!(drops.get(), 1);
+
+    assert_eq!(box_kept_across_iterations(), 3);
+
+    let b = returned_box();
+    assert_eq!(ALLOCS.load(Ordering::SeqCst), n + 1);
+    assert_eq!(*b, 7);
+}
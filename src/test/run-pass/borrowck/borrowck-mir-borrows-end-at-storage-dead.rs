@@ -0,0 +1,37 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// revisions: lxl nll
+//[lxl]compile-flags: -Z borrowck=mir
+//[nll]compile-flags: -Z borrowck=mir -Z nll
+
+// Borrows of a temporary end once its storage is dead, even if their
+// region is still in scope.
+
+fn first(v: &Vec<u32>) -> u32 {
+    v[0]
+}
+
+fn tail_temporaries(v: &mut Vec<u32>) {
+    for _ in 0..3 {
+        let n = {
+            let tmp = v.clone();
+            first(&tmp) + tmp.len() as u32
+        };
+        v.push(n);
+    }
+}
+
+fn main() {
+    let mut v = vec![1];
+    tail_temporaries(&mut v);
+    assert_eq!(v, [1, 2, 3, 4]);
+}
@@ -0,0 +1,50 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// compile-flags: -Z borrowck=mir
+
+// Fields behind the same `Box` are disjoint, just like the fields of a
+// struct held directly.
+
+#![feature(box_syntax)]
+
+struct S {
+    a: u32,
+    b: u32,
+    c: Box<(u32, u32)>,
+}
+
+fn main() {
+    let mut s: Box<S> = box S { a: 1, b: 2, c: box (3, 4) };
+
+    {
+        let a = &mut s.a;
+        let b = &mut s.b;
+        *a += 10;
+        *b += 20;
+    }
+
+    {
+        let c0 = &mut s.c.0;
+        let c1 = &s.c.1;
+        let a = &s.a;
+        *c0 += *c1 + *a;
+    }
+
+    {
+        let b = &mut s.b;
+        let c = s.c;
+        *b += c.1;
+    }
+
+    assert_eq!(s.a, 11);
+    assert_eq!(s.b, 26);
+}
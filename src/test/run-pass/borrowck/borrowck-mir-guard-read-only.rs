@@ -0,0 +1,63 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// revisions: lxl nll
+//[lxl]compile-flags: -Z borrowck=mir
+//[nll]compile-flags: -Z borrowck=mir -Z nll
+
+// Guards that only read the scrutinee are accepted, even though the
+// scrutinee is borrowed while they are evaluated.
+
+fn read_scrutinee(v: Vec<u32>) -> u32 {
+    match v.len() {
+        n if v.contains(&3) => n as u32,
+        n if n > 2 && v[0] == 1 => v[n - 1],
+        _ => 0,
+    }
+}
+
+fn shared_ref_binding(o: &mut Option<String>) -> usize {
+    match *o {
+        Some(ref s) if s.len() > o.as_ref().map_or(0, |s| s.len() - 1) => s.len(),
+        _ => 0,
+    }
+}
+
+fn shared_ref_binding_only_in_guard(v: &mut Vec<u32>) -> bool {
+    match *v {
+        ref w if w.len() > 1 && v[0] == 1 => true,
+        _ => false,
+    }
+}
+
+fn mut_ref_binding(o: &mut Option<Vec<u32>>) {
+    match *o {
+        Some(ref mut v) if v.is_empty() => v.push(0),
+        Some(ref mut v) => v.push(1),
+        None => {}
+    }
+}
+
+fn main() {
+    assert_eq!(read_scrutinee(vec![1, 2, 3]), 3);
+    assert_eq!(read_scrutinee(vec![1, 5, 6]), 6);
+    assert_eq!(read_scrutinee(vec![2]), 0);
+
+    assert_eq!(shared_ref_binding(&mut Some("abc".to_string())), 3);
+    assert_eq!(shared_ref_binding(&mut None), 0);
+
+    assert!(shared_ref_binding_only_in_guard(&mut vec![1, 2]));
+    assert!(!shared_ref_binding_only_in_guard(&mut vec![1]));
+
+    let mut o = Some(vec![]);
+    mut_ref_binding(&mut o);
+    mut_ref_binding(&mut o);
+    assert_eq!(o, Some(vec![0, 1]));
+}
@@ -0,0 +1,43 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// revisions: lxl nll
+//[lxl]compile-flags: -Z borrowck=mir -Z two-phase-borrows
+//[nll]compile-flags: -Z borrowck=mir -Z two-phase-borrows -Z nll
+
+// The `&mut` auto-ref of a method receiver is only reserved while the
+// arguments are evaluated, so the arguments may still read the receiver.
+
+struct Counter {
+    values: Vec<u32>,
+}
+
+impl Counter {
+    fn get(&self) -> u32 {
+        self.values.len() as u32
+    }
+
+    fn insert(&mut self, value: u32) {
+        self.values.push(value);
+    }
+}
+
+fn main() {
+    let mut v = vec![];
+    v.push(v.len());
+    v.push(v.len());
+    assert_eq!(v, [0, 1]);
+
+    let mut c = Counter { values: vec![] };
+    c.insert(c.get());
+    c.insert(c.get());
+    assert_eq!(c.values, [0, 1]);
+}
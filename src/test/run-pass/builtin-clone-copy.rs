@@ -0,0 +1,51 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Test that cloning arrays and tuples of `Copy` elements, which is done by
+// copying them as a whole, gives back the original value.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Bytes([u8; 64]);
+
+fn clone_generic<T: Clone>(arg: &T) -> T {
+    arg.clone()
+}
+
+fn main() {
+    let mut bytes = [0u8; 64];
+    for (i, b) in bytes.iter_mut().enumerate() {
+        *b = i as u8;
+    }
+    assert_eq!(Bytes(bytes).clone(), Bytes(bytes));
+    assert_eq!(clone_generic(&bytes)[..], bytes[..]);
+
+    let points = [Point { x: 1, y: 2 }, Point { x: 3, y: 4 }];
+    assert_eq!(points.clone(), points);
+    assert_eq!(clone_generic(&points), points);
+
+    let tuple = (1u8, 2u64, Point { x: 5, y: 6 }, [7u16; 3], ('a', 8.0f32));
+    assert_eq!(tuple.clone(), tuple);
+    assert_eq!(clone_generic(&tuple), tuple);
+
+    let nested = [(1u32, [2u8; 4]); 40];
+    assert_eq!(&nested.clone()[..], &nested[..]);
+
+    // Arrays and tuples of non-`Copy` elements are still cloned element-wise.
+    let strings = [String::from("a"), String::from("b")];
+    assert_eq!(strings.clone(), strings);
+    let mixed = (String::from("c"), 9u8);
+    assert_eq!(clone_generic(&mixed), mixed);
+}
@@ -0,0 +1,61 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that `if` and `match` on integers and booleans work in constants,
+// statics and `const fn`s under `#![feature(const_if_match)]`, whichever
+// branch is taken.
+
+#![feature(const_fn, const_if_match)]
+
+const fn abs(x: i32) -> i32 {
+    if x < 0 { -x } else { x }
+}
+
+const fn max(a: u32, b: u32) -> u32 {
+    if a > b { a } else { b }
+}
+
+const fn to_int(b: bool) -> u32 {
+    match b {
+        true => 1,
+        false => 0,
+    }
+}
+
+const fn digit_name(d: u8) -> &'static str {
+    match d {
+        0 => "zero",
+        1 => "one",
+        2...9 => "other",
+        _ => "none",
+    }
+}
+
+const fn both(a: bool, b: bool) -> bool {
+    a && b
+}
+
+const LIMIT: u32 = 10;
+const BIGGER: u32 = if LIMIT > 5 { LIMIT } else { 5 };
+const ABS: i32 = abs(-3);
+const MAX: u32 = max(to_int(true), BIGGER);
+const NAME: &'static str = digit_name(7);
+static ZERO: u32 = to_int(both(true, false));
+
+fn main() {
+    assert_eq!(BIGGER, 10);
+    assert_eq!(ABS, 3);
+    assert_eq!(abs(4), 4);
+    assert_eq!(MAX, 10);
+    assert_eq!(NAME, "other");
+    assert_eq!(digit_name(0), "zero");
+    assert_eq!(digit_name(10), "none");
+    assert_eq!(ZERO, 0);
+}
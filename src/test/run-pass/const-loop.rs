@@ -0,0 +1,41 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![feature(const_fn, const_if_match, const_loop)]
+
+const fn sum(n: u32) -> u32 {
+    let mut total = 0;
+    let mut i = 1;
+    while i <= n {
+        total += i;
+        i += 1;
+    }
+    total
+}
+
+const fn power_of_two_above(n: u32) -> u32 {
+    let mut power = 1;
+    loop {
+        if power > n {
+            return power;
+        }
+        power *= 2;
+    }
+}
+
+const SUM: u32 = sum(10);
+const POWER: u32 = power_of_two_above(SUM);
+
+fn main() {
+    assert_eq!(SUM, 55);
+    assert_eq!(POWER, 64);
+    assert_eq!(sum(100), 5050);
+    assert_eq!(power_of_two_above(100), 128);
+}
@@ -0,0 +1,83 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// Check that locals whose storage spans a yield are only saved in the
+// generator if they can actually be read after it is resumed.
+
+#![feature(generators, generator_trait)]
+
+use std::mem::size_of_val;
+use std::ops::{Generator, GeneratorState};
+
+fn consume(buf: [u8; 1024]) -> u8 {
+    buf[0]
+}
+
+fn main() {
+    // `buf` is fully consumed before the yield, so it stays on the stack.
+    let mut consumed = || {
+        let buf = [1u8; 1024];
+        let first = consume(buf);
+        yield first;
+    };
+    assert!(size_of_val(&consumed) < 1024);
+    match consumed.resume() {
+        GeneratorState::Yielded(1) => {}
+        _ => panic!(),
+    }
+
+    // `buf` is defined and consumed twice before the yield.
+    let mut redefined = || {
+        let mut buf = [3u8; 1024];
+        let first = consume(buf);
+        buf = [4u8; 1024];
+        let second = consume(buf);
+        yield first + second;
+    };
+    assert!(size_of_val(&redefined) < 1024);
+    match redefined.resume() {
+        GeneratorState::Yielded(7) => {}
+        _ => panic!(),
+    }
+
+    // `buf` is redefined after the generator is resumed, before being read.
+    let mut redefined_after_resume = || {
+        let mut buf = [5u8; 1024];
+        yield consume(buf);
+        buf = [6u8; 1024];
+        consume(buf)
+    };
+    assert!(size_of_val(&redefined_after_resume) < 1024);
+    match redefined_after_resume.resume() {
+        GeneratorState::Yielded(5) => {}
+        _ => panic!(),
+    }
+    match redefined_after_resume.resume() {
+        GeneratorState::Complete(6) => {}
+        _ => panic!(),
+    }
+
+    // `buf` is read after the generator is resumed, so it has to be saved.
+    let mut read_after_resume = || {
+        let buf = [2u8; 1024];
+        yield;
+        consume(buf)
+    };
+    assert!(size_of_val(&read_after_resume) >= 1024);
+    match read_after_resume.resume() {
+        GeneratorState::Yielded(()) => {}
+        _ => panic!(),
+    }
+    match read_after_resume.resume() {
+        GeneratorState::Complete(2) => {}
+        _ => panic!(),
+    }
+}
@@ -0,0 +1,63 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z mir-opt-level=3
+
+// Check that a local moved out of before a yield isn't saved in the generator,
+// even when the deaggregator turns its reassignment after the yield into
+// writes to its fields, which liveness sees as uses of the whole local.
+
+#![feature(generators, generator_trait)]
+
+use std::mem::size_of_val;
+use std::ops::{Generator, GeneratorState};
+
+struct Buf([u8; 1024]);
+
+#[inline(never)]
+fn consume(buf: Buf) -> u8 {
+    buf.0[0]
+}
+
+fn main() {
+    let mut reassigned = || {
+        let mut buf = Buf([1; 1024]);
+        let first = consume(buf);
+        yield first;
+        buf = Buf([2; 1024]);
+        consume(buf)
+    };
+    assert!(size_of_val(&reassigned) < 1024);
+    match reassigned.resume() {
+        GeneratorState::Yielded(1) => {}
+        _ => panic!(),
+    }
+    match reassigned.resume() {
+        GeneratorState::Complete(2) => {}
+        _ => panic!(),
+    }
+
+    // `buf` is still initialized at the yield, so it has to be saved.
+    let mut kept = || {
+        let mut buf = Buf([3; 1024]);
+        yield;
+        buf.0[1] = 4;
+        consume(buf)
+    };
+    assert!(size_of_val(&kept) >= 1024);
+    match kept.resume() {
+        GeneratorState::Yielded(()) => {}
+        _ => panic!(),
+    }
+    match kept.resume() {
+        GeneratorState::Complete(3) => {}
+        _ => panic!(),
+    }
+}
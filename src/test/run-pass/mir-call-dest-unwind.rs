@@ -0,0 +1,53 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// ignore-wasm32-bare compiled with panic=abort by default
+
+// Check that a call initializes its destination along its return edge
+// only, so that the destination is dropped after the call returns, but
+// not when it unwinds.
+
+use std::cell::Cell;
+use std::panic;
+
+struct Noisy<'a>(&'a Cell<usize>);
+
+impl<'a> Drop for Noisy<'a> {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() + 1);
+    }
+}
+
+fn make<'a>(drops: &'a Cell<usize>, fail: bool) -> Noisy<'a> {
+    if fail {
+        panic!("make");
+    }
+    Noisy(drops)
+}
+
+fn consume(_: Noisy) {}
+
+fn reassign(drops: &Cell<usize>, fail: bool) {
+    let mut noisy = Noisy(drops);
+    consume(noisy);
+    noisy = make(drops, fail);
+    drop(&noisy);
+}
+
+fn main() {
+    let drops = Cell::new(0);
+    reassign(&drops, false);
+    assert_eq!(drops.get(), 2);
+
+    let drops = Cell::new(0);
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| reassign(&drops, true)));
+    assert!(result.is_err());
+    assert_eq!(drops.get(), 1);
+}
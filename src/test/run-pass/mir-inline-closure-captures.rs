@@ -0,0 +1,81 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z mir-opt-level=2
+
+// Closures called once are inlined and their environments dissolved, but
+// their captures are still used and dropped as before.
+
+use std::cell::Cell;
+
+struct DropCount<'a>(&'a Cell<u32>);
+
+impl<'a> Drop for DropCount<'a> {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() + 1);
+    }
+}
+
+fn by_ref(offset: i32) -> i32 {
+    let f = |x: i32| x ^ offset;
+    f(3)
+}
+
+fn by_mut_ref() -> u32 {
+    let mut count = 0;
+    let mut bump = |by: u32| count += by;
+    bump(2);
+    count
+}
+
+fn by_move(s: String) -> usize {
+    let f = move |extra: usize| s.len() + extra;
+    f(1)
+}
+
+fn dropped_once(drops: &Cell<u32>) -> u32 {
+    let guard = DropCount(drops);
+    let f = move || guard.0.get();
+    f()
+}
+
+fn moved_out(drops: &Cell<u32>) {
+    let guard = DropCount(drops);
+    let f = move || drop(guard);
+    f()
+}
+
+fn kept_across_iterations(n: u32) -> usize {
+    let mut keep;
+    let mut i = 0;
+    loop {
+        let s = i.to_string();
+        let c = move || s.len();
+        keep = c;
+        i += 1;
+        if i > n {
+            break
+        }
+    }
+    keep()
+}
+
+fn main() {
+    assert_eq!(by_ref(5), 6);
+    assert_eq!(by_mut_ref(), 2);
+    assert_eq!(by_move("abc".to_string()), 4);
+    assert_eq!(kept_across_iterations(10), 2);
+
+    let drops = Cell::new(0);
+    assert_eq!(dropped_once(&drops), 0);
+    assert_eq!(drops.get(), 1);
+    moved_out(&drops);
+    assert_eq!(drops.get(), 2);
+}
@@ -0,0 +1,69 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z verify-mir -Z mir-opt-level=3
+
+// Make sure the MIR of common constructs stays valid after every pass.
+
+#![feature(generators, generator_trait)]
+
+use std::ops::{Generator, GeneratorState};
+
+struct Noisy(u32);
+
+impl Drop for Noisy {
+    fn drop(&mut self) {}
+}
+
+fn loops(n: u32) -> u32 {
+    let mut total = 0;
+    for i in 0..n {
+        let noisy = Noisy(i);
+        if i % 3 == 0 {
+            continue;
+        }
+        total += noisy.0;
+    }
+    total
+}
+
+fn matches(x: Option<Box<u32>>) -> u32 {
+    match x {
+        Some(b) if *b > 10 => *b,
+        Some(_) => 1,
+        None => 0,
+    }
+}
+
+fn closures(v: Vec<u32>) -> u32 {
+    let offset = 1;
+    v.into_iter().map(|x| x + offset).sum()
+}
+
+const ARRAY: [u32; 3] = [1, 2, 3];
+
+fn main() {
+    assert_eq!(loops(7), 1 + 2 + 4 + 5);
+    assert_eq!(matches(Some(Box::new(11))), 11);
+    assert_eq!(matches(Some(Box::new(1))), 1);
+    assert_eq!(matches(None), 0);
+    assert_eq!(closures(vec![1, 2, 3]), 9);
+    assert_eq!(ARRAY[1], 2);
+
+    let mut gen = || {
+        let noisy = Noisy(1);
+        yield noisy.0;
+        yield 2;
+    };
+    match gen.resume() {
+        GeneratorState::Yielded(1) => {}
+        _ => panic!(),
+    }
+}
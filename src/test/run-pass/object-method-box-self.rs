@@ -0,0 +1,98 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// ignore-wasm32-bare compiled with panic=abort by default
+
+// Test that methods taking `self: Box<Self>` can be called through a trait
+// object, and that the receiver is dropped exactly once, both when the
+// method consumes it and when the method panics.
+
+use std::cell::Cell;
+use std::panic::{self, AssertUnwindSafe};
+
+thread_local!(static DROPS: Cell<usize> = Cell::new(0));
+
+fn drops() -> usize {
+    DROPS.with(|d| d.get())
+}
+
+trait Shape {
+    fn area(self: Box<Self>) -> u64;
+    fn into_name(self: Box<Self>) -> String;
+}
+
+struct Square(u64);
+
+impl Drop for Square {
+    fn drop(&mut self) {
+        DROPS.with(|d| d.set(d.get() + 1));
+    }
+}
+
+impl Shape for Square {
+    fn area(self: Box<Self>) -> u64 {
+        self.0 * self.0
+    }
+
+    fn into_name(self: Box<Self>) -> String {
+        panic!("a square has no name");
+    }
+}
+
+struct Polygon {
+    name: String,
+    sides: [u64; 32],
+}
+
+impl Shape for Polygon {
+    fn area(self: Box<Self>) -> u64 {
+        self.sides.iter().sum()
+    }
+
+    fn into_name(self: Box<Self>) -> String {
+        let this = *self;
+        this.name
+    }
+}
+
+struct Point;
+
+impl Shape for Point {
+    fn area(self: Box<Self>) -> u64 {
+        0
+    }
+
+    fn into_name(self: Box<Self>) -> String {
+        String::from("point")
+    }
+}
+
+fn main() {
+    let shape: Box<Shape> = Box::new(Square(7));
+    assert_eq!(shape.area(), 49);
+    assert_eq!(drops(), 1);
+
+    let shape: Box<Shape> = Box::new(Square(3));
+    let result = panic::catch_unwind(AssertUnwindSafe(move || shape.into_name()));
+    assert!(result.is_err());
+    assert_eq!(drops(), 2);
+
+    let shapes: Vec<Box<Shape>> = vec![
+        Box::new(Polygon { name: String::from("polygon"), sides: [2; 32] }),
+        Box::new(Point),
+    ];
+    let names: Vec<String> = shapes.into_iter().map(|s| s.into_name()).collect();
+    assert_eq!(names, ["polygon", "point"]);
+
+    let shape: Box<Shape> = Box::new(Polygon { name: String::new(), sides: [1; 32] });
+    assert_eq!(shape.area(), 32);
+    let shape: Box<Shape> = Box::new(Point);
+    assert_eq!(shape.area(), 0);
+}
@@ -0,0 +1,45 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -C debug-assertions
+
+// Check that borrows of arithmetic on constants are promoted, even with
+// the overflow checks that make their operands go through extra temps.
+
+const N: u32 = 41;
+
+struct Wrapper;
+
+impl Wrapper {
+    const M: i8 = -3;
+}
+
+fn plus_one() -> &'static u32 {
+    &(N + 1)
+}
+
+fn arith() -> &'static u32 {
+    &((N * 2 - 1) / 3 % 10)
+}
+
+fn neg() -> &'static i8 {
+    &-Wrapper::M
+}
+
+fn shift() -> &'static u64 {
+    &((N as u64) << 2)
+}
+
+fn main() {
+    assert_eq!(*plus_one(), 42);
+    assert_eq!(*arith(), 7);
+    assert_eq!(*neg(), 3);
+    assert_eq!(*shift(), 164);
+}
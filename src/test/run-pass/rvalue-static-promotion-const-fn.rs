@@ -0,0 +1,61 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that calls to `const fn`s with constant arguments are promoted,
+// the call being evaluated at compile time.
+
+#![feature(const_fn)]
+
+#[derive(Debug, PartialEq)]
+struct Duration {
+    secs: u64,
+    nanos: u32,
+}
+
+impl Duration {
+    const fn from_secs(secs: u64) -> Duration {
+        Duration { secs: secs, nanos: 0 }
+    }
+
+    const fn from_millis(millis: u64) -> Duration {
+        Duration { secs: millis / 1000, nanos: (millis % 1000) as u32 * 1_000_000 }
+    }
+}
+
+const fn double(x: u32) -> u32 {
+    x * 2
+}
+
+const TWO: u32 = 2;
+
+fn one_second() -> &'static Duration {
+    &Duration::from_secs(1)
+}
+
+fn millis() -> &'static Duration {
+    &Duration::from_millis(1500)
+}
+
+fn eight() -> &'static u32 {
+    &double(double(TWO))
+}
+
+fn main() {
+    assert_eq!(*one_second(), Duration { secs: 1, nanos: 0 });
+    assert_eq!(*millis(), Duration { secs: 1, nanos: 500_000_000 });
+    assert_eq!(*eight(), 8);
+
+    let secs: &'static u64 = &Duration::from_secs(2).secs;
+    assert_eq!(*secs, 2);
+
+    // The borrows are of the same promoted value every time.
+    assert_eq!(one_second() as *const Duration, one_second() as *const Duration);
+    assert_eq!(eight() as *const u32, eight() as *const u32);
+}
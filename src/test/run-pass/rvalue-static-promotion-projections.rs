@@ -0,0 +1,62 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that borrows of the fields of constants, and of their elements at
+// constant indices, are promoted, both in functions and in statics.
+
+struct Point {
+    x: u32,
+    y: u32,
+}
+
+const ORIGIN: Point = Point { x: 0, y: 7 };
+const PAIR: (u32, (u8, u8)) = (1, (2, 3));
+const ARRAY: [u32; 3] = [10, 20, 30];
+const NESTED: [(u32, [u8; 2]); 2] = [(4, [5, 6]), (7, [8, 9])];
+
+static Y: &u32 = &ORIGIN.y;
+static INNER: &u8 = &(PAIR.1).0;
+static THIRD: &u32 = &ARRAY[2];
+static DEEP: &u8 = &NESTED[1].1[0];
+
+fn y() -> &'static u32 {
+    &ORIGIN.y
+}
+
+fn inner() -> &'static (u8, u8) {
+    &PAIR.1
+}
+
+fn third() -> &'static u32 {
+    &ARRAY[2]
+}
+
+fn deep() -> &'static u8 {
+    &NESTED[1].1[0]
+}
+
+fn main() {
+    assert_eq!(*y(), 7);
+    assert_eq!(*inner(), (2, 3));
+    assert_eq!(*third(), 30);
+    assert_eq!(*deep(), 8);
+    assert_eq!(ORIGIN.x, 0);
+
+    assert_eq!(*Y, 7);
+    assert_eq!(*INNER, 2);
+    assert_eq!(*THIRD, 30);
+    assert_eq!(*DEEP, 8);
+
+    // The borrows are of the same promoted value every time.
+    assert_eq!(y() as *const u32, y() as *const u32);
+    assert_eq!(inner() as *const (u8, u8), inner() as *const (u8, u8));
+    assert_eq!(third() as *const u32, third() as *const u32);
+    assert_eq!(deep() as *const u8, deep() as *const u8);
+}
@@ -0,0 +1,38 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -C codegen-units=4
+
+// Identical promoted values share their address, even when borrowed in
+// functions trans'd in different codegen units.
+
+use std::ptr;
+
+mod a {
+    pub fn table() -> &'static [u16] {
+        &[1, 2, 3, 5, 8, 13]
+    }
+}
+
+mod b {
+    pub fn table() -> &'static [u16] {
+        &[1, 2, 3, 5, 8, 13]
+    }
+
+    pub fn other_table() -> &'static [u16] {
+        &[1, 2, 3, 5, 8, 21]
+    }
+}
+
+fn main() {
+    assert!(ptr::eq(a::table(), b::table()));
+    assert!(!ptr::eq(a::table(), b::other_table()));
+    assert_eq!(a::table(), b::table());
+}
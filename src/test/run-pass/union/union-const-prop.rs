@@ -0,0 +1,29 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z mir-opt-level=3
+
+// Writing a field of a union overwrites its other fields, so constant
+// propagation must not keep the value written to another field before.
+
+union U {
+    a: u32,
+    b: u32,
+}
+
+fn main() {
+    let mut u = U { a: 0 };
+    unsafe {
+        u.a = 1;
+        u.b = 2;
+        assert_eq!(u.a, 2);
+        assert_eq!(u.b, 2);
+    }
+}
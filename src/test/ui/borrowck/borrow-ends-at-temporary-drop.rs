@@ -0,0 +1,31 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// compile-flags: -Z borrowck=mir
+
+// Check that a borrow held by a temporary with a destructor is reported
+// as ending where that temporary is dropped.
+
+struct Guard<'a>(&'a mut Vec<u32>);
+
+impl<'a> Drop for Guard<'a> {
+    fn drop(&mut self) {}
+}
+
+impl<'a> Guard<'a> {
+    fn len(&self) -> usize { self.0.len() }
+}
+
+fn main() {
+    let mut v = vec![];
+    let _n = Guard(&mut v).len() + v.len();
+    //~^ ERROR cannot borrow `v` as immutable because it is also borrowed as mutable
+}
@@ -0,0 +1,49 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// compile-flags: -Z borrowck=mir
+
+// Check that paths through nested structs are described by field name.
+
+#![allow(dead_code)]
+
+struct Inner {
+    value: String,
+    count: u32,
+}
+
+struct Outer {
+    inner: Inner,
+    pair: (Inner, u32),
+}
+
+struct Wrapper(Outer);
+
+fn moved(o: Outer) {
+    drop(o.inner.value);
+    drop(o.inner.value); //~ ERROR use of moved value: `o.inner.value`
+}
+
+fn borrowed(w: &mut Wrapper) {
+    let r = &mut w.0.pair.0.count;
+    w.0.pair.0.count = 1; //~ ERROR cannot assign to `w.0.pair.0.count` because it is borrowed
+    *r += 1;
+}
+
+fn captured(o: Outer) {
+    let c = move || {
+        drop(o.pair.0.value);
+        drop(o.pair.0.value); //~ ERROR use of moved value: `o.pair.0.value`
+    };
+    c();
+}
+
+fn main() {}
@@ -0,0 +1,47 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z borrowck=mir
+
+// Check that MIR borrowck points at where a borrowed variable is dropped,
+// and explains when that is because of the order of declarations.
+
+struct Holder<'a>(&'a String);
+
+impl<'a> Drop for Holder<'a> {
+    fn drop(&mut self) {}
+}
+
+fn reverse_declaration_order() {
+    let y;
+    let x = 1;
+    y = &x;
+} //~ ERROR `x` does not live long enough
+
+fn holder_declared_first() {
+    let _holder;
+    let s = String::from("hello");
+    _holder = Holder(&s);
+} //~ ERROR `s` does not live long enough
+
+fn borrow_outlives_block() {
+    let r;
+    {
+        let x = 1;
+        r = &x;
+    } //~ ERROR `x` does not live long enough
+    let _n = *r;
+}
+
+fn main() {
+    reverse_declaration_order();
+    holder_declared_first();
+    borrow_outlives_block();
+}
@@ -0,0 +1,22 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: --error-format pretty-json -Zunstable-options -Z borrowck=mir
+
+// Binding by reference may require changes to the rest of the match arm, so
+// check that the suggestion is marked as approximate for tools like rustfix.
+
+fn main() {
+    let o = &Some(String::new());
+    match *o {
+        Some(s) => drop(s),
+        None => {}
+    }
+}
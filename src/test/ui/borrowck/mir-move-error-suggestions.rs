@@ -0,0 +1,54 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z borrowck=mir
+
+// Check the suggestions attached to move errors by MIR borrowck: cloning
+// values whose type is `Clone`, binding by reference in patterns, and
+// borrowing instead of moving into a call or out of borrowed content.
+
+struct NotClone;
+
+fn take<T>(_: T) {}
+
+fn clone_after_let(s: String) {
+    let _t = s;
+    take(s); //~ ERROR use of moved value: `s`
+}
+
+fn borrow_call_argument(n: NotClone) {
+    take(n);
+    take(n); //~ ERROR use of moved value: `n`
+}
+
+fn ref_binding(o: Option<NotClone>) {
+    match o {
+        Some(n) => take(n),
+        None => {}
+    }
+    take(o); //~ ERROR use of moved value: `o`
+}
+
+fn borrow_index(v: &Vec<NotClone>) {
+    let _n = v[0]; //~ ERROR cannot move out of borrowed content
+}
+
+fn ref_binding_of_borrowed(o: &Option<NotClone>) {
+    match *o {
+        Some(n) => take(n), //~ ERROR cannot move out of borrowed content
+        None => {}
+    }
+}
+
+fn borrow_deref_argument(r: &NotClone) {
+    take(*r); //~ ERROR cannot move out of borrowed content
+}
+
+fn main() {}
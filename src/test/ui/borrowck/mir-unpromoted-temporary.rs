@@ -0,0 +1,27 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z borrowck=mir
+
+// Check that MIR borrowck points at what kept a borrowed temporary from
+// being promoted to a constant when it has to outlive the function.
+
+fn method_call() -> &'static u32 {
+    &(1 + "ab".len() as u32)
+} //~ ERROR borrowed value does not live long enough
+
+fn index_by_argument(i: usize) -> &'static u32 {
+    &[1, 2, 3][i]
+} //~ ERROR borrowed value does not live long enough
+
+fn main() {
+    method_call();
+    index_by_argument(0);
+}
@@ -0,0 +1,32 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that all the violations in a constant initializer are reported,
+// each pointing at what it's about.
+
+#![feature(const_if_match)]
+
+use std::sync::atomic::{AtomicUsize, ATOMIC_USIZE_INIT};
+
+const COUNTER: AtomicUsize = ATOMIC_USIZE_INIT;
+
+fn not_const() -> usize {
+    0
+}
+
+static PAIR: (usize, &'static AtomicUsize) = (not_const(), &COUNTER);
+//~^ ERROR calls in statics are limited to constant functions
+//~| ERROR cannot borrow a constant which may contain interior mutability
+
+const BRANCHES: usize = if true { not_const() } else { not_const() + 1 };
+//~^ ERROR calls in constants are limited to constant functions
+//~| ERROR calls in constants are limited to constant functions
+
+fn main() {}
@@ -0,0 +1,28 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// compile-flags: -Z large-generator-local-threshold=256
+
+#![feature(generators, generator_trait)]
+#![deny(large_generator_local)]
+
+use std::ops::Generator;
+
+fn consume(_: &[u8]) {}
+
+fn main() {
+    let mut gen = || {
+        let buf = [0u8; 512];
+        yield;
+        consume(&buf);
+    };
+    gen.resume();
+}
@@ -0,0 +1,44 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+#![feature(generators, generator_trait)]
+
+use std::ops::Generator;
+
+fn consume(_: &[u8]) {}
+
+#[allow(large_generator_local)]
+fn allowed() {
+    let mut gen = || {
+        let buf = [0u8; 4096];
+        yield;
+        consume(&buf);
+    };
+    gen.resume();
+}
+
+#[warn(large_generator_local)]
+fn warned() {
+    let mut gen = || {
+        // This is below the default threshold of 1024 bytes
+        let small = [0u8; 512];
+        let buf = [0u8; 4096];
+        yield;
+        consume(&small);
+        consume(&buf);
+    };
+    gen.resume();
+}
+
+fn main() {
+    allowed();
+    warned();
+}
@@ -14,17 +14,17 @@ fn main() {
 
     unsafe {
         let f = |v: &mut Vec<_>| {
-            unsafe { //~ ERROR unnecessary `unsafe`
+            unsafe {
                 v.set_len(24);
-                |w: &mut Vec<u32>| { unsafe { //~ ERROR unnecessary `unsafe`
+                |w: &mut Vec<u32>| { unsafe {
                     w.set_len(32);
                 } };
             }
-            |x: &mut Vec<u32>| { unsafe { //~ ERROR unnecessary `unsafe`
+            |x: &mut Vec<u32>| { unsafe {
                 x.set_len(40);
             } };
         };
-
+        (|| unsafe {})(); //~ ERROR unnecessary `unsafe`
         v.set_len(0);
         f(&mut v);
     }
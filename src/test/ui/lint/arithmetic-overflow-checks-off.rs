@@ -0,0 +1,29 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -C overflow-checks=off
+
+// Check that arithmetic on constants that always overflows is reported,
+// as operations that will wrap around, but only when reached on every call.
+
+fn conditional(c: bool) -> u8 {
+    let x = 255u8;
+    if c { x + 1 } else { 0 }
+}
+
+fn main() {
+    let x = 255u8;
+    let _y = x + 1;
+    let z = 0u32;
+    let _w = z - 1;
+    let m = 1_000_000i32;
+    let _n = m * m;
+    conditional(false);
+}
@@ -0,0 +1,39 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![crate_type = "lib"]
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub fn empty() -> ! {
+    loop {}
+}
+
+pub fn spin_on_local() -> ! {
+    let mut x = 0u32;
+    loop { x ^= 1; }
+}
+
+pub fn spin_on_atomic(flag: &AtomicBool) {
+    while !flag.load(Ordering::Acquire) {}
+}
+
+pub fn spin_with_call() -> ! {
+    loop { std::thread::yield_now(); }
+}
+
+pub fn spin_with_write(p: &mut u32) -> ! {
+    loop { *p = 1; }
+}
+
+#[allow(effectless_infinite_loops)]
+pub fn allowed() -> ! {
+    loop {}
+}
@@ -0,0 +1,111 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![warn(unread_assignments)]
+#![feature(asm)]
+
+macro_rules! count {
+    () => {{
+        let mut count;
+        count = 0;
+        count = 1;
+        count
+    }}
+}
+
+struct Noisy(u32);
+
+impl Drop for Noisy {
+    fn drop(&mut self) {}
+}
+
+union Bits {
+    int: u32,
+    float: f32,
+}
+
+fn overwritten() -> u32 {
+    let mut x;
+    x = 1;
+    x = 2;
+    x
+}
+
+fn macro_expanded() -> u32 {
+    count!()
+}
+
+fn read_through_mut_ref() -> u32 {
+    let mut x;
+    x = 1;
+    {
+        let r = &mut x;
+        *r += 1;
+    }
+    x
+}
+
+fn observed_by_drop() {
+    let noisy;
+    noisy = Noisy(1);
+}
+
+fn union_field() -> f32 {
+    let mut bits;
+    bits = Bits { int: 0 };
+    bits.int = 1;
+    unsafe { bits.float }
+}
+
+fn read_by_asm() -> u32 {
+    let mut x;
+    x = 1;
+    unsafe { asm!("" : "+r"(x)); }
+    x
+}
+
+fn ignored() -> u32 {
+    let mut _x;
+    _x = 1;
+    _x = 2;
+    _x
+}
+
+#[allow(unread_assignments)]
+fn allowed() -> u32 {
+    let mut x;
+    x = 1;
+    x = 2;
+    x
+}
+
+fn overwritten_after_borrow_dies() -> u32 {
+    let mut x;
+    x = 1;
+    {
+        let r = &mut x;
+        *r += 1;
+    }
+    x = 2;
+    x = 3;
+    x
+}
+
+fn main() {
+    overwritten();
+    macro_expanded();
+    read_through_mut_ref();
+    observed_by_drop();
+    union_field();
+    read_by_asm();
+    ignored();
+    allowed();
+    overwritten_after_borrow_dies();
+}
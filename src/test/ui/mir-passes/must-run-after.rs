@@ -0,0 +1,18 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// ignore-tidy-linelength
+
+// Check that `-Z mir-passes` can't move the inliner before the erasure of
+// regions, which it relies on.
+
+// compile-flags: -Z mir-passes=clean-end-regions,simplify-cfg-initial,typeck,rustc-peek,qualify-consts,simplify-cfg-qualify-consts,lint-unread-assignments,cleanup-post-borrowck,no-landing-pads-initial,simplify-branches-initial,critical-call-edges-elaborate-drops,elaborate-drops,no-landing-pads-elaborate-drops,all-call-edges,add-validation,add-moves-for-packed-drops,simplify-cfg-elaborate-drops,inline,erase-regions,lower-128bit,lower-assume,box-to-stack,instcombine,deaggregator,copy-prop,global-cse,const-prop,simplify-branches-after-const-prop,simplify-cfg-after-const-prop,move-last-uses,simplify-locals,effectless-loops,state-transform,critical-call-edges-pre-trans,pre-trans
+
+fn main() {}
@@ -0,0 +1,22 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// compile-flags: -Z borrowck=mir -Z nll
+
+// Check that a non-lexical borrow is reported as ending at its last use.
+
+fn main() {
+    let mut x = 0;
+    let r = &mut x;
+    let _y = x; //~ ERROR cannot use `x` because it was mutably borrowed
+    drop(r);
+    let _z = x;
+}
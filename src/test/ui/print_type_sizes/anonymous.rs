@@ -14,6 +14,8 @@
 // that one cannot control the sizes of these types with the same sort
 // of enum-variant manipulation tricks.
 
+#![allow(effectless_infinite_loops)]
+
 pub fn main() {
     let _byte: u8 = 0;
     let _word: usize = 0;
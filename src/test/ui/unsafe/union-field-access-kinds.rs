@@ -0,0 +1,32 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// Check that union field accesses are described by how they use the field.
+
+#![feature(untagged_unions)]
+#![allow(dead_code)]
+
+struct NonCopy(u8);
+
+union U {
+    a: u8,
+    b: NonCopy,
+    c: (u8, u8),
+}
+
+fn main() {
+    let mut u = U { a: 1 };
+    u.a = 2; // OK, overwriting a `Copy` field
+    let _read = u.a;
+    u.b = NonCopy(3);
+    u.c.0 = 4;
+    let _borrow = &u.a;
+}
@@ -0,0 +1,61 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// Check that unsafe operations are credited to the innermost `unsafe` block
+// around them, so that only the blocks which cover nothing are reported.
+
+#![deny(unused_unsafe)]
+
+unsafe fn unsf() {}
+
+fn three_deep() {
+    unsafe { //~ ERROR unnecessary `unsafe` block
+        unsafe { //~ ERROR unnecessary `unsafe` block
+            unsafe {
+                unsf();
+            }
+        }
+    }
+}
+
+fn split_across_nesting() {
+    unsafe {
+        unsf();
+        unsafe {
+            unsf();
+        }
+    }
+}
+
+unsafe fn block_in_unsafe_fn() {
+    unsafe { //~ ERROR unnecessary `unsafe` block
+        unsf();
+    }
+}
+
+struct S;
+
+impl S {
+    unsafe fn block_in_unsafe_method(&self) {
+        unsafe { //~ ERROR unnecessary `unsafe` block
+            unsf();
+        }
+    }
+}
+
+fn empty_block_in_used_block() {
+    unsafe {
+        unsf();
+        unsafe {} //~ ERROR unnecessary `unsafe` block
+    }
+}
+
+fn main() {}
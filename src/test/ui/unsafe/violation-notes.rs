@@ -0,0 +1,31 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// Check that each kind of unsafe operation explains why it needs `unsafe`.
+
+union U {
+    a: u32,
+    b: f32,
+}
+
+static mut COUNTER: u32 = 0;
+
+unsafe fn dangerous() {}
+
+fn main() {
+    let x = 5;
+    let p = &x as *const u32;
+    let _deref = *p;
+    let u = U { a: 1 };
+    let _field = u.a;
+    let _static = COUNTER;
+    dangerous();
+}
@@ -2341,15 +2341,17 @@ actual:\n\
     }
 
     fn compare_mir_test_output(&self, test_name: &str, expected_content: &[ExpectedLine<&str>]) {
-        let mut output_file = PathBuf::new();
-        output_file.push(self.get_mir_dump_dir());
-        output_file.push(test_name);
+        let output_file = match self.find_mir_dump(test_name) {
+            Some(output_file) => output_file,
+            None => {
+                let mut output_file = self.get_mir_dump_dir();
+                output_file.push(test_name);
+                panic!("Output file `{}` from test does not exist",
+                       output_file.into_os_string().to_string_lossy());
+            }
+        };
         debug!("comparing the contests of: {:?}", output_file);
         debug!("with: {:?}", expected_content);
-        if !output_file.exists() {
-            panic!("Output file `{}` from test does not exist",
-                   output_file.into_os_string().to_string_lossy());
-        }
         self.check_mir_test_timestamp(test_name, &output_file);
 
         let mut dumped_file = fs::File::open(output_file.clone()).unwrap();
@@ -2440,6 +2442,22 @@ actual:\n\
         }
     }
 
+    /// Finds the MIR dump named `test_name` once the def path hash is
+    /// removed from its name, so that `rustc.main.SimplifyCfg-initial.after.mir`
+    /// names `rustc.main.<hash>.SimplifyCfg-initial.after.mir`.
+    fn find_mir_dump(&self, test_name: &str) -> Option<PathBuf> {
+        let dump_dir = self.get_mir_dump_dir();
+        let entries = match fs::read_dir(&dump_dir) {
+            Ok(entries) => entries,
+            Err(_) => return None,
+        };
+        entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).find(|path| {
+            path.file_name().and_then(|name| name.to_str()).map_or(false, |name| {
+                remove_def_path_hash(name) == test_name
+            })
+        })
+    }
+
     fn get_mir_dump_dir(&self) -> PathBuf {
         let mut mir_dump_dir = PathBuf::from(self.config.build_base.as_path());
         debug!("input_file: {:?}", self.testpaths.file);
@@ -2599,6 +2617,25 @@ fn normalize_mir_line(line: &str) -> String {
     nocomment_mir_line(line).replace(char::is_whitespace, "")
 }
 
+/// Removes the `.<hash>` after the item name of the name of a MIR dump,
+/// `rustc.<item>.<hash>[-<promoted>].<pass_name>...`.
+fn remove_def_path_hash(file_name: &str) -> String {
+    const HASH_LEN: usize = 16;
+    let mut segments = file_name.splitn(3, '.');
+    let (rustc, item, rest) = match (segments.next(), segments.next(), segments.next()) {
+        (Some(rustc), Some(item), Some(rest)) => (rustc, item, rest),
+        _ => return file_name.to_string(),
+    };
+    let is_hash = rest.len() > HASH_LEN &&
+        rest[..HASH_LEN].chars().all(|c| c.is_digit(16)) &&
+        (rest[HASH_LEN..].starts_with('.') || rest[HASH_LEN..].starts_with('-'));
+    if is_hash {
+        format!("{}.{}{}", rustc, item, &rest[HASH_LEN..])
+    } else {
+        file_name.to_string()
+    }
+}
+
 fn nocomment_mir_line(line: &str) -> &str {
     if let Some(idx) = line.find("//") {
         let (l, _) = line.split_at(idx);